@@ -13,6 +13,7 @@ fn main() {
     println!("cargo::rerun-if-changed=types/fresh.d.ts.template");
     println!("cargo::rerun-if-changed=src/config.rs");
     println!("cargo::rerun-if-changed=src/services/lsp/client.rs");
+    println!("cargo::rerun-if-changed=src/services/lsp/features.rs");
     println!("cargo::rerun-if-changed=src/services/process_limits.rs");
 
     // Skip type generation during cargo publish (files should be pre-committed)
@@ -1119,6 +1120,8 @@ struct ConfigStructInfo {
     fields: Vec<ConfigFieldInfo>,
     doc_comment: String,
     is_enum: bool,
+    /// `#[serde(untagged)]` enum, serialized as the variant's content
+    is_untagged: bool,
     enum_variants: Vec<EnumVariantInfo>,
 }
 
@@ -1140,6 +1143,8 @@ struct EnumVariantInfo {
     name: String,
     serde_rename: Option<String>,
     is_default: bool,
+    /// Type wrapped by a single-field tuple variant
+    tuple_type: Option<String>,
 }
 
 /// Generate JSON Schema for configuration
@@ -1147,12 +1152,14 @@ fn generate_config_schema() -> Result<(), Box<dyn std::error::Error>> {
     // Read config.rs and related files
     let config_source = fs::read_to_string("src/config.rs")?;
     let lsp_client_source = fs::read_to_string("src/services/lsp/client.rs")?;
+    let lsp_features_source = fs::read_to_string("src/services/lsp/features.rs")?;
     let process_limits_source = fs::read_to_string("src/services/process_limits.rs")?;
 
     // Extract struct and enum definitions
     let mut all_structs = Vec::new();
     all_structs.extend(extract_config_structs(&config_source));
     all_structs.extend(extract_config_structs(&lsp_client_source));
+    all_structs.extend(extract_config_structs(&lsp_features_source));
     all_structs.extend(extract_config_structs(&process_limits_source));
 
     // Generate JSON Schema
@@ -1187,6 +1194,7 @@ fn extract_config_structs(source: &str) -> Vec<ConfigStructInfo> {
             // Find the struct or enum definition
             let mut def_line_idx = i + 1;
             let mut serde_rename_all: Option<String> = None;
+            let mut serde_untagged = false;
 
             while def_line_idx < lines.len() {
                 let next_line = lines[def_line_idx].trim();
@@ -1198,12 +1206,16 @@ fn extract_config_structs(source: &str) -> Vec<ConfigStructInfo> {
                     }
                     break;
                 } else if next_line.starts_with("pub enum ") || next_line.starts_with("enum ") {
-                    if let Some(enum_info) =
+                    if let Some(mut enum_info) =
                         parse_config_enum(&lines, def_line_idx, &doc_comment, &serde_rename_all)
                     {
+                        enum_info.is_untagged = serde_untagged;
                         structs.push(enum_info);
                     }
                     break;
+                } else if next_line.starts_with("#[serde(untagged") {
+                    serde_untagged = true;
+                    def_line_idx += 1;
                 } else if next_line.starts_with("#[serde(rename_all") {
                     // Extract rename_all value
                     if let Some(start) = next_line.find('"') {
@@ -1302,6 +1314,7 @@ fn parse_config_struct(
         fields,
         doc_comment: doc_comment.to_string(),
         is_enum: false,
+        is_untagged: false,
         enum_variants: Vec::new(),
     })
 }
@@ -1382,6 +1395,7 @@ fn parse_config_enum(
         fields: Vec::new(),
         doc_comment: doc_comment.to_string(),
         is_enum: true,
+        is_untagged: false,
         enum_variants: variants,
     })
 }
@@ -1447,6 +1461,10 @@ fn parse_enum_variant(
     } else {
         line.to_string()
     };
+    let tuple_type = line
+        .find('(')
+        .filter(|_| line.ends_with(')'))
+        .map(|paren| line[paren + 1..line.len() - 1].trim().to_string());
 
     let mut serde_rename = None;
     let mut is_default = false;
@@ -1476,6 +1494,7 @@ fn parse_enum_variant(
         name,
         serde_rename,
         is_default,
+        tuple_type,
     })
 }
 
@@ -1622,7 +1641,14 @@ fn generate_json_schema(structs: &[ConfigStructInfo]) -> serde_json::Value {
 
     // Second pass: generate definitions
     for struct_info in structs {
-        let schema = if struct_info.is_enum {
+        let schema = if struct_info.is_untagged
+            && struct_info
+                .enum_variants
+                .iter()
+                .all(|v| v.tuple_type.is_some())
+        {
+            generate_untagged_enum_schema(struct_info, &def_names)
+        } else if struct_info.is_enum {
             generate_enum_schema(struct_info)
         } else {
             generate_struct_schema(struct_info, &def_names)
@@ -1731,3 +1757,30 @@ fn generate_enum_schema(enum_info: &ConfigStructInfo) -> serde_json::Value {
 
     schema
 }
+
+/// Generate JSON Schema for an untagged enum of tuple variants, which
+/// accepts any of the wrapped types
+fn generate_untagged_enum_schema(
+    enum_info: &ConfigStructInfo,
+    def_names: &std::collections::HashSet<String>,
+) -> serde_json::Value {
+    let definitions: BTreeMap<String, serde_json::Value> = def_names
+        .iter()
+        .map(|name| (name.clone(), serde_json::json!({})))
+        .collect();
+
+    let variants: Vec<serde_json::Value> = enum_info
+        .enum_variants
+        .iter()
+        .filter_map(|v| v.tuple_type.as_deref())
+        .map(|rust_type| rust_type_to_json_schema(rust_type, &definitions))
+        .collect();
+
+    let mut schema = serde_json::json!({ "anyOf": variants });
+
+    if !enum_info.doc_comment.is_empty() {
+        schema["description"] = serde_json::Value::String(enum_info.doc_comment.clone());
+    }
+
+    schema
+}
@@ -505,11 +505,32 @@ function getNestedValue(obj: Record<string, unknown>, path: string): unknown {
  * This ensures changes are tracked for display and saved correctly
  */
 function updateConfigValue(path: string, value: unknown): void {
+  seedMapEntry(path);
   setNestedValue(state.workingConfig, path, value);
   setNestedValue(state.userConfig, path, value);
   state.hasChanges = true;
 }
 
+/**
+ * Copy a map entry (e.g. `lsp.rust`) from the merged config into the user config
+ * before one of its fields changes, so the saved entry keeps its other fields
+ * (an LSP entry without its `command` would not load)
+ */
+function seedMapEntry(path: string): void {
+  const parts = parsePath(path);
+  if (parts.length < 3) return;
+  const mapSchema = state.schema[parts[0]];
+  if (!mapSchema || mapSchema.type !== "object" || !mapSchema.itemSchema || mapSchema.nestedSchema) {
+    return;
+  }
+  const entryPath = `${parts[0]}.${parts[1]}`;
+  if (hasNestedValue(state.userConfig, entryPath)) return;
+  const entry = getNestedValue(state.workingConfig, entryPath);
+  if (entry !== undefined) {
+    setNestedValue(state.userConfig, entryPath, deepClone(entry));
+  }
+}
+
 /**
  * Set a nested value in an object using a path (supports array indices)
  */
@@ -948,8 +969,17 @@ function buildVisibleFields(): ConfigField[] {
             const itemExpanded = state.expandedSections.has(itemPath);
             const itemIsDefault = !hasNestedValue(state.userConfig, itemPath);
 
+            // A list of entries (e.g. several language servers for one language)
+            if (Array.isArray(itemValue)) {
+              const listSchema: FieldSchema = {
+                type: "array",
+                description: `${mapKey} configuration`,
+                itemSchema,
+              };
+              addFieldsFromSchema({ [mapKey]: listSchema }, path, depth + 1, mapData);
+            }
             // Check if item is an object with nested schema
-            if (itemSchema.type === "object" && itemSchema.nestedSchema) {
+            else if (itemSchema.type === "object" && itemSchema.nestedSchema) {
               fields.push({
                 path: itemPath,
                 name: mapKey,
@@ -231,6 +231,7 @@ function buildPanelEntries(): TextPropertyEntry[] {
         const line = diag.range.start.line + 1;
         const col = diag.range.start.character + 1;
         const msg = diag.message.split("\n")[0]; // First line only
+        const source = diag.source ? ` [${diag.source}]` : "";
 
         const location: DiagnosticLocation = {
           file: filePath,
@@ -245,7 +246,7 @@ function buildPanelEntries(): TextPropertyEntry[] {
         });
 
         entries.push({
-          text: `  ${icon} ${line}:${col} ${msg}\n`,
+          text: `  ${icon} ${line}:${col} ${msg}${source}\n`,
           properties: {
             type: "diagnostic",
             index: diagIndex,
@@ -541,6 +541,24 @@ interface EditorAPI {
    * @returns true if indicators were cleared
    */
   clearLineIndicators(buffer_id: number, namespace: string): boolean;
+  /**
+   * Set a badge on a file in the file explorer, shown instead of its git status
+   * @param namespace - Namespace for grouping (e.g., "diagnostics")
+   * @param path - File path (absolute or relative to the working directory)
+   * @param symbol - Symbol to display (e.g., "●", "2")
+   * @param r - Red color component (0-255)
+   * @param g - Green color component (0-255)
+   * @param b - Blue color component (0-255)
+   * @param priority - Priority when several namespaces decorate a file (higher wins)
+   * @returns true if the badge was set
+   */
+  setFileExplorerDecoration(namespace: string, path: string, symbol: string, r: number, g: number, b: number, priority: number): boolean;
+  /**
+   * Clear all file explorer badges of a namespace
+   * @param namespace - Namespace to clear (e.g., "diagnostics")
+   * @returns true if the badges were cleared
+   */
+  clearFileExplorerDecorations(namespace: string): boolean;
   /**
    * Submit a transformed view stream for a viewport
    * @param buffer_id - Buffer to apply the transform to
@@ -152,6 +152,11 @@ const THEME_SECTIONS: ThemeSection[] = [
       { key: "scrollbar_thumb_hover_fg", displayName: "Scrollbar Thumb Hover", description: "Scrollbar thumb hover color", section: "ui" },
       { key: "compose_margin_bg", displayName: "Compose Margin Background", description: "Compose mode margin background", section: "ui" },
       { key: "semantic_highlight_bg", displayName: "Semantic Highlight Background", description: "Word under cursor highlight", section: "ui" },
+      { key: "git_modified_fg", displayName: "Git Modified", description: "Modified files in the file explorer", section: "ui" },
+      { key: "git_added_fg", displayName: "Git Added", description: "Staged new files in the file explorer", section: "ui" },
+      { key: "git_untracked_fg", displayName: "Git Untracked", description: "Untracked files in the file explorer", section: "ui" },
+      { key: "git_conflicted_fg", displayName: "Git Conflicted", description: "Files with merge conflicts in the file explorer", section: "ui" },
+      { key: "git_ignored_fg", displayName: "Git Ignored", description: "Ignored files in the file explorer", section: "ui" },
     ],
   },
   {
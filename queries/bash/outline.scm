@@ -0,0 +1,3 @@
+; Functions
+(function_definition
+  name: (word) @name) @definition.function
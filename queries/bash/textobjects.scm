@@ -0,0 +1,17 @@
+; Functions
+(function_definition
+  body: (_) @function.inner) @function.outer
+
+; Arguments of commands
+(command
+  argument: (_) @parameter.inner @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+[
+  (compound_statement)
+  (do_group)
+  (subshell)
+] @block.outer @block.inner
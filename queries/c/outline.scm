@@ -0,0 +1,32 @@
+; Functions, including those returning pointers
+(function_definition
+  declarator: (function_declarator
+    declarator: (identifier) @name)) @definition.function
+
+(function_definition
+  declarator: (pointer_declarator
+    declarator: (function_declarator
+      declarator: (identifier) @name))) @definition.function
+
+; Structs, unions and enums with a body
+(struct_specifier
+  name: (type_identifier) @name
+  body: (_)) @definition.struct
+
+(union_specifier
+  name: (type_identifier) @name
+  body: (_)) @definition.struct
+
+(enum_specifier
+  name: (type_identifier) @name
+  body: (_)) @definition.enum
+
+(enumerator
+  name: (identifier) @name) @definition.variant
+
+(field_declaration
+  declarator: (field_identifier) @name) @definition.field
+
+; Typedefs
+(type_definition
+  declarator: (type_identifier) @name) @definition.type
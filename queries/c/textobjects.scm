@@ -0,0 +1,30 @@
+; Function definitions
+(function_definition
+  body: (compound_statement) @function.inner) @function.outer
+
+; Structs, unions and enums
+[
+  (struct_specifier
+    body: (field_declaration_list) @class.inner)
+  (union_specifier
+    body: (field_declaration_list) @class.inner)
+  (enum_specifier
+    body: (enumerator_list) @class.inner)
+] @class.outer
+
+; Parameters and arguments, with the comma after them
+(parameter_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+(compound_statement) @block.outer @block.inner
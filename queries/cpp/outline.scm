@@ -0,0 +1,48 @@
+; Namespaces
+(namespace_definition
+  name: (namespace_identifier) @name) @definition.namespace
+
+; Functions and methods, including those returning pointers or references
+(function_definition
+  declarator: (function_declarator
+    declarator: (_) @name)) @definition.function
+
+(function_definition
+  declarator: (pointer_declarator
+    declarator: (function_declarator
+      declarator: (_) @name))) @definition.function
+
+(function_definition
+  declarator: (reference_declarator
+    (function_declarator
+      declarator: (_) @name))) @definition.function
+
+; Classes, structs, unions and enums with a body
+(class_specifier
+  name: (_) @name
+  body: (_)) @definition.class
+
+(struct_specifier
+  name: (_) @name
+  body: (_)) @definition.struct
+
+(union_specifier
+  name: (_) @name
+  body: (_)) @definition.struct
+
+(enum_specifier
+  name: (_) @name
+  body: (_)) @definition.enum
+
+(enumerator
+  name: (identifier) @name) @definition.variant
+
+(field_declaration
+  declarator: (field_identifier) @name) @definition.field
+
+; Typedefs and aliases
+(type_definition
+  declarator: (type_identifier) @name) @definition.type
+
+(alias_declaration
+  name: (type_identifier) @name) @definition.type
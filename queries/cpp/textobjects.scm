@@ -0,0 +1,45 @@
+; Function definitions and lambdas
+(function_definition
+  body: (compound_statement) @function.inner) @function.outer
+
+(lambda_expression
+  body: (compound_statement) @function.inner) @function.outer
+
+; Classes, structs, unions and enums
+[
+  (class_specifier
+    body: (field_declaration_list) @class.inner)
+  (struct_specifier
+    body: (field_declaration_list) @class.inner)
+  (union_specifier
+    body: (field_declaration_list) @class.inner)
+  (enum_specifier
+    body: (enumerator_list) @class.inner)
+] @class.outer
+
+; Parameters, arguments and template arguments, with the comma after them
+(parameter_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(template_parameter_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(template_argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+(compound_statement) @block.outer @block.inner
@@ -0,0 +1,35 @@
+; Namespaces
+(namespace_declaration
+  name: (_) @name) @definition.namespace
+
+(file_scoped_namespace_declaration
+  name: (_) @name) @definition.namespace
+
+; Classes, structs, interfaces, records and enums
+(class_declaration
+  name: (identifier) @name) @definition.class
+
+(struct_declaration
+  name: (identifier) @name) @definition.struct
+
+(interface_declaration
+  name: (identifier) @name) @definition.interface
+
+(record_declaration
+  name: (identifier) @name) @definition.class
+
+(enum_declaration
+  name: (identifier) @name) @definition.enum
+
+(enum_member_declaration
+  name: (identifier) @name) @definition.variant
+
+; Methods, constructors and properties
+(method_declaration
+  name: (identifier) @name) @definition.method
+
+(constructor_declaration
+  name: (identifier) @name) @definition.constructor
+
+(property_declaration
+  name: (identifier) @name) @definition.property
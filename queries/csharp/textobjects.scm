@@ -0,0 +1,42 @@
+; Methods, constructors, local functions and lambdas
+[
+  (method_declaration
+    body: (block) @function.inner)
+  (constructor_declaration
+    body: (block) @function.inner)
+  (local_function_statement
+    body: (block) @function.inner)
+  (lambda_expression
+    body: (_) @function.inner)
+] @function.outer
+
+; Classes, structs, interfaces, records and enums
+[
+  (class_declaration
+    body: (declaration_list) @class.inner)
+  (struct_declaration
+    body: (declaration_list) @class.inner)
+  (interface_declaration
+    body: (declaration_list) @class.inner)
+  (record_declaration
+    body: (declaration_list) @class.inner)
+  (enum_declaration
+    body: (enum_member_declaration_list) @class.inner)
+] @class.outer
+
+; Parameters and arguments, with the comma after them
+(parameter_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+(block) @block.outer @block.inner
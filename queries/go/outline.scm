@@ -0,0 +1,22 @@
+; Functions and methods
+(function_declaration
+  name: (identifier) @name) @definition.function
+
+(method_declaration
+  name: (field_identifier) @name) @definition.method
+
+; Structs and interfaces
+(type_spec
+  name: (type_identifier) @name
+  type: (struct_type)) @definition.struct
+
+(type_spec
+  name: (type_identifier) @name
+  type: (interface_type)) @definition.interface
+
+(field_declaration
+  name: (field_identifier) @name) @definition.field
+
+; Constants
+(const_spec
+  name: (identifier) @name) @definition.constant
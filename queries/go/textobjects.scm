@@ -0,0 +1,35 @@
+; Functions, methods and function literals
+[
+  (function_declaration
+    body: (block) @function.inner)
+  (method_declaration
+    body: (block) @function.inner)
+  (func_literal
+    body: (block) @function.inner)
+] @function.outer
+
+; Type declarations
+(type_declaration
+  (type_spec
+    type: [
+      (struct_type
+        (field_declaration_list) @class.inner)
+      (interface_type)
+    ])) @class.outer
+
+; Parameters and arguments, with the comma after them
+(parameter_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+(block) @block.outer @block.inner
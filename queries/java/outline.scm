@@ -0,0 +1,26 @@
+; Classes, interfaces, enums and records
+(class_declaration
+  name: (identifier) @name) @definition.class
+
+(interface_declaration
+  name: (identifier) @name) @definition.interface
+
+(enum_declaration
+  name: (identifier) @name) @definition.enum
+
+(record_declaration
+  name: (identifier) @name) @definition.class
+
+(enum_constant
+  name: (identifier) @name) @definition.variant
+
+; Methods, constructors and fields
+(method_declaration
+  name: (identifier) @name) @definition.method
+
+(constructor_declaration
+  name: (identifier) @name) @definition.constructor
+
+(field_declaration
+  declarator: (variable_declarator
+    name: (identifier) @name)) @definition.field
@@ -0,0 +1,44 @@
+; Methods, constructors and lambdas
+[
+  (method_declaration
+    body: (block) @function.inner)
+  (constructor_declaration
+    body: (constructor_body) @function.inner)
+  (lambda_expression
+    body: (_) @function.inner)
+] @function.outer
+
+; Classes, interfaces, enums and records
+[
+  (class_declaration
+    body: (class_body) @class.inner)
+  (interface_declaration
+    body: (interface_body) @class.inner)
+  (enum_declaration
+    body: (enum_body) @class.inner)
+  (record_declaration
+    body: (class_body) @class.inner)
+] @class.outer
+
+; Parameters and arguments, with the comma after them
+(formal_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+[
+  (line_comment)
+  (block_comment)
+] @comment.outer
+
+; Blocks
+[
+  (block)
+  (constructor_body)
+] @block.outer @block.inner
@@ -0,0 +1,22 @@
+; Classes
+(class_declaration
+  name: (identifier) @name) @definition.class
+
+; Functions
+(function_declaration
+  name: (identifier) @name) @definition.function
+
+(generator_function_declaration
+  name: (identifier) @name) @definition.function
+
+; Functions assigned to variables
+(variable_declarator
+  name: (identifier) @name
+  value: [
+    (arrow_function)
+    (function_expression)
+  ]) @definition.function
+
+; Methods
+(method_definition
+  name: (_) @name) @definition.method
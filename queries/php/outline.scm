@@ -0,0 +1,23 @@
+; Namespaces
+(namespace_definition
+  name: (namespace_name) @name) @definition.namespace
+
+; Classes, interfaces, traits and enums
+(class_declaration
+  name: (name) @name) @definition.class
+
+(interface_declaration
+  name: (name) @name) @definition.interface
+
+(trait_declaration
+  name: (name) @name) @definition.class
+
+(enum_declaration
+  name: (name) @name) @definition.enum
+
+; Functions and methods
+(function_definition
+  name: (name) @name) @definition.function
+
+(method_declaration
+  name: (name) @name) @definition.method
@@ -0,0 +1,40 @@
+; Functions, methods, closures and arrow functions
+[
+  (function_definition
+    body: (compound_statement) @function.inner)
+  (method_declaration
+    body: (compound_statement) @function.inner)
+  (anonymous_function
+    body: (compound_statement) @function.inner)
+  (arrow_function
+    body: (_) @function.inner)
+] @function.outer
+
+; Classes, interfaces, traits and enums
+[
+  (class_declaration
+    body: (declaration_list) @class.inner)
+  (interface_declaration
+    body: (declaration_list) @class.inner)
+  (trait_declaration
+    body: (declaration_list) @class.inner)
+  (enum_declaration
+    body: (enum_declaration_list) @class.inner)
+] @class.outer
+
+; Parameters and arguments, with the comma after them
+(formal_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(arguments
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+(compound_statement) @block.outer @block.inner
@@ -0,0 +1,7 @@
+; Classes
+(class_definition
+  name: (identifier) @name) @definition.class
+
+; Functions, which become methods inside classes
+(function_definition
+  name: (identifier) @name) @definition.function
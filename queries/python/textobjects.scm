@@ -0,0 +1,32 @@
+; Functions and lambdas
+(function_definition
+  body: (block) @function.inner) @function.outer
+
+(lambda
+  body: (_) @function.inner) @function.outer
+
+; Classes
+(class_definition
+  body: (block) @class.inner) @class.outer
+
+; Parameters and arguments, with the comma after them
+(parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(lambda_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+(block) @block.outer @block.inner
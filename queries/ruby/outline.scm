@@ -0,0 +1,13 @@
+; Modules and classes
+(module
+  name: (_) @name) @definition.module
+
+(class
+  name: (_) @name) @definition.class
+
+; Methods
+(method
+  name: (_) @name) @definition.method
+
+(singleton_method
+  name: (_) @name) @definition.method
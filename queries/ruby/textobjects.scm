@@ -0,0 +1,51 @@
+; Methods, blocks and lambdas
+(method
+  body: (_)? @function.inner) @function.outer
+
+(singleton_method
+  body: (_)? @function.inner) @function.outer
+
+(lambda
+  body: (_) @function.inner) @function.outer
+
+; Classes and modules
+(class
+  body: (_)? @class.inner) @class.outer
+
+(singleton_class
+  body: (_)? @class.inner) @class.outer
+
+(module
+  body: (_)? @class.inner) @class.outer
+
+; Parameters and arguments, with the comma after them
+(method_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(lambda_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(block_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(argument_list
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+[
+  (do_block
+    body: (_)? @block.inner)
+  (block
+    body: (_)? @block.inner)
+] @block.outer
@@ -0,0 +1,46 @@
+; Modules
+(mod_item
+  name: (identifier) @name) @definition.module
+
+; Types
+(struct_item
+  name: (type_identifier) @name) @definition.struct
+
+(union_item
+  name: (type_identifier) @name) @definition.struct
+
+(enum_item
+  name: (type_identifier) @name) @definition.enum
+
+(enum_variant
+  name: (identifier) @name) @definition.variant
+
+(field_declaration
+  name: (field_identifier) @name) @definition.field
+
+(trait_item
+  name: (type_identifier) @name) @definition.interface
+
+(type_item
+  name: (type_identifier) @name) @definition.type
+
+; Impl blocks, named after their type
+(impl_item
+  type: (_) @name) @definition.impl
+
+; Functions and macros
+(function_item
+  name: (identifier) @name) @definition.function
+
+(function_signature_item
+  name: (identifier) @name) @definition.function
+
+(macro_definition
+  name: (identifier) @name) @definition.macro
+
+; Constants and statics
+(const_item
+  name: (identifier) @name) @definition.constant
+
+(static_item
+  name: (identifier) @name) @definition.constant
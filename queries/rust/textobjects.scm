@@ -0,0 +1,62 @@
+; Functions and closures
+(function_item
+  body: (block) @function.inner) @function.outer
+
+(function_signature_item) @function.outer
+
+(closure_expression
+  body: (_) @function.inner) @function.outer
+
+; Structs, enums, unions, traits and impl blocks
+(struct_item
+  body: (field_declaration_list) @class.inner) @class.outer
+
+(enum_item
+  body: (enum_variant_list) @class.inner) @class.outer
+
+(union_item
+  body: (field_declaration_list) @class.inner) @class.outer
+
+(trait_item
+  body: (declaration_list) @class.inner) @class.outer
+
+(impl_item
+  body: (declaration_list) @class.inner) @class.outer
+
+; Parameters and arguments, with the comma after them
+(parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(closure_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(arguments
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(type_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(type_arguments
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+[
+  (line_comment)
+  (block_comment)
+] @comment.outer
+
+; Blocks
+[
+  (block)
+  (match_block)
+] @block.outer @block.inner
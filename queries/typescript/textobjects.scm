@@ -0,0 +1,57 @@
+; Functions, arrow functions and methods
+[
+  (function_declaration
+    body: (statement_block) @function.inner)
+  (function_expression
+    body: (statement_block) @function.inner)
+  (generator_function_declaration
+    body: (statement_block) @function.inner)
+  (arrow_function
+    body: (_) @function.inner)
+  (method_definition
+    body: (statement_block) @function.inner)
+  (function_signature)
+  (method_signature)
+] @function.outer
+
+; Classes
+[
+  (class_declaration
+    body: (class_body) @class.inner)
+  (abstract_class_declaration
+    body: (class_body) @class.inner)
+  (interface_declaration
+    body: (interface_body) @class.inner)
+  (class
+    body: (class_body) @class.inner)
+] @class.outer
+
+; Parameters and arguments, with the comma after them
+(formal_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(arguments
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(type_parameters
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+(type_arguments
+  (_) @parameter.inner @parameter.outer
+  .
+  ","? @parameter.outer)
+
+; Comments
+(comment) @comment.outer
+
+; Blocks
+[
+  (statement_block)
+  (class_body)
+] @block.outer @block.inner
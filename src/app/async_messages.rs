@@ -9,6 +9,7 @@
 
 use crate::model::event::BufferId;
 use crate::services::async_bridge::{LspMessageType, LspProgressValue, LspServerStatus};
+use crate::services::lsp::features::LspFeature;
 use crate::view::file_tree::{FileTreeView, NodeId};
 use lsp_types::{Diagnostic, InlayHint};
 use serde_json::Value;
@@ -88,18 +89,37 @@ impl Editor {
     }
 
     /// Handle LSP diagnostics (push model)
-    pub(super) fn handle_lsp_diagnostics(&mut self, uri: String, diagnostics: Vec<Diagnostic>) {
+    pub(super) fn handle_lsp_diagnostics(
+        &mut self,
+        language: String,
+        server: String,
+        uri: String,
+        diagnostics: Vec<Diagnostic>,
+    ) {
         tracing::debug!(
-            "Processing {} LSP diagnostics for {}",
+            "Processing {} LSP diagnostics for {} from {}",
             diagnostics.len(),
-            uri
+            uri,
+            server
         );
+
+        // A server configured without diagnostics still publishes them; drop those
+        let allowed = self
+            .lsp
+            .as_ref()
+            .is_none_or(|lsp| lsp.allows_feature(&language, &server, LspFeature::Diagnostics));
+        if !allowed {
+            return;
+        }
+
+        let diagnostics = self.server_diagnostics.update(&uri, &server, diagnostics);
         self.store_and_apply_diagnostics(uri, diagnostics);
     }
 
     /// Handle LSP pulled diagnostics (pull model - LSP 3.17+)
     pub(super) fn handle_lsp_pulled_diagnostics(
         &mut self,
+        server: String,
         uri: String,
         result_id: Option<String>,
         diagnostics: Vec<Diagnostic>,
@@ -115,17 +135,20 @@ impl Editor {
         }
 
         tracing::debug!(
-            "Processing {} pulled diagnostics for {} (result_id: {:?})",
+            "Processing {} pulled diagnostics for {} from {} (result_id: {:?})",
             diagnostics.len(),
             uri,
+            server,
             result_id
         );
 
         // Store result_id for incremental updates
         if let Some(result_id) = result_id {
-            self.diagnostic_result_ids.insert(uri.clone(), result_id);
+            self.diagnostic_result_ids
+                .insert((uri.clone(), server.clone()), result_id);
         }
 
+        let diagnostics = self.server_diagnostics.update(&uri, &server, diagnostics);
         self.store_and_apply_diagnostics(uri, diagnostics);
     }
 }
@@ -185,32 +208,29 @@ impl Editor {
             return;
         }
 
-        let Some(lsp) = self.lsp.as_mut() else {
-            return;
-        };
-
-        let Some(client) = lsp.get_or_spawn(&language) else {
-            return;
-        };
-
         // Collect buffer info first to avoid borrow issues
         let buffer_infos: Vec<_> = self
             .buffer_metadata
             .iter()
+            .filter(|(_, metadata)| metadata.language.as_deref() == Some(language.as_str()))
             .filter_map(|(buffer_id, metadata)| {
-                metadata.file_uri().map(|uri| {
-                    let line_count = self
-                        .buffers
-                        .get(buffer_id)
-                        .and_then(|s| s.buffer.line_count())
-                        .unwrap_or(1000);
-                    (uri.clone(), line_count)
-                })
+                let uri = metadata.file_uri()?;
+                let line_count = self
+                    .buffers
+                    .get(buffer_id)
+                    .and_then(|s| s.buffer.line_count())
+                    .unwrap_or(1000);
+                Some((uri.clone(), metadata.file_path()?.clone(), line_count))
             })
             .collect();
 
-        // Request inlay hints for each buffer
-        for (uri, line_count) in buffer_infos {
+        // Request inlay hints for each buffer, from the server of its root
+        for (uri, path, line_count) in buffer_infos {
+            let Some(client) = self.lsp.as_mut().and_then(|lsp| {
+                lsp.get_or_spawn_for(&language, Some(&path), LspFeature::InlayHints)
+            }) else {
+                continue;
+            };
             let request_id = self.next_lsp_request_id;
             self.next_lsp_request_id += 1;
             self.pending_inlay_hints_request = Some(request_id);
@@ -325,15 +345,23 @@ impl Editor {
     }
 
     /// Handle LSP server status update
-    pub(super) fn handle_lsp_status_update(&mut self, language: String, status: LspServerStatus) {
+    pub(super) fn handle_lsp_status_update(
+        &mut self,
+        language: String,
+        server: String,
+        root: Option<lsp_types::Uri>,
+        status: LspServerStatus,
+    ) {
         use crate::services::async_bridge::LspServerStatus;
 
+        let root = root.and_then(|root| super::uri_to_path(&root).ok());
+        let key = (language.clone(), server.clone(), root);
+
         // Get old status for event
-        let old_status = self.lsp_server_statuses.get(&language).cloned();
+        let old_status = self.lsp_server_statuses.get(&key).cloned();
 
         // Update server status
-        self.lsp_server_statuses
-            .insert(language.clone(), status.clone());
+        self.lsp_server_statuses.insert(key, status.clone());
         self.update_lsp_status_from_server_statuses();
 
         // Handle server crash - trigger auto-restart
@@ -345,9 +373,15 @@ impl Editor {
 
             if was_running {
                 if let Some(lsp) = self.lsp.as_mut() {
-                    let message = lsp.handle_server_crash(&language);
+                    let message = lsp.handle_server_crash(&language, &server);
                     self.status_message = Some(message);
                 }
+
+                // Its diagnostics would otherwise linger until it restarts
+                for uri in self.server_diagnostics.remove_server(&server) {
+                    let diagnostics = self.server_diagnostics.update(&uri, &server, Vec::new());
+                    self.store_and_apply_diagnostics(uri, diagnostics);
+                }
             }
         }
 
@@ -373,6 +407,7 @@ impl Editor {
             crate::model::control_event::events::LSP_STATUS_CHANGED.name,
             serde_json::json!({
                 "language": language,
+                "server": server,
                 "old_status": old_status_str,
                 "status": status_str
             }),
@@ -534,6 +569,7 @@ impl Editor {
 
         self.file_explorer = Some(view);
         self.set_status_message("File explorer ready".to_string());
+        self.refresh_git_status();
     }
 
     /// Handle file explorer node toggle completed
@@ -666,9 +702,7 @@ impl Editor {
             .iter()
             .filter_map(|(buf_id, meta)| {
                 meta.file_path().and_then(|path| {
-                    if crate::services::lsp::manager::detect_language(path, &self.config.languages)
-                        == Some(language.to_string())
-                    {
+                    if meta.language.as_deref() == Some(language) {
                         Some((*buf_id, path.clone()))
                     } else {
                         None
@@ -689,13 +723,11 @@ impl Editor {
                     .and_then(|u| u.as_str().parse::<lsp_types::Uri>().ok());
 
                 if let Some(uri) = uri {
-                    if let Some(lang_id) = crate::services::lsp::manager::detect_language(
-                        &path,
-                        &self.config.languages,
-                    ) {
+                    if let Some(lang_id) = self.buffer_language(buffer_id) {
                         if let Some(lsp) = self.lsp.as_mut() {
-                            if let Some(handle) = lsp.get_or_spawn(&lang_id) {
-                                let _ = handle.did_open(uri, content, lang_id);
+                            for handle in lsp.get_or_spawn_all(&lang_id, Some(&path)) {
+                                let _ =
+                                    handle.did_open(uri.clone(), content.clone(), lang_id.clone());
                             }
                         }
                     }
@@ -1,4 +1,8 @@
+use super::types::{ConflictChoice, FileClipboard, FileTransferState};
 use super::*;
+use crate::services::file_transfer::{self, TransferMode};
+use crate::services::git_status::GitStatus;
+use crate::services::trash::Trash;
 use crate::view::file_tree::TreeNode;
 use std::path::PathBuf;
 
@@ -24,6 +28,18 @@ fn timestamp_suffix() -> u64 {
         .as_secs()
 }
 
+/// A short description of the paths of a file operation: the name of a
+/// single one, else a count
+fn describe_paths(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => format!(
+            "'{}'",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        _ => format!("{} items", paths.len()),
+    }
+}
+
 /// Get the parent node ID for refreshing after file operations.
 /// If the node is a directory, the node itself is the parent. Otherwise, look up the actual parent.
 fn get_parent_node_id(
@@ -355,6 +371,7 @@ impl Editor {
                 }
             }
         }
+        self.refresh_git_status();
     }
 
     pub fn file_explorer_new_file(&mut self) {
@@ -388,6 +405,7 @@ impl Editor {
                 }
             }
         }
+        self.refresh_git_status();
     }
 
     pub fn file_explorer_new_directory(&mut self) {
@@ -433,46 +451,154 @@ impl Editor {
                 }
             }
         }
+        self.refresh_git_status();
     }
 
+    /// Move the marked or selected files to the trash, after confirming if
+    /// any is a directory. Without a trash they're deleted for good, after
+    /// confirming.
     pub fn file_explorer_delete(&mut self) {
+        self.start_file_explorer_delete(false);
+    }
+
+    /// Delete the marked or selected files for good, after confirming
+    pub fn file_explorer_delete_permanently(&mut self) {
+        self.start_file_explorer_delete(true);
+    }
+
+    fn start_file_explorer_delete(&mut self, permanent: bool) {
+        let Some(explorer) = &self.file_explorer else {
+            return;
+        };
+        // Without a trash every delete is permanent, and confirmed as such
+        let permanent = permanent || self.dir_context.trash_dir().is_none();
+        let paths = explorer.operation_paths();
+        if paths.is_empty() {
+            // Only the root can be selected without being an operation path
+            self.set_status_message("Cannot delete project root".to_string());
+            return;
+        }
+
+        let message = if permanent {
+            Some(format!(
+                "Permanently delete {}? This cannot be undone (y/n): ",
+                describe_paths(&paths)
+            ))
+        } else if paths.iter().any(|path| path.is_dir() && !path.is_symlink()) {
+            Some(format!(
+                "Move {} and contents to trash? (y/n): ",
+                describe_paths(&paths)
+            ))
+        } else {
+            None
+        };
+        match message {
+            Some(message) => self.start_prompt(
+                message,
+                PromptType::ConfirmFileExplorerDelete { paths, permanent },
+            ),
+            None => self.perform_file_explorer_delete(paths, false),
+        }
+    }
+
+    /// Delete files confirmed in the file explorer, to the trash unless
+    /// `permanent`. Trashed files can be restored with Undo Delete.
+    pub(super) fn perform_file_explorer_delete(&mut self, paths: Vec<PathBuf>, permanent: bool) {
+        let trash = if permanent {
+            None
+        } else {
+            self.dir_context.trash_dir().map(Trash::new)
+        };
+
+        let mut deleted = Vec::new();
+        let mut trashed = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            let result = match &trash {
+                Some(trash) => trash.trash(&path).map(|item| trashed.push(item)),
+                None => file_transfer::remove(&path),
+            };
+            match result {
+                Ok(()) => deleted.push(path),
+                Err(e) => errors.push(e.to_string()),
+            }
+        }
+
+        let parents: Vec<PathBuf> = deleted
+            .iter()
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+        if let Some(first) = parents.first().cloned() {
+            self.refresh_file_explorer_dirs(parents, &first);
+        }
         if let Some(explorer) = &mut self.file_explorer {
-            if let Some(selected_id) = explorer.get_selected() {
-                // Don't allow deleting the root directory
-                if selected_id == explorer.tree().root_id() {
-                    self.set_status_message("Cannot delete project root".to_string());
-                    return;
-                }
+            explorer.clear_marks();
+        }
 
-                let node = explorer.tree().get_node(selected_id);
-                if let Some(node) = node {
-                    let path = node.entry.path.clone();
-                    let name = node.entry.name.clone();
+        let mut message = if deleted.is_empty() {
+            "Nothing was deleted".to_string()
+        } else if trash.is_none() {
+            format!("Deleted {}", describe_paths(&deleted))
+        } else {
+            format!(
+                "Moved {} to trash (Undo Delete restores)",
+                describe_paths(&deleted)
+            )
+        };
+        if let Some(first) = errors.first() {
+            message.push_str(&format!("; {} failed: {}", errors.len(), first));
+        }
+        if !trashed.is_empty() {
+            self.file_explorer_trashed.push(trashed);
+        }
+        self.set_status_message(message);
+    }
 
-                    if let Some(runtime) = &self.tokio_runtime {
-                        let result = if node.is_dir() {
-                            runtime.block_on(async { tokio::fs::remove_dir_all(&path).await })
-                        } else {
-                            runtime.block_on(async { tokio::fs::remove_file(&path).await })
-                        };
+    /// Restore the files of the last delete to the trash. Files that can't be
+    /// restored (their path is taken again) stay to be retried.
+    pub fn file_explorer_undo_delete(&mut self) {
+        let Some(items) = self.file_explorer_trashed.pop() else {
+            self.set_status_message("No deleted files to restore".to_string());
+            return;
+        };
 
-                        match result {
-                            Ok(_) => {
-                                // For delete, always get the parent (the deleted item can't be refreshed)
-                                let parent_id =
-                                    get_parent_node_id(explorer.tree(), selected_id, false);
-                                let tree = explorer.tree_mut();
-                                let _ = runtime.block_on(tree.refresh_node(parent_id));
-                                self.set_status_message(format!("Deleted {}", name));
-                            }
-                            Err(e) => {
-                                self.set_status_message(format!("Error deleting: {}", e));
-                            }
-                        }
-                    }
+        let mut restored = Vec::new();
+        let mut failed = Vec::new();
+        let mut errors = Vec::new();
+        for item in items {
+            match item.restore() {
+                Ok(()) => restored.push(item.original_path),
+                Err(e) => {
+                    errors.push(e.to_string());
+                    failed.push(item);
                 }
             }
         }
+        if !failed.is_empty() {
+            self.file_explorer_trashed.push(failed);
+        }
+
+        let parents: Vec<PathBuf> = restored
+            .iter()
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+        if let Some(first) = parents.first().cloned() {
+            self.refresh_file_explorer_dirs(parents, &first);
+        }
+        if let (Some(explorer), Some(last)) = (&mut self.file_explorer, restored.last()) {
+            explorer.navigate_to_path(last);
+            explorer.update_scroll_for_selection();
+        }
+
+        let mut message = if restored.is_empty() {
+            "Nothing was restored".to_string()
+        } else {
+            format!("Restored {}", describe_paths(&restored))
+        };
+        if let Some(first) = errors.first() {
+            message.push_str(&format!("; {} failed: {}", errors.len(), first));
+        }
+        self.set_status_message(message);
     }
 
     pub fn file_explorer_rename(&mut self) {
@@ -527,6 +653,7 @@ impl Editor {
 
             match result {
                 Ok(_) => {
+                    self.retarget_moved_buffers(&original_path, &new_path);
                     // Refresh the parent directory and select the renamed item
                     if let Some(explorer) = &mut self.file_explorer {
                         if let Some(selected_id) = explorer.get_selected() {
@@ -544,6 +671,7 @@ impl Editor {
                 }
             }
         }
+        self.refresh_git_status();
     }
 
     pub fn file_explorer_toggle_hidden(&mut self) {
@@ -570,4 +698,387 @@ impl Editor {
             self.set_status_message(msg.to_string());
         }
     }
+
+    /// Mark or unmark the selected node, then move to the next one
+    pub fn file_explorer_toggle_mark(&mut self) {
+        if let Some(explorer) = &mut self.file_explorer {
+            if let Some(selected_id) = explorer.get_selected() {
+                explorer.toggle_mark(selected_id);
+                explorer.select_next();
+                explorer.update_scroll_for_selection();
+            }
+        }
+    }
+
+    /// Mark the selected node and the one above or below it, and move there
+    pub fn file_explorer_extend_marks(&mut self, down: bool) {
+        if let Some(explorer) = &mut self.file_explorer {
+            if let Some(selected_id) = explorer.get_selected() {
+                explorer.mark(selected_id);
+            }
+            if down {
+                explorer.select_next();
+            } else {
+                explorer.select_prev();
+            }
+            if let Some(selected_id) = explorer.get_selected() {
+                explorer.mark(selected_id);
+            }
+            explorer.update_scroll_for_selection();
+        }
+    }
+
+    pub fn file_explorer_clear_marks(&mut self) {
+        if let Some(explorer) = &mut self.file_explorer {
+            explorer.clear_marks();
+            self.set_status_message("Marks cleared".to_string());
+        }
+    }
+
+    /// Put the marked nodes (or the selected one) on the file explorer's
+    /// clipboard, to be copied, or with `TransferMode::Move` moved, on paste
+    pub fn file_explorer_copy(&mut self, mode: TransferMode) {
+        let Some(explorer) = &self.file_explorer else {
+            return;
+        };
+        let paths = explorer.operation_paths();
+        if paths.is_empty() {
+            self.set_status_message("Select or mark files to copy".to_string());
+            return;
+        }
+
+        let verb = match mode {
+            TransferMode::Copy => "Copied",
+            TransferMode::Move => "Cut",
+        };
+        self.set_status_message(format!(
+            "{} {}; paste them into a directory",
+            verb,
+            describe_paths(&paths)
+        ));
+        self.file_explorer_clipboard = Some(FileClipboard { paths, mode });
+    }
+
+    /// Paste the clipboard's files into the selected directory (or the
+    /// selected file's directory). Cut files are pasted only once.
+    pub fn file_explorer_paste(&mut self) {
+        let Some(clipboard) = self.file_explorer_clipboard.clone() else {
+            self.set_status_message("Nothing to paste; copy or cut files first".to_string());
+            return;
+        };
+        let Some(target_dir) = self
+            .file_explorer
+            .as_ref()
+            .and_then(|explorer| explorer.tree().get_node(explorer.get_selected()?))
+            .map(get_parent_dir_path)
+        else {
+            return;
+        };
+
+        if clipboard.mode == TransferMode::Move {
+            self.file_explorer_clipboard = None;
+        }
+        self.start_file_transfer(clipboard.paths, target_dir, clipboard.mode);
+    }
+
+    /// Copy or move files into a directory, asking what to do about each name
+    /// that is already taken there
+    pub(super) fn start_file_transfer(
+        &mut self,
+        paths: Vec<PathBuf>,
+        target_dir: PathBuf,
+        mode: TransferMode,
+    ) {
+        let mut source_dirs: Vec<PathBuf> = paths
+            .iter()
+            .filter_map(|path| path.parent().map(|dir| dir.to_path_buf()))
+            .collect();
+        source_dirs.sort();
+        source_dirs.dedup();
+
+        self.file_transfer = Some(FileTransferState {
+            mode,
+            target_dir,
+            remaining: paths.into(),
+            source_dirs,
+            done: Vec::new(),
+            errors: Vec::new(),
+            conflict_choice: None,
+        });
+        self.continue_file_transfer(None);
+    }
+
+    /// Transfer the remaining sources, resolving the conflict of the first one
+    /// with `choice`. Stops to prompt at a conflict without a choice.
+    fn continue_file_transfer(&mut self, mut choice: Option<ConflictChoice>) {
+        let Some(mut transfer) = self.file_transfer.take() else {
+            return;
+        };
+
+        while let Some(source) = transfer.remaining.front().cloned() {
+            let mut destination = match file_transfer::destination(&source, &transfer.target_dir) {
+                Ok(destination) => destination,
+                Err(e) => {
+                    transfer.errors.push(e.to_string());
+                    transfer.remaining.pop_front();
+                    continue;
+                }
+            };
+
+            let mut overwrite = false;
+            if destination == source {
+                // Copying into its own directory makes a copy next to it;
+                // moving there does nothing
+                if transfer.mode == TransferMode::Move {
+                    transfer.remaining.pop_front();
+                    continue;
+                }
+                destination = file_transfer::keep_both_destination(&destination);
+            } else if destination.exists() {
+                match choice.take().or(transfer.conflict_choice) {
+                    Some(ConflictChoice::Overwrite) => overwrite = true,
+                    Some(ConflictChoice::KeepBoth) => {
+                        destination = file_transfer::keep_both_destination(&destination);
+                    }
+                    Some(ConflictChoice::Skip) => {
+                        transfer.remaining.pop_front();
+                        continue;
+                    }
+                    None => {
+                        let message = format!(
+                            "'{}' exists in {}: (o)verwrite, (k)eep both, (s)kip, (c)ancel (O/K/S: all): ",
+                            destination.file_name().unwrap_or_default().to_string_lossy(),
+                            transfer.target_dir.display()
+                        );
+                        self.file_transfer = Some(transfer);
+                        self.start_prompt(message, PromptType::FileTransferConflict);
+                        return;
+                    }
+                }
+            }
+
+            transfer.remaining.pop_front();
+            match file_transfer::transfer(&source, &destination, transfer.mode, overwrite) {
+                Ok(()) => {
+                    if transfer.mode == TransferMode::Move {
+                        self.retarget_moved_buffers(&source, &destination);
+                    }
+                    transfer.done.push(destination);
+                }
+                Err(e) => transfer.errors.push(format!("{}: {}", source.display(), e)),
+            }
+        }
+
+        self.finish_file_transfer(transfer);
+    }
+
+    /// Handle the answer to a file transfer's conflict prompt
+    pub(super) fn resolve_file_transfer_conflict(&mut self, answer: &str) {
+        let (choice, for_all) = match answer.trim() {
+            "o" => (ConflictChoice::Overwrite, false),
+            "O" => (ConflictChoice::Overwrite, true),
+            "k" => (ConflictChoice::KeepBoth, false),
+            "K" => (ConflictChoice::KeepBoth, true),
+            "s" => (ConflictChoice::Skip, false),
+            "S" => (ConflictChoice::Skip, true),
+            _ => {
+                self.cancel_file_transfer();
+                return;
+            }
+        };
+        if let Some(transfer) = &mut self.file_transfer {
+            if for_all {
+                transfer.conflict_choice = Some(choice);
+            }
+        }
+        self.continue_file_transfer(Some(choice));
+    }
+
+    /// Stop a file transfer at its conflict, keeping what was transferred
+    pub(super) fn cancel_file_transfer(&mut self) {
+        if let Some(transfer) = self.file_transfer.take() {
+            self.finish_file_transfer(transfer);
+        }
+    }
+
+    /// The explorer node shown at a screen position
+    fn file_explorer_node_at(&self, col: u16, row: u16) -> Option<crate::view::file_tree::NodeId> {
+        let area = self.cached_layout.file_explorer_area?;
+        // Skip the top border; the bottom border has no node
+        if col < area.x
+            || col >= area.x + area.width
+            || row <= area.y
+            || row + 1 >= area.y + area.height
+        {
+            return None;
+        }
+        let explorer = self.file_explorer.as_ref()?;
+        let index = (row - area.y - 1) as usize + explorer.get_scroll_offset();
+        explorer.get_display_nodes().get(index).map(|(id, _)| *id)
+    }
+
+    /// Highlight the directory the dragged files would be dropped into
+    pub(super) fn handle_file_explorer_drag(&mut self, col: u16, row: u16) {
+        let target = self.file_explorer_node_at(col, row).and_then(|node_id| {
+            let explorer = self.file_explorer.as_ref()?;
+            explorer.tree().get_node(node_id).map(get_parent_dir_path)
+        });
+        if let Some(explorer) = &mut self.file_explorer {
+            explorer.set_drop_target(target);
+        }
+    }
+
+    /// Move dragged files into the directory they were dropped on
+    pub(super) fn handle_file_explorer_drop(&mut self, paths: Vec<PathBuf>) {
+        let Some(target_dir) = self.file_explorer.as_mut().and_then(|explorer| {
+            let target = explorer.drop_target().map(|dir| dir.to_path_buf());
+            explorer.set_drop_target(None);
+            target
+        }) else {
+            return;
+        };
+        // Dropped where they already are (a click, or a drag within one row)
+        if paths.contains(&target_dir)
+            || paths
+                .iter()
+                .all(|path| path.parent() == Some(target_dir.as_path()))
+        {
+            return;
+        }
+        self.start_file_transfer(paths, target_dir, TransferMode::Move);
+    }
+
+    /// Refresh the explorer after a transfer, select what was transferred and report
+    fn finish_file_transfer(&mut self, transfer: FileTransferState) {
+        let mut dirs = vec![transfer.target_dir.clone()];
+        if transfer.mode == TransferMode::Move {
+            dirs.extend(transfer.source_dirs.iter().cloned());
+        }
+        self.refresh_file_explorer_dirs(dirs, &transfer.target_dir);
+        if let (Some(explorer), Some(last)) = (&mut self.file_explorer, transfer.done.last()) {
+            explorer.clear_marks();
+            explorer.navigate_to_path(last);
+            explorer.update_scroll_for_selection();
+        }
+
+        let verb = match transfer.mode {
+            TransferMode::Copy => "Copied",
+            TransferMode::Move => "Moved",
+        };
+        let mut message = if transfer.done.is_empty() {
+            "Nothing was transferred".to_string()
+        } else {
+            format!(
+                "{} {} to {}",
+                verb,
+                describe_paths(&transfer.done),
+                transfer.target_dir.display()
+            )
+        };
+        if let Some(first) = transfer.errors.first() {
+            message.push_str(&format!("; {} failed: {}", transfer.errors.len(), first));
+        }
+        if !transfer.remaining.is_empty() {
+            message.push_str(&format!("; cancelled, {} left", transfer.remaining.len()));
+        }
+        self.set_status_message(message);
+    }
+
+    /// Re-read directories shown in the explorer. `expand` is expanded even
+    /// if it was collapsed, the others only refreshed if expanded.
+    fn refresh_file_explorer_dirs(&mut self, mut dirs: Vec<PathBuf>, expand: &Path) {
+        // Parents first: refreshing a directory collapses its subdirectories
+        dirs.sort_by_key(|dir| dir.components().count());
+        dirs.dedup();
+        if let (Some(runtime), Some(explorer)) = (&self.tokio_runtime, &mut self.file_explorer) {
+            for dir in dirs {
+                let Some(node) = explorer.tree().get_node_by_path(&dir) else {
+                    continue;
+                };
+                if node.is_expanded() || dir == expand {
+                    let node_id = node.id;
+                    let _ = runtime.block_on(explorer.tree_mut().refresh_node(node_id));
+                }
+            }
+        }
+        self.refresh_git_status();
+    }
+
+    /// Point open buffers of files at or under `from`, which was moved to
+    /// `to`, at their new paths
+    pub(super) fn retarget_moved_buffers(&mut self, from: &Path, to: &Path) {
+        let moved: Vec<(BufferId, PathBuf)> = self
+            .buffer_metadata
+            .iter()
+            .filter_map(|(buffer_id, metadata)| {
+                let relative = metadata.file_path()?.strip_prefix(from).ok()?;
+                let new_path = if relative.as_os_str().is_empty() {
+                    to.to_path_buf()
+                } else {
+                    to.join(relative)
+                };
+                Some((*buffer_id, new_path))
+            })
+            .collect();
+
+        for (buffer_id, new_path) in moved {
+            let Some(old) = self.buffer_metadata.remove(&buffer_id) else {
+                continue;
+            };
+            if let Some(state) = self.buffers.get_mut(&buffer_id) {
+                state.buffer.set_file_path(new_path.clone());
+            }
+            if let Some(mtime) = old.file_path().and_then(|p| self.file_mod_times.remove(p)) {
+                self.file_mod_times.insert(new_path.clone(), mtime);
+            }
+
+            if old.lsp_enabled {
+                self.notify_lsp_file_closed(&old);
+            }
+            let mut metadata = BufferMetadata::with_file(new_path.clone(), &self.working_dir);
+            metadata.lsp_enabled = old.lsp_enabled;
+            metadata.lsp_disabled_reason = old.lsp_disabled_reason;
+            metadata.read_only = old.read_only;
+            metadata.binary = old.binary;
+            metadata.language = old.language;
+            if metadata.lsp_enabled {
+                self.notify_lsp_file_opened(&new_path, buffer_id, &mut metadata);
+            }
+            self.buffer_metadata.insert(buffer_id, metadata);
+        }
+    }
+
+    /// Reload the git status shown in the file explorer in the background.
+    /// Requests while a reload runs are coalesced into one more reload.
+    pub(super) fn refresh_git_status(&mut self) {
+        if self.file_explorer.is_none() {
+            return;
+        }
+        if self.git_status_loading {
+            self.git_status_stale = true;
+            return;
+        }
+        let (Some(runtime), Some(bridge)) = (&self.tokio_runtime, &self.async_bridge) else {
+            return;
+        };
+        let sender = bridge.sender();
+        let dir = self.working_dir.clone();
+        self.git_status_loading = true;
+        runtime.spawn_blocking(move || {
+            let status = GitStatus::load(&dir).unwrap_or_else(|e| {
+                tracing::warn!("Failed to get git status of {}: {}", dir.display(), e);
+                None
+            });
+            let _ = sender.send(AsyncMessage::GitStatusChanged { status });
+        });
+    }
+
+    /// Handle a reloaded git status
+    pub(super) fn handle_git_status_changed(&mut self, status: Option<GitStatus>) {
+        self.git_status_loading = false;
+        self.file_decorations.set_git_status(status);
+        if std::mem::take(&mut self.git_status_stale) {
+            self.refresh_git_status();
+        }
+    }
 }
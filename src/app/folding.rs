@@ -0,0 +1,224 @@
+//! Code folding commands
+//!
+//! Fold commands work on the active buffer's foldable ranges (see
+//! `EditorState::fold_ranges`). A buffer without a syntax tree first asks its
+//! language server for `textDocument/foldingRange`; the command runs when the
+//! answer arrives, on indentation-based ranges if the server has none.
+//!
+//! Cursors hidden by a new fold are moved to the end of its header line, since
+//! rendering unfolds any fold that hides a cursor.
+
+use super::*;
+use crate::primitives::fold_ranges::{self, FoldRange};
+
+/// A folding range request and the fold command waiting for its answer
+#[derive(Debug)]
+pub(super) struct PendingFoldingRanges {
+    request_id: u64,
+    buffer_id: BufferId,
+    action: Action,
+}
+
+impl Editor {
+    /// Run a command that needs the buffer's foldable ranges (Fold, Fold All,
+    /// Fold Level), asking the language server for them first if needed
+    pub(super) fn run_fold_command(&mut self, action: Action) {
+        if !self.request_folding_ranges(&action) {
+            self.apply_fold_command(action);
+        }
+    }
+
+    /// Unfold the folded region whose header is the cursor's line
+    pub(super) fn unfold_at_cursor(&mut self) {
+        let state = self.active_state_mut();
+        let cursor_line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position);
+        if !state.folds.unfold(&state.buffer, cursor_line) {
+            self.set_status_message("Nothing folded at cursor".to_string());
+        }
+    }
+
+    /// Unfold every folded region
+    pub(super) fn unfold_all(&mut self) {
+        self.active_state_mut().folds.unfold_all();
+    }
+
+    fn apply_fold_command(&mut self, action: Action) {
+        match action {
+            Action::Fold => self.fold_at_cursor(),
+            Action::FoldAll => self.fold_all(),
+            Action::FoldToLevel(level) => self.fold_to_level(level),
+            _ => {}
+        }
+    }
+
+    /// Fold the innermost unfolded region containing the cursor's line
+    fn fold_at_cursor(&mut self) {
+        let state = self.active_state_mut();
+        let cursor_line = state
+            .buffer
+            .get_line_number(state.cursors.primary().position);
+        let target = state
+            .fold_ranges()
+            .into_iter()
+            .filter(|range| range.contains_line(cursor_line))
+            .filter(|range| !state.folds.is_folded(&state.buffer, range.start_line))
+            .max_by_key(|range| range.start_line);
+
+        match target {
+            Some(range) => {
+                state.folds.fold(&state.buffer, range);
+                self.move_cursors_out_of_folds();
+            }
+            None => self.set_status_message("Nothing to fold at cursor".to_string()),
+        }
+    }
+
+    /// Fold every foldable region, nested ones included
+    fn fold_all(&mut self) {
+        let state = self.active_state_mut();
+        let ranges = state.fold_ranges();
+        self.fold_each(ranges);
+    }
+
+    /// Unfold everything, then fold the regions nested `level` deep
+    /// (1 folds the outermost regions)
+    fn fold_to_level(&mut self, level: usize) {
+        let state = self.active_state_mut();
+        let ranges = state.fold_ranges();
+        let levels = fold_ranges::nesting_levels(&ranges);
+        state.folds.unfold_all();
+        let at_level = ranges
+            .into_iter()
+            .zip(levels)
+            .filter(|&(_, range_level)| range_level == level)
+            .map(|(range, _)| range)
+            .collect();
+        self.fold_each(at_level);
+    }
+
+    fn fold_each(&mut self, ranges: Vec<FoldRange>) {
+        if ranges.is_empty() {
+            self.set_status_message("Nothing to fold".to_string());
+            return;
+        }
+        let state = self.active_state_mut();
+        let folded = ranges
+            .into_iter()
+            .filter(|&range| state.folds.fold(&state.buffer, range))
+            .count();
+        self.move_cursors_out_of_folds();
+        self.set_status_message(format!("Folded {} regions", folded));
+    }
+
+    /// Move cursors hidden by a fold to the end of the fold's header line
+    fn move_cursors_out_of_folds(&mut self) {
+        let state = self.active_state();
+        let events: Vec<Event> = state
+            .cursors
+            .iter()
+            .filter_map(|(cursor_id, cursor)| {
+                let fold = state.folds.hiding(&state.buffer, cursor.position)?;
+                let header_end = state.buffer.line_start_offset(fold.start_line + 1)? - 1;
+                Some(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: header_end,
+                    old_anchor: cursor.anchor,
+                    new_anchor: None,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                })
+            })
+            .collect();
+        for event in events {
+            self.apply_event_to_active_buffer(&event);
+        }
+    }
+
+    /// Ask the language server for the active buffer's folding ranges when it
+    /// has no syntax tree and no answer for its current content. `action` runs
+    /// when the answer arrives. Returns true if a request was sent.
+    fn request_folding_ranges(&mut self, action: &Action) -> bool {
+        let buffer_id = self.active_buffer();
+        let state = self.active_state_mut();
+        if state.buffer.is_large_file() || state.folds.lsp_ranges().is_some() {
+            return false;
+        }
+        let has_tree = state
+            .syntax_tree
+            .as_mut()
+            .and_then(|syntax| syntax.tree(&state.buffer))
+            .is_some();
+        if has_tree {
+            return false;
+        }
+
+        let Some((uri, path)) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| Some((meta.file_uri()?.clone(), meta.file_path()?.clone())))
+        else {
+            return false;
+        };
+        let Some(language) = self.language_for_path(&path) else {
+            return false;
+        };
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.get_or_spawn_for(&language, Some(&path), LspFeature::FoldingRange))
+        else {
+            return false;
+        };
+        if let Err(e) = handle.folding_ranges(request_id, uri) {
+            tracing::debug!("Failed to request folding ranges: {}", e);
+            return false;
+        }
+
+        self.next_lsp_request_id += 1;
+        self.pending_folding_range_request = Some(PendingFoldingRanges {
+            request_id,
+            buffer_id,
+            action: action.clone(),
+        });
+        true
+    }
+
+    /// Handle a folding range response: remember the ranges, then run the
+    /// waiting fold command if its buffer is still active
+    pub(super) fn handle_folding_range_response(
+        &mut self,
+        request_id: u64,
+        uri: String,
+        result: Result<Vec<lsp_types::FoldingRange>, String>,
+    ) {
+        let Some(pending) = self
+            .pending_folding_range_request
+            .take_if(|pending| pending.request_id == request_id)
+        else {
+            tracing::debug!("Ignoring stale folding range response: {}", request_id);
+            return;
+        };
+
+        match result {
+            // An empty answer falls back to indentation
+            Ok(ranges) if !ranges.is_empty() => {
+                if let Some(state) = self
+                    .find_buffer_by_uri(&uri)
+                    .and_then(|buffer_id| self.buffers.get_mut(&buffer_id))
+                {
+                    state.folds.set_lsp_ranges(fold_ranges::from_lsp(&ranges));
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("No folding ranges from the language server: {}", e),
+        }
+
+        if self.active_buffer() == pending.buffer_id {
+            self.apply_fold_command(pending.action);
+        }
+    }
+}
@@ -0,0 +1,463 @@
+//! Document formatting through the language server
+//!
+//! Format Document and Format Selection send `textDocument/formatting` or
+//! `textDocument/rangeFormatting` and apply the returned edits as one undoable
+//! batch. Languages with `format_on_save` are formatted before each save; the
+//! save waits for the server, but no longer than `format_on_save_timeout_ms`.
+//!
+//! Languages with `formatters` are formatted by piping the buffer through those
+//! commands instead of asking the server. They run on a thread of their own so
+//! the editor stays responsive. The output is applied as a line diff, so
+//! unchanged lines (and the cursors in them) are left alone, and it is dropped
+//! if the buffer was edited in the meantime.
+//!
+//! Each buffer has at most one formatting request or formatter run pending,
+//! so formatting one buffer never drops another's pending save. A newer
+//! request or run for the same buffer supersedes the older one and takes
+//! over its save.
+
+use super::*;
+use crate::config::ExternalFormatterConfig;
+use crate::model::event::UndoNodeId;
+use crate::model::line_diff::diff_hunks;
+use crate::services::formatter::{self, FormatterError};
+use std::collections::hash_map::Entry;
+use std::time::{Duration, Instant};
+
+/// A formatting request waiting for the server's answer
+#[derive(Debug)]
+pub(super) struct PendingFormat {
+    request_id: u64,
+    buffer_id: BufferId,
+    /// Undo position when the request was sent; the edits are dropped if the buffer changed since
+    undo_node: Option<UndoNodeId>,
+    /// Undo description of the edits
+    description: &'static str,
+    /// For format-on-save, when to stop waiting and save unformatted
+    save_deadline: Option<Instant>,
+}
+
+/// External formatters running on a buffer's text
+#[derive(Debug)]
+pub(super) struct PendingExternalFormat {
+    /// Which run this is, to tell its output from a superseded run's
+    run: u64,
+    /// The text the formatters were given
+    text: String,
+    /// Undo position when they started; the output is dropped if the buffer changed since
+    undo_node: Option<UndoNodeId>,
+    /// Undo description of the edits
+    description: &'static str,
+    /// Save the buffer once formatted (format on save)
+    save: bool,
+}
+
+impl Editor {
+    /// Format the whole active buffer
+    pub(super) fn format_document(&mut self) {
+        let formatters = self.active_external_formatters();
+        if !formatters.is_empty() {
+            match self.start_external_formatters(formatters, "Format Document", false) {
+                Ok(()) => self.set_status_message("Formatting...".to_string()),
+                Err(e) => self.set_status_message(format!("Formatting failed: {}", e)),
+            }
+            return;
+        }
+        if let Err(reason) = self.request_formatting(None, "Format Document", None) {
+            self.set_status_message(reason);
+        }
+    }
+
+    /// Format the selected range of the active buffer
+    pub(super) fn format_selection(&mut self) {
+        let state = self.active_state();
+        let Some(selection) = state.cursors.primary().selection_range() else {
+            self.set_status_message("No selection to format".to_string());
+            return;
+        };
+        let (start_line, start_char) = state.buffer.position_to_lsp_position(selection.start);
+        let (end_line, end_char) = state.buffer.position_to_lsp_position(selection.end);
+        let range = lsp_types::Range {
+            start: lsp_types::Position::new(start_line as u32, start_char as u32),
+            end: lsp_types::Position::new(end_line as u32, end_char as u32),
+        };
+        if let Err(reason) = self.request_formatting(Some(range), "Format Selection", None) {
+            self.set_status_message(reason);
+        }
+    }
+
+    /// Save the active buffer, formatting it first if its language formats on save
+    pub(super) fn save_with_format_on_save(&mut self) -> io::Result<()> {
+        let timeout = self.active_language_config().and_then(|config| {
+            config
+                .format_on_save
+                .then(|| Duration::from_millis(config.format_on_save_timeout_ms))
+        });
+        let formatters = self.active_external_formatters();
+        if timeout.is_some() && !formatters.is_empty() {
+            // External formatters run to completion (each has its own timeout),
+            // then the buffer is saved
+            match self.start_external_formatters(formatters, "Format on Save", true) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    self.save_or_offer_root()?;
+                    if !self.is_prompting() {
+                        self.set_status_message(format!("Saved (not formatted: {})", e));
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        if let Some(timeout) = timeout {
+            let deadline = Instant::now() + timeout;
+            match self.request_formatting(None, "Format on Save", Some(deadline)) {
+                Ok(()) => return Ok(()),
+                Err(reason) => tracing::debug!("Saving without formatting: {}", reason),
+            }
+        }
+        self.save_or_offer_root()
+    }
+
+    /// The language config of the active buffer, if any
+    fn active_language_config(&self) -> Option<&crate::config::LanguageConfig> {
+        let language = self.buffer_language(self.active_buffer())?;
+        self.config.languages.get(&language)
+    }
+
+    /// The external formatters of the active buffer's language (empty if it has none)
+    fn active_external_formatters(&self) -> Vec<ExternalFormatterConfig> {
+        self.active_language_config()
+            .map(|config| config.formatters.clone())
+            .unwrap_or_default()
+    }
+
+    /// Start piping the active buffer through external formatters on a
+    /// thread. The result arrives as `AsyncMessage::ExternalFormatFinished`.
+    fn start_external_formatters(
+        &mut self,
+        formatters: Vec<ExternalFormatterConfig>,
+        description: &'static str,
+        save: bool,
+    ) -> Result<(), String> {
+        let buffer_id = self.active_buffer();
+        let path = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| meta.file_path().cloned());
+        let text = self
+            .active_state()
+            .buffer
+            .to_string()
+            .ok_or_else(|| "file is too large to format".to_string())?;
+        let sender = self
+            .async_bridge
+            .as_ref()
+            .map(|bridge| bridge.sender())
+            .ok_or_else(|| "no async runtime".to_string())?;
+        let undo_node = self
+            .event_logs
+            .get(&buffer_id)
+            .and_then(|log| log.current_node());
+
+        let run = self.next_external_format_run;
+        self.next_external_format_run += 1;
+        let input = text.clone();
+        std::thread::spawn(move || {
+            let result = formatter::run_pipeline(&formatters, &input, path.as_deref());
+            let _ = sender.send(AsyncMessage::ExternalFormatFinished {
+                buffer_id,
+                run,
+                result,
+            });
+        });
+        // A run still going on the buffer is superseded; its save is made
+        // once this one finishes
+        let save = save
+            || self
+                .pending_external_formats
+                .get(&buffer_id)
+                .is_some_and(|previous| previous.save);
+        self.pending_external_formats.insert(
+            buffer_id,
+            PendingExternalFormat {
+                run,
+                text,
+                undo_node,
+                description,
+                save,
+            },
+        );
+        Ok(())
+    }
+
+    /// Handle the formatters' output: apply the changed lines as one undoable
+    /// batch, then save if they ran for format on save. A failing formatter
+    /// leaves the buffer alone and logs its stderr.
+    pub(super) fn handle_external_format_finished(
+        &mut self,
+        buffer_id: BufferId,
+        run: u64,
+        result: Result<String, FormatterError>,
+    ) {
+        let pending = match self.pending_external_formats.entry(buffer_id) {
+            Entry::Occupied(entry) if entry.get().run == run => entry.remove(),
+            _ => {
+                tracing::debug!("Ignoring stale formatter output for {:?}", buffer_id);
+                return;
+            }
+        };
+
+        let unchanged = self
+            .event_logs
+            .get(&buffer_id)
+            .is_some_and(|log| log.current_node() == pending.undo_node);
+        let outcome = match result {
+            Err(e) => {
+                tracing::warn!(
+                    "Formatter {} failed: {}\n{}",
+                    e.command,
+                    e.message,
+                    e.stderr.trim_end()
+                );
+                Err(format!("{}: {}", e.command, e.message))
+            }
+            Ok(_) if !unchanged => Err("buffer changed while formatting".to_string()),
+            Ok(formatted) => {
+                self.apply_formatted_text(buffer_id, &pending.text, &formatted, pending.description)
+            }
+        };
+
+        if pending.save {
+            let note = outcome.err().map(|e| format!("not formatted: {}", e));
+            self.finish_format_on_save(buffer_id, note);
+            return;
+        }
+        match outcome {
+            Ok(0) => self.set_status_message("Already formatted".to_string()),
+            Ok(changes) => self.set_status_message(format!("Formatted ({} edits)", changes)),
+            Err(e) => self.set_status_message(format!("Formatting failed: {}", e)),
+        }
+    }
+
+    /// Apply the lines that differ between `text` (the buffer's content) and
+    /// `formatted` as one undoable batch. Returns the number of changed regions.
+    fn apply_formatted_text(
+        &mut self,
+        buffer_id: BufferId,
+        text: &str,
+        formatted: &str,
+        description: &str,
+    ) -> Result<usize, String> {
+        let cursor_id = self
+            .buffers
+            .get(&buffer_id)
+            .map(|state| state.cursors.primary_id())
+            .ok_or_else(|| "buffer was closed".to_string())?;
+        let hunks = diff_hunks(text.as_bytes(), formatted.as_bytes());
+        if hunks.is_empty() {
+            return Ok(0);
+        }
+        // Apply from the end of the buffer so earlier offsets stay valid
+        let mut events = Vec::new();
+        for hunk in hunks.iter().rev() {
+            if !hunk.old.is_empty() {
+                events.push(Event::Delete {
+                    range: hunk.old.clone(),
+                    deleted_text: text[hunk.old.clone()].to_string(),
+                    cursor_id,
+                });
+            }
+            if !hunk.new.is_empty() {
+                events.push(Event::Insert {
+                    position: hunk.old.start,
+                    text: formatted[hunk.new.clone()].to_string(),
+                    cursor_id,
+                });
+            }
+        }
+        let batch = Event::Batch {
+            events,
+            description: description.to_string(),
+        };
+        self.apply_rename_batch_to_buffer(buffer_id, batch)
+            .map_err(|e| e.to_string())?;
+        Ok(hunks.len())
+    }
+
+    /// Send a formatting request for the active buffer (the whole document, or `range`).
+    /// Returns why no request could be sent.
+    fn request_formatting(
+        &mut self,
+        range: Option<lsp_types::Range>,
+        description: &'static str,
+        save_deadline: Option<Instant>,
+    ) -> Result<(), String> {
+        let buffer_id = self.active_buffer();
+        let (uri, path) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| Some((meta.file_uri()?.clone(), meta.file_path()?.clone())))
+            .ok_or_else(|| "Formatting needs a file".to_string())?;
+        let language = self
+            .language_for_path(&path)
+            .ok_or_else(|| "No language server for this file".to_string())?;
+
+        let state = self.active_state();
+        let settings = &state.settings;
+        let options = lsp_types::FormattingOptions {
+            tab_size: settings.indent_size(self.config.editor.tab_size) as u32,
+            insert_spaces: !settings.use_tabs,
+            trim_trailing_whitespace: settings.trim_trailing_whitespace.then_some(true),
+            insert_final_newline: settings.insert_final_newline.then_some(true),
+            ..Default::default()
+        };
+        let undo_node = self
+            .event_logs
+            .get(&buffer_id)
+            .and_then(|log| log.current_node());
+
+        let feature = match range {
+            Some(_) => LspFeature::RangeFormatting,
+            None => LspFeature::Formatting,
+        };
+        let request_id = self.next_lsp_request_id;
+        let handle = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.get_or_spawn_for(&language, Some(&path), feature))
+            .ok_or_else(|| format!("No language server running for {}", language))?;
+        let sent = match range {
+            Some(range) => handle.range_formatting(request_id, uri, range, options),
+            None => handle.formatting(request_id, uri, options),
+        };
+        sent?;
+
+        self.next_lsp_request_id += 1;
+        // An older request for the buffer is superseded; its save is made
+        // once this one is answered
+        let save_deadline = match self.pending_format_requests.remove(&buffer_id) {
+            Some(previous) => {
+                self.send_lsp_cancel_request(previous.request_id);
+                save_deadline
+                    .into_iter()
+                    .chain(previous.save_deadline)
+                    .min()
+            }
+            None => save_deadline,
+        };
+        self.pending_format_requests.insert(
+            buffer_id,
+            PendingFormat {
+                request_id,
+                buffer_id,
+                undo_node,
+                description,
+                save_deadline,
+            },
+        );
+        self.lsp_status = "LSP: formatting...".to_string();
+        Ok(())
+    }
+
+    /// Handle a formatting response: apply the edits as one undoable batch,
+    /// then save if the request was a format-on-save
+    pub(super) fn handle_formatting_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Vec<lsp_types::TextEdit>, String>,
+    ) {
+        let Some(pending) = self
+            .pending_format_requests
+            .values()
+            .find(|pending| pending.request_id == request_id)
+            .map(|pending| pending.buffer_id)
+            .and_then(|buffer_id| self.pending_format_requests.remove(&buffer_id))
+        else {
+            tracing::debug!("Ignoring stale formatting response: {}", request_id);
+            return;
+        };
+        if self.pending_format_requests.is_empty() {
+            self.lsp_status.clear();
+        }
+
+        let unchanged = self
+            .event_logs
+            .get(&pending.buffer_id)
+            .is_some_and(|log| log.current_node() == pending.undo_node);
+        let outcome = match result {
+            Ok(_) if !unchanged => Err("buffer changed while formatting".to_string()),
+            Ok(edits) => self
+                .apply_lsp_text_edits(pending.buffer_id, edits, pending.description)
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+
+        if pending.save_deadline.is_some() {
+            let note = outcome.err().map(|e| format!("not formatted: {}", e));
+            self.finish_format_on_save(pending.buffer_id, note);
+            return;
+        }
+        match outcome {
+            Ok(0) => self.set_status_message("Already formatted".to_string()),
+            Ok(changes) => self.set_status_message(format!("Formatted ({} edits)", changes)),
+            Err(e) => self.set_status_message(format!("Formatting failed: {}", e)),
+        }
+    }
+
+    /// Save unformatted the buffers whose format-on-save the server didn't
+    /// answer in time. Returns true if a save was made.
+    pub(super) fn check_format_on_save_timeout(&mut self) -> bool {
+        let now = Instant::now();
+        let expired: Vec<BufferId> = self
+            .pending_format_requests
+            .values()
+            .filter(|pending| {
+                pending
+                    .save_deadline
+                    .is_some_and(|deadline| now >= deadline)
+            })
+            .map(|pending| pending.buffer_id)
+            .collect();
+        if expired.is_empty() {
+            return false;
+        }
+        for buffer_id in expired {
+            let Some(pending) = self.pending_format_requests.remove(&buffer_id) else {
+                continue;
+            };
+            self.send_lsp_cancel_request(pending.request_id);
+            self.finish_format_on_save(
+                buffer_id,
+                Some("not formatted: language server timed out".to_string()),
+            );
+        }
+        if self.pending_format_requests.is_empty() {
+            self.lsp_status.clear();
+        }
+        true
+    }
+
+    /// Save a buffer after format-on-save, noting in the status when formatting didn't happen
+    fn finish_format_on_save(&mut self, buffer_id: BufferId, note: Option<String>) {
+        if !self.buffers.contains_key(&buffer_id) {
+            return;
+        }
+        let result = if buffer_id == self.active_buffer() {
+            self.save_or_offer_root()
+        } else {
+            // The user switched buffers while waiting; save the formatted one in place
+            let original_active = self.active_buffer();
+            self.split_manager.set_active_buffer_id(buffer_id);
+            let result = self.save();
+            self.split_manager.set_active_buffer_id(original_active);
+            result
+        };
+        match (result, note) {
+            (Err(e), _) => self.set_status_message(format!("Error saving: {}", e)),
+            (Ok(()), Some(note)) if !self.is_prompting() => {
+                self.set_status_message(format!("Saved ({})", note))
+            }
+            (Ok(()), _) => {}
+        }
+    }
+}
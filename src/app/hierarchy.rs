@@ -0,0 +1,560 @@
+//! Call and type hierarchy explorer
+//!
+//! Show Call Hierarchy and Show Type Hierarchy ask the language server for the
+//! items at the cursor and show them as a tree in a panel below the editor.
+//! Expanding an item requests its callers (or callees, supertypes, subtypes)
+//! only then, so large hierarchies are explored one level at a time. Enter
+//! opens the item's location in the split the explorer was opened from, and
+//! `r` explores the other direction from the selected item.
+
+use super::*;
+use crate::primitives::outline;
+use crate::primitives::text_property::TextPropertyEntry;
+use crate::services::lsp::hierarchy::{HierarchyDirection, HierarchyItem};
+
+/// Name of the explorer's buffer
+const HIERARCHY_BUFFER_NAME: &str = "*Hierarchy*";
+
+/// Share of the height the source split keeps when the explorer opens
+const HIERARCHY_SPLIT_RATIO: f32 = 0.7;
+
+/// What a pending hierarchy request is for
+#[derive(Debug)]
+pub(super) enum HierarchyRequest {
+    /// The items at the cursor, to explore in `direction`
+    Prepare {
+        direction: HierarchyDirection,
+        language: String,
+        path: PathBuf,
+        source_split: SplitId,
+    },
+    /// The children of a node of the open explorer
+    Children { node: usize },
+}
+
+/// An item in the explorer's tree
+#[derive(Debug)]
+struct HierarchyNode {
+    item: HierarchyItem,
+    parent: Option<usize>,
+    /// None until requested
+    children: Option<Vec<usize>>,
+    expanded: bool,
+    /// Why the children couldn't be found
+    error: Option<String>,
+}
+
+/// The call or type hierarchy explorer
+#[derive(Debug)]
+pub(super) struct HierarchyPanel {
+    buffer_id: BufferId,
+    /// The split locations are opened in
+    source_split: SplitId,
+    /// Language and file the items came from, to send requests to the same server
+    language: String,
+    path: PathBuf,
+    direction: HierarchyDirection,
+    nodes: Vec<HierarchyNode>,
+    roots: Vec<usize>,
+    /// Start of each node's line in the buffer, as last rendered
+    rows: Vec<(usize, usize)>,
+}
+
+impl Editor {
+    /// Ask the language server for the call or type hierarchy at the cursor
+    pub(super) fn show_hierarchy(&mut self, direction: HierarchyDirection) {
+        let buffer_id = self.active_buffer();
+        let Some((uri, path)) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| Some((meta.file_uri()?.clone(), meta.file_path()?.clone())))
+        else {
+            self.set_status_message("Hierarchies need a file with a language server".into());
+            return;
+        };
+        let Some(language) = self.language_for_path(&path) else {
+            self.set_status_message("No language server for this file".to_string());
+            return;
+        };
+        let state = self.active_state();
+        let (line, character) = state
+            .buffer
+            .position_to_lsp_position(state.cursors.primary().position);
+
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self
+            .lsp
+            .as_mut()
+            .and_then(|lsp| lsp.get_or_spawn_for(&language, Some(&path), direction.feature()))
+        else {
+            self.set_status_message(format!(
+                "No {} language server provides {}",
+                language,
+                hierarchy_name(direction)
+            ));
+            return;
+        };
+        if let Err(e) =
+            handle.prepare_hierarchy(request_id, direction, uri, line as u32, character as u32)
+        {
+            self.set_status_message(format!(
+                "Failed to request the {}: {}",
+                hierarchy_name(direction),
+                e
+            ));
+            return;
+        }
+
+        self.next_lsp_request_id += 1;
+        // A newer request supersedes an older one
+        self.pending_hierarchy
+            .retain(|_, request| !matches!(request, HierarchyRequest::Prepare { .. }));
+        self.pending_hierarchy.insert(
+            request_id,
+            HierarchyRequest::Prepare {
+                direction,
+                language,
+                path,
+                source_split: self.split_manager.active_split(),
+            },
+        );
+        self.lsp_status = format!("LSP: finding {}...", hierarchy_name(direction));
+    }
+
+    /// Handle hierarchy items: the roots of a new tree, or the children of a node
+    pub(super) fn handle_hierarchy_items_response(
+        &mut self,
+        request_id: u64,
+        result: Result<Vec<HierarchyItem>, String>,
+    ) {
+        let Some(request) = self.pending_hierarchy.remove(&request_id) else {
+            tracing::debug!("Ignoring stale hierarchy response: {}", request_id);
+            return;
+        };
+
+        match request {
+            HierarchyRequest::Prepare {
+                direction,
+                language,
+                path,
+                source_split,
+            } => {
+                self.lsp_status.clear();
+                match result {
+                    Ok(items) if !items.is_empty() => {
+                        self.open_hierarchy(direction, language, path, source_split, items)
+                    }
+                    Ok(_) => self.set_status_message(format!(
+                        "No {} at the cursor",
+                        hierarchy_name(direction)
+                    )),
+                    Err(e) => self.set_status_message(format!(
+                        "Failed to get the {}: {}",
+                        hierarchy_name(direction),
+                        e
+                    )),
+                }
+            }
+            HierarchyRequest::Children { node } => {
+                if !self.hierarchy_panel_open() {
+                    return;
+                }
+                let Some(panel) = self.hierarchy_panel.as_mut() else {
+                    return;
+                };
+                match result {
+                    Ok(items) => {
+                        let children = items
+                            .into_iter()
+                            .map(|item| panel.add_node(item, Some(node)))
+                            .collect();
+                        panel.nodes[node].children = Some(children);
+                    }
+                    Err(e) => {
+                        panel.nodes[node].children = Some(Vec::new());
+                        panel.nodes[node].error = Some(e);
+                    }
+                }
+                self.render_hierarchy_panel();
+            }
+        }
+    }
+
+    /// Show a new tree in the explorer, opening it if needed, and expand its roots
+    fn open_hierarchy(
+        &mut self,
+        direction: HierarchyDirection,
+        language: String,
+        path: PathBuf,
+        source_split: SplitId,
+        items: Vec<HierarchyItem>,
+    ) {
+        let buffer_id = if self.hierarchy_panel_open() {
+            self.hierarchy_panel.as_ref().map(|panel| panel.buffer_id)
+        } else {
+            self.create_hierarchy_split()
+        };
+        let Some(buffer_id) = buffer_id else {
+            return;
+        };
+
+        // Requests for the children of the old tree's nodes no longer apply
+        self.pending_hierarchy
+            .retain(|_, request| !matches!(request, HierarchyRequest::Children { .. }));
+        let mut panel = HierarchyPanel {
+            buffer_id,
+            source_split,
+            language,
+            path,
+            direction,
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            rows: Vec::new(),
+        };
+        let roots: Vec<usize> = items
+            .into_iter()
+            .map(|item| panel.add_node(item, None))
+            .collect();
+        panel.roots = roots.clone();
+        self.hierarchy_panel = Some(panel);
+
+        for root in roots {
+            self.expand_hierarchy_node(root);
+        }
+        if let Some(&split_id) = self.split_manager.splits_for_buffer(buffer_id).first() {
+            self.focus_split(split_id, buffer_id);
+        }
+        self.move_to_hierarchy_node(0);
+    }
+
+    /// Put the explorer's cursor on a node's line
+    fn move_to_hierarchy_node(&mut self, node: usize) {
+        let line = self.hierarchy_panel.as_ref().and_then(|panel| {
+            let &(_, offset) = panel.rows.iter().find(|(n, _)| *n == node)?;
+            Some((panel.buffer_id, offset))
+        });
+        if let Some((buffer_id, offset)) = line {
+            self.handle_set_buffer_cursor(buffer_id, offset);
+        }
+    }
+
+    /// Create the explorer's buffer in a split below the active one.
+    /// Returns the buffer, None if the split couldn't be made.
+    fn create_hierarchy_split(&mut self) -> Option<BufferId> {
+        let source_split = self.split_manager.active_split();
+        let buffer_id = self.create_virtual_buffer(
+            HIERARCHY_BUFFER_NAME.to_string(),
+            "hierarchy".to_string(),
+            true,
+        );
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.margins.set_line_numbers(false);
+            state.editing_disabled = true;
+        }
+        // The explorer gets a split of its own, not a tab beside the source
+        if let Some(view_state) = self.split_view_states.get_mut(&source_split) {
+            view_state.remove_buffer(buffer_id);
+        }
+
+        self.save_current_split_view_state();
+        match self.split_manager.split_active(
+            SplitDirection::Horizontal,
+            buffer_id,
+            HIERARCHY_SPLIT_RATIO,
+        ) {
+            Ok(split_id) => {
+                let mut view_state = SplitViewState::with_buffer(
+                    self.terminal_width,
+                    self.terminal_height,
+                    buffer_id,
+                );
+                view_state.viewport.line_wrap_enabled = false;
+                self.split_view_states.insert(split_id, view_state);
+                Some(buffer_id)
+            }
+            Err(e) => {
+                tracing::error!("Failed to create the hierarchy split: {}", e);
+                let _ = self.close_buffer(buffer_id);
+                None
+            }
+        }
+    }
+
+    /// Whether the explorer is open; forgets it if its buffer was closed
+    fn hierarchy_panel_open(&mut self) -> bool {
+        let open = self
+            .hierarchy_panel
+            .as_ref()
+            .is_some_and(|panel| self.buffers.contains_key(&panel.buffer_id));
+        if !open {
+            self.hierarchy_panel = None;
+        }
+        open
+    }
+
+    /// Run an explorer key command (`hierarchy-<command>` in the hierarchy mode)
+    pub(super) fn hierarchy_command(&mut self, command: &str) {
+        if !self.hierarchy_panel_open() {
+            return;
+        }
+        let Some(node) = self.hierarchy_node_at_cursor() else {
+            if command == "close" {
+                self.close_hierarchy();
+            }
+            return;
+        };
+        match command {
+            "goto" => self.hierarchy_goto(node),
+            "expand" => self.expand_hierarchy_node(node),
+            "collapse" => self.collapse_hierarchy_node(node),
+            "toggle" => {
+                let expanded = self
+                    .hierarchy_panel
+                    .as_ref()
+                    .is_some_and(|panel| panel.nodes[node].expanded);
+                if expanded {
+                    self.collapse_hierarchy_node(node);
+                } else {
+                    self.expand_hierarchy_node(node);
+                }
+            }
+            "reverse" => self.reverse_hierarchy(node),
+            "close" => self.close_hierarchy(),
+            _ => tracing::warn!("Unknown hierarchy command: {}", command),
+        }
+    }
+
+    /// The node on the explorer's current line
+    fn hierarchy_node_at_cursor(&self) -> Option<usize> {
+        let panel = self.hierarchy_panel.as_ref()?;
+        let state = self.buffers.get(&panel.buffer_id)?;
+        state
+            .text_properties
+            .get_at(state.cursors.primary().position)
+            .iter()
+            .find_map(|property| property.get_as::<usize>("node"))
+            .filter(|&node| node < panel.nodes.len())
+    }
+
+    /// Show a node's children, requesting them the first time
+    fn expand_hierarchy_node(&mut self, node: usize) {
+        let Some(panel) = self.hierarchy_panel.as_mut() else {
+            return;
+        };
+        panel.nodes[node].expanded = true;
+
+        if panel.nodes[node].children.is_none()
+            && !self.pending_hierarchy.values().any(
+                |request| matches!(request, HierarchyRequest::Children { node: n } if *n == node),
+            )
+        {
+            let request_id = self.next_lsp_request_id;
+            let direction = panel.direction;
+            let sent = self
+                .lsp
+                .as_mut()
+                .and_then(|lsp| {
+                    lsp.get_or_spawn_for(&panel.language, Some(&panel.path), direction.feature())
+                })
+                .ok_or_else(|| format!("No {} language server", panel.language))
+                .and_then(|handle| {
+                    handle.hierarchy_children(
+                        request_id,
+                        direction,
+                        panel.nodes[node].item.raw.clone(),
+                    )
+                });
+            match sent {
+                Ok(()) => {
+                    self.next_lsp_request_id += 1;
+                    self.pending_hierarchy
+                        .insert(request_id, HierarchyRequest::Children { node });
+                }
+                Err(e) => {
+                    panel.nodes[node].children = Some(Vec::new());
+                    panel.nodes[node].error = Some(e);
+                }
+            }
+        }
+        self.render_hierarchy_panel();
+    }
+
+    /// Hide a node's children, or if they're hidden, collapse its parent and
+    /// move to it
+    fn collapse_hierarchy_node(&mut self, node: usize) {
+        let Some(panel) = self.hierarchy_panel.as_mut() else {
+            return;
+        };
+        if panel.nodes[node].expanded {
+            panel.nodes[node].expanded = false;
+            self.render_hierarchy_panel();
+            return;
+        }
+        let Some(parent) = panel.nodes[node].parent else {
+            return;
+        };
+        panel.nodes[parent].expanded = false;
+        self.render_hierarchy_panel();
+        self.move_to_hierarchy_node(parent);
+    }
+
+    /// Explore the other direction of the hierarchy from a node
+    fn reverse_hierarchy(&mut self, node: usize) {
+        let Some(panel) = self.hierarchy_panel.as_ref() else {
+            return;
+        };
+        let direction = panel.direction.reversed();
+        let item = panel.nodes[node].item.clone();
+        let (language, path, source_split) = (
+            panel.language.clone(),
+            panel.path.clone(),
+            panel.source_split,
+        );
+        self.open_hierarchy(direction, language, path, source_split, vec![item]);
+    }
+
+    /// Open a node's location in the source split
+    fn hierarchy_goto(&mut self, node: usize) {
+        let Some(panel) = self.hierarchy_panel.as_ref() else {
+            return;
+        };
+        let item = &panel.nodes[node].item;
+        let (target, source_split) = (item.target, panel.source_split);
+        let path = match uri_to_path(&item.uri) {
+            Ok(path) => path,
+            Err(e) => {
+                let message = format!("Can't open {}: {}", item.uri.as_str(), e);
+                self.set_status_message(message);
+                return;
+            }
+        };
+
+        if let Some(buffer_id) = self.split_manager.buffer_for_split(source_split) {
+            self.focus_split(source_split, buffer_id);
+        }
+        if let Err(e) = self.open_file(&path) {
+            self.set_status_message(format!("Failed to open {}: {}", path.display(), e));
+            return;
+        }
+        let position = self
+            .active_state()
+            .buffer
+            .lsp_position_to_byte(target.line as usize, target.character as usize);
+        self.jump_to_byte(position);
+    }
+
+    /// Close the explorer
+    fn close_hierarchy(&mut self) {
+        let Some(panel) = self.hierarchy_panel.take() else {
+            return;
+        };
+        self.pending_hierarchy
+            .retain(|_, request| !matches!(request, HierarchyRequest::Children { .. }));
+        for split_id in self.split_manager.splits_for_buffer(panel.buffer_id) {
+            self.handle_close_split(split_id);
+        }
+        if let Err(e) = self.close_buffer(panel.buffer_id) {
+            tracing::warn!("Failed to close the hierarchy: {}", e);
+        }
+        if let Some(buffer_id) = self.split_manager.buffer_for_split(panel.source_split) {
+            self.focus_split(panel.source_split, buffer_id);
+        }
+    }
+
+    /// Fill the explorer's buffer: a header, the visible nodes indented by
+    /// depth, and a line of key hints
+    fn render_hierarchy_panel(&mut self) {
+        let Some(panel) = self.hierarchy_panel.as_mut() else {
+            return;
+        };
+        let title = panel.direction.title();
+        let mut entries = vec![TextPropertyEntry::text(format!("═══ {} ═══\n", title))];
+        let mut offset = entries[0].text.len();
+        let mut rows = Vec::new();
+
+        // Depth-first, children in the server's order
+        let mut stack: Vec<(usize, usize)> = panel.roots.iter().rev().map(|&n| (n, 0)).collect();
+        while let Some((node, depth)) = stack.pop() {
+            let entry = &panel.nodes[node];
+            let indent = "  ".repeat(depth);
+            let marker = match &entry.children {
+                Some(children) if children.is_empty() && entry.error.is_none() => "  ",
+                _ if entry.expanded => "▾ ",
+                _ => "▸ ",
+            };
+            let text = format!(
+                "{}{}{}  {} · {}\n",
+                indent,
+                marker,
+                entry.item.name,
+                outline::kind_label(entry.item.kind),
+                location_label(&entry.item, &self.working_dir)
+            );
+            rows.push((node, offset));
+            offset += text.len();
+            entries
+                .push(TextPropertyEntry::text(text).with_property("node", serde_json::json!(node)));
+
+            if !entry.expanded {
+                continue;
+            }
+            let note = match (&entry.children, &entry.error) {
+                (None, _) => Some("Loading...".to_string()),
+                (_, Some(e)) => Some(e.clone()),
+                _ => None,
+            };
+            if let Some(note) = note {
+                let text = format!("{}    {}\n", indent, note);
+                offset += text.len();
+                entries.push(TextPropertyEntry::text(text));
+            }
+            if let Some(children) = &entry.children {
+                stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
+            }
+        }
+
+        entries.push(TextPropertyEntry::text(format!(
+            "[→/Tab] expand  [←] collapse  [RET] jump  [r] {}  [q] close\n",
+            panel.direction.reversed().title().to_lowercase()
+        )));
+        panel.rows = rows;
+        let buffer_id = panel.buffer_id;
+
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::error!("Failed to update the hierarchy: {}", e);
+        }
+    }
+}
+
+impl HierarchyPanel {
+    fn add_node(&mut self, item: HierarchyItem, parent: Option<usize>) -> usize {
+        self.nodes.push(HierarchyNode {
+            item,
+            parent,
+            children: None,
+            expanded: false,
+            error: None,
+        });
+        self.nodes.len() - 1
+    }
+}
+
+/// "call hierarchy" or "type hierarchy", for messages
+fn hierarchy_name(direction: HierarchyDirection) -> &'static str {
+    match direction.feature() {
+        LspFeature::CallHierarchy => "call hierarchy",
+        _ => "type hierarchy",
+    }
+}
+
+/// An item's file, relative to the working directory, and line
+fn location_label(item: &HierarchyItem, working_dir: &Path) -> String {
+    let line = item.target.line + 1;
+    match uri_to_path(&item.uri) {
+        Ok(path) => {
+            let shown = path.strip_prefix(working_dir).unwrap_or(&path);
+            format!("{}:{}", shown.display(), line)
+        }
+        Err(_) => format!("{}:{}", item.uri.as_str(), line),
+    }
+}
@@ -1,5 +1,8 @@
+use super::macro_runs::MacroRun;
 use super::normalize_path;
 use super::*;
+use crate::services::file_transfer::TransferMode;
+use crate::services::lsp::hierarchy::HierarchyDirection;
 use crate::services::plugins::hooks::HookArgs;
 impl Editor {
     /// Determine the current keybinding context based on UI state
@@ -30,11 +33,14 @@ impl Editor {
 
         let _t_total = std::time::Instant::now();
 
-        tracing::trace!(
-            "Editor.handle_key: code={:?}, modifiers={:?}",
-            code,
-            modifiers
-        );
+        let masked = self.is_input_masked();
+        if !masked {
+            tracing::trace!(
+                "Editor.handle_key: code={:?}, modifiers={:?}",
+                code,
+                modifiers
+            );
+        }
 
         // Check if we're in a prompt or popup - these take priority over terminal handling
         // so that command palette, open file dialog, etc. work correctly
@@ -199,6 +205,12 @@ impl Editor {
                     // Handle built-in mode commands
                     let buffer_id = self.active_buffer();
                     return self.close_buffer(buffer_id);
+                } else if command_name == "outline-goto" {
+                    self.outline_goto();
+                    return Ok(());
+                } else if let Some(command) = command_name.strip_prefix("hierarchy-") {
+                    self.hierarchy_command(command);
+                    return Ok(());
                 } else if command_name == "revert-buffer" {
                     // Refresh the buffer (for virtual buffers, this would re-query data)
                     self.set_status_message("Refreshing buffer...".to_string());
@@ -243,7 +255,9 @@ impl Editor {
         // Regular single-key resolution
         let action = self.keybindings.resolve(&key_event, context);
 
-        tracing::trace!("Context: {:?} -> Action: {:?}", context, action);
+        if !masked {
+            tracing::trace!("Context: {:?} -> Action: {:?}", context, action);
+        }
 
         // Cancel pending LSP requests on user actions (except LSP actions themselves)
         // This ensures stale completions don't show up after the user has moved on
@@ -709,7 +723,7 @@ impl Editor {
                         PromptType::ConfirmSaveConflict,
                     );
                 } else {
-                    self.save()?;
+                    self.save_with_format_on_save()?;
                 }
             }
             Action::SaveAs => {
@@ -777,6 +791,26 @@ impl Editor {
                     }
                 }
             }
+            Action::ReopenWithEncoding => {
+                self.start_encoding_prompt(PromptType::ReopenWithEncoding);
+            }
+            Action::SetLanguage => {
+                self.start_set_language_prompt();
+            }
+            Action::SaveWithEncoding => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                } else {
+                    self.start_encoding_prompt(PromptType::SaveWithEncoding);
+                }
+            }
+            Action::SaveAsRoot => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                } else {
+                    self.start_save_as_root();
+                }
+            }
             Action::ToggleAutoRevert => {
                 self.toggle_auto_revert();
             }
@@ -833,6 +867,27 @@ impl Editor {
                 // Update modified status based on event log position
                 self.update_modified_from_event_log();
             }
+            Action::UndoTreeEarlier
+            | Action::UndoTreeLater
+            | Action::UndoTreeSwitchBranch
+            | Action::UndoTreeGoto => {
+                if self.is_editing_disabled() {
+                    self.set_status_message("Editing disabled in this buffer".to_string());
+                    return Ok(());
+                }
+                match action {
+                    Action::UndoTreeEarlier => self.undo_tree_earlier(),
+                    Action::UndoTreeLater => self.undo_tree_later(),
+                    Action::UndoTreeSwitchBranch => self.undo_tree_switch_branch(),
+                    _ => self.start_prompt(
+                        "Go to undo state (#N, 5m ago, +30s): ".to_string(),
+                        PromptType::UndoTreeGoto,
+                    ),
+                }
+            }
+            Action::ShowUndoTree => {
+                self.show_undo_tree();
+            }
             Action::ShowHelp => {
                 self.open_help_manual();
             }
@@ -979,6 +1034,34 @@ impl Editor {
                     default_amount,
                 );
             }
+            Action::Fold | Action::FoldAll | Action::FoldToLevel(_) => {
+                self.run_fold_command(action);
+            }
+            Action::Unfold => self.unfold_at_cursor(),
+            Action::GotoNextFunction
+            | Action::GotoPreviousFunction
+            | Action::GotoNextClass
+            | Action::GotoPreviousClass
+            | Action::GotoNextParameter
+            | Action::GotoPreviousParameter
+            | Action::GotoNextComment
+            | Action::GotoPreviousComment
+            | Action::SelectInsideFunction
+            | Action::SelectAroundFunction
+            | Action::SelectInsideClass
+            | Action::SelectAroundClass
+            | Action::SelectInsideParameter
+            | Action::SelectAroundParameter
+            | Action::SelectInsideBlock
+            | Action::SelectAroundBlock => {
+                self.run_text_object_command(action);
+            }
+            Action::GotoSymbol => self.start_goto_symbol(),
+            Action::GotoWorkspaceSymbol => self.start_workspace_symbol_prompt(),
+            Action::ToggleOutline => self.toggle_outline(),
+            Action::CallHierarchy => self.show_hierarchy(HierarchyDirection::IncomingCalls),
+            Action::TypeHierarchy => self.show_hierarchy(HierarchyDirection::Subtypes),
+            Action::UnfoldAll => self.unfold_all(),
             Action::LspCompletion => {
                 self.request_completion()?;
             }
@@ -1000,14 +1083,17 @@ impl Editor {
             Action::LspCodeActions => {
                 self.request_code_actions()?;
             }
+            Action::FormatDocument => {
+                self.format_document();
+            }
+            Action::FormatSelection => {
+                self.format_selection();
+            }
             Action::LspRestart => {
                 // Get the language for the current buffer
                 if let Some(metadata) = self.buffer_metadata.get(&self.active_buffer()) {
-                    if let Some(path) = metadata.file_path() {
-                        if let Some(language) = crate::services::lsp::manager::detect_language(
-                            path,
-                            &self.config.languages,
-                        ) {
+                    if metadata.file_path().is_some() {
+                        if let Some(language) = metadata.language.clone() {
                             let restart_result = if let Some(lsp) = self.lsp.as_mut() {
                                 Some(lsp.manual_restart(&language))
                             } else {
@@ -1023,11 +1109,7 @@ impl Editor {
                                         .iter()
                                         .filter_map(|(buf_id, meta)| {
                                             if let Some(p) = meta.file_path() {
-                                                if crate::services::lsp::manager::detect_language(
-                                                    p,
-                                                    &self.config.languages,
-                                                ) == Some(language.clone())
-                                                {
+                                                if meta.language.as_ref() == Some(&language) {
                                                     Some((*buf_id, p.clone()))
                                                 } else {
                                                     None
@@ -1050,17 +1132,18 @@ impl Editor {
                                                 );
                                             if let Some(uri) = uri {
                                                 if let Some(lang_id) =
-                                                    crate::services::lsp::manager::detect_language(
-                                                        &buf_path,
-                                                        &self.config.languages,
-                                                    )
+                                                    self.buffer_language(buffer_id)
                                                 {
                                                     if let Some(lsp) = self.lsp.as_mut() {
-                                                        if let Some(handle) =
-                                                            lsp.get_or_spawn(&lang_id)
-                                                        {
-                                                            let _ = handle
-                                                                .did_open(uri, content, lang_id);
+                                                        for handle in lsp.get_or_spawn_all(
+                                                            &lang_id,
+                                                            Some(&buf_path),
+                                                        ) {
+                                                            let _ = handle.did_open(
+                                                                uri.clone(),
+                                                                content.clone(),
+                                                                lang_id.clone(),
+                                                            );
                                                         }
                                                     }
                                                 }
@@ -1097,8 +1180,11 @@ impl Editor {
                         .iter()
                         .map(|lang| {
                             let description = if let Some(lsp) = &self.lsp {
-                                lsp.get_config(lang)
-                                    .map(|c| format!("Command: {}", c.command))
+                                lsp.get_config(lang).map(|servers| {
+                                    let commands: Vec<&str> =
+                                        servers.iter().map(|c| c.command.as_str()).collect();
+                                    format!("Command: {}", commands.join(", "))
+                                })
                             } else {
                                 None
                             };
@@ -1250,9 +1336,18 @@ impl Editor {
             Action::FileExplorerNewFile => self.file_explorer_new_file(),
             Action::FileExplorerNewDirectory => self.file_explorer_new_directory(),
             Action::FileExplorerDelete => self.file_explorer_delete(),
+            Action::FileExplorerDeletePermanently => self.file_explorer_delete_permanently(),
+            Action::FileExplorerUndoDelete => self.file_explorer_undo_delete(),
             Action::FileExplorerRename => self.file_explorer_rename(),
             Action::FileExplorerToggleHidden => self.file_explorer_toggle_hidden(),
             Action::FileExplorerToggleGitignored => self.file_explorer_toggle_gitignored(),
+            Action::FileExplorerToggleMark => self.file_explorer_toggle_mark(),
+            Action::FileExplorerExtendMarkUp => self.file_explorer_extend_marks(false),
+            Action::FileExplorerExtendMarkDown => self.file_explorer_extend_marks(true),
+            Action::FileExplorerClearMarks => self.file_explorer_clear_marks(),
+            Action::FileExplorerCopy => self.file_explorer_copy(TransferMode::Copy),
+            Action::FileExplorerCut => self.file_explorer_copy(TransferMode::Move),
+            Action::FileExplorerPaste => self.file_explorer_paste(),
             Action::RemoveSecondaryCursors => {
                 // Convert action to events and apply them
                 if let Some(events) = self.action_to_events(Action::RemoveSecondaryCursors) {
@@ -1574,6 +1669,42 @@ impl Editor {
                     self.set_status_message("No macro has been recorded yet".to_string());
                 }
             }
+            Action::PlayNamedMacro(name) => {
+                self.play_named_macro(&name);
+            }
+            Action::PromptEditMacro => {
+                self.start_prompt("Edit macro (0-9): ".to_string(), PromptType::EditMacro);
+            }
+            Action::SaveMacroFromBuffer => {
+                self.save_macro_from_buffer();
+            }
+            Action::ApplyMacroToLines(key) => {
+                self.run_macro_batch(key, MacroRun::Lines);
+            }
+            Action::ApplyMacroToMatches(key) => {
+                self.run_macro_batch(key, MacroRun::Matches);
+            }
+            Action::RepeatMacro(key) => {
+                self.run_macro_batch(key, MacroRun::Repeat(None));
+            }
+            Action::PromptApplyMacroToLines => {
+                self.start_prompt(
+                    "Play macro on each line (0-9): ".to_string(),
+                    PromptType::ApplyMacroToLines,
+                );
+            }
+            Action::PromptApplyMacroToMatches => {
+                self.start_prompt(
+                    "Play macro at each match (0-9): ".to_string(),
+                    PromptType::ApplyMacroToMatches,
+                );
+            }
+            Action::PromptRepeatMacro => {
+                self.start_prompt(
+                    "Repeat macro (0-9 [count], no count = until it fails): ".to_string(),
+                    PromptType::RepeatMacro,
+                );
+            }
             Action::PromptSetBookmark => {
                 self.start_prompt("Set bookmark (0-9): ".to_string(), PromptType::SetBookmark);
             }
@@ -1750,14 +1881,18 @@ impl Editor {
                                     );
 
                                     // Update metadata with the new path
-                                    let metadata = BufferMetadata::with_file(
+                                    let mut metadata = BufferMetadata::with_file(
                                         full_path.clone(),
                                         &self.working_dir,
                                     );
+                                    metadata.language =
+                                        self.detect_file_language(self.active_state(), &full_path);
                                     self.buffer_metadata.insert(self.active_buffer(), metadata);
+                                    self.rebind_language_highlighter(self.active_buffer());
 
                                     // Mark the event log position as saved (for undo modified tracking)
                                     self.active_event_log_mut().mark_saved();
+                                    self.record_undo_history_saved(self.active_buffer());
                                     tracing::debug!(
                                         "SaveFileAs AFTER mark_saved: event_log index={}, len={}",
                                         self.active_event_log().current_index(),
@@ -1789,6 +1924,7 @@ impl Editor {
                                             path: full_path.clone(),
                                         },
                                     );
+                                    self.refresh_git_status();
 
                                     // Check if we should close the buffer after saving
                                     if let Some(buffer_to_close) = self.pending_close_buffer.take()
@@ -1880,6 +2016,9 @@ impl Editor {
                                 self.set_status_message(format!("Invalid line number: {}", input));
                             }
                         },
+                        PromptType::UndoTreeGoto => {
+                            self.undo_tree_goto(&input);
+                        }
                         PromptType::SetBackgroundFile => {
                             if let Err(e) = self.load_ansi_background(&input) {
                                 self.set_status_message(format!(
@@ -1972,6 +2111,70 @@ impl Editor {
                                 self.set_status_message("No register specified".to_string());
                             }
                         }
+                        PromptType::EditMacro => {
+                            if let Some(c) = input.trim().chars().next() {
+                                if c.is_ascii_digit() {
+                                    self.show_macro_in_buffer(c);
+                                } else {
+                                    self.set_status_message(
+                                        "Macro register must be 0-9".to_string(),
+                                    );
+                                }
+                            } else {
+                                self.set_status_message("No register specified".to_string());
+                            }
+                        }
+                        PromptType::ApplyMacroToLines => {
+                            if let Some(c) = input.trim().chars().next() {
+                                if c.is_ascii_digit() {
+                                    self.run_macro_batch(c, MacroRun::Lines);
+                                } else {
+                                    self.set_status_message(
+                                        "Macro register must be 0-9".to_string(),
+                                    );
+                                }
+                            } else {
+                                self.set_status_message("No register specified".to_string());
+                            }
+                        }
+                        PromptType::ApplyMacroToMatches => {
+                            if let Some(c) = input.trim().chars().next() {
+                                if c.is_ascii_digit() {
+                                    self.run_macro_batch(c, MacroRun::Matches);
+                                } else {
+                                    self.set_status_message(
+                                        "Macro register must be 0-9".to_string(),
+                                    );
+                                }
+                            } else {
+                                self.set_status_message("No register specified".to_string());
+                            }
+                        }
+                        PromptType::RepeatMacro => {
+                            let mut words = input.split_whitespace();
+                            match (words.next().and_then(|w| w.chars().next()), words.next()) {
+                                (None, _) => {
+                                    self.set_status_message("No register specified".to_string());
+                                }
+                                (Some(c), _) if !c.is_ascii_digit() => {
+                                    self.set_status_message(
+                                        "Macro register must be 0-9".to_string(),
+                                    );
+                                }
+                                (Some(c), None) => {
+                                    self.run_macro_batch(c, MacroRun::Repeat(None));
+                                }
+                                (Some(c), Some(count)) => match count.parse::<usize>() {
+                                    Ok(count) => {
+                                        self.run_macro_batch(c, MacroRun::Repeat(Some(count)))
+                                    }
+                                    Err(_) => self.set_status_message(format!(
+                                        "Invalid repeat count: {}",
+                                        count
+                                    )),
+                                },
+                            }
+                        }
                         PromptType::SetBookmark => {
                             if let Some(c) = input.trim().chars().next() {
                                 if c.is_ascii_digit() {
@@ -2022,7 +2225,7 @@ impl Editor {
                             let input_lower = input.trim().to_lowercase();
                             if input_lower == "o" || input_lower == "overwrite" {
                                 // Force save despite conflict
-                                if let Err(e) = self.save() {
+                                if let Err(e) = self.save_with_format_on_save() {
                                     self.set_status_message(format!("Failed to save: {}", e));
                                 }
                             } else {
@@ -2123,6 +2326,17 @@ impl Editor {
                             // Perform file explorer rename with the new name from the prompt
                             self.perform_file_explorer_rename(original_path, original_name, input);
                         }
+                        PromptType::ConfirmFileExplorerDelete { paths, permanent } => {
+                            let input_lower = input.trim().to_lowercase();
+                            if input_lower == "y" || input_lower == "yes" {
+                                self.perform_file_explorer_delete(paths, permanent);
+                            } else {
+                                self.set_status_message("Delete cancelled".to_string());
+                            }
+                        }
+                        PromptType::FileTransferConflict => {
+                            self.resolve_file_transfer_conflict(&input);
+                        }
                         PromptType::StopLspServer => {
                             // Stop the selected LSP server
                             let language = input.trim();
@@ -2132,7 +2346,16 @@ impl Editor {
                                         // Update config to disable auto-start for this language
                                         if let Some(lsp_config) = self.config.lsp.get_mut(language)
                                         {
-                                            lsp_config.auto_start = false;
+                                            for server in lsp_config.iter_mut() {
+                                                server.auto_start = false;
+                                            }
+                                            if let Some(user_lsp_config) =
+                                                self.user_config.lsp.get_mut(language)
+                                            {
+                                                for server in user_lsp_config.iter_mut() {
+                                                    server.auto_start = false;
+                                                }
+                                            }
                                             if let Err(e) = self.save_config() {
                                                 tracing::warn!(
                                                     "Failed to save config after disabling LSP auto-start: {}",
@@ -2168,6 +2391,42 @@ impl Editor {
                         PromptType::SelectKeybindingMap => {
                             self.apply_keybinding_map(input.trim());
                         }
+                        PromptType::ReopenWithEncoding => {
+                            self.reopen_with_encoding(input.trim());
+                        }
+                        PromptType::SaveWithEncoding => {
+                            self.save_with_encoding(input.trim());
+                        }
+                        PromptType::SetLanguage => {
+                            self.set_buffer_language(input.trim());
+                        }
+                        PromptType::GotoSymbol => {
+                            self.goto_symbol(input.trim());
+                        }
+                        PromptType::WorkspaceSymbol => {
+                            self.open_workspace_symbol(input.trim());
+                        }
+                        PromptType::ConfirmSaveAsRoot => {
+                            let input_lower = input.trim().to_lowercase();
+                            if input_lower == "y" || input_lower == "yes" {
+                                self.start_save_as_root();
+                            } else {
+                                self.set_status_message("Save cancelled".to_string());
+                            }
+                        }
+                        PromptType::SaveAsRootPassword => {
+                            self.save_as_root(Some(&input));
+                        }
+                        PromptType::TrustProjectConfig => {
+                            let input_lower = input.trim().to_lowercase();
+                            if input_lower == "y" || input_lower == "yes" {
+                                self.trust_project_config();
+                            } else {
+                                self.set_status_message(
+                                    "Project config commands not applied".to_string(),
+                                );
+                            }
+                        }
                         PromptType::SwitchToTab => {
                             // input is the buffer id as a string
                             if let Ok(id) = input.trim().parse::<usize>() {
@@ -2360,88 +2619,95 @@ impl Editor {
                     }
                 }
             }
-            _ => {
-                // Convert action to events and apply them
-                // Get description before moving action
-                let action_description = format!("{:?}", action);
-
-                // Check if this is an editing action and editing is disabled
-                let is_editing_action = matches!(
-                    action,
-                    Action::InsertNewline
-                        | Action::InsertTab
-                        | Action::DeleteForward
-                        | Action::DeleteWordBackward
-                        | Action::DeleteWordForward
-                        | Action::DeleteLine
-                        | Action::IndentSelection
-                        | Action::DedentSelection
-                        | Action::ToggleComment
-                );
+            Action::ExpandSelection if self.needs_lsp_selection_ranges() => {
+                // Expands when the server answers
+                self.request_selection_ranges();
+            }
+            _ => self.apply_action_as_events(action),
+        }
 
-                if is_editing_action && self.is_editing_disabled() {
-                    self.set_status_message("Editing disabled in this buffer".to_string());
-                    return Ok(());
-                }
+        Ok(())
+    }
 
-                if let Some(events) = self.action_to_events(action) {
-                    // Wrap multiple events (multi-cursor) in a Batch for atomic undo
-                    if events.len() > 1 {
-                        let batch = Event::Batch {
-                            events: events.clone(),
-                            description: action_description,
-                        };
-                        self.active_event_log_mut().append(batch.clone());
-                        self.apply_event_to_active_buffer(&batch);
-                        // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
+    /// Convert an action to events and apply them to the active buffer,
+    /// as one undoable batch when there are several
+    pub(super) fn apply_action_as_events(&mut self, action: Action) {
+        // Get description before moving action
+        let action_description = format!("{:?}", action);
+
+        // Check if this is an editing action and editing is disabled
+        let is_editing_action = matches!(
+            action,
+            Action::InsertNewline
+                | Action::InsertTab
+                | Action::DeleteForward
+                | Action::DeleteWordBackward
+                | Action::DeleteWordForward
+                | Action::DeleteLine
+                | Action::IndentSelection
+                | Action::DedentSelection
+                | Action::ToggleComment
+        );
 
-                        // Track position history for all events in the batch
-                        for event in &events {
-                            // Track cursor movements in position history (but not during navigation)
-                            if !self.in_navigation {
-                                if let Event::MoveCursor {
-                                    new_position,
-                                    new_anchor,
-                                    ..
-                                } = event
-                                {
-                                    self.position_history.record_movement(
-                                        self.active_buffer(),
-                                        *new_position,
-                                        *new_anchor,
-                                    );
-                                }
-                            }
+        if is_editing_action && self.is_editing_disabled() {
+            self.set_status_message("Editing disabled in this buffer".to_string());
+            return;
+        }
+
+        if let Some(events) = self.action_to_events(action) {
+            // Wrap multiple events (multi-cursor) in a Batch for atomic undo
+            if events.len() > 1 {
+                let batch = Event::Batch {
+                    events: events.clone(),
+                    description: action_description,
+                };
+                self.active_event_log_mut().append(batch.clone());
+                self.apply_event_to_active_buffer(&batch);
+                // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
+
+                // Track position history for all events in the batch
+                for event in &events {
+                    // Track cursor movements in position history (but not during navigation)
+                    if !self.in_navigation {
+                        if let Event::MoveCursor {
+                            new_position,
+                            new_anchor,
+                            ..
+                        } = event
+                        {
+                            self.position_history.record_movement(
+                                self.active_buffer(),
+                                *new_position,
+                                *new_anchor,
+                            );
                         }
-                    } else {
-                        // Single cursor - no need for batch
-                        for event in events {
-                            self.active_event_log_mut().append(event.clone());
-                            self.apply_event_to_active_buffer(&event);
-                            // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
+                    }
+                }
+            } else {
+                // Single cursor - no need for batch
+                for event in events {
+                    self.active_event_log_mut().append(event.clone());
+                    self.apply_event_to_active_buffer(&event);
+                    // Note: LSP notifications now handled automatically by apply_event_to_active_buffer
 
-                            // Track cursor movements in position history (but not during navigation)
-                            if !self.in_navigation {
-                                if let Event::MoveCursor {
-                                    new_position,
-                                    new_anchor,
-                                    ..
-                                } = event
-                                {
-                                    self.position_history.record_movement(
-                                        self.active_buffer(),
-                                        new_position,
-                                        new_anchor,
-                                    );
-                                }
-                            }
+                    // Track cursor movements in position history (but not during navigation)
+                    if !self.in_navigation {
+                        if let Event::MoveCursor {
+                            new_position,
+                            new_anchor,
+                            ..
+                        } = event
+                        {
+                            self.position_history.record_movement(
+                                self.active_buffer(),
+                                new_position,
+                                new_anchor,
+                            );
                         }
                     }
                 }
             }
         }
-
-        Ok(())
     }
 
     /// Handle a mouse event
@@ -2507,6 +2773,7 @@ impl Editor {
                     self.previous_click_time = Some(std::time::Instant::now());
                     self.previous_click_position = Some((col, row));
                 }
+                self.mouse_state.click_modifiers = Some(mouse_event.modifiers);
                 self.handle_mouse_click(col, row)?;
                 needs_render = true;
             }
@@ -2532,6 +2799,11 @@ impl Editor {
                 self.mouse_state.drag_selection_split = None;
                 self.mouse_state.drag_selection_anchor = None;
 
+                // Drop files dragged in the file explorer
+                if let Some(paths) = self.mouse_state.dragging_explorer_paths.take() {
+                    self.handle_file_explorer_drop(paths);
+                }
+
                 // If we finished dragging a separator, resize visible terminals
                 if was_dragging_separator {
                     self.resize_visible_terminals();
@@ -3419,6 +3691,12 @@ impl Editor {
             return Ok(());
         }
 
+        // If dragging files in the file explorer, track the drop target
+        if self.mouse_state.dragging_explorer_paths.is_some() {
+            self.handle_file_explorer_drag(col, row);
+            return Ok(());
+        }
+
         // If dragging to select text
         if self.mouse_state.dragging_text_selection {
             self.handle_text_selection_drag(col, row)?;
@@ -4265,6 +4543,42 @@ impl Editor {
             if clicked_index < display_nodes.len() {
                 let (node_id, _indent) = display_nodes[clicked_index];
 
+                // Ctrl+click marks or unmarks a node, Shift+click marks a range
+                let modifiers = self
+                    .mouse_state
+                    .click_modifiers
+                    .unwrap_or(crossterm::event::KeyModifiers::NONE);
+                if modifiers.contains(crossterm::event::KeyModifiers::CONTROL) {
+                    explorer.toggle_mark(node_id);
+                    explorer.set_selected(Some(node_id));
+                    return Ok(());
+                }
+                if modifiers.contains(crossterm::event::KeyModifiers::SHIFT) {
+                    explorer.mark_range_to(node_id);
+                    explorer.set_selected(Some(node_id));
+                    return Ok(());
+                }
+
+                // The node, or all marked nodes if it's marked, may be dragged
+                // onto a directory to move them
+                if node_id != explorer.tree().root_id() {
+                    let path = explorer
+                        .tree()
+                        .get_node(node_id)
+                        .map(|node| node.entry.path.clone());
+                    if let Some(path) = path {
+                        let dragged = if explorer.is_marked(&path) {
+                            explorer.operation_paths()
+                        } else {
+                            explorer.clear_marks();
+                            vec![path]
+                        };
+                        self.mouse_state.dragging_explorer_paths = Some(dragged);
+                    }
+                } else {
+                    explorer.clear_marks();
+                }
+
                 // Select this node
                 explorer.set_selected(Some(node_id));
 
@@ -4489,6 +4803,54 @@ impl Editor {
         Ok(None)
     }
 
+    /// Start a prompt for choosing a text encoding, with the active buffer's encoding selected
+    fn start_encoding_prompt(&mut self, prompt_type: PromptType) {
+        use crate::model::encoding::Encoding;
+
+        let current = self.active_state().buffer.encoding();
+        let message = match prompt_type {
+            PromptType::SaveWithEncoding => "Save with encoding: ",
+            _ => "Reopen with encoding: ",
+        };
+
+        let suggestions: Vec<crate::input::commands::Suggestion> = Encoding::ALL
+            .iter()
+            .map(|encoding| crate::input::commands::Suggestion {
+                text: encoding.display_name().to_string(),
+                description: (*encoding == current).then(|| "(current)".to_string()),
+                value: Some(encoding.display_name().to_string()),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+            .collect();
+
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            message.to_string(),
+            prompt_type,
+            suggestions,
+        ));
+
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.selected_suggestion = Encoding::ALL.iter().position(|e| *e == current);
+        }
+    }
+
+    /// Save the active buffer; if the file is not writable, offer to save it as root
+    /// instead of failing
+    pub(super) fn save_or_offer_root(&mut self) -> std::io::Result<()> {
+        match self.save() {
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                self.start_prompt(
+                    "Permission denied. Save as root? (y/N) ".to_string(),
+                    PromptType::ConfirmSaveAsRoot,
+                );
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
     /// Start the theme selection prompt with available themes
     fn start_select_theme_prompt(&mut self) {
         let available_themes = crate::view::theme::Theme::available_themes();
@@ -4542,6 +4904,7 @@ impl Editor {
 
             // Update the config in memory
             self.config.theme = self.theme.name.clone();
+            self.user_config.theme = self.theme.name.clone();
 
             // Persist to config file
             self.save_theme_to_config();
@@ -4560,7 +4923,7 @@ impl Editor {
 
         // Save the config
         let config_path = self.dir_context.config_path();
-        if let Err(e) = self.config.save_to_file(&config_path) {
+        if let Err(e) = self.user_config.save_to_file(&config_path) {
             tracing::warn!("Failed to save theme to config: {}", e);
         }
     }
@@ -4642,6 +5005,7 @@ impl Editor {
         if is_builtin || is_user_defined {
             // Update the active keybinding map in config
             self.config.active_keybinding_map = map_name.to_string();
+            self.user_config.active_keybinding_map = map_name.to_string();
 
             // Reload the keybinding resolver with the new map
             self.keybindings = crate::input::keybindings::KeybindingResolver::new(&self.config);
@@ -4665,7 +5029,7 @@ impl Editor {
 
         // Save the config
         let config_path = self.dir_context.config_path();
-        if let Err(e) = self.config.save_to_file(&config_path) {
+        if let Err(e) = self.user_config.save_to_file(&config_path) {
             tracing::warn!("Failed to save keybinding map to config: {}", e);
         }
     }
@@ -0,0 +1,136 @@
+//! Buffer languages
+//!
+//! A buffer's language is detected when its file is opened, from a modeline,
+//! the file name or the `#!` line (see `services::language_detection`), and
+//! kept in its metadata. It picks the buffer's language server, comment prefix
+//! and formatters. Set Language replaces it and re-binds the highlighter and
+//! language server.
+
+use super::*;
+use crate::services::language_detection::detect_buffer_language;
+
+/// Set Language suggestion for a buffer without a language
+const PLAIN_TEXT: &str = "Plain Text";
+
+impl Editor {
+    /// Language of a buffer
+    pub(super) fn buffer_language(&self, buffer_id: BufferId) -> Option<String> {
+        self.buffer_metadata.get(&buffer_id)?.language.clone()
+    }
+
+    /// Language of the buffer open for `path`, or the one its path suggests
+    pub(super) fn language_for_path(&self, path: &Path) -> Option<String> {
+        self.buffer_metadata
+            .values()
+            .find(|meta| meta.file_path().is_some_and(|p| p == path))
+            .map_or_else(
+                || detect_language(path, &self.config.languages),
+                |meta| meta.language.clone(),
+            )
+    }
+
+    /// Detect the language of a file just loaded into `state`
+    pub(super) fn detect_file_language(&self, state: &EditorState, path: &Path) -> Option<String> {
+        detect_buffer_language(Some(path), &state.buffer, &self.config.languages)
+    }
+
+    /// Re-bind a buffer's highlighter and syntax tree to its language when the
+    /// file extension didn't already choose them (`Makefile`, a `#!` script)
+    pub(super) fn rebind_language_highlighter(&mut self, buffer_id: BufferId) {
+        let Some(metadata) = self.buffer_metadata.get(&buffer_id) else {
+            return;
+        };
+        let Some(config) = metadata
+            .language
+            .as_ref()
+            .and_then(|language| self.config.languages.get(language))
+        else {
+            return;
+        };
+        let extension = metadata
+            .file_path()
+            .and_then(|path| path.extension())
+            .and_then(|ext| ext.to_str());
+        if extension.is_some_and(|ext| config.extensions.iter().any(|e| e == ext)) {
+            return;
+        }
+
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.set_language(Some(config), &self.grammar_registry);
+        }
+    }
+
+    /// Prompt for the active buffer's language
+    pub(super) fn start_set_language_prompt(&mut self) {
+        let current = self
+            .buffer_language(self.active_buffer())
+            .unwrap_or_else(|| PLAIN_TEXT.to_string());
+        let mut names: Vec<&str> = self.config.languages.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names.insert(0, PLAIN_TEXT);
+
+        let suggestions: Vec<crate::input::commands::Suggestion> = names
+            .iter()
+            .map(|name| crate::input::commands::Suggestion {
+                text: name.to_string(),
+                description: (*name == current).then(|| "(current)".to_string()),
+                value: Some(name.to_string()),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+            .collect();
+
+        self.prompt = Some(crate::view::prompt::Prompt::with_suggestions(
+            "Set language: ".to_string(),
+            PromptType::SetLanguage,
+            suggestions,
+        ));
+
+        if let Some(prompt) = self.prompt.as_mut() {
+            prompt.selected_suggestion = names.iter().position(|name| *name == current);
+        }
+    }
+
+    /// Set the active buffer's language, overriding detection, and re-bind its
+    /// highlighter and language server
+    pub(super) fn set_buffer_language(&mut self, name: &str) {
+        let language = if name.eq_ignore_ascii_case(PLAIN_TEXT) || name.is_empty() {
+            None
+        } else if self.config.languages.contains_key(name) {
+            Some(name.to_string())
+        } else {
+            self.set_status_message(format!("Unknown language: {}", name));
+            return;
+        };
+
+        let buffer_id = self.active_buffer();
+        let config = language
+            .as_ref()
+            .and_then(|language| self.config.languages.get(language));
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.set_language(config, &self.grammar_registry);
+        }
+
+        let Some(mut metadata) = self.buffer_metadata.remove(&buffer_id) else {
+            return;
+        };
+        if metadata.lsp_enabled {
+            // The old language's servers let go of the document before the
+            // new language's servers open it
+            self.notify_lsp_file_closed(&metadata);
+        }
+        metadata.language = language.clone();
+        if let Some(path) = metadata.file_path().cloned() {
+            if metadata.lsp_enabled {
+                self.notify_lsp_file_opened(&path, buffer_id, &mut metadata);
+            }
+        }
+        self.buffer_metadata.insert(buffer_id, metadata);
+
+        self.set_status_message(format!(
+            "Language set to {}",
+            language.as_deref().unwrap_or(PLAIN_TEXT)
+        ));
+    }
+}
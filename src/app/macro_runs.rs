@@ -0,0 +1,218 @@
+//! Batch macro runs
+//!
+//! Besides playing once at the cursor, a macro can be applied at the start of
+//! every line of the selection, at every match of the last search, or repeated
+//! until it fails, i.e. a run leaves the buffer and cursor as they were. The
+//! positions to visit are tracked with markers so they follow the macro's
+//! edits. A whole run is one undo step: afterwards, the events it added to the
+//! buffer's log are grouped into a batch (see `EventLog::group_since`).
+
+use super::*;
+use crate::model::marker::MarkerId;
+
+/// Most runs of Repeat Macro without a count, in case it never fails
+const MAX_MACRO_REPEATS: usize = 10_000;
+
+/// Where a batch macro run plays its macro
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum MacroRun {
+    /// At the start of each line touched by a selection
+    Lines,
+    /// At each match of the last search
+    Matches,
+    /// From the cursor, this many times or until it fails
+    Repeat(Option<usize>),
+}
+
+impl Editor {
+    /// Play the macro in `register` over lines, search matches or repeatedly,
+    /// as one undo step
+    pub(super) fn run_macro_batch(&mut self, register: char, run: MacroRun) {
+        let Some(actions) = self.macros.get(register).map(|m| m.actions.clone()) else {
+            self.set_status_message(format!("No macro recorded for '{}'", register));
+            return;
+        };
+        if actions.is_empty() {
+            self.set_status_message(format!("Macro '{}' is empty", register));
+            return;
+        }
+
+        let buffer_id = self.active_buffer();
+        // Where the run starts in the active branch; anything undone before
+        // it is cut off by the run's first event
+        let start_index = self.active_event_log().current_index();
+        // Don't record the playback into a macro being recorded
+        let was_recording = self.macro_recording.take();
+
+        let played = match run {
+            MacroRun::Lines => self.play_macro_at_lines(&actions),
+            MacroRun::Matches => self.play_macro_at_matches(&actions),
+            MacroRun::Repeat(limit) => self.repeat_macro(&actions, limit),
+        };
+
+        self.macro_recording = was_recording;
+
+        if let Some(log) = self.event_logs.get_mut(&buffer_id) {
+            log.group_since(start_index, format!("Macro '{}'", register));
+        }
+
+        let plural = |count: usize, suffix: &'static str| if count == 1 { "" } else { suffix };
+        let message = match (run, played) {
+            (_, None) => format!("Macro '{}' switched buffers; stopped", register),
+            (MacroRun::Matches, Some(0)) => {
+                "No search matches; search for the places to play the macro first".to_string()
+            }
+            (MacroRun::Lines, Some(count)) => format!(
+                "Played macro '{}' on {} line{}",
+                register,
+                count,
+                plural(count, "s")
+            ),
+            (MacroRun::Matches, Some(count)) => format!(
+                "Played macro '{}' at {} match{}",
+                register,
+                count,
+                plural(count, "es")
+            ),
+            (MacroRun::Repeat(_), Some(count)) => format!(
+                "Played macro '{}' {} time{}",
+                register,
+                count,
+                plural(count, "s")
+            ),
+        };
+        self.set_status_message(message);
+    }
+
+    /// Play the macro at the start of each line of the selections (the
+    /// cursor's line without one). Returns the number of lines, or `None` if
+    /// the macro left the buffer.
+    fn play_macro_at_lines(&mut self, actions: &[Action]) -> Option<usize> {
+        let state = self.active_state();
+        let mut lines: Vec<usize> = Vec::new();
+        for (_, cursor) in state.cursors.iter() {
+            let range = cursor
+                .selection_range()
+                .unwrap_or(cursor.position..cursor.position);
+            let first = state.buffer.get_line_number(range.start);
+            let mut last = state.buffer.get_line_number(range.end);
+            // A selection ending at the start of a line doesn't include it
+            if range.end > range.start && state.buffer.line_start_offset(last) == Some(range.end) {
+                last -= 1;
+            }
+            lines.extend(first..=last.max(first));
+        }
+        lines.sort_unstable();
+        lines.dedup();
+
+        let positions: Vec<usize> = lines
+            .into_iter()
+            .filter_map(|line| state.buffer.line_start_offset(line))
+            .collect();
+        self.play_macro_at(actions, positions)
+    }
+
+    /// Play the macro at each match of the last search. Returns the number of
+    /// matches, or `None` if the macro left the buffer.
+    fn play_macro_at_matches(&mut self, actions: &[Action]) -> Option<usize> {
+        let matches = self
+            .search_state
+            .as_ref()
+            .map(|search| search.matches.clone())
+            .unwrap_or_default();
+        self.play_macro_at(actions, matches)
+    }
+
+    /// Play the macro with a single cursor at each position, in order. Markers
+    /// keep the positions still to visit in place while the macro edits.
+    fn play_macro_at(&mut self, actions: &[Action], positions: Vec<usize>) -> Option<usize> {
+        if positions.is_empty() {
+            return Some(0);
+        }
+        let buffer_id = self.active_buffer();
+        if self.active_state().cursors.count() > 1 {
+            let _ = self.handle_action(Action::RemoveSecondaryCursors);
+        }
+
+        let markers: Vec<MarkerId> = {
+            let state = self.active_state_mut();
+            positions
+                .iter()
+                .map(|&position| state.marker_list.create(position, true))
+                .collect()
+        };
+
+        let mut played = Some(0);
+        for &marker in &markers {
+            if self.active_buffer() != buffer_id {
+                played = None;
+                break;
+            }
+            let Some(position) = self.active_state().marker_list.get_position(marker) else {
+                continue;
+            };
+            self.move_primary_cursor_for_macro(position);
+            for action in actions {
+                let _ = self.handle_action(action.clone());
+            }
+            played = played.map(|count| count + 1);
+        }
+
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            for marker in markers {
+                state.marker_list.delete(marker);
+            }
+        }
+        played
+    }
+
+    /// Play the macro `limit` times (or up to `MAX_MACRO_REPEATS`), stopping
+    /// early when a run neither edits the buffer nor moves the cursor. Returns
+    /// the number of runs that did something, or `None` if the macro left the
+    /// buffer.
+    fn repeat_macro(&mut self, actions: &[Action], limit: Option<usize>) -> Option<usize> {
+        let buffer_id = self.active_buffer();
+        let mut played = 0;
+        while played < limit.unwrap_or(MAX_MACRO_REPEATS) {
+            let start_index = self.active_event_log().current_index();
+            let position = self.active_state().cursors.primary().position;
+
+            for action in actions {
+                let _ = self.handle_action(action.clone());
+            }
+            if self.active_buffer() != buffer_id {
+                return None;
+            }
+
+            let log = self.active_event_log();
+            let end_index = log.current_index();
+            let edited = log
+                .range(start_index.min(end_index)..end_index)
+                .iter()
+                .any(|entry| entry.event.modifies_buffer());
+            if !edited && self.active_state().cursors.primary().position == position {
+                break;
+            }
+            played += 1;
+        }
+        Some(played)
+    }
+
+    /// Collapse to the primary cursor at `position`, logged so that undoing
+    /// the run puts the cursor back
+    fn move_primary_cursor_for_macro(&mut self, position: usize) {
+        let state = self.active_state();
+        let cursor = state.cursors.primary();
+        let event = Event::MoveCursor {
+            cursor_id: state.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: position,
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+    }
+}
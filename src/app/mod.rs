@@ -2,14 +2,23 @@ mod async_messages;
 mod file_explorer;
 pub mod file_open;
 mod file_open_input;
+mod folding;
+mod formatting;
 mod help;
+mod hierarchy;
 mod input;
+mod language;
+mod macro_runs;
 mod plugin_commands;
 mod render;
 pub mod script_control;
+mod selection_expansion;
 pub mod session;
+mod symbols;
 mod terminal;
+mod text_objects;
 pub mod types;
+mod undo_tree;
 
 use std::path::Component;
 
@@ -46,8 +55,9 @@ pub(crate) fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
 }
 
 use self::types::{
-    Bookmark, CachedLayout, EventLineInfo, InteractiveReplaceState, LspMessageEntry,
-    LspProgressInfo, MacroRecordingState, MouseState, SearchState, DEFAULT_BACKGROUND_FILE,
+    Bookmark, CachedLayout, EventLineInfo, FileClipboard, FileTransferState,
+    InteractiveReplaceState, LspMessageEntry, LspProgressInfo, MacroRecordingState, MouseState,
+    SearchState, DEFAULT_BACKGROUND_FILE,
 };
 use crate::config::{Config, DirectoryContext};
 use crate::input::actions::action_to_events as convert_action_to_events;
@@ -59,16 +69,19 @@ use crate::input::multi_cursor::{
     add_cursor_above, add_cursor_at_next_match, add_cursor_below, AddCursorResult,
 };
 use crate::input::position_history::PositionHistory;
+use crate::model::encoding::Encoding;
 use crate::model::event::{CursorId, Event, EventLog, SplitDirection, SplitId};
 use crate::services::async_bridge::{AsyncBridge, AsyncMessage};
 use crate::services::fs::{FsBackend, FsManager, LocalFsBackend};
 use crate::services::lsp::client::LspServerConfig;
+use crate::services::lsp::features::LspFeature;
 use crate::services::lsp::manager::{detect_language, LspManager, LspSpawnResult};
 use crate::services::plugins::api::{BufferSavedDiff, PluginCommand};
 use crate::services::plugins::PluginManager;
 use crate::services::recovery::{RecoveryConfig, RecoveryService};
+use crate::services::undo_history::UndoHistoryStorage;
 use crate::state::EditorState;
-use crate::view::file_tree::{FileTree, FileTreeView};
+use crate::view::file_tree::{FileDecorations, FileTree, FileTreeView};
 use crate::view::prompt::{Prompt, PromptType};
 use crate::view::split::{SplitManager, SplitViewState};
 use crate::view::ui::{
@@ -99,6 +112,16 @@ fn uri_to_path(uri: &lsp_types::Uri) -> Result<PathBuf, String> {
         .map_err(|_| "URI is not a file path".to_string())
 }
 
+/// How a workspace root is named in the status bar: relative to the working
+/// directory when inside it, else in full
+fn workspace_root_label(root: &Path, working_dir: &Path) -> String {
+    match root.strip_prefix(working_dir) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+        Ok(relative) => relative.display().to_string(),
+        Err(_) => root.display().to_string(),
+    }
+}
+
 /// The main editor struct - manages multiple buffers, clipboard, and rendering
 pub struct Editor {
     /// All open buffers
@@ -113,15 +136,33 @@ pub struct Editor {
     /// Next buffer ID to assign
     next_buffer_id: usize,
 
-    /// Configuration
+    /// Effective configuration (user config with the project config layered over it)
     config: Config,
 
+    /// User configuration, before the project layer (what gets saved to config.json)
+    user_config: Config,
+
+    /// The project's `.fresh/config.json`, if any
+    project_config: Option<crate::project_config::ProjectConfig>,
+
+    /// Trust decisions for project configs
+    project_trust: crate::project_config::ProjectTrust,
+
+    /// Whether the project config's command settings are applied
+    project_trusted: bool,
+
     /// Directory context for editor state paths
     dir_context: DirectoryContext,
 
     /// Grammar registry for TextMate syntax highlighting
     grammar_registry: std::sync::Arc<crate::primitives::grammar_registry::GrammarRegistry>,
 
+    /// Compiled `textobjects.scm` queries for structural motions and selections
+    text_object_queries: crate::primitives::language_queries::LanguageQueries,
+
+    /// Compiled `outline.scm` queries for the outline when no server provides symbols
+    outline_queries: crate::primitives::language_queries::LanguageQueries,
+
     /// Active theme
     theme: crate::view::theme::Theme,
 
@@ -200,6 +241,24 @@ pub struct Editor {
     /// This is the runtime value that can be modified by dragging the border
     file_explorer_width_percent: f32,
 
+    /// Files copied or cut in the file explorer
+    file_explorer_clipboard: Option<FileClipboard>,
+
+    /// Paste or drag-and-drop in the file explorer waiting on a name conflict
+    file_transfer: Option<FileTransferState>,
+
+    /// Git status and plugin badges of file explorer entries
+    file_decorations: FileDecorations,
+
+    /// Whether the git status is being reloaded, and whether to reload it
+    /// again when done because something changed meanwhile
+    git_status_loading: bool,
+    git_status_stale: bool,
+
+    /// Files moved to the trash by each file explorer delete, most recent
+    /// last, for Undo Delete
+    file_explorer_trashed: Vec<Vec<crate::services::trash::TrashedItem>>,
+
     /// Whether mouse capture is enabled
     mouse_enabled: bool,
 
@@ -232,6 +291,11 @@ pub struct Editor {
     /// Pending LSP completion request ID (if any)
     pending_completion_request: Option<u64>,
 
+    /// Completion items received so far for the pending request, with the
+    /// server that sent each, and how many servers have yet to respond
+    completion_responses: Vec<(String, lsp_types::CompletionItem)>,
+    completion_servers_pending: usize,
+
     /// Pending LSP go-to-definition request ID (if any)
     pending_goto_definition_request: Option<u64>,
 
@@ -253,6 +317,36 @@ pub struct Editor {
     /// Pending LSP inlay hints request ID (if any)
     pending_inlay_hints_request: Option<u64>,
 
+    /// Pending LSP formatting requests, at most one per buffer
+    pending_format_requests: HashMap<BufferId, formatting::PendingFormat>,
+
+    /// External formatters running on buffers' text, at most one run per buffer
+    pending_external_formats: HashMap<BufferId, formatting::PendingExternalFormat>,
+
+    /// Next external formatter run ID
+    next_external_format_run: u64,
+
+    /// Pending LSP folding range request and the fold command waiting for it
+    pending_folding_range_request: Option<folding::PendingFoldingRanges>,
+
+    /// Pending LSP selection range request for Expand Selection
+    pending_selection_range_request: Option<selection_expansion::PendingSelectionRanges>,
+
+    /// Pending LSP document symbol requests for the symbol prompt and the outline
+    pending_document_symbols: Vec<symbols::PendingDocumentSymbols>,
+
+    /// Latest LSP workspace symbol request; answers to earlier ones are dropped
+    pending_workspace_symbols: Option<u64>,
+
+    /// The outline side panel, when open
+    outline_panel: Option<symbols::OutlinePanel>,
+
+    /// Pending LSP call and type hierarchy requests
+    pending_hierarchy: HashMap<u64, hierarchy::HierarchyRequest>,
+
+    /// The call or type hierarchy explorer, when open
+    hierarchy_panel: Option<hierarchy::HierarchyPanel>,
+
     /// Hover symbol range (byte offsets) - for highlighting the symbol under hover
     /// Format: (start_byte_offset, end_byte_offset)
     hover_symbol_range: Option<(usize, usize)>,
@@ -312,9 +406,11 @@ pub struct Editor {
     /// LSP progress tracking (token -> progress info)
     lsp_progress: std::collections::HashMap<String, LspProgressInfo>,
 
-    /// LSP server statuses (language -> status)
-    lsp_server_statuses:
-        std::collections::HashMap<String, crate::services::async_bridge::LspServerStatus>,
+    /// LSP server statuses ((language, server, workspace root) -> status)
+    lsp_server_statuses: std::collections::HashMap<
+        (String, String, Option<PathBuf>),
+        crate::services::async_bridge::LspServerStatus,
+    >,
 
     /// LSP window messages (recent messages from window/showMessage)
     lsp_window_messages: Vec<LspMessageEntry>,
@@ -322,9 +418,13 @@ pub struct Editor {
     /// LSP log messages (recent messages from window/logMessage)
     lsp_log_messages: Vec<LspMessageEntry>,
 
-    /// Diagnostic result IDs per URI (for incremental pull diagnostics)
-    /// Maps URI string to last result_id received from server
-    diagnostic_result_ids: HashMap<String, String>,
+    /// Diagnostic result IDs per URI and server (for incremental pull diagnostics)
+    /// Maps (URI string, server name) to last result_id received from that server
+    diagnostic_result_ids: HashMap<(String, String), String>,
+
+    /// Diagnostics as reported by each language server, merged per URI
+    /// before being stored in `stored_diagnostics`
+    server_diagnostics: crate::services::lsp::diagnostics::ServerDiagnostics,
 
     /// Stored LSP diagnostics per URI
     /// Maps file URI string to Vec of diagnostics for that file
@@ -343,8 +443,8 @@ pub struct Editor {
     /// Whether to confirm each replacement (interactive/query-replace mode)
     search_confirm_each: bool,
 
-    /// Macro storage (key -> list of recorded actions)
-    macros: HashMap<char, Vec<Action>>,
+    /// Recorded macros by register, saved in the data directory
+    macros: crate::input::macros::MacroStore,
 
     /// Macro recording state (Some(key) if recording, None otherwise)
     macro_recording: Option<MacroRecordingState>,
@@ -402,6 +502,9 @@ pub struct Editor {
     /// Recovery service for auto-save and crash recovery
     recovery_service: RecoveryService,
 
+    /// Persistent undo history storage (used when `editor.persistent_undo` is enabled)
+    undo_history: UndoHistoryStorage,
+
     /// Last auto-save time for rate limiting
     last_auto_save: std::time::Instant,
 
@@ -526,6 +629,29 @@ impl Editor {
         // This ensures consistent path comparisons throughout the editor
         let working_dir = working_dir.canonicalize().unwrap_or_else(|_| working_dir);
 
+        // Layer the project's .fresh/config.json over the user config. Settings that
+        // run programs are held back until the project config is trusted.
+        let user_config = config;
+        let project_config = crate::project_config::ProjectConfig::discover(&working_dir);
+        let project_trust =
+            crate::project_config::ProjectTrust::load(dir_context.trusted_projects_path());
+        let project_trusted = project_config
+            .as_ref()
+            .is_some_and(|project| project_trust.is_trusted(project));
+        let config = match &project_config {
+            Some(project) => project
+                .apply(&user_config, project_trusted)
+                .unwrap_or_else(|e| {
+                    tracing::warn!(
+                        "Ignoring invalid project config {}: {}",
+                        project.path.display(),
+                        e
+                    );
+                    user_config.clone()
+                }),
+            None => user_config.clone(),
+        };
+
         // Load theme from config
         let theme = crate::view::theme::Theme::from_name(&config.theme);
 
@@ -679,13 +805,21 @@ impl Editor {
             None
         };
 
-        Ok(Editor {
+        let mut editor = Editor {
             buffers,
             event_logs,
             next_buffer_id: 1,
             config,
+            user_config,
+            project_config,
+            project_trust,
+            project_trusted,
             dir_context: dir_context.clone(),
             grammar_registry,
+            text_object_queries: crate::primitives::text_objects::queries(Some(
+                dir_context.queries_dir(),
+            )),
+            outline_queries: crate::primitives::outline::queries(Some(dir_context.queries_dir())),
             theme,
             ansi_background: None,
             ansi_background_path: None,
@@ -711,6 +845,12 @@ impl Editor {
             file_explorer_visible: false,
             file_explorer_sync_in_progress: false,
             file_explorer_width_percent: file_explorer_width,
+            file_explorer_clipboard: None,
+            file_transfer: None,
+            file_explorer_trashed: Vec::new(),
+            file_decorations: FileDecorations::default(),
+            git_status_loading: false,
+            git_status_stale: false,
             mouse_enabled: true,
             mouse_cursor_position: None,
             gpm_active: false,
@@ -721,12 +861,24 @@ impl Editor {
             in_navigation: false,
             next_lsp_request_id: 0,
             pending_completion_request: None,
+            completion_responses: Vec::new(),
+            completion_servers_pending: 0,
             pending_goto_definition_request: None,
             pending_hover_request: None,
             pending_references_request: None,
             pending_references_symbol: String::new(),
             pending_signature_help_request: None,
             pending_code_actions_request: None,
+            pending_format_requests: HashMap::new(),
+            pending_external_formats: HashMap::new(),
+            next_external_format_run: 0,
+            pending_folding_range_request: None,
+            pending_selection_range_request: None,
+            pending_document_symbols: Vec::new(),
+            pending_workspace_symbols: None,
+            outline_panel: None,
+            pending_hierarchy: HashMap::new(),
+            hierarchy_panel: None,
             pending_inlay_hints_request: None,
             hover_symbol_range: None,
             hover_symbol_overlay: None,
@@ -772,6 +924,7 @@ impl Editor {
             lsp_window_messages: Vec::new(),
             lsp_log_messages: Vec::new(),
             diagnostic_result_ids: HashMap::new(),
+            server_diagnostics: crate::services::lsp::diagnostics::ServerDiagnostics::new(),
             stored_diagnostics: HashMap::new(),
             event_broadcaster: crate::model::control_event::EventBroadcaster::default(),
             bookmarks: HashMap::new(),
@@ -779,7 +932,7 @@ impl Editor {
             search_whole_word: false,
             search_use_regex: false,
             search_confirm_each: false,
-            macros: HashMap::new(),
+            macros: crate::input::macros::MacroStore::load(dir_context.macros_path()),
             macro_recording: None,
             last_macro_register: None,
             #[cfg(feature = "plugins")]
@@ -804,6 +957,7 @@ impl Editor {
                 };
                 RecoveryService::with_config_and_dir(recovery_config, dir_context.recovery_dir())
             },
+            undo_history: UndoHistoryStorage::new(dir_context.undo_history_dir()),
             last_auto_save: std::time::Instant::now(),
             active_custom_contexts: HashSet::new(),
             warning_log: None,
@@ -817,7 +971,10 @@ impl Editor {
             terminal_mode_resume: std::collections::HashSet::new(),
             previous_click_time: None,
             previous_click_position: None,
-        })
+        };
+        editor.sync_macro_commands();
+        editor.offer_project_trust();
+        Ok(editor)
     }
 
     /// Get a reference to the event broadcaster
@@ -925,13 +1082,12 @@ impl Editor {
             .collect()
     }
 
-    /// Check if LSP server for a given language is running (ready)
+    /// Check if an LSP server for a given language is running (ready)
     pub fn is_lsp_server_ready(&self, language: &str) -> bool {
         use crate::services::async_bridge::LspServerStatus;
         self.lsp_server_statuses
-            .get(language)
-            .map(|status| matches!(status, LspServerStatus::Running))
-            .unwrap_or(false)
+            .iter()
+            .any(|((lang, _, _), status)| lang == language && *status == LspServerStatus::Running)
     }
 
     /// Get the LSP status string (displayed in status bar)
@@ -984,7 +1140,7 @@ impl Editor {
     /// Configure LSP server for a specific language
     pub fn set_lsp_config(&mut self, language: String, config: LspServerConfig) {
         if let Some(ref mut lsp) = self.lsp {
-            lsp.set_language_config(language, config);
+            lsp.set_language_config(language, config.into());
         }
     }
 
@@ -1018,6 +1174,9 @@ impl Editor {
 
     /// Log keystroke for debugging
     pub fn log_keystroke(&mut self, key_code: &str, modifiers: &str) {
+        if self.is_input_masked() {
+            return;
+        }
         if let Some(event_log) = self.event_logs.get_mut(&self.active_buffer()) {
             event_log.log_keystroke(key_code, modifiers);
         }
@@ -1275,17 +1434,35 @@ impl Editor {
 
         // Check if the buffer contains binary content
         let is_binary = state.buffer.is_binary();
+        if !is_binary {
+            self.apply_editorconfig(&mut state, path, true);
+        }
         if is_binary {
             // Make binary buffers read-only
             state.editing_disabled = true;
             tracing::info!("Detected binary file: {}", path.display());
         }
 
+        // Restore persisted undo history if the file is unchanged since it was recorded
+        let event_log = if file_exists && !is_binary && self.config.editor.persistent_undo {
+            let large = state.buffer.is_large_file();
+            self.undo_history.load(path, large).unwrap_or_default()
+        } else {
+            EventLog::new()
+        };
+
+        let language = if is_binary {
+            None
+        } else {
+            self.detect_file_language(&state, path)
+        };
+
         self.buffers.insert(buffer_id, state);
-        self.event_logs.insert(buffer_id, EventLog::new());
+        self.event_logs.insert(buffer_id, event_log);
 
         // Create metadata for this buffer
         let mut metadata = BufferMetadata::with_file(path.to_path_buf(), &self.working_dir);
+        metadata.language = language;
 
         // Mark binary files in metadata and disable LSP
         if is_binary {
@@ -1301,6 +1478,7 @@ impl Editor {
 
         // Store metadata for this buffer
         self.buffer_metadata.insert(buffer_id, metadata);
+        self.rebind_language_highlighter(buffer_id);
 
         // Add buffer to the active split's tabs (but don't switch to it)
         let active_split = self.split_manager.active_split();
@@ -1332,6 +1510,34 @@ impl Editor {
         Ok(buffer_id)
     }
 
+    /// Apply the `.editorconfig` settings covering `path` to a freshly loaded buffer.
+    /// The charset is only applied if `detect_encoding` (no encoding was chosen explicitly).
+    fn apply_editorconfig(&self, state: &mut EditorState, path: &Path, detect_encoding: bool) {
+        use crate::services::editorconfig::{properties_for, IndentStyle};
+
+        if !self.config.editor.editorconfig {
+            return;
+        }
+        let properties = properties_for(path);
+        if properties.is_empty() {
+            return;
+        }
+        tracing::debug!("EditorConfig for {}: {:?}", path.display(), properties);
+
+        state.settings = crate::state::BufferSettings {
+            indent_size: properties.indent_size,
+            use_tabs: properties.indent_style == Some(IndentStyle::Tab),
+            tab_width: properties.tab_width,
+            trim_trailing_whitespace: properties.trim_trailing_whitespace.unwrap_or(false),
+            insert_final_newline: properties.insert_final_newline.unwrap_or(false),
+            max_line_length: properties.max_line_length,
+        };
+        let charset = properties.charset.filter(|_| detect_encoding);
+        state
+            .buffer
+            .apply_format_preferences(properties.end_of_line, charset);
+    }
+
     /// Navigate to a specific line and column in the active buffer.
     ///
     /// Line and column are 1-indexed (matching typical editor conventions).
@@ -1677,7 +1883,13 @@ impl Editor {
                 .set_split_buffer(split_id, replacement_buffer);
         }
 
-        self.buffers.remove(&id);
+        self.persist_undo_history(id);
+
+        if let Some(state) = self.buffers.remove(&id) {
+            if let Some(path) = state.buffer.file_path() {
+                self.undo_history.forget(path);
+            }
+        }
         self.event_logs.remove(&id);
         self.seen_byte_ranges.remove(&id);
         self.buffer_metadata.remove(&id);
@@ -2428,7 +2640,7 @@ impl Editor {
             None => return,
         };
 
-        let language = match detect_language(&path, &self.config.languages) {
+        let language = match self.language_for_path(&path) {
             Some(lang) => lang,
             None => return,
         };
@@ -2443,7 +2655,9 @@ impl Editor {
 
         // Get LSP client for this language
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
+            if let Some(client) =
+                lsp.get_or_spawn_for(&language, Some(&path), LspFeature::InlayHints)
+            {
                 let request_id = self.next_lsp_request_id;
                 self.next_lsp_request_id += 1;
                 self.pending_inlay_hints_request = Some(request_id);
@@ -2473,12 +2687,13 @@ impl Editor {
 
         let config_path = self.dir_context.config_path();
 
-        // Save the config
-        match self.config.save_to_file(&config_path) {
+        // Save the user layer (project settings stay in the project's config file)
+        match self.user_config.save_to_file(&config_path) {
             Ok(()) => {
-                // Open the saved config file in a new buffer
+                // Open the saved config file in a new buffer, next to the layers report
                 match self.open_file(&config_path) {
                     Ok(_buffer_id) => {
+                        self.show_config_layers();
                         self.set_status_message(format!(
                             "Config saved to {}",
                             config_path.display()
@@ -2504,20 +2719,31 @@ impl Editor {
             .map_err(|e| format!("Failed to create config directory: {}", e))?;
 
         let config_path = self.dir_context.config_path();
-        self.config
+        self.user_config
             .save_to_file(&config_path)
             .map_err(|e| format!("Failed to save config: {}", e))
     }
 
     /// Reload configuration from the config file
     ///
-    /// This reloads the config from disk and emits a config_changed event
-    /// so plugins can update their state accordingly.
+    /// This reloads the user and project config from disk, sends language
+    /// servers their new settings and emits a config_changed event so plugins
+    /// can update their state accordingly.
     pub fn reload_config(&mut self) {
         let config_path = self.dir_context.config_path();
         match Config::load_from_file(&config_path) {
             Ok(new_config) => {
-                self.config = new_config;
+                self.user_config = new_config;
+                self.reload_project_config();
+                self.config = self.layered_config();
+
+                // Running language servers pick up their new settings
+                if let Some(lsp) = &mut self.lsp {
+                    for (language, lsp_config) in &self.config.lsp {
+                        lsp.set_language_config(language.clone(), lsp_config.clone());
+                    }
+                }
+
                 // Emit event so plugins know config changed
                 self.emit_event(
                     "config_changed",
@@ -2534,6 +2760,101 @@ impl Editor {
         }
     }
 
+    /// Re-read the project config; a changed file has to be trusted again
+    fn reload_project_config(&mut self) {
+        let previous = std::mem::replace(
+            &mut self.project_config,
+            crate::project_config::ProjectConfig::discover(&self.working_dir),
+        );
+        if self.project_config == previous {
+            return;
+        }
+        self.project_trusted = self
+            .project_config
+            .as_ref()
+            .is_some_and(|project| self.project_trust.is_trusted(project));
+        self.offer_project_trust();
+    }
+
+    /// The user config with the project config layered over it
+    fn layered_config(&self) -> Config {
+        let Some(project) = &self.project_config else {
+            return self.user_config.clone();
+        };
+        project
+            .apply(&self.user_config, self.project_trusted)
+            .unwrap_or_else(|e| {
+                tracing::warn!(
+                    "Ignoring invalid project config {}: {}",
+                    project.path.display(),
+                    e
+                );
+                self.user_config.clone()
+            })
+    }
+
+    /// Ask whether to trust the project config if it sets commands that are held back
+    fn offer_project_trust(&mut self) {
+        let Some(project) = &self.project_config else {
+            return;
+        };
+        let commands = project.command_settings();
+        if self.project_trusted || commands.is_empty() {
+            return;
+        }
+        self.start_prompt(
+            format!(
+                "Trust project config (sets {})? (y/N) ",
+                commands.join(", ")
+            ),
+            PromptType::TrustProjectConfig,
+        );
+    }
+
+    /// Trust the project config and apply its command settings
+    pub(crate) fn trust_project_config(&mut self) {
+        let Some(project) = &self.project_config else {
+            return;
+        };
+        if let Err(e) = self.project_trust.trust(project) {
+            tracing::warn!("Failed to record project config trust: {}", e);
+        }
+        self.project_trusted = true;
+        self.config = self.layered_config();
+
+        // Reconfigure language servers, restarting the ones already running
+        if let Some(lsp) = &mut self.lsp {
+            for (language, lsp_config) in &self.config.lsp {
+                lsp.set_language_config(language.clone(), lsp_config.clone());
+            }
+            for language in lsp.running_servers() {
+                lsp.manual_restart(&language);
+            }
+        }
+        self.set_status_message("Project config trusted".to_string());
+    }
+
+    /// Show each effective setting and the layer (default, user or project) it comes from
+    fn show_config_layers(&mut self) {
+        let report = crate::project_config::describe_layers(
+            &self.config,
+            &self.dir_context.config_path(),
+            self.project_config
+                .as_ref()
+                .map(|project| (project, self.project_trusted)),
+        );
+
+        let buffer_id =
+            self.create_virtual_buffer("*Config Layers*".to_string(), "special".to_string(), true);
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.buffer.insert(0, &report);
+            state.buffer.clear_modified();
+            state.editing_disabled = true;
+            state.margins.set_line_numbers(false);
+        }
+        self.set_active_buffer(buffer_id);
+    }
+
     /// Calculate the effective width available for tabs.
     ///
     /// When the file explorer is visible, tabs only get a portion of the terminal width
@@ -2768,6 +3089,11 @@ impl Editor {
             _ => {}
         }
 
+        // 1d. Let the outline refresh once editing pauses
+        if event.modifies_buffer() {
+            self.mark_outline_stale(self.active_buffer());
+        }
+
         // 2. Adjust cursors in other splits that share the same buffer
         self.adjust_other_split_cursors_for_event(event);
 
@@ -3303,11 +3629,73 @@ impl Editor {
             .buffer
             .file_path()
             .map(|p| p.to_path_buf());
+        self.apply_save_whitespace_fixes();
         self.active_state_mut().buffer.save()?;
+        self.finish_save(path);
+        Ok(())
+    }
+
+    /// Apply the buffer's save-time whitespace settings (trimming trailing
+    /// whitespace, adding a final newline) to the active buffer as one undoable edit
+    fn apply_save_whitespace_fixes(&mut self) {
+        let state = self.active_state();
+        let settings = &state.settings;
+        if !settings.trim_trailing_whitespace && !settings.insert_final_newline {
+            return;
+        }
+        // Whole-buffer fixes are skipped for large files
+        if state.buffer.is_large_file() {
+            return;
+        }
+        let Some(text) = state.buffer.to_string() else {
+            return;
+        };
+        let cursor_id = state.cursors.primary_id();
+
+        // Edits are collected from the end of the buffer backwards
+        let mut events = Vec::new();
+        if settings.insert_final_newline && !text.is_empty() && !text.ends_with('\n') {
+            events.push(Event::Insert {
+                position: text.len(),
+                text: "\n".to_string(),
+                cursor_id,
+            });
+        }
+        if settings.trim_trailing_whitespace {
+            let mut line_start = text.len();
+            for line in text.rsplit('\n') {
+                line_start -= line.len();
+                let content = line.strip_suffix('\r').unwrap_or(line);
+                let trimmed = content.trim_end_matches([' ', '\t']);
+                if trimmed.len() < content.len() {
+                    events.push(Event::Delete {
+                        range: line_start + trimmed.len()..line_start + content.len(),
+                        deleted_text: content[trimmed.len()..].to_string(),
+                        cursor_id,
+                    });
+                }
+                line_start = line_start.saturating_sub(1);
+            }
+        }
+
+        if events.is_empty() {
+            return;
+        }
+        let batch = Event::Batch {
+            events,
+            description: "Fix whitespace on save".to_string(),
+        };
+        self.active_event_log_mut().append(batch.clone());
+        self.apply_event_to_active_buffer(&batch);
+    }
+
+    /// Bookkeeping after the active buffer was written to its file
+    fn finish_save(&mut self, path: Option<std::path::PathBuf>) {
         self.status_message = Some("Saved".to_string());
 
         // Mark the event log position as saved (for undo modified tracking)
         self.active_event_log_mut().mark_saved();
+        self.record_undo_history_saved(self.active_buffer());
 
         // Update file modification time after save
         if let Some(ref p) = path {
@@ -3345,13 +3733,76 @@ impl Editor {
                 },
             );
         }
+        self.refresh_git_status();
+    }
 
-        Ok(())
+    /// Save the active buffer through the privileged helper
+    /// (`editor.privileged_save_command`), asking for the sudo password first if needed
+    pub fn start_save_as_root(&mut self) {
+        if self.active_state().buffer.file_path().is_none() {
+            self.set_status_message("Buffer has no file; use Save As first".to_string());
+            return;
+        }
+        if crate::services::privileged_save::needs_password(
+            &self.config.editor.privileged_save_command,
+        ) {
+            self.start_prompt(
+                "[sudo] password: ".to_string(),
+                PromptType::SaveAsRootPassword,
+            );
+        } else {
+            self.save_as_root(None);
+        }
+    }
+
+    /// Write the active buffer through the privileged helper and mark it saved
+    pub fn save_as_root(&mut self, password: Option<&str>) {
+        use crate::services::privileged_save;
+
+        let Some(path) = self
+            .active_state()
+            .buffer
+            .file_path()
+            .map(|p| p.to_path_buf())
+        else {
+            return;
+        };
+        let command = self.config.editor.privileged_save_command.clone();
+        self.apply_save_whitespace_fixes();
+
+        let result = password
+            .map_or(Ok(()), |password| {
+                privileged_save::authenticate(&command, password)
+            })
+            .and_then(|()| {
+                let buffer = &self.active_state().buffer;
+                privileged_save::write_privileged(&command, &path, |out| buffer.write_contents(out))
+            })
+            .and_then(|()| self.active_state_mut().buffer.mark_saved_externally());
+
+        match result {
+            Ok(()) => {
+                self.finish_save(Some(path));
+                self.set_status_message("Saved as root".to_string());
+            }
+            Err(e) => self.set_status_message(format!("Save as root failed: {}", e)),
+        }
     }
 
     /// Revert the active buffer to the last saved version on disk
     /// Returns Ok(true) if reverted, Ok(false) if no file path, Err on failure
     pub fn revert_file(&mut self) -> io::Result<bool> {
+        let reverted = self.reload_active_file(None)?;
+        if reverted {
+            self.status_message = Some("Reverted to saved file".to_string());
+        }
+        Ok(reverted)
+    }
+
+    /// Reload the active buffer from disk, decoding it with the given encoding
+    /// (or a detected one if `None`). Discards unsaved changes and undo history.
+    /// Returns Ok(true) if reloaded, Ok(false) if no file path, Err on failure
+    fn reload_active_file(&mut self, encoding: Option<Encoding>) -> io::Result<bool> {
         let path = match self.active_state().buffer.file_path() {
             Some(p) => p.to_path_buf(),
             None => {
@@ -3375,13 +3826,17 @@ impl Editor {
         let old_cursors = self.active_state().cursors.clone();
 
         // Load the file content fresh from disk
-        let mut new_state = EditorState::from_file(
+        let mut new_state = EditorState::from_file_with_encoding(
             &path,
             self.terminal_width,
             self.terminal_height,
             self.config.editor.large_file_threshold_bytes as usize,
             &self.grammar_registry,
+            encoding,
         )?;
+        if !new_state.buffer.is_binary() {
+            self.apply_editorconfig(&mut new_state, &path, encoding.is_none());
+        }
 
         // Restore cursor positions (clamped to valid range for new file size)
         let new_file_size = new_state.buffer.len();
@@ -3399,6 +3854,7 @@ impl Editor {
             *state = new_state;
             // Note: line_wrap_enabled is now in SplitViewState.viewport
         }
+        self.rebind_language_highlighter(buffer_id);
 
         // Restore scroll position in SplitViewState (clamped to valid range for new file size)
         let active_split = self.split_manager.active_split();
@@ -3411,6 +3867,7 @@ impl Editor {
         if let Some(event_log) = self.event_logs.get_mut(&buffer_id) {
             *event_log = EventLog::new();
         }
+        self.undo_history.forget(&path);
 
         // Clear seen_byte_ranges so plugins get notified of all visible lines
         self.seen_byte_ranges.remove(&buffer_id);
@@ -3425,10 +3882,61 @@ impl Editor {
         // Notify LSP that the file was changed
         self.notify_lsp_file_changed(&path);
 
-        self.status_message = Some("Reverted to saved file".to_string());
         Ok(true)
     }
 
+    /// Reload the active buffer from disk, decoding it with the named encoding
+    pub fn reopen_with_encoding(&mut self, name: &str) {
+        let Some(encoding) = Encoding::from_name(name) else {
+            self.set_status_message(format!("Unknown encoding: {}", name));
+            return;
+        };
+        if self.active_state().buffer.is_modified() {
+            self.set_status_message(
+                "Buffer has unsaved changes; save or revert before reopening".to_string(),
+            );
+            return;
+        }
+        match self.reload_active_file(Some(encoding)) {
+            Ok(true) => self.set_status_message(format!(
+                "Reopened with encoding {}",
+                encoding.display_name()
+            )),
+            Ok(false) => {}
+            Err(e) => self.set_status_message(format!("Failed to reopen: {}", e)),
+        }
+    }
+
+    /// Save the active buffer in the named encoding
+    pub fn save_with_encoding(&mut self, name: &str) {
+        let Some(encoding) = Encoding::from_name(name) else {
+            self.set_status_message(format!("Unknown encoding: {}", name));
+            return;
+        };
+        let previous = self.active_state().buffer.encoding();
+        self.active_state_mut().buffer.set_encoding(encoding);
+
+        if self.active_state().buffer.file_path().is_none() {
+            // Encoding is kept for when the buffer is saved with a name
+            self.start_prompt_with_initial_text(
+                "Save as: ".to_string(),
+                PromptType::SaveFileAs,
+                String::new(),
+            );
+            return;
+        }
+
+        match self.save() {
+            Ok(()) => {
+                self.set_status_message(format!("Saved with encoding {}", encoding.display_name()))
+            }
+            Err(e) => {
+                self.active_state_mut().buffer.set_encoding(previous);
+                self.set_status_message(format!("Failed to save: {}", e));
+            }
+        }
+    }
+
     /// Toggle auto-revert mode
     pub fn toggle_auto_revert(&mut self) {
         self.auto_revert_enabled = !self.auto_revert_enabled;
@@ -3518,6 +4026,25 @@ impl Editor {
         }
     }
 
+    /// Send didClose for a buffer's file to the servers that have it open
+    fn notify_lsp_file_closed(&mut self, metadata: &BufferMetadata) {
+        let (Some(language), Some(path), Some(uri)) = (
+            metadata.language.as_deref(),
+            metadata.file_path(),
+            metadata.file_uri(),
+        ) else {
+            return;
+        };
+        let Some(lsp) = &mut self.lsp else {
+            return;
+        };
+        for client in lsp.get_running_all(language, path) {
+            if let Err(e) = client.did_close(uri.clone()) {
+                tracing::warn!("Failed to send didClose to LSP: {}", e);
+            }
+        }
+    }
+
     /// Notify LSP server about a newly opened file
     /// Handles language detection, spawning LSP clients, and sending didOpen notifications
     fn notify_lsp_file_opened(
@@ -3527,7 +4054,7 @@ impl Editor {
         metadata: &mut BufferMetadata,
     ) {
         // Early return checks that don't need mutable lsp borrow
-        let Some(language) = detect_language(path, &self.config.languages) else {
+        let Some(language) = metadata.language.clone() else {
             tracing::debug!("No language detected for file: {}", path.display());
             return;
         };
@@ -3567,7 +4094,6 @@ impl Editor {
         };
 
         let enable_inlay_hints = self.config.editor.enable_inlay_hints;
-        let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
 
         // Get buffer line count for inlay hints
         let (last_line, last_char) = self
@@ -3594,38 +4120,33 @@ impl Editor {
         tracing::debug!("Using URI from metadata: {}", uri.as_str());
         tracing::debug!("Attempting to spawn LSP client for language: {}", language);
 
-        match lsp.try_spawn(&language) {
+        match lsp.try_spawn(&language, Some(path)) {
             LspSpawnResult::Spawned => {
-                if let Some(client) = lsp.get_or_spawn(&language) {
-                    // Send didOpen
-                    tracing::info!("Sending didOpen to LSP for: {}", uri.as_str());
-                    if let Err(e) = client.did_open(uri.clone(), text, language.clone()) {
+                metadata.lsp_root = lsp.root_for(&language, path);
+                let clients = lsp.get_or_spawn_all(&language, Some(path));
+                if clients.is_empty() {
+                    return;
+                }
+
+                // Send didOpen to every server for the language
+                tracing::info!("Sending didOpen to LSP for: {}", uri.as_str());
+                for client in clients {
+                    if let Err(e) = client.did_open(uri.clone(), text.clone(), language.clone()) {
                         tracing::warn!("Failed to send didOpen to LSP: {}", e);
                         return;
                     }
-                    tracing::info!("Successfully sent didOpen to LSP");
+                }
+                tracing::info!("Successfully sent didOpen to LSP");
 
-                    // Request pull diagnostics
-                    let request_id = self.next_lsp_request_id;
-                    self.next_lsp_request_id += 1;
-                    if let Err(e) =
-                        client.document_diagnostic(request_id, uri.clone(), previous_result_id)
-                    {
-                        tracing::debug!(
-                            "Failed to request pull diagnostics (server may not support): {}",
-                            e
-                        );
-                    } else {
-                        tracing::info!(
-                            "Requested pull diagnostics for {} (request_id={})",
-                            uri.as_str(),
-                            request_id
-                        );
-                    }
+                // Request pull diagnostics
+                self.request_pull_diagnostics(&language, &uri);
 
-                    // Request inlay hints
-                    if enable_inlay_hints {
-                        let request_id = self.next_lsp_request_id;
+                // Request inlay hints
+                if enable_inlay_hints {
+                    let request_id = self.next_lsp_request_id;
+                    if let Some(client) = self.lsp.as_mut().and_then(|lsp| {
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::InlayHints)
+                    }) {
                         self.next_lsp_request_id += 1;
                         self.pending_inlay_hints_request = Some(request_id);
 
@@ -3693,13 +4214,47 @@ impl Editor {
         }
     }
 
+    /// Request pull diagnostics for a document from each server that provides them,
+    /// passing every server its own previous result ID
+    pub(crate) fn request_pull_diagnostics(&mut self, language: &str, uri: &lsp_types::Uri) {
+        let Some(lsp) = self.lsp.as_mut() else {
+            return;
+        };
+
+        let path = uri_to_path(uri).ok();
+        let clients = lsp.get_or_spawn_all_for(language, path.as_deref(), LspFeature::Diagnostics);
+        for client in clients {
+            let previous_result_id = self
+                .diagnostic_result_ids
+                .get(&(uri.as_str().to_string(), client.server().to_string()))
+                .cloned();
+            let request_id = self.next_lsp_request_id;
+            self.next_lsp_request_id += 1;
+
+            if let Err(e) = client.document_diagnostic(request_id, uri.clone(), previous_result_id)
+            {
+                tracing::debug!(
+                    "Failed to request pull diagnostics (server may not support): {}",
+                    e
+                );
+            } else {
+                tracing::info!(
+                    "Requested pull diagnostics for {} from {} (request_id={})",
+                    uri.as_str(),
+                    client.server(),
+                    request_id
+                );
+            }
+        }
+    }
+
     /// Notify LSP that a file's contents changed (e.g., after revert)
     fn notify_lsp_file_changed(&mut self, path: &Path) {
+        let language = self.language_for_path(path);
         if let Some(lsp) = &mut self.lsp {
             if let Ok(uri) = url::Url::from_file_path(path) {
                 if let Ok(lsp_uri) = uri.as_str().parse::<lsp_types::Uri>() {
-                    // Detect language for this file
-                    if let Some(language) = detect_language(path, &self.config.languages) {
+                    if let Some(language) = language {
                         // Get the new content
                         let content = self
                             .buffers
@@ -3709,13 +4264,15 @@ impl Editor {
 
                         // Use full document sync - send the entire new content
                         if let Some(content) = content {
-                            if let Some(client) = lsp.get_or_spawn(&language) {
+                            for client in lsp.get_or_spawn_all(&language, Some(path)) {
                                 let content_change = TextDocumentContentChangeEvent {
                                     range: None, // None means full document replacement
                                     range_length: None,
-                                    text: content,
+                                    text: content.clone(),
                                 };
-                                if let Err(e) = client.did_change(lsp_uri, vec![content_change]) {
+                                if let Err(e) =
+                                    client.did_change(lsp_uri.clone(), vec![content_change])
+                                {
                                     tracing::warn!("Failed to notify LSP of file change: {}", e);
                                 }
                             }
@@ -4178,6 +4735,56 @@ impl Editor {
         Ok(())
     }
 
+    /// Record that a buffer was just written to disk and persist its undo history
+    pub(crate) fn record_undo_history_saved(&mut self, buffer_id: BufferId) {
+        if !self.config.editor.persistent_undo {
+            return;
+        }
+        let Some((path, large)) = self.buffers.get(&buffer_id).and_then(|state| {
+            let path = state.buffer.file_path()?.to_path_buf();
+            Some((path, state.buffer.is_large_file()))
+        }) else {
+            return;
+        };
+        if let Err(e) = self.undo_history.record_saved(&path, large) {
+            tracing::warn!("Failed to hash {} for undo history: {}", path.display(), e);
+            return;
+        }
+        self.persist_undo_history(buffer_id);
+    }
+
+    /// Persist a buffer's undo history (no-op unless `editor.persistent_undo` is enabled)
+    fn persist_undo_history(&self, buffer_id: BufferId) {
+        if !self.config.editor.persistent_undo {
+            return;
+        }
+        let (Some(state), Some(event_log)) = (
+            self.buffers.get(&buffer_id),
+            self.event_logs.get(&buffer_id),
+        ) else {
+            return;
+        };
+        let Some(path) = state.buffer.file_path() else {
+            return;
+        };
+        // Large files only keep the edits themselves, not cursor movement etc.
+        let compact = state.buffer.is_large_file();
+        if let Err(e) = self.undo_history.save(path, event_log, compact) {
+            tracing::warn!(
+                "Failed to persist undo history for {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    /// Persist the undo history of all open buffers (call before exiting)
+    pub fn persist_all_undo_history(&self) {
+        for &buffer_id in self.buffers.keys() {
+            self.persist_undo_history(buffer_id);
+        }
+    }
+
     /// Resize all buffers to match new terminal size
     pub fn resize(&mut self, width: u16, height: u16) {
         // Update terminal dimensions for future buffer creation
@@ -4485,6 +5092,9 @@ impl Editor {
                     };
                     self.apply_event_to_active_buffer(&remove_overlay_event);
                 }
+                PromptType::FileTransferConflict => {
+                    self.cancel_file_transfer();
+                }
                 PromptType::OpenFile | PromptType::SwitchProject => {
                     // Clear file browser state
                     self.file_open_state = None;
@@ -4516,6 +5126,11 @@ impl Editor {
                     | PromptType::StopLspServer
                     | PromptType::SelectTheme
                     | PromptType::SwitchToTab
+                    | PromptType::ReopenWithEncoding
+                    | PromptType::SaveWithEncoding
+                    | PromptType::SetLanguage
+                    | PromptType::GotoSymbol
+                    | PromptType::WorkspaceSymbol
             ) {
                 // Use the selected suggestion if any
                 if let Some(selected_idx) = prompt.selected_suggestion {
@@ -4583,6 +5198,14 @@ impl Editor {
         self.prompt.is_some()
     }
 
+    /// Check if the open prompt masks its input (a password). Keys typed
+    /// into it are neither logged nor recorded into macros.
+    pub fn is_input_masked(&self) -> bool {
+        self.prompt
+            .as_ref()
+            .is_some_and(|prompt| prompt.prompt_type.is_masked())
+    }
+
     /// Check if file explorer has focus
     pub fn file_explorer_is_focused(&self) -> bool {
         self.key_context == KeyContext::FileExplorer
@@ -4681,7 +5304,13 @@ impl Editor {
                     },
                 );
             }
-            PromptType::SwitchToTab | PromptType::SelectTheme | PromptType::StopLspServer => {
+            PromptType::SwitchToTab
+            | PromptType::SelectTheme
+            | PromptType::StopLspServer
+            | PromptType::ReopenWithEncoding
+            | PromptType::SaveWithEncoding
+            | PromptType::SetLanguage
+            | PromptType::GotoSymbol => {
                 // Filter suggestions using fuzzy matching
                 use crate::input::fuzzy::fuzzy_match;
 
@@ -4712,6 +5341,10 @@ impl Editor {
                     }
                 }
             }
+            PromptType::WorkspaceSymbol => {
+                // Ask the language server again; the answer replaces the suggestions
+                self.request_workspace_symbols(input);
+            }
             _ => {}
         }
     }
@@ -4733,8 +5366,13 @@ impl Editor {
 
         for message in messages {
             match message {
-                AsyncMessage::LspDiagnostics { uri, diagnostics } => {
-                    self.handle_lsp_diagnostics(uri, diagnostics);
+                AsyncMessage::LspDiagnostics {
+                    language,
+                    server,
+                    uri,
+                    diagnostics,
+                } => {
+                    self.handle_lsp_diagnostics(language, server, uri, diagnostics);
                 }
                 AsyncMessage::LspInitialized { language } => {
                     tracing::info!("LSP server initialized for language: {}", language);
@@ -4745,10 +5383,12 @@ impl Editor {
                 }
                 AsyncMessage::LspError {
                     language,
+                    server,
                     error,
                     stderr_log_path,
                 } => {
-                    tracing::error!("LSP error for {}: {}", language, error);
+                    tracing::error!("LSP error for {} ({}): {}", language, server, error);
+                    let language = format!("{}/{}", language, server);
                     self.status_message = Some(format!("LSP error ({}): {}", language, error));
 
                     // Open stderr log as read-only buffer if it exists and has content
@@ -4779,8 +5419,12 @@ impl Editor {
                         }
                     }
                 }
-                AsyncMessage::LspCompletion { request_id, items } => {
-                    if let Err(e) = self.handle_completion_response(request_id, items) {
+                AsyncMessage::LspCompletion {
+                    request_id,
+                    server,
+                    items,
+                } => {
+                    if let Err(e) = self.handle_completion_response(request_id, server, items) {
                         tracing::error!("Error handling completion response: {}", e);
                     }
                 }
@@ -4825,14 +5469,49 @@ impl Editor {
                 } => {
                     self.handle_code_actions_response(request_id, actions);
                 }
+                AsyncMessage::LspFormatting { request_id, result } => {
+                    self.handle_formatting_response(request_id, result);
+                }
+                AsyncMessage::LspFoldingRanges {
+                    request_id,
+                    uri,
+                    result,
+                } => {
+                    self.handle_folding_range_response(request_id, uri, result);
+                }
+                AsyncMessage::LspSelectionRanges {
+                    request_id,
+                    uri,
+                    result,
+                } => {
+                    self.handle_selection_range_response(request_id, uri, result);
+                }
+                AsyncMessage::LspDocumentSymbols {
+                    request_id, result, ..
+                } => {
+                    self.handle_document_symbols_response(request_id, result);
+                }
+                AsyncMessage::LspWorkspaceSymbols { request_id, result } => {
+                    self.handle_workspace_symbols_response(request_id, result);
+                }
+                AsyncMessage::LspHierarchyItems { request_id, result } => {
+                    self.handle_hierarchy_items_response(request_id, result);
+                }
                 AsyncMessage::LspPulledDiagnostics {
                     request_id: _,
+                    server,
                     uri,
                     result_id,
                     diagnostics,
                     unchanged,
                 } => {
-                    self.handle_lsp_pulled_diagnostics(uri, result_id, diagnostics, unchanged);
+                    self.handle_lsp_pulled_diagnostics(
+                        server,
+                        uri,
+                        result_id,
+                        diagnostics,
+                        unchanged,
+                    );
                 }
                 AsyncMessage::LspInlayHints {
                     request_id,
@@ -4845,11 +5524,18 @@ impl Editor {
                     self.handle_lsp_server_quiescent(language);
                 }
                 AsyncMessage::FileChanged { path } => {
+                    self.refresh_git_status();
                     self.handle_async_file_changed(path);
                 }
                 AsyncMessage::GitStatusChanged { status } => {
-                    tracing::info!("Git status changed: {}", status);
-                    // TODO: Handle git status changes
+                    self.handle_git_status_changed(status);
+                }
+                AsyncMessage::ExternalFormatFinished {
+                    buffer_id,
+                    run,
+                    result,
+                } => {
+                    self.handle_external_format_finished(buffer_id, run, result);
                 }
                 AsyncMessage::FileExplorerInitialized(view) => {
                     self.handle_file_explorer_initialized(view);
@@ -4914,8 +5600,13 @@ impl Editor {
                 } => {
                     self.handle_lsp_log_message(language, message_type, message);
                 }
-                AsyncMessage::LspStatusUpdate { language, status } => {
-                    self.handle_lsp_status_update(language, status);
+                AsyncMessage::LspStatusUpdate {
+                    language,
+                    server,
+                    root,
+                    status,
+                } => {
+                    self.handle_lsp_status_update(language, server, root, status);
                 }
                 AsyncMessage::FileOpenDirectoryLoaded(result) => {
                     self.handle_file_open_directory_loaded(result);
@@ -4977,6 +5668,16 @@ impl Editor {
         // Process pending LSP server restarts (with exponential backoff)
         self.process_pending_lsp_restarts();
 
+        // Save unformatted if the language server is too slow to format on save
+        let saved_unformatted = self.check_format_on_save_timeout();
+
+        // Redraw once background syntax parses finish, to highlight from the full tree
+        let syntax_parsed = self
+            .buffers
+            .values_mut()
+            .filter_map(|state| state.syntax_tree.as_mut())
+            .fold(false, |parsed, syntax_tree| syntax_tree.poll() | parsed);
+
         // Check and clear the plugin render request flag
         #[cfg(feature = "plugins")]
         let plugin_render = {
@@ -4994,7 +5695,11 @@ impl Editor {
         }
 
         // Trigger render if any async messages, plugin commands were processed, or plugin requested render
-        needs_render || processed_any_commands || plugin_render
+        needs_render
+            || processed_any_commands
+            || plugin_render
+            || saved_unformatted
+            || syntax_parsed
     }
 
     /// Update LSP status bar string from active progress operations
@@ -5023,10 +5728,10 @@ impl Editor {
         use crate::services::async_bridge::LspServerStatus;
 
         // Collect all server statuses
-        let mut statuses: Vec<(String, LspServerStatus)> = self
+        let mut statuses: Vec<((String, String, Option<PathBuf>), LspServerStatus)> = self
             .lsp_server_statuses
             .iter()
-            .map(|(lang, status)| (lang.clone(), *status))
+            .map(|(key, status)| (key.clone(), *status))
             .collect();
 
         if statuses.is_empty() {
@@ -5034,13 +5739,28 @@ impl Editor {
             return;
         }
 
-        // Sort by language name for consistent display
+        // Sort by language, server name and root for consistent display
         statuses.sort_by(|a, b| a.0.cmp(&b.0));
+        let servers_for = |language: &str| {
+            let mut servers: Vec<&str> = statuses
+                .iter()
+                .filter(|((lang, _, _), _)| lang == language)
+                .map(|((_, server, _), _)| server.as_str())
+                .collect();
+            servers.dedup();
+            servers.len()
+        };
+        let roots_for = |language: &str, server: &str| {
+            statuses
+                .iter()
+                .filter(|((lang, name, _), _)| lang == language && name == server)
+                .count()
+        };
 
         // Build status string
         let status_parts: Vec<String> = statuses
             .iter()
-            .map(|(lang, status)| {
+            .map(|((lang, server, root), status)| {
                 let status_str = match status {
                     LspServerStatus::Starting => "starting",
                     LspServerStatus::Initializing => "initializing",
@@ -5048,7 +5768,20 @@ impl Editor {
                     LspServerStatus::Error => "error",
                     LspServerStatus::Shutdown => "shutdown",
                 };
-                format!("{}: {}", lang, status_str)
+                // Only name the server when the language has several, and its
+                // root when it runs in several
+                let mut part = if servers_for(lang) > 1 {
+                    format!("{}/{}: {}", lang, server, status_str)
+                } else {
+                    format!("{}: {}", lang, status_str)
+                };
+                if let Some(root) = root.as_deref().filter(|_| roots_for(lang, server) > 1) {
+                    part.push_str(&format!(
+                        " @ {}",
+                        workspace_root_label(root, &self.working_dir)
+                    ));
+                }
+                part
             })
             .collect();
 
@@ -5388,6 +6121,18 @@ impl Editor {
             } => {
                 self.handle_clear_line_indicators(buffer_id, namespace);
             }
+            PluginCommand::SetFileExplorerDecoration {
+                namespace,
+                path,
+                symbol,
+                color,
+                priority,
+            } => {
+                self.handle_set_file_explorer_decoration(namespace, path, symbol, color, priority);
+            }
+            PluginCommand::ClearFileExplorerDecorations { namespace } => {
+                self.file_decorations.clear_namespace(&namespace);
+            }
 
             // ==================== Status/Prompt Commands ====================
             PluginCommand::SetStatus { message } => {
@@ -5811,6 +6556,7 @@ impl Editor {
     fn handle_completion_response(
         &mut self,
         request_id: u64,
+        server: String,
         items: Vec<lsp_types::CompletionItem>,
     ) -> io::Result<()> {
         // Check if this is the pending completion request
@@ -5822,9 +6568,34 @@ impl Editor {
             return Ok(());
         }
 
+        // Wait until every server asked has responded, then show them together
+        self.completion_responses
+            .extend(items.into_iter().map(|item| (server.clone(), item)));
+        self.completion_servers_pending = self.completion_servers_pending.saturating_sub(1);
+        if self.completion_servers_pending > 0 {
+            return Ok(());
+        }
+
         self.pending_completion_request = None;
         self.lsp_status.clear();
 
+        // Label each item with its server when more than one contributed
+        let responses = std::mem::take(&mut self.completion_responses);
+        let servers: HashSet<&str> = responses.iter().map(|(s, _)| s.as_str()).collect();
+        let label_servers = servers.len() > 1;
+        let items: Vec<lsp_types::CompletionItem> = responses
+            .into_iter()
+            .map(|(server, mut item)| {
+                if label_servers {
+                    item.detail = Some(match item.detail.take() {
+                        Some(detail) => format!("{} ({})", detail, server),
+                        None => server,
+                    });
+                }
+                item
+            })
+            .collect();
+
         if items.is_empty() {
             tracing::debug!("No completion items received");
             return Ok(());
@@ -6043,9 +6814,9 @@ impl Editor {
         let file_path = metadata.and_then(|meta| meta.file_path());
 
         if let Some(path) = file_path {
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    for handle in lsp.get_or_spawn_all(&language, Some(path)) {
                         if let Err(e) = handle.cancel_request(request_id) {
                             tracing::warn!("Failed to send LSP cancel request: {}", e);
                         } else {
@@ -6075,22 +6846,29 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
-                // Get LSP handle
+            if let Some(language) = self.language_for_path(path) {
+                // Ask every server providing completions, with one request ID
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    let handles =
+                        lsp.get_or_spawn_all_for(&language, Some(path), LspFeature::Completion);
+                    if !handles.is_empty() {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
-                        self.pending_completion_request = Some(request_id);
-                        self.lsp_status = "LSP: completion...".to_string();
+                        self.completion_responses.clear();
+                        self.completion_servers_pending = 0;
 
-                        let _ = handle.completion(
-                            request_id,
-                            uri.clone(),
-                            line as u32,
-                            character as u32,
-                        );
+                        for handle in handles {
+                            if handle
+                                .completion(request_id, uri.clone(), line as u32, character as u32)
+                                .is_ok()
+                            {
+                                self.completion_servers_pending += 1;
+                            }
+                        }
+                        if self.completion_servers_pending > 0 {
+                            self.pending_completion_request = Some(request_id);
+                            self.lsp_status = "LSP: completion...".to_string();
+                        }
                         tracing::info!(
                             "Requested completion at {}:{}:{}",
                             uri.as_str(),
@@ -6123,11 +6901,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::Definition)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_goto_definition_request = Some(request_id);
@@ -6181,11 +6960,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::Hover)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_hover_request = Some(request_id);
@@ -6237,11 +7017,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::Hover)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_hover_request = Some(request_id);
@@ -6502,11 +7283,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::References)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_references_request = Some(request_id);
@@ -6552,11 +7334,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::SignatureHelp)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_signature_help_request = Some(request_id);
@@ -6730,11 +7513,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::CodeAction)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.pending_code_actions_request = Some(request_id);
@@ -6902,6 +7686,7 @@ impl Editor {
         &mut self,
         buffer_id: BufferId,
         mut edits: Vec<lsp_types::TextEdit>,
+        description: &str,
     ) -> io::Result<usize> {
         if edits.is_empty() {
             return Ok(0);
@@ -6982,11 +7767,11 @@ impl Editor {
             changes += 1;
         }
 
-        // Create a batch event for all rename changes
+        // Create a batch event for all the changes
         if !batch_events.is_empty() {
             let batch = Event::Batch {
                 events: batch_events,
-                description: "LSP Rename".to_string(),
+                description: description.to_string(),
             };
             self.apply_rename_batch_to_buffer(buffer_id, batch)?;
         }
@@ -7023,7 +7808,8 @@ impl Editor {
                     for (uri, edits) in changes {
                         if let Ok(path) = uri_to_path(&uri) {
                             let buffer_id = self.open_file(&path)?;
-                            total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
+                            total_changes +=
+                                self.apply_lsp_text_edits(buffer_id, edits, "LSP Rename")?;
                         }
                     }
                 }
@@ -7083,7 +7869,8 @@ impl Editor {
                                 );
                             }
 
-                            total_changes += self.apply_lsp_text_edits(buffer_id, edits)?;
+                            total_changes +=
+                                self.apply_lsp_text_edits(buffer_id, edits, "LSP Rename")?;
                         }
                     }
                 }
@@ -7248,7 +8035,7 @@ impl Editor {
             }
         };
 
-        let language = match detect_language(path, &self.config.languages) {
+        let language = match self.language_for_path(path) {
             Some(l) => l,
             None => {
                 tracing::debug!(
@@ -7266,11 +8053,14 @@ impl Editor {
         );
 
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
-                if let Err(e) = client.did_change(uri, changes) {
-                    tracing::warn!("Failed to send didChange to LSP: {}", e);
-                } else {
-                    tracing::trace!("Successfully sent batched didChange to LSP");
+            let clients = lsp.get_or_spawn_all(&language, Some(path));
+            if !clients.is_empty() {
+                for client in clients {
+                    if let Err(e) = client.did_change(uri.clone(), changes.clone()) {
+                        tracing::warn!("Failed to send didChange to LSP: {}", e);
+                    } else {
+                        tracing::trace!("Successfully sent batched didChange to LSP");
+                    }
                 }
             } else {
                 tracing::warn!(
@@ -7377,11 +8167,12 @@ impl Editor {
         };
 
         if let (Some(uri), Some(path)) = (uri, file_path) {
-            // Detect language from file extension
-            if let Some(language) = detect_language(path, &self.config.languages) {
+            if let Some(language) = self.language_for_path(path) {
                 // Get LSP handle
                 if let Some(lsp) = self.lsp.as_mut() {
-                    if let Some(handle) = lsp.get_or_spawn(&language) {
+                    if let Some(handle) =
+                        lsp.get_or_spawn_for(&language, Some(path), LspFeature::Rename)
+                    {
                         let request_id = self.next_lsp_request_id;
                         self.next_lsp_request_id += 1;
                         self.lsp_status = "LSP: rename...".to_string();
@@ -7507,6 +8298,66 @@ mod tests {
         assert_eq!(editor.active_buffer(), id);
     }
 
+    #[test]
+    fn test_masked_prompt_input_not_recorded_in_macro() {
+        let config = Config::default();
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+        editor.macro_recording = Some(MacroRecordingState {
+            key: 'q',
+            actions: Vec::new(),
+        });
+
+        editor.start_prompt("Find: ".to_string(), PromptType::Search);
+        editor
+            .handle_key(KeyCode::Char('a'), KeyModifiers::NONE)
+            .unwrap();
+        assert!(!editor.is_input_masked());
+        editor.cancel_prompt();
+
+        editor.start_prompt(
+            "[sudo] password: ".to_string(),
+            PromptType::SaveAsRootPassword,
+        );
+        assert!(editor.is_input_masked());
+        editor
+            .handle_key(KeyCode::Char('s'), KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(editor.prompt_input(), Some("s"));
+
+        let recorded = &editor.macro_recording.as_ref().unwrap().actions;
+        assert!(!recorded.contains(&Action::InsertChar('s')));
+        assert!(recorded.contains(&Action::InsertChar('a')));
+    }
+
+    #[test]
+    fn test_lsp_status_per_workspace_root() {
+        use crate::services::async_bridge::LspServerStatus;
+
+        let config = Config::default();
+        let (dir_context, _temp) = test_dir_context();
+        let mut editor = Editor::new(config, 80, 24, dir_context).unwrap();
+        editor.working_dir = PathBuf::from("/work");
+
+        // Instances of one server in two roots don't overwrite each other
+        for (root, status) in [
+            ("file:///work/a", LspServerStatus::Running),
+            ("file:///work/b", LspServerStatus::Starting),
+        ] {
+            editor.handle_lsp_status_update(
+                "rust".to_string(),
+                "rust-analyzer".to_string(),
+                Some(root.parse().unwrap()),
+                status,
+            );
+        }
+        assert_eq!(
+            editor.get_lsp_status(),
+            "LSP [rust: ready @ a, rust: starting @ b]"
+        );
+        assert!(editor.is_lsp_server_ready("rust"));
+    }
+
     #[test]
     #[ignore]
     fn test_clipboard() {
@@ -851,6 +851,32 @@ impl Editor {
         }
     }
 
+    /// Handle SetFileExplorerDecoration command
+    pub(super) fn handle_set_file_explorer_decoration(
+        &mut self,
+        namespace: String,
+        path: std::path::PathBuf,
+        symbol: String,
+        color: (u8, u8, u8),
+        priority: i32,
+    ) {
+        // Tree paths are absolute; plugins may pass paths relative to the working dir
+        let path = if path.is_absolute() {
+            path
+        } else {
+            self.working_dir.join(path)
+        };
+        self.file_decorations.set(
+            namespace,
+            path,
+            crate::view::file_tree::FileDecoration {
+                symbol,
+                color: ratatui::style::Color::Rgb(color.0, color.1, color.2),
+                priority,
+            },
+        );
+    }
+
     // ==================== Status/Prompt Commands ====================
 
     /// Handle SetStatus command
@@ -988,7 +1014,7 @@ impl Editor {
             method
         );
         let error = if let Some(lsp) = self.lsp.as_mut() {
-            if let Some(handle) = lsp.get_or_spawn(&language) {
+            if let Some(handle) = lsp.get_or_spawn(&language, None) {
                 if let Err(e) = handle.send_plugin_request(request_id, method, params) {
                     Some(e)
                 } else {
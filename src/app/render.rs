@@ -134,6 +134,7 @@ impl Editor {
                     horizontal_chunks[0],
                     is_focused,
                     &files_with_unsaved_changes,
+                    &self.file_decorations,
                     &self.keybindings,
                     self.key_context,
                     &self.theme,
@@ -179,6 +180,7 @@ impl Editor {
                     // This allows plugins to transform the view (e.g., soft breaks for markdown)
                     let visible_count = split_area.height as usize;
                     let is_binary = state.buffer.is_binary();
+                    let hidden = state.folds.hidden_byte_ranges(&state.buffer);
                     let base_tokens =
                         crate::view::ui::split_rendering::SplitRenderer::build_base_tokens_for_hook(
                             &mut state.buffer,
@@ -186,6 +188,7 @@ impl Editor {
                             self.config.editor.estimated_line_length,
                             visible_count,
                             is_binary,
+                            &hidden,
                         );
                     let viewport_start = viewport_top_byte;
                     let viewport_end = base_tokens
@@ -410,7 +413,18 @@ impl Editor {
         let status_message = self.status_message.clone();
         let plugin_status_message = self.plugin_status_message.clone();
         let prompt = self.prompt.clone();
-        let lsp_status = self.lsp_status.clone();
+        let mut lsp_status = self.lsp_status.clone();
+        // Name the workspace root the active buffer's servers run in
+        if let Some(root) = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|m| m.lsp_root.as_ref())
+        {
+            if !lsp_status.is_empty() {
+                let root = workspace_root_label(root, &self.working_dir);
+                lsp_status.push_str(&format!(" @ {}", root));
+            }
+        }
         let theme = self.theme.clone();
         let keybindings_cloned = self.keybindings.clone(); // Clone the keybindings
         let chord_state_cloned = self.chord_state.clone(); // Clone the chord state
@@ -621,7 +635,7 @@ impl Editor {
                 }
                 // Get file path and detect language
                 metadata.file_path().and_then(|path| {
-                    detect_language(path, &self.config.languages).and_then(|language| {
+                    self.language_for_path(path).and_then(|language| {
                         // Check if LSP server for this language is ready
                         self.lsp.as_ref().map(|lsp| lsp.is_server_ready(&language))
                     })
@@ -647,6 +661,7 @@ impl Editor {
             .set(context_keys::COMPOSE_MODE, compose_mode)
             .set(context_keys::FILE_EXPLORER, file_explorer_visible)
             .set(context_keys::FILE_EXPLORER_FOCUSED, file_explorer_focused)
+            .set(context_keys::OUTLINE, self.outline_panel.is_some())
             .set(context_keys::MOUSE_CAPTURE, mouse_capture)
             .set(context_keys::MOUSE_HOVER, mouse_hover)
             .set(context_keys::LSP_AVAILABLE, lsp_available)
@@ -996,8 +1011,9 @@ impl Editor {
 
         // Get the server command for display
         let server_info = if let Some(lsp) = &self.lsp {
-            if let Some(config) = lsp.get_config(language) {
-                format!("{} ({})", language, config.command)
+            if let Some(servers) = lsp.get_config(language) {
+                let commands: Vec<&str> = servers.iter().map(|c| c.command.as_str()).collect();
+                format!("{} ({})", language, commands.join(", "))
             } else {
                 language.to_string()
             }
@@ -1050,6 +1066,10 @@ impl Editor {
         let Some(language) = self.pending_lsp_confirmation.take() else {
             return false;
         };
+        let path = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|meta| meta.file_path().cloned());
 
         match action {
             "allow_once" => {
@@ -1057,7 +1077,7 @@ impl Editor {
                 if let Some(lsp) = &mut self.lsp {
                     // Temporarily allow this language for spawning
                     lsp.allow_language(&language);
-                    if lsp.get_or_spawn(&language).is_some() {
+                    if lsp.get_or_spawn(&language, path.as_deref()).is_some() {
                         tracing::info!("LSP server for {} started (allowed once)", language);
                         self.set_status_message(format!("LSP server for {} started", language));
                     } else {
@@ -1074,7 +1094,7 @@ impl Editor {
                 // Spawn the LSP server and remember the preference
                 if let Some(lsp) = &mut self.lsp {
                     lsp.allow_language(&language);
-                    if lsp.get_or_spawn(&language).is_some() {
+                    if lsp.get_or_spawn(&language, path.as_deref()).is_some() {
                         tracing::info!("LSP server for {} started (always allowed)", language);
                         self.set_status_message(format!(
                             "LSP server for {} started (will auto-start in future)",
@@ -1135,14 +1155,14 @@ impl Editor {
 
         // Get the file path and verify language matches
         let path = match metadata.file_path() {
-            Some(p) => p,
+            Some(p) => p.clone(),
             None => {
                 tracing::debug!("notify_lsp_current_file_opened: no file path for buffer");
                 return;
             }
         };
 
-        let file_language = match detect_language(path, &self.config.languages) {
+        let file_language = match self.language_for_path(&path) {
             Some(l) => l,
             None => {
                 tracing::debug!(
@@ -1180,48 +1200,52 @@ impl Editor {
             return;
         };
 
-        // Send didOpen to LSP
-        if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(language) {
-                tracing::info!("Sending didOpen to newly started LSP for: {}", uri.as_str());
-                if let Err(e) = client.did_open(uri.clone(), text, file_language) {
-                    tracing::warn!("Failed to send didOpen to LSP: {}", e);
-                } else {
-                    tracing::info!("Successfully sent didOpen to LSP after confirmation");
-
-                    // Request pull diagnostics
-                    let previous_result_id = self.diagnostic_result_ids.get(uri.as_str()).cloned();
-                    let request_id = self.next_lsp_request_id;
-                    self.next_lsp_request_id += 1;
-
-                    if let Err(e) =
-                        client.document_diagnostic(request_id, uri.clone(), previous_result_id)
-                    {
-                        tracing::debug!(
-                            "Failed to request pull diagnostics (server may not support): {}",
-                            e
-                        );
-                    }
+        // Send didOpen to every server for the language
+        let Some(lsp) = &mut self.lsp else {
+            return;
+        };
+        let lsp_root = lsp.root_for(language, &path);
+        if let Some(metadata) = self.buffer_metadata.get_mut(&active_buffer) {
+            metadata.lsp_root = lsp_root;
+        }
+        let clients = lsp.get_or_spawn_all(language, Some(&path));
+        if clients.is_empty() {
+            return;
+        }
+        tracing::info!("Sending didOpen to newly started LSP for: {}", uri.as_str());
+        for client in clients {
+            if let Err(e) = client.did_open(uri.clone(), text.clone(), file_language.clone()) {
+                tracing::warn!("Failed to send didOpen to LSP: {}", e);
+                return;
+            }
+        }
+        tracing::info!("Successfully sent didOpen to LSP after confirmation");
 
-                    // Request inlay hints if enabled
-                    if self.config.editor.enable_inlay_hints {
-                        let request_id = self.next_lsp_request_id;
-                        self.next_lsp_request_id += 1;
-                        self.pending_inlay_hints_request = Some(request_id);
+        // Request pull diagnostics
+        self.request_pull_diagnostics(language, &uri);
 
-                        let last_line = line_count.saturating_sub(1) as u32;
-                        let last_char = 10000u32;
+        // Request inlay hints if enabled
+        if self.config.editor.enable_inlay_hints {
+            let request_id = self.next_lsp_request_id;
+            if let Some(client) = self
+                .lsp
+                .as_mut()
+                .and_then(|lsp| lsp.get_or_spawn_for(language, Some(&path), LspFeature::InlayHints))
+            {
+                self.next_lsp_request_id += 1;
+                self.pending_inlay_hints_request = Some(request_id);
 
-                        if let Err(e) =
-                            client.inlay_hints(request_id, uri.clone(), 0, 0, last_line, last_char)
-                        {
-                            tracing::debug!(
-                                "Failed to request inlay hints (server may not support): {}",
-                                e
-                            );
-                            self.pending_inlay_hints_request = None;
-                        }
-                    }
+                let last_line = line_count.saturating_sub(1) as u32;
+                let last_char = 10000u32;
+
+                if let Err(e) =
+                    client.inlay_hints(request_id, uri.clone(), 0, 0, last_line, last_char)
+                {
+                    tracing::debug!(
+                        "Failed to request inlay hints (server may not support): {}",
+                        e
+                    );
+                    self.pending_inlay_hints_request = None;
                 }
             }
         }
@@ -1246,7 +1270,7 @@ impl Editor {
 
         let result = {
             let lsp = self.lsp.as_mut()?;
-            lsp.try_spawn(language)
+            lsp.try_spawn(language, None)
         };
 
         match result {
@@ -1467,7 +1491,7 @@ impl Editor {
             }
         };
 
-        let language = match detect_language(path, &self.config.languages) {
+        let language = match self.language_for_path(path) {
             Some(l) => l,
             None => {
                 tracing::debug!("notify_lsp_save: no language detected for {:?}", path);
@@ -1490,12 +1514,15 @@ impl Editor {
         );
 
         if let Some(lsp) = &mut self.lsp {
-            if let Some(client) = lsp.get_or_spawn(&language) {
-                // Send didSave with the full text content
-                if let Err(e) = client.did_save(uri, Some(full_text)) {
-                    tracing::warn!("Failed to send didSave to LSP: {}", e);
-                } else {
-                    tracing::info!("Successfully sent didSave to LSP");
+            let clients = lsp.get_or_spawn_all(&language, Some(path));
+            if !clients.is_empty() {
+                // Send didSave with the full text content to every server
+                for client in clients {
+                    if let Err(e) = client.did_save(uri.clone(), Some(full_text.clone())) {
+                        tracing::warn!("Failed to send didSave to LSP: {}", e);
+                    } else {
+                        tracing::info!("Successfully sent didSave to LSP");
+                    }
                 }
             } else {
                 tracing::warn!(
@@ -2387,9 +2414,14 @@ impl Editor {
 
     /// Indent the selection or current line
     pub(super) fn indent_selection(&mut self) {
-        let tab_size = self.config.editor.tab_size;
         let estimated_line_length = self.config.editor.estimated_line_length;
-        let indent_str = " ".repeat(tab_size);
+        let state = self.active_state();
+        let tab_size = state.settings.indent_size(self.config.editor.tab_size);
+        let indent_str = if state.settings.use_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(tab_size)
+        };
 
         let state = self.active_state_mut();
         // Collect lines to indent
@@ -2462,7 +2494,10 @@ impl Editor {
 
     /// Dedent the selection or current line
     pub(super) fn dedent_selection(&mut self) {
-        let tab_size = self.config.editor.tab_size;
+        let tab_size = self
+            .active_state()
+            .settings
+            .indent_size(self.config.editor.tab_size);
         let estimated_line_length = self.config.editor.estimated_line_length;
 
         let state = self.active_state_mut();
@@ -2518,18 +2553,23 @@ impl Editor {
         let mut lines_dedented = 0;
 
         for &line_start in line_starts.iter().rev() {
-            // Check how many leading spaces the line has
+            // Check how many leading spaces (or a single leading tab) the line has
             let line_bytes = state
                 .buffer
                 .slice_bytes(line_start..buffer_len.min(line_start + tab_size + 1));
-            let spaces_to_remove = line_bytes
-                .iter()
-                .take(tab_size)
-                .take_while(|&&b| b == b' ')
-                .count();
+            let spaces_to_remove = if line_bytes.first() == Some(&b'\t') {
+                1
+            } else {
+                line_bytes
+                    .iter()
+                    .take(tab_size)
+                    .take_while(|&&b| b == b' ')
+                    .count()
+            };
 
             if spaces_to_remove > 0 {
-                let deleted_text = " ".repeat(spaces_to_remove);
+                let deleted_text =
+                    String::from_utf8_lossy(&line_bytes[..spaces_to_remove]).into_owned();
                 events.push(Event::Delete {
                     range: line_start..line_start + spaces_to_remove,
                     deleted_text,
@@ -2556,9 +2596,14 @@ impl Editor {
 
     /// Toggle comment on the current line or selection
     pub(super) fn toggle_comment(&mut self) {
-        // Determine comment prefix based on file extension
-        let comment_prefix = if let Some(metadata) = self.buffer_metadata.get(&self.active_buffer())
-        {
+        // Use the buffer language's comment prefix, else guess from the file extension
+        let language_prefix = self
+            .buffer_language(self.active_buffer())
+            .and_then(|language| self.config.languages.get(&language)?.comment_prefix.clone())
+            .map(|prefix| format!("{} ", prefix.trim_end()));
+        let comment_prefix = if let Some(prefix) = language_prefix.as_deref() {
+            prefix
+        } else if let Some(metadata) = self.buffer_metadata.get(&self.active_buffer()) {
             if let Some(path) = metadata.file_path() {
                 match path.extension().and_then(|e| e.to_str()) {
                     Some("rs") | Some("c") | Some("cpp") | Some("h") | Some("hpp") | Some("js")
@@ -2967,9 +3012,18 @@ impl Editor {
         if let Some(state) = self.macro_recording.take() {
             let action_count = state.actions.len();
             let key = state.key;
-            self.macros.insert(key, state.actions);
+            let result = self.macros.set_actions(key, state.actions);
             self.last_macro_register = Some(key);
-            self.set_status_message(format!("Macro '{}' saved ({} actions)", key, action_count));
+            match result {
+                Ok(()) => self.set_status_message(format!(
+                    "Macro '{}' saved ({} actions)",
+                    key, action_count
+                )),
+                Err(e) => self.set_status_message(format!(
+                    "Macro '{}' recorded ({} actions) but not saved to disk: {}",
+                    key, action_count, e
+                )),
+            }
         } else {
             self.set_status_message("Not recording a macro".to_string());
         }
@@ -2977,7 +3031,7 @@ impl Editor {
 
     /// Play back a recorded macro
     pub(super) fn play_macro(&mut self, key: char) {
-        if let Some(actions) = self.macros.get(&key).cloned() {
+        if let Some(actions) = self.macros.get(key).map(|m| m.actions.clone()) {
             if actions.is_empty() {
                 self.set_status_message(format!("Macro '{}' is empty", key));
                 return;
@@ -3002,6 +3056,10 @@ impl Editor {
 
     /// Record an action to the current macro (if recording)
     pub(super) fn record_macro_action(&mut self, action: &Action) {
+        // A password typed into a masked prompt must not end up in a macro
+        if self.is_input_masked() {
+            return;
+        }
         if let Some(state) = &mut self.macro_recording {
             // Don't record macro control actions themselves
             match action {
@@ -3013,7 +3071,16 @@ impl Editor {
                 | Action::ListMacros
                 | Action::PromptRecordMacro
                 | Action::PromptPlayMacro
-                | Action::PlayLastMacro => {}
+                | Action::PlayLastMacro
+                | Action::PlayNamedMacro(_)
+                | Action::PromptEditMacro
+                | Action::SaveMacroFromBuffer
+                | Action::ApplyMacroToLines(_)
+                | Action::ApplyMacroToMatches(_)
+                | Action::RepeatMacro(_)
+                | Action::PromptApplyMacroToLines
+                | Action::PromptApplyMacroToMatches
+                | Action::PromptRepeatMacro => {}
                 _ => {
                     state.actions.push(action.clone());
                 }
@@ -3021,29 +3088,29 @@ impl Editor {
         }
     }
 
-    /// Show a macro in a buffer as JSON
+    /// Show a macro in a buffer as JSON for editing (an empty one if the
+    /// register has none)
     pub(super) fn show_macro_in_buffer(&mut self, key: char) {
+        use crate::input::macros::{macro_to_json, Macro};
+
         // Get macro data and cache what we need before any mutable borrows
-        let (json, actions_len) = match self.macros.get(&key) {
-            Some(actions) => {
-                let json = match serde_json::to_string_pretty(actions) {
-                    Ok(json) => json,
-                    Err(e) => {
-                        self.set_status_message(format!("Failed to serialize macro: {}", e));
-                        return;
-                    }
-                };
-                (json, actions.len())
-            }
-            None => {
-                self.set_status_message(format!("No macro recorded for '{}'", key));
+        let empty = Macro {
+            name: None,
+            actions: Vec::new(),
+        };
+        let shown = self.macros.get(key).unwrap_or(&empty);
+        let actions_len = shown.actions.len();
+        let json = match macro_to_json(key, shown) {
+            Ok(json) => json,
+            Err(e) => {
+                self.set_status_message(format!("Failed to serialize macro: {}", e));
                 return;
             }
         };
 
         // Create header with macro info
         let content = format!(
-            "// Macro '{}' ({} actions)\n// This buffer can be saved as a .json file for persistence\n\n{}",
+            "// Macro '{}' ({} actions)\n// Edit the register, name and actions, then run \"Save Macro from Buffer\".\n// Named macros are listed in the command palette as \"Macro: <name>\".\n\n{}",
             key,
             actions_len,
             json
@@ -3080,17 +3147,77 @@ impl Editor {
             lsp_disabled_reason: Some("Virtual macro buffer".to_string()),
             read_only: false, // Allow editing for saving
             binary: false,
+            language: None,
+            lsp_root: None,
         };
         self.buffer_metadata.insert(buffer_id, metadata);
 
         // Switch to the new buffer
         self.set_active_buffer(buffer_id);
         self.set_status_message(format!(
-            "Macro '{}' shown in buffer ({} actions) - save as .json for persistence",
+            "Macro '{}' shown in buffer ({} actions) - run Save Macro from Buffer to store changes",
             key, actions_len
         ));
     }
 
+    /// Store the macro edited in the active buffer (see `show_macro_in_buffer`)
+    pub(super) fn save_macro_from_buffer(&mut self) {
+        let Some(text) = self.active_state().buffer.to_string() else {
+            self.set_status_message("Buffer is not fully loaded".to_string());
+            return;
+        };
+        let (key, edited) = match crate::input::macros::macro_from_json(&text) {
+            Ok(edited) => edited,
+            Err(e) => {
+                self.set_status_message(e);
+                return;
+            }
+        };
+
+        let action_count = edited.actions.len();
+        let result = self.macros.insert(key, edited);
+        self.sync_macro_commands();
+        match result {
+            Ok(()) => {
+                self.set_status_message(format!("Macro '{}' saved ({} actions)", key, action_count))
+            }
+            Err(e) => self.set_status_message(format!(
+                "Macro '{}' stored but not saved to disk: {}",
+                key, e
+            )),
+        }
+    }
+
+    /// Play the macro with the given name
+    pub(super) fn play_named_macro(&mut self, name: &str) {
+        match self.macros.find_named(name) {
+            Some((key, _)) => self.play_macro(key),
+            None => self.set_status_message(format!("No macro named '{}'", name)),
+        }
+    }
+
+    /// Replace the command palette entries of named macros with the current ones
+    pub(super) fn sync_macro_commands(&self) {
+        use crate::input::commands::{Command, CommandSource};
+        use crate::input::macros::MACRO_COMMAND_PREFIX;
+
+        let registry = self.command_registry.read().unwrap();
+        registry.unregister_by_prefix(MACRO_COMMAND_PREFIX);
+        for (key, m) in self.macros.iter() {
+            let Some(name) = &m.name else {
+                continue;
+            };
+            registry.register(Command {
+                name: format!("{}{}", MACRO_COMMAND_PREFIX, name),
+                description: format!("Play macro '{}' ({} actions)", key, m.actions.len()),
+                action: Action::PlayNamedMacro(name.clone()),
+                contexts: vec![KeyContext::Normal],
+                custom_contexts: vec![],
+                source: CommandSource::Builtin,
+            });
+        }
+    }
+
     /// List all recorded macros in a buffer
     pub(super) fn list_macros_in_buffer(&mut self) {
         if self.macros.is_empty() {
@@ -3100,24 +3227,24 @@ impl Editor {
 
         // Build a summary of all macros
         let mut content =
-            String::from("// Recorded Macros\n// Use ShowMacro(key) to see details\n\n");
+            String::from("// Recorded Macros\n// Use Edit Macro to see or change one\n\n");
 
-        let mut keys: Vec<char> = self.macros.keys().copied().collect();
-        keys.sort();
-
-        for key in keys {
-            if let Some(actions) = self.macros.get(&key) {
-                content.push_str(&format!("Macro '{}': {} actions\n", key, actions.len()));
+        for (key, m) in self.macros.iter() {
+            let actions = &m.actions;
+            let label = match &m.name {
+                Some(name) => format!("'{}' ({})", key, name),
+                None => format!("'{}'", key),
+            };
+            content.push_str(&format!("Macro {}: {} actions\n", label, actions.len()));
 
-                // Show first few actions as preview
-                for (i, action) in actions.iter().take(5).enumerate() {
-                    content.push_str(&format!("  {}. {:?}\n", i + 1, action));
-                }
-                if actions.len() > 5 {
-                    content.push_str(&format!("  ... and {} more actions\n", actions.len() - 5));
-                }
-                content.push('\n');
+            // Show first few actions as preview
+            for (i, action) in actions.iter().take(5).enumerate() {
+                content.push_str(&format!("  {}. {:?}\n", i + 1, action));
+            }
+            if actions.len() > 5 {
+                content.push_str(&format!("  ... and {} more actions\n", actions.len() - 5));
             }
+            content.push('\n');
         }
 
         // Create a new buffer for the macro list
@@ -3151,6 +3278,8 @@ impl Editor {
             lsp_disabled_reason: Some("Virtual macro list buffer".to_string()),
             read_only: true,
             binary: false,
+            language: None,
+            lsp_root: None,
         };
         self.buffer_metadata.insert(buffer_id, metadata);
 
@@ -0,0 +1,160 @@
+//! Expand Selection with the language server's selection ranges
+//!
+//! Buffers with a syntax tree expand selections to its nodes. Buffers without
+//! one first ask their language server for `textDocument/selectionRange` at
+//! each cursor; the expansion runs when the answer arrives, word by word if the
+//! server has nothing.
+
+use super::*;
+
+/// A selection range request waiting to expand the selections of a buffer
+#[derive(Debug)]
+pub(super) struct PendingSelectionRanges {
+    request_id: u64,
+    buffer_id: BufferId,
+}
+
+impl Editor {
+    /// Whether Expand Selection in the active buffer needs the language server:
+    /// it has no syntax tree, and the server's last answer can't expand every
+    /// cursor's selection
+    pub(super) fn needs_lsp_selection_ranges(&mut self) -> bool {
+        let state = self.active_state_mut();
+        if state.buffer.is_large_file() {
+            return false;
+        }
+        let has_tree = state
+            .syntax_tree
+            .as_mut()
+            .and_then(|syntax| syntax.tree(&state.buffer))
+            .is_some();
+        if has_tree {
+            return false;
+        }
+        state.cursors.iter().any(|(_, cursor)| {
+            let selection = cursor
+                .selection_range()
+                .unwrap_or(cursor.position..cursor.position);
+            state
+                .selection_expansion
+                .expand_with_lsp(selection)
+                .is_none()
+        })
+    }
+
+    /// Ask the language server for the selection ranges at each cursor, then
+    /// expand when the answer arrives. Expands right away if no request could
+    /// be sent.
+    pub(super) fn request_selection_ranges(&mut self) {
+        if !self.send_selection_range_request() {
+            self.apply_action_as_events(Action::ExpandSelection);
+        }
+    }
+
+    /// Returns true if a request was sent
+    fn send_selection_range_request(&mut self) -> bool {
+        let buffer_id = self.active_buffer();
+        let Some((uri, path)) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| Some((meta.file_uri()?.clone(), meta.file_path()?.clone())))
+        else {
+            return false;
+        };
+        let Some(language) = self.language_for_path(&path) else {
+            return false;
+        };
+
+        let state = self.active_state();
+        let positions: Vec<lsp_types::Position> = state
+            .cursors
+            .iter()
+            .map(|(_, cursor)| {
+                let start = cursor
+                    .selection_range()
+                    .map_or(cursor.position, |selection| selection.start);
+                let (line, character) = state.buffer.position_to_lsp_position(start);
+                lsp_types::Position::new(line as u32, character as u32)
+            })
+            .collect();
+
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self.lsp.as_mut().and_then(|lsp| {
+            lsp.get_or_spawn_for(&language, Some(&path), LspFeature::SelectionRange)
+        }) else {
+            return false;
+        };
+        if let Err(e) = handle.selection_ranges(request_id, uri, positions) {
+            tracing::debug!("Failed to request selection ranges: {}", e);
+            return false;
+        }
+
+        self.next_lsp_request_id += 1;
+        self.pending_selection_range_request = Some(PendingSelectionRanges {
+            request_id,
+            buffer_id,
+        });
+        true
+    }
+
+    /// Handle a selection range response: remember the ranges, then expand the
+    /// selections if their buffer is still active
+    pub(super) fn handle_selection_range_response(
+        &mut self,
+        request_id: u64,
+        uri: String,
+        result: Result<Vec<lsp_types::SelectionRange>, String>,
+    ) {
+        let Some(pending) = self
+            .pending_selection_range_request
+            .take_if(|pending| pending.request_id == request_id)
+        else {
+            tracing::debug!("Ignoring stale selection range response: {}", request_id);
+            return;
+        };
+
+        match result {
+            // An empty answer falls back to expanding by words
+            Ok(ranges) if !ranges.is_empty() => {
+                if let Some(state) = self
+                    .find_buffer_by_uri(&uri)
+                    .and_then(|buffer_id| self.buffers.get_mut(&buffer_id))
+                {
+                    let chains = ranges
+                        .iter()
+                        .map(|range| selection_range_chain(&state.buffer, range))
+                        .collect();
+                    state.selection_expansion.set_lsp_chains(chains);
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("No selection ranges from the language server: {}", e),
+        }
+
+        if self.active_buffer() == pending.buffer_id {
+            self.apply_action_as_events(Action::ExpandSelection);
+        }
+    }
+}
+
+/// Byte ranges of a selection range and its parents, innermost first
+fn selection_range_chain(
+    buffer: &crate::model::buffer::Buffer,
+    range: &lsp_types::SelectionRange,
+) -> Vec<Range<usize>> {
+    let mut chain = Vec::new();
+    let mut current = Some(range);
+    while let Some(range) = current {
+        let start = buffer.lsp_position_to_byte(
+            range.range.start.line as usize,
+            range.range.start.character as usize,
+        );
+        let end = buffer.lsp_position_to_byte(
+            range.range.end.line as usize,
+            range.range.end.character as usize,
+        );
+        chain.push(start..end);
+        current = range.parent.as_deref();
+    }
+    chain
+}
@@ -29,12 +29,13 @@ use std::time::Instant;
 use crate::state::EditorState;
 
 use crate::model::event::{BufferId, SplitDirection, SplitId};
+use crate::primitives::fold_ranges::FoldRange;
 use crate::services::terminal::TerminalId;
 use crate::session::{
     FileExplorerState, SearchOptions, SerializedBookmark, SerializedCursor, SerializedFileState,
-    SerializedScroll, SerializedSplitDirection, SerializedSplitNode, SerializedSplitViewState,
-    SerializedTabRef, SerializedTerminalSession, SerializedViewMode, Session,
-    SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
+    SerializedFold, SerializedScroll, SerializedSplitDirection, SerializedSplitNode,
+    SerializedSplitViewState, SerializedTabRef, SerializedTerminalSession, SerializedViewMode,
+    Session, SessionConfigOverrides, SessionError, SessionHistories, SESSION_VERSION,
 };
 use crate::state::ViewMode;
 use crate::view::split::{SplitNode, SplitViewState};
@@ -169,6 +170,7 @@ impl Editor {
             let active_buffer = active_buffers.get(split_id).copied();
             let serialized = serialize_split_view_state(
                 view_state,
+                &self.buffers,
                 &self.buffer_metadata,
                 &self.working_dir,
                 active_buffer,
@@ -860,6 +862,16 @@ impl Editor {
                         editor_state.cursors.primary_mut().sticky_column =
                             file_state.cursor.sticky_column;
                         // Note: viewport is now exclusively owned by SplitViewState (restored above)
+
+                        // Restore folds (lines past the end of a changed file are skipped)
+                        if !editor_state.buffer.is_large_file() {
+                            for fold in &file_state.folds {
+                                editor_state.folds.fold(
+                                    &editor_state.buffer,
+                                    FoldRange::new(fold.start_line, fold.end_line),
+                                );
+                            }
+                        }
                     }
                     break;
                 }
@@ -973,6 +985,7 @@ fn serialize_split_node(
 
 fn serialize_split_view_state(
     view_state: &crate::view::split::SplitViewState,
+    buffers: &HashMap<BufferId, EditorState>,
     buffer_metadata: &HashMap<BufferId, super::types::BufferMetadata>,
     working_dir: &Path,
     active_buffer: Option<BufferId>,
@@ -1019,13 +1032,23 @@ fn serialize_split_view_state(
         })
         .unwrap_or(0);
 
-    // Serialize file states - only save cursor/scroll for the ACTIVE buffer if it is a file
+    // Serialize file states - only save cursor/scroll/folds for the ACTIVE buffer if it is a file
     let mut file_states = HashMap::new();
     if let Some(active_id) = active_buffer {
         if let Some(meta) = buffer_metadata.get(&active_id) {
             if let Some(abs_path) = meta.file_path() {
                 if let Ok(rel_path) = abs_path.strip_prefix(working_dir) {
                     let primary_cursor = view_state.cursors.primary();
+                    let folds = buffers
+                        .get(&active_id)
+                        .map(|state| state.folds.folded(&state.buffer))
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|range| SerializedFold {
+                            start_line: range.start_line,
+                            end_line: range.end_line,
+                        })
+                        .collect();
 
                     file_states.insert(
                         rel_path.to_path_buf(),
@@ -1050,6 +1073,7 @@ fn serialize_split_view_state(
                                 top_view_line_offset: view_state.viewport.top_view_line_offset,
                                 left_column: view_state.viewport.left_column,
                             },
+                            folds,
                         },
                     );
                 }
@@ -0,0 +1,729 @@
+//! Document and workspace symbols
+//!
+//! Go to Symbol in File lists the active buffer's symbols in a prompt that
+//! filters them fuzzily. Go to Symbol in Workspace asks the language server
+//! for `workspace/symbol` matches of the input each time it changes. The
+//! outline panel shows the symbol tree of the active file in a split beside
+//! it, marks the symbol at the cursor, and refreshes once editing pauses.
+//!
+//! Document symbols come from the language server when one provides them, and
+//! from the buffer's syntax tree otherwise (see `primitives::outline`).
+
+use super::*;
+use crate::primitives::outline::{self, OutlineSymbol};
+use crate::primitives::text_property::TextPropertyEntry;
+use std::time::{Duration, Instant};
+
+/// Name of the outline panel's buffer
+const OUTLINE_BUFFER_NAME: &str = "*Outline*";
+
+/// Share of the width the source split keeps when the outline opens
+const OUTLINE_SPLIT_RATIO: f32 = 0.75;
+
+/// How long editing must pause before the outline is refreshed
+const OUTLINE_REFRESH_DELAY: Duration = Duration::from_millis(500);
+
+/// What a buffer's symbols were requested for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SymbolsFor {
+    Prompt,
+    Outline,
+}
+
+/// A document symbol request waiting for the language server
+#[derive(Debug)]
+pub(super) struct PendingDocumentSymbols {
+    request_id: u64,
+    buffer_id: BufferId,
+    purpose: SymbolsFor,
+}
+
+/// The outline side panel
+#[derive(Debug)]
+pub(super) struct OutlinePanel {
+    /// The panel's virtual buffer
+    buffer_id: BufferId,
+    /// The buffer whose outline is shown
+    source: Option<BufferId>,
+    symbols: Vec<OutlineSymbol>,
+    /// Shown instead of the symbols when there are none
+    message: String,
+    /// Index in the flattened outline of the symbol at the source's cursor
+    current: Option<usize>,
+    /// Source cursor position `current` was found for
+    last_cursor: Option<usize>,
+    /// When the source was edited after the symbols were loaded
+    stale_since: Option<Instant>,
+}
+
+impl Editor {
+    /// Ask for the active buffer's symbols, then prompt for one to go to
+    pub(super) fn start_goto_symbol(&mut self) {
+        self.request_document_symbols(self.active_buffer(), SymbolsFor::Prompt);
+    }
+
+    /// Go to the symbol picked in the Go to Symbol prompt
+    pub(super) fn goto_symbol(&mut self, value: &str) {
+        match value.parse::<usize>() {
+            Ok(position) => self.jump_to_byte(position),
+            Err(_) => self.set_status_message(format!("No symbol matches '{}'", value)),
+        }
+    }
+
+    /// Request a buffer's symbols from its language server, or take them from
+    /// its syntax tree when no server provides them
+    fn request_document_symbols(&mut self, buffer_id: BufferId, purpose: SymbolsFor) {
+        if !self.send_document_symbol_request(buffer_id, purpose) {
+            let result = self.syntax_outline(buffer_id);
+            self.show_document_symbols(buffer_id, purpose, result);
+        }
+    }
+
+    /// Returns true if a request was sent
+    fn send_document_symbol_request(&mut self, buffer_id: BufferId, purpose: SymbolsFor) -> bool {
+        let Some((uri, path)) = self
+            .buffer_metadata
+            .get(&buffer_id)
+            .and_then(|meta| Some((meta.file_uri()?.clone(), meta.file_path()?.clone())))
+        else {
+            return false;
+        };
+        let Some(language) = self.language_for_path(&path) else {
+            return false;
+        };
+
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self.lsp.as_mut().and_then(|lsp| {
+            lsp.get_or_spawn_for(&language, Some(&path), LspFeature::DocumentSymbol)
+        }) else {
+            return false;
+        };
+        if let Err(e) = handle.document_symbols(request_id, uri) {
+            tracing::debug!("Failed to request document symbols: {}", e);
+            return false;
+        }
+
+        self.next_lsp_request_id += 1;
+        // A newer request for the same buffer and purpose supersedes an older one
+        self.pending_document_symbols
+            .retain(|pending| pending.buffer_id != buffer_id || pending.purpose != purpose);
+        self.pending_document_symbols.push(PendingDocumentSymbols {
+            request_id,
+            buffer_id,
+            purpose,
+        });
+        true
+    }
+
+    /// Handle a document symbol response. Servers that fail or find nothing
+    /// fall back to the syntax tree.
+    pub(super) fn handle_document_symbols_response(
+        &mut self,
+        request_id: u64,
+        result: Result<lsp_types::DocumentSymbolResponse, String>,
+    ) {
+        let Some(index) = self
+            .pending_document_symbols
+            .iter()
+            .position(|pending| pending.request_id == request_id)
+        else {
+            tracing::debug!("Ignoring stale document symbol response: {}", request_id);
+            return;
+        };
+        let pending = self.pending_document_symbols.remove(index);
+
+        let from_server = result.map(|response| self.lsp_outline(pending.buffer_id, response));
+        let result = match from_server {
+            Ok(symbols) if !symbols.is_empty() => Ok(symbols),
+            from_server => {
+                if let Err(e) = &from_server {
+                    tracing::debug!("No document symbols from the language server: {}", e);
+                }
+                self.syntax_outline(pending.buffer_id).or(from_server)
+            }
+        };
+        self.show_document_symbols(pending.buffer_id, pending.purpose, result);
+    }
+
+    /// Outline of a buffer from a document symbol response
+    fn lsp_outline(
+        &self,
+        buffer_id: BufferId,
+        response: lsp_types::DocumentSymbolResponse,
+    ) -> Vec<OutlineSymbol> {
+        let Some(state) = self.buffers.get(&buffer_id) else {
+            return Vec::new();
+        };
+        let to_byte = |position: lsp_types::Position| {
+            state
+                .buffer
+                .lsp_position_to_byte(position.line as usize, position.character as usize)
+        };
+        match response {
+            lsp_types::DocumentSymbolResponse::Nested(symbols) => {
+                outline::from_document_symbols(symbols, &to_byte)
+            }
+            lsp_types::DocumentSymbolResponse::Flat(symbols) => {
+                outline::from_symbol_information(symbols, &to_byte)
+            }
+        }
+    }
+
+    /// Outline of a buffer from its syntax tree and `outline.scm` query
+    fn syntax_outline(&mut self, buffer_id: BufferId) -> Result<Vec<OutlineSymbol>, String> {
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| "Buffer not found".to_string())?;
+        let source = state
+            .buffer
+            .get_all_text()
+            .ok_or_else(|| "Buffer text is not loaded".to_string())?;
+        let syntax = state
+            .syntax_tree
+            .as_mut()
+            .ok_or_else(|| "No language server or syntax tree for this buffer".to_string())?;
+        let language = *syntax.language();
+        let tree = syntax
+            .tree(&state.buffer)
+            .ok_or_else(|| "Syntax tree is not ready yet".to_string())?;
+        let query = self.outline_queries.get(&language)?;
+        Ok(outline::from_query(query, tree, &source))
+    }
+
+    /// Show a buffer's symbols in the prompt or the outline they were requested for
+    fn show_document_symbols(
+        &mut self,
+        buffer_id: BufferId,
+        purpose: SymbolsFor,
+        result: Result<Vec<OutlineSymbol>, String>,
+    ) {
+        match purpose {
+            SymbolsFor::Prompt => self.show_symbol_prompt(buffer_id, result),
+            SymbolsFor::Outline => {
+                let Some(panel) = self
+                    .outline_panel
+                    .as_mut()
+                    .filter(|panel| panel.source == Some(buffer_id))
+                else {
+                    return;
+                };
+                match result {
+                    Ok(symbols) => {
+                        panel.symbols = symbols;
+                        panel.message = "No symbols".to_string();
+                    }
+                    Err(e) => {
+                        panel.symbols.clear();
+                        panel.message = e;
+                    }
+                }
+                panel.last_cursor = None;
+                self.update_outline_current();
+                self.render_outline_panel();
+            }
+        }
+    }
+
+    /// Prompt for one of the symbols, the one at the cursor selected
+    fn show_symbol_prompt(
+        &mut self,
+        buffer_id: BufferId,
+        result: Result<Vec<OutlineSymbol>, String>,
+    ) {
+        // The user has moved on since asking
+        if self.active_buffer() != buffer_id || self.prompt.is_some() {
+            return;
+        }
+        let symbols = match result {
+            Ok(symbols) if !symbols.is_empty() => symbols,
+            Ok(_) => {
+                self.set_status_message("No symbols found".to_string());
+                return;
+            }
+            Err(e) => {
+                self.set_status_message(format!("No symbols: {}", e));
+                return;
+            }
+        };
+
+        let state = self.active_state();
+        let suggestions = symbol_suggestions(&symbols, &state.buffer);
+        let current = outline::symbol_at(
+            &outline::flatten(&symbols),
+            state.cursors.primary().position,
+        );
+
+        self.prompt = Some(Prompt::with_suggestions(
+            "Go to symbol: ".to_string(),
+            PromptType::GotoSymbol,
+            suggestions,
+        ));
+        if let (Some(prompt), Some(current)) = (self.prompt.as_mut(), current) {
+            prompt.selected_suggestion = Some(current);
+        }
+    }
+
+    /// Open the workspace symbol prompt if the active file has a language
+    /// server that can search for symbols
+    pub(super) fn start_workspace_symbol_prompt(&mut self) {
+        if self.request_workspace_symbols(String::new()) {
+            self.prompt = Some(Prompt::with_suggestions(
+                "Go to symbol in workspace: ".to_string(),
+                PromptType::WorkspaceSymbol,
+                Vec::new(),
+            ));
+        }
+    }
+
+    /// Ask the active file's language server for the workspace symbols
+    /// matching `query`. Returns true if a request was sent.
+    pub(super) fn request_workspace_symbols(&mut self, query: String) -> bool {
+        let Some(path) = self
+            .buffer_metadata
+            .get(&self.active_buffer())
+            .and_then(|meta| meta.file_path().cloned())
+        else {
+            self.set_status_message("Workspace symbols need a file with a language server".into());
+            return false;
+        };
+        let Some(language) = self.language_for_path(&path) else {
+            self.set_status_message("No language server for this file".to_string());
+            return false;
+        };
+
+        let request_id = self.next_lsp_request_id;
+        let Some(handle) = self.lsp.as_mut().and_then(|lsp| {
+            lsp.get_or_spawn_for(&language, Some(&path), LspFeature::WorkspaceSymbol)
+        }) else {
+            self.set_status_message(format!(
+                "No {} language server provides workspace symbols",
+                language
+            ));
+            return false;
+        };
+        if let Err(e) = handle.workspace_symbols(request_id, query) {
+            tracing::debug!("Failed to request workspace symbols: {}", e);
+            return false;
+        }
+
+        self.next_lsp_request_id += 1;
+        self.pending_workspace_symbols = Some(request_id);
+        true
+    }
+
+    /// Handle a workspace symbol response: replace the prompt's suggestions
+    pub(super) fn handle_workspace_symbols_response(
+        &mut self,
+        request_id: u64,
+        result: Result<lsp_types::WorkspaceSymbolResponse, String>,
+    ) {
+        if self.pending_workspace_symbols != Some(request_id) {
+            tracing::debug!("Ignoring stale workspace symbol response: {}", request_id);
+            return;
+        }
+        self.pending_workspace_symbols = None;
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.set_status_message(format!("Workspace symbols failed: {}", e));
+                return;
+            }
+        };
+        let suggestions = workspace_symbol_suggestions(response, &self.working_dir);
+        if let Some(prompt) = self
+            .prompt
+            .as_mut()
+            .filter(|prompt| prompt.prompt_type == PromptType::WorkspaceSymbol)
+        {
+            prompt.selected_suggestion = (!suggestions.is_empty()).then_some(0);
+            prompt.suggestions = suggestions;
+        }
+    }
+
+    /// Open the file of the symbol picked in the workspace symbol prompt and
+    /// go to the symbol. The value is `path:line:character`, zero-based.
+    pub(super) fn open_workspace_symbol(&mut self, value: &str) {
+        let mut parts = value.rsplitn(3, ':');
+        let (Some(character), Some(line), Some(path)) = (parts.next(), parts.next(), parts.next())
+        else {
+            self.set_status_message(format!("No symbol matches '{}'", value));
+            return;
+        };
+        let (Ok(line), Ok(character)) = (line.parse::<usize>(), character.parse::<usize>()) else {
+            self.set_status_message(format!("No symbol matches '{}'", value));
+            return;
+        };
+
+        if let Err(e) = self.open_file(Path::new(path)) {
+            self.set_status_message(format!("Failed to open {}: {}", path, e));
+            return;
+        }
+        let position = self
+            .active_state()
+            .buffer
+            .lsp_position_to_byte(line, character);
+        self.jump_to_byte(position);
+    }
+
+    /// Move the active buffer's primary cursor to `position`
+    pub(super) fn jump_to_byte(&mut self, position: usize) {
+        let state = self.active_state();
+        let cursor = state.cursors.primary();
+        let event = Event::MoveCursor {
+            cursor_id: state.cursors.primary_id(),
+            old_position: cursor.position,
+            new_position: position.min(state.buffer.len()),
+            old_anchor: cursor.anchor,
+            new_anchor: None,
+            old_sticky_column: cursor.sticky_column,
+            new_sticky_column: 0,
+        };
+        self.active_event_log_mut().append(event.clone());
+        self.apply_event_to_active_buffer(&event);
+    }
+
+    /// Open the outline panel beside the active split, or close it
+    pub(super) fn toggle_outline(&mut self) {
+        if let Some(panel) = self.outline_panel.take() {
+            for split_id in self.split_manager.splits_for_buffer(panel.buffer_id) {
+                self.handle_close_split(split_id);
+            }
+            if let Err(e) = self.close_buffer(panel.buffer_id) {
+                tracing::warn!("Failed to close the outline: {}", e);
+            }
+            return;
+        }
+
+        let source = self.active_buffer();
+        let source_split = self.split_manager.active_split();
+        let buffer_id = self.create_virtual_buffer(
+            OUTLINE_BUFFER_NAME.to_string(),
+            "outline".to_string(),
+            true,
+        );
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            state.margins.set_line_numbers(false);
+            state.editing_disabled = true;
+        }
+        // The panel gets a split of its own, not a tab beside the source
+        if let Some(view_state) = self.split_view_states.get_mut(&source_split) {
+            view_state.remove_buffer(buffer_id);
+        }
+
+        self.save_current_split_view_state();
+        match self.split_manager.split_active(
+            SplitDirection::Vertical,
+            buffer_id,
+            OUTLINE_SPLIT_RATIO,
+        ) {
+            Ok(split_id) => {
+                let mut view_state = SplitViewState::with_buffer(
+                    self.terminal_width,
+                    self.terminal_height,
+                    buffer_id,
+                );
+                view_state.viewport.line_wrap_enabled = false;
+                self.split_view_states.insert(split_id, view_state);
+                // Keep editing the source
+                self.split_manager.set_active_split(source_split);
+            }
+            Err(e) => {
+                tracing::error!("Failed to create the outline split: {}", e);
+                let _ = self.close_buffer(buffer_id);
+                return;
+            }
+        }
+
+        self.outline_panel = Some(OutlinePanel {
+            buffer_id,
+            source: None,
+            symbols: Vec::new(),
+            message: String::new(),
+            current: None,
+            last_cursor: None,
+            stale_since: None,
+        });
+        self.show_outline_for(source);
+    }
+
+    /// Point the outline at a buffer and load its symbols
+    fn show_outline_for(&mut self, source: BufferId) {
+        let Some(panel) = self.outline_panel.as_mut() else {
+            return;
+        };
+        panel.source = Some(source);
+        panel.symbols.clear();
+        panel.message = "Loading symbols...".to_string();
+        panel.current = None;
+        panel.last_cursor = None;
+        panel.stale_since = None;
+        self.render_outline_panel();
+        self.request_document_symbols(source, SymbolsFor::Outline);
+    }
+
+    /// Note an edit of a buffer, so that its outline is refreshed
+    pub(super) fn mark_outline_stale(&mut self, buffer_id: BufferId) {
+        if let Some(panel) = self
+            .outline_panel
+            .as_mut()
+            .filter(|panel| panel.source == Some(buffer_id))
+        {
+            panel.stale_since = Some(Instant::now());
+        }
+    }
+
+    /// Keep the outline panel in step with the editor: follow the active
+    /// file, refresh the symbols once editing pauses, and mark the symbol at
+    /// the cursor. Returns true if the panel changed.
+    pub fn check_outline_panel(&mut self) -> bool {
+        let Some((panel_buffer, source, stale_since)) = self
+            .outline_panel
+            .as_ref()
+            .map(|panel| (panel.buffer_id, panel.source, panel.stale_since))
+        else {
+            return false;
+        };
+        // Closed some other way than Toggle Outline
+        if !self.buffers.contains_key(&panel_buffer) {
+            self.outline_panel = None;
+            return true;
+        }
+
+        let active = self.active_buffer();
+        let is_new_file = active != panel_buffer
+            && source != Some(active)
+            && self
+                .buffer_metadata
+                .get(&active)
+                .is_some_and(|meta| meta.file_path().is_some());
+        if is_new_file {
+            self.show_outline_for(active);
+            return true;
+        }
+
+        if let (Some(source), Some(since)) = (source, stale_since) {
+            if since.elapsed() >= OUTLINE_REFRESH_DELAY {
+                if let Some(panel) = self.outline_panel.as_mut() {
+                    panel.stale_since = None;
+                }
+                self.request_document_symbols(source, SymbolsFor::Outline);
+            }
+        }
+
+        if self.update_outline_current() {
+            self.render_outline_panel();
+            return true;
+        }
+        false
+    }
+
+    /// Find the symbol at the source's cursor. Returns true if it changed.
+    fn update_outline_current(&mut self) -> bool {
+        let Some(panel) = self.outline_panel.as_mut() else {
+            return false;
+        };
+        let Some(cursor) = panel
+            .source
+            .and_then(|source| self.buffers.get(&source))
+            .map(|state| state.cursors.primary().position)
+        else {
+            return false;
+        };
+        if panel.last_cursor == Some(cursor) {
+            return false;
+        }
+        panel.last_cursor = Some(cursor);
+
+        let current = outline::symbol_at(&outline::flatten(&panel.symbols), cursor);
+        let changed = current != panel.current;
+        panel.current = current;
+        changed
+    }
+
+    /// Fill the outline buffer: a header naming the source, then one line per
+    /// symbol, indented by depth, with the one at the cursor marked
+    fn render_outline_panel(&mut self) {
+        let Some(panel) = &self.outline_panel else {
+            return;
+        };
+        let buffer_id = panel.buffer_id;
+        let title = panel
+            .source
+            .map(|source| self.get_buffer_display_name(source))
+            .unwrap_or_default();
+
+        let mut entries = vec![TextPropertyEntry::text(format!("{}\n", title))];
+        let mut current_line_start = None;
+        let mut offset = entries[0].text.len();
+        let flat = outline::flatten(&panel.symbols);
+        if flat.is_empty() {
+            entries.push(TextPropertyEntry::text(format!("  {}\n", panel.message)));
+        }
+        for (index, (depth, symbol)) in flat.into_iter().enumerate() {
+            let marker = if panel.current == Some(index) {
+                current_line_start = Some(offset);
+                "▶ "
+            } else {
+                "  "
+            };
+            let text = format!(
+                "{}{}{}  {}\n",
+                marker,
+                "  ".repeat(depth),
+                symbol.name,
+                outline::kind_label(symbol.kind)
+            );
+            offset += text.len();
+            entries.push(
+                TextPropertyEntry::text(text)
+                    .with_property("offset", serde_json::json!(symbol.selection.start)),
+            );
+        }
+
+        if let Err(e) = self.set_virtual_buffer_content(buffer_id, entries) {
+            tracing::error!("Failed to update the outline: {}", e);
+            return;
+        }
+
+        // Scroll the marked symbol into view, unless the user is browsing the panel
+        let panel_splits = self.split_manager.splits_for_buffer(buffer_id);
+        if let Some(line_start) = current_line_start {
+            if !panel_splits.contains(&self.split_manager.active_split()) {
+                self.handle_set_buffer_cursor(buffer_id, line_start);
+            }
+        }
+    }
+
+    /// Go to the symbol on the outline panel's current line
+    pub(super) fn outline_goto(&mut self) {
+        let Some((panel_buffer, Some(source))) = self
+            .outline_panel
+            .as_ref()
+            .map(|panel| (panel.buffer_id, panel.source))
+        else {
+            return;
+        };
+        if self.active_buffer() != panel_buffer || !self.buffers.contains_key(&source) {
+            return;
+        }
+        let state = self.active_state();
+        let Some(position) = state
+            .text_properties
+            .get_at(state.cursors.primary().position)
+            .iter()
+            .find_map(|property| property.get_as::<usize>("offset"))
+        else {
+            return;
+        };
+
+        match self.split_manager.splits_for_buffer(source).first() {
+            Some(&split_id) => self.focus_split(split_id, source),
+            None => self.set_active_buffer(source),
+        }
+        self.jump_to_byte(position);
+    }
+}
+
+/// Prompt suggestions for an outline's symbols, in outline order. The value
+/// is the byte offset to go to.
+fn symbol_suggestions(
+    symbols: &[OutlineSymbol],
+    buffer: &crate::model::buffer::Buffer,
+) -> Vec<Suggestion> {
+    fn walk(
+        symbols: &[OutlineSymbol],
+        container: Option<&str>,
+        buffer: &crate::model::buffer::Buffer,
+        suggestions: &mut Vec<Suggestion>,
+    ) {
+        for symbol in symbols {
+            let kind = outline::kind_label(symbol.kind);
+            let line = buffer.get_line_number(symbol.selection.start) + 1;
+            let description = match container {
+                Some(container) => format!("{} in {} · line {}", kind, container, line),
+                None => format!("{} · line {}", kind, line),
+            };
+            suggestions.push(Suggestion {
+                text: symbol.name.clone(),
+                description: Some(description),
+                value: Some(symbol.selection.start.to_string()),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            });
+            walk(&symbol.children, Some(&symbol.name), buffer, suggestions);
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    walk(symbols, None, buffer, &mut suggestions);
+    suggestions
+}
+
+/// Prompt suggestions for a workspace symbol response, in the server's order.
+/// The value is `path:line:character` of the symbol's start.
+fn workspace_symbol_suggestions(
+    response: lsp_types::WorkspaceSymbolResponse,
+    working_dir: &Path,
+) -> Vec<Suggestion> {
+    let symbols: Vec<_> = match response {
+        lsp_types::WorkspaceSymbolResponse::Flat(symbols) => symbols
+            .into_iter()
+            .map(|symbol| {
+                (
+                    symbol.name,
+                    symbol.kind,
+                    symbol.container_name,
+                    symbol.location.uri,
+                    symbol.location.range.start,
+                )
+            })
+            .collect(),
+        lsp_types::WorkspaceSymbolResponse::Nested(symbols) => symbols
+            .into_iter()
+            .map(|symbol| {
+                let (uri, start) = match symbol.location {
+                    lsp_types::OneOf::Left(location) => (location.uri, location.range.start),
+                    lsp_types::OneOf::Right(location) => {
+                        (location.uri, lsp_types::Position::default())
+                    }
+                };
+                (symbol.name, symbol.kind, symbol.container_name, uri, start)
+            })
+            .collect(),
+    };
+
+    symbols
+        .into_iter()
+        .filter_map(|(name, kind, container, uri, start)| {
+            let path = uri_to_path(&uri).ok()?;
+            let shown = path.strip_prefix(working_dir).unwrap_or(&path);
+            let kind = outline::kind_label(kind);
+            let description = match container.filter(|container| !container.is_empty()) {
+                Some(container) => format!(
+                    "{} in {} · {}:{}",
+                    kind,
+                    container,
+                    shown.display(),
+                    start.line + 1
+                ),
+                None => format!("{} · {}:{}", kind, shown.display(), start.line + 1),
+            };
+            Some(Suggestion {
+                text: name,
+                description: Some(description),
+                value: Some(format!(
+                    "{}:{}:{}",
+                    path.display(),
+                    start.line,
+                    start.character
+                )),
+                disabled: false,
+                keybinding: None,
+                source: None,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,121 @@
+//! Structural motions and selections
+//!
+//! The Go to Next/Previous commands move each cursor to the start of the next
+//! or previous function, class, parameter or comment; the Select Inside/Around
+//! commands select the function, class, parameter or block around each cursor.
+//! Objects come from the buffer's syntax tree and its language's
+//! `textobjects.scm` query (see `primitives::text_objects`).
+
+use super::*;
+use crate::primitives::text_objects::{self, TextObject, TextObjectRange};
+
+/// What a text object command does with the objects it finds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    Next,
+    Previous,
+    Inside,
+    Around,
+}
+
+/// The object and motion of a text object command
+fn text_object_command(action: &Action) -> Option<(TextObject, Motion)> {
+    Some(match action {
+        Action::GotoNextFunction => (TextObject::Function, Motion::Next),
+        Action::GotoPreviousFunction => (TextObject::Function, Motion::Previous),
+        Action::GotoNextClass => (TextObject::Class, Motion::Next),
+        Action::GotoPreviousClass => (TextObject::Class, Motion::Previous),
+        Action::GotoNextParameter => (TextObject::Parameter, Motion::Next),
+        Action::GotoPreviousParameter => (TextObject::Parameter, Motion::Previous),
+        Action::GotoNextComment => (TextObject::Comment, Motion::Next),
+        Action::GotoPreviousComment => (TextObject::Comment, Motion::Previous),
+        Action::SelectInsideFunction => (TextObject::Function, Motion::Inside),
+        Action::SelectAroundFunction => (TextObject::Function, Motion::Around),
+        Action::SelectInsideClass => (TextObject::Class, Motion::Inside),
+        Action::SelectAroundClass => (TextObject::Class, Motion::Around),
+        Action::SelectInsideParameter => (TextObject::Parameter, Motion::Inside),
+        Action::SelectAroundParameter => (TextObject::Parameter, Motion::Around),
+        Action::SelectInsideBlock => (TextObject::Block, Motion::Inside),
+        Action::SelectAroundBlock => (TextObject::Block, Motion::Around),
+        _ => return None,
+    })
+}
+
+impl Editor {
+    /// Run a text object motion or selection on every cursor
+    pub(super) fn run_text_object_command(&mut self, action: Action) {
+        let Some((object, motion)) = text_object_command(&action) else {
+            return;
+        };
+        let objects = match self.active_text_objects(object) {
+            Ok(objects) => objects,
+            Err(message) => {
+                self.set_status_message(message);
+                return;
+            }
+        };
+
+        let state = self.active_state();
+        let events: Vec<Event> = state
+            .cursors
+            .iter()
+            .filter_map(|(cursor_id, cursor)| {
+                let (new_anchor, new_position) = match motion {
+                    Motion::Next => (None, text_objects::next_start(&objects, cursor.position)?),
+                    Motion::Previous => (
+                        None,
+                        text_objects::previous_start(&objects, cursor.position)?,
+                    ),
+                    Motion::Inside | Motion::Around => {
+                        let selection = cursor
+                            .selection_range()
+                            .unwrap_or(cursor.position..cursor.position);
+                        let range =
+                            text_objects::select(&objects, selection, motion == Motion::Around)?;
+                        ((!range.is_empty()).then_some(range.start), range.end)
+                    }
+                };
+                Some(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position,
+                    old_anchor: cursor.anchor,
+                    new_anchor,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0,
+                })
+            })
+            .collect();
+
+        if events.is_empty() {
+            self.set_status_message(format!("No {} found", object.capture_name()));
+            return;
+        }
+        for event in events {
+            self.apply_event_to_active_buffer(&event);
+        }
+    }
+
+    /// Text objects of one kind in the active buffer
+    fn active_text_objects(&mut self, object: TextObject) -> Result<Vec<TextObjectRange>, String> {
+        let buffer_id = self.active_buffer();
+        let state = self
+            .buffers
+            .get_mut(&buffer_id)
+            .ok_or_else(|| "No active buffer".to_string())?;
+        let source = state
+            .buffer
+            .get_all_text()
+            .ok_or_else(|| "Buffer text is not loaded".to_string())?;
+        let syntax = state
+            .syntax_tree
+            .as_mut()
+            .ok_or_else(|| "No syntax tree for this buffer".to_string())?;
+        let language = *syntax.language();
+        let tree = syntax
+            .tree(&state.buffer)
+            .ok_or_else(|| "Syntax tree is not ready yet".to_string())?;
+        let query = self.text_object_queries.get(&language)?;
+        Ok(text_objects::find_objects(query, tree, &source, object))
+    }
+}
@@ -2,8 +2,9 @@ use crate::app::file_open::SortMode;
 use crate::input::keybindings::Action;
 use crate::model::event::{BufferId, SplitDirection, SplitId};
 use crate::services::async_bridge::LspMessageType;
+use crate::services::file_transfer::TransferMode;
 use ratatui::layout::Rect;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 
@@ -101,6 +102,13 @@ pub struct BufferMetadata {
     /// Whether the buffer contains binary content
     /// Binary buffers are automatically read-only and render unprintable chars as code points
     pub binary: bool,
+
+    /// Language of the buffer (a key of the config's `languages`), detected when
+    /// the file is opened or chosen with Set Language
+    pub language: Option<String>,
+
+    /// Workspace root of the language server the buffer is attached to
+    pub lsp_root: Option<PathBuf>,
 }
 
 impl BufferMetadata {
@@ -147,6 +155,8 @@ impl BufferMetadata {
             lsp_disabled_reason: None,
             read_only: false,
             binary: false,
+            language: None,
+            lsp_root: None,
         }
     }
 
@@ -175,6 +185,8 @@ impl BufferMetadata {
             lsp_disabled_reason: None,
             read_only: false,
             binary: false,
+            language: None,
+            lsp_root: None,
         }
     }
 
@@ -222,6 +234,8 @@ impl BufferMetadata {
             lsp_disabled_reason: Some("Virtual buffer".to_string()),
             read_only,
             binary: false,
+            language: None,
+            lsp_root: None,
         }
     }
 
@@ -241,6 +255,40 @@ pub(super) struct MacroRecordingState {
     pub actions: Vec<Action>,
 }
 
+/// Files and directories copied or cut in the file explorer, to be pasted
+#[derive(Debug, Clone)]
+pub(super) struct FileClipboard {
+    pub paths: Vec<PathBuf>,
+    /// `Move` for cut
+    pub mode: TransferMode,
+}
+
+/// How a name conflict in a file transfer is resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ConflictChoice {
+    Overwrite,
+    KeepBoth,
+    Skip,
+}
+
+/// A paste or drag-and-drop in progress. It pauses on a name conflict until
+/// the user chooses what to do.
+#[derive(Debug)]
+pub(super) struct FileTransferState {
+    pub mode: TransferMode,
+    pub target_dir: PathBuf,
+    /// Sources not transferred yet, the next one first
+    pub remaining: VecDeque<PathBuf>,
+    /// Directories the sources came from, refreshed afterwards after a move
+    pub source_dirs: Vec<PathBuf>,
+    /// Destinations of the sources transferred so far
+    pub done: Vec<PathBuf>,
+    /// Sources that failed, with the error
+    pub errors: Vec<String>,
+    /// Choice for all remaining conflicts, once the user asked for one
+    pub conflict_choice: Option<ConflictChoice>,
+}
+
 /// LSP progress information
 #[derive(Debug, Clone)]
 pub(super) struct LspProgressInfo {
@@ -337,6 +385,10 @@ pub(super) struct MouseState {
     pub drag_selection_split: Option<SplitId>,
     /// The buffer byte position where the selection anchor is
     pub drag_selection_anchor: Option<usize>,
+    /// Modifier keys held when the left button was last pressed
+    pub click_modifiers: Option<crossterm::event::KeyModifiers>,
+    /// Paths being dragged in the file explorer
+    pub dragging_explorer_paths: Option<Vec<PathBuf>>,
 }
 
 /// Mapping from visual row to buffer positions for mouse click handling
@@ -0,0 +1,364 @@
+//! Undo tree navigation and visualization
+//!
+//! The per-buffer `EventLog` keeps every branch of edit history. This module
+//! provides the editor-level operations on top of it: chronological
+//! navigation across branches, switching the branch redo follows, jumping to a
+//! state by number or by time, and a virtual buffer listing the whole tree.
+
+use super::*;
+use crate::model::event::UndoNodeId;
+
+/// Prefix of the undo tree visualizer buffer name
+const UNDO_TREE_BUFFER_PREFIX: &str = "*Undo Tree";
+
+/// Maximum characters of inserted/deleted text shown per node in the visualizer
+const MAX_DIFF_PREVIEW_CHARS: usize = 40;
+
+/// Where the user asked to go in the undo tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum UndoTarget {
+    /// A state number as shown in the visualizer (0 is the original state)
+    State(usize),
+    /// The state as it was this many milliseconds ago
+    Ago(u64),
+    /// The state this many milliseconds after the current one
+    Later(u64),
+}
+
+/// Parse a duration like "30s", "5m", "2h" or "1d" into milliseconds.
+/// A bare number is taken as minutes.
+fn parse_duration_ms(input: &str) -> Option<u64> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(split);
+    let number: u64 = number.parse().ok()?;
+    let unit_ms = match unit.trim() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1_000,
+        "" | "m" | "min" | "mins" | "minute" | "minutes" => 60_000,
+        "h" | "hour" | "hours" => 3_600_000,
+        "d" | "day" | "days" => 86_400_000,
+        _ => return None,
+    };
+    Some(number * unit_ms)
+}
+
+/// Parse the "go to state" prompt input.
+///
+/// Accepts `#12` (a state number), `5m` / `5m ago` (time travel back from now)
+/// and `+5m` (forward from the current state).
+fn parse_undo_target(input: &str) -> Option<UndoTarget> {
+    let input = input.trim();
+    if let Some(number) = input.strip_prefix('#') {
+        return number.trim().parse().ok().map(UndoTarget::State);
+    }
+    if let Some(duration) = input.strip_prefix('+') {
+        return parse_duration_ms(duration).map(UndoTarget::Later);
+    }
+    let duration = input.strip_suffix("ago").unwrap_or(input);
+    parse_duration_ms(duration.trim_start_matches('-')).map(UndoTarget::Ago)
+}
+
+/// State number shown to the user for a node (0 is the original state)
+fn state_number(node: Option<UndoNodeId>) -> usize {
+    node.map(|id| id + 1).unwrap_or(0)
+}
+
+/// Shorten text for a one-line diff preview
+fn preview_text(text: &str) -> String {
+    let escaped: String = text.escape_debug().collect();
+    if escaped.chars().count() > MAX_DIFF_PREVIEW_CHARS {
+        let truncated: String = escaped.chars().take(MAX_DIFF_PREVIEW_CHARS).collect();
+        format!("\"{}\"…", truncated)
+    } else {
+        format!("\"{}\"", escaped)
+    }
+}
+
+/// Describe the change an event makes, as a compact diff
+fn describe_event_diff(event: &Event) -> String {
+    match event {
+        Event::Insert { position, text, .. } => format!("+{} @{}", preview_text(text), position),
+        Event::Delete {
+            range,
+            deleted_text,
+            ..
+        } => format!("-{} @{}", preview_text(deleted_text), range.start),
+        Event::AddCursor { position, .. } => format!("add cursor @{}", position),
+        Event::RemoveCursor { position, .. } => format!("remove cursor @{}", position),
+        Event::Batch {
+            events,
+            description,
+        } => {
+            let edits: Vec<String> = events
+                .iter()
+                .filter(|e| e.modifies_buffer())
+                .map(describe_event_diff)
+                .collect();
+            match edits.len() {
+                0 => description.clone(),
+                1 => format!("{}: {}", description, edits[0]),
+                n => format!("{}: {} … ({} edits)", description, edits[0], n),
+            }
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+/// Format an event timestamp (milliseconds since epoch) as local wall-clock time
+fn format_timestamp(timestamp: u64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp as i64)
+        .map(|t| {
+            t.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+/// Render the undo tree of an event log as text for the visualizer buffer
+fn render_undo_tree(log: &EventLog) -> String {
+    let nodes = log.nodes();
+
+    // Only write actions create states; readonly events are folded into their neighbours
+    fn state_children(log: &EventLog, children: &[UndoNodeId], out: &mut Vec<UndoNodeId>) {
+        for &child in children {
+            if log.nodes()[child].entry.event.is_write_action() {
+                out.push(child);
+            } else {
+                state_children(log, &log.nodes()[child].children, out);
+            }
+        }
+    }
+
+    fn render_state(
+        log: &EventLog,
+        node: UndoNodeId,
+        depth: usize,
+        current: Option<UndoNodeId>,
+        out: &mut String,
+    ) {
+        let entry = &log.nodes()[node].entry;
+        let marker = if Some(node) == current { '>' } else { ' ' };
+        out.push_str(&format!(
+            "{}{}#{:<5} {}  {}\n",
+            marker,
+            "  ".repeat(depth + 1),
+            state_number(Some(node)),
+            format_timestamp(entry.timestamp),
+            describe_event_diff(&entry.event)
+        ));
+
+        let mut children = Vec::new();
+        state_children(log, &log.nodes()[node].children, &mut children);
+        render_branches(log, &children, depth, current, out);
+    }
+
+    fn render_branches(
+        log: &EventLog,
+        children: &[UndoNodeId],
+        depth: usize,
+        current: Option<UndoNodeId>,
+        out: &mut String,
+    ) {
+        if let [only] = children {
+            render_state(log, *only, depth, current, out);
+            return;
+        }
+        for (i, &child) in children.iter().enumerate() {
+            out.push_str(&format!(
+                " {}branch {}/{}:\n",
+                "  ".repeat(depth + 1),
+                i + 1,
+                children.len()
+            ));
+            render_state(log, child, depth + 1, current, out);
+        }
+    }
+
+    let current = log.current_state();
+    let state_count = nodes
+        .iter()
+        .filter(|n| n.entry.event.is_write_action())
+        .count();
+
+    let mut out = String::from("Undo Tree\n");
+    out.push_str("=========\n\n");
+    out.push_str("Press 'q' to close this buffer. '>' marks the current state.\n");
+    out.push_str("Use \"Undo Tree: Go to State\" with #N to jump to a state.\n\n");
+    out.push_str(&format!("{} states\n\n", state_count));
+
+    let root_marker = if current.is_none() { '>' } else { ' ' };
+    out.push_str(&format!("{}  #0     (original)\n", root_marker));
+
+    let mut children = Vec::new();
+    state_children(log, log.root_children(), &mut children);
+    render_branches(log, &children, 0, current, &mut out);
+    out
+}
+
+impl Editor {
+    /// Move the active buffer to a node of its undo tree
+    fn undo_tree_jump(&mut self, target: Option<UndoNodeId>) {
+        let events = self.active_event_log_mut().jump_to(target);
+        for event in &events {
+            self.apply_event_to_active_buffer(event);
+        }
+        self.update_modified_from_event_log();
+        self.set_status_message(format!("Undo tree: state #{}", state_number(target)));
+    }
+
+    /// Go to the state created just before the current one, across branches
+    pub fn undo_tree_earlier(&mut self) {
+        match self.active_event_log().earlier_state() {
+            Some(target) => self.undo_tree_jump(target),
+            None => self.set_status_message("Undo tree: already at original state".to_string()),
+        }
+    }
+
+    /// Go to the state created just after the current one, across branches
+    pub fn undo_tree_later(&mut self) {
+        match self.active_event_log().later_state() {
+            Some(target) => self.undo_tree_jump(Some(target)),
+            None => self.set_status_message("Undo tree: already at newest state".to_string()),
+        }
+    }
+
+    /// Cycle which branch redo follows from the current position
+    pub fn undo_tree_switch_branch(&mut self) {
+        let log = self.active_event_log_mut();
+        if log.switch_redo_branch() {
+            let (count, selected) = log.redo_branches();
+            self.set_status_message(format!(
+                "Redo will follow branch {}/{}",
+                selected + 1,
+                count
+            ));
+        } else {
+            self.set_status_message("No other branch to redo here".to_string());
+        }
+    }
+
+    /// Jump to a state given as prompt input (see `parse_undo_target`)
+    pub fn undo_tree_goto(&mut self, input: &str) {
+        let log = self.active_event_log();
+        let target = match parse_undo_target(input) {
+            Some(UndoTarget::State(0)) => None,
+            Some(UndoTarget::State(n)) if n <= log.nodes().len() => Some(n - 1),
+            Some(UndoTarget::State(n)) => {
+                self.set_status_message(format!("Undo tree: no state #{}", n));
+                return;
+            }
+            Some(UndoTarget::Ago(ms)) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as u64;
+                log.state_at_time(now.saturating_sub(ms))
+            }
+            Some(UndoTarget::Later(ms)) => {
+                let from = log
+                    .current_state()
+                    .map(|id| log.nodes()[id].entry.timestamp)
+                    .unwrap_or(0);
+                log.state_at_time(from.saturating_add(ms))
+            }
+            None => {
+                self.set_status_message(format!(
+                    "Invalid undo target: {} (use #N, 5m, 2h ago or +30s)",
+                    input
+                ));
+                return;
+            }
+        };
+        self.undo_tree_jump(target);
+    }
+
+    /// Open a virtual buffer showing the undo tree of the active buffer
+    pub fn show_undo_tree(&mut self) {
+        let source_buffer = self.active_buffer();
+        let source_name = self
+            .buffer_metadata
+            .get(&source_buffer)
+            .map(|m| m.display_name.clone())
+            .unwrap_or_default();
+        if source_name.starts_with(UNDO_TREE_BUFFER_PREFIX) {
+            return;
+        }
+
+        let content = render_undo_tree(self.active_event_log());
+        let buffer_name = format!("{}: {}*", UNDO_TREE_BUFFER_PREFIX, source_name);
+
+        // Reuse the visualizer buffer for this source buffer if it's already open
+        let existing_buffer = self
+            .buffer_metadata
+            .iter()
+            .find(|(_, m)| m.display_name == buffer_name)
+            .map(|(id, _)| *id);
+
+        let buffer_id = existing_buffer.unwrap_or_else(|| {
+            self.create_virtual_buffer(buffer_name, "special".to_string(), true)
+        });
+
+        if let Some(state) = self.buffers.get_mut(&buffer_id) {
+            let len = state.buffer.len();
+            if len > 0 {
+                state.buffer.delete_bytes(0, len);
+            }
+            state.buffer.insert(0, &content);
+            state.buffer.clear_modified();
+            state.editing_disabled = true;
+            state.margins.set_line_numbers(false);
+            state.cursors.primary_mut().position = 0;
+            state.cursors.primary_mut().anchor = None;
+        }
+
+        self.set_active_buffer(buffer_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert(position: usize, text: &str) -> Event {
+        Event::Insert {
+            position,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        }
+    }
+
+    #[test]
+    fn test_parse_undo_target() {
+        assert_eq!(parse_undo_target("#3"), Some(UndoTarget::State(3)));
+        assert_eq!(parse_undo_target("5m"), Some(UndoTarget::Ago(300_000)));
+        assert_eq!(parse_undo_target("5m ago"), Some(UndoTarget::Ago(300_000)));
+        assert_eq!(parse_undo_target("-30s"), Some(UndoTarget::Ago(30_000)));
+        assert_eq!(parse_undo_target("+2h"), Some(UndoTarget::Later(7_200_000)));
+        assert_eq!(parse_undo_target("10"), Some(UndoTarget::Ago(600_000)));
+        assert_eq!(parse_undo_target("soon"), None);
+        assert_eq!(parse_undo_target("#x"), None);
+    }
+
+    #[test]
+    fn test_render_undo_tree_shows_branches() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+
+        let text = render_undo_tree(&log);
+        assert!(text.contains("3 states"));
+        assert!(text.contains("branch 1/2:"));
+        assert!(text.contains("branch 2/2:"));
+        assert!(text.contains("#2"));
+        assert!(text.contains("+\"b\" @1"));
+        // The current state is the newest branch
+        let current_line = text.lines().find(|l| l.starts_with('>')).unwrap();
+        assert!(current_line.contains("#3"));
+        assert!(current_line.contains("+\"c\" @1"));
+    }
+}
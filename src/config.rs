@@ -1,4 +1,4 @@
-use crate::services::lsp::client::LspServerConfig;
+use crate::services::lsp::client::{LspServerConfig, LspServers};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -44,9 +44,10 @@ pub struct Config {
     #[serde(default)]
     pub languages: HashMap<String, LanguageConfig>,
 
-    /// LSP server configurations by language
+    /// LSP server configurations by language: one server, or a list of servers
+    /// whose features are combined
     #[serde(default)]
-    pub lsp: HashMap<String, LspServerConfig>,
+    pub lsp: HashMap<String, LspServers>,
 
     /// Menu bar configuration
     #[serde(default)]
@@ -72,6 +73,11 @@ pub struct EditorConfig {
     #[serde(default = "default_true")]
     pub auto_indent: bool,
 
+    /// Apply `.editorconfig` files (indentation, line endings, charset and
+    /// save-time whitespace fixes) to the files they cover
+    #[serde(default = "default_true")]
+    pub editorconfig: bool,
+
     /// Show line numbers in the gutter
     #[serde(default = "default_true")]
     pub line_numbers: bool,
@@ -131,6 +137,21 @@ pub struct EditorConfig {
     #[serde(default = "default_auto_save_interval")]
     pub auto_save_interval_secs: u32,
 
+    /// Whether to persist each file's undo history across editor restarts
+    /// When enabled, the undo tree is stored in the data directory when a file
+    /// is saved or closed, and restored on reopen if the file content is unchanged.
+    /// Default: false
+    #[serde(default = "default_false")]
+    pub persistent_undo: bool,
+
+    /// Command used by "Save as Root" to write files the user cannot write.
+    /// The file content is passed on stdin; `{path}` is replaced with the target
+    /// file and `{temp}` with a temporary file holding the content.
+    /// When the command starts with `sudo`, the password is asked in the prompt.
+    /// Default: ["sudo", "tee", "{path}"]
+    #[serde(default = "default_privileged_save_command")]
+    pub privileged_save_command: Vec<String>,
+
     /// Number of bytes to look back/forward from the viewport for syntax highlighting context.
     /// Larger values improve accuracy for multi-line constructs (strings, comments, nested blocks)
     /// but may slow down highlighting for very large files.
@@ -198,6 +219,10 @@ fn default_auto_save_interval() -> u32 {
     2 // Auto-save every 2 seconds for fast recovery
 }
 
+fn default_privileged_save_command() -> Vec<String> {
+    vec!["sudo".to_string(), "tee".to_string(), "{path}".to_string()]
+}
+
 fn default_highlight_context_bytes() -> usize {
     10_000 // 10KB context for accurate syntax highlighting
 }
@@ -210,11 +235,20 @@ fn default_double_click_time() -> u64 {
     500 // 500ms window for detecting double-clicks
 }
 
+fn default_format_on_save_timeout() -> u64 {
+    1000 // 1s for the language server to format before saving anyway
+}
+
+fn default_formatter_timeout() -> u64 {
+    5000 // 5s before an external formatter is stopped
+}
+
 impl Default for EditorConfig {
     fn default() -> Self {
         Self {
             tab_size: default_tab_size(),
             auto_indent: true,
+            editorconfig: true,
             line_numbers: true,
             relative_line_numbers: false,
             scroll_offset: default_scroll_offset(),
@@ -227,6 +261,8 @@ impl Default for EditorConfig {
             enable_inlay_hints: true,
             recovery_enabled: true,
             auto_save_interval_secs: default_auto_save_interval(),
+            persistent_undo: false,
+            privileged_save_command: default_privileged_save_command(),
             highlight_context_bytes: default_highlight_context_bytes(),
             mouse_hover_enabled: true,
             mouse_hover_delay_ms: default_mouse_hover_delay(),
@@ -349,6 +385,16 @@ pub struct LanguageConfig {
     #[serde(default)]
     pub extensions: Vec<String>,
 
+    /// File names (e.g. `Makefile`, `.bashrc`) or glob patterns (e.g. `Dockerfile.*`)
+    /// for this language. They take precedence over extensions.
+    #[serde(default)]
+    pub filenames: Vec<String>,
+
+    /// Interpreters that select this language from a file's `#!` line
+    /// (e.g. `python3` for `#!/usr/bin/env python3`)
+    #[serde(default)]
+    pub interpreters: Vec<String>,
+
     /// Tree-sitter grammar name
     #[serde(default)]
     pub grammar: String,
@@ -369,6 +415,35 @@ pub struct LanguageConfig {
     /// If specified, this grammar will be used when highlighter is "textmate"
     #[serde(default)]
     pub textmate_grammar: Option<std::path::PathBuf>,
+
+    /// Format the buffer with the language server before saving
+    #[serde(default)]
+    pub format_on_save: bool,
+
+    /// How long saving waits for the language server to format (milliseconds).
+    /// The file is saved unformatted if the server doesn't answer in time.
+    #[serde(default = "default_format_on_save_timeout")]
+    pub format_on_save_timeout_ms: u64,
+
+    /// External formatter commands, run in order in place of the language server.
+    /// Each gets the text on stdin and prints the formatted text on stdout.
+    #[serde(default)]
+    pub formatters: Vec<ExternalFormatterConfig>,
+}
+
+/// An external formatter command (e.g. `black -`, `shfmt`, `jq .`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExternalFormatterConfig {
+    /// Command to run
+    pub command: String,
+
+    /// Arguments; `{path}` is replaced with the file's path
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// How long the formatter may run before it is stopped (milliseconds)
+    #[serde(default = "default_formatter_timeout")]
+    pub timeout_ms: u64,
 }
 
 /// Preference for which syntax highlighting backend to use
@@ -617,11 +692,16 @@ impl Config {
             "rust".to_string(),
             LanguageConfig {
                 extensions: vec!["rs".to_string()],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "rust".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -629,11 +709,16 @@ impl Config {
             "javascript".to_string(),
             LanguageConfig {
                 extensions: vec!["js".to_string(), "jsx".to_string()],
+                filenames: Vec::new(),
+                interpreters: vec!["node".to_string(), "nodejs".to_string()],
                 grammar: "javascript".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -641,11 +726,16 @@ impl Config {
             "typescript".to_string(),
             LanguageConfig {
                 extensions: vec!["ts".to_string(), "tsx".to_string()],
+                filenames: Vec::new(),
+                interpreters: vec!["deno".to_string(), "ts-node".to_string()],
                 grammar: "typescript".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -653,11 +743,20 @@ impl Config {
             "python".to_string(),
             LanguageConfig {
                 extensions: vec!["py".to_string()],
+                filenames: Vec::new(),
+                interpreters: vec![
+                    "python".to_string(),
+                    "python3".to_string(),
+                    "python2".to_string(),
+                ],
                 grammar: "python".to_string(),
                 comment_prefix: Some("#".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -665,11 +764,16 @@ impl Config {
             "c".to_string(),
             LanguageConfig {
                 extensions: vec!["c".to_string(), "h".to_string()],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "c".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -684,11 +788,16 @@ impl Config {
                     "hh".to_string(),
                     "hxx".to_string(),
                 ],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "cpp".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -696,11 +805,91 @@ impl Config {
             "csharp".to_string(),
             LanguageConfig {
                 extensions: vec!["cs".to_string()],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "c_sharp".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
+            },
+        );
+
+        languages.insert(
+            "bash".to_string(),
+            LanguageConfig {
+                extensions: vec!["sh".to_string(), "bash".to_string()],
+                filenames: vec![
+                    ".bashrc".to_string(),
+                    ".bash_profile".to_string(),
+                    ".bash_aliases".to_string(),
+                    ".bash_logout".to_string(),
+                    ".profile".to_string(),
+                    ".zshrc".to_string(),
+                    ".zprofile".to_string(),
+                    "PKGBUILD".to_string(),
+                    "APKBUILD".to_string(),
+                ],
+                interpreters: vec![
+                    "sh".to_string(),
+                    "bash".to_string(),
+                    "dash".to_string(),
+                    "ksh".to_string(),
+                    "zsh".to_string(),
+                ],
+                grammar: "bash".to_string(),
+                comment_prefix: Some("#".to_string()),
+                auto_indent: true,
+                highlighter: HighlighterPreference::Auto,
+                textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
+            },
+        );
+
+        languages.insert(
+            "makefile".to_string(),
+            LanguageConfig {
+                extensions: vec!["mk".to_string(), "mak".to_string()],
+                filenames: vec![
+                    "Makefile".to_string(),
+                    "makefile".to_string(),
+                    "GNUmakefile".to_string(),
+                ],
+                interpreters: vec!["make".to_string()],
+                grammar: "make".to_string(),
+                comment_prefix: Some("#".to_string()),
+                auto_indent: true,
+                highlighter: HighlighterPreference::Auto,
+                textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
+            },
+        );
+
+        languages.insert(
+            "dockerfile".to_string(),
+            LanguageConfig {
+                extensions: vec!["dockerfile".to_string()],
+                filenames: vec![
+                    "Dockerfile".to_string(),
+                    "Dockerfile.*".to_string(),
+                    "Containerfile".to_string(),
+                ],
+                interpreters: Vec::new(),
+                grammar: "dockerfile".to_string(),
+                comment_prefix: Some("#".to_string()),
+                auto_indent: true,
+                highlighter: HighlighterPreference::Auto,
+                textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: default_format_on_save_timeout(),
+                formatters: Vec::new(),
             },
         );
 
@@ -708,7 +897,7 @@ impl Config {
     }
 
     /// Create default LSP configurations
-    fn default_lsp_config() -> HashMap<String, LspServerConfig> {
+    fn default_lsp_config() -> HashMap<String, LspServers> {
         let mut lsp = HashMap::new();
 
         // rust-analyzer (installed via rustup or package manager)
@@ -728,7 +917,20 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                settings: Some(serde_json::json!({
+                    "rust-analyzer": {
+                        "inlayHints": {
+                            "typeHints": { "enable": true },
+                            "parameterHints": { "enable": true },
+                            "chainingHints": { "enable": true },
+                            "closureReturnTypeHints": { "enable": "always" }
+                        }
+                    }
+                })),
+                root_markers: vec!["Cargo.toml".to_string()],
+                ..Default::default()
+            }
+            .into(),
         );
 
         // pylsp (installed via pip)
@@ -741,7 +943,14 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                root_markers: vec![
+                    "pyproject.toml".to_string(),
+                    "setup.py".to_string(),
+                    "setup.cfg".to_string(),
+                ],
+                ..Default::default()
+            }
+            .into(),
         );
 
         // typescript-language-server (installed via npm)
@@ -753,9 +962,15 @@ impl Config {
             auto_start: false,
             process_limits: crate::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
+            root_markers: vec![
+                "tsconfig.json".to_string(),
+                "jsconfig.json".to_string(),
+                "package.json".to_string(),
+            ],
+            ..Default::default()
         };
-        lsp.insert("javascript".to_string(), ts_lsp.clone());
-        lsp.insert("typescript".to_string(), ts_lsp);
+        lsp.insert("javascript".to_string(), ts_lsp.clone().into());
+        lsp.insert("typescript".to_string(), ts_lsp.into());
 
         // vscode-html-languageserver-bin (installed via npm)
         lsp.insert(
@@ -767,7 +982,9 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                ..Default::default()
+            }
+            .into(),
         );
 
         // vscode-css-languageserver-bin (installed via npm)
@@ -780,7 +997,9 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                ..Default::default()
+            }
+            .into(),
         );
 
         // clangd (installed via package manager)
@@ -793,7 +1012,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                root_markers: vec!["compile_commands.json".to_string(), ".clangd".to_string()],
+                ..Default::default()
+            }
+            .into(),
         );
         lsp.insert(
             "cpp".to_string(),
@@ -804,7 +1026,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                root_markers: vec!["compile_commands.json".to_string(), ".clangd".to_string()],
+                ..Default::default()
+            }
+            .into(),
         );
 
         // gopls (installed via go install)
@@ -817,7 +1042,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                root_markers: vec!["go.work".to_string(), "go.mod".to_string()],
+                ..Default::default()
+            }
+            .into(),
         );
 
         // vscode-json-languageserver (installed via npm)
@@ -830,7 +1058,9 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                ..Default::default()
+            }
+            .into(),
         );
 
         // csharp-language-server (installed via dotnet tool install -g csharp-ls)
@@ -843,7 +1073,10 @@ impl Config {
                 auto_start: false,
                 process_limits: crate::services::process_limits::ProcessLimits::default(),
                 initialization_options: None,
-            },
+                root_markers: vec!["*.sln".to_string(), "*.csproj".to_string()],
+                ..Default::default()
+            }
+            .into(),
         );
 
         lsp
@@ -1022,6 +1255,13 @@ impl Config {
                         when: None,
                         checkbox: Some(crate::view::ui::context_keys::FILE_EXPLORER.to_string()),
                     },
+                    MenuItem::Action {
+                        label: "Outline".to_string(),
+                        action: "toggle_outline".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: Some(crate::view::ui::context_keys::OUTLINE.to_string()),
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Line Numbers".to_string(),
@@ -1044,6 +1284,35 @@ impl Config {
                         when: None,
                         checkbox: Some(crate::view::ui::context_keys::MOUSE_CAPTURE.to_string()),
                     },
+                    MenuItem::Separator { separator: true },
+                    MenuItem::Action {
+                        label: "Fold".to_string(),
+                        action: "fold".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Unfold".to_string(),
+                        action: "unfold".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Fold All".to_string(),
+                        action: "fold_all".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Unfold All".to_string(),
+                        action: "unfold_all".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     // Note: Compose Mode removed from menu - markdown_compose plugin provides this
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
@@ -1067,6 +1336,13 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Set Language...".to_string(),
+                        action: "set_language".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Select Theme...".to_string(),
@@ -1219,6 +1495,27 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Shrink Selection".to_string(),
+                        action: "shrink_selection".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Select Around Function".to_string(),
+                        action: "select_around_function".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Select Inside Block".to_string(),
+                        action: "select_inside_block".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Add Cursor Above".to_string(),
@@ -1275,6 +1572,49 @@ impl Config {
                         when: None,
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Go to Symbol in File...".to_string(),
+                        action: "goto_symbol".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Go to Symbol in Workspace...".to_string(),
+                        action: "goto_workspace_symbol".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Call Hierarchy".to_string(),
+                        action: "call_hierarchy".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Type Hierarchy".to_string(),
+                        action: "type_hierarchy".to_string(),
+                        args: HashMap::new(),
+                        when: Some(crate::view::ui::context_keys::LSP_AVAILABLE.to_string()),
+                        checkbox: None,
+                    },
+                    MenuItem::Separator { separator: true },
+                    MenuItem::Action {
+                        label: "Next Function".to_string(),
+                        action: "goto_next_function".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Previous Function".to_string(),
+                        action: "goto_previous_function".to_string(),
+                        args: HashMap::new(),
+                        when: None,
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Next Buffer".to_string(),
@@ -1436,6 +1776,24 @@ impl Config {
                         ),
                         checkbox: None,
                     },
+                    MenuItem::Action {
+                        label: "Delete Permanently".to_string(),
+                        action: "file_explorer_delete_permanently".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
+                    MenuItem::Action {
+                        label: "Undo Delete".to_string(),
+                        action: "file_explorer_undo_delete".to_string(),
+                        args: HashMap::new(),
+                        when: Some(
+                            crate::view::ui::context_keys::FILE_EXPLORER_FOCUSED.to_string(),
+                        ),
+                        checkbox: None,
+                    },
                     MenuItem::Separator { separator: true },
                     MenuItem::Action {
                         label: "Refresh".to_string(),
@@ -1635,6 +1993,11 @@ impl DirectoryContext {
         self.data_dir.join("recovery")
     }
 
+    /// Get the persistent undo history directory path
+    pub fn undo_history_dir(&self) -> std::path::PathBuf {
+        self.data_dir.join("undo")
+    }
+
     /// Get the sessions directory path
     pub fn sessions_dir(&self) -> std::path::PathBuf {
         self.data_dir.join("sessions")
@@ -1650,6 +2013,33 @@ impl DirectoryContext {
         self.data_dir.join("replace_history.json")
     }
 
+    /// Get the freedesktop.org home trash the file explorer deletes to:
+    /// `Trash` next to the data directory, e.g. ~/.local/share/Trash. `None`
+    /// on macOS and Windows, whose trashes work differently; deletes there
+    /// are permanent.
+    pub fn trash_dir(&self) -> Option<std::path::PathBuf> {
+        if cfg!(all(unix, not(target_os = "macos"))) {
+            Some(
+                self.data_dir
+                    .parent()
+                    .unwrap_or(&self.data_dir)
+                    .join("Trash"),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Get the saved keyboard macros file path
+    pub fn macros_path(&self) -> std::path::PathBuf {
+        self.data_dir.join("macros.json")
+    }
+
+    /// Get the trusted project configs file path
+    pub fn trusted_projects_path(&self) -> std::path::PathBuf {
+        self.data_dir.join("trusted_projects.json")
+    }
+
     /// Get the terminals root directory
     pub fn terminals_dir(&self) -> std::path::PathBuf {
         self.data_dir.join("terminals")
@@ -1676,6 +2066,11 @@ impl DirectoryContext {
         self.config_dir.join("grammars")
     }
 
+    /// Get the user tree-sitter queries directory path
+    pub fn queries_dir(&self) -> std::path::PathBuf {
+        self.config_dir.join("queries")
+    }
+
     /// Get the plugins directory path
     pub fn plugins_dir(&self) -> std::path::PathBuf {
         self.config_dir.join("plugins")
@@ -1768,7 +2163,7 @@ mod tests {
 
         // User's rust override should be present
         assert!(loaded.lsp.contains_key("rust"));
-        assert_eq!(loaded.lsp["rust"].command, "custom-rust-analyzer");
+        assert_eq!(loaded.lsp["rust"][0].command, "custom-rust-analyzer");
 
         // Default LSP servers should also be present (merged from defaults)
         assert!(
@@ -1807,4 +2202,41 @@ mod tests {
         // Should have all default languages
         assert_eq!(loaded.languages.len(), defaults.languages.len());
     }
+
+    #[test]
+    fn test_lsp_server_list() {
+        use crate::services::lsp::features::LspFeature;
+
+        let json = r#"{
+            "lsp": {
+                "python": [
+                    { "command": "pyright-langserver", "args": ["--stdio"] },
+                    {
+                        "command": "/usr/bin/ruff",
+                        "args": ["server"],
+                        "only_features": ["diagnostics", "formatting", "code_action"]
+                    }
+                ],
+                "rust": { "command": "rust-analyzer", "except_features": ["inlay_hints"] }
+            }
+        }"#;
+        let config: Config = serde_json::from_str(json).unwrap();
+
+        let python = &config.lsp["python"];
+        assert_eq!(python.len(), 2);
+        assert_eq!(python[1].display_name(), "ruff");
+        assert!(python[0].allows_feature(LspFeature::Completion));
+        assert!(!python[1].allows_feature(LspFeature::Completion));
+        assert!(python[1].allows_feature(LspFeature::Formatting));
+
+        let rust = &config.lsp["rust"];
+        assert_eq!(rust.len(), 1);
+        assert!(!rust[0].allows_feature(LspFeature::InlayHints));
+
+        // A single server is written back as an object, so project configs
+        // can still override some of its fields
+        let saved = serde_json::to_value(&config.lsp).unwrap();
+        assert!(saved["rust"].is_object());
+        assert!(saved["python"].is_array());
+    }
 }
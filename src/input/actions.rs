@@ -5,6 +5,7 @@ use crate::model::buffer::Buffer;
 use crate::model::cursor::{Position2D, SelectionMode};
 use crate::model::event::{CursorId, Event};
 use crate::primitives::display_width::{byte_offset_at_visual_column, str_width};
+use crate::primitives::selection_expansion::{expand_in_tree, shrink_in_tree};
 use crate::primitives::word_navigation::{
     find_word_end, find_word_start, find_word_start_left, find_word_start_right,
 };
@@ -65,6 +66,47 @@ fn calculate_visual_column(
     }
 }
 
+/// The line above the one containing `position`, skipping lines hidden by folds.
+/// Returns (line_start, line_content).
+fn visible_line_above(
+    buffer: &mut Buffer,
+    position: usize,
+    hidden: &[Range<usize>],
+    estimated_line_length: usize,
+) -> Option<(usize, String)> {
+    let line = buffer
+        .line_iterator(position, estimated_line_length)
+        .prev()?;
+    match hidden.iter().find(|range| range.contains(&line.0)) {
+        // Land on the fold's header line
+        Some(range) => buffer
+            .line_iterator(range.start, estimated_line_length)
+            .prev(),
+        None => Some(line),
+    }
+}
+
+/// The line below the one containing `position`, skipping lines hidden by folds.
+/// Returns (line_start, line_content).
+fn visible_line_below(
+    buffer: &mut Buffer,
+    position: usize,
+    hidden: &[Range<usize>],
+    estimated_line_length: usize,
+) -> Option<(usize, String)> {
+    let mut iter = buffer.line_iterator(position, estimated_line_length);
+    iter.next();
+    let line = iter.next()?;
+    match hidden.iter().find(|range| range.contains(&line.0)) {
+        // Land on the first line after the fold, if there is one
+        Some(range) => buffer
+            .line_iterator(range.end, estimated_line_length)
+            .next()
+            .filter(|(line_start, _)| *line_start >= range.end),
+        None => Some(line),
+    }
+}
+
 /// Convert deletion ranges to Delete events
 ///
 /// This is a common pattern used across many deletion actions.
@@ -209,7 +251,7 @@ fn max_cursor_position(buffer: &Buffer) -> usize {
 /// # Parameters
 /// * `state` - The current editor state
 /// * `action` - The action to convert
-/// * `tab_size` - Number of spaces per tab
+/// * `tab_size` - Number of spaces per indent level (unless the buffer settings override it)
 /// * `auto_indent` - Whether auto-indent is enabled
 /// * `estimated_line_length` - Estimated bytes per line for large files
 /// * `viewport_height` - Height of the viewport in lines (for PageUp/PageDown)
@@ -225,6 +267,7 @@ pub fn action_to_events(
     estimated_line_length: usize,
     viewport_height: u16,
 ) -> Option<Vec<Event>> {
+    let tab_size = state.settings.indent_size(tab_size);
     let mut events = Vec::new();
 
     match action {
@@ -358,11 +401,16 @@ pub fn action_to_events(
                                 // Calculate correct indent
                                 let correct_indent =
                                     if let Some(language) = state.highlighter.language() {
+                                        let tree = state
+                                            .syntax_tree
+                                            .as_mut()
+                                            .and_then(|syntax| syntax.tree(&state.buffer));
                                         state
                                             .indent_calculator
                                             .borrow_mut()
                                             .calculate_dedent_for_delimiter(
                                                 &state.buffer,
+                                                tree,
                                                 insert_position,
                                                 ch,
                                                 language,
@@ -388,7 +436,9 @@ pub fn action_to_events(
                                     if correct_indent > 0 {
                                         events.push(Event::Insert {
                                             position: line_start,
-                                            text: " ".repeat(correct_indent),
+                                            text: state
+                                                .settings
+                                                .indent_string(correct_indent, tab_size),
                                             cursor_id,
                                         });
                                     }
@@ -432,11 +482,16 @@ pub fn action_to_events(
                 {
                     // Calculate correct indent for the closing delimiter using tree-sitter
                     let correct_indent = if let Some(language) = state.highlighter.language() {
+                        let tree = state
+                            .syntax_tree
+                            .as_mut()
+                            .and_then(|syntax| syntax.tree(&state.buffer));
                         state
                             .indent_calculator
                             .borrow_mut()
                             .calculate_dedent_for_delimiter(
                                 &state.buffer,
+                                tree,
                                 insert_position,
                                 ch,
                                 language,
@@ -459,7 +514,7 @@ pub fn action_to_events(
                     }
 
                     // Insert correct spacing + the closing delimiter
-                    let mut text = " ".repeat(correct_indent);
+                    let mut text = state.settings.indent_string(correct_indent, tab_size);
                     text.push(ch);
                     events.push(Event::Insert {
                         position: line_start,
@@ -568,12 +623,20 @@ pub fn action_to_events(
                 if auto_indent {
                     if let Some(language) = state.highlighter.language() {
                         // Use tree-sitter-based indent when we have a highlighter
-                        if let Some(indent_spaces) = state
-                            .indent_calculator
-                            .borrow_mut()
-                            .calculate_indent(&state.buffer, indent_position, language, tab_size)
+                        let tree = state
+                            .syntax_tree
+                            .as_mut()
+                            .and_then(|syntax| syntax.tree(&state.buffer));
+                        if let Some(indent_spaces) =
+                            state.indent_calculator.borrow_mut().calculate_indent(
+                                &state.buffer,
+                                tree,
+                                indent_position,
+                                language,
+                                tab_size,
+                            )
                         {
-                            text.push_str(&" ".repeat(indent_spaces));
+                            text.push_str(&state.settings.indent_string(indent_spaces, tab_size));
                         }
                     } else {
                         // Fallback for files without syntax highlighting (e.g., .txt)
@@ -583,7 +646,7 @@ pub fn action_to_events(
                                 indent_position,
                                 tab_size,
                             );
-                        text.push_str(&" ".repeat(indent_spaces));
+                        text.push_str(&state.settings.indent_string(indent_spaces, tab_size));
                     }
                 }
 
@@ -596,7 +659,11 @@ pub fn action_to_events(
         }
 
         Action::InsertTab => {
-            let tab_str = " ".repeat(tab_size);
+            let tab_str = if state.settings.use_tabs {
+                "\t".to_string()
+            } else {
+                " ".repeat(tab_size)
+            };
             // Sort cursors by position (reverse order) to avoid position shifts
             let mut cursor_vec: Vec<_> = state.cursors.iter().collect();
             cursor_vec.sort_by_key(|(_, c)| std::cmp::Reverse(c.position));
@@ -677,6 +744,7 @@ pub fn action_to_events(
         }
 
         Action::MoveUp => {
+            let hidden = state.folds.hidden_byte_ranges(&state.buffer);
             for (cursor_id, cursor) in state.cursors.iter() {
                 // Calculate visual column first (iterator is dropped after this call)
                 let (current_visual_column, _) = calculate_visual_column(
@@ -692,12 +760,12 @@ pub fn action_to_events(
                     current_visual_column
                 };
 
-                // Now create iterator for navigation
-                let mut iter = state
-                    .buffer
-                    .line_iterator(cursor.position, estimated_line_length);
-
-                if let Some((prev_line_start, prev_line_content)) = iter.prev() {
+                if let Some((prev_line_start, prev_line_content)) = visible_line_above(
+                    &mut state.buffer,
+                    cursor.position,
+                    &hidden,
+                    estimated_line_length,
+                ) {
                     // Calculate byte offset from visual column, ensuring valid character boundary
                     let prev_line_text = prev_line_content.trim_end_matches('\n');
                     let byte_offset =
@@ -724,6 +792,7 @@ pub fn action_to_events(
         }
 
         Action::MoveDown => {
+            let hidden = state.folds.hidden_byte_ranges(&state.buffer);
             for (cursor_id, cursor) in state.cursors.iter() {
                 // Calculate visual column first (iterator is dropped after this call)
                 let (current_visual_column, _) = calculate_visual_column(
@@ -739,15 +808,12 @@ pub fn action_to_events(
                     current_visual_column
                 };
 
-                // Now create iterator for navigation
-                let mut iter = state
-                    .buffer
-                    .line_iterator(cursor.position, estimated_line_length);
-
-                // Consume current line
-                iter.next();
-
-                if let Some((next_line_start, next_line_content)) = iter.next() {
+                if let Some((next_line_start, next_line_content)) = visible_line_below(
+                    &mut state.buffer,
+                    cursor.position,
+                    &hidden,
+                    estimated_line_length,
+                ) {
                     // Calculate byte offset from visual column, ensuring valid character boundary
                     let next_line_text = next_line_content.trim_end_matches('\n');
                     let byte_offset =
@@ -1046,11 +1112,12 @@ pub fn action_to_events(
         }
 
         Action::SelectUp => {
+            let hidden = state.folds.hidden_byte_ranges(&state.buffer);
             for (cursor_id, cursor) in state.cursors.iter() {
-                let mut iter = state
+                let current_line_start = state
                     .buffer
-                    .line_iterator(cursor.position, estimated_line_length);
-                let current_line_start = iter.current_position();
+                    .line_iterator(cursor.position, estimated_line_length)
+                    .current_position();
                 let current_column = cursor.position - current_line_start;
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
 
@@ -1061,7 +1128,12 @@ pub fn action_to_events(
                     current_column
                 };
 
-                if let Some((prev_line_start, prev_line_content)) = iter.prev() {
+                if let Some((prev_line_start, prev_line_content)) = visible_line_above(
+                    &mut state.buffer,
+                    cursor.position,
+                    &hidden,
+                    estimated_line_length,
+                ) {
                     let prev_line_len = prev_line_content.trim_end_matches('\n').len();
                     let new_pos = prev_line_start + goal_column.min(prev_line_len);
 
@@ -1079,11 +1151,12 @@ pub fn action_to_events(
         }
 
         Action::SelectDown => {
+            let hidden = state.folds.hidden_byte_ranges(&state.buffer);
             for (cursor_id, cursor) in state.cursors.iter() {
-                let mut iter = state
+                let current_line_start = state
                     .buffer
-                    .line_iterator(cursor.position, estimated_line_length);
-                let current_line_start = iter.current_position();
+                    .line_iterator(cursor.position, estimated_line_length)
+                    .current_position();
                 let current_column = cursor.position - current_line_start;
                 let anchor = cursor.anchor.unwrap_or(cursor.position);
 
@@ -1094,9 +1167,12 @@ pub fn action_to_events(
                     current_column
                 };
 
-                // Skip current line, then get next line
-                iter.next();
-                if let Some((next_line_start, next_line_content)) = iter.next() {
+                if let Some((next_line_start, next_line_content)) = visible_line_below(
+                    &mut state.buffer,
+                    cursor.position,
+                    &hidden,
+                    estimated_line_length,
+                ) {
                     let next_line_len = next_line_content.trim_end_matches('\n').len();
                     let new_pos = next_line_start + goal_column.min(next_line_len);
 
@@ -1664,6 +1740,27 @@ pub fn action_to_events(
         | Action::GoToMatchingBracket
         | Action::JumpToNextError
         | Action::JumpToPreviousError
+        | Action::GotoNextFunction
+        | Action::GotoPreviousFunction
+        | Action::GotoNextClass
+        | Action::GotoPreviousClass
+        | Action::GotoNextParameter
+        | Action::GotoPreviousParameter
+        | Action::GotoNextComment
+        | Action::GotoPreviousComment
+        | Action::SelectInsideFunction
+        | Action::SelectAroundFunction
+        | Action::SelectInsideClass
+        | Action::SelectAroundClass
+        | Action::SelectInsideParameter
+        | Action::SelectAroundParameter
+        | Action::SelectInsideBlock
+        | Action::SelectAroundBlock
+        | Action::GotoSymbol
+        | Action::GotoWorkspaceSymbol
+        | Action::ToggleOutline
+        | Action::CallHierarchy
+        | Action::TypeHierarchy
         | Action::ShowKeyboardShortcuts
         | Action::SmartHome
         | Action::IndentSelection
@@ -1686,6 +1783,15 @@ pub fn action_to_events(
         | Action::PromptRecordMacro
         | Action::PromptPlayMacro
         | Action::PlayLastMacro
+        | Action::PlayNamedMacro(_)
+        | Action::PromptEditMacro
+        | Action::SaveMacroFromBuffer
+        | Action::ApplyMacroToLines(_)
+        | Action::ApplyMacroToMatches(_)
+        | Action::RepeatMacro(_)
+        | Action::PromptApplyMacroToLines
+        | Action::PromptApplyMacroToMatches
+        | Action::PromptRepeatMacro
         | Action::PromptSetBookmark
         | Action::PromptJumpToBookmark
         | Action::PromptConfirm
@@ -1727,6 +1833,11 @@ pub fn action_to_events(
         | Action::FocusEditor
         | Action::SetBackground
         | Action::SetBackgroundBlend
+        | Action::Fold
+        | Action::Unfold
+        | Action::FoldAll
+        | Action::UnfoldAll
+        | Action::FoldToLevel(_)
         | Action::FileExplorerUp
         | Action::FileExplorerDown
         | Action::FileExplorerPageUp
@@ -1738,9 +1849,18 @@ pub fn action_to_events(
         | Action::FileExplorerNewFile
         | Action::FileExplorerNewDirectory
         | Action::FileExplorerDelete
+        | Action::FileExplorerDeletePermanently
+        | Action::FileExplorerUndoDelete
         | Action::FileExplorerRename
         | Action::FileExplorerToggleHidden
         | Action::FileExplorerToggleGitignored
+        | Action::FileExplorerToggleMark
+        | Action::FileExplorerExtendMarkUp
+        | Action::FileExplorerExtendMarkDown
+        | Action::FileExplorerClearMarks
+        | Action::FileExplorerCopy
+        | Action::FileExplorerCut
+        | Action::FileExplorerPaste
         | Action::LspCompletion
         | Action::LspGotoDefinition
         | Action::LspReferences
@@ -1748,6 +1868,8 @@ pub fn action_to_events(
         | Action::LspHover
         | Action::LspSignatureHelp
         | Action::LspCodeActions
+        | Action::FormatDocument
+        | Action::FormatSelection
         | Action::LspRestart
         | Action::LspStop
         | Action::ToggleInlayHints
@@ -1778,6 +1900,15 @@ pub fn action_to_events(
         | Action::SelectKeybindingMap
         | Action::Revert
         | Action::ToggleAutoRevert
+        | Action::ReopenWithEncoding
+        | Action::SaveWithEncoding
+        | Action::SaveAsRoot
+        | Action::SetLanguage
+        | Action::UndoTreeEarlier
+        | Action::UndoTreeLater
+        | Action::UndoTreeSwitchBranch
+        | Action::UndoTreeGoto
+        | Action::ShowUndoTree
         | Action::OpenTerminal
         | Action::CloseTerminal
         | Action::FocusTerminal
@@ -1827,31 +1958,63 @@ pub fn action_to_events(
         }
 
         Action::ExpandSelection => {
-            // Expand selection for each cursor
-            for (cursor_id, cursor) in state.cursors.iter() {
-                if let Some(anchor) = cursor.anchor {
-                    // Already have a selection - expand by one word to the right
-                    // First move to the start of the next word, then to its end
-                    let next_word_start = find_word_start_right(&state.buffer, cursor.position);
-                    let new_end = find_word_end(&state.buffer, next_word_start);
+            let tree = state
+                .syntax_tree
+                .as_mut()
+                .and_then(|syntax| syntax.tree(&state.buffer));
+            if tree.is_some() || state.selection_expansion.has_lsp_chains() {
+                // Grow each selection to the enclosing syntax node
+                for (cursor_id, cursor) in state.cursors.iter() {
+                    let before = cursor
+                        .selection_range()
+                        .unwrap_or(cursor.position..cursor.position);
+                    let expanded = match tree {
+                        Some(tree) => expand_in_tree(tree, before.clone()),
+                        None => state.selection_expansion.expand_with_lsp(before.clone()),
+                    };
+                    let Some(after) = expanded else {
+                        continue;
+                    };
+                    state
+                        .selection_expansion
+                        .record(cursor_id, before, after.clone());
                     events.push(Event::MoveCursor {
                         cursor_id,
                         old_position: cursor.position,
-                        new_position: new_end,
+                        new_position: after.end,
                         old_anchor: cursor.anchor,
-                        new_anchor: Some(anchor),
+                        new_anchor: Some(after.start),
                         old_sticky_column: cursor.sticky_column,
                         new_sticky_column: 0, // Reset sticky column
                     });
-                } else {
-                    // No selection - select from cursor to end of current word
-                    let word_start = find_word_start(&state.buffer, cursor.position);
-                    let word_end = find_word_end(&state.buffer, cursor.position);
-
-                    // If cursor is on non-word char OR at the end of a word,
-                    // select from current position to end of next word
-                    let (final_start, final_end) =
-                        if word_start == word_end || cursor.position == word_end {
+                }
+            } else {
+                // Expand selection for each cursor
+                for (cursor_id, cursor) in state.cursors.iter() {
+                    if let Some(anchor) = cursor.anchor {
+                        // Already have a selection - expand by one word to the right
+                        // First move to the start of the next word, then to its end
+                        let next_word_start = find_word_start_right(&state.buffer, cursor.position);
+                        let new_end = find_word_end(&state.buffer, next_word_start);
+                        events.push(Event::MoveCursor {
+                            cursor_id,
+                            old_position: cursor.position,
+                            new_position: new_end,
+                            old_anchor: cursor.anchor,
+                            new_anchor: Some(anchor),
+                            old_sticky_column: cursor.sticky_column,
+                            new_sticky_column: 0, // Reset sticky column
+                        });
+                    } else {
+                        // No selection - select from cursor to end of current word
+                        let word_start = find_word_start(&state.buffer, cursor.position);
+                        let word_end = find_word_end(&state.buffer, cursor.position);
+
+                        // If cursor is on non-word char OR at the end of a word,
+                        // select from current position to end of next word
+                        let (final_start, final_end) = if word_start == word_end
+                            || cursor.position == word_end
+                        {
                             // Find the next word (skip non-word characters to find it)
                             let next_start = find_word_start_right(&state.buffer, cursor.position);
                             let next_end = find_word_end(&state.buffer, next_start);
@@ -1862,18 +2025,51 @@ pub fn action_to_events(
                             (cursor.position, word_end)
                         };
 
-                    events.push(Event::MoveCursor {
-                        cursor_id,
-                        old_position: cursor.position,
-                        new_position: final_end,
-                        old_anchor: cursor.anchor,
-                        new_anchor: Some(final_start),
-                        old_sticky_column: cursor.sticky_column,
-                        new_sticky_column: 0, // Reset sticky column
-                    });
+                        events.push(Event::MoveCursor {
+                            cursor_id,
+                            old_position: cursor.position,
+                            new_position: final_end,
+                            old_anchor: cursor.anchor,
+                            new_anchor: Some(final_start),
+                            old_sticky_column: cursor.sticky_column,
+                            new_sticky_column: 0, // Reset sticky column
+                        });
+                    }
                 }
             }
         }
+
+        Action::ShrinkSelection => {
+            let tree = state
+                .syntax_tree
+                .as_mut()
+                .and_then(|syntax| syntax.tree(&state.buffer));
+            for (cursor_id, cursor) in state.cursors.iter() {
+                let Some(current) = cursor.selection_range() else {
+                    continue;
+                };
+                // Undo the last expansion, or shrink to the node around the cursor
+                let shrunk = state
+                    .selection_expansion
+                    .previous(cursor_id, current.clone())
+                    .or_else(|| {
+                        tree.and_then(|tree| shrink_in_tree(tree, current, cursor.position))
+                    });
+                let Some(shrunk) = shrunk else {
+                    continue;
+                };
+                let new_anchor = (!shrunk.is_empty()).then_some(shrunk.start);
+                events.push(Event::MoveCursor {
+                    cursor_id,
+                    old_position: cursor.position,
+                    new_position: shrunk.end,
+                    old_anchor: cursor.anchor,
+                    new_anchor,
+                    old_sticky_column: cursor.sticky_column,
+                    new_sticky_column: 0, // Reset sticky column
+                });
+            }
+        }
     }
 
     Some(events)
@@ -3083,4 +3279,36 @@ mod tests {
 
         assert_eq!(state.buffer.to_string().unwrap(), "(bc)");
     }
+
+    #[test]
+    fn test_visible_lines_skip_folds() {
+        // Lines 1 and 2 are folded under line 0
+        let mut buffer = Buffer::from_str_test("a {\n  b\n  c\n}\nd\n");
+        let hidden = [4..12];
+
+        assert_eq!(
+            visible_line_below(&mut buffer, 0, &hidden, 80),
+            Some((12, "}\n".to_string()))
+        );
+        assert_eq!(
+            visible_line_above(&mut buffer, 12, &hidden, 80),
+            Some((0, "a {\n".to_string()))
+        );
+        assert_eq!(
+            visible_line_below(&mut buffer, 12, &hidden, 80),
+            Some((14, "d\n".to_string()))
+        );
+        assert_eq!(visible_line_above(&mut buffer, 0, &hidden, 80), None);
+    }
+
+    #[test]
+    fn test_visible_line_below_fold_at_end() {
+        let mut buffer = Buffer::from_str_test("a {\n  b\n}");
+        let hidden = [4..9];
+        assert_eq!(visible_line_below(&mut buffer, 0, &hidden, 80), None);
+        assert_eq!(
+            visible_line_above(&mut buffer, 9, &hidden, 80),
+            Some((0, "a {\n".to_string()))
+        );
+    }
 }
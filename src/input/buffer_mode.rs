@@ -91,6 +91,27 @@ impl ModeRegistry {
 
         registry.register(special_mode);
 
+        // Built-in "outline" mode for the outline panel
+        let outline_mode = BufferMode::new("outline")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "outline-goto")
+            .with_binding(KeyCode::Char('q'), KeyModifiers::NONE, "Toggle Outline");
+
+        registry.register(outline_mode);
+
+        // Built-in "hierarchy" mode for the call and type hierarchy explorer
+        let hierarchy_mode = BufferMode::new("hierarchy")
+            .with_parent("special")
+            .with_binding(KeyCode::Enter, KeyModifiers::NONE, "hierarchy-goto")
+            .with_binding(KeyCode::Tab, KeyModifiers::NONE, "hierarchy-toggle")
+            .with_binding(KeyCode::Right, KeyModifiers::NONE, "hierarchy-expand")
+            .with_binding(KeyCode::Left, KeyModifiers::NONE, "hierarchy-collapse")
+            .with_binding(KeyCode::Char('r'), KeyModifiers::NONE, "hierarchy-reverse")
+            .with_binding(KeyCode::Char('q'), KeyModifiers::NONE, "hierarchy-close")
+            .with_binding(KeyCode::Esc, KeyModifiers::NONE, "hierarchy-close");
+
+        registry.register(hierarchy_mode);
+
         registry
     }
 
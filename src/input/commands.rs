@@ -190,6 +190,39 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Reopen with Encoding".to_string(),
+            description: "Reload the file from disk using a different text encoding".to_string(),
+            action: Action::ReopenWithEncoding,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Set Language".to_string(),
+            description: "Override the detected language of the buffer".to_string(),
+            action: Action::SetLanguage,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Save with Encoding".to_string(),
+            description: "Save the file using a different text encoding".to_string(),
+            action: Action::SaveWithEncoding,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Save as Root".to_string(),
+            description: "Save a file you lack permission to write, using sudo or another helper"
+                .to_string(),
+            action: Action::SaveAsRoot,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Toggle Auto-Revert".to_string(),
             description: "Toggle automatic reloading when files change on disk".to_string(),
@@ -223,6 +256,46 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Undo Tree: Earlier State".to_string(),
+            description: "Go to the previous state in time, across undo branches".to_string(),
+            action: Action::UndoTreeEarlier,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Undo Tree: Later State".to_string(),
+            description: "Go to the next state in time, across undo branches".to_string(),
+            action: Action::UndoTreeLater,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Undo Tree: Switch Redo Branch".to_string(),
+            description: "Choose which undo branch redo follows from here".to_string(),
+            action: Action::UndoTreeSwitchBranch,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Undo Tree: Go to State".to_string(),
+            description: "Jump to a state by number (#N) or time (5m ago, +30s)".to_string(),
+            action: Action::UndoTreeGoto,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Undo Tree: Show".to_string(),
+            description: "Show all undo branches with the changes of each state".to_string(),
+            action: Action::ShowUndoTree,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Copy".to_string(),
             description: "Copy selection to clipboard".to_string(),
@@ -338,12 +411,21 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "Expand Selection".to_string(),
-            description: "Expand the current selection by one word".to_string(),
+            description: "Grow each selection to the enclosing syntax node".to_string(),
             action: Action::ExpandSelection,
             contexts: vec![KeyContext::Normal],
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Shrink Selection".to_string(),
+            description: "Undo the last Expand Selection, or shrink to the node at the cursor"
+                .to_string(),
+            action: Action::ShrinkSelection,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Multi-cursor
         Command {
             name: "Add Cursor Above".to_string(),
@@ -579,12 +661,28 @@ pub fn get_all_commands() -> Vec<Command> {
         },
         Command {
             name: "File Explorer: Delete".to_string(),
-            description: "Delete the selected file or directory".to_string(),
+            description: "Move the marked or selected files to the trash".to_string(),
             action: Action::FileExplorerDelete,
             contexts: vec![KeyContext::FileExplorer],
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "File Explorer: Delete Permanently".to_string(),
+            description: "Delete the marked or selected files without using the trash".to_string(),
+            action: Action::FileExplorerDeletePermanently,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Undo Delete".to_string(),
+            description: "Restore the files of the last delete from the trash".to_string(),
+            action: Action::FileExplorerUndoDelete,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "File Explorer: Rename".to_string(),
             description: "Rename the selected file or directory".to_string(),
@@ -609,6 +707,46 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "File Explorer: Toggle Mark".to_string(),
+            description: "Mark or unmark the selected file for copy and cut".to_string(),
+            action: Action::FileExplorerToggleMark,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Clear Marks".to_string(),
+            description: "Unmark all files in the file explorer".to_string(),
+            action: Action::FileExplorerClearMarks,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Copy".to_string(),
+            description: "Copy the marked or selected files".to_string(),
+            action: Action::FileExplorerCopy,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Cut".to_string(),
+            description: "Cut the marked or selected files, to move them on paste".to_string(),
+            action: Action::FileExplorerCut,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "File Explorer: Paste".to_string(),
+            description: "Paste copied or cut files into the selected directory".to_string(),
+            action: Action::FileExplorerPaste,
+            contexts: vec![KeyContext::FileExplorer],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // View
         Command {
             name: "Toggle Line Wrap".to_string(),
@@ -635,6 +773,63 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        // Code folding
+        Command {
+            name: "Fold".to_string(),
+            description: "Fold the innermost region containing the cursor".to_string(),
+            action: Action::Fold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold".to_string(),
+            description: "Unfold the folded region at the cursor".to_string(),
+            action: Action::Unfold,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold All".to_string(),
+            description: "Fold every foldable region in the buffer".to_string(),
+            action: Action::FoldAll,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Unfold All".to_string(),
+            description: "Unfold every folded region in the buffer".to_string(),
+            action: Action::UnfoldAll,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold Level 1".to_string(),
+            description: "Fold the outermost regions".to_string(),
+            action: Action::FoldToLevel(1),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold Level 2".to_string(),
+            description: "Fold the regions nested 2 deep".to_string(),
+            action: Action::FoldToLevel(2),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Fold Level 3".to_string(),
+            description: "Fold the regions nested 3 deep".to_string(),
+            action: Action::FoldToLevel(3),
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // Note: Command Palette is intentionally not in the command list
         // to avoid confusion when it's already open (use Ctrl+P or Ctrl+/ to toggle)
         // Search and replace
@@ -752,6 +947,22 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Format Document".to_string(),
+            description: "Format the current file with the language server".to_string(),
+            action: Action::FormatDocument,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Format Selection".to_string(),
+            description: "Format the selected lines with the language server".to_string(),
+            action: Action::FormatSelection,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Start/Restart LSP Server".to_string(),
             description: "Start or restart the LSP server for the current language".to_string(),
@@ -842,6 +1053,177 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        // Structural navigation and selection
+        Command {
+            name: "Go to Next Function".to_string(),
+            description: "Move to the start of the next function".to_string(),
+            action: Action::GotoNextFunction,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Previous Function".to_string(),
+            description: "Move to the start of the previous function".to_string(),
+            action: Action::GotoPreviousFunction,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Next Class".to_string(),
+            description: "Move to the start of the next class or type".to_string(),
+            action: Action::GotoNextClass,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Previous Class".to_string(),
+            description: "Move to the start of the previous class or type".to_string(),
+            action: Action::GotoPreviousClass,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Next Parameter".to_string(),
+            description: "Move to the next parameter or argument".to_string(),
+            action: Action::GotoNextParameter,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Previous Parameter".to_string(),
+            description: "Move to the previous parameter or argument".to_string(),
+            action: Action::GotoPreviousParameter,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Next Comment".to_string(),
+            description: "Move to the start of the next comment".to_string(),
+            action: Action::GotoNextComment,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Previous Comment".to_string(),
+            description: "Move to the start of the previous comment".to_string(),
+            action: Action::GotoPreviousComment,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Inside Function".to_string(),
+            description: "Select the body of the function around the cursor".to_string(),
+            action: Action::SelectInsideFunction,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Around Function".to_string(),
+            description: "Select the whole function around the cursor".to_string(),
+            action: Action::SelectAroundFunction,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Inside Class".to_string(),
+            description: "Select the body of the class around the cursor".to_string(),
+            action: Action::SelectInsideClass,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Around Class".to_string(),
+            description: "Select the whole class around the cursor".to_string(),
+            action: Action::SelectAroundClass,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Inside Argument".to_string(),
+            description: "Select the argument at the cursor".to_string(),
+            action: Action::SelectInsideParameter,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Around Argument".to_string(),
+            description: "Select the argument at the cursor and its comma".to_string(),
+            action: Action::SelectAroundParameter,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Inside Block".to_string(),
+            description: "Select the contents of the block around the cursor".to_string(),
+            action: Action::SelectInsideBlock,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Select Around Block".to_string(),
+            description: "Select the block around the cursor with its braces".to_string(),
+            action: Action::SelectAroundBlock,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        // Symbols
+        Command {
+            name: "Go to Symbol in File".to_string(),
+            description: "Pick a function, class or other symbol of this file to jump to"
+                .to_string(),
+            action: Action::GotoSymbol,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Go to Symbol in Workspace".to_string(),
+            description: "Search the language server's symbols across the project".to_string(),
+            action: Action::GotoWorkspaceSymbol,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Toggle Outline".to_string(),
+            description: "Show or hide the symbol outline of the current file".to_string(),
+            action: Action::ToggleOutline,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Call Hierarchy".to_string(),
+            description: "Explore the callers of the function under cursor".to_string(),
+            action: Action::CallHierarchy,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Show Type Hierarchy".to_string(),
+            description: "Explore the subtypes of the type under cursor".to_string(),
+            action: Action::TypeHierarchy,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         // LSP
         Command {
             name: "Rename Symbol".to_string(),
@@ -900,6 +1282,49 @@ pub fn get_all_commands() -> Vec<Command> {
             custom_contexts: vec![],
             source: CommandSource::Builtin,
         },
+        Command {
+            name: "Edit Macro".to_string(),
+            description: "Open a macro's name and actions as JSON in a buffer".to_string(),
+            action: Action::PromptEditMacro,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Save Macro from Buffer".to_string(),
+            description: "Store the macro edited in the current buffer".to_string(),
+            action: Action::SaveMacroFromBuffer,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Play Macro on Each Line".to_string(),
+            description: "Play a macro (0-9) at the start of each selected line, as one undo step"
+                .to_string(),
+            action: Action::PromptApplyMacroToLines,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Play Macro at Each Match".to_string(),
+            description: "Play a macro (0-9) at each match of the last search, as one undo step"
+                .to_string(),
+            action: Action::PromptApplyMacroToMatches,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
+        Command {
+            name: "Repeat Macro".to_string(),
+            description: "Play a macro (0-9) a number of times or until it fails, as one undo step"
+                .to_string(),
+            action: Action::PromptRepeatMacro,
+            contexts: vec![KeyContext::Normal],
+            custom_contexts: vec![],
+            source: CommandSource::Builtin,
+        },
         Command {
             name: "Set Bookmark".to_string(),
             description: "Set a bookmark at current position (0-9)".to_string(),
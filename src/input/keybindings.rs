@@ -238,6 +238,7 @@ pub enum Action {
     SelectWord,
     SelectLine,
     ExpandSelection,
+    ShrinkSelection,
 
     // Block/rectangular selection (column-wise)
     BlockSelectLeft,
@@ -283,6 +284,10 @@ pub enum Action {
     Quit,
     Revert,
     ToggleAutoRevert,
+    ReopenWithEncoding,
+    SaveWithEncoding,
+    SaveAsRoot,
+    SetLanguage,
 
     // Navigation
     GotoLine,
@@ -290,6 +295,31 @@ pub enum Action {
     JumpToNextError,
     JumpToPreviousError,
 
+    // Structural navigation and selection (tree-sitter text objects)
+    GotoNextFunction,
+    GotoPreviousFunction,
+    GotoNextClass,
+    GotoPreviousClass,
+    GotoNextParameter,
+    GotoPreviousParameter,
+    GotoNextComment,
+    GotoPreviousComment,
+    SelectInsideFunction,
+    SelectAroundFunction,
+    SelectInsideClass,
+    SelectAroundClass,
+    SelectInsideParameter,
+    SelectAroundParameter,
+    SelectInsideBlock,
+    SelectAroundBlock,
+
+    // Symbols (language server, or tree-sitter outline queries)
+    GotoSymbol,
+    GotoWorkspaceSymbol,
+    ToggleOutline,
+    CallHierarchy,
+    TypeHierarchy,
+
     // Smart editing
     SmartHome,
     IndentSelection,
@@ -318,6 +348,15 @@ pub enum Action {
     PromptRecordMacro,
     PromptPlayMacro,
     PlayLastMacro,
+    PlayNamedMacro(String),
+    PromptEditMacro,
+    SaveMacroFromBuffer,
+    ApplyMacroToLines(char),
+    ApplyMacroToMatches(char),
+    RepeatMacro(char),
+    PromptApplyMacroToLines,
+    PromptApplyMacroToMatches,
+    PromptRepeatMacro,
 
     // Bookmarks (prompt-based)
     PromptSetBookmark,
@@ -326,6 +365,11 @@ pub enum Action {
     // Undo/redo
     Undo,
     Redo,
+    UndoTreeEarlier,
+    UndoTreeLater,
+    UndoTreeSwitchBranch,
+    UndoTreeGoto,
+    ShowUndoTree,
 
     // View
     ScrollUp,
@@ -418,9 +462,18 @@ pub enum Action {
     FileExplorerNewFile,
     FileExplorerNewDirectory,
     FileExplorerDelete,
+    FileExplorerDeletePermanently,
+    FileExplorerUndoDelete,
     FileExplorerRename,
     FileExplorerToggleHidden,
     FileExplorerToggleGitignored,
+    FileExplorerToggleMark,
+    FileExplorerExtendMarkUp,
+    FileExplorerExtendMarkDown,
+    FileExplorerClearMarks,
+    FileExplorerCopy,
+    FileExplorerCut,
+    FileExplorerPaste,
 
     // LSP operations
     LspCompletion,
@@ -430,6 +483,8 @@ pub enum Action {
     LspHover,
     LspSignatureHelp,
     LspCodeActions,
+    FormatDocument,
+    FormatSelection,
     LspRestart,
     LspStop,
     ToggleInlayHints,
@@ -441,6 +496,13 @@ pub enum Action {
     SetBackground,
     SetBackgroundBlend,
 
+    // Code folding
+    Fold,
+    Unfold,
+    FoldAll,
+    UnfoldAll,
+    FoldToLevel(usize), // Fold everything nested exactly this deep (1 = outermost)
+
     // Config operations
     DumpConfig,
 
@@ -523,6 +585,7 @@ impl Action {
             "select_word" => Some(Action::SelectWord),
             "select_line" => Some(Action::SelectLine),
             "expand_selection" => Some(Action::ExpandSelection),
+            "shrink_selection" => Some(Action::ShrinkSelection),
 
             // Block/rectangular selection
             "block_select_left" => Some(Action::BlockSelectLeft),
@@ -560,10 +623,35 @@ impl Action {
             "quit" => Some(Action::Quit),
             "revert" => Some(Action::Revert),
             "toggle_auto_revert" => Some(Action::ToggleAutoRevert),
+            "reopen_with_encoding" => Some(Action::ReopenWithEncoding),
+            "save_with_encoding" => Some(Action::SaveWithEncoding),
+            "save_as_root" => Some(Action::SaveAsRoot),
+            "set_language" => Some(Action::SetLanguage),
             "goto_line" => Some(Action::GotoLine),
             "goto_matching_bracket" => Some(Action::GoToMatchingBracket),
             "jump_to_next_error" => Some(Action::JumpToNextError),
             "jump_to_previous_error" => Some(Action::JumpToPreviousError),
+            "goto_next_function" => Some(Action::GotoNextFunction),
+            "goto_previous_function" => Some(Action::GotoPreviousFunction),
+            "goto_next_class" => Some(Action::GotoNextClass),
+            "goto_previous_class" => Some(Action::GotoPreviousClass),
+            "goto_next_parameter" => Some(Action::GotoNextParameter),
+            "goto_previous_parameter" => Some(Action::GotoPreviousParameter),
+            "goto_next_comment" => Some(Action::GotoNextComment),
+            "goto_previous_comment" => Some(Action::GotoPreviousComment),
+            "select_inside_function" => Some(Action::SelectInsideFunction),
+            "select_around_function" => Some(Action::SelectAroundFunction),
+            "select_inside_class" => Some(Action::SelectInsideClass),
+            "select_around_class" => Some(Action::SelectAroundClass),
+            "select_inside_parameter" => Some(Action::SelectInsideParameter),
+            "select_around_parameter" => Some(Action::SelectAroundParameter),
+            "select_inside_block" => Some(Action::SelectInsideBlock),
+            "select_around_block" => Some(Action::SelectAroundBlock),
+            "goto_symbol" => Some(Action::GotoSymbol),
+            "goto_workspace_symbol" => Some(Action::GotoWorkspaceSymbol),
+            "toggle_outline" => Some(Action::ToggleOutline),
+            "call_hierarchy" => Some(Action::CallHierarchy),
+            "type_hierarchy" => Some(Action::TypeHierarchy),
 
             "smart_home" => Some(Action::SmartHome),
             "indent_selection" => Some(Action::IndentSelection),
@@ -625,11 +713,49 @@ impl Action {
             "prompt_record_macro" => Some(Action::PromptRecordMacro),
             "prompt_play_macro" => Some(Action::PromptPlayMacro),
             "play_last_macro" => Some(Action::PlayLastMacro),
+            "play_named_macro" => {
+                if let Some(serde_json::Value::String(name)) = args.get("name") {
+                    Some(Action::PlayNamedMacro(name.clone()))
+                } else {
+                    None
+                }
+            }
+            "prompt_edit_macro" => Some(Action::PromptEditMacro),
+            "save_macro_from_buffer" => Some(Action::SaveMacroFromBuffer),
+            "apply_macro_to_lines" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::ApplyMacroToLines)
+                } else {
+                    None
+                }
+            }
+            "apply_macro_to_matches" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::ApplyMacroToMatches)
+                } else {
+                    None
+                }
+            }
+            "repeat_macro" => {
+                if let Some(serde_json::Value::String(c)) = args.get("char") {
+                    c.chars().next().map(Action::RepeatMacro)
+                } else {
+                    None
+                }
+            }
+            "prompt_apply_macro_to_lines" => Some(Action::PromptApplyMacroToLines),
+            "prompt_apply_macro_to_matches" => Some(Action::PromptApplyMacroToMatches),
+            "prompt_repeat_macro" => Some(Action::PromptRepeatMacro),
             "prompt_set_bookmark" => Some(Action::PromptSetBookmark),
             "prompt_jump_to_bookmark" => Some(Action::PromptJumpToBookmark),
 
             "undo" => Some(Action::Undo),
             "redo" => Some(Action::Redo),
+            "undo_tree_earlier" => Some(Action::UndoTreeEarlier),
+            "undo_tree_later" => Some(Action::UndoTreeLater),
+            "undo_tree_switch_branch" => Some(Action::UndoTreeSwitchBranch),
+            "undo_tree_goto" => Some(Action::UndoTreeGoto),
+            "show_undo_tree" => Some(Action::ShowUndoTree),
 
             "scroll_up" => Some(Action::ScrollUp),
             "scroll_down" => Some(Action::ScrollDown),
@@ -705,9 +831,18 @@ impl Action {
             "file_explorer_new_file" => Some(Action::FileExplorerNewFile),
             "file_explorer_new_directory" => Some(Action::FileExplorerNewDirectory),
             "file_explorer_delete" => Some(Action::FileExplorerDelete),
+            "file_explorer_delete_permanently" => Some(Action::FileExplorerDeletePermanently),
+            "file_explorer_undo_delete" => Some(Action::FileExplorerUndoDelete),
             "file_explorer_rename" => Some(Action::FileExplorerRename),
             "file_explorer_toggle_hidden" => Some(Action::FileExplorerToggleHidden),
             "file_explorer_toggle_gitignored" => Some(Action::FileExplorerToggleGitignored),
+            "file_explorer_toggle_mark" => Some(Action::FileExplorerToggleMark),
+            "file_explorer_extend_mark_up" => Some(Action::FileExplorerExtendMarkUp),
+            "file_explorer_extend_mark_down" => Some(Action::FileExplorerExtendMarkDown),
+            "file_explorer_clear_marks" => Some(Action::FileExplorerClearMarks),
+            "file_explorer_copy" => Some(Action::FileExplorerCopy),
+            "file_explorer_cut" => Some(Action::FileExplorerCut),
+            "file_explorer_paste" => Some(Action::FileExplorerPaste),
 
             "lsp_completion" => Some(Action::LspCompletion),
             "lsp_goto_definition" => Some(Action::LspGotoDefinition),
@@ -716,6 +851,8 @@ impl Action {
             "lsp_hover" => Some(Action::LspHover),
             "lsp_signature_help" => Some(Action::LspSignatureHelp),
             "lsp_code_actions" => Some(Action::LspCodeActions),
+            "format_document" => Some(Action::FormatDocument),
+            "format_selection" => Some(Action::FormatSelection),
             "lsp_restart" => Some(Action::LspRestart),
             "lsp_stop" => Some(Action::LspStop),
             "toggle_inlay_hints" => Some(Action::ToggleInlayHints),
@@ -725,6 +862,15 @@ impl Action {
             "toggle_mouse_capture" => Some(Action::ToggleMouseCapture),
             "set_background" => Some(Action::SetBackground),
             "set_background_blend" => Some(Action::SetBackgroundBlend),
+
+            "fold" => Some(Action::Fold),
+            "unfold" => Some(Action::Unfold),
+            "fold_all" => Some(Action::FoldAll),
+            "unfold_all" => Some(Action::UnfoldAll),
+            "fold_to_level" => {
+                let level = args.get("level")?.as_u64()?;
+                Some(Action::FoldToLevel(level as usize))
+            }
             "select_theme" => Some(Action::SelectTheme),
             "select_keybinding_map" => Some(Action::SelectKeybindingMap),
 
@@ -1488,6 +1634,7 @@ impl KeybindingResolver {
             Action::SelectWord => "Select word under cursor".to_string(),
             Action::SelectLine => "Select current line".to_string(),
             Action::ExpandSelection => "Expand selection".to_string(),
+            Action::ShrinkSelection => "Shrink selection".to_string(),
             Action::BlockSelectLeft => "Block select left".to_string(),
             Action::BlockSelectRight => "Block select right".to_string(),
             Action::BlockSelectUp => "Block select up".to_string(),
@@ -1519,10 +1666,35 @@ impl KeybindingResolver {
             Action::Quit => "Quit editor".to_string(),
             Action::Revert => "Revert to saved file".to_string(),
             Action::ToggleAutoRevert => "Toggle auto-revert mode".to_string(),
+            Action::ReopenWithEncoding => "Reopen file with encoding".to_string(),
+            Action::SaveWithEncoding => "Save file with encoding".to_string(),
+            Action::SaveAsRoot => "Save file as root".to_string(),
+            Action::SetLanguage => "Set the buffer's language".to_string(),
             Action::GotoLine => "Go to line number".to_string(),
             Action::GoToMatchingBracket => "Go to matching bracket".to_string(),
             Action::JumpToNextError => "Jump to next error/diagnostic".to_string(),
             Action::JumpToPreviousError => "Jump to previous error/diagnostic".to_string(),
+            Action::GotoNextFunction => "Go to next function".to_string(),
+            Action::GotoPreviousFunction => "Go to previous function".to_string(),
+            Action::GotoNextClass => "Go to next class".to_string(),
+            Action::GotoPreviousClass => "Go to previous class".to_string(),
+            Action::GotoNextParameter => "Go to next parameter".to_string(),
+            Action::GotoPreviousParameter => "Go to previous parameter".to_string(),
+            Action::GotoNextComment => "Go to next comment".to_string(),
+            Action::GotoPreviousComment => "Go to previous comment".to_string(),
+            Action::SelectInsideFunction => "Select inside function".to_string(),
+            Action::SelectAroundFunction => "Select around function".to_string(),
+            Action::SelectInsideClass => "Select inside class".to_string(),
+            Action::SelectAroundClass => "Select around class".to_string(),
+            Action::SelectInsideParameter => "Select inside parameter".to_string(),
+            Action::SelectAroundParameter => "Select around parameter".to_string(),
+            Action::SelectInsideBlock => "Select inside block".to_string(),
+            Action::SelectAroundBlock => "Select around block".to_string(),
+            Action::GotoSymbol => "Go to symbol in file".to_string(),
+            Action::GotoWorkspaceSymbol => "Go to symbol in workspace".to_string(),
+            Action::ToggleOutline => "Toggle outline panel".to_string(),
+            Action::CallHierarchy => "Show call hierarchy".to_string(),
+            Action::TypeHierarchy => "Show type hierarchy".to_string(),
             Action::SmartHome => {
                 "Smart home (toggle line start / first non-whitespace)".to_string()
             }
@@ -1546,10 +1718,30 @@ impl KeybindingResolver {
             Action::PromptRecordMacro => "Record macro (prompts for register)".to_string(),
             Action::PromptPlayMacro => "Play macro (prompts for register)".to_string(),
             Action::PlayLastMacro => "Play last recorded macro".to_string(),
+            Action::PlayNamedMacro(name) => format!("Play macro '{}'", name),
+            Action::PromptEditMacro => "Edit macro (prompts for register)".to_string(),
+            Action::SaveMacroFromBuffer => "Save macro edited in the buffer".to_string(),
+            Action::ApplyMacroToLines(c) => format!("Play macro '{}' on each selected line", c),
+            Action::ApplyMacroToMatches(c) => format!("Play macro '{}' at each search match", c),
+            Action::RepeatMacro(c) => format!("Repeat macro '{}' until it fails", c),
+            Action::PromptApplyMacroToLines => {
+                "Play macro on each selected line (prompts for register)".to_string()
+            }
+            Action::PromptApplyMacroToMatches => {
+                "Play macro at each search match (prompts for register)".to_string()
+            }
+            Action::PromptRepeatMacro => {
+                "Repeat macro (prompts for register and count)".to_string()
+            }
             Action::PromptSetBookmark => "Set bookmark (prompts for register)".to_string(),
             Action::PromptJumpToBookmark => "Jump to bookmark (prompts for register)".to_string(),
             Action::Undo => "Undo".to_string(),
             Action::Redo => "Redo".to_string(),
+            Action::UndoTreeEarlier => "Undo tree: go to earlier state".to_string(),
+            Action::UndoTreeLater => "Undo tree: go to later state".to_string(),
+            Action::UndoTreeSwitchBranch => "Undo tree: switch redo branch".to_string(),
+            Action::UndoTreeGoto => "Undo tree: go to state or time".to_string(),
+            Action::ShowUndoTree => "Undo tree: show history".to_string(),
             Action::ScrollUp => "Scroll up".to_string(),
             Action::ScrollDown => "Scroll down".to_string(),
             Action::ShowHelp => "Show manual".to_string(),
@@ -1617,12 +1809,23 @@ impl KeybindingResolver {
             Action::FileExplorerRefresh => "File explorer: refresh".to_string(),
             Action::FileExplorerNewFile => "File explorer: new file".to_string(),
             Action::FileExplorerNewDirectory => "File explorer: new directory".to_string(),
-            Action::FileExplorerDelete => "File explorer: delete".to_string(),
+            Action::FileExplorerDelete => "File explorer: move to trash".to_string(),
+            Action::FileExplorerDeletePermanently => {
+                "File explorer: delete permanently".to_string()
+            }
+            Action::FileExplorerUndoDelete => "File explorer: undo delete".to_string(),
             Action::FileExplorerRename => "File explorer: rename".to_string(),
             Action::FileExplorerToggleHidden => "File explorer: toggle hidden files".to_string(),
             Action::FileExplorerToggleGitignored => {
                 "File explorer: toggle gitignored files".to_string()
             }
+            Action::FileExplorerToggleMark => "File explorer: mark or unmark".to_string(),
+            Action::FileExplorerExtendMarkUp => "File explorer: mark upwards".to_string(),
+            Action::FileExplorerExtendMarkDown => "File explorer: mark downwards".to_string(),
+            Action::FileExplorerClearMarks => "File explorer: clear marks".to_string(),
+            Action::FileExplorerCopy => "File explorer: copy".to_string(),
+            Action::FileExplorerCut => "File explorer: cut".to_string(),
+            Action::FileExplorerPaste => "File explorer: paste".to_string(),
             Action::LspCompletion => "LSP: Show completion suggestions".to_string(),
             Action::LspGotoDefinition => "LSP: Go to definition".to_string(),
             Action::LspReferences => "LSP: Find references".to_string(),
@@ -1630,6 +1833,8 @@ impl KeybindingResolver {
             Action::LspHover => "LSP: Show hover documentation".to_string(),
             Action::LspSignatureHelp => "LSP: Show signature help".to_string(),
             Action::LspCodeActions => "LSP: Show code actions".to_string(),
+            Action::FormatDocument => "Format document".to_string(),
+            Action::FormatSelection => "Format selection".to_string(),
             Action::LspRestart => "LSP: Start/restart server for current language".to_string(),
             Action::LspStop => "LSP: Stop a running server".to_string(),
             Action::ToggleInlayHints => "Toggle inlay hints".to_string(),
@@ -1638,6 +1843,11 @@ impl KeybindingResolver {
             Action::ToggleMouseCapture => "Toggle mouse support".to_string(),
             Action::SetBackground => "Set ANSI background file".to_string(),
             Action::SetBackgroundBlend => "Set background blend ratio".to_string(),
+            Action::Fold => "Fold region at cursor".to_string(),
+            Action::Unfold => "Unfold region at cursor".to_string(),
+            Action::FoldAll => "Fold all regions".to_string(),
+            Action::UnfoldAll => "Unfold all regions".to_string(),
+            Action::FoldToLevel(level) => format!("Fold regions at level {}", level),
             Action::DumpConfig => "Dump config to file".to_string(),
             Action::Search => "Search for text in buffer".to_string(),
             Action::FindInSelection => "Search within selection".to_string(),
@@ -0,0 +1,202 @@
+//! Keyboard macros
+//!
+//! Macros are recorded into registers `0`-`9` and stored in `macros.json` in
+//! the data directory, so they survive restarts. Each is a list of actions in
+//! their JSON form (`"MoveDown"`, `{"InsertChar": "x"}`), optionally with a
+//! name. Named macros get a "Macro: <name>" command in the command palette and
+//! can be bound to keys with the `play_named_macro` action.
+//!
+//! Edit Macro opens a macro as JSON in a buffer (see `macro_to_json`); Save
+//! Macro from Buffer parses it back, so macros can also be shared as files.
+
+use crate::input::keybindings::Action;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+
+/// Prefix of the command palette entries of named macros
+pub const MACRO_COMMAND_PREFIX: &str = "Macro: ";
+
+/// A recorded macro
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Macro {
+    /// Name shown in the command palette and used by `play_named_macro`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub actions: Vec<Action>,
+}
+
+/// A macro with its register, as edited in a buffer
+#[derive(Debug, Serialize, Deserialize)]
+struct EditableMacro {
+    register: char,
+    #[serde(default)]
+    name: Option<String>,
+    actions: Vec<Action>,
+}
+
+/// Macros by register, saved to a file on every change
+#[derive(Debug)]
+pub struct MacroStore {
+    path: PathBuf,
+    macros: BTreeMap<char, Macro>,
+}
+
+impl MacroStore {
+    /// Load the macros saved at `path` (none if the file is missing or unreadable)
+    pub fn load(path: PathBuf) -> Self {
+        let macros = std::fs::read(&path)
+            .ok()
+            .and_then(|content| match serde_json::from_slice(&content) {
+                Ok(macros) => Some(macros),
+                Err(e) => {
+                    tracing::warn!("Ignoring invalid macros file {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Self { path, macros }
+    }
+
+    pub fn get(&self, register: char) -> Option<&Macro> {
+        self.macros.get(&register)
+    }
+
+    /// The register and macro with the given name
+    pub fn find_named(&self, name: &str) -> Option<(char, &Macro)> {
+        self.macros
+            .iter()
+            .find(|(_, m)| m.name.as_deref() == Some(name))
+            .map(|(register, m)| (*register, m))
+    }
+
+    /// Macros in register order
+    pub fn iter(&self) -> impl Iterator<Item = (char, &Macro)> {
+        self.macros.iter().map(|(register, m)| (*register, m))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.macros.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.macros.len()
+    }
+
+    /// Replace the actions of a register's macro, keeping its name
+    pub fn set_actions(&mut self, register: char, actions: Vec<Action>) -> io::Result<()> {
+        let name = self.macros.remove(&register).and_then(|m| m.name);
+        self.insert(register, Macro { name, actions })
+    }
+
+    /// Store a macro in a register. A macro elsewhere with the same name loses it.
+    pub fn insert(&mut self, register: char, new_macro: Macro) -> io::Result<()> {
+        if let Some(name) = &new_macro.name {
+            for m in self.macros.values_mut() {
+                if m.name.as_ref() == Some(name) {
+                    m.name = None;
+                }
+            }
+        }
+        self.macros.insert(register, new_macro);
+        self.save()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&self.macros)?)
+    }
+}
+
+/// A macro as JSON for editing: its register, name and actions
+pub fn macro_to_json(register: char, m: &Macro) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&EditableMacro {
+        register,
+        name: m.name.clone(),
+        actions: m.actions.clone(),
+    })
+}
+
+/// Parse a macro edited as JSON (see `macro_to_json`). Lines starting with
+/// `//` are ignored; an empty name means none.
+pub fn macro_from_json(text: &str) -> Result<(char, Macro), String> {
+    let json: String = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let edited: EditableMacro =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid macro: {}", e))?;
+    if !edited.register.is_ascii_digit() {
+        return Err(format!(
+            "Macro register must be 0-9, not '{}'",
+            edited.register
+        ));
+    }
+    let name = edited
+        .name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    Ok((
+        edited.register,
+        Macro {
+            name,
+            actions: edited.actions,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Macro {
+        Macro {
+            name: Some("quote line".to_string()),
+            actions: vec![
+                Action::MoveLineStart,
+                Action::InsertChar('"'),
+                Action::MoveLineEnd,
+                Action::InsertChar('"'),
+                Action::MoveDown,
+            ],
+        }
+    }
+
+    #[test]
+    fn test_edit_round_trip() {
+        let json = macro_to_json('3', &sample()).unwrap();
+        let edited = format!("// Macro '3'\n{}", json.replace("quote line", " quote "));
+        let (register, parsed) = macro_from_json(&edited).unwrap();
+        assert_eq!(register, '3');
+        assert_eq!(parsed.name.as_deref(), Some("quote"));
+        assert_eq!(parsed.actions, sample().actions);
+
+        assert!(macro_from_json(r#"{"register": "x", "actions": []}"#).is_err());
+        assert!(macro_from_json(r#"{"register": "1", "actions": ["NoSuchAction"]}"#).is_err());
+        let (_, unnamed) =
+            macro_from_json(r#"{"register": "1", "name": "", "actions": ["MoveUp"]}"#).unwrap();
+        assert_eq!(unnamed.name, None);
+    }
+
+    #[test]
+    fn test_store_persists_and_keeps_names_unique() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data/macros.json");
+
+        let mut store = MacroStore::load(path.clone());
+        assert!(store.is_empty());
+        store.insert('1', sample()).unwrap();
+        store.set_actions('1', vec![Action::MoveUp]).unwrap();
+        store.insert('2', sample()).unwrap();
+
+        let store = MacroStore::load(path);
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get('1').unwrap().actions, vec![Action::MoveUp]);
+        assert_eq!(store.get('1').unwrap().name, None);
+        assert_eq!(store.find_named("quote line").map(|(r, _)| r), Some('2'));
+    }
+}
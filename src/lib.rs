@@ -5,6 +5,7 @@ pub mod v8_init;
 
 // Core modules at root level
 pub mod config;
+pub mod project_config;
 pub mod session;
 pub mod state;
 
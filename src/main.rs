@@ -19,7 +19,7 @@ use fresh::{
 use ratatui::Terminal;
 use std::{
     io::{self, stdout},
-    path::PathBuf,
+    path::{Path, PathBuf},
     time::Duration,
 };
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
@@ -30,9 +30,9 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 #[command(about = "A terminal text editor with multi-cursor support", long_about = None)]
 #[command(version)]
 struct Args {
-    /// File to open
+    /// Files or directories to open (supports file:line:col and glob patterns)
     #[arg(value_name = "FILE")]
-    file: Option<PathBuf>,
+    files: Vec<PathBuf>,
 
     /// Disable plugin loading
     #[arg(long)]
@@ -174,6 +174,132 @@ fn parse_file_location(input: &str) -> FileLocation {
     }
 }
 
+/// Check whether a path argument contains glob wildcards
+fn is_glob_pattern(input: &str) -> bool {
+    input.contains(['*', '?'])
+}
+
+/// Match a single path component against a glob pattern supporting `*` and `?`.
+/// Leading dots must be matched explicitly, like in the shell.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` in the pattern and the name position it matched up to
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+            }
+            Some(&c) if c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Expand a glob pattern against the filesystem, component by component.
+/// Returns matching paths in sorted order.
+fn expand_glob(pattern: &str) -> Vec<PathBuf> {
+    use std::path::Component;
+
+    let mut candidates = vec![PathBuf::new()];
+    for component in Path::new(pattern).components() {
+        let part = match component {
+            Component::Normal(part) => part.to_string_lossy(),
+            other => {
+                // Root, prefix, `.` and `..` are taken literally
+                for candidate in &mut candidates {
+                    candidate.push(other.as_os_str());
+                }
+                continue;
+            }
+        };
+
+        if !is_glob_pattern(&part) {
+            for candidate in &mut candidates {
+                candidate.push(part.as_ref());
+            }
+            candidates.retain(|c| c.exists());
+            continue;
+        }
+
+        let mut next = Vec::new();
+        for candidate in &candidates {
+            let dir = if candidate.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                candidate.as_path()
+            };
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            let mut matches: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| glob_match(&part, &entry.file_name().to_string_lossy()))
+                .map(|entry| candidate.join(entry.file_name()))
+                .collect();
+            matches.sort();
+            next.extend(matches);
+        }
+        candidates = next;
+    }
+
+    candidates
+}
+
+/// Turn the CLI path arguments into file locations.
+///
+/// Each argument may carry a `:line:col` suffix. Arguments containing glob
+/// wildcards that don't name an existing path are expanded here, which covers
+/// quoted patterns and shells that don't expand globs (e.g. on Windows).
+fn expand_file_args(args: &[PathBuf]) -> Vec<FileLocation> {
+    let mut locations = Vec::new();
+    for arg in args {
+        let input = arg.to_string_lossy();
+        let location = parse_file_location(&input);
+
+        if location.path.exists() || !is_glob_pattern(&location.path.to_string_lossy()) {
+            locations.push(location);
+            continue;
+        }
+
+        let matches = expand_glob(&location.path.to_string_lossy());
+        if matches.is_empty() {
+            tracing::warn!("No files match pattern: {}", input);
+        }
+        locations.extend(matches.into_iter().map(|path| FileLocation {
+            path,
+            line: location.line,
+            column: location.column,
+        }));
+    }
+    locations
+}
+
 fn main() -> io::Result<()> {
     // Parse command-line arguments
     let args = Args::parse();
@@ -284,25 +410,25 @@ fn main() -> io::Result<()> {
     let size = terminal.size()?;
     tracing::info!("Terminal size: {}x{}", size.width, size.height);
 
-    // Parse the file argument, extracting any line:col suffix
-    let file_location = args
-        .file
-        .as_ref()
-        .map(|p| parse_file_location(p.to_string_lossy().as_ref()));
+    // Parse the file arguments, extracting any line:col suffix and expanding globs
+    let file_locations = expand_file_args(&args.files);
 
-    // Determine if the provided path is a directory or file
-    let (working_dir, file_to_open, show_file_explorer) = if let Some(ref loc) = file_location {
+    // A directory argument becomes the project root (like SwitchProject) and shows the
+    // file explorer; every other argument is opened as a file in its own tab.
+    let mut working_dir = None;
+    let mut files_to_open = Vec::new();
+    for loc in &file_locations {
         if loc.path.is_dir() {
-            // Path is a directory: use as working dir, don't open any file, show file explorer
-            (Some(loc.path.clone()), None, true)
+            if working_dir.is_none() {
+                working_dir = Some(loc.path.clone());
+            } else {
+                tracing::warn!("Ignoring extra directory argument: {}", loc.path.display());
+            }
         } else {
-            // Path is a file: use current dir as working dir, open the file, don't auto-show explorer
-            (None, Some(loc.path.clone()), false)
+            files_to_open.push(loc);
         }
-    } else {
-        // No path provided: use current dir, no file, don't auto-show explorer
-        (None, None, false)
-    };
+    }
+    let show_file_explorer = working_dir.is_some();
 
     // Get directory context from system (data dir, config dir, etc.)
     let dir_context = DirectoryContext::from_system()?;
@@ -364,7 +490,7 @@ fn main() -> io::Result<()> {
         // Try to restore previous session:
         // - On first run (unless --no-session flag is set or a file was specified)
         // - After project switch (restore session for the new project)
-        let session_enabled = !args.no_session && file_to_open.is_none();
+        let session_enabled = !args.no_session && files_to_open.is_empty();
         if (is_first_run && session_enabled) || restore_session_on_restart {
             match editor.try_restore_session() {
                 Ok(true) => {
@@ -379,18 +505,23 @@ fn main() -> io::Result<()> {
             }
         }
 
-        // Open file if provided (only on first run, this takes precedence over session)
+        // Open files if provided (only on first run, this takes precedence over session)
         if is_first_run {
-            if let Some(path) = &file_to_open {
-                editor.open_file(path)?;
+            let mut first_buffer = None;
+            for loc in &files_to_open {
+                let buffer_id = editor.open_file(&loc.path)?;
+                first_buffer.get_or_insert(buffer_id);
 
                 // Navigate to line:col if specified
-                if let Some(ref loc) = file_location {
-                    if let Some(line) = loc.line {
-                        editor.goto_line_col(line, loc.column);
-                    }
+                if let Some(line) = loc.line {
+                    editor.goto_line_col(line, loc.column);
                 }
             }
+
+            // Focus the first file given on the command line
+            if let Some(buffer_id) = first_buffer {
+                editor.switch_buffer(buffer_id);
+            }
         }
 
         // Show file explorer if directory was provided (only on first run)
@@ -437,6 +568,9 @@ fn main() -> io::Result<()> {
         #[cfg(not(target_os = "linux"))]
         let loop_result = run_event_loop(&mut editor, &mut terminal, session_enabled);
 
+        // Persist undo history of open buffers (if enabled)
+        editor.persist_all_undo_history();
+
         // End recovery session (clean shutdown)
         if let Err(e) = editor.end_recovery_session() {
             tracing::warn!("Failed to end recovery session: {}", e);
@@ -498,39 +632,39 @@ fn main() -> io::Result<()> {
 
 /// Run the editor in script control mode
 fn run_script_control_mode(args: &Args) -> io::Result<()> {
-    // Parse the file argument, extracting any line:col suffix
-    let file_location = args
-        .file
-        .as_ref()
-        .map(|p| parse_file_location(p.to_string_lossy().as_ref()));
+    // Parse the file arguments, extracting any line:col suffix and expanding globs
+    let file_locations = expand_file_args(&args.files);
 
     // Get directory context from system
     let dir_context = DirectoryContext::from_system()?;
 
-    // Create script control mode instance
-    let mut control = if let Some(ref loc) = file_location {
-        if loc.path.is_dir() {
-            ScriptControlMode::with_working_dir(
-                args.script_width,
-                args.script_height,
-                loc.path.clone(),
-                dir_context,
-            )?
-        } else {
-            let mut ctrl =
-                ScriptControlMode::new(args.script_width, args.script_height, dir_context)?;
-            // Open the file if provided
-            ctrl.open_file(&loc.path)?;
-            // Navigate to line:col if specified
-            if let Some(line) = loc.line {
-                ctrl.goto_line_col(line, loc.column);
-            }
-            ctrl
-        }
-    } else {
-        ScriptControlMode::new(args.script_width, args.script_height, dir_context)?
+    // Create script control mode instance, rooted at the first directory argument if any
+    let mut control = match file_locations.iter().find(|loc| loc.path.is_dir()) {
+        Some(dir) => ScriptControlMode::with_working_dir(
+            args.script_width,
+            args.script_height,
+            dir.path.clone(),
+            dir_context,
+        )?,
+        None => ScriptControlMode::new(args.script_width, args.script_height, dir_context)?,
     };
 
+    // Open the files in order, then refocus the first one
+    let files: Vec<&FileLocation> = file_locations
+        .iter()
+        .filter(|loc| !loc.path.is_dir())
+        .collect();
+    for loc in &files {
+        control.open_file(&loc.path)?;
+        // Navigate to line:col if specified
+        if let Some(line) = loc.line {
+            control.goto_line_col(line, loc.column);
+        }
+    }
+    if files.len() > 1 {
+        control.open_file(&files[0].path)?;
+    }
+
     control.run()
 }
 
@@ -559,6 +693,11 @@ fn run_event_loop(
             needs_render = true;
         }
 
+        // Refresh the outline panel after edits and follow the cursor
+        if editor.check_outline_panel() {
+            needs_render = true;
+        }
+
         // Check for warnings and open warning log if any occurred
         if editor.check_warning_log() {
             needs_render = true;
@@ -657,6 +796,11 @@ fn run_event_loop(
             needs_render = true;
         }
 
+        // Refresh the outline panel after edits and follow the cursor
+        if editor.check_outline_panel() {
+            needs_render = true;
+        }
+
         // Check for warnings and open warning log if any occurred
         if editor.check_warning_log() {
             needs_render = true;
@@ -818,14 +962,16 @@ fn poll_with_gpm(
 
 /// Handle a keyboard event
 fn handle_key_event(editor: &mut Editor, key_event: KeyEvent) -> io::Result<()> {
-    // Trace the full key event
-    tracing::trace!(
-        "Key event received: code={:?}, modifiers={:?}, kind={:?}, state={:?}",
-        key_event.code,
-        key_event.modifiers,
-        key_event.kind,
-        key_event.state
-    );
+    // Trace the full key event, unless it is typed into a password prompt
+    if !editor.is_input_masked() {
+        tracing::trace!(
+            "Key event received: code={:?}, modifiers={:?}, kind={:?}, state={:?}",
+            key_event.code,
+            key_event.modifiers,
+            key_event.kind,
+            key_event.state
+        );
+    }
 
     // Log the keystroke
     let key_code = format!("{:?}", key_event.code);
@@ -940,6 +1086,48 @@ mod tests {
         assert_eq!(loc.line, None);
         assert_eq!(loc.column, None);
     }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(glob_match("ma?n.rs", "main.rs"));
+        assert!(glob_match("*a*n*", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rs.bak"));
+        assert!(!glob_match("*.rs", ".hidden.rs"));
+        assert!(glob_match(".*.rs", ".hidden.rs"));
+    }
+
+    #[test]
+    fn test_expand_file_args_globs_and_plain_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir(&src).unwrap();
+        for name in ["b.rs", "a.rs", "notes.txt"] {
+            std::fs::write(src.join(name), "").unwrap();
+        }
+
+        let pattern = src.join("*.rs");
+        let plain = dir.path().join("new_file.txt:3");
+        let locations = expand_file_args(&[pattern, plain]);
+
+        let paths: Vec<PathBuf> = locations.iter().map(|l| l.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                src.join("a.rs"),
+                src.join("b.rs"),
+                dir.path().join("new_file.txt")
+            ]
+        );
+        assert_eq!(locations[2].line, Some(3));
+    }
+
+    #[test]
+    fn test_expand_file_args_unmatched_glob_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let locations = expand_file_args(&[dir.path().join("*.nothing")]);
+        assert!(locations.is_empty());
+    }
 }
 
 // Property tests use Unix-style path generation strategy, skip on Windows
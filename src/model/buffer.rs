@@ -1,5 +1,7 @@
 /// Text buffer that uses PieceTree with integrated line tracking
 /// Architecture where the tree is the single source of truth for text and line information
+use crate::model::encoding::Encoding;
+use crate::model::file_write;
 use crate::model::piece_tree::{
     BufferData, BufferLocation, Cursor, PieceInfo, PieceRangeIter, PieceTree, Position,
     StringBuffer, TreeStats,
@@ -139,6 +141,9 @@ pub struct TextBuffer {
     /// Line ending format detected from the file (or default for new files)
     line_ending: LineEnding,
 
+    /// Text encoding detected from the file (or default for new files)
+    encoding: Encoding,
+
     /// The file size on disk after the last save.
     /// Used for chunked recovery to know the original file size for reconstruction.
     /// Updated when loading from file or after saving.
@@ -161,6 +166,7 @@ impl TextBuffer {
             large_file: false,
             is_binary: false,
             line_ending: LineEnding::default(),
+            encoding: Encoding::default(),
             saved_file_size: None,
         }
     }
@@ -192,6 +198,7 @@ impl TextBuffer {
             large_file: false,
             is_binary: false,
             line_ending: LineEnding::default(),
+            encoding: Encoding::default(),
             saved_file_size: Some(bytes), // Treat initial content as "saved" state
         }
     }
@@ -216,6 +223,7 @@ impl TextBuffer {
             large_file: false,
             is_binary: false,
             line_ending: LineEnding::default(),
+            encoding: Encoding::default(),
             saved_file_size: None,
         }
     }
@@ -224,6 +232,16 @@ impl TextBuffer {
     pub fn load_from_file<P: AsRef<Path>>(
         path: P,
         large_file_threshold: usize,
+    ) -> io::Result<Self> {
+        Self::load_from_file_with_encoding(path, large_file_threshold, None)
+    }
+
+    /// Load a text buffer from a file, decoding it with the given encoding
+    /// (or a detected one if `None`)
+    pub fn load_from_file_with_encoding<P: AsRef<Path>>(
+        path: P,
+        large_file_threshold: usize,
+        encoding: Option<Encoding>,
     ) -> io::Result<Self> {
         let path = path.as_ref();
 
@@ -239,20 +257,25 @@ impl TextBuffer {
         };
 
         // Choose loading strategy based on file size
-        if file_size >= threshold {
+        // Lazy loading works on raw bytes, so only UTF-8 files can use it
+        if file_size >= threshold && matches!(encoding, None | Some(Encoding::Utf8)) {
             Self::load_large_file(path, file_size)
         } else {
-            Self::load_small_file(path)
+            Self::load_small_file(path, encoding)
         }
     }
 
     /// Load a small file with full eager loading and line indexing
-    fn load_small_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+    fn load_small_file<P: AsRef<Path>>(path: P, encoding: Option<Encoding>) -> io::Result<Self> {
         let path = path.as_ref();
         let mut file = std::fs::File::open(path)?;
         let mut contents = Vec::new();
         file.read_to_end(&mut contents)?;
 
+        // Decode to UTF-8 for internal representation
+        let encoding = encoding.unwrap_or_else(|| Self::detect_encoding(&contents));
+        let contents = encoding.decode(contents);
+
         // Detect if this is a binary file
         let is_binary = Self::detect_binary(&contents);
 
@@ -268,6 +291,7 @@ impl TextBuffer {
         buffer.large_file = false;
         buffer.is_binary = is_binary;
         buffer.line_ending = line_ending;
+        buffer.encoding = encoding;
         Ok(buffer)
     }
 
@@ -277,18 +301,29 @@ impl TextBuffer {
 
         let path = path.as_ref();
 
-        // Read a sample of the file to detect if it's binary and line ending format
-        // We read the first 8KB for both binary and line ending detection
-        let (is_binary, line_ending) = {
+        // Read a sample of the file to detect if it's binary, its encoding and line ending format
+        // We read the first 8KB for binary, encoding and line ending detection
+        let (is_binary, line_ending, encoding) = {
             let mut file = std::fs::File::open(path)?;
             let sample_size = file_size.min(8 * 1024);
             let mut sample = vec![0u8; sample_size];
             file.read_exact(&mut sample)?;
             let is_binary = Self::detect_binary(&sample);
             let line_ending = Self::detect_line_ending(&sample);
-            (is_binary, line_ending)
+            let encoding = Self::detect_encoding(&sample);
+            (is_binary, line_ending, encoding)
         };
 
+        // Files that need decoding can't be lazily loaded
+        if encoding != Encoding::Utf8 {
+            tracing::info!(
+                "Loading {} file eagerly: {}",
+                encoding.display_name(),
+                path.display()
+            );
+            return Self::load_small_file(path, Some(encoding));
+        }
+
         // Create an unloaded buffer that references the entire file
         let buffer = StringBuffer {
             id: 0,
@@ -325,6 +360,7 @@ impl TextBuffer {
             large_file: true,
             is_binary,
             line_ending,
+            encoding,
             saved_file_size: Some(file_size),
         })
     }
@@ -346,22 +382,72 @@ impl TextBuffer {
     /// This uses incremental saving for large files: instead of loading the entire
     /// file into memory, it streams unmodified regions directly from the source file
     /// and only keeps edited regions in memory.
+    ///
+    /// The file is replaced atomically where possible, keeping its permissions,
+    /// ownership and symlinks intact (see `file_write`).
     pub fn save_to_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
         let dest_path = path.as_ref();
-        let total = self.total_bytes();
 
-        if total == 0 {
-            // Empty file - just create it
-            std::fs::File::create(dest_path)?;
-            self.file_path = Some(dest_path.to_path_buf());
-            self.mark_saved_snapshot();
-            self.saved_file_size = Some(0);
-            return Ok(());
+        // Unloaded regions are streamed from the original file, so it must not be
+        // truncated before they are read
+        let allow_in_place = self.buffers.iter().all(|b| b.is_loaded());
+        let (written_path, method) =
+            file_write::write_file(dest_path, allow_in_place, |out| self.write_contents(out))?;
+        tracing::debug!(
+            "Buffer::save: wrote {} ({:?})",
+            written_path.display(),
+            method
+        );
+
+        // Update saved file size to match the file on disk
+        let new_size = std::fs::metadata(&written_path)?.len() as usize;
+        tracing::debug!(
+            "Buffer::save: updating saved_file_size from {:?} to {}",
+            self.saved_file_size,
+            new_size
+        );
+        self.saved_file_size = Some(new_size);
+
+        self.file_path = Some(dest_path.to_path_buf());
+        self.mark_saved_snapshot();
+        Ok(())
+    }
+
+    /// Mark the buffer as saved after its file was written by another process
+    /// (e.g. the privileged helper) with the output of `write_contents`
+    pub fn mark_saved_externally(&mut self) -> io::Result<()> {
+        let path = self.file_path.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "No file path associated with buffer",
+            )
+        })?;
+        self.saved_file_size = Some(std::fs::metadata(&path)?.len() as usize);
+        self.mark_saved_snapshot();
+        Ok(())
+    }
+
+    /// Write the file content as it should appear on disk (original line endings
+    /// and encoding) to `out`
+    pub fn write_contents<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        if !self.encoding.is_utf8() {
+            // Non-UTF-8 documents are encoded in memory. Buffers in such encodings
+            // are always fully loaded, but a lazily loaded UTF-8 file can't be converted.
+            let text = self.get_all_text().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!(
+                        "Cannot save a partially loaded large file as {}",
+                        self.encoding.display_name()
+                    ),
+                )
+            })?;
+            let converted = Self::convert_line_endings(&text, self.line_ending);
+            return out.write_all(&self.encoding.encode(&converted)?);
         }
 
-        // Use a temp file to avoid corrupting the original if something goes wrong
-        let temp_path = dest_path.with_extension("tmp");
-        let mut out_file = std::fs::File::create(&temp_path)?;
+        let total = self.total_bytes();
+        out.write_all(self.encoding.bom())?;
 
         // Cache for open source files (for streaming unloaded regions)
         let mut source_file_cache: Option<(PathBuf, std::fs::File)> = None;
@@ -383,7 +469,7 @@ impl TextBuffer {
                     let end = start + piece_view.bytes;
                     let chunk = &data[start..end];
                     let converted = Self::convert_line_endings(chunk, self.line_ending);
-                    out_file.write_all(&converted)?;
+                    out.write_all(&converted)?;
                 }
                 BufferData::Unloaded {
                     file_path,
@@ -414,31 +500,13 @@ impl TextBuffer {
                     while remaining > 0 {
                         let to_read = remaining.min(chunk_buf.len());
                         source_file.read_exact(&mut chunk_buf[..to_read])?;
-                        out_file.write_all(&chunk_buf[..to_read])?;
+                        out.write_all(&chunk_buf[..to_read])?;
                         remaining -= to_read;
                     }
                 }
             }
         }
 
-        // Ensure all data is written
-        out_file.sync_all()?;
-        drop(out_file);
-
-        // Atomically replace the original file
-        std::fs::rename(&temp_path, dest_path)?;
-
-        // Update saved file size to match the file on disk
-        let new_size = std::fs::metadata(dest_path)?.len() as usize;
-        tracing::debug!(
-            "Buffer::save: updating saved_file_size from {:?} to {}",
-            self.saved_file_size,
-            new_size
-        );
-        self.saved_file_size = Some(new_size);
-
-        self.file_path = Some(dest_path.to_path_buf());
-        self.mark_saved_snapshot();
         Ok(())
     }
 
@@ -1120,6 +1188,22 @@ impl TextBuffer {
             .unwrap_or_default()
     }
 
+    /// The text from `offset` to the end of the piece containing it, without
+    /// copying. Empty at the end of the buffer; None if the piece's data isn't
+    /// loaded. For reading the whole buffer chunk by chunk, e.g. for parsing.
+    pub(crate) fn chunk_at(&self, offset: usize) -> Option<&[u8]> {
+        let Some(piece) = self
+            .piece_tree
+            .iter_pieces_in_range(offset, offset + 1)
+            .next()
+        else {
+            return Some(&[]);
+        };
+        let data = self.buffers.get(piece.location.buffer_id())?.get_data()?;
+        let start = piece.buffer_offset + (offset - piece.doc_offset);
+        data.get(start..piece.buffer_offset + piece.bytes)
+    }
+
     /// Get all text as a String
     /// Returns None if any buffers are unloaded (lazy loading)
     pub fn to_string(&self) -> Option<String> {
@@ -1253,6 +1337,67 @@ impl TextBuffer {
         self.recovery_pending = true;
     }
 
+    /// Get the text encoding of the file on disk
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Set the text encoding used when saving this buffer
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding;
+        // Changing the encoding marks buffer as modified and needing recovery
+        self.modified = true;
+        self.recovery_pending = true;
+    }
+
+    /// Apply a preferred line ending and encoding (e.g. from `.editorconfig`)
+    /// without marking the buffer modified. The line ending takes effect on the
+    /// next save. The encoding is only adopted when the content reads the same in
+    /// it (ASCII or UTF-8 content), so a file is never reinterpreted.
+    pub fn apply_format_preferences(
+        &mut self,
+        line_ending: Option<LineEnding>,
+        encoding: Option<Encoding>,
+    ) {
+        if let Some(line_ending) = line_ending {
+            self.line_ending = line_ending;
+        }
+
+        let Some(encoding) = encoding else {
+            return;
+        };
+        let same_reading = if self.is_empty() {
+            true
+        } else if encoding.is_utf8() {
+            self.encoding.is_utf8()
+        } else {
+            self.encoding == Encoding::Utf8
+                && !encoding.is_utf16()
+                && self.to_string().is_some_and(|text| text.is_ascii())
+        };
+        if same_reading {
+            self.encoding = encoding;
+        }
+    }
+
+    /// Detect the text encoding of file content
+    ///
+    /// Binary content is never decoded (it is treated as UTF-8 so the raw
+    /// bytes are kept), except for UTF-16, where zero bytes are expected and
+    /// the decoded text is checked instead.
+    pub fn detect_encoding(bytes: &[u8]) -> Encoding {
+        let encoding = Encoding::detect(bytes);
+        if encoding.is_utf8() || !Self::detect_binary(bytes) {
+            return encoding;
+        }
+        let sample = &bytes[..bytes.len().min(8 * 1024)];
+        if encoding.is_utf16() && !Self::detect_binary(&encoding.decode(sample.to_vec())) {
+            encoding
+        } else {
+            Encoding::Utf8
+        }
+    }
+
     /// Detect if the given bytes contain binary content.
     ///
     /// Binary content is detected by looking for:
@@ -2394,6 +2539,22 @@ mod tests {
         assert_eq!(buffer.get_all_text().unwrap(), b"hello\nworld");
     }
 
+    #[test]
+    fn test_chunk_at() {
+        let mut buffer = TextBuffer::from_bytes(b"world".to_vec());
+        buffer.insert_bytes(0, b"hello ".to_vec());
+
+        let mut text = Vec::new();
+        while text.len() < buffer.len() {
+            let chunk = buffer.chunk_at(text.len()).unwrap();
+            assert!(!chunk.is_empty());
+            text.extend_from_slice(chunk);
+        }
+        assert_eq!(text, b"hello world");
+        assert_eq!(buffer.chunk_at(3).unwrap(), b"lo ");
+        assert_eq!(buffer.chunk_at(buffer.len()).unwrap(), b"");
+    }
+
     #[test]
     fn test_insert_at_start() {
         let mut buffer = TextBuffer::from_bytes(b"world".to_vec());
@@ -3487,6 +3648,89 @@ mod tests {
             "Content should start with our edit"
         );
     }
+
+    // ===== Encoding Tests =====
+
+    #[test]
+    fn test_utf16_file_roundtrip() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("resource.rc");
+        let original: Vec<u8> = b"\xFF\xFE"
+            .iter()
+            .copied()
+            .chain(
+                "caf\u{e9}\r\nok\r\n"
+                    .encode_utf16()
+                    .flat_map(|u| u.to_le_bytes()),
+            )
+            .collect();
+        std::fs::write(&file_path, &original).unwrap();
+
+        let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+        assert_eq!(buffer.encoding(), Encoding::Utf16Le);
+        assert_eq!(buffer.line_ending(), LineEnding::CRLF);
+        assert!(!buffer.is_binary());
+        assert_eq!(buffer.get_all_text().unwrap(), "caf\u{e9}\nok\n".as_bytes());
+
+        buffer.save().unwrap();
+        assert_eq!(std::fs::read(&file_path).unwrap(), original);
+
+        buffer.insert_bytes(0, b"!".to_vec());
+        buffer.save().unwrap();
+        let saved = std::fs::read(&file_path).unwrap();
+        assert_eq!(&saved[..4], b"\xFF\xFE!\x00");
+    }
+
+    #[test]
+    fn test_latin1_file_roundtrip_and_convert() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("legacy.conf");
+        std::fs::write(&file_path, b"name=Ren\xE9e\n").unwrap();
+
+        let mut buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+        assert_eq!(buffer.encoding(), Encoding::Latin1);
+        assert_eq!(
+            buffer.get_all_text().unwrap(),
+            "name=Ren\u{e9}e\n".as_bytes()
+        );
+        buffer.save().unwrap();
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"name=Ren\xE9e\n");
+
+        buffer.set_encoding(Encoding::Utf8);
+        assert!(buffer.is_modified());
+        buffer.save().unwrap();
+        assert_eq!(
+            std::fs::read(&file_path).unwrap(),
+            "name=Ren\u{e9}e\n".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_binary_file_is_not_decoded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("image.bin");
+        let data = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\xff\xfe";
+        std::fs::write(&file_path, data).unwrap();
+
+        let buffer = TextBuffer::load_from_file(&file_path, 0).unwrap();
+        assert!(buffer.is_binary());
+        assert_eq!(buffer.encoding(), Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_large_non_utf8_file_is_loaded_eagerly() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let file_path = temp_dir.path().join("large.txt");
+        std::fs::write(&file_path, b"\xEF\xBB\xBFhello world, this is large").unwrap();
+
+        let buffer = TextBuffer::load_from_file(&file_path, 10).unwrap();
+        assert_eq!(buffer.encoding(), Encoding::Utf8Bom);
+        assert!(!buffer.is_large_file());
+        assert_eq!(
+            buffer.get_all_text().unwrap(),
+            b"hello world, this is large"
+        );
+    }
 }
 
 #[cfg(test)]
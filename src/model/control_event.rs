@@ -46,7 +46,7 @@ pub mod events {
     pub const LSP_STATUS_CHANGED: EventDef = EventDef {
         name: "lsp:status_changed",
         description: "LSP server status changed",
-        data_schema_fn: || json!({"language": "string", "old_status": "string", "status": "string"}),
+        data_schema_fn: || json!({"language": "string", "server": "string", "old_status": "string", "status": "string"}),
     };
 
     /// Get all registered events (for schema generation)
@@ -0,0 +1,351 @@
+//! Text encodings for files on disk
+//!
+//! Buffers always hold UTF-8 internally. Files in other encodings are decoded
+//! when loaded and encoded back to their original encoding when saved, in the
+//! same way line endings are normalized to LF and restored on save.
+
+use std::io;
+
+/// Number of bytes sampled for encoding detection
+const DETECTION_SAMPLE_SIZE: usize = 64 * 1024;
+
+/// Text encoding of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// UTF-8 without a byte order mark (default for new files)
+    #[default]
+    Utf8,
+    /// UTF-8 with a byte order mark
+    Utf8Bom,
+    /// UTF-16 little endian (written with a byte order mark)
+    Utf16Le,
+    /// UTF-16 big endian (written with a byte order mark)
+    Utf16Be,
+    /// ISO-8859-1 / Windows-1252 (Western European)
+    Latin1,
+    /// Windows-1251 (Cyrillic)
+    Windows1251,
+    /// Shift-JIS (Japanese)
+    ShiftJis,
+    /// EUC-JP (Japanese)
+    EucJp,
+    /// EUC-KR (Korean)
+    EucKr,
+    /// GBK (Simplified Chinese)
+    Gbk,
+    /// Big5 (Traditional Chinese)
+    Big5,
+}
+
+impl Encoding {
+    /// All supported encodings, in the order they are offered to the user
+    pub const ALL: &'static [Encoding] = &[
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Latin1,
+        Encoding::Windows1251,
+        Encoding::ShiftJis,
+        Encoding::EucJp,
+        Encoding::EucKr,
+        Encoding::Gbk,
+        Encoding::Big5,
+    ];
+
+    /// Get the display name for the status bar and prompts
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Windows1251 => "Windows-1251",
+            Encoding::ShiftJis => "Shift-JIS",
+            Encoding::EucJp => "EUC-JP",
+            Encoding::EucKr => "EUC-KR",
+            Encoding::Gbk => "GBK",
+            Encoding::Big5 => "Big5",
+        }
+    }
+
+    /// Parse an encoding name as typed by the user (case, spaces, dashes and
+    /// underscores are ignored; common aliases are accepted)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized: String = name
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .flat_map(|c| c.to_lowercase())
+            .collect();
+        match normalized.as_str() {
+            "utf8" => Some(Encoding::Utf8),
+            "utf8bom" | "utf8sig" => Some(Encoding::Utf8Bom),
+            "utf16" | "utf16le" | "ucs2" => Some(Encoding::Utf16Le),
+            "utf16be" => Some(Encoding::Utf16Be),
+            "latin1" | "iso88591" | "windows1252" | "cp1252" => Some(Encoding::Latin1),
+            "windows1251" | "cp1251" => Some(Encoding::Windows1251),
+            "shiftjis" | "sjis" | "cp932" => Some(Encoding::ShiftJis),
+            "eucjp" => Some(Encoding::EucJp),
+            "euckr" | "cp949" => Some(Encoding::EucKr),
+            "gbk" | "gb2312" | "cp936" => Some(Encoding::Gbk),
+            "big5" => Some(Encoding::Big5),
+            _ => None,
+        }
+    }
+
+    /// Is this UTF-8 (with or without BOM)? UTF-8 content is used as-is, without decoding.
+    pub fn is_utf8(&self) -> bool {
+        matches!(self, Encoding::Utf8 | Encoding::Utf8Bom)
+    }
+
+    /// Is this UTF-16 (either byte order)?
+    pub fn is_utf16(&self) -> bool {
+        matches!(self, Encoding::Utf16Le | Encoding::Utf16Be)
+    }
+
+    /// Byte order mark written at the start of files in this encoding
+    pub fn bom(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8Bom => b"\xEF\xBB\xBF",
+            Encoding::Utf16Le => b"\xFF\xFE",
+            Encoding::Utf16Be => b"\xFE\xFF",
+            _ => b"",
+        }
+    }
+
+    /// The encoding_rs encoding used to decode/encode this encoding
+    fn codec(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => encoding_rs::UTF_8,
+            Encoding::Utf16Le => encoding_rs::UTF_16LE,
+            Encoding::Utf16Be => encoding_rs::UTF_16BE,
+            Encoding::Latin1 => encoding_rs::WINDOWS_1252,
+            Encoding::Windows1251 => encoding_rs::WINDOWS_1251,
+            Encoding::ShiftJis => encoding_rs::SHIFT_JIS,
+            Encoding::EucJp => encoding_rs::EUC_JP,
+            Encoding::EucKr => encoding_rs::EUC_KR,
+            Encoding::Gbk => encoding_rs::GBK,
+            Encoding::Big5 => encoding_rs::BIG5,
+        }
+    }
+
+    /// Map an encoding guessed by chardetng to a supported encoding.
+    /// Anything unsupported falls back to Latin-1, which round-trips every byte.
+    fn from_codec(codec: &'static encoding_rs::Encoding) -> Self {
+        Encoding::ALL
+            .iter()
+            .copied()
+            .find(|e| !e.is_utf8() && e.codec() == codec)
+            .unwrap_or(if codec == encoding_rs::UTF_8 {
+                Encoding::Utf8
+            } else {
+                Encoding::Latin1
+            })
+    }
+
+    /// Detect the encoding of file content
+    ///
+    /// Detection order:
+    /// 1. Byte order mark (UTF-8, UTF-16 LE/BE)
+    /// 2. UTF-16 without BOM (mostly-ASCII text has a zero byte in every other position)
+    /// 3. Valid UTF-8
+    /// 4. Legacy encoding heuristics (chardetng), falling back to Latin-1
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(Encoding::Utf8Bom.bom()) {
+            return Encoding::Utf8Bom;
+        }
+        if bytes.starts_with(Encoding::Utf16Le.bom()) {
+            return Encoding::Utf16Le;
+        }
+        if bytes.starts_with(Encoding::Utf16Be.bom()) {
+            return Encoding::Utf16Be;
+        }
+
+        let sample = &bytes[..bytes.len().min(DETECTION_SAMPLE_SIZE)];
+        if let Some(utf16) = Self::detect_utf16_without_bom(sample) {
+            return utf16;
+        }
+
+        // A sample may end in the middle of a multi-byte sequence; that is still UTF-8
+        match std::str::from_utf8(sample) {
+            Ok(_) => return Encoding::Utf8,
+            Err(e) if e.error_len().is_none() && sample.len() < bytes.len() => {
+                return Encoding::Utf8
+            }
+            Err(_) => {}
+        }
+
+        let mut detector = chardetng::EncodingDetector::new();
+        detector.feed(sample, sample.len() == bytes.len());
+        Self::from_codec(detector.guess(None, false))
+    }
+
+    /// Detect BOM-less UTF-16 by the distribution of zero bytes
+    fn detect_utf16_without_bom(sample: &[u8]) -> Option<Self> {
+        let pairs = sample.len() / 2;
+        if pairs < 2 {
+            return None;
+        }
+        let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+        let odd_zeros = sample
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count();
+
+        // At least 40% zeros on one side, almost none on the other
+        let mostly = |zeros: usize| zeros * 10 >= pairs * 4;
+        let hardly = |zeros: usize| zeros * 20 <= pairs;
+        if mostly(odd_zeros) && hardly(even_zeros) {
+            Some(Encoding::Utf16Le)
+        } else if mostly(even_zeros) && hardly(odd_zeros) {
+            Some(Encoding::Utf16Be)
+        } else {
+            None
+        }
+    }
+
+    /// Decode file content in this encoding to UTF-8, stripping the BOM if present
+    ///
+    /// UTF-8 content is returned unchanged (apart from the BOM), so invalid
+    /// sequences are preserved. Malformed input in other encodings is replaced
+    /// with U+FFFD.
+    pub fn decode(&self, bytes: Vec<u8>) -> Vec<u8> {
+        let bom = self.bom();
+        let has_bom = !bom.is_empty() && bytes.starts_with(bom);
+        if self.is_utf8() {
+            return if has_bom {
+                bytes[bom.len()..].to_vec()
+            } else {
+                bytes
+            };
+        }
+
+        let content = if has_bom { &bytes[bom.len()..] } else { &bytes };
+        let (decoded, had_errors) = self.codec().decode_without_bom_handling(content);
+        if had_errors {
+            tracing::warn!(
+                "File content is not valid {}; invalid sequences were replaced",
+                self.display_name()
+            );
+        }
+        decoded.into_owned().into_bytes()
+    }
+
+    /// Encode UTF-8 text to this encoding, including the BOM
+    ///
+    /// Returns an `InvalidData` error if the text contains characters that
+    /// cannot be represented in this encoding.
+    pub fn encode(&self, text: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = self.bom().to_vec();
+        if self.is_utf8() {
+            out.extend_from_slice(text);
+            return Ok(out);
+        }
+
+        let text = String::from_utf8_lossy(text);
+        match self {
+            // encoding_rs only encodes to UTF-8 for UTF-16 encodings, so encode by hand
+            Encoding::Utf16Le => {
+                out.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+            }
+            Encoding::Utf16Be => {
+                out.extend(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+            }
+            _ => {
+                let (encoded, _, had_unmappable) = self.codec().encode(&text);
+                if had_unmappable {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "Buffer contains characters that cannot be encoded as {}",
+                            self.display_name()
+                        ),
+                    ));
+                }
+                out.extend_from_slice(&encoded);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_bom() {
+        assert_eq!(Encoding::detect(b"\xEF\xBB\xBFhello"), Encoding::Utf8Bom);
+        assert_eq!(Encoding::detect(b"\xFF\xFEh\x00i\x00"), Encoding::Utf16Le);
+        assert_eq!(Encoding::detect(b"\xFE\xFF\x00h\x00i"), Encoding::Utf16Be);
+    }
+
+    #[test]
+    fn test_detect_without_bom() {
+        assert_eq!(Encoding::detect(b"plain ascii"), Encoding::Utf8);
+        assert_eq!(Encoding::detect("caf\u{e9}".as_bytes()), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b""), Encoding::Utf8);
+        assert_eq!(
+            Encoding::detect(b"h\x00e\x00l\x00l\x00o\x00"),
+            Encoding::Utf16Le
+        );
+        assert_eq!(
+            Encoding::detect(b"\x00h\x00e\x00l\x00l\x00o"),
+            Encoding::Utf16Be
+        );
+        // "café au lait, déjà vu" in Latin-1
+        assert_eq!(
+            Encoding::detect(b"caf\xE9 au lait, d\xE9j\xE0 vu"),
+            Encoding::Latin1
+        );
+    }
+
+    #[test]
+    fn test_detect_shift_jis() {
+        let (encoded, _, _) = encoding_rs::SHIFT_JIS
+            .encode("日本語のテキストファイルです。これはテストです。ひらがなとカタカナ。");
+        assert_eq!(Encoding::detect(&encoded), Encoding::ShiftJis);
+    }
+
+    #[test]
+    fn test_decode_encode_roundtrip() {
+        for (encoding, raw) in [
+            (Encoding::Utf8Bom, b"\xEF\xBB\xBFhi\n".to_vec()),
+            (Encoding::Utf16Le, b"\xFF\xFEh\x00\xE9\x00\n\x00".to_vec()),
+            (Encoding::Utf16Be, b"\xFE\xFF\x00h\x00\xE9\x00\n".to_vec()),
+            (Encoding::Latin1, b"caf\xE9\n".to_vec()),
+        ] {
+            let decoded = encoding.decode(raw.clone());
+            assert!(std::str::from_utf8(&decoded).is_ok(), "{:?}", encoding);
+            assert_eq!(encoding.encode(&decoded).unwrap(), raw, "{:?}", encoding);
+        }
+        assert_eq!(
+            Encoding::Latin1.decode(b"caf\xE9".to_vec()),
+            "café".as_bytes()
+        );
+    }
+
+    #[test]
+    fn test_encode_unmappable_fails() {
+        let err = Encoding::Latin1.encode("日本".as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(Encoding::from_name("utf-16le"), Some(Encoding::Utf16Le));
+        assert_eq!(Encoding::from_name("ISO-8859-1"), Some(Encoding::Latin1));
+        assert_eq!(Encoding::from_name("Shift_JIS"), Some(Encoding::ShiftJis));
+        assert_eq!(Encoding::from_name("UTF-8 BOM"), Some(Encoding::Utf8Bom));
+        assert_eq!(Encoding::from_name("klingon"), None);
+        for encoding in Encoding::ALL {
+            assert_eq!(
+                Encoding::from_name(encoding.display_name()),
+                Some(*encoding)
+            );
+        }
+    }
+}
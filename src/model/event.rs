@@ -507,12 +507,59 @@ pub struct Snapshot {
     pub cursor_positions: Vec<(CursorId, usize, Option<usize>)>,
 }
 
-/// The event log - append-only log of all events
+/// Identifier of a node in the undo tree (index in creation order)
+pub type UndoNodeId = usize;
+
+/// A node in the undo tree
+///
+/// Each node holds one logged event. The path from the root to a node
+/// describes the sequence of events that produced the state after that node.
+#[derive(Debug, Clone)]
+pub struct UndoNode {
+    /// The logged event
+    pub entry: LogEntry,
+
+    /// Parent node (None if this node hangs directly off the root)
+    pub parent: Option<UndoNodeId>,
+
+    /// Child nodes, in creation order. More than one child means history branched here.
+    pub children: Vec<UndoNodeId>,
+}
+
+/// A node of a serialized undo tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoTreeNodeData {
+    /// Parent node (always an earlier node; None for the root)
+    pub parent: Option<UndoNodeId>,
+    /// The logged event
+    pub entry: LogEntry,
+}
+
+/// Serializable form of an undo tree, used to persist history across editor restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoTreeData {
+    /// Nodes in creation order
+    pub nodes: Vec<UndoTreeNodeData>,
+    /// Node matching the file contents on disk (None for the root)
+    pub saved_node: Option<UndoNodeId>,
+}
+
+/// The event log - an undo tree of all events
+///
+/// Editing after an undo does not discard the undone events: they stay in the
+/// tree as a separate branch. Undo/redo walk the *active branch*, a path from
+/// the root to the tip of the branch that redo currently follows.
 pub struct EventLog {
-    /// All logged events
-    entries: Vec<LogEntry>,
+    /// All logged events, indexed by `UndoNodeId`
+    nodes: Vec<UndoNode>,
+
+    /// Nodes hanging directly off the root (first events of each top-level branch)
+    root_children: Vec<UndoNodeId>,
 
-    /// Current position in the log (for undo/redo)
+    /// The active branch: node ids from the root to the tip that redo follows
+    path: Vec<UndoNodeId>,
+
+    /// Current position in the active branch (for undo/redo)
     current_index: usize,
 
     /// Periodic snapshots for fast seeking
@@ -524,50 +571,45 @@ pub struct EventLog {
     /// Optional file for streaming events to disk
     stream_file: Option<std::fs::File>,
 
-    /// Index at which the buffer was last saved (for tracking modified status)
-    /// When current_index equals saved_at_index, the buffer is not modified
-    saved_at_index: Option<usize>,
+    /// Node at which the buffer was last saved (for tracking modified status)
+    /// `Some(None)` is the root, i.e. the state before any event
+    saved_at: Option<Option<UndoNodeId>>,
 }
 
 impl EventLog {
     /// Create a new empty event log
     pub fn new() -> Self {
         Self {
-            entries: Vec::new(),
+            nodes: Vec::new(),
+            root_children: Vec::new(),
+            path: Vec::new(),
             current_index: 0,
             snapshots: Vec::new(),
             snapshot_interval: 100,
             stream_file: None,
-            saved_at_index: Some(0), // New buffer starts at "saved" state (index 0)
+            saved_at: Some(None), // New buffer starts at "saved" state (the root)
         }
     }
 
     /// Mark the current position as the saved point
     /// Call this when the buffer is saved to disk
     pub fn mark_saved(&mut self) {
-        self.saved_at_index = Some(self.current_index);
+        self.saved_at = Some(self.current_node());
     }
 
     /// Check if the buffer is at the saved position (not modified)
     /// Returns true if we're at the saved position OR if all events between
-    /// saved_at_index and current_index are readonly (don't modify buffer content)
+    /// the saved node and the current node are readonly (don't modify buffer content).
+    /// The saved node may be on another branch of the undo tree.
     pub fn is_at_saved_position(&self) -> bool {
-        match self.saved_at_index {
+        match self.saved_at {
             None => false,
-            Some(saved_idx) if saved_idx == self.current_index => true,
-            Some(saved_idx) => {
-                // Check if all events between saved position and current position
-                // are readonly (don't modify buffer content)
-                let (start, end) = if saved_idx < self.current_index {
-                    (saved_idx, self.current_index)
-                } else {
-                    (self.current_index, saved_idx)
-                };
-
-                // All events in range [start, end) must be readonly
-                self.entries[start..end]
-                    .iter()
-                    .all(|entry| !entry.event.modifies_buffer())
+            Some(saved) if saved == self.current_node() => true,
+            Some(saved) => {
+                let (up, down) = self.path_between(saved, self.current_node());
+                up.iter()
+                    .chain(down.iter())
+                    .all(|&id| !self.nodes[id].entry.event.modifies_buffer())
             }
         }
     }
@@ -647,18 +689,19 @@ impl EventLog {
     }
 
     /// Append an event to the log
+    ///
+    /// If we're not at the tip of the active branch, the undone events are kept
+    /// as a separate branch and the new event starts a new one.
     pub fn append(&mut self, event: Event) -> usize {
-        // If we're not at the end, truncate future events
-        if self.current_index < self.entries.len() {
-            self.entries.truncate(self.current_index);
-        }
+        // Leave the undone events in the tree; the active branch ends here now
+        self.path.truncate(self.current_index);
 
         // Stream event to file if enabled
         if let Some(ref mut file) = self.stream_file {
             use std::io::Write;
 
             let stream_entry = serde_json::json!({
-                "index": self.entries.len(),
+                "index": self.path.len(),
                 "timestamp": chrono::Local::now().to_rfc3339(),
                 "event": event,
             });
@@ -672,12 +715,11 @@ impl EventLog {
             }
         }
 
-        let entry = LogEntry::new(event);
-        self.entries.push(entry);
-        self.current_index = self.entries.len();
+        self.push_node(LogEntry::new(event));
+        self.current_index = self.path.len();
 
         // Check if we should create a snapshot
-        if self.entries.len() % self.snapshot_interval == 0 {
+        if self.path.len() % self.snapshot_interval == 0 {
             // Snapshot creation will be implemented when we have Buffer
             // For now, just track that we'd create one here
         }
@@ -685,14 +727,75 @@ impl EventLog {
         self.current_index - 1
     }
 
+    /// Add a node as a child of the current tip of the active branch and extend the branch
+    fn push_node(&mut self, entry: LogEntry) -> UndoNodeId {
+        let id = self.nodes.len();
+        let parent = self.path.last().copied();
+        self.nodes.push(UndoNode {
+            entry,
+            parent,
+            children: Vec::new(),
+        });
+        match parent {
+            Some(parent) => self.nodes[parent].children.push(id),
+            None => self.root_children.push(id),
+        }
+        self.path.push(id);
+        id
+    }
+
+    /// Combine the events appended since the current index was `start_index`
+    /// (see `current_index`) into one batch, so that a single undo reverts them all.
+    /// Returns false, leaving the log unchanged, if there are fewer than two
+    /// or they are no longer the newest events at the tip of the active branch
+    /// (something was undone in between).
+    pub fn group_since(&mut self, start_index: usize, description: String) -> bool {
+        if self.current_index != self.path.len() || start_index + 2 > self.path.len() {
+            return false;
+        }
+        let first = self.nodes.len() - (self.path.len() - start_index);
+        if !self.path[start_index..]
+            .iter()
+            .enumerate()
+            .all(|(i, &id)| id == first + i)
+        {
+            return false;
+        }
+
+        let events: Vec<Event> = self
+            .nodes
+            .drain(first..)
+            .map(|node| node.entry.event)
+            // A batch can only be undone if all of its events can; undo skips
+            // the others (viewport changes) anyway
+            .filter(|event| event.inverse().is_some())
+            .collect();
+        self.path.truncate(start_index);
+        match self.path.last() {
+            Some(&parent) => self.nodes[parent].children.retain(|&id| id != first),
+            None => self.root_children.retain(|&id| id != first),
+        }
+        // A save in the middle of the group leaves no node to be saved at
+        if matches!(self.saved_at, Some(Some(id)) if id >= first) {
+            self.saved_at = None;
+        }
+
+        self.push_node(LogEntry::new(Event::Batch {
+            events,
+            description,
+        }));
+        self.current_index = self.path.len();
+        true
+    }
+
     /// Get the current event index
     pub fn current_index(&self) -> usize {
         self.current_index
     }
 
-    /// Get the number of events in the log
+    /// Get the number of events in the active branch
     pub fn len(&self) -> usize {
-        self.entries.len()
+        self.path.len()
     }
 
     /// Can we undo?
@@ -702,7 +805,7 @@ impl EventLog {
 
     /// Can we redo?
     pub fn can_redo(&self) -> bool {
-        self.current_index < self.entries.len()
+        self.current_index < self.path.len()
     }
 
     /// Move back through events (for undo)
@@ -715,7 +818,7 @@ impl EventLog {
         // Keep moving backward until we find a write action
         while self.can_undo() && !found_write_action {
             self.current_index -= 1;
-            let event = &self.entries[self.current_index].event;
+            let event = &self.nodes[self.path[self.current_index]].entry.event;
 
             // Check if this is a write action - we'll stop after processing it
             if event.is_write_action() {
@@ -741,7 +844,10 @@ impl EventLog {
 
         // Keep moving forward to collect write action and subsequent readonly events
         while self.can_redo() {
-            let event = self.entries[self.current_index].event.clone();
+            let event = self.nodes[self.path[self.current_index]]
+                .entry
+                .event
+                .clone();
 
             // If we've already found a write action and this is another write action, stop
             if found_write_action && event.is_write_action() {
@@ -762,39 +868,249 @@ impl EventLog {
         events
     }
 
-    /// Get all events from the log
-    pub fn entries(&self) -> &[LogEntry] {
-        &self.entries
+    /// Get all events in the active branch
+    pub fn entries(&self) -> Vec<&LogEntry> {
+        self.path.iter().map(|&id| &self.nodes[id].entry).collect()
     }
 
-    /// Get events in a range
-    pub fn range(&self, range: Range<usize>) -> &[LogEntry] {
-        &self.entries[range]
+    /// Get events of the active branch in a range
+    pub fn range(&self, range: Range<usize>) -> Vec<&LogEntry> {
+        self.path[range]
+            .iter()
+            .map(|&id| &self.nodes[id].entry)
+            .collect()
     }
 
     /// Get the most recent event
     pub fn last_event(&self) -> Option<&Event> {
-        if self.current_index > 0 {
-            Some(&self.entries[self.current_index - 1].event)
-        } else {
-            None
-        }
+        self.current_node().map(|id| &self.nodes[id].entry.event)
     }
 
     /// Clear all events (for testing or reset)
     pub fn clear(&mut self) {
-        self.entries.clear();
+        self.nodes.clear();
+        self.root_children.clear();
+        self.path.clear();
         self.current_index = 0;
         self.snapshots.clear();
     }
 
-    /// Save event log to JSON Lines format
+    /// All nodes of the undo tree, indexed by `UndoNodeId`
+    pub fn nodes(&self) -> &[UndoNode] {
+        &self.nodes
+    }
+
+    /// Nodes hanging directly off the root of the undo tree
+    pub fn root_children(&self) -> &[UndoNodeId] {
+        &self.root_children
+    }
+
+    /// The node the buffer is currently at (None for the root)
+    pub fn current_node(&self) -> Option<UndoNodeId> {
+        self.current_index.checked_sub(1).map(|i| self.path[i])
+    }
+
+    /// Children of a node (or of the root for None)
+    fn children_of(&self, node: Option<UndoNodeId>) -> &[UndoNodeId] {
+        match node {
+            Some(id) => &self.nodes[id].children,
+            None => &self.root_children,
+        }
+    }
+
+    /// Ancestors of a node from the root down to and including the node itself
+    fn ancestry(&self, node: Option<UndoNodeId>) -> Vec<UndoNodeId> {
+        let mut chain = Vec::new();
+        let mut cursor = node;
+        while let Some(id) = cursor {
+            chain.push(id);
+            cursor = self.nodes[id].parent;
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// Nodes passed when walking the tree from `from` to `to`.
+    /// Returns (nodes walked up from `from` towards the common ancestor, nearest first;
+    /// nodes walked down from the common ancestor to `to`, in order).
+    fn path_between(
+        &self,
+        from: Option<UndoNodeId>,
+        to: Option<UndoNodeId>,
+    ) -> (Vec<UndoNodeId>, Vec<UndoNodeId>) {
+        let from_chain = self.ancestry(from);
+        let to_chain = self.ancestry(to);
+        let common = from_chain
+            .iter()
+            .zip(to_chain.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let up = from_chain[common..].iter().rev().copied().collect();
+        let down = to_chain[common..].to_vec();
+        (up, down)
+    }
+
+    /// Rebuild the active branch so it runs through `node`, continuing past it
+    /// along the most recently created children.
+    fn set_active_branch(&mut self, node: Option<UndoNodeId>) {
+        self.path = self.ancestry(node);
+        self.current_index = self.path.len();
+        let mut tip = node;
+        while let Some(&newest) = self.children_of(tip).last() {
+            self.path.push(newest);
+            tip = Some(newest);
+        }
+    }
+
+    /// Move the buffer to any node of the undo tree.
+    /// Returns the events to apply: inverses of the events walked back over,
+    /// followed by the events walked forward over.
+    pub fn jump_to(&mut self, target: Option<UndoNodeId>) -> Vec<Event> {
+        let (up, down) = self.path_between(self.current_node(), target);
+        let mut events: Vec<Event> = up
+            .iter()
+            .filter_map(|&id| self.nodes[id].entry.event.inverse())
+            .collect();
+        events.extend(down.iter().map(|&id| self.nodes[id].entry.event.clone()));
+        self.set_active_branch(target);
+        events
+    }
+
+    /// Number of redo branches at the current position, and the index of the one redo follows
+    pub fn redo_branches(&self) -> (usize, usize) {
+        let children = self.children_of(self.current_node());
+        let selected = self
+            .path
+            .get(self.current_index)
+            .and_then(|next| children.iter().position(|c| c == next))
+            .unwrap_or(0);
+        (children.len(), selected)
+    }
+
+    /// Make redo follow the next branch at the current position (wrapping around).
+    /// Does not change the buffer. Returns false if there is nothing to switch to.
+    pub fn switch_redo_branch(&mut self) -> bool {
+        let (count, selected) = self.redo_branches();
+        if count < 2 {
+            return false;
+        }
+        let next = self.children_of(self.current_node())[(selected + 1) % count];
+        let current_index = self.current_index;
+        self.set_active_branch(Some(next));
+        self.current_index = current_index;
+        true
+    }
+
+    /// The state the buffer content is in: the nearest write node at or above the
+    /// current node (None for the root). Readonly events don't create new states.
+    pub fn current_state(&self) -> Option<UndoNodeId> {
+        let mut cursor = self.current_node();
+        while let Some(id) = cursor {
+            if self.nodes[id].entry.event.is_write_action() {
+                return Some(id);
+            }
+            cursor = self.nodes[id].parent;
+        }
+        None
+    }
+
+    /// Write nodes in creation (chronological) order
+    fn states(&self) -> impl DoubleEndedIterator<Item = UndoNodeId> + '_ {
+        (0..self.nodes.len()).filter(|&id| self.nodes[id].entry.event.is_write_action())
+    }
+
+    /// The state created just before the current one, across all branches.
+    /// Returns None if already at the root.
+    pub fn earlier_state(&self) -> Option<Option<UndoNodeId>> {
+        let current = self.current_state()?;
+        Some(self.states().rev().find(|&id| id < current))
+    }
+
+    /// The state created just after the current one, across all branches
+    pub fn later_state(&self) -> Option<UndoNodeId> {
+        match self.current_state() {
+            Some(current) => self.states().find(|&id| id > current),
+            None => self.states().next(),
+        }
+    }
+
+    /// The most recently created state whose event happened at or before `timestamp`
+    /// (milliseconds since epoch). Returns None (the root) if every state is newer.
+    pub fn state_at_time(&self, timestamp: u64) -> Option<UndoNodeId> {
+        self.states()
+            .rev()
+            .find(|&id| self.nodes[id].entry.timestamp <= timestamp)
+    }
+
+    /// Export the whole undo tree for persisting, positioned at the saved node.
+    ///
+    /// With `compact`, only events that modify buffer content are kept (cursor
+    /// movement, scrolling and similar readonly events are dropped), which keeps
+    /// histories of large files small. Returns None if there is no saved position.
+    pub fn export_tree(&self, compact: bool) -> Option<UndoTreeData> {
+        let saved = self.saved_at?;
+        let keep = |id: UndoNodeId| !compact || self.nodes[id].entry.event.modifies_buffer();
+
+        // New id of the nearest kept ancestor-or-self of every node
+        let mut nearest_kept: Vec<Option<UndoNodeId>> = Vec::with_capacity(self.nodes.len());
+        let mut nodes = Vec::new();
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            let parent = node.parent.and_then(|p| nearest_kept[p]);
+            if keep(id) {
+                let mut entry = node.entry.clone();
+                if compact {
+                    if let Event::Batch { events, .. } = &mut entry.event {
+                        events.retain(|e| e.modifies_buffer());
+                    }
+                }
+                nodes.push(UndoTreeNodeData { parent, entry });
+                nearest_kept.push(Some(nodes.len() - 1));
+            } else {
+                nearest_kept.push(parent);
+            }
+        }
+
+        Some(UndoTreeData {
+            nodes,
+            saved_node: saved.and_then(|id| nearest_kept[id]),
+        })
+    }
+
+    /// Rebuild an event log from a persisted undo tree, positioned at (and marked
+    /// saved at) the saved node. Returns None if the data is inconsistent.
+    pub fn from_tree(data: UndoTreeData) -> Option<Self> {
+        let mut log = Self::new();
+        for (id, node) in data.nodes.into_iter().enumerate() {
+            if node.parent.is_some_and(|parent| parent >= id) {
+                return None;
+            }
+            log.nodes.push(UndoNode {
+                entry: node.entry,
+                parent: node.parent,
+                children: Vec::new(),
+            });
+            match node.parent {
+                Some(parent) => log.nodes[parent].children.push(id),
+                None => log.root_children.push(id),
+            }
+        }
+        if data.saved_node.is_some_and(|id| id >= log.nodes.len()) {
+            return None;
+        }
+
+        log.set_active_branch(data.saved_node);
+        log.saved_at = Some(data.saved_node);
+        Some(log)
+    }
+
+    /// Save the active branch of the event log to JSON Lines format
     pub fn save_to_file(&self, path: &std::path::Path) -> std::io::Result<()> {
         use std::io::Write;
         let file = std::fs::File::create(path)?;
         let mut writer = std::io::BufWriter::new(file);
 
-        for entry in &self.entries {
+        for entry in self.entries() {
             let json = serde_json::to_string(entry)?;
             writeln!(writer, "{json}")?;
         }
@@ -816,10 +1132,10 @@ impl EventLog {
                 continue;
             }
             let entry: LogEntry = serde_json::from_str(&line)?;
-            log.entries.push(entry);
+            log.push_node(entry);
         }
 
-        log.current_index = log.entries.len();
+        log.current_index = log.path.len();
 
         Ok(log)
     }
@@ -1048,4 +1364,200 @@ mod tests {
         assert_eq!(log.entries().len(), 2);
         assert_eq!(log.current_index(), 2);
     }
+
+    fn insert(position: usize, text: &str) -> Event {
+        Event::Insert {
+            position,
+            text: text.to_string(),
+            cursor_id: CursorId(0),
+        }
+    }
+
+    #[test]
+    fn test_edit_after_undo_keeps_branch() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+
+        log.undo();
+        log.append(insert(1, "c"));
+
+        // Both "b" and "c" are kept as children of "a"
+        assert_eq!(log.nodes().len(), 3);
+        assert_eq!(log.nodes()[0].children, vec![1, 2]);
+        assert_eq!(log.current_node(), Some(2));
+
+        // Walking back to "b" undoes "c" and redoes "b"
+        let events = log.jump_to(Some(1));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], Event::Delete { deleted_text, .. } if deleted_text == "c"));
+        assert!(matches!(&events[1], Event::Insert { text, .. } if text == "b"));
+        assert_eq!(log.current_node(), Some(1));
+        assert!(!log.can_redo());
+    }
+
+    #[test]
+    fn test_group_since() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.append(Event::Scroll { line_offset: 1 });
+        log.append(insert(2, "c"));
+        assert!(log.group_since(1, "Macro".to_string()));
+
+        assert_eq!(log.nodes().len(), 2);
+        assert_eq!(log.nodes()[0].children, vec![1]);
+        assert!(
+            matches!(&log.nodes()[1].entry.event, Event::Batch { events, .. } if events.len() == 3)
+        );
+
+        // One undo reverts the whole group
+        let undone = log.undo();
+        assert_eq!(undone.len(), 1);
+        assert_eq!(log.current_node(), Some(0));
+
+        // Groups need two events at the tip of the active branch
+        assert!(!log.group_since(0, "Macro".to_string()));
+        log.redo();
+        assert!(!log.group_since(1, "Macro".to_string()));
+    }
+
+    #[test]
+    fn test_group_since_after_undo() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+
+        // The undone "b" is cut off by the first grouped event
+        let start = log.current_index();
+        log.append(insert(1, "c"));
+        log.append(insert(2, "d"));
+        assert!(log.group_since(start, "Macro".to_string()));
+
+        log.undo();
+        assert_eq!(log.current_node(), Some(0));
+    }
+
+    #[test]
+    fn test_switch_redo_branch() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.undo();
+        log.append(insert(0, "b"));
+        log.undo();
+
+        // Redo follows the most recent branch by default
+        assert_eq!(log.redo_branches(), (2, 1));
+        assert!(log.switch_redo_branch());
+        assert_eq!(log.redo_branches(), (2, 0));
+
+        let events = log.redo();
+        assert!(matches!(&events[0], Event::Insert { text, .. } if text == "a"));
+        assert_eq!(log.current_node(), Some(0));
+        assert!(!log.switch_redo_branch());
+    }
+
+    #[test]
+    fn test_chronological_states() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(Event::Scroll { line_offset: 1 });
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+
+        // States are write nodes: 0 ("a"), 2 ("b"), 3 ("c")
+        assert_eq!(log.current_state(), Some(3));
+        assert_eq!(log.earlier_state(), Some(Some(2)));
+        assert_eq!(log.later_state(), None);
+
+        log.jump_to(Some(0));
+        assert_eq!(log.earlier_state(), Some(None));
+        assert_eq!(log.later_state(), Some(2));
+
+        log.jump_to(None);
+        assert_eq!(log.earlier_state(), None);
+    }
+
+    #[test]
+    fn test_state_at_time() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.nodes[0].entry.timestamp = 1_000;
+        log.nodes[1].entry.timestamp = 5_000;
+
+        assert_eq!(log.state_at_time(500), None);
+        assert_eq!(log.state_at_time(1_000), Some(0));
+        assert_eq!(log.state_at_time(4_999), Some(0));
+        assert_eq!(log.state_at_time(10_000), Some(1));
+    }
+
+    #[test]
+    fn test_saved_position_across_branches() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.mark_saved();
+        log.undo();
+        log.append(insert(0, "b"));
+        assert!(!log.is_at_saved_position());
+
+        // Going back to the saved node on the other branch is unmodified again
+        log.jump_to(Some(0));
+        assert!(log.is_at_saved_position());
+    }
+
+    #[test]
+    fn test_export_and_restore_tree() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(insert(1, "b"));
+        log.undo();
+        log.append(insert(1, "c"));
+        log.mark_saved();
+
+        let data = log.export_tree(false).unwrap();
+        let json = serde_json::to_string(&data).unwrap();
+        let restored = EventLog::from_tree(serde_json::from_str(&json).unwrap()).unwrap();
+
+        assert_eq!(restored.nodes().len(), 3);
+        assert_eq!(restored.current_node(), Some(2));
+        assert!(restored.is_at_saved_position());
+        assert!(restored.can_undo());
+        assert_eq!(restored.nodes()[0].children, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_compact_export_drops_readonly_events() {
+        let mut log = EventLog::new();
+        log.append(insert(0, "a"));
+        log.append(Event::Scroll { line_offset: 3 });
+        log.append(insert(1, "b"));
+        log.append(Event::Scroll { line_offset: -3 });
+        log.mark_saved();
+
+        let data = log.export_tree(true).unwrap();
+        assert_eq!(data.nodes.len(), 2);
+        assert_eq!(data.nodes[1].parent, Some(0));
+        // The saved node was a readonly event; it maps to the last edit before it
+        assert_eq!(data.saved_node, Some(1));
+
+        let mut restored = EventLog::from_tree(data).unwrap();
+        assert!(restored.is_at_saved_position());
+        let events = restored.undo();
+        assert!(matches!(&events[0], Event::Delete { deleted_text, .. } if deleted_text == "b"));
+    }
+
+    #[test]
+    fn test_from_tree_rejects_bad_parents() {
+        let data = UndoTreeData {
+            nodes: vec![UndoTreeNodeData {
+                parent: Some(0),
+                entry: LogEntry::new(insert(0, "a")),
+            }],
+            saved_node: None,
+        };
+        assert!(EventLog::from_tree(data).is_none());
+    }
 }
@@ -0,0 +1,386 @@
+//! Safe file writing for buffer saves
+//!
+//! Saving writes the new content to a temporary file next to the target and
+//! renames it over the original, so a failed save never leaves a half-written
+//! file behind. To make that transparent to the user:
+//!
+//! - Symlinks are resolved, so the link target is replaced and the link is kept
+//! - The temporary file gets the original's permissions, ownership and (on
+//!   Linux) extended attributes before it is renamed into place
+//! - The temporary file has a unique name, so it never clobbers a real sibling
+//!
+//! When an atomic replace would lose something (the file has other hard links,
+//! its ownership can't be reproduced, or the directory isn't writable), the
+//! content is written in place instead.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Maximum number of symlinks followed when resolving a save target
+const MAX_SYMLINK_DEPTH: usize = 40;
+
+/// Counter making temporary file names unique within this process
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// How a file was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMethod {
+    /// Written to a temporary file and renamed over the target
+    AtomicReplace,
+    /// The target was truncated and overwritten
+    InPlace,
+}
+
+/// Write a file, preserving the metadata and identity of an existing file
+///
+/// `write_content` is called exactly once with the file to write to.
+/// If `allow_in_place` is false, the file is always replaced atomically (hard
+/// links are broken, and an unwritable directory is an error); use this when
+/// `write_content` reads from the target file itself.
+/// Returns the path that was actually written (the symlink target, if `path`
+/// is a symlink) and how it was written.
+pub fn write_file<F>(
+    path: &Path,
+    allow_in_place: bool,
+    write_content: F,
+) -> io::Result<(PathBuf, WriteMethod)>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let target = resolve_symlinks(path)?;
+    let existing = fs::metadata(&target).ok();
+
+    if allow_in_place && existing.as_ref().is_some_and(has_other_hard_links) {
+        tracing::debug!(
+            "{} has multiple hard links, writing in place",
+            target.display()
+        );
+        write_in_place(&target, write_content)?;
+        return Ok((target, WriteMethod::InPlace));
+    }
+
+    let (temp_path, mut temp_file) = match create_temp_file(&target) {
+        Ok(temp) => temp,
+        Err(e) if allow_in_place && e.kind() == io::ErrorKind::PermissionDenied => {
+            tracing::debug!(
+                "Cannot create a temporary file next to {}, writing in place",
+                target.display()
+            );
+            write_in_place(&target, write_content)?;
+            return Ok((target, WriteMethod::InPlace));
+        }
+        Err(e) => return Err(e),
+    };
+
+    if let Some(metadata) = &existing {
+        if let Err(e) = copy_metadata(&target, metadata, &temp_file) {
+            if allow_in_place {
+                tracing::debug!(
+                    "Cannot preserve metadata of {} ({}), writing in place",
+                    target.display(),
+                    e
+                );
+                drop(temp_file);
+                let _ = fs::remove_file(&temp_path);
+                write_in_place(&target, write_content)?;
+                return Ok((target, WriteMethod::InPlace));
+            }
+            // Keep at least the permissions
+            tracing::warn!("Cannot preserve metadata of {}: {}", target.display(), e);
+            let _ = temp_file.set_permissions(metadata.permissions());
+        }
+    }
+
+    let result = write_content(&mut temp_file)
+        .and_then(|_| temp_file.sync_all())
+        .and_then(|_| {
+            drop(temp_file);
+            fs::rename(&temp_path, &target)
+        });
+    if let Err(e) = result {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e);
+    }
+    Ok((target, WriteMethod::AtomicReplace))
+}
+
+/// Follow symlinks to the file that should actually be written.
+/// Unlike `canonicalize`, this works for dangling links and new files.
+pub fn resolve_symlinks(path: &Path) -> io::Result<PathBuf> {
+    let mut current = path.to_path_buf();
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        match fs::symlink_metadata(&current) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = fs::read_link(&current)?;
+                current = match current.parent() {
+                    Some(parent) if link.is_relative() => parent.join(link),
+                    _ => link,
+                };
+            }
+            _ => return Ok(current),
+        }
+    }
+    Err(io::Error::other(format!(
+        "Too many levels of symbolic links: {}",
+        path.display()
+    )))
+}
+
+/// Does the file have hard links that an atomic replace would break?
+#[cfg(unix)]
+fn has_other_hard_links(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn has_other_hard_links(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Create a new, uniquely named temporary file in the target's directory
+fn create_temp_file(target: &Path) -> io::Result<(PathBuf, File)> {
+    let dir = match target.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    loop {
+        let temp_path = dir.join(format!(
+            ".{}.{}-{}.fresh-save",
+            file_name,
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Truncate and overwrite the target, keeping its inode and metadata
+fn write_in_place<F>(target: &Path, write_content: F) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(target)?;
+    write_content(&mut file)?;
+    file.flush()?;
+    file.sync_all()
+}
+
+/// Copy permissions, ownership and extended attributes of the original file
+/// onto the temporary file. Fails if ownership can't be reproduced.
+fn copy_metadata(original: &Path, metadata: &fs::Metadata, temp_file: &File) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let temp_metadata = temp_file.metadata()?;
+        if temp_metadata.uid() != metadata.uid() || temp_metadata.gid() != metadata.gid() {
+            std::os::unix::fs::fchown(temp_file, Some(metadata.uid()), Some(metadata.gid()))?;
+        }
+    }
+
+    // Set permissions after ownership: chown clears the setuid/setgid bits
+    temp_file.set_permissions(metadata.permissions())?;
+
+    #[cfg(target_os = "linux")]
+    copy_xattrs(original, temp_file);
+    #[cfg(not(target_os = "linux"))]
+    let _ = original;
+
+    Ok(())
+}
+
+/// Copy extended attributes (ACLs, SELinux labels, user attributes).
+/// Attributes that can't be copied (e.g. for lack of privileges) are skipped.
+#[cfg(target_os = "linux")]
+fn copy_xattrs(original: &Path, temp_file: &File) {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::io::AsRawFd;
+
+    let Ok(path) = CString::new(original.as_os_str().as_bytes()) else {
+        return;
+    };
+
+    // SAFETY: querying the size with a null buffer is allowed
+    let size = unsafe { libc::listxattr(path.as_ptr(), std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        return;
+    }
+    let mut names = vec![0u8; size as usize];
+    // SAFETY: `names` is valid for `names.len()` bytes
+    let size = unsafe {
+        libc::listxattr(
+            path.as_ptr(),
+            names.as_mut_ptr() as *mut libc::c_char,
+            names.len(),
+        )
+    };
+    if size <= 0 {
+        return;
+    }
+    names.truncate(size as usize);
+
+    for name in names.split(|&b| b == 0).filter(|n| !n.is_empty()) {
+        let Ok(name) = CString::new(name) else {
+            continue;
+        };
+        // SAFETY: querying the size with a null buffer is allowed
+        let len = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) };
+        if len < 0 {
+            continue;
+        }
+        let mut value = vec![0u8; len as usize];
+        // SAFETY: `value` is valid for `value.len()` bytes
+        let len = unsafe {
+            libc::getxattr(
+                path.as_ptr(),
+                name.as_ptr(),
+                value.as_mut_ptr() as *mut libc::c_void,
+                value.len(),
+            )
+        };
+        if len < 0 {
+            continue;
+        }
+        // SAFETY: `value` is valid for `len` bytes and the fd is open
+        let result = unsafe {
+            libc::fsetxattr(
+                temp_file.as_raw_fd(),
+                name.as_ptr(),
+                value.as_ptr() as *const libc::c_void,
+                len as usize,
+                0,
+            )
+        };
+        if result != 0 {
+            tracing::debug!(
+                "Could not copy xattr {:?} of {}: {}",
+                name,
+                original.display(),
+                io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_str(path: &Path, content: &str) -> io::Result<(PathBuf, WriteMethod)> {
+        write_file(path, true, |file| file.write_all(content.as_bytes()))
+    }
+
+    #[test]
+    fn test_write_new_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("new.txt");
+
+        let (written, method) = write_str(&path, "hello").unwrap();
+        assert_eq!(written, path);
+        assert_eq!(method, WriteMethod::AtomicReplace);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_does_not_clobber_tmp_sibling() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("foo.txt");
+        let sibling = temp_dir.path().join("foo.tmp");
+        fs::write(&path, "old").unwrap();
+        fs::write(&sibling, "precious").unwrap();
+
+        write_str(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&sibling).unwrap(), "precious");
+        // No temporary files left behind
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_preserves_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("script.sh");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write_str(&path, "#!/bin/sh\necho hi\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o750);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_writes_through_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("real.conf");
+        let link = temp_dir.path().join("link.conf");
+        fs::write(&target, "old").unwrap();
+        std::os::unix::fs::symlink("real.conf", &link).unwrap();
+
+        let (written, _) = write_str(&link, "new").unwrap();
+        assert_eq!(written, target);
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_keeps_hard_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("a.txt");
+        let other = temp_dir.path().join("b.txt");
+        fs::write(&path, "old").unwrap();
+        fs::hard_link(&path, &other).unwrap();
+
+        let (_, method) = write_str(&path, "new").unwrap();
+        assert_eq!(method, WriteMethod::InPlace);
+        assert_eq!(fs::read_to_string(&other).unwrap(), "new");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_falls_back_to_in_place_in_readonly_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("locked");
+        fs::create_dir(&dir).unwrap();
+        let path = dir.join("file.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o555)).unwrap();
+
+        let result = write_str(&path, "new");
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Root can create files anywhere, so only check the outcome
+        result.unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    }
+}
@@ -299,10 +299,123 @@ pub fn merge_ranges(ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
     merged
 }
 
+/// Largest LCS table (lines x lines) `diff_hunks` builds before treating the
+/// whole differing region as one hunk
+const MAX_HUNK_DIFF_CELLS: usize = 4_000_000;
+
+/// A differing region: bytes `old` of the original text are replaced by bytes `new`
+/// of the new text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Compare two texts line by line and return the differing regions as byte ranges.
+///
+/// Replacing each hunk's `old` range with its `new` range turns `old` into `new`.
+/// Lines outside the hunks are left alone, so positions in them stay valid when the
+/// hunks are applied as edits. Hunks are in ascending order.
+pub fn diff_hunks(old: &[u8], new: &[u8]) -> Vec<Hunk> {
+    if old == new {
+        return vec![];
+    }
+    let old_lines: Vec<&[u8]> = old.split_inclusive(|&b| b == b'\n').collect();
+    let new_lines: Vec<&[u8]> = new.split_inclusive(|&b| b == b'\n').collect();
+
+    // Match common leading and trailing lines directly to keep the LCS table small
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_mid = &old_lines[prefix..old_lines.len() - suffix];
+    let new_mid = &new_lines[prefix..new_lines.len() - suffix];
+
+    let mut matches = if old_mid.len().saturating_mul(new_mid.len()) <= MAX_HUNK_DIFF_CELLS {
+        longest_common_subsequence(old_mid, new_mid)
+    } else {
+        Vec::new()
+    };
+    // The common suffix acts as a final match closing the last hunk
+    matches.push(LineMatch {
+        saved_idx: old_mid.len(),
+        current_idx: new_mid.len(),
+    });
+
+    let old_offsets = line_offsets(&old_lines);
+    let new_offsets = line_offsets(&new_lines);
+    let mut hunks = Vec::new();
+    let (mut old_idx, mut new_idx) = (0, 0);
+    for m in matches {
+        if m.saved_idx > old_idx || m.current_idx > new_idx {
+            hunks.push(Hunk {
+                old: old_offsets[prefix + old_idx]..old_offsets[prefix + m.saved_idx],
+                new: new_offsets[prefix + new_idx]..new_offsets[prefix + m.current_idx],
+            });
+        }
+        old_idx = m.saved_idx + 1;
+        new_idx = m.current_idx + 1;
+    }
+    hunks
+}
+
+/// Byte offset of the start of each line, plus the total length
+fn line_offsets(lines: &[&[u8]]) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(lines.len() + 1);
+    let mut pos = 0;
+    offsets.push(pos);
+    for line in lines {
+        pos += line.len();
+        offsets.push(pos);
+    }
+    offsets
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Apply hunks (from the end backwards) to `old`
+    fn apply_hunks(old: &[u8], new: &[u8], hunks: &[Hunk]) -> Vec<u8> {
+        let mut result = old.to_vec();
+        for hunk in hunks.iter().rev() {
+            result.splice(hunk.old.clone(), new[hunk.new.clone()].iter().copied());
+        }
+        result
+    }
+
+    #[test]
+    fn test_diff_hunks_only_cover_changed_lines() {
+        let old = b"fn main() {\nlet x = 1;\n  let y = 2;\n}\n";
+        let new = b"fn main() {\n    let x = 1;\n    let y = 2;\n}\n";
+        let hunks = diff_hunks(old, new);
+        assert_eq!(
+            hunks,
+            vec![Hunk {
+                old: 12..36,
+                new: 12..42
+            }]
+        );
+        assert_eq!(apply_hunks(old, new, &hunks), new);
+
+        let old = b"a\nb\nc\nd\ne";
+        let new = b"a\nB\nc\nd\ne\nf\n";
+        let hunks = diff_hunks(old, new);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(&old[hunks[0].old.clone()], b"b\n");
+        assert_eq!(apply_hunks(old, new, &hunks), new);
+
+        assert!(diff_hunks(b"same\n", b"same\n").is_empty());
+        assert_eq!(apply_hunks(b"", b"x\n", &diff_hunks(b"", b"x\n")), b"x\n");
+    }
+
     #[test]
     fn test_identical_content() {
         let content = b"line 1\nline 2\nline 3\n";
@@ -7,7 +7,9 @@ pub mod control_event;
 pub mod cursor;
 pub mod document_model;
 pub mod edit;
+pub mod encoding;
 pub mod event;
+pub mod file_write;
 pub mod line_diff;
 pub mod marker;
 pub mod marker_tree;
@@ -0,0 +1,223 @@
+//! Foldable line ranges
+//!
+//! A fold range is a header line that stays visible and the lines after it that
+//! folding hides. Ranges come from the syntax tree (every named node spanning
+//! several lines), from the language server (`textDocument/foldingRange`), or,
+//! for buffers with neither, from indentation (a line followed by deeper
+//! indented lines).
+
+use std::ops::Range;
+use tree_sitter::Tree;
+
+/// A foldable region of lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FoldRange {
+    /// Line that stays visible when folded
+    pub start_line: usize,
+    /// Last line hidden when folded (inclusive)
+    pub end_line: usize,
+}
+
+impl FoldRange {
+    pub fn new(start_line: usize, end_line: usize) -> Self {
+        Self {
+            start_line,
+            end_line,
+        }
+    }
+
+    /// Whether `line` is the header or one of the hidden lines
+    pub fn contains_line(&self, line: usize) -> bool {
+        line >= self.start_line && line <= self.end_line
+    }
+}
+
+/// Fold ranges of the syntax tree's multi-line nodes, sorted by start line.
+/// When several nodes start on the same line, the largest one wins.
+pub fn from_syntax_tree(tree: &Tree) -> Vec<FoldRange> {
+    from_syntax_tree_in(tree, 0..usize::MAX)
+}
+
+/// Like `from_syntax_tree`, for the nodes starting on `lines` only. Subtrees
+/// outside `lines` aren't walked.
+pub fn from_syntax_tree_in(tree: &Tree, lines: Range<usize>) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    let mut cursor = tree.walk();
+    'walk: loop {
+        let node = cursor.node();
+        let start = node.start_position();
+        let end = node.end_position();
+        // A node ending at column 0 ends with the previous line's newline
+        let end_line = if end.column == 0 {
+            end.row.saturating_sub(1)
+        } else {
+            end.row
+        };
+        // The root node spans the whole document and isn't worth folding
+        if node.is_named()
+            && cursor.depth() > 0
+            && end_line > start.row
+            && lines.contains(&start.row)
+        {
+            ranges.push(FoldRange::new(start.row, end_line));
+        }
+        let overlaps = end.row >= lines.start && start.row < lines.end;
+        if overlaps && cursor.goto_first_child() {
+            continue;
+        }
+        loop {
+            if cursor.goto_next_sibling() {
+                continue 'walk;
+            }
+            if !cursor.goto_parent() {
+                break 'walk;
+            }
+        }
+    }
+    largest_per_start_line(ranges)
+}
+
+/// Fold ranges from the language server, sorted by start line
+pub fn from_lsp(ranges: &[lsp_types::FoldingRange]) -> Vec<FoldRange> {
+    largest_per_start_line(
+        ranges
+            .iter()
+            .filter(|range| range.end_line > range.start_line)
+            .map(|range| FoldRange::new(range.start_line as usize, range.end_line as usize))
+            .collect(),
+    )
+}
+
+/// Fold ranges from indentation, sorted by start line.
+/// A non-blank line starts a fold when the next non-blank line is indented
+/// deeper; the fold ends at the last non-blank line before the indentation
+/// drops back to the header's level.
+pub fn from_indentation(text: &str, tab_width: usize) -> Vec<FoldRange> {
+    let mut ranges = Vec::new();
+    // Open headers as (line, indent), indents strictly increasing
+    let mut open: Vec<(usize, usize)> = Vec::new();
+    let mut last_non_blank = 0;
+
+    for (line, content) in text.split('\n').enumerate() {
+        let Some(indent) = indent_width(content, tab_width) else {
+            continue;
+        };
+        while let Some(&(start, start_indent)) = open.last() {
+            if start_indent < indent {
+                break;
+            }
+            open.pop();
+            if last_non_blank > start {
+                ranges.push(FoldRange::new(start, last_non_blank));
+            }
+        }
+        open.push((line, indent));
+        last_non_blank = line;
+    }
+    for (start, _) in open {
+        if last_non_blank > start {
+            ranges.push(FoldRange::new(start, last_non_blank));
+        }
+    }
+
+    ranges.sort();
+    ranges
+}
+
+/// Nesting depth of each range (1 for ranges not inside another one).
+/// `ranges` must be sorted by start line.
+pub fn nesting_levels(ranges: &[FoldRange]) -> Vec<usize> {
+    let mut enclosing: Vec<FoldRange> = Vec::new();
+    ranges
+        .iter()
+        .map(|range| {
+            while enclosing
+                .last()
+                .is_some_and(|outer| outer.end_line < range.end_line)
+            {
+                enclosing.pop();
+            }
+            enclosing.push(*range);
+            enclosing.len()
+        })
+        .collect()
+}
+
+/// Columns of leading whitespace, or None for a blank line
+fn indent_width(line: &str, tab_width: usize) -> Option<usize> {
+    let mut width = 0;
+    for ch in line.chars() {
+        match ch {
+            ' ' => width += 1,
+            '\t' => width += tab_width.max(1) - width % tab_width.max(1),
+            '\r' => {}
+            _ => return Some(width),
+        }
+    }
+    None
+}
+
+/// Keep the largest range for each start line, sorted by start line
+fn largest_per_start_line(mut ranges: Vec<FoldRange>) -> Vec<FoldRange> {
+    // Sort by start, largest first, then keep the first of each start line
+    ranges.sort_by(|a, b| {
+        a.start_line
+            .cmp(&b.start_line)
+            .then(b.end_line.cmp(&a.end_line))
+    });
+    ranges.dedup_by_key(|range| range.start_line);
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::highlighter::Language;
+    use tree_sitter::Parser;
+
+    fn parse_rust(source: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_syntax_tree_ranges() {
+        let source = "fn main() {\n    if x {\n        y();\n    }\n}\n\nstruct S;\n";
+        let ranges = from_syntax_tree(&parse_rust(source));
+        assert_eq!(ranges, vec![FoldRange::new(0, 4), FoldRange::new(1, 3)]);
+
+        let ranges = from_syntax_tree_in(&parse_rust(source), 1..3);
+        assert_eq!(ranges, vec![FoldRange::new(1, 3)]);
+    }
+
+    #[test]
+    fn test_indentation_ranges() {
+        let text = "def f():\n    x = 1\n\n    y = 2\n\ndef g():\n    pass\nz = 3\n";
+        assert_eq!(
+            from_indentation(text, 4),
+            vec![FoldRange::new(0, 3), FoldRange::new(5, 6)]
+        );
+    }
+
+    #[test]
+    fn test_indentation_nested_and_tabs() {
+        let text = "a\n\tb\n\t\tc\n\td\n";
+        assert_eq!(
+            from_indentation(text, 4),
+            vec![FoldRange::new(0, 3), FoldRange::new(1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_nesting_levels() {
+        let ranges = vec![
+            FoldRange::new(0, 10),
+            FoldRange::new(1, 3),
+            FoldRange::new(2, 3),
+            FoldRange::new(5, 8),
+            FoldRange::new(12, 14),
+        ];
+        assert_eq!(nesting_levels(&ranges), vec![1, 2, 3, 2, 1]);
+    }
+}
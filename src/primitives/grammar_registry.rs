@@ -253,6 +253,16 @@ impl GrammarRegistry {
         self.syntax_set.find_syntax_for_file(path).ok().flatten()
     }
 
+    /// Find syntax for a file extension or file name (e.g. `py`, `Makefile`)
+    pub fn find_syntax_by_extension(&self, ext: &str) -> Option<&SyntaxReference> {
+        if let Some(scope) = self.user_extensions.get(ext) {
+            if let Some(syntax) = self.find_syntax_by_scope(scope) {
+                return Some(syntax);
+            }
+        }
+        self.syntax_set.find_syntax_by_extension(ext)
+    }
+
     /// Find syntax by scope name
     pub fn find_syntax_by_scope(&self, scope: &str) -> Option<&SyntaxReference> {
         let scope = syntect::parsing::Scope::new(scope).ok()?;
@@ -23,7 +23,7 @@ use crate::view::theme::Theme;
 use std::ops::Range;
 use std::path::Path;
 use std::sync::Arc;
-use syntect::parsing::SyntaxSet;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
 
 /// Preference for which highlighting backend to use
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -301,6 +301,16 @@ impl HighlightEngine {
         Self::for_file_with_preference(path, registry, HighlighterPreference::Auto)
     }
 
+    /// Create a highlighting engine for a language: its TextMate grammar is
+    /// found by the language's extensions or file names, its tree-sitter
+    /// language by grammar name
+    pub fn for_language(grammar: &str, extensions: &[&str], registry: &GrammarRegistry) -> Self {
+        let syntax = extensions
+            .iter()
+            .find_map(|ext| registry.find_syntax_by_extension(ext));
+        Self::textmate_or_tree_sitter(syntax, Language::from_grammar(grammar), registry)
+    }
+
     /// Create a highlighting engine with explicit preference
     pub fn for_file_with_preference(
         path: &Path,
@@ -326,13 +336,25 @@ impl HighlightEngine {
 
     /// Create a TextMate engine for a file, falling back to tree-sitter if no TextMate grammar
     fn textmate_for_file(path: &Path, registry: &GrammarRegistry) -> Self {
-        let syntax_set = registry.syntax_set_arc();
+        // Find syntax by file extension, and detect tree-sitter language for
+        // non-highlighting features
+        Self::textmate_or_tree_sitter(
+            registry.find_syntax_for_file(path),
+            Language::from_path(path),
+            registry,
+        )
+    }
 
-        // Detect tree-sitter language for non-highlighting features
-        let ts_language = Language::from_path(path);
+    /// Create a TextMate engine for `syntax`, falling back to tree-sitter if
+    /// there is none
+    fn textmate_or_tree_sitter(
+        syntax: Option<&SyntaxReference>,
+        ts_language: Option<Language>,
+        registry: &GrammarRegistry,
+    ) -> Self {
+        let syntax_set = registry.syntax_set_arc();
 
-        // Find syntax by file extension
-        if let Some(syntax) = registry.find_syntax_for_file(path) {
+        if let Some(syntax) = syntax {
             // Find the index of this syntax in the set
             if let Some(index) = syntax_set
                 .syntaxes()
@@ -353,7 +375,7 @@ impl HighlightEngine {
             if let Ok(highlighter) = Highlighter::new(lang) {
                 tracing::debug!(
                     "No TextMate grammar for {:?}, falling back to tree-sitter",
-                    path.extension()
+                    lang
                 );
                 return Self::TreeSitter(highlighter);
             }
@@ -366,6 +388,8 @@ impl HighlightEngine {
     ///
     /// `context_bytes` controls how far before/after the viewport to parse for accurate
     /// highlighting of multi-line constructs (strings, comments, nested blocks).
+    /// `syntax_tree` is the buffer's full syntax tree, if it has been parsed; tree-sitter
+    /// highlighting reads from it instead of parsing a window.
     pub fn highlight_viewport(
         &mut self,
         buffer: &Buffer,
@@ -373,11 +397,17 @@ impl HighlightEngine {
         viewport_end: usize,
         theme: &Theme,
         context_bytes: usize,
+        syntax_tree: Option<&tree_sitter::Tree>,
     ) -> Vec<HighlightSpan> {
         match self {
-            Self::TreeSitter(h) => {
-                h.highlight_viewport(buffer, viewport_start, viewport_end, theme, context_bytes)
-            }
+            Self::TreeSitter(h) => h.highlight_viewport(
+                buffer,
+                viewport_start,
+                viewport_end,
+                theme,
+                context_bytes,
+                syntax_tree,
+            ),
             Self::TextMate(h) => {
                 h.highlight_viewport(buffer, viewport_start, viewport_end, theme, context_bytes)
             }
@@ -403,6 +433,11 @@ impl HighlightEngine {
         }
     }
 
+    /// Whether highlighting reads from the buffer's syntax tree
+    pub fn uses_syntax_tree(&self) -> bool {
+        matches!(self, Self::TreeSitter(_))
+    }
+
     /// Check if this engine has highlighting available
     pub fn has_highlighting(&self) -> bool {
         !matches!(self, Self::None)
@@ -488,6 +523,26 @@ mod tests {
         assert!(engine.language().is_some());
     }
 
+    #[test]
+    fn test_for_language() {
+        let registry = GrammarRegistry::load();
+
+        let engine = HighlightEngine::for_language("python", &["py", "pyi"], &registry);
+        assert_eq!(engine.backend_name(), "textmate");
+        assert!(engine.language().is_some());
+
+        let engine = HighlightEngine::for_language("typescript", &["ts"], &registry);
+        assert_eq!(engine.backend_name(), "tree-sitter");
+
+        // Languages known by file name only
+        let engine = HighlightEngine::for_language("make", &["Makefile"], &registry);
+        assert_eq!(engine.backend_name(), "textmate");
+        assert!(engine.language().is_none());
+
+        let engine = HighlightEngine::for_language("", &[], &registry);
+        assert!(!engine.has_highlighting());
+    }
+
     #[test]
     fn test_tree_sitter_explicit_preference() {
         let registry = GrammarRegistry::load();
@@ -14,13 +14,57 @@ use crate::model::buffer::Buffer;
 use crate::view::theme::Theme;
 use ratatui::style::Color;
 use std::ops::Range;
+use tree_sitter::{Node, QueryCursor, StreamingIterator, Tree};
 use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter as TSHighlighter};
 
 /// Maximum bytes to parse in a single operation (for viewport highlighting)
 const MAX_PARSE_BYTES: usize = LARGE_FILE_THRESHOLD_BYTES as usize; // 1MB
 
+/// Highlight names recognized for default languages (see `HighlightCategory::from_default_index`)
+const DEFAULT_HIGHLIGHT_NAMES: &[&str] = &[
+    "attribute",
+    "comment",
+    "constant",
+    "function",
+    "keyword",
+    "number",
+    "operator",
+    "property",
+    "string",
+    "type",
+    "variable",
+];
+
+/// Highlight names recognized for TypeScript, which uses more of the captures of
+/// its (and JavaScript's) queries (see `HighlightCategory::from_typescript_index`)
+const TYPESCRIPT_HIGHLIGHT_NAMES: &[&str] = &[
+    "attribute",
+    "comment",
+    "constant",
+    "constant.builtin",
+    "constructor",
+    "embedded",
+    "function",
+    "function.builtin",
+    "function.method",
+    "keyword",
+    "number",
+    "operator",
+    "property",
+    "punctuation.bracket",
+    "punctuation.delimiter",
+    "punctuation.special",
+    "string",
+    "string.special",
+    "type",
+    "type.builtin",
+    "variable",
+    "variable.builtin",
+    "variable.parameter",
+];
+
 /// Highlight category names used for default languages.
-/// The order matches the `configure()` call in `highlight_config()`.
+/// The order matches `DEFAULT_HIGHLIGHT_NAMES`.
 /// Index 0 = attribute, 1 = comment, 2 = constant, 3 = function, 4 = keyword,
 /// 5 = number, 6 = operator, 7 = property, 8 = string, 9 = type, 10 = variable
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -171,6 +215,73 @@ impl Language {
         }
     }
 
+    /// The language of a tree-sitter grammar name (`LanguageConfig::grammar`)
+    pub fn from_grammar(grammar: &str) -> Option<Self> {
+        match grammar {
+            "rust" => Some(Language::Rust),
+            "python" => Some(Language::Python),
+            "javascript" => Some(Language::JavaScript),
+            "typescript" => Some(Language::TypeScript),
+            "html" => Some(Language::HTML),
+            "css" => Some(Language::CSS),
+            "c" => Some(Language::C),
+            "cpp" => Some(Language::Cpp),
+            "go" => Some(Language::Go),
+            "json" => Some(Language::Json),
+            "java" => Some(Language::Java),
+            "c_sharp" | "csharp" => Some(Language::CSharp),
+            "php" => Some(Language::Php),
+            "ruby" => Some(Language::Ruby),
+            "bash" => Some(Language::Bash),
+            "lua" => Some(Language::Lua),
+            _ => None,
+        }
+    }
+
+    /// Get the tree-sitter grammar for this language
+    pub fn ts_language(&self) -> tree_sitter::Language {
+        match self {
+            Language::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Language::Python => tree_sitter_python::LANGUAGE.into(),
+            Language::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Language::HTML => tree_sitter_html::LANGUAGE.into(),
+            Language::CSS => tree_sitter_css::LANGUAGE.into(),
+            Language::C => tree_sitter_c::LANGUAGE.into(),
+            Language::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+            Language::Go => tree_sitter_go::LANGUAGE.into(),
+            Language::Json => tree_sitter_json::LANGUAGE.into(),
+            Language::Java => tree_sitter_java::LANGUAGE.into(),
+            Language::CSharp => tree_sitter_c_sharp::LANGUAGE.into(),
+            Language::Php => tree_sitter_php::LANGUAGE_PHP.into(),
+            Language::Ruby => tree_sitter_ruby::LANGUAGE.into(),
+            Language::Bash => tree_sitter_bash::LANGUAGE.into(),
+            Language::Lua => tree_sitter_lua::LANGUAGE.into(),
+        }
+    }
+
+    /// Name of the language's directory under `queries/`
+    pub fn query_dir(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::HTML => "html",
+            Language::CSS => "css",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Go => "go",
+            Language::Json => "json",
+            Language::Java => "java",
+            Language::CSharp => "csharp",
+            Language::Php => "php",
+            Language::Ruby => "ruby",
+            Language::Bash => "bash",
+            Language::Lua => "lua",
+        }
+    }
+
     /// Get tree-sitter highlight configuration for this language
     fn highlight_config(&self) -> Result<HighlightConfiguration, String> {
         match self {
@@ -183,21 +294,7 @@ impl Language {
                     "", // locals query
                 )
                 .map_err(|e| format!("Failed to create Rust highlight config: {e}"))?;
-
-                // Configure highlight names
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -210,21 +307,7 @@ impl Language {
                     "", // locals query
                 )
                 .map_err(|e| format!("Failed to create Python highlight config: {e}"))?;
-
-                // Configure highlight names
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -237,21 +320,7 @@ impl Language {
                     "", // locals query
                 )
                 .map_err(|e| format!("Failed to create JavaScript highlight config: {e}"))?;
-
-                // Configure highlight names
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -273,33 +342,7 @@ impl Language {
                     tree_sitter_typescript::LOCALS_QUERY, // locals query for proper scoping
                 )
                 .map_err(|e| format!("Failed to create TypeScript highlight config: {e}"))?;
-
-                // Configure highlight names - must include all captures from both JS and TS queries
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "constant.builtin",
-                    "constructor",
-                    "embedded",
-                    "function",
-                    "function.builtin",
-                    "function.method",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "punctuation.bracket",
-                    "punctuation.delimiter",
-                    "punctuation.special",
-                    "string",
-                    "string.special",
-                    "type",
-                    "type.builtin",
-                    "variable",
-                    "variable.builtin",
-                    "variable.parameter",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -313,19 +356,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create HTML highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -339,19 +370,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create CSS highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -365,19 +384,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create C highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -391,19 +398,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create C++ highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -417,19 +412,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create Go highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -443,19 +426,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create JSON highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -469,19 +440,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create Java highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -497,19 +456,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create C# highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -523,19 +470,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create PHP highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -549,19 +484,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create Ruby highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -575,19 +498,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create Bash highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             }
@@ -601,19 +512,7 @@ impl Language {
                 )
                 .map_err(|e| format!("Failed to create Lua highlight config: {e}"))?;
 
-                config.configure(&[
-                    "attribute",
-                    "comment",
-                    "constant",
-                    "function",
-                    "keyword",
-                    "number",
-                    "operator",
-                    "property",
-                    "string",
-                    "type",
-                    "variable",
-                ]);
+                config.configure(self.highlight_names());
 
                 Ok(config)
             } // Language::Markdown => {
@@ -623,6 +522,15 @@ impl Language {
         }
     }
 
+    /// Highlight names recognized in this language's highlight queries.
+    /// The index of a name is the index passed to `highlight_category`.
+    fn highlight_names(&self) -> &'static [&'static str] {
+        match self {
+            Language::TypeScript => TYPESCRIPT_HIGHLIGHT_NAMES,
+            _ => DEFAULT_HIGHLIGHT_NAMES,
+        }
+    }
+
     /// Map tree-sitter highlight index to a highlight category
     fn highlight_category(&self, index: usize) -> Option<HighlightCategory> {
         match self {
@@ -639,6 +547,8 @@ struct HighlightCache {
     range: Range<usize>,
     /// Highlighted spans within this range (stores categories for theme-independent caching)
     spans: Vec<CachedSpan>,
+    /// Whether the spans come from the full syntax tree (rather than a parsed window)
+    from_tree: bool,
 }
 
 /// Syntax highlighter with incremental viewport-based parsing
@@ -649,6 +559,8 @@ pub struct Highlighter {
     language: Language,
     /// Highlight configuration for the language
     config: HighlightConfiguration,
+    /// Category of each capture of the highlight query (by capture index)
+    capture_categories: Vec<Option<HighlightCategory>>,
     /// Cache of highlighted spans (only for visible viewport)
     cache: Option<HighlightCache>,
     /// Last known buffer length (for detecting complete buffer changes)
@@ -659,18 +571,46 @@ impl Highlighter {
     /// Create a new highlighter for the given language
     pub fn new(language: Language) -> Result<Self, String> {
         let config = language.highlight_config()?;
+        let capture_categories = config
+            .query
+            .capture_names()
+            .iter()
+            .map(|capture| {
+                Self::recognized_name_index(capture, language.highlight_names())
+                    .and_then(|index| language.highlight_category(index))
+            })
+            .collect();
         Ok(Self {
             ts_highlighter: TSHighlighter::new(),
             language,
             config,
+            capture_categories,
             cache: None,
             last_buffer_len: 0,
         })
     }
 
+    /// The configured highlight name matching a capture name, the same way
+    /// tree-sitter-highlight matches them (most dot-separated parts wins)
+    fn recognized_name_index(capture: &str, recognized_names: &[&str]) -> Option<usize> {
+        let capture_parts: Vec<&str> = capture.split('.').collect();
+        let mut best = None;
+        let mut best_len = 0;
+        for (index, name) in recognized_names.iter().enumerate() {
+            let parts: Vec<&str> = name.split('.').collect();
+            if parts.len() > best_len && parts.iter().all(|part| capture_parts.contains(part)) {
+                best = Some(index);
+                best_len = parts.len();
+            }
+        }
+        best
+    }
+
     /// Highlight the visible viewport range
     ///
-    /// This only parses the visible lines for instant performance with large files.
+    /// With the buffer's full `syntax_tree`, highlights are read from the tree.
+    /// Without it (large files, or before the background parse finishes), only
+    /// a window around the visible lines is parsed.
     /// Returns highlighted spans for the requested byte range, colored according to the theme.
     ///
     /// `context_bytes` controls how far before/after the viewport to parse for accurate
@@ -682,12 +622,14 @@ impl Highlighter {
         viewport_end: usize,
         theme: &Theme,
         context_bytes: usize,
+        syntax_tree: Option<&Tree>,
     ) -> Vec<HighlightSpan> {
         // Check if cache is valid for this range
         if let Some(cache) = &self.cache {
             if cache.range.start <= viewport_start
                 && cache.range.end >= viewport_end
                 && self.last_buffer_len == buffer.len()
+                && cache.from_tree == syntax_tree.is_some()
             {
                 // Cache hit! Filter spans to the requested range and resolve colors from theme
                 return cache
@@ -721,10 +663,38 @@ impl Highlighter {
             return Vec::new();
         }
 
+        // Highlight the source - store categories for theme-independent caching
+        let cached_spans = match syntax_tree {
+            Some(tree) => self.highlight_tree(tree, buffer, parse_range.clone()),
+            None => self.highlight_window(buffer, parse_range.clone()),
+        };
+
+        // Update cache
+        self.cache = Some(HighlightCache {
+            range: parse_range,
+            spans: cached_spans.clone(),
+            from_tree: syntax_tree.is_some(),
+        });
+        self.last_buffer_len = buffer.len();
+
+        // Filter to requested viewport and resolve colors from theme
+        cached_spans
+            .into_iter()
+            .filter(|span| span.range.start < viewport_end && span.range.end > viewport_start)
+            .map(|span| HighlightSpan {
+                range: span.range,
+                color: span.category.color(theme),
+            })
+            .collect()
+    }
+
+    /// Highlight a range by parsing just that window of the buffer
+    fn highlight_window(&mut self, buffer: &Buffer, parse_range: Range<usize>) -> Vec<CachedSpan> {
+        let parse_start = parse_range.start;
+
         // Extract source bytes from buffer
-        let source = buffer.slice_bytes(parse_range.clone());
+        let source = buffer.slice_bytes(parse_range);
 
-        // Highlight the source - store categories for theme-independent caching
         let mut cached_spans = Vec::new();
         match self.ts_highlighter.highlight(
             &self.config,
@@ -769,23 +739,27 @@ impl Highlighter {
                 tracing::error!("Failed to highlight: {}", e);
             }
         }
-
-        // Update cache
-        self.cache = Some(HighlightCache {
-            range: parse_range,
-            spans: cached_spans.clone(),
-        });
-        self.last_buffer_len = buffer.len();
-
-        // Filter to requested viewport and resolve colors from theme
         cached_spans
-            .into_iter()
-            .filter(|span| span.range.start < viewport_end && span.range.end > viewport_start)
-            .map(|span| HighlightSpan {
-                range: span.range,
-                color: span.category.color(theme),
-            })
-            .collect()
+    }
+
+    /// Highlight a range by running the highlight query over the full syntax tree
+    fn highlight_tree(&self, tree: &Tree, buffer: &Buffer, range: Range<usize>) -> Vec<CachedSpan> {
+        let mut query_cursor = QueryCursor::new();
+        query_cursor.set_byte_range(range);
+        let text = |node: Node| std::iter::once(buffer.slice_bytes(node.byte_range()));
+        let mut captures = query_cursor.captures(&self.config.query, tree.root_node(), text);
+
+        let mut spans = Vec::new();
+        while let Some((query_match, capture_index)) = captures.next() {
+            let capture = query_match.captures[*capture_index];
+            if let Some(Some(category)) = self.capture_categories.get(capture.index as usize) {
+                spans.push(CachedSpan {
+                    range: capture.node.byte_range(),
+                    category: *category,
+                });
+            }
+        }
+        flatten_spans(spans)
     }
 
     /// Invalidate cache for an edited range
@@ -811,6 +785,66 @@ impl Highlighter {
     }
 }
 
+/// Turn possibly nested spans into non-overlapping ones where the innermost
+/// span wins, like tree-sitter-highlight's nested highlight events. When
+/// several patterns capture the same node, the first one wins.
+fn flatten_spans(mut spans: Vec<CachedSpan>) -> Vec<CachedSpan> {
+    // Outer spans first; the stable sort keeps pattern order for identical ranges
+    spans.sort_by(|a, b| {
+        a.range
+            .start
+            .cmp(&b.range.start)
+            .then(b.range.end.cmp(&a.range.end))
+    });
+    spans.dedup_by(|later, earlier| later.range == earlier.range);
+
+    let mut flat = Vec::new();
+    let mut open: Vec<CachedSpan> = Vec::new();
+    let mut pos = 0;
+    for mut span in spans {
+        // Close the spans that end before this one starts
+        while let Some(top) = open.last() {
+            if top.range.end > span.range.start {
+                break;
+            }
+            push_span(
+                &mut flat,
+                top.category,
+                pos.max(top.range.start)..top.range.end,
+            );
+            pos = pos.max(top.range.end);
+            open.pop();
+        }
+        if let Some(top) = open.last() {
+            push_span(
+                &mut flat,
+                top.category,
+                pos.max(top.range.start)..span.range.start,
+            );
+            // Keep spans nested
+            span.range.end = span.range.end.min(top.range.end);
+        }
+        pos = pos.max(span.range.start);
+        open.push(span);
+    }
+    while let Some(top) = open.pop() {
+        push_span(
+            &mut flat,
+            top.category,
+            pos.max(top.range.start)..top.range.end,
+        );
+        pos = pos.max(top.range.end);
+    }
+    flat
+}
+
+/// Add a span unless it is empty
+fn push_span(spans: &mut Vec<CachedSpan>, category: HighlightCategory, range: Range<usize>) {
+    if range.start < range.end {
+        spans.push(CachedSpan { range, category });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -920,7 +954,7 @@ mod tests {
         let theme = Theme::dark();
 
         // Highlight entire buffer
-        let spans = highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000);
+        let spans = highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000, None);
 
         // Should have some highlighted spans
         assert!(!spans.is_empty());
@@ -945,8 +979,14 @@ mod tests {
         // Highlight only a small viewport in the middle
         let viewport_start = 10000;
         let viewport_end = 10500;
-        let spans =
-            highlighter.highlight_viewport(&buffer, viewport_start, viewport_end, &theme, 100_000);
+        let spans = highlighter.highlight_viewport(
+            &buffer,
+            viewport_start,
+            viewport_end,
+            &theme,
+            100_000,
+            None,
+        );
 
         // Should have some spans in the viewport
         assert!(!spans.is_empty());
@@ -969,7 +1009,7 @@ mod tests {
         let theme = Theme::dark();
 
         // First highlight
-        highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000);
+        highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000, None);
         assert!(highlighter.cache.is_some());
 
         // Invalidate a range
@@ -977,7 +1017,7 @@ mod tests {
         assert!(highlighter.cache.is_none());
 
         // Highlight again to rebuild cache
-        highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000);
+        highlighter.highlight_viewport(&buffer, 0, buffer.len(), &theme, 100_000, None);
         assert!(highlighter.cache.is_some());
 
         // Invalidate all
@@ -993,12 +1033,12 @@ mod tests {
         // Highlight with dark theme
         let dark_theme = Theme::dark();
         let dark_spans =
-            highlighter.highlight_viewport(&buffer, 0, buffer.len(), &dark_theme, 100_000);
+            highlighter.highlight_viewport(&buffer, 0, buffer.len(), &dark_theme, 100_000, None);
 
         // Highlight with light theme (cache should still work, colors should change)
         let light_theme = Theme::light();
         let light_spans =
-            highlighter.highlight_viewport(&buffer, 0, buffer.len(), &light_theme, 100_000);
+            highlighter.highlight_viewport(&buffer, 0, buffer.len(), &light_theme, 100_000, None);
 
         // Both should have spans
         assert!(!dark_spans.is_empty());
@@ -1021,4 +1061,69 @@ mod tests {
             "Themes should have different keyword colors"
         );
     }
+
+    #[test]
+    fn test_highlight_from_full_tree() {
+        // A block comment that starts far outside the parsed context window
+        let mut content = String::from("/*\n");
+        for i in 0..200 {
+            content.push_str(&format!("fn function_{i}() {{}}\n"));
+        }
+        content.push_str("*/\nfn after() {}\n");
+        let buffer = Buffer::from_str_test(&content);
+        let theme = Theme::dark();
+        let viewport = 2000..2200;
+
+        let mut highlighter = Highlighter::new(Language::Rust).unwrap();
+        let windowed = highlighter.highlight_viewport(
+            &buffer,
+            viewport.start,
+            viewport.end,
+            &theme,
+            100,
+            None,
+        );
+        assert!(windowed.iter().any(|s| s.color == theme.syntax_keyword));
+
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        let tree = parser.parse(content.as_bytes(), None).unwrap();
+        let from_tree = highlighter.highlight_viewport(
+            &buffer,
+            viewport.start,
+            viewport.end,
+            &theme,
+            100,
+            Some(&tree),
+        );
+        assert!(!from_tree.is_empty());
+        assert!(from_tree.iter().all(|s| s.color == theme.syntax_comment));
+
+        // Past the comment, code is highlighted as usual
+        let after = content.find("fn after").unwrap();
+        let spans =
+            highlighter.highlight_viewport(&buffer, after, content.len(), &theme, 100, Some(&tree));
+        assert!(spans.iter().any(|s| s.color == theme.syntax_keyword));
+    }
+
+    #[test]
+    fn test_flatten_spans_innermost_wins() {
+        let span = |range: Range<usize>, category| CachedSpan { range, category };
+        let flat = flatten_spans(vec![
+            span(0..10, HighlightCategory::String),
+            span(3..5, HighlightCategory::Constant),
+            span(3..5, HighlightCategory::Keyword),
+            span(12..14, HighlightCategory::Number),
+        ]);
+        let flat: Vec<_> = flat.into_iter().map(|s| (s.range, s.category)).collect();
+        assert_eq!(
+            flat,
+            vec![
+                (0..3, HighlightCategory::String),
+                (3..5, HighlightCategory::Constant),
+                (5..10, HighlightCategory::String),
+                (12..14, HighlightCategory::Number),
+            ]
+        );
+    }
 }
@@ -32,7 +32,8 @@
 //! 3. Finding the unmatched opening delimiter to dedent to its level
 //!
 //! ## Performance
-//! - Parses up to 2000 bytes before cursor (balances accuracy vs speed)
+//! - Queries the buffer's syntax tree (see `SyntaxTree`) over the 2000 bytes
+//!   before the cursor; without one, parses just those bytes
 //! - Pattern matching is O(n) where n = lines scanned (typically < 100)
 //! - Tree-sitter queries cached per-language
 //!
@@ -43,11 +44,19 @@
 use crate::model::buffer::Buffer;
 use crate::primitives::highlighter::Language;
 use std::collections::HashMap;
-use tree_sitter::{Parser, Query, QueryCursor, StreamingIterator};
+use std::ops::Range;
+use tree_sitter::{Node, Parser, Query, QueryCursor, StreamingIterator, Tree};
 
 /// Maximum bytes to parse before cursor for indent calculation
 const MAX_PARSE_BYTES: usize = 2000;
 
+/// The kind of a tree-sitter indent query capture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndentCaptureKind {
+    Indent,
+    Dedent,
+}
+
 /// Indent calculator using tree-sitter queries
 pub struct IndentCalculator {
     /// Map of language to (parser, query)
@@ -173,19 +182,74 @@ impl IndentCalculator {
         Some((parser, query))
     }
 
+    /// The `@indent` and `@dedent` captures over `window`, with byte ranges
+    /// relative to its start. Queries the buffer's syntax tree when there is
+    /// one, else parses the window on its own.
+    fn window_captures(
+        &mut self,
+        buffer: &Buffer,
+        tree: Option<&Tree>,
+        window: Range<usize>,
+        language: &Language,
+    ) -> Option<Vec<(IndentCaptureKind, Range<usize>)>> {
+        let (parser, query) = self.get_config(language)?;
+        let kinds: Vec<Option<IndentCaptureKind>> = query
+            .capture_names()
+            .iter()
+            .map(|name| match *name {
+                "indent" => Some(IndentCaptureKind::Indent),
+                "dedent" => Some(IndentCaptureKind::Dedent),
+                _ => None,
+            })
+            .collect();
+
+        let mut query_cursor = QueryCursor::new();
+        let window_tree;
+        // Offset of the tree's byte 0 in the buffer
+        let (root, tree_start) = match tree {
+            Some(tree) => {
+                query_cursor.set_byte_range(window.clone());
+                (tree.root_node(), 0)
+            }
+            None => {
+                window_tree = parser.parse(buffer.slice_bytes(window.clone()), None)?;
+                (window_tree.root_node(), window.start)
+            }
+        };
+        let node_range = |node: Node| node.start_byte() + tree_start..node.end_byte() + tree_start;
+        let text = |node: Node| std::iter::once(buffer.slice_bytes(node_range(node)));
+
+        let mut found = Vec::new();
+        let mut captures = query_cursor.captures(query, root, text);
+        while let Some((match_result, _)) = captures.next() {
+            for capture in match_result.captures {
+                if let Some(Some(kind)) = kinds.get(capture.index as usize) {
+                    let range = node_range(capture.node);
+                    found.push((
+                        *kind,
+                        range.start.saturating_sub(window.start)
+                            ..range.end.saturating_sub(window.start),
+                    ));
+                }
+            }
+        }
+        Some(found)
+    }
+
     /// Calculate indent for a new line at the given position
     ///
     /// Returns the number of spaces to indent, or None if auto-indent should be disabled
     pub fn calculate_indent(
         &mut self,
         buffer: &Buffer,
+        tree: Option<&Tree>,
         position: usize,
         language: &Language,
         tab_size: usize,
     ) -> Option<usize> {
         // Try tree-sitter-based indent
         if let Some(indent) =
-            self.calculate_indent_tree_sitter(buffer, position, language, tab_size)
+            self.calculate_indent_tree_sitter(buffer, tree, position, language, tab_size)
         {
             return Some(indent);
         }
@@ -224,7 +288,7 @@ impl IndentCalculator {
     /// when the syntax is incomplete (which is the common case during typing).
     ///
     /// ## Tree-sitter Path
-    /// 1. Parse buffer content before cursor (up to 2000 bytes)
+    /// 1. Query the syntax tree over the content before cursor (up to 2000 bytes)
     /// 2. Count @indent nodes at cursor position vs reference line
     /// 3. Calculate dedent based on nesting level difference
     /// 4. **Problem**: Fails when syntax is incomplete (e.g., missing closing brace)
@@ -249,15 +313,13 @@ impl IndentCalculator {
     pub fn calculate_dedent_for_delimiter(
         &mut self,
         buffer: &Buffer,
+        tree: Option<&Tree>,
         position: usize,
         _delimiter: char,
         language: &Language,
         tab_size: usize,
     ) -> Option<usize> {
-        // Get parser and query for this language
-        let (parser, query) = self.get_config(language)?;
-
-        // Extract context before cursor (for parsing)
+        // Context before cursor
         let parse_start = position.saturating_sub(MAX_PARSE_BYTES);
         let parse_range = parse_start..position;
 
@@ -265,22 +327,7 @@ impl IndentCalculator {
             return Some(0);
         }
 
-        let source = buffer.slice_bytes(parse_range.clone());
-
-        // Parse the source
-        let tree = parser.parse(&source, None)?;
-        let root = tree.root_node();
-
-        // Find capture index for @indent
-        let mut indent_capture_idx = None;
-        for (i, name) in query.capture_names().iter().enumerate() {
-            if *name == "indent" {
-                indent_capture_idx = Some(i);
-                break;
-            }
-        }
-
-        let indent_capture_idx = indent_capture_idx?;
+        let captures = self.window_captures(buffer, tree, parse_range, language)?;
 
         let cursor_offset = position - parse_start;
 
@@ -349,26 +396,19 @@ impl IndentCalculator {
         let mut reference_indent_count: i32 = 0;
         let mut cursor_indent_count: i32 = 0;
 
-        let mut query_cursor = QueryCursor::new();
-        let mut captures = query_cursor.captures(query, root, source.as_slice());
-
-        while let Some((match_result, _)) = captures.next() {
-            for capture in match_result.captures {
-                if capture.index == indent_capture_idx as u32 {
-                    let node = capture.node;
-                    let node_start = node.start_byte();
-                    let node_end = node.end_byte();
+        for (kind, node) in &captures {
+            if *kind != IndentCaptureKind::Indent {
+                continue;
+            }
 
-                    // Count @indent nodes at reference position
-                    if node_start < reference_line_offset && reference_line_offset <= node_end {
-                        reference_indent_count += 1;
-                    }
+            // Count @indent nodes at reference position
+            if node.start < reference_line_offset && reference_line_offset <= node.end {
+                reference_indent_count += 1;
+            }
 
-                    // Count @indent nodes at cursor position
-                    if node_start < cursor_offset && cursor_offset <= node_end {
-                        cursor_indent_count += 1;
-                    }
-                }
+            // Count @indent nodes at cursor position
+            if node.start < cursor_offset && cursor_offset <= node.end {
+                cursor_indent_count += 1;
             }
         }
 
@@ -671,14 +711,12 @@ impl IndentCalculator {
     fn calculate_indent_tree_sitter(
         &mut self,
         buffer: &Buffer,
+        tree: Option<&Tree>,
         position: usize,
         language: &Language,
         tab_size: usize,
     ) -> Option<usize> {
-        // Get parser and query
-        let (parser, query) = self.get_config(language)?;
-
-        // Extract context before cursor (for parsing)
+        // Context before cursor
         let parse_start = position.saturating_sub(MAX_PARSE_BYTES);
         let parse_range = parse_start..position;
 
@@ -687,24 +725,7 @@ impl IndentCalculator {
         }
 
         let source = buffer.slice_bytes(parse_range.clone());
-
-        // Parse the source
-        let tree = parser.parse(&source, None)?;
-        let root = tree.root_node();
-
-        // Find capture indices for @indent and @dedent
-        let mut indent_capture_idx = None;
-        let mut dedent_capture_idx = None;
-        for (i, name) in query.capture_names().iter().enumerate() {
-            if *name == "indent" {
-                indent_capture_idx = Some(i);
-            } else if *name == "dedent" {
-                dedent_capture_idx = Some(i);
-            }
-        }
-
-        // Query for indent/dedent captures
-        let mut query_cursor = QueryCursor::new();
+        let captures = self.window_captures(buffer, tree, parse_range, language)?;
 
         // Count indent/dedent at cursor position
         // The cursor position in the parsed text is (position - parse_start)
@@ -808,47 +829,37 @@ impl IndentCalculator {
         let mut reference_indent_count: i32 = 0;
         let mut cursor_indent_count: i32 = 0;
 
-        // Manually iterate through matches to count indent/dedent captures
-        let mut captures = query_cursor.captures(query, root, source.as_slice());
-        while let Some((match_result, _)) = captures.next() {
-            for capture in match_result.captures {
-                let node = capture.node;
-                let node_start = node.start_byte();
-                let node_end = node.end_byte();
-
+        for (kind, node) in &captures {
+            match kind {
                 // Count @indent nodes at reference position
-                if let Some(idx) = indent_capture_idx {
-                    if capture.index == idx as u32 {
-                        // Reference line: count if reference position is inside this node
-                        if node_start < reference_line_offset && reference_line_offset <= node_end {
-                            reference_indent_count += 1;
-                        }
+                IndentCaptureKind::Indent => {
+                    // Reference line: count if reference position is inside this node
+                    if node.start < reference_line_offset && reference_line_offset <= node.end {
+                        reference_indent_count += 1;
+                    }
 
-                        // Cursor position: count if cursor is inside this node
-                        // Also check: node must start on a previous line (not current line)
-                        let node_on_previous_line = node_start < line_start_offset;
-                        let cursor_inside_node =
-                            node_start < cursor_offset && cursor_offset <= node_end;
-
-                        if cursor_inside_node && node_on_previous_line && !last_nonws_is_closing {
-                            cursor_indent_count += 1;
-                            found_any_captures = true;
-                        } else if last_nonws_is_closing && cursor_inside_node {
-                            // Mark as found but don't count (closing delimiter line)
-                            found_any_captures = true;
-                        }
+                    // Cursor position: count if cursor is inside this node
+                    // Also check: node must start on a previous line (not current line)
+                    let node_on_previous_line = node.start < line_start_offset;
+                    let cursor_inside_node =
+                        node.start < cursor_offset && cursor_offset <= node.end;
+
+                    if cursor_inside_node && node_on_previous_line && !last_nonws_is_closing {
+                        cursor_indent_count += 1;
+                        found_any_captures = true;
+                    } else if last_nonws_is_closing && cursor_inside_node {
+                        // Mark as found but don't count (closing delimiter line)
+                        found_any_captures = true;
                     }
                 }
 
                 // Handle @dedent at cursor position
-                if let Some(idx) = dedent_capture_idx {
-                    if capture.index == idx as u32 {
-                        // Dedent node: only apply if cursor is right at the start of this dedent marker
-                        // Also ignore zero-width nodes (error recovery nodes)
-                        if cursor_offset == node_start && node_end > node_start {
-                            indent_delta -= 1;
-                            found_any_captures = true;
-                        }
+                IndentCaptureKind::Dedent => {
+                    // Dedent node: only apply if cursor is right at the start of this dedent marker
+                    // Also ignore zero-width nodes (error recovery nodes)
+                    if cursor_offset == node.start && node.end > node.start {
+                        indent_delta -= 1;
+                        found_any_captures = true;
                     }
                 }
             }
@@ -1001,7 +1012,7 @@ mod tests {
         println!("Pattern matching result: {:?}", pattern_result);
 
         // This should trigger tree-sitter parsing
-        let indent = calc.calculate_indent(&buffer, position, &Language::Rust, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::Rust, 4);
 
         println!("Test buffer: {:?}", buffer.to_string().unwrap());
         println!("Position: {}", position);
@@ -1024,7 +1035,7 @@ mod tests {
         let buffer = Buffer::from_str_test("def foo():");
         let position = buffer.len(); // After the :
 
-        let indent = calc.calculate_indent(&buffer, position, &Language::Python, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::Python, 4);
         assert!(indent.is_some());
         // Should suggest indenting
         assert!(indent.unwrap() >= 4);
@@ -1040,7 +1051,8 @@ mod tests {
 
         // Tree-sitter should recognize this is a complete block
         // Pattern matching would see '}' and not indent, but tree-sitter context should work
-        let ts_result = calc.calculate_indent_tree_sitter(&buffer, position, &Language::Rust, 4);
+        let ts_result =
+            calc.calculate_indent_tree_sitter(&buffer, None, position, &Language::Rust, 4);
 
         // Tree-sitter should return Some (even if it's 0 indent)
         assert!(
@@ -1059,7 +1071,7 @@ mod tests {
         let position = buffer.len();
 
         // This should be 8 spaces (maintaining nested indent from current line)
-        let indent = calc.calculate_indent(&buffer, position, &Language::Rust, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::Rust, 4);
         assert_eq!(
             indent,
             Some(8),
@@ -1090,7 +1102,7 @@ mod tests {
         let buffer = Buffer::from_str_test("    var x = 1;");
         let position = buffer.len();
 
-        let indent = calc.calculate_indent(&buffer, position, &Language::CSharp, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::CSharp, 4);
         // Should fall back to previous line indent (4 spaces)
         assert_eq!(indent, Some(4));
     }
@@ -1101,7 +1113,7 @@ mod tests {
         let buffer = Buffer::from_str_test("interface User {");
         let position = buffer.len(); // Position after the {
 
-        let indent = calc.calculate_indent(&buffer, position, &Language::TypeScript, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::TypeScript, 4);
         assert!(indent.is_some(), "TypeScript interface should get indent");
         assert_eq!(
             indent.unwrap(),
@@ -1141,7 +1153,7 @@ mod tests {
         let position = buffer.len(); // Position right after the }
 
         // Tree-sitter should recognize we're outside the block and return 0 indent
-        let indent = calc.calculate_indent(&buffer, position, &Language::Rust, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::Rust, 4);
         assert_eq!(
             indent,
             Some(0),
@@ -1149,7 +1161,8 @@ mod tests {
         );
 
         // Verify tree-sitter is being used (not just pattern fallback)
-        let ts_result = calc.calculate_indent_tree_sitter(&buffer, position, &Language::Rust, 4);
+        let ts_result =
+            calc.calculate_indent_tree_sitter(&buffer, None, position, &Language::Rust, 4);
         assert!(ts_result.is_some(), "Tree-sitter should handle this case");
     }
 
@@ -1164,7 +1177,7 @@ mod tests {
 
         // Calculate where the } should be placed using tree-sitter
         let correct_indent =
-            calc.calculate_dedent_for_delimiter(&buffer, position, '}', &Language::Rust, 4);
+            calc.calculate_dedent_for_delimiter(&buffer, None, position, '}', &Language::Rust, 4);
 
         // Should dedent to column 0 (same level as fn main)
         assert_eq!(
@@ -1179,6 +1192,7 @@ mod tests {
 
         let nested_indent = calc.calculate_dedent_for_delimiter(
             &nested_buffer,
+            None,
             nested_pos,
             '}',
             &Language::Rust,
@@ -1192,6 +1206,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_indent_with_document_tree() {
+        let buffer = Buffer::from_str_test("fn main() {\n    let x = 1;\n}\n");
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        let tree = parser.parse(buffer.get_all_text().unwrap(), None).unwrap();
+        let mut calc = IndentCalculator::new();
+
+        // After a statement in the block
+        let position = "fn main() {\n    let x = 1;".len();
+        let indent = calc.calculate_indent(&buffer, Some(&tree), position, &Language::Rust, 4);
+        assert_eq!(indent, Some(4));
+
+        // After the block's closing brace
+        let position = buffer.len() - 1;
+        let indent = calc.calculate_indent(&buffer, Some(&tree), position, &Language::Rust, 4);
+        assert_eq!(indent, Some(0));
+    }
+
     #[test]
     fn test_tree_sitter_handles_multiple_languages() {
         // Verify tree-sitter-based auto-dedent works across languages
@@ -1199,13 +1232,15 @@ mod tests {
 
         // Python
         let py_buffer = Buffer::from_str_test("def foo():\n    ");
-        let py_indent = calc.calculate_indent(&py_buffer, py_buffer.len(), &Language::Python, 4);
+        let py_indent =
+            calc.calculate_indent(&py_buffer, None, py_buffer.len(), &Language::Python, 4);
         assert_eq!(py_indent, Some(4), "Python should indent after colon");
 
         // JavaScript
         let js_buffer = Buffer::from_str_test("function foo() {\n    ");
         let js_dedent = calc.calculate_dedent_for_delimiter(
             &js_buffer,
+            None,
             js_buffer.len(),
             '}',
             &Language::JavaScript,
@@ -1217,6 +1252,7 @@ mod tests {
         let cpp_buffer = Buffer::from_str_test("class Foo {\n    ");
         let cpp_dedent = calc.calculate_dedent_for_delimiter(
             &cpp_buffer,
+            None,
             cpp_buffer.len(),
             '}',
             &Language::Cpp,
@@ -1234,7 +1270,7 @@ mod tests {
         let buffer = Buffer::from_str_test("fn main() {\n    let x = 1;\n}");
         let position = 27; // Position after second \n, before the }
 
-        let indent = calc.calculate_indent(&buffer, position, &Language::Rust, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::Rust, 4);
         tracing::trace!("TEST: With closing brace, indent = {:?}", indent);
         assert_eq!(
             indent,
@@ -1251,7 +1287,7 @@ mod tests {
         let buffer = Buffer::from_str_test("fn main() {\n    let x = 1;\n");
         let position = buffer.len(); // After the second \n, start of empty line
 
-        let indent = calc.calculate_indent(&buffer, position, &Language::Rust, 4);
+        let indent = calc.calculate_indent(&buffer, None, position, &Language::Rust, 4);
         tracing::trace!("TEST: Without closing brace, indent = {:?}", indent);
         // With incomplete syntax, tree-sitter returns ERROR nodes
         // We should fall back to pattern matching or reference line heuristic
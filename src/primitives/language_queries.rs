@@ -0,0 +1,72 @@
+//! Per-language tree-sitter query files
+//!
+//! Features such as text objects and the fallback outline run a query file
+//! (`textobjects.scm`, `outline.scm`, ...) over a buffer's syntax tree. The
+//! built-in files ship in `queries/<lang>/`; a file at the same path under the
+//! user's config directory (`~/.config/fresh/queries/<lang>/`) replaces the
+//! built-in one, so queries can be changed, or added for languages without a
+//! built-in one, without rebuilding.
+
+use crate::primitives::highlighter::Language;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tree_sitter::Query;
+
+/// Compiled queries of one query file, loaded on first use of each language
+pub struct LanguageQueries {
+    /// Name of the query file, e.g. `textobjects.scm`
+    file_name: &'static str,
+    /// The built-in query file for a language, if there is one
+    builtin: fn(&Language) -> Option<&'static str>,
+    /// Directory of the user's queries, `<lang>/<file_name>` inside it
+    user_dir: Option<PathBuf>,
+    queries: HashMap<&'static str, Result<Query, String>>,
+}
+
+impl LanguageQueries {
+    pub fn new(
+        file_name: &'static str,
+        builtin: fn(&Language) -> Option<&'static str>,
+        user_dir: Option<PathBuf>,
+    ) -> Self {
+        Self {
+            file_name,
+            builtin,
+            user_dir,
+            queries: HashMap::new(),
+        }
+    }
+
+    /// The query for `language`: the user's if there is one, else the built-in one
+    pub fn get(&mut self, language: &Language) -> Result<&Query, String> {
+        let name = language.query_dir();
+        if !self.queries.contains_key(name) {
+            let query = self.load(language);
+            if let Err(e) = &query {
+                tracing::warn!("{}", e);
+            }
+            self.queries.insert(name, query);
+        }
+        self.queries[name].as_ref().map_err(|e| e.clone())
+    }
+
+    fn load(&self, language: &Language) -> Result<Query, String> {
+        let name = language.query_dir();
+        let user_path = self
+            .user_dir
+            .as_ref()
+            .map(|dir| dir.join(name).join(self.file_name))
+            .filter(|path| path.is_file());
+        let source = match &user_path {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+            None => (self.builtin)(language)
+                .ok_or_else(|| format!("No {} query for {}", self.file_name, name))?
+                .to_string(),
+        };
+        Query::new(&language.ts_language(), &source).map_err(|e| match &user_path {
+            Some(path) => format!("Invalid query in {}: {}", path.display(), e),
+            None => format!("Invalid built-in {} for {}: {}", self.file_name, name, e),
+        })
+    }
+}
@@ -6,13 +6,19 @@
 pub mod ansi;
 pub mod ansi_background;
 pub mod display_width;
+pub mod fold_ranges;
 pub mod grammar_registry;
 pub mod highlight_engine;
 pub mod highlighter;
 pub mod indent;
+pub mod language_queries;
 pub mod line_iterator;
 pub mod line_wrapping;
+pub mod outline;
+pub mod selection_expansion;
 pub mod semantic_highlight;
+pub mod syntax_tree;
+pub mod text_objects;
 pub mod text_property;
 pub mod textmate_highlighter;
 pub mod visual_layout;
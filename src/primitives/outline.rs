@@ -0,0 +1,348 @@
+//! Document outlines
+//!
+//! An outline is the tree of a document's symbols: its modules, classes and
+//! functions, with the methods and fields of each class nested inside it.
+//! Symbols come from the language server (`textDocument/documentSymbol`) or,
+//! when no server is running, from the language's `outline.scm` query. A query
+//! captures each symbol as `@definition.<kind>` (e.g. `@definition.function`)
+//! and its name as `@name`; symbols nest by range, and functions nested in a
+//! class-like symbol become its methods.
+//!
+//! Queries ship in `queries/<lang>/outline.scm` and can be replaced by the
+//! user's own (see `primitives::language_queries`).
+
+use crate::primitives::highlighter::Language;
+use crate::primitives::language_queries::LanguageQueries;
+use lsp_types::{DocumentSymbol, Position, SymbolInformation, SymbolKind};
+use std::ops::Range;
+use std::path::PathBuf;
+use tree_sitter::{Query, QueryCursor, StreamingIterator, Tree};
+
+/// A symbol of the outline and the symbols nested inside it
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineSymbol {
+    pub name: String,
+    /// Extra information, such as a function's signature
+    pub detail: Option<String>,
+    pub kind: SymbolKind,
+    /// The whole symbol, e.g. a function with its body
+    pub range: Range<usize>,
+    /// The part to go to, usually the name
+    pub selection: Range<usize>,
+    pub children: Vec<OutlineSymbol>,
+}
+
+/// Outline from a hierarchical `textDocument/documentSymbol` response.
+/// `to_byte` converts a position in the document to a byte offset.
+pub fn from_document_symbols(
+    symbols: Vec<DocumentSymbol>,
+    to_byte: &impl Fn(Position) -> usize,
+) -> Vec<OutlineSymbol> {
+    let mut outline: Vec<OutlineSymbol> = symbols
+        .into_iter()
+        .map(|symbol| OutlineSymbol {
+            name: symbol.name,
+            detail: symbol.detail.filter(|detail| !detail.is_empty()),
+            kind: symbol.kind,
+            range: to_byte(symbol.range.start)..to_byte(symbol.range.end),
+            selection: to_byte(symbol.selection_range.start)..to_byte(symbol.selection_range.end),
+            children: from_document_symbols(symbol.children.unwrap_or_default(), to_byte),
+        })
+        .collect();
+    outline.sort_by_key(|symbol| symbol.range.start);
+    outline
+}
+
+/// Outline from a flat `textDocument/documentSymbol` response, nested by range
+pub fn from_symbol_information(
+    symbols: Vec<SymbolInformation>,
+    to_byte: &impl Fn(Position) -> usize,
+) -> Vec<OutlineSymbol> {
+    nest(
+        symbols
+            .into_iter()
+            .map(|symbol| {
+                let range = symbol.location.range;
+                let start = to_byte(range.start);
+                OutlineSymbol {
+                    name: symbol.name,
+                    detail: None,
+                    kind: symbol.kind,
+                    range: start..to_byte(range.end),
+                    selection: start..start,
+                    children: Vec::new(),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Outline from the syntax tree and the language's `outline.scm` query
+pub fn from_query(query: &Query, tree: &Tree, source: &[u8]) -> Vec<OutlineSymbol> {
+    let capture_names = query.capture_names();
+    let mut symbols = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source);
+    while let Some(query_match) = matches.next() {
+        let mut name: Option<Range<usize>> = None;
+        let mut definition: Option<(&str, Range<usize>)> = None;
+        for capture in query_match.captures {
+            let capture_name = capture_names[capture.index as usize];
+            if capture_name == "name" {
+                name = Some(capture.node.byte_range());
+            } else if let Some(kind) = capture_name.strip_prefix("definition.") {
+                definition = Some((kind, capture.node.byte_range()));
+            }
+        }
+        let (Some(selection), Some((kind, range))) = (name, definition) else {
+            continue;
+        };
+        let name = String::from_utf8_lossy(&source[selection.clone()]);
+        // Impl blocks are named after the type they implement
+        let name = if kind == "impl" {
+            format!("impl {}", name)
+        } else {
+            name.into_owned()
+        };
+        symbols.push(OutlineSymbol {
+            name,
+            detail: None,
+            kind: symbol_kind(kind),
+            range,
+            selection,
+            children: Vec::new(),
+        });
+    }
+
+    let mut outline = nest(symbols);
+    mark_methods(&mut outline, false);
+    outline
+}
+
+/// The symbol kind of a `@definition.<kind>` capture
+fn symbol_kind(kind: &str) -> SymbolKind {
+    match kind {
+        "function" | "macro" => SymbolKind::FUNCTION,
+        "method" => SymbolKind::METHOD,
+        "constructor" => SymbolKind::CONSTRUCTOR,
+        "class" => SymbolKind::CLASS,
+        "struct" => SymbolKind::STRUCT,
+        "enum" => SymbolKind::ENUM,
+        "variant" => SymbolKind::ENUM_MEMBER,
+        "interface" => SymbolKind::INTERFACE,
+        "impl" => SymbolKind::OBJECT,
+        "module" => SymbolKind::MODULE,
+        "namespace" => SymbolKind::NAMESPACE,
+        "constant" => SymbolKind::CONSTANT,
+        "field" => SymbolKind::FIELD,
+        "property" => SymbolKind::PROPERTY,
+        "type" => SymbolKind::TYPE_PARAMETER,
+        _ => SymbolKind::VARIABLE,
+    }
+}
+
+/// Whether symbols of this kind have methods rather than functions
+fn is_class_like(kind: SymbolKind) -> bool {
+    [
+        SymbolKind::CLASS,
+        SymbolKind::STRUCT,
+        SymbolKind::ENUM,
+        SymbolKind::INTERFACE,
+        SymbolKind::OBJECT,
+    ]
+    .contains(&kind)
+}
+
+fn mark_methods(symbols: &mut [OutlineSymbol], in_class: bool) {
+    for symbol in symbols {
+        if in_class && symbol.kind == SymbolKind::FUNCTION {
+            symbol.kind = SymbolKind::METHOD;
+        }
+        let class_like = is_class_like(symbol.kind);
+        mark_methods(&mut symbol.children, class_like);
+    }
+}
+
+/// Nest symbols by range: each symbol becomes a child of the smallest symbol
+/// containing it. Of several symbols with the same range, the first is kept.
+fn nest(mut symbols: Vec<OutlineSymbol>) -> Vec<OutlineSymbol> {
+    symbols.sort_by(|a, b| {
+        a.range
+            .start
+            .cmp(&b.range.start)
+            .then(b.range.end.cmp(&a.range.end))
+    });
+    symbols.dedup_by(|later, earlier| later.range == earlier.range);
+
+    fn attach(
+        stack: &mut Vec<OutlineSymbol>,
+        roots: &mut Vec<OutlineSymbol>,
+        symbol: OutlineSymbol,
+    ) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(symbol),
+            None => roots.push(symbol),
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut stack: Vec<OutlineSymbol> = Vec::new();
+    for symbol in symbols {
+        while let Some(top) = stack.last() {
+            if symbol.range.end <= top.range.end {
+                break;
+            }
+            let done = stack.pop().expect("stack is not empty");
+            attach(&mut stack, &mut roots, done);
+        }
+        stack.push(symbol);
+    }
+    while let Some(done) = stack.pop() {
+        attach(&mut stack, &mut roots, done);
+    }
+    roots
+}
+
+/// The outline's symbols in document order, each with its depth
+pub fn flatten(symbols: &[OutlineSymbol]) -> Vec<(usize, &OutlineSymbol)> {
+    fn walk<'a>(
+        symbols: &'a [OutlineSymbol],
+        depth: usize,
+        out: &mut Vec<(usize, &'a OutlineSymbol)>,
+    ) {
+        for symbol in symbols {
+            out.push((depth, symbol));
+            walk(&symbol.children, depth + 1, out);
+        }
+    }
+
+    let mut flat = Vec::new();
+    walk(symbols, 0, &mut flat);
+    flat
+}
+
+/// Index in `flat` of the innermost symbol containing `position`
+pub fn symbol_at(flat: &[(usize, &OutlineSymbol)], position: usize) -> Option<usize> {
+    flat.iter()
+        .rposition(|(_, symbol)| symbol.range.contains(&position))
+}
+
+/// Short name of a symbol kind, shown next to symbols
+pub fn kind_label(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::FILE => "file",
+        SymbolKind::MODULE => "module",
+        SymbolKind::NAMESPACE => "namespace",
+        SymbolKind::PACKAGE => "package",
+        SymbolKind::CLASS => "class",
+        SymbolKind::METHOD => "method",
+        SymbolKind::PROPERTY => "property",
+        SymbolKind::FIELD => "field",
+        SymbolKind::CONSTRUCTOR => "constructor",
+        SymbolKind::ENUM => "enum",
+        SymbolKind::INTERFACE => "interface",
+        SymbolKind::FUNCTION => "function",
+        SymbolKind::VARIABLE => "variable",
+        SymbolKind::CONSTANT => "constant",
+        SymbolKind::ENUM_MEMBER => "variant",
+        SymbolKind::STRUCT => "struct",
+        SymbolKind::EVENT => "event",
+        SymbolKind::OPERATOR => "operator",
+        SymbolKind::TYPE_PARAMETER => "type",
+        SymbolKind::OBJECT => "object",
+        _ => "symbol",
+    }
+}
+
+/// The `outline.scm` query shipped for a language
+fn builtin_query(language: &Language) -> Option<&'static str> {
+    match language {
+        Language::Rust => Some(include_str!("../../queries/rust/outline.scm")),
+        Language::Python => Some(include_str!("../../queries/python/outline.scm")),
+        Language::JavaScript => Some(include_str!("../../queries/javascript/outline.scm")),
+        Language::TypeScript => Some(include_str!("../../queries/typescript/outline.scm")),
+        Language::C => Some(include_str!("../../queries/c/outline.scm")),
+        Language::Cpp => Some(include_str!("../../queries/cpp/outline.scm")),
+        Language::Go => Some(include_str!("../../queries/go/outline.scm")),
+        Language::Java => Some(include_str!("../../queries/java/outline.scm")),
+        Language::CSharp => Some(include_str!("../../queries/csharp/outline.scm")),
+        Language::Php => Some(include_str!("../../queries/php/outline.scm")),
+        Language::Ruby => Some(include_str!("../../queries/ruby/outline.scm")),
+        Language::Bash => Some(include_str!("../../queries/bash/outline.scm")),
+        Language::HTML | Language::CSS | Language::Json | Language::Lua => None,
+    }
+}
+
+/// Compiled `outline.scm` queries, the user's where they exist
+pub fn queries(user_dir: Option<PathBuf>) -> LanguageQueries {
+    LanguageQueries::new("outline.scm", builtin_query, user_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+
+    const SOURCE: &str = "struct Point {\n    x: i32,\n}\n\nimpl Point {\n    fn new() -> Self {\n        Point { x: 0 }\n    }\n}\n\nfn main() {}\n";
+
+    fn rust_outline() -> Vec<OutlineSymbol> {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        let tree = parser.parse(SOURCE, None).unwrap();
+        let mut queries = queries(None);
+        let query = queries.get(&Language::Rust).unwrap();
+        from_query(query, &tree, SOURCE.as_bytes())
+    }
+
+    #[test]
+    fn test_query_outline_nests_symbols() {
+        let outline = rust_outline();
+        let flat: Vec<(usize, &str, SymbolKind)> = flatten(&outline)
+            .into_iter()
+            .map(|(depth, symbol)| (depth, symbol.name.as_str(), symbol.kind))
+            .collect();
+        assert_eq!(
+            flat,
+            vec![
+                (0, "Point", SymbolKind::STRUCT),
+                (1, "x", SymbolKind::FIELD),
+                (0, "impl Point", SymbolKind::OBJECT),
+                (1, "new", SymbolKind::METHOD),
+                (0, "main", SymbolKind::FUNCTION),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symbol_at_finds_innermost() {
+        let outline = rust_outline();
+        let flat = flatten(&outline);
+        let body = SOURCE.find("Point { x: 0 }").unwrap();
+        assert_eq!(flat[symbol_at(&flat, body).unwrap()].1.name, "new");
+        let between = SOURCE.find("\n\nfn main").unwrap() + 1;
+        assert_eq!(symbol_at(&flat, between), None);
+    }
+
+    #[test]
+    fn test_flat_symbols_nest_by_range() {
+        let symbol = |name: &str, range: Range<usize>| OutlineSymbol {
+            name: name.to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            selection: range.start..range.start,
+            range,
+            children: Vec::new(),
+        };
+        let outline = nest(vec![
+            symbol("b", 10..20),
+            symbol("a", 0..50),
+            symbol("c", 30..40),
+            symbol("d", 60..70),
+        ]);
+        let flat: Vec<(usize, &str)> = flatten(&outline)
+            .into_iter()
+            .map(|(depth, symbol)| (depth, symbol.name.as_str()))
+            .collect();
+        assert_eq!(flat, vec![(0, "a"), (1, "b"), (1, "c"), (0, "d")]);
+    }
+}
@@ -0,0 +1,203 @@
+//! Syntax-aware selection expansion
+//!
+//! Expanding a selection grows it to the smallest syntax node that strictly
+//! contains it (identifier → expression → statement → block → function).
+//! Buffers without a syntax tree use the ranges from the language server's
+//! `textDocument/selectionRange` answer instead.
+//!
+//! Shrinking steps back through the selections that expansions replaced, per
+//! cursor. A selection that wasn't made by expanding shrinks to the node inside
+//! it that contains the cursor.
+
+use crate::model::event::CursorId;
+use std::collections::HashMap;
+use std::ops::Range;
+use tree_sitter::Tree;
+
+/// The smallest named node strictly containing `selection`
+pub fn expand_in_tree(tree: &Tree, selection: Range<usize>) -> Option<Range<usize>> {
+    let mut node = tree
+        .root_node()
+        .named_descendant_for_byte_range(selection.start, selection.end)?;
+    loop {
+        let range = node.byte_range();
+        if contains(&range, &selection) && range != selection {
+            return Some(range);
+        }
+        node = node.parent()?;
+    }
+}
+
+/// The largest named node inside `selection` that contains `position`
+pub fn shrink_in_tree(
+    tree: &Tree,
+    selection: Range<usize>,
+    position: usize,
+) -> Option<Range<usize>> {
+    let mut node = tree
+        .root_node()
+        .named_descendant_for_byte_range(selection.start, selection.end)?;
+    let mut cursor = tree.walk();
+    loop {
+        let child = node.named_children(&mut cursor).find(|child| {
+            let range = child.byte_range();
+            contains(&selection, &range) && range.start <= position && position <= range.end
+        })?;
+        if child.byte_range() != selection {
+            return Some(child.byte_range());
+        }
+        node = child;
+    }
+}
+
+/// The smallest range of a language server selection range chain (innermost
+/// first) that strictly contains `selection`
+pub fn expand_in_chain(chain: &[Range<usize>], selection: Range<usize>) -> Option<Range<usize>> {
+    chain
+        .iter()
+        .find(|range| contains(range, &selection) && **range != selection)
+        .cloned()
+}
+
+fn contains(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// A selection replaced by an expansion, and the selection it was replaced with
+#[derive(Debug, Clone)]
+struct Expansion {
+    before: Range<usize>,
+    after: Range<usize>,
+}
+
+/// Expansion history of a buffer's cursors and the language server's selection ranges
+#[derive(Debug, Default)]
+pub struct SelectionExpansion {
+    history: HashMap<CursorId, Vec<Expansion>>,
+    /// Chains from the server's last `textDocument/selectionRange` answer, innermost first
+    lsp_chains: Option<Vec<Vec<Range<usize>>>>,
+}
+
+impl SelectionExpansion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forget everything; byte ranges are invalid after an edit
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.lsp_chains = None;
+    }
+
+    /// Remember that a cursor's selection was expanded from `before` to `after`
+    pub fn record(&mut self, cursor_id: CursorId, before: Range<usize>, after: Range<usize>) {
+        let history = self.history.entry(cursor_id).or_default();
+        // A selection changed since the last expansion starts a new history
+        if history.last().is_some_and(|last| last.after != before) {
+            history.clear();
+        }
+        history.push(Expansion { before, after });
+    }
+
+    /// The selection a cursor had before its `current` selection was expanded,
+    /// or None if `current` wasn't made by expanding
+    pub fn previous(&mut self, cursor_id: CursorId, current: Range<usize>) -> Option<Range<usize>> {
+        let history = self.history.get_mut(&cursor_id)?;
+        match history.pop() {
+            Some(expansion) if expansion.after == current => Some(expansion.before),
+            _ => {
+                history.clear();
+                None
+            }
+        }
+    }
+
+    /// Whether the language server's selection ranges are known for the current content
+    pub fn has_lsp_chains(&self) -> bool {
+        self.lsp_chains.is_some()
+    }
+
+    /// Replace the language server's selection ranges
+    pub fn set_lsp_chains(&mut self, chains: Vec<Vec<Range<usize>>>) {
+        self.lsp_chains = Some(chains);
+    }
+
+    /// Expand `selection` using the language server's selection ranges
+    pub fn expand_with_lsp(&self, selection: Range<usize>) -> Option<Range<usize>> {
+        self.lsp_chains
+            .as_ref()?
+            .iter()
+            .filter_map(|chain| expand_in_chain(chain, selection.clone()))
+            .min_by_key(|range| range.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::highlighter::Language;
+    use tree_sitter::Parser;
+
+    const SOURCE: &str = "fn main() {\n    let x = foo(a + b);\n}\n";
+
+    fn parse_rust(source: &str) -> Tree {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    fn text(range: Range<usize>) -> &'static str {
+        &SOURCE[range]
+    }
+
+    #[test]
+    fn test_expand_walks_ancestors() {
+        let tree = parse_rust(SOURCE);
+        let a = SOURCE.find("a +").unwrap();
+
+        let identifier = expand_in_tree(&tree, a..a).unwrap();
+        assert_eq!(text(identifier.clone()), "a");
+        let binary = expand_in_tree(&tree, identifier).unwrap();
+        assert_eq!(text(binary.clone()), "a + b");
+        let arguments = expand_in_tree(&tree, binary).unwrap();
+        assert_eq!(text(arguments.clone()), "(a + b)");
+        let call = expand_in_tree(&tree, arguments).unwrap();
+        assert_eq!(text(call), "foo(a + b)");
+    }
+
+    #[test]
+    fn test_shrink_toward_cursor() {
+        let tree = parse_rust(SOURCE);
+        let call_start = SOURCE.find("foo").unwrap();
+        let call = call_start..call_start + "foo(a + b)".len();
+        let b = SOURCE.find("b)").unwrap();
+
+        let arguments = shrink_in_tree(&tree, call, b).unwrap();
+        assert_eq!(text(arguments.clone()), "(a + b)");
+        let binary = shrink_in_tree(&tree, arguments, b).unwrap();
+        assert_eq!(text(binary), "a + b");
+    }
+
+    #[test]
+    fn test_history_and_lsp_chains() {
+        let cursor = CursorId(0);
+        let mut expansion = SelectionExpansion::new();
+        expansion.record(cursor, 5..5, 4..7);
+        expansion.record(cursor, 4..7, 0..10);
+        assert_eq!(expansion.previous(cursor, 0..10), Some(4..7));
+        assert_eq!(expansion.previous(cursor, 4..7), Some(5..5));
+        assert_eq!(expansion.previous(cursor, 5..5), None);
+
+        // An expansion from a selection that wasn't the last result starts over
+        expansion.record(cursor, 5..5, 4..7);
+        expansion.record(cursor, 20..22, 18..30);
+        assert_eq!(expansion.previous(cursor, 18..30), Some(20..22));
+        assert_eq!(expansion.previous(cursor, 4..7), None);
+
+        assert!(!expansion.has_lsp_chains());
+        expansion.set_lsp_chains(vec![vec![4..7, 0..10, 0..40], vec![20..22, 18..30]]);
+        assert_eq!(expansion.expand_with_lsp(4..7), Some(0..10));
+        assert_eq!(expansion.expand_with_lsp(19..22), Some(18..30));
+        assert_eq!(expansion.expand_with_lsp(0..40), None);
+    }
+}
@@ -0,0 +1,256 @@
+//! Full-document tree-sitter syntax trees
+//!
+//! Each buffer with a tree-sitter language keeps one syntax tree of the whole
+//! document. The first parse runs on a background thread, over a snapshot of
+//! the text; after that every insert and delete is applied to the tree with
+//! `Tree::edit`, and the next query re-parses incrementally, reusing the
+//! subtrees the edit didn't touch. Re-parses read the buffer piece by piece
+//! rather than copying it.
+//!
+//! Large files are never parsed whole: for them (and until the background
+//! parse finishes) `tree()` returns `None`, and highlighting falls back to
+//! parsing a window around the viewport. Auto-indentation queries the tree
+//! around the cursor in the same way (see `IndentCalculator`).
+
+use crate::model::buffer::Buffer;
+use crate::primitives::highlighter::Language;
+use std::ops::Range;
+use std::sync::mpsc;
+use tree_sitter::{InputEdit, Parser, Point, Tree};
+
+/// Syntax tree of a buffer, kept up to date with its edits
+pub struct SyntaxTree {
+    language: Language,
+    parser: Parser,
+    tree: Option<Tree>,
+    /// Edits were applied to the tree since it was last parsed
+    stale: bool,
+    /// Buffer length the tree (with its edits) corresponds to; a buffer of another
+    /// length was changed behind our back and gets parsed from scratch
+    expected_len: usize,
+    /// The initial parse, running on a background thread
+    pending: Option<PendingParse>,
+}
+
+/// A background parse and the edits made while it runs
+struct PendingParse {
+    receiver: mpsc::Receiver<Option<Tree>>,
+    edits: Vec<InputEdit>,
+}
+
+impl SyntaxTree {
+    /// Start parsing a buffer in the background.
+    /// Returns None for large files and buffers whose text isn't loaded.
+    pub fn new(language: Language, buffer: &Buffer) -> Option<Self> {
+        if buffer.is_large_file() {
+            return None;
+        }
+        let source = buffer.get_all_text()?;
+        let mut parser = Parser::new();
+        parser.set_language(&language.ts_language()).ok()?;
+
+        let (sender, receiver) = mpsc::channel();
+        let ts_language = language.ts_language();
+        std::thread::spawn(move || {
+            let mut parser = Parser::new();
+            let tree = parser
+                .set_language(&ts_language)
+                .ok()
+                .and_then(|_| parser.parse(&source, None));
+            // The buffer may be gone by now
+            let _ = sender.send(tree);
+        });
+
+        Some(Self {
+            language,
+            parser,
+            tree: None,
+            stale: false,
+            expected_len: buffer.len(),
+            pending: Some(PendingParse {
+                receiver,
+                edits: Vec::new(),
+            }),
+        })
+    }
+
+    /// The language of the tree
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+
+    /// Record an insertion. Call before the buffer is changed.
+    pub fn edit_insert(&mut self, buffer: &Buffer, position: usize, text: &str) {
+        let start = point_at(buffer, position);
+        self.edit(InputEdit {
+            start_byte: position,
+            old_end_byte: position,
+            new_end_byte: position + text.len(),
+            start_position: start,
+            old_end_position: start,
+            new_end_position: point_after(start, text),
+        });
+        self.expected_len += text.len();
+    }
+
+    /// Record a deletion. Call before the buffer is changed.
+    pub fn edit_delete(&mut self, buffer: &Buffer, range: Range<usize>, deleted_text: &str) {
+        let start = point_at(buffer, range.start);
+        self.edit(InputEdit {
+            start_byte: range.start,
+            old_end_byte: range.end,
+            new_end_byte: range.start,
+            start_position: start,
+            old_end_position: point_after(start, deleted_text),
+            new_end_position: start,
+        });
+        self.expected_len = self.expected_len.saturating_sub(range.len());
+    }
+
+    fn edit(&mut self, edit: InputEdit) {
+        if let Some(pending) = &mut self.pending {
+            pending.edits.push(edit);
+        } else if let Some(tree) = &mut self.tree {
+            tree.edit(&edit);
+            self.stale = true;
+        }
+    }
+
+    /// Pick up the result of the background parse.
+    /// Returns true when it finished since the last call.
+    pub fn poll(&mut self) -> bool {
+        let Some(pending) = &self.pending else {
+            return false;
+        };
+        let result = match pending.receiver.try_recv() {
+            Ok(tree) => tree,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => None,
+        };
+        let edits = self.pending.take().map(|p| p.edits).unwrap_or_default();
+        self.tree = result.map(|mut tree| {
+            for edit in &edits {
+                tree.edit(edit);
+            }
+            tree
+        });
+        self.stale = !edits.is_empty();
+        true
+    }
+
+    /// The syntax tree of the buffer's current content, re-parsing the edited
+    /// parts first. None while the initial parse is still running.
+    pub fn tree(&mut self, buffer: &Buffer) -> Option<&Tree> {
+        self.poll();
+        if self.pending.is_some() {
+            return None;
+        }
+        if buffer.len() != self.expected_len {
+            // Changed without going through edit_insert/edit_delete
+            self.tree = None;
+            self.expected_len = buffer.len();
+        }
+        if self.tree.is_none() || self.stale {
+            self.tree = Some(parse_buffer(&mut self.parser, buffer, self.tree.as_ref())?);
+            self.stale = false;
+        }
+        self.tree.as_ref()
+    }
+}
+
+/// Parse a buffer chunk by chunk. None if some of its text isn't loaded.
+fn parse_buffer(parser: &mut Parser, buffer: &Buffer, old_tree: Option<&Tree>) -> Option<Tree> {
+    let mut unloaded = false;
+    let tree = parser.parse_with_options(
+        &mut |offset, _| {
+            buffer.chunk_at(offset).unwrap_or_else(|| {
+                unloaded = true;
+                &[]
+            })
+        },
+        old_tree,
+        None,
+    )?;
+    (!unloaded).then_some(tree)
+}
+
+/// Tree-sitter point (row, byte column) of a buffer offset
+fn point_at(buffer: &Buffer, offset: usize) -> Point {
+    buffer
+        .offset_to_position(offset)
+        .map(|pos| Point::new(pos.line, pos.column))
+        .unwrap_or_default()
+}
+
+/// The point reached by inserting `text` at `start`
+fn point_after(start: Point, text: &str) -> Point {
+    match text.rfind('\n') {
+        Some(last_newline) => Point::new(
+            start.row + text.matches('\n').count(),
+            text.len() - last_newline - 1,
+        ),
+        None => Point::new(start.row, start.column + text.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wait_for_tree<'a>(syntax: &'a mut SyntaxTree, buffer: &Buffer) -> &'a Tree {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while syntax.pending.is_some() && std::time::Instant::now() < deadline {
+            syntax.poll();
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        syntax.tree(buffer).expect("tree should be parsed")
+    }
+
+    fn fresh_parse(buffer: &Buffer) -> String {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        let source = buffer.get_all_text().unwrap();
+        parser.parse(&source, None).unwrap().root_node().to_sexp()
+    }
+
+    #[test]
+    fn test_incremental_edits_match_fresh_parse() {
+        let mut buffer = Buffer::from_str_test("fn main() {\n    let x = 1;\n}\n");
+        let mut syntax = SyntaxTree::new(Language::Rust, &buffer).unwrap();
+
+        // Edits made before the background parse is picked up are replayed on its tree
+        syntax.edit_insert(&buffer, 0, "/* start\n");
+        buffer.insert(0, "/* start\n");
+        wait_for_tree(&mut syntax, &buffer);
+
+        syntax.edit_insert(&buffer, buffer.len(), "*/\n");
+        buffer.insert(buffer.len(), "*/\n");
+        let tree = syntax.tree(&buffer).unwrap();
+        // The whole document is one block comment now
+        assert_eq!(tree.root_node().to_sexp(), "(source_file (block_comment))");
+
+        let deleted = buffer.slice_bytes(0..9);
+        syntax.edit_delete(&buffer, 0..9, std::str::from_utf8(&deleted).unwrap());
+        buffer.delete(0..9);
+        let sexp = syntax.tree(&buffer).unwrap().root_node().to_sexp();
+        assert_eq!(sexp, fresh_parse(&buffer));
+    }
+
+    #[test]
+    fn test_untracked_change_reparses() {
+        let mut buffer = Buffer::from_str_test("fn main() {}\n");
+        let mut syntax = SyntaxTree::new(Language::Rust, &buffer).unwrap();
+        wait_for_tree(&mut syntax, &buffer);
+
+        buffer.insert(0, "struct S;\n");
+        let sexp = syntax.tree(&buffer).unwrap().root_node().to_sexp();
+        assert_eq!(sexp, fresh_parse(&buffer));
+    }
+
+    #[test]
+    fn test_point_after() {
+        assert_eq!(point_after(Point::new(2, 4), "abc"), Point::new(2, 7));
+        assert_eq!(point_after(Point::new(2, 4), "ab\ncd\nx"), Point::new(4, 1));
+        assert_eq!(point_after(Point::new(0, 3), "\n"), Point::new(1, 0));
+    }
+}
@@ -0,0 +1,304 @@
+//! Structural text objects
+//!
+//! Text objects are the functions, classes, parameters, comments and blocks of
+//! a buffer, found by running its language's `textobjects.scm` query over the
+//! syntax tree. A query captures the whole object as `@<object>.outer` and,
+//! optionally, its contents as `@<object>.inner` (e.g. `@function.outer` and
+//! `@function.inner` for a function and its body). When a capture name appears
+//! several times in one match, such as a parameter and the comma after it, the
+//! range spans all of them. Inner ranges lose one pair of enclosing brackets
+//! and the whitespace inside them.
+//!
+//! Queries ship in `queries/<lang>/textobjects.scm` and can be replaced by the
+//! user's own (see `primitives::language_queries`).
+
+use crate::primitives::highlighter::Language;
+use crate::primitives::language_queries::LanguageQueries;
+use std::ops::Range;
+use std::path::PathBuf;
+use tree_sitter::{Query, QueryCursor, StreamingIterator, Tree};
+
+/// Kinds of text objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextObject {
+    Function,
+    Class,
+    /// Parameters of a definition and arguments of a call
+    Parameter,
+    Comment,
+    Block,
+}
+
+impl TextObject {
+    /// Prefix of the object's capture names
+    pub fn capture_name(&self) -> &'static str {
+        match self {
+            TextObject::Function => "function",
+            TextObject::Class => "class",
+            TextObject::Parameter => "parameter",
+            TextObject::Comment => "comment",
+            TextObject::Block => "block",
+        }
+    }
+}
+
+/// A text object's whole range and the range of its contents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextObjectRange {
+    pub outer: Range<usize>,
+    pub inner: Range<usize>,
+}
+
+/// Objects of one kind, sorted by start. When several start at the same
+/// byte, the largest one wins.
+pub fn find_objects(
+    query: &Query,
+    tree: &Tree,
+    source: &[u8],
+    object: TextObject,
+) -> Vec<TextObjectRange> {
+    let outer_name = format!("{}.outer", object.capture_name());
+    let inner_name = format!("{}.inner", object.capture_name());
+    let capture_names = query.capture_names();
+
+    let mut objects = Vec::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), source);
+    while let Some(query_match) = matches.next() {
+        let mut outer: Option<Range<usize>> = None;
+        let mut inner: Option<Range<usize>> = None;
+        for capture in query_match.captures {
+            let name = capture_names[capture.index as usize];
+            let slot = if name == outer_name {
+                &mut outer
+            } else if name == inner_name {
+                &mut inner
+            } else {
+                continue;
+            };
+            let range = capture.node.byte_range();
+            *slot = Some(match slot.take() {
+                Some(spanned) => spanned.start.min(range.start)..spanned.end.max(range.end),
+                None => range,
+            });
+        }
+        let Some(outer) = outer else {
+            continue;
+        };
+        let inner = trim_delimiters(source, inner.unwrap_or_else(|| outer.clone()));
+        objects.push(TextObjectRange { outer, inner });
+    }
+
+    // Sort by start, largest first, then keep the first of each start
+    objects.sort_by(|a, b| {
+        a.outer
+            .start
+            .cmp(&b.outer.start)
+            .then(b.outer.end.cmp(&a.outer.end))
+    });
+    objects.dedup_by_key(|object| object.outer.start);
+    objects
+}
+
+/// Start of the first object starting after `position`
+pub fn next_start(objects: &[TextObjectRange], position: usize) -> Option<usize> {
+    objects
+        .iter()
+        .map(|object| object.outer.start)
+        .find(|&start| start > position)
+}
+
+/// Start of the last object starting before `position`
+pub fn previous_start(objects: &[TextObjectRange], position: usize) -> Option<usize> {
+    objects
+        .iter()
+        .map(|object| object.outer.start)
+        .rfind(|&start| start < position)
+}
+
+/// The range to select from `selection`: the inner (or, `around`, outer) range
+/// of the smallest object containing the selection, skipping objects whose
+/// range is already selected so that repeating grows outwards. Without an
+/// enclosing object, the next object after the selection.
+pub fn select(
+    objects: &[TextObjectRange],
+    selection: Range<usize>,
+    around: bool,
+) -> Option<Range<usize>> {
+    let range_of = |object: &TextObjectRange| {
+        if around {
+            object.outer.clone()
+        } else {
+            object.inner.clone()
+        }
+    };
+
+    let mut enclosing: Vec<&TextObjectRange> = objects
+        .iter()
+        .filter(|object| object.outer.start <= selection.start && selection.end <= object.outer.end)
+        .collect();
+    enclosing.sort_by_key(|object| object.outer.len());
+    enclosing
+        .into_iter()
+        .map(range_of)
+        .find(|range| *range != selection)
+        .or_else(|| {
+            objects
+                .iter()
+                .find(|object| object.outer.start >= selection.end)
+                .map(range_of)
+        })
+}
+
+/// Strip one pair of enclosing brackets, then surrounding whitespace
+fn trim_delimiters(source: &[u8], range: Range<usize>) -> Range<usize> {
+    let Range { mut start, mut end } = range;
+    if end >= start + 2
+        && matches!(
+            (source[start], source[end - 1]),
+            (b'{', b'}') | (b'(', b')') | (b'[', b']')
+        )
+    {
+        start += 1;
+        end -= 1;
+    }
+    while start < end && source[start].is_ascii_whitespace() {
+        start += 1;
+    }
+    while end > start && source[end - 1].is_ascii_whitespace() {
+        end -= 1;
+    }
+    start..end
+}
+
+/// The `textobjects.scm` query shipped for a language
+fn builtin_query(language: &Language) -> Option<&'static str> {
+    match language {
+        Language::Rust => Some(include_str!("../../queries/rust/textobjects.scm")),
+        Language::Python => Some(include_str!("../../queries/python/textobjects.scm")),
+        Language::JavaScript => Some(include_str!("../../queries/javascript/textobjects.scm")),
+        Language::TypeScript => Some(include_str!("../../queries/typescript/textobjects.scm")),
+        Language::C => Some(include_str!("../../queries/c/textobjects.scm")),
+        Language::Cpp => Some(include_str!("../../queries/cpp/textobjects.scm")),
+        Language::Go => Some(include_str!("../../queries/go/textobjects.scm")),
+        Language::Java => Some(include_str!("../../queries/java/textobjects.scm")),
+        Language::CSharp => Some(include_str!("../../queries/csharp/textobjects.scm")),
+        Language::Php => Some(include_str!("../../queries/php/textobjects.scm")),
+        Language::Ruby => Some(include_str!("../../queries/ruby/textobjects.scm")),
+        Language::Bash => Some(include_str!("../../queries/bash/textobjects.scm")),
+        Language::HTML | Language::CSS | Language::Json | Language::Lua => None,
+    }
+}
+
+/// Compiled `textobjects.scm` queries, the user's where they exist
+pub fn queries(user_dir: Option<PathBuf>) -> LanguageQueries {
+    LanguageQueries::new("textobjects.scm", builtin_query, user_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+
+    const SOURCE: &str =
+        "// add\nfn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\nfn main() {\n    add(1, 2);\n}\n";
+
+    fn rust_objects(object: TextObject) -> Vec<TextObjectRange> {
+        let mut parser = Parser::new();
+        parser.set_language(&Language::Rust.ts_language()).unwrap();
+        let tree = parser.parse(SOURCE, None).unwrap();
+        let mut queries = queries(None);
+        let query = queries.get(&Language::Rust).unwrap();
+        find_objects(query, &tree, SOURCE.as_bytes(), object)
+    }
+
+    fn text(range: Range<usize>) -> &'static str {
+        &SOURCE[range]
+    }
+
+    #[test]
+    fn test_builtin_queries_compile() {
+        let languages = [
+            Language::Rust,
+            Language::Python,
+            Language::JavaScript,
+            Language::TypeScript,
+            Language::C,
+            Language::Cpp,
+            Language::Go,
+            Language::Java,
+            Language::CSharp,
+            Language::Php,
+            Language::Ruby,
+            Language::Bash,
+        ];
+        let mut queries = queries(None);
+        for language in &languages {
+            assert!(queries.get(language).is_ok(), "{:?}", language);
+        }
+        assert!(queries.get(&Language::Json).is_err());
+    }
+
+    #[test]
+    fn test_functions_and_navigation() {
+        let functions = rust_objects(TextObject::Function);
+        assert_eq!(functions.len(), 2);
+        assert!(text(functions[0].outer.clone()).starts_with("fn add"));
+        assert_eq!(text(functions[0].inner.clone()), "a + b");
+
+        let main = SOURCE.find("fn main").unwrap();
+        assert_eq!(next_start(&functions, 0), Some(functions[0].outer.start));
+        assert_eq!(next_start(&functions, functions[0].outer.start), Some(main));
+        assert_eq!(
+            previous_start(&functions, main),
+            Some(functions[0].outer.start)
+        );
+        assert_eq!(next_start(&functions, main), None);
+    }
+
+    #[test]
+    fn test_select_parameters() {
+        let parameters = rust_objects(TextObject::Parameter);
+        let a = SOURCE.find("a: i32").unwrap();
+
+        // Around includes the comma after the parameter
+        let around = select(&parameters, a..a, true).unwrap();
+        assert_eq!(text(around), "a: i32,");
+        let inside = select(&parameters, a..a, false).unwrap();
+        assert_eq!(text(inside.clone()), "a: i32");
+
+        // Outside any argument list, the next parameter is selected
+        let next = select(&parameters, 0..0, false).unwrap();
+        assert_eq!(next, inside);
+    }
+
+    #[test]
+    fn test_select_grows_outwards() {
+        let blocks = rust_objects(TextObject::Block);
+        let functions = rust_objects(TextObject::Function);
+        let call = SOURCE.find("add(1").unwrap();
+
+        let body = select(&blocks, call..call, false).unwrap();
+        assert_eq!(text(body.clone()), "add(1, 2);");
+        let main = select(&functions, body, true).unwrap();
+        assert!(text(main).starts_with("fn main() {"));
+    }
+
+    #[test]
+    fn test_user_query_replaces_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("json")).unwrap();
+        std::fs::write(
+            dir.path().join("json/textobjects.scm"),
+            "(pair) @parameter.outer",
+        )
+        .unwrap();
+        std::fs::create_dir_all(dir.path().join("rust")).unwrap();
+        std::fs::write(dir.path().join("rust/textobjects.scm"), "(no_such_node) @x").unwrap();
+
+        let mut queries = queries(Some(dir.path().to_path_buf()));
+        assert!(queries.get(&Language::Json).is_ok());
+        let error = queries.get(&Language::Rust).unwrap_err();
+        assert!(error.contains("Invalid query"), "{}", error);
+    }
+}
@@ -0,0 +1,402 @@
+//! Project-local configuration
+//!
+//! A project can commit a `.fresh/config.json` (found in the working directory
+//! or one of its parents) that is deep-merged over the user configuration:
+//! objects are merged key by key, any other value replaces the user's.
+//!
+//! Settings that make the editor run programs (see [`COMMAND_SETTINGS`]) are
+//! only honored once the user trusts the project config. Trust is recorded per
+//! config file together with a hash of its content, so any change to the file
+//! asks again.
+
+use crate::config::{Config, ConfigError};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Directory holding the project configuration
+pub const PROJECT_CONFIG_DIR: &str = ".fresh";
+
+/// Name of the project configuration file
+pub const PROJECT_CONFIG_FILE: &str = "config.json";
+
+/// Settings that run external programs, as paths into the config (`*` matches any key)
+pub const COMMAND_SETTINGS: &[&[&str]] = &[
+    &["lsp", "*"],
+    &["editor", "privileged_save_command"],
+    &["languages", "*", "formatters"],
+];
+
+/// A project's `.fresh/config.json`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    /// Path of the config file
+    pub path: PathBuf,
+    /// The raw settings of the file
+    value: Value,
+    /// SHA-256 of the file content (for trust)
+    content_hash: String,
+}
+
+impl ProjectConfig {
+    /// Find the project config for a working directory, looking in the
+    /// directory and its parents. Unreadable files are logged and ignored.
+    pub fn discover(working_dir: &Path) -> Option<Self> {
+        let path = working_dir
+            .ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_DIR).join(PROJECT_CONFIG_FILE))
+            .find(|path| path.is_file())?;
+        match Self::load(&path) {
+            Ok(project) => Some(project),
+            Err(e) => {
+                tracing::warn!("Ignoring project config {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Load a project config file
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        use sha2::{Digest, Sha256};
+
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::IoError(e.to_string()))?;
+        let value: Value =
+            serde_json::from_str(&contents).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        if !value.is_object() {
+            return Err(ConfigError::ParseError(
+                "project config must be a JSON object".to_string(),
+            ));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            value,
+            content_hash: format!("{:x}", Sha256::digest(contents.as_bytes())),
+        })
+    }
+
+    /// The command settings this config sets, as dotted names (e.g. `lsp.rust`)
+    pub fn command_settings(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        for setting in COMMAND_SETTINGS {
+            collect_paths(&self.value, setting, &mut Vec::new(), &mut names);
+        }
+        names
+    }
+
+    /// The settings to layer, without the command settings unless `trusted`
+    fn layer(&self, trusted: bool) -> Value {
+        let mut value = self.value.clone();
+        if !trusted {
+            for setting in COMMAND_SETTINGS {
+                remove_at(&mut value, setting);
+            }
+        }
+        value
+    }
+
+    /// Layer this config over `base`
+    pub fn apply(&self, base: &Config, trusted: bool) -> Result<Config, ConfigError> {
+        let mut merged =
+            serde_json::to_value(base).map_err(|e| ConfigError::SerializeError(e.to_string()))?;
+        deep_merge(&mut merged, &self.layer(trusted));
+        serde_json::from_value(merged).map_err(|e| ConfigError::ParseError(e.to_string()))
+    }
+}
+
+/// Deep-merge `overlay` into `base`: objects are merged key by key, other values replace
+pub fn deep_merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+fn value_at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(*key))
+}
+
+/// Collect the dotted names of the values matching `pattern` (`*` matches any key)
+fn collect_paths(value: &Value, pattern: &[&str], prefix: &mut Vec<String>, out: &mut Vec<String>) {
+    let Some((first, rest)) = pattern.split_first() else {
+        out.push(prefix.join("."));
+        return;
+    };
+    let Some(map) = value.as_object() else {
+        return;
+    };
+    for (key, child) in map {
+        if *first == "*" || first == key {
+            prefix.push(key.clone());
+            collect_paths(child, rest, prefix, out);
+            prefix.pop();
+        }
+    }
+}
+
+/// Remove the values matching `pattern` (`*` matches any key)
+fn remove_at(value: &mut Value, pattern: &[&str]) {
+    let (Some((first, rest)), Some(map)) = (pattern.split_first(), value.as_object_mut()) else {
+        return;
+    };
+    if rest.is_empty() {
+        map.retain(|key, _| *first != "*" && first != key);
+        return;
+    }
+    for (key, child) in map.iter_mut() {
+        if *first == "*" || first == key {
+            remove_at(child, rest);
+        }
+    }
+}
+
+/// Trust decisions for project configs, stored in the data directory
+#[derive(Debug)]
+pub struct ProjectTrust {
+    /// File holding the trusted config paths and content hashes
+    path: PathBuf,
+    trusted: HashMap<PathBuf, String>,
+}
+
+impl ProjectTrust {
+    /// Load the trust decisions (none if the file is missing or unreadable)
+    pub fn load(path: PathBuf) -> Self {
+        let trusted = std::fs::read(&path)
+            .ok()
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+        Self { path, trusted }
+    }
+
+    /// Whether the project config is trusted in its current content
+    pub fn is_trusted(&self, project: &ProjectConfig) -> bool {
+        self.trusted.get(&project.path) == Some(&project.content_hash)
+    }
+
+    /// Trust the project config in its current content
+    pub fn trust(&mut self, project: &ProjectConfig) -> io::Result<()> {
+        self.trusted
+            .insert(project.path.clone(), project.content_hash.clone());
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&self.trusted)?)
+    }
+}
+
+/// The configuration layer a setting's value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigLayer {
+    Default,
+    User,
+    Project,
+}
+
+impl ConfigLayer {
+    fn label(&self) -> &'static str {
+        match self {
+            ConfigLayer::Default => "default",
+            ConfigLayer::User => "user",
+            ConfigLayer::Project => "project",
+        }
+    }
+}
+
+/// Every setting of the effective config (dotted name -> value) with the layer it comes from.
+/// `project` is the project config and whether it is trusted.
+pub fn setting_layers(
+    effective: &Config,
+    project: Option<(&ProjectConfig, bool)>,
+) -> BTreeMap<String, (Value, ConfigLayer)> {
+    let effective = serde_json::to_value(effective).unwrap_or(Value::Null);
+    let defaults = serde_json::to_value(Config::default()).unwrap_or(Value::Null);
+    let project_layer = project.map(|(project, trusted)| project.layer(trusted));
+
+    let mut leaves = Vec::new();
+    collect_leaves(&effective, &mut Vec::new(), &mut leaves);
+    leaves
+        .into_iter()
+        .map(|(path, value)| {
+            let keys: Vec<&str> = path.iter().map(String::as_str).collect();
+            let layer = if project_layer
+                .as_ref()
+                .is_some_and(|layer| value_at(layer, &keys).is_some())
+            {
+                ConfigLayer::Project
+            } else if value_at(&defaults, &keys) != Some(&value) {
+                ConfigLayer::User
+            } else {
+                ConfigLayer::Default
+            };
+            (path.join("."), (value, layer))
+        })
+        .collect()
+}
+
+/// Collect the non-object values of a JSON tree with their key paths
+fn collect_leaves(value: &Value, path: &mut Vec<String>, out: &mut Vec<(Vec<String>, Value)>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                path.push(key.clone());
+                collect_leaves(child, path, out);
+                path.pop();
+            }
+        }
+        _ => out.push((path.clone(), value.clone())),
+    }
+}
+
+/// Render the settings of the effective config with their layers, one per line
+pub fn describe_layers(
+    effective: &Config,
+    user_path: &Path,
+    project: Option<(&ProjectConfig, bool)>,
+) -> String {
+    let mut out = String::from("Configuration layers (later layers override earlier ones)\n");
+    out.push_str("  default  built-in defaults\n");
+    out.push_str(&format!("  user     {}\n", user_path.display()));
+    match project {
+        Some((project, trusted)) => {
+            out.push_str(&format!("  project  {}", project.path.display()));
+            if !trusted && !project.command_settings().is_empty() {
+                out.push_str(&format!(
+                    " (untrusted, ignoring: {})",
+                    project.command_settings().join(", ")
+                ));
+            }
+            out.push('\n');
+        }
+        None => out.push_str("  project  (none)\n"),
+    }
+    out.push('\n');
+
+    for (name, (value, layer)) in setting_layers(effective, project) {
+        out.push_str(&format!("[{:<7}] {} = {}\n", layer.label(), name, value));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_project(dir: &Path, value: &Value) -> PathBuf {
+        let config_dir = dir.join(PROJECT_CONFIG_DIR);
+        std::fs::create_dir_all(&config_dir).unwrap();
+        let path = config_dir.join(PROJECT_CONFIG_FILE);
+        std::fs::write(&path, serde_json::to_string(value).unwrap()).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_deep_merge() {
+        let mut base = json!({"editor": {"tab_size": 4, "line_wrap": true}, "keybindings": [1, 2]});
+        deep_merge(
+            &mut base,
+            &json!({"editor": {"tab_size": 2}, "keybindings": [3]}),
+        );
+        assert_eq!(
+            base,
+            json!({"editor": {"tab_size": 2, "line_wrap": true}, "keybindings": [3]})
+        );
+    }
+
+    #[test]
+    fn test_discover_and_apply_without_trust() {
+        let temp = tempfile::tempdir().unwrap();
+        write_project(
+            temp.path(),
+            &json!({
+                "editor": {"tab_size": 2, "privileged_save_command": ["doas", "tee", "{path}"]},
+                "lsp": {"rust": {"command": "/tmp/evil"}},
+                "languages": {"rust": {"formatters": [{"command": "/tmp/evil"}]}}
+            }),
+        );
+        let nested = temp.path().join("src").join("app");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        let project = ProjectConfig::discover(&nested).expect("project config found");
+        assert_eq!(
+            project.command_settings(),
+            vec![
+                "lsp.rust",
+                "editor.privileged_save_command",
+                "languages.rust.formatters"
+            ]
+        );
+
+        let base = Config::default();
+        let untrusted = project.apply(&base, false).unwrap();
+        assert_eq!(untrusted.editor.tab_size, 2);
+        assert_eq!(
+            untrusted.lsp["rust"][0].command,
+            base.lsp["rust"][0].command
+        );
+        assert_eq!(
+            untrusted.editor.privileged_save_command,
+            base.editor.privileged_save_command
+        );
+        assert!(untrusted.languages["rust"].formatters.is_empty());
+
+        let trusted = project.apply(&base, true).unwrap();
+        assert_eq!(trusted.lsp["rust"][0].command, "/tmp/evil");
+        assert_eq!(trusted.languages["rust"].formatters[0].command, "/tmp/evil");
+        // Fields not set by the project keep the user's values
+        assert_eq!(trusted.lsp["rust"][0].args, base.lsp["rust"][0].args);
+
+        let layers = setting_layers(&untrusted, Some((&project, false)));
+        assert_eq!(layers["editor.tab_size"].1, ConfigLayer::Project);
+        assert_eq!(layers["editor.line_wrap"].1, ConfigLayer::Default);
+        assert_eq!(layers["lsp.rust.command"].1, ConfigLayer::Default);
+    }
+
+    #[test]
+    fn test_project_lsp_settings_merge() {
+        let temp = tempfile::tempdir().unwrap();
+        write_project(
+            temp.path(),
+            &json!({"lsp": {"rust": {"settings": {"rust-analyzer": {"check": {"command": "clippy"}}}}}}),
+        );
+        let project = ProjectConfig::discover(temp.path()).unwrap();
+
+        let base = Config::default();
+        let trusted = project.apply(&base, true).unwrap();
+        let settings = trusted.lsp["rust"][0].settings.as_ref().unwrap();
+        assert_eq!(settings["rust-analyzer"]["check"]["command"], "clippy");
+        // The default settings and the server command are kept
+        assert_eq!(
+            settings["rust-analyzer"]["inlayHints"]["typeHints"]["enable"],
+            true
+        );
+        assert_eq!(trusted.lsp["rust"][0].command, base.lsp["rust"][0].command);
+    }
+
+    #[test]
+    fn test_trust_is_tied_to_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = write_project(temp.path(), &json!({"lsp": {}}));
+        let trust_path = temp.path().join("data").join("trusted_projects.json");
+
+        let project = ProjectConfig::load(&path).unwrap();
+        let mut trust = ProjectTrust::load(trust_path.clone());
+        assert!(!trust.is_trusted(&project));
+        trust.trust(&project).unwrap();
+        assert!(ProjectTrust::load(trust_path.clone()).is_trusted(&project));
+
+        write_project(temp.path(), &json!({"lsp": {"rust": {"command": "x"}}}));
+        let changed = ProjectConfig::load(&path).unwrap();
+        assert!(!ProjectTrust::load(trust_path).is_trusted(&changed));
+    }
+}
@@ -10,6 +10,7 @@
 //! - Computation should be sync (editing, rendering)
 //! - Main loop remains responsive and simple
 
+use crate::services::git_status::GitStatus;
 use crate::services::terminal::TerminalId;
 use crate::view::file_tree::{FileTreeView, NodeId};
 use lsp_types::{
@@ -23,6 +24,9 @@ use std::sync::mpsc;
 pub enum AsyncMessage {
     /// LSP diagnostics received for a file
     LspDiagnostics {
+        language: String,
+        /// Name of the server that published them
+        server: String,
         uri: String,
         diagnostics: Vec<Diagnostic>,
     },
@@ -33,6 +37,7 @@ pub enum AsyncMessage {
     /// LSP server crashed or failed
     LspError {
         language: String,
+        server: String,
         error: String,
         /// Path to the stderr log file for this LSP session
         stderr_log_path: Option<std::path::PathBuf>,
@@ -41,6 +46,8 @@ pub enum AsyncMessage {
     /// LSP completion response
     LspCompletion {
         request_id: u64,
+        /// Name of the server that answered
+        server: String,
         items: Vec<CompletionItem>,
     },
 
@@ -86,9 +93,51 @@ pub enum AsyncMessage {
         actions: Vec<CodeActionOrCommand>,
     },
 
+    /// LSP formatting response (textDocument/formatting or rangeFormatting)
+    LspFormatting {
+        request_id: u64,
+        result: Result<Vec<lsp_types::TextEdit>, String>,
+    },
+
+    /// LSP folding range response (textDocument/foldingRange)
+    LspFoldingRanges {
+        request_id: u64,
+        uri: String,
+        result: Result<Vec<lsp_types::FoldingRange>, String>,
+    },
+
+    /// LSP selection range response (textDocument/selectionRange)
+    LspSelectionRanges {
+        request_id: u64,
+        uri: String,
+        result: Result<Vec<lsp_types::SelectionRange>, String>,
+    },
+
+    /// LSP document symbol response (textDocument/documentSymbol)
+    LspDocumentSymbols {
+        request_id: u64,
+        uri: String,
+        result: Result<lsp_types::DocumentSymbolResponse, String>,
+    },
+
+    /// LSP workspace symbol response (workspace/symbol)
+    LspWorkspaceSymbols {
+        request_id: u64,
+        result: Result<lsp_types::WorkspaceSymbolResponse, String>,
+    },
+
+    /// LSP call or type hierarchy items: those at a position, or the
+    /// callers, callees, supertypes or subtypes of an item
+    LspHierarchyItems {
+        request_id: u64,
+        result: Result<Vec<crate::services::lsp::hierarchy::HierarchyItem>, String>,
+    },
+
     /// LSP pulled diagnostics response (textDocument/diagnostic)
     LspPulledDiagnostics {
         request_id: u64,
+        /// Name of the server that answered
+        server: String,
         uri: String,
         /// New result_id for incremental updates (None if server doesn't support)
         result_id: Option<String>,
@@ -113,8 +162,17 @@ pub enum AsyncMessage {
     /// File changed externally (future: file watching)
     FileChanged { path: String },
 
-    /// Git status updated (future: git integration)
-    GitStatusChanged { status: String },
+    /// Git status of the working directory reloaded (`None` outside a git
+    /// work tree or if git failed)
+    GitStatusChanged { status: Option<GitStatus> },
+
+    /// External formatters finished with a buffer's text
+    ExternalFormatFinished {
+        buffer_id: crate::model::event::BufferId,
+        /// Which run finished; the output of a run that was superseded is dropped
+        run: u64,
+        result: Result<String, crate::services::formatter::FormatterError>,
+    },
 
     /// File explorer initialized with tree view
     FileExplorerInitialized(FileTreeView),
@@ -165,6 +223,9 @@ pub enum AsyncMessage {
     /// LSP server status update
     LspStatusUpdate {
         language: String,
+        server: String,
+        /// Workspace root the server instance was started in
+        root: Option<lsp_types::Uri>,
         status: LspServerStatus,
     },
     /// Generic notification from an LSP server
@@ -417,6 +478,8 @@ mod tests {
 
         sender
             .send(AsyncMessage::LspDiagnostics {
+                language: "rust".to_string(),
+                server: "rust-analyzer".to_string(),
                 uri: "file:///test.rs".to_string(),
                 diagnostics: diagnostics.clone(),
             })
@@ -429,6 +492,7 @@ mod tests {
             AsyncMessage::LspDiagnostics {
                 uri,
                 diagnostics: diags,
+                ..
             } => {
                 assert_eq!(uri, "file:///test.rs");
                 assert_eq!(diags.len(), 1);
@@ -446,6 +510,7 @@ mod tests {
         sender
             .send(AsyncMessage::LspError {
                 language: "rust".to_string(),
+                server: "rust-analyzer".to_string(),
                 error: "Failed to initialize".to_string(),
                 stderr_log_path: None,
             })
@@ -459,6 +524,7 @@ mod tests {
                 language,
                 error,
                 stderr_log_path,
+                ..
             } => {
                 assert_eq!(language, "rust");
                 assert_eq!(error, "Failed to initialize");
@@ -0,0 +1,411 @@
+//! EditorConfig support
+//!
+//! Finds the `.editorconfig` files that apply to a file (walking up the
+//! directory tree until one declares `root = true`), matches their section
+//! globs against the file path and merges the properties, nearer files and
+//! later sections taking precedence. See <https://editorconfig.org>.
+
+use crate::model::buffer::LineEnding;
+use crate::model::encoding::Encoding;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Name of the EditorConfig file looked up in each directory
+pub const EDITORCONFIG_FILE: &str = ".editorconfig";
+
+/// Whether indentation uses tab characters or spaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+/// The EditorConfig properties that apply to one file (unset properties are `None`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditorConfigProperties {
+    pub indent_style: Option<IndentStyle>,
+    /// Columns per indentation level (`indent_size = tab` is resolved to the tab width)
+    pub indent_size: Option<usize>,
+    pub tab_width: Option<usize>,
+    pub end_of_line: Option<LineEnding>,
+    pub charset: Option<Encoding>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub insert_final_newline: Option<bool>,
+    pub max_line_length: Option<usize>,
+}
+
+impl EditorConfigProperties {
+    /// Whether no property is set
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Build the typed properties from raw (lowercased) key/value pairs
+    fn from_raw(raw: &HashMap<String, String>) -> Self {
+        let number = |key: &str| raw.get(key).and_then(|v| v.parse::<usize>().ok());
+        let boolean = |key: &str| match raw.get(key).map(String::as_str) {
+            Some("true") => Some(true),
+            Some("false") => Some(false),
+            _ => None,
+        };
+
+        let indent_style = match raw.get("indent_style").map(String::as_str) {
+            Some("tab") => Some(IndentStyle::Tab),
+            Some("space") => Some(IndentStyle::Space),
+            _ => None,
+        };
+        let indent_size_is_tab = raw.get("indent_size").map(String::as_str) == Some("tab");
+        let mut indent_size = number("indent_size").filter(|&n| n > 0);
+        let mut tab_width = number("tab_width").filter(|&n| n > 0);
+
+        // Defaults between the indentation properties, as specified by EditorConfig
+        if indent_size.is_none() && (indent_size_is_tab || indent_style == Some(IndentStyle::Tab)) {
+            indent_size = tab_width;
+        }
+        if tab_width.is_none() {
+            tab_width = indent_size;
+        }
+
+        let end_of_line = match raw.get("end_of_line").map(String::as_str) {
+            Some("lf") => Some(LineEnding::LF),
+            Some("crlf") => Some(LineEnding::CRLF),
+            Some("cr") => Some(LineEnding::CR),
+            _ => None,
+        };
+
+        Self {
+            indent_style,
+            indent_size,
+            tab_width,
+            end_of_line,
+            charset: raw.get("charset").and_then(|v| Encoding::from_name(v)),
+            trim_trailing_whitespace: boolean("trim_trailing_whitespace"),
+            insert_final_newline: boolean("insert_final_newline"),
+            max_line_length: number("max_line_length").filter(|&n| n > 0),
+        }
+    }
+}
+
+/// A parsed `.editorconfig` file
+#[derive(Debug, Default)]
+struct EditorConfigFile {
+    root: bool,
+    /// Sections in file order: (glob, properties)
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl EditorConfigFile {
+    fn parse(content: &str) -> Self {
+        let mut file = Self::default();
+        let mut in_preamble = true;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                in_preamble = false;
+                file.sections
+                    .push((line[1..line.len() - 1].to_string(), Vec::new()));
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_lowercase();
+            if in_preamble {
+                if key == "root" {
+                    file.root = value == "true";
+                }
+            } else if let Some((_, properties)) = file.sections.last_mut() {
+                properties.push((key, value));
+            }
+        }
+
+        file
+    }
+}
+
+/// Resolve the EditorConfig properties for a file
+pub fn properties_for(path: &Path) -> EditorConfigProperties {
+    // Collect config files from the nearest directory up to the root one
+    let mut files = Vec::new();
+    let mut dir = path.parent();
+    while let Some(current) = dir {
+        let config_path = current.join(EDITORCONFIG_FILE);
+        if let Ok(content) = std::fs::read_to_string(&config_path) {
+            let file = EditorConfigFile::parse(&content);
+            let root = file.root;
+            files.push((current.to_path_buf(), file));
+            if root {
+                break;
+            }
+        }
+        dir = current.parent();
+    }
+
+    let file_path = slash_path(path);
+    let mut raw = HashMap::new();
+    for (dir, file) in files.iter().rev() {
+        let dir = slash_path(dir);
+        for (glob, properties) in &file.sections {
+            if !glob_matches(&dir, glob, &file_path) {
+                continue;
+            }
+            for (key, value) in properties {
+                if value == "unset" {
+                    raw.remove(key);
+                } else {
+                    raw.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    EditorConfigProperties::from_raw(&raw)
+}
+
+/// A path as a string with `/` separators
+fn slash_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if std::path::MAIN_SEPARATOR == '/' {
+        path.into_owned()
+    } else {
+        path.replace(std::path::MAIN_SEPARATOR, "/")
+    }
+}
+
+/// Whether a section glob from the `.editorconfig` in `dir` matches `file_path`
+fn glob_matches(dir: &str, glob: &str, file_path: &str) -> bool {
+    // Globs without a slash match the file name in any subdirectory
+    let glob = if let Some(anchored) = glob.strip_prefix('/') {
+        anchored.to_string()
+    } else if glob.contains('/') {
+        glob.to_string()
+    } else {
+        format!("**/{}", glob)
+    };
+    let dir = dir.trim_end_matches('/');
+
+    let mut ranges = Vec::new();
+    let pattern = format!(
+        "^{}/{}$",
+        regex::escape(dir),
+        glob_to_regex(&glob, &mut ranges)
+    );
+    let Ok(regex) = Regex::new(&pattern) else {
+        return false;
+    };
+    let Some(captures) = regex.captures(file_path) else {
+        return false;
+    };
+
+    // Numeric ranges ({n1..n2}) are captured and checked here
+    ranges.iter().enumerate().all(|(i, (min, max))| {
+        captures
+            .get(i + 1)
+            .and_then(|m| m.as_str().parse::<i64>().ok())
+            .is_some_and(|n| n >= *min && n <= *max)
+    })
+}
+
+/// Translate an EditorConfig glob into a regex. Numeric ranges become capture
+/// groups, with their bounds appended to `ranges` in group order.
+fn glob_to_regex(glob: &str, ranges: &mut Vec<(i64, i64)>) -> String {
+    let chars: Vec<char> = glob.chars().collect();
+    let mut regex = String::new();
+    let mut brace_depth = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\\' if i + 1 < chars.len() => {
+                regex.push_str(&regex::escape(&chars[i + 1].to_string()));
+                i += 1;
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                if chars.get(i + 2) == Some(&'/') {
+                    // `**/` matches zero or more directories
+                    regex.push_str("(?:.*/)?");
+                    i += 2;
+                } else {
+                    regex.push_str(".*");
+                    i += 1;
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => match chars[i + 1..].iter().position(|&ch| ch == ']') {
+                Some(len) => {
+                    let class: String = chars[i + 1..i + 1 + len].iter().collect();
+                    let (negated, class) = match class.strip_prefix('!') {
+                        Some(rest) => (true, rest.to_string()),
+                        None => (false, class),
+                    };
+                    regex.push('[');
+                    if negated {
+                        regex.push('^');
+                    }
+                    regex.push_str(&class.replace('\\', "\\\\").replace('[', "\\["));
+                    regex.push(']');
+                    i += len + 1;
+                }
+                None => regex.push_str("\\["),
+            },
+            '{' => {
+                let close = matching_brace(&chars, i);
+                let inner: Option<String> = close.map(|end| chars[i + 1..end].iter().collect());
+                if let Some(range) = inner.as_deref().and_then(parse_numeric_range) {
+                    ranges.push(range);
+                    regex.push_str("([+-]?[0-9]+)");
+                    i = close.unwrap_or(i);
+                } else if close.is_some() && inner.as_deref().is_some_and(|s| s.contains(',')) {
+                    regex.push_str("(?:");
+                    brace_depth += 1;
+                } else {
+                    // Braces without alternatives are literal
+                    regex.push_str("\\{");
+                }
+            }
+            ',' if brace_depth > 0 => regex.push('|'),
+            '}' if brace_depth > 0 => {
+                regex.push(')');
+                brace_depth -= 1;
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    regex
+}
+
+/// Index of the brace closing the one at `open`, honouring nesting and escapes
+fn matching_brace(chars: &[char], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = open;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parse the inside of a `{n1..n2}` numeric range
+fn parse_numeric_range(inner: &str) -> Option<(i64, i64)> {
+    let (min, max) = inner.split_once("..")?;
+    let (min, max) = (min.parse::<i64>().ok()?, max.parse::<i64>().ok()?);
+    Some((min.min(max), min.max(max)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_glob_matching() {
+        let dir = "/project";
+        assert!(glob_matches(dir, "*", "/project/src/main.rs"));
+        assert!(glob_matches(dir, "*.rs", "/project/src/main.rs"));
+        assert!(!glob_matches(dir, "*.rs", "/project/src/main.py"));
+        assert!(glob_matches(dir, "*.{js,ts}", "/project/app.ts"));
+        assert!(!glob_matches(dir, "*.{js,ts}", "/project/app.tsx"));
+        assert!(glob_matches(dir, "Makefile", "/project/sub/Makefile"));
+        assert!(glob_matches(dir, "/Makefile", "/project/Makefile"));
+        assert!(!glob_matches(dir, "/Makefile", "/project/sub/Makefile"));
+        assert!(glob_matches(dir, "src/*.rs", "/project/src/lib.rs"));
+        assert!(!glob_matches(dir, "src/*.rs", "/project/src/app/lib.rs"));
+        assert!(glob_matches(dir, "src/**.rs", "/project/src/app/lib.rs"));
+        assert!(glob_matches(dir, "lib/**/*.c", "/project/lib/x.c"));
+        assert!(glob_matches(dir, "file[0-9].txt", "/project/file3.txt"));
+        assert!(!glob_matches(dir, "file[!0-9].txt", "/project/file3.txt"));
+        assert!(glob_matches(dir, "test{1..10}.txt", "/project/test7.txt"));
+        assert!(!glob_matches(dir, "test{1..10}.txt", "/project/test11.txt"));
+        assert!(glob_matches(
+            dir,
+            "{package.json,.travis.yml}",
+            "/project/.travis.yml"
+        ));
+    }
+
+    #[test]
+    fn test_indentation_defaults() {
+        let raw = |pairs: &[(&str, &str)]| {
+            EditorConfigProperties::from_raw(
+                &pairs
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            )
+        };
+
+        let props = raw(&[("indent_style", "space"), ("indent_size", "2")]);
+        assert_eq!(props.indent_size, Some(2));
+        assert_eq!(props.tab_width, Some(2));
+
+        let props = raw(&[("indent_style", "tab"), ("tab_width", "8")]);
+        assert_eq!(props.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(props.indent_size, Some(8));
+
+        let props = raw(&[("indent_size", "tab"), ("tab_width", "4")]);
+        assert_eq!(props.indent_size, Some(4));
+    }
+
+    #[test]
+    fn test_properties_merge_up_to_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("repo");
+        let sub = root.join("web");
+        fs::create_dir_all(&sub).unwrap();
+
+        // A config above the root must be ignored
+        fs::write(
+            temp.path().join(EDITORCONFIG_FILE),
+            "[*]\nmax_line_length = 10\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(EDITORCONFIG_FILE),
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\nend_of_line = lf\n\
+             insert_final_newline = true\n\n[*.md]\ntrim_trailing_whitespace = false\n",
+        )
+        .unwrap();
+        fs::write(
+            sub.join(EDITORCONFIG_FILE),
+            "# web overrides\n[*.{js,css}]\nindent_size = 2\nend_of_line = unset\ncharset = latin1\n",
+        )
+        .unwrap();
+
+        let props = properties_for(&sub.join("app.js"));
+        assert_eq!(props.indent_style, Some(IndentStyle::Space));
+        assert_eq!(props.indent_size, Some(2));
+        assert_eq!(props.end_of_line, None);
+        assert_eq!(props.charset, Some(Encoding::Latin1));
+        assert_eq!(props.insert_final_newline, Some(true));
+        assert_eq!(props.max_line_length, None);
+
+        let props = properties_for(&root.join("README.md"));
+        assert_eq!(props.indent_size, Some(4));
+        assert_eq!(props.end_of_line, Some(LineEnding::LF));
+        assert_eq!(props.trim_trailing_whitespace, Some(false));
+
+        assert!(properties_for(&temp.path().join("other").join("x.txt"))
+            .max_line_length
+            .is_some());
+    }
+}
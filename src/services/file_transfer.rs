@@ -0,0 +1,254 @@
+//! Copying and moving files and directories
+//!
+//! Used by the file explorer's copy, cut, paste and drag-and-drop. A transfer
+//! puts each source into a target directory under its own name; callers check
+//! for an existing destination first and choose to overwrite it, skip, or keep
+//! both with `keep_both_destination`.
+
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Whether a transfer copies its sources or moves them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferMode {
+    Copy,
+    Move,
+}
+
+/// Where `source` goes when transferred into `target_dir`. Fails for a
+/// directory transferred into itself or one of its subdirectories.
+pub fn destination(source: &Path, target_dir: &Path) -> io::Result<PathBuf> {
+    let name = source.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot transfer {}", source.display()),
+        )
+    })?;
+    if source.is_dir() && target_dir.starts_with(source) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Cannot put {} inside itself", source.display()),
+        ));
+    }
+    Ok(target_dir.join(name))
+}
+
+/// A free name next to `destination` for keeping both files:
+/// `notes copy.txt`, then `notes copy 2.txt`, ...
+pub fn keep_both_destination(destination: &Path) -> PathBuf {
+    let dir = destination.parent().unwrap_or(Path::new(""));
+    let is_dir = destination.is_dir();
+    let (stem, extension) = match (destination.file_stem(), destination.extension()) {
+        (Some(stem), Some(ext)) if !is_dir => (stem.to_os_string(), Some(ext.to_os_string())),
+        _ => (
+            destination.file_name().unwrap_or_default().to_os_string(),
+            None,
+        ),
+    };
+
+    (1..)
+        .map(|n| {
+            let mut name: OsString = stem.clone();
+            name.push(if n == 1 {
+                " copy".to_string()
+            } else {
+                format!(" copy {}", n)
+            });
+            if let Some(ext) = &extension {
+                name.push(".");
+                name.push(ext);
+            }
+            dir.join(name)
+        })
+        .find(|path| !path.exists())
+        .expect("some copy name is free")
+}
+
+/// Copy or move `source` to `destination`, replacing what is there if
+/// `overwrite`. A replaced destination is only deleted once the new one is in
+/// place. Moves across file systems fall back to copy and delete.
+pub fn transfer(
+    source: &Path,
+    destination: &Path,
+    mode: TransferMode,
+    overwrite: bool,
+) -> io::Result<()> {
+    if source == destination {
+        return Ok(());
+    }
+    let replacing = destination.exists() || destination.is_symlink();
+    if replacing && (!overwrite || source.starts_with(destination)) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", destination.display()),
+        ));
+    }
+
+    if mode == TransferMode::Move {
+        let moved = if replacing {
+            replace(source, destination)
+        } else {
+            std::fs::rename(source, destination)
+        };
+        match moved {
+            Err(e) if e.kind() == io::ErrorKind::CrossesDevices => {}
+            moved => return moved,
+        }
+    }
+
+    if replacing {
+        // Copy next to the destination first, so a failed copy leaves it alone
+        let staged = staging_path(destination);
+        if let Err(e) = copy_recursive(source, &staged).and_then(|_| replace(&staged, destination))
+        {
+            let _ = remove(&staged);
+            return Err(e);
+        }
+    } else {
+        copy_recursive(source, destination)?;
+    }
+    match mode {
+        TransferMode::Copy => Ok(()),
+        TransferMode::Move => remove(source),
+    }
+}
+
+/// Put `source` in place of the existing `destination` by renaming. A file
+/// replaces a file in one step; otherwise the destination is renamed aside
+/// first, and restored if `source` can't take its place.
+fn replace(source: &Path, destination: &Path) -> io::Result<()> {
+    let is_dir = |path: &Path| path.is_dir() && !path.is_symlink();
+    if !is_dir(source) && !is_dir(destination) {
+        return std::fs::rename(source, destination);
+    }
+    let aside = staging_path(destination);
+    std::fs::rename(destination, &aside)?;
+    if let Err(e) = std::fs::rename(source, destination) {
+        let _ = std::fs::rename(&aside, destination);
+        return Err(e);
+    }
+    remove(&aside)
+}
+
+/// An unused hidden name next to `path`, for staging a transfer
+fn staging_path(path: &Path) -> PathBuf {
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().unwrap_or_default();
+    (0..)
+        .map(|n| {
+            let mut staged = OsString::from(".");
+            staged.push(name);
+            staged.push(format!(".fresh-transfer-{}-{}", std::process::id(), n));
+            dir.join(staged)
+        })
+        .find(|path| !path.exists() && !path.is_symlink())
+        .expect("some staging name is free")
+}
+
+/// Copy a file or directory tree. Symlinks are copied as links on Unix.
+fn copy_recursive(source: &Path, destination: &Path) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(source)?;
+    #[cfg(unix)]
+    if metadata.is_symlink() {
+        return std::os::unix::fs::symlink(std::fs::read_link(source)?, destination);
+    }
+    if metadata.is_dir() {
+        std::fs::create_dir(destination)?;
+        for entry in std::fs::read_dir(source)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &destination.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        std::fs::copy(source, destination).map(|_| ())
+    }
+}
+
+/// Delete a file or directory tree for good
+pub fn remove(path: &Path) -> io::Result<()> {
+    if path.is_dir() && !path.is_symlink() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_and_move_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("nested")).unwrap();
+        std::fs::write(src.join("nested/a.txt"), "a").unwrap();
+        let target = dir.path().join("target");
+        std::fs::create_dir(&target).unwrap();
+
+        let copy = destination(&src, &target).unwrap();
+        transfer(&src, &copy, TransferMode::Copy, false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(copy.join("nested/a.txt")).unwrap(),
+            "a"
+        );
+        assert!(src.exists());
+
+        // The copy is in the way of the move until overwriting is allowed
+        assert!(transfer(&src, &copy, TransferMode::Move, false).is_err());
+        std::fs::write(src.join("b.txt"), "b").unwrap();
+        transfer(&src, &copy, TransferMode::Move, true).unwrap();
+        assert!(!src.exists());
+        assert!(copy.join("b.txt").exists());
+
+        // A directory can't go inside itself
+        assert!(destination(&target, &copy).is_err());
+    }
+
+    #[test]
+    fn test_overwrite_replaces_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target");
+        std::fs::create_dir(&target).unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "new").unwrap();
+        std::fs::write(target.join("a.txt"), "old").unwrap();
+
+        transfer(&file, &target.join("a.txt"), TransferMode::Copy, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(target.join("a.txt")).unwrap(),
+            "new"
+        );
+
+        // A file replaces a directory of the same name, and nothing is left staged
+        let b = dir.path().join("b");
+        std::fs::write(&b, "file").unwrap();
+        std::fs::create_dir_all(target.join("b/old")).unwrap();
+        transfer(&b, &target.join("b"), TransferMode::Move, true).unwrap();
+        assert_eq!(std::fs::read_to_string(target.join("b")).unwrap(), "file");
+        assert!(!b.exists());
+        assert_eq!(std::fs::read_dir(&target).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_keep_both_destination() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "").unwrap();
+        assert_eq!(
+            keep_both_destination(&notes),
+            dir.path().join("notes copy.txt")
+        );
+
+        std::fs::write(dir.path().join("notes copy.txt"), "").unwrap();
+        assert_eq!(
+            keep_both_destination(&notes),
+            dir.path().join("notes copy 2.txt")
+        );
+
+        let folder = dir.path().join("v1.2");
+        std::fs::create_dir(&folder).unwrap();
+        assert_eq!(keep_both_destination(&folder), dir.path().join("v1.2 copy"));
+    }
+}
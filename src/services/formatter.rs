@@ -0,0 +1,228 @@
+//! External formatter pipelines
+//!
+//! A language can configure formatter commands (e.g. `black -`, `shfmt`,
+//! `jq .`) that get the text on stdin and print the formatted text on stdout.
+//! The commands run in order, each one formatting the previous one's output.
+//! A formatter that exits unsuccessfully, prints invalid UTF-8 or runs past
+//! its timeout fails the whole pipeline, leaving the buffer untouched.
+//!
+//! The pipeline blocks until it finishes, so the editor runs it on a thread of
+//! its own. On Unix each formatter leads its own process group, and a timeout
+//! kills the whole group, including anything it started in the background.
+
+use crate::config::ExternalFormatterConfig;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Placeholder for the formatted file's path in formatter arguments
+pub const PATH_PLACEHOLDER: &str = "{path}";
+
+/// How often a running formatter is checked for completion
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A formatter that failed
+#[derive(Debug)]
+pub struct FormatterError {
+    /// The formatter's command
+    pub command: String,
+    /// Short description for the status bar
+    pub message: String,
+    /// Everything the formatter printed on stderr
+    pub stderr: String,
+}
+
+/// Run the formatters in order over `text`. `path` is the file being formatted:
+/// formatters run in its directory and get it in place of `{path}`.
+pub fn run_pipeline(
+    formatters: &[ExternalFormatterConfig],
+    text: &str,
+    path: Option<&Path>,
+) -> Result<String, FormatterError> {
+    let mut text = text.to_string();
+    for formatter in formatters {
+        text = run_formatter(formatter, text, path)?;
+    }
+    Ok(text)
+}
+
+fn run_formatter(
+    formatter: &ExternalFormatterConfig,
+    input: String,
+    path: Option<&Path>,
+) -> Result<String, FormatterError> {
+    let error = |message: String, stderr: String| FormatterError {
+        command: formatter.command.clone(),
+        message,
+        stderr,
+    };
+
+    let path_arg = path.map(|p| p.to_string_lossy()).unwrap_or_default();
+    let mut command = Command::new(&formatter.command);
+    command
+        .args(
+            formatter
+                .args
+                .iter()
+                .map(|arg| arg.replace(PATH_PLACEHOLDER, &path_arg)),
+        )
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = path.and_then(Path::parent) {
+        command.current_dir(dir);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| error(e.to_string(), String::new()))?;
+
+    // Feed stdin and drain stdout/stderr on their own threads so a formatter
+    // blocked on a full pipe can't deadlock us
+    let stdin = child.stdin.take().map(|mut stdin| {
+        thread::spawn(move || {
+            // A formatter may exit without reading everything; its status tells
+            let _ = stdin.write_all(input.as_bytes());
+        })
+    });
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let Some(status) = wait_with_timeout(&mut child, Duration::from_millis(formatter.timeout_ms))
+    else {
+        // Don't wait for the pipe threads: something that survived the kill
+        // could keep the pipes open. They end once the pipes close.
+        return Err(error(
+            format!("timed out after {}ms", formatter.timeout_ms),
+            String::new(),
+        ));
+    };
+    if let Some(stdin) = stdin {
+        let _ = stdin.join();
+    }
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned();
+
+    if !status.success() {
+        let message = stderr
+            .lines()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string())
+            .unwrap_or_else(|| format!("exited with {}", status));
+        return Err(error(message, stderr));
+    }
+    String::from_utf8(stdout).map_err(|_| error("output is not valid UTF-8".to_string(), stderr))
+}
+
+/// Read a pipe to the end on a separate thread
+fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Wait for the child to exit, killing it after `timeout` (then None is returned)
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Option<std::process::ExitStatus> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Some(status),
+            Ok(None) if Instant::now() < deadline => thread::sleep(POLL_INTERVAL),
+            _ => {
+                kill(child);
+                return None;
+            }
+        }
+    }
+}
+
+/// Kill the formatter and the processes it started
+fn kill(child: &mut Child) {
+    #[cfg(unix)]
+    {
+        // The formatter leads its own process group (see `run_formatter`)
+        unsafe {
+            libc::killpg(child.id() as libc::pid_t, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn formatter(command: &str, args: &[&str]) -> ExternalFormatterConfig {
+        ExternalFormatterConfig {
+            command: command.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            timeout_ms: 5000,
+        }
+    }
+
+    #[test]
+    fn test_pipeline_chains_formatters() {
+        let formatters = [
+            formatter("tr", &["a-z", "A-Z"]),
+            formatter("sed", &["s/^/> /"]),
+        ];
+        assert_eq!(
+            run_pipeline(&formatters, "one\ntwo\n", None).unwrap(),
+            "> ONE\n> TWO\n"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file.txt");
+        let echo_path = [formatter("sh", &["-c", "echo {path}"])];
+        assert_eq!(
+            run_pipeline(&echo_path, "", Some(&path)).unwrap(),
+            format!("{}\n", path.display())
+        );
+    }
+
+    #[test]
+    fn test_pipeline_failures() {
+        let failing = [
+            formatter("sh", &["-c", "echo 'line 1: syntax error' >&2; exit 3"]),
+            formatter("cat", &[]),
+        ];
+        let err = run_pipeline(&failing, "text", None).unwrap_err();
+        assert_eq!(err.command, "sh");
+        assert_eq!(err.message, "line 1: syntax error");
+        assert!(err.stderr.contains("syntax error"));
+
+        let mut slow = formatter("sleep", &["5"]);
+        slow.timeout_ms = 50;
+        let err = run_pipeline(&[slow], "text", None).unwrap_err();
+        assert_eq!(err.message, "timed out after 50ms");
+
+        assert!(run_pipeline(&[formatter("/nonexistent/formatter", &[])], "", None).is_err());
+    }
+
+    #[test]
+    fn test_timeout_kills_background_processes() {
+        // The background sleep inherits stdout; only killing the whole
+        // process group lets the pipeline return
+        let mut forking = formatter("sh", &["-c", "sleep 30 & sleep 30"]);
+        forking.timeout_ms = 200;
+        let start = Instant::now();
+        let err = run_pipeline(&[forking], "text", None).unwrap_err();
+        assert_eq!(err.message, "timed out after 200ms");
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "took {:?}",
+            start.elapsed()
+        );
+    }
+}
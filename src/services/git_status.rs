@@ -0,0 +1,229 @@
+//! Git status of the files under a directory
+//!
+//! Runs `git status --porcelain=v2` for the file explorer's decorations. Each
+//! changed, untracked or ignored path gets a `GitFileStatus`; directories take
+//! the most important status below them, so a collapsed directory still shows
+//! that something inside changed. Untracked and ignored directories are
+//! reported once by git and cover everything inside them.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Git status of a file, in increasing order of importance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GitFileStatus {
+    Ignored,
+    Untracked,
+    Added,
+    Modified,
+    Conflicted,
+}
+
+impl GitFileStatus {
+    /// Letter shown next to a file with this status (ignored files are only dimmed)
+    pub fn badge(self) -> Option<char> {
+        match self {
+            Self::Ignored => None,
+            Self::Untracked => Some('U'),
+            Self::Added => Some('A'),
+            Self::Modified => Some('M'),
+            Self::Conflicted => Some('C'),
+        }
+    }
+}
+
+/// Status of the files under a directory
+#[derive(Debug, Clone, Default)]
+pub struct GitStatus {
+    /// Changed files, by absolute path
+    files: HashMap<PathBuf, GitFileStatus>,
+    /// Directories containing changes, with the most important one
+    dirs: HashMap<PathBuf, GitFileStatus>,
+    /// Untracked and ignored directories, which cover their contents
+    covering_dirs: Vec<(PathBuf, GitFileStatus)>,
+}
+
+impl GitStatus {
+    /// Run `git status` for `dir`. `None` if it isn't in a git work tree.
+    pub fn load(dir: &Path) -> io::Result<Option<Self>> {
+        // Status paths are relative to the top of the work tree; the prefix
+        // is `dir` relative to it
+        let prefix = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["rev-parse", "--show-prefix"])
+            .output()?;
+        if !prefix.status.success() {
+            return Ok(None);
+        }
+        let prefix = String::from_utf8_lossy(&prefix.stdout).trim().to_string();
+
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args([
+                "status",
+                "--porcelain=v2",
+                "-z",
+                "--untracked-files=normal",
+                "--ignored=matching",
+            ])
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+        Ok(Some(Self::parse(
+            &String::from_utf8_lossy(&output.stdout),
+            &prefix,
+            dir,
+        )))
+    }
+
+    /// Parse `git status --porcelain=v2 -z` output. Paths in it are relative
+    /// to the work tree top, which is `dir` minus `prefix`; paths outside
+    /// `dir` are left out.
+    pub fn parse(output: &str, prefix: &str, dir: &Path) -> Self {
+        let mut status = Self::default();
+        let mut records = output.split('\0');
+        while let Some(record) = records.next() {
+            let (file_status, path) = match record.as_bytes().first() {
+                Some(b'1') => {
+                    let mut fields = record.splitn(9, ' ');
+                    let xy = fields.nth(1).unwrap_or("");
+                    let kind = if xy.starts_with('A') {
+                        GitFileStatus::Added
+                    } else {
+                        GitFileStatus::Modified
+                    };
+                    (kind, fields.nth(6))
+                }
+                Some(b'2') => {
+                    // Renames and copies are followed by the original path
+                    records.next();
+                    (GitFileStatus::Added, record.splitn(10, ' ').nth(9))
+                }
+                Some(b'u') => (GitFileStatus::Conflicted, record.splitn(11, ' ').nth(10)),
+                Some(b'?') => (GitFileStatus::Untracked, record.get(2..)),
+                Some(b'!') => (GitFileStatus::Ignored, record.get(2..)),
+                _ => continue,
+            };
+            if let Some(path) = path.filter(|path| !path.is_empty()) {
+                status.add(file_status, path, prefix, dir);
+            }
+        }
+        status
+    }
+
+    fn add(&mut self, file_status: GitFileStatus, path: &str, prefix: &str, dir: &Path) {
+        let is_dir = path.ends_with('/');
+        let path = path.trim_end_matches('/');
+        let prefix = prefix.trim_end_matches('/');
+
+        let absolute = if prefix.is_empty() {
+            dir.join(path)
+        } else if let Some(relative) = path.strip_prefix(prefix).and_then(|p| p.strip_prefix('/')) {
+            dir.join(relative)
+        } else if is_dir && (prefix == path || prefix.starts_with(&format!("{}/", path))) {
+            // `dir` is inside an untracked or ignored directory
+            dir.to_path_buf()
+        } else {
+            return;
+        };
+
+        if file_status != GitFileStatus::Ignored {
+            for ancestor in absolute.ancestors().skip(1) {
+                if !ancestor.starts_with(dir) {
+                    break;
+                }
+                let entry = self
+                    .dirs
+                    .entry(ancestor.to_path_buf())
+                    .or_insert(file_status);
+                *entry = (*entry).max(file_status);
+            }
+        }
+        if is_dir {
+            self.covering_dirs.push((absolute, file_status));
+        } else {
+            self.files.insert(absolute, file_status);
+        }
+    }
+
+    /// Status of a file or directory, `None` if it is unchanged
+    pub fn get(&self, path: &Path) -> Option<GitFileStatus> {
+        self.files
+            .get(path)
+            .or_else(|| self.dirs.get(path))
+            .copied()
+            .or_else(|| {
+                self.covering_dirs
+                    .iter()
+                    .find(|(dir, _)| path.starts_with(dir))
+                    .map(|(_, status)| *status)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = concat!(
+        "1 .M N... 100644 100644 100644 3f2a 3f2a src/main.rs\0",
+        "1 A. N... 000000 100644 100644 0000 9c1d src/new file.rs\0",
+        "2 R. N... 100644 100644 100644 aa11 aa11 R100 docs/guide.md\0docs/old.md\0",
+        "u UU N... 100644 100644 100644 100644 b1 b2 b3 src/util/merge.rs\0",
+        "? notes/\0",
+        "! target/\0",
+        "! .env\0",
+    );
+
+    #[test]
+    fn test_parse_and_propagate() {
+        let root = Path::new("/repo");
+        let status = GitStatus::parse(OUTPUT, "", root);
+        let get = |path: &str| status.get(&root.join(path));
+
+        assert_eq!(get("src/main.rs"), Some(GitFileStatus::Modified));
+        assert_eq!(get("src/new file.rs"), Some(GitFileStatus::Added));
+        assert_eq!(get("docs/guide.md"), Some(GitFileStatus::Added));
+        assert_eq!(get("docs/old.md"), None);
+        assert_eq!(get("src/util/merge.rs"), Some(GitFileStatus::Conflicted));
+        assert_eq!(get("notes/todo.txt"), Some(GitFileStatus::Untracked));
+        assert_eq!(get("target/debug/app"), Some(GitFileStatus::Ignored));
+        assert_eq!(get(".env"), Some(GitFileStatus::Ignored));
+        assert_eq!(get("README.md"), None);
+
+        // Directories show the most important status inside them
+        assert_eq!(get("src"), Some(GitFileStatus::Conflicted));
+        assert_eq!(get("docs"), Some(GitFileStatus::Added));
+        assert_eq!(status.get(root), Some(GitFileStatus::Conflicted));
+    }
+
+    #[test]
+    fn test_parse_in_subdirectory() {
+        let dir = Path::new("/repo/src");
+        let status = GitStatus::parse(OUTPUT, "src/", dir);
+
+        assert_eq!(
+            status.get(&dir.join("main.rs")),
+            Some(GitFileStatus::Modified)
+        );
+        assert_eq!(
+            status.get(&dir.join("util")),
+            Some(GitFileStatus::Conflicted)
+        );
+        assert_eq!(status.get(Path::new("/repo/docs/guide.md")), None);
+
+        // Everything in an ignored directory is ignored
+        let status = GitStatus::parse(OUTPUT, "target/debug/", Path::new("/repo/target/debug"));
+        assert_eq!(
+            status.get(Path::new("/repo/target/debug/app")),
+            Some(GitFileStatus::Ignored)
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Language detection from a buffer's path and content
+//!
+//! A Vim or Emacs modeline in the first or last lines names the language
+//! explicitly and wins. Otherwise the path decides (see `detect_language`),
+//! and files whose path matches no language fall back to their `#!` line.
+//! Modeline values and interpreters are looked up in the configured languages'
+//! names, extensions and `interpreters`.
+
+use crate::config::LanguageConfig;
+use crate::model::buffer::Buffer;
+use crate::services::lsp::manager::detect_language;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// How many lines at each end of a buffer are searched for a modeline
+/// (Vim's default `modelines` setting)
+const MODELINE_LINES: usize = 5;
+
+/// Modeline values that differ from the language's name, extension and interpreter
+const MODE_ALIASES: &[(&str, &str)] = &[
+    ("c++", "cpp"),
+    ("shell-script", "sh"),
+    ("js", "javascript"),
+    ("make", "makefile"),
+];
+
+/// A Vim modeline, capturing its options
+static VIM_MODELINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)(?:vim?|Vim|ex)(?:[<=>]?\d+)?:(.*)").unwrap());
+/// The file type option of a Vim modeline
+static VIM_FILETYPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|[\s:])(?:ft|filetype|syntax)=([^\s:]+)").unwrap());
+/// An Emacs modeline, capturing its variables
+static EMACS_MODELINE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"-\*-(.*?)-\*-").unwrap());
+/// The mode variable of an Emacs modeline
+static EMACS_MODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:^|;)\s*mode:\s*([^\s;]+)").unwrap());
+
+/// Detect the language of a buffer from its modeline, path or `#!` line
+pub fn detect_buffer_language(
+    path: Option<&Path>,
+    buffer: &Buffer,
+    languages: &HashMap<String, LanguageConfig>,
+) -> Option<String> {
+    let line = |n: usize| {
+        buffer
+            .get_line(n)
+            .map(|bytes| String::from_utf8_lossy(&bytes).trim_end().to_string())
+    };
+    let mut lines: Vec<String> = (0..MODELINE_LINES).map_while(line).collect();
+    // Large files don't know their line count; only their first lines are searched
+    if let Some(count) = buffer.line_count() {
+        let tail_start = count.saturating_sub(MODELINE_LINES).max(lines.len());
+        lines.extend((tail_start..count).filter_map(line));
+    }
+
+    modeline_language(&lines, languages)
+        .or_else(|| path.and_then(|path| detect_language(path, languages)))
+        .or_else(|| shebang_language(lines.first()?, languages))
+}
+
+/// The language named by a Vim (`vim: set ft=python:`) or Emacs
+/// (`-*- mode: python -*-`) modeline in `lines`
+pub fn modeline_language(
+    lines: &[String],
+    languages: &HashMap<String, LanguageConfig>,
+) -> Option<String> {
+    lines.iter().find_map(|line| {
+        let value = if let Some(options) = VIM_MODELINE.captures(line) {
+            VIM_FILETYPE.captures(&options[1])?[1].to_string()
+        } else {
+            let variables = EMACS_MODELINE.captures(line)?[1].trim().to_string();
+            if variables.contains(':') {
+                EMACS_MODE.captures(&variables)?[1].to_string()
+            } else {
+                variables
+            }
+        };
+        language_named(&value, languages)
+    })
+}
+
+/// The language of the interpreter in a `#!` line, e.g. `#!/bin/bash` or
+/// `#!/usr/bin/env -S python3 -u`. Versioned interpreters (`python3.12`)
+/// fall back to their unversioned name.
+pub fn shebang_language(
+    first_line: &str,
+    languages: &HashMap<String, LanguageConfig>,
+) -> Option<String> {
+    let mut words = first_line.strip_prefix("#!")?.split_whitespace();
+    let mut program = basename(words.next()?);
+    if program == "env" {
+        // Skip env's options (-S, -i) and variable assignments
+        program = basename(words.find(|word| !word.starts_with('-') && !word.contains('='))?);
+    }
+
+    let find = |name: &str| {
+        languages
+            .iter()
+            .find(|(_, config)| config.interpreters.iter().any(|i| i == name))
+            .map(|(language, _)| language.clone())
+    };
+    // python3.12 -> python3 -> python
+    let major = program.split('.').next().unwrap_or(program);
+    let unversioned = major.trim_end_matches(|c: char| c.is_ascii_digit());
+    [program, major, unversioned].into_iter().find_map(find)
+}
+
+/// The configured language a modeline value names: a language name, an
+/// extension or an interpreter (case-insensitive)
+fn language_named(value: &str, languages: &HashMap<String, LanguageConfig>) -> Option<String> {
+    let value = value.to_lowercase();
+    let value = MODE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == value)
+        .map_or(value.as_str(), |(_, name)| *name);
+
+    if languages.contains_key(value) {
+        return Some(value.to_string());
+    }
+    languages
+        .iter()
+        .find(|(_, config)| {
+            config.extensions.iter().any(|ext| ext == value)
+                || config.interpreters.iter().any(|i| i == value)
+        })
+        .map(|(language, _)| language.clone())
+}
+
+fn basename(program: &str) -> &str {
+    program.rsplit('/').next().unwrap_or(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn languages() -> HashMap<String, LanguageConfig> {
+        Config::default().languages
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_shebang() {
+        let languages = languages();
+        let shebang = |line: &str| shebang_language(line, &languages);
+
+        assert_eq!(
+            shebang("#!/usr/bin/env python3"),
+            Some("python".to_string())
+        );
+        assert_eq!(
+            shebang("#!/usr/bin/python3.12 -u"),
+            Some("python".to_string())
+        );
+        assert_eq!(shebang("#!/bin/bash -e"), Some("bash".to_string()));
+        assert_eq!(
+            shebang("#!/usr/bin/env -S NODE_ENV=dev node --trace"),
+            Some("javascript".to_string())
+        );
+        assert_eq!(shebang("#!/usr/bin/make -f"), Some("makefile".to_string()));
+        assert_eq!(shebang("#!/usr/bin/env perl"), None);
+        assert_eq!(shebang("import os"), None);
+    }
+
+    #[test]
+    fn test_modelines() {
+        let languages = languages();
+        let modeline = |text: &str| modeline_language(&lines(text), &languages);
+
+        assert_eq!(
+            modeline("x = 1\n# vim: set ft=python ts=4:"),
+            Some("python".to_string())
+        );
+        assert_eq!(modeline("/* vim:filetype=cpp */"), Some("cpp".to_string()));
+        assert_eq!(
+            modeline("# -*- mode: shell-script; indent-tabs-mode: nil -*-"),
+            Some("bash".to_string())
+        );
+        assert_eq!(modeline("// -*- C++ -*-"), Some("cpp".to_string()));
+        assert_eq!(modeline("# vim: ts=4 sw=4"), None);
+        assert_eq!(modeline("revim: ft=python"), None);
+    }
+
+    #[test]
+    fn test_detect_buffer_language() {
+        let languages = languages();
+        let detect = |path: &str, text: &str| {
+            detect_buffer_language(
+                Some(Path::new(path)),
+                &Buffer::from_str_test(text),
+                &languages,
+            )
+        };
+
+        // The path wins over the shebang, the modeline over both
+        assert_eq!(
+            detect("deploy", "#!/bin/sh\necho hi\n"),
+            Some("bash".to_string())
+        );
+        assert_eq!(
+            detect("tool.js", "#!/usr/bin/env python3\n"),
+            Some("javascript".to_string())
+        );
+        assert_eq!(
+            detect(
+                "notes.txt",
+                "line\nline\nline\nline\nline\nline\n# vim: ft=python\n"
+            ),
+            Some("python".to_string())
+        );
+        assert_eq!(detect("README", "hello\n"), None);
+    }
+}
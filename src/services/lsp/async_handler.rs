@@ -14,24 +14,28 @@
 use crate::services::async_bridge::{
     AsyncBridge, AsyncMessage, LspMessageType, LspProgressValue, LspServerStatus,
 };
+use crate::services::lsp::features::LspFeature;
+use crate::services::lsp::hierarchy::{parse_items as parse_hierarchy_items, HierarchyDirection};
 use crate::services::process_limits::ProcessLimits;
 use lsp_types::{
     notification::{
-        DidChangeTextDocument, DidOpenTextDocument, DidSaveTextDocument, Initialized, Notification,
+        DidChangeConfiguration, DidChangeTextDocument, DidChangeWorkspaceFolders,
+        DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument, Initialized, Notification,
         PublishDiagnostics,
     },
     request::{Initialize, Request, Shutdown},
-    ClientCapabilities, DidChangeTextDocumentParams, DidOpenTextDocumentParams,
+    ClientCapabilities, DidChangeConfigurationParams, DidChangeTextDocumentParams,
+    DidChangeWorkspaceFoldersParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
     DidSaveTextDocumentParams, InitializeParams, InitializeResult, InitializedParams,
     PublishDiagnosticsParams, ServerCapabilities, TextDocumentContentChangeEvent,
     TextDocumentIdentifier, TextDocumentItem, Uri, VersionedTextDocumentIdentifier,
-    WindowClientCapabilities, WorkspaceFolder,
+    WindowClientCapabilities, WorkspaceFolder, WorkspaceFoldersChangeEvent,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{mpsc as std_mpsc, Arc, Mutex};
 use std::time::Instant;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
@@ -199,8 +203,12 @@ impl LspClientState {
 /// Create common LSP client capabilities with workDoneProgress support
 fn create_client_capabilities() -> ClientCapabilities {
     use lsp_types::{
-        GeneralClientCapabilities, RenameClientCapabilities, TextDocumentClientCapabilities,
+        DocumentFormattingClientCapabilities, DocumentRangeFormattingClientCapabilities,
+        DocumentSymbolClientCapabilities, DynamicRegistrationClientCapabilities,
+        FoldingRangeClientCapabilities, GeneralClientCapabilities, RenameClientCapabilities,
+        SelectionRangeClientCapabilities, TextDocumentClientCapabilities,
         WorkspaceClientCapabilities, WorkspaceEditClientCapabilities,
+        WorkspaceSymbolClientCapabilities,
     };
 
     ClientCapabilities {
@@ -214,6 +222,15 @@ fn create_client_capabilities() -> ClientCapabilities {
                 document_changes: Some(true),
                 ..Default::default()
             }),
+            configuration: Some(true),
+            workspace_folders: Some(true),
+            did_change_configuration: Some(DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            symbol: Some(WorkspaceSymbolClientCapabilities {
+                dynamic_registration: Some(false),
+                ..Default::default()
+            }),
             ..Default::default()
         }),
         text_document: Some(TextDocumentClientCapabilities {
@@ -223,6 +240,30 @@ fn create_client_capabilities() -> ClientCapabilities {
                 honors_change_annotations: Some(true),
                 ..Default::default()
             }),
+            formatting: Some(DocumentFormattingClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            range_formatting: Some(DocumentRangeFormattingClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            folding_range: Some(FoldingRangeClientCapabilities {
+                line_folding_only: Some(true),
+                ..Default::default()
+            }),
+            selection_range: Some(SelectionRangeClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            document_symbol: Some(DocumentSymbolClientCapabilities {
+                dynamic_registration: Some(false),
+                hierarchical_document_symbol_support: Some(true),
+                ..Default::default()
+            }),
+            call_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
+            type_hierarchy: Some(DynamicRegistrationClientCapabilities {
+                dynamic_registration: Some(false),
+            }),
             ..Default::default()
         }),
         general: Some(GeneralClientCapabilities {
@@ -262,6 +303,15 @@ enum LspCommand {
     /// Notify document saved
     DidSave { uri: Uri, text: Option<String> },
 
+    /// Notify document closed
+    DidClose { uri: Uri },
+
+    /// Notify the workspace settings changed
+    DidChangeConfiguration { settings: Value },
+
+    /// Add workspace folders to a server that supports several
+    DidChangeWorkspaceFolders { added: Vec<WorkspaceFolder> },
+
     /// Request completion at position
     Completion {
         request_id: u64,
@@ -341,6 +391,46 @@ enum LspCommand {
         end_char: u32,
     },
 
+    /// Request formatting of the whole document, or of a range when given
+    Formatting {
+        request_id: u64,
+        uri: Uri,
+        range: Option<lsp_types::Range>,
+        options: lsp_types::FormattingOptions,
+    },
+
+    /// Request the document's foldable ranges
+    FoldingRange { request_id: u64, uri: Uri },
+
+    /// Request the enclosing selection ranges at each position
+    SelectionRange {
+        request_id: u64,
+        uri: Uri,
+        positions: Vec<lsp_types::Position>,
+    },
+
+    /// Request the document's symbols
+    DocumentSymbol { request_id: u64, uri: Uri },
+
+    /// Request the workspace's symbols matching a query
+    WorkspaceSymbol { request_id: u64, query: String },
+
+    /// Request the call or type hierarchy items at a position
+    PrepareHierarchy {
+        request_id: u64,
+        direction: HierarchyDirection,
+        uri: Uri,
+        line: u32,
+        character: u32,
+    },
+
+    /// Request the callers, callees, supertypes or subtypes of a hierarchy item
+    HierarchyChildren {
+        request_id: u64,
+        direction: HierarchyDirection,
+        item: Value,
+    },
+
     /// Cancel a pending request
     CancelRequest {
         /// Editor's request ID to cancel
@@ -385,6 +475,12 @@ struct LspState {
     /// Language ID (for error reporting)
     language: String,
 
+    /// Server name, identifying this server among the language's servers
+    server: String,
+
+    /// Workspace root the server was started in (for status updates)
+    root: Option<Uri>,
+
     /// Mapping from editor request_id to LSP JSON-RPC id for cancellation
     /// Key: editor request_id, Value: LSP JSON-RPC id
     active_requests: HashMap<u64, i64>,
@@ -429,6 +525,14 @@ impl LspState {
                     tracing::info!("Replaying DidSave for {}", uri.as_str());
                     let _ = self.handle_did_save(uri, text).await;
                 }
+                LspCommand::DidClose { uri } => {
+                    tracing::info!("Replaying DidClose for {}", uri.as_str());
+                    let _ = self.handle_did_close(uri).await;
+                }
+                LspCommand::DidChangeWorkspaceFolders { added } => {
+                    tracing::info!("Replaying DidChangeWorkspaceFolders");
+                    let _ = self.handle_did_change_workspace_folders(added).await;
+                }
                 _ => {}
             }
         }
@@ -550,18 +654,7 @@ impl LspState {
             initialization_options
         );
 
-        let workspace_folders = root_uri.as_ref().map(|uri| {
-            vec![WorkspaceFolder {
-                uri: uri.clone(),
-                name: uri
-                    .path()
-                    .as_str()
-                    .split('/')
-                    .last()
-                    .unwrap_or("workspace")
-                    .to_string(),
-            }]
-        });
+        let workspace_folders = root_uri.as_ref().map(|uri| vec![workspace_folder(uri)]);
 
         let params = InitializeParams {
             process_id: Some(std::process::id()),
@@ -591,6 +684,8 @@ impl LspState {
         // Send running status
         let _ = self.async_tx.send(AsyncMessage::LspStatusUpdate {
             language: self.language.clone(),
+            server: self.server.clone(),
+            root: self.root.clone(),
             status: LspServerStatus::Running,
         });
 
@@ -699,6 +794,50 @@ impl LspState {
         self.send_notification::<DidSaveTextDocument>(params).await
     }
 
+    /// Handle did_close command
+    async fn handle_did_close(&mut self, uri: Uri) -> Result<(), String> {
+        tracing::trace!("LSP: did_close for {}", uri.as_str());
+
+        // A later didOpen of the same path must not be skipped
+        let path = PathBuf::from(uri.path().as_str());
+        self.document_versions.remove(&path);
+        self.pending_opens.remove(&path);
+
+        let params = DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri },
+        };
+
+        self.send_notification::<DidCloseTextDocument>(params).await
+    }
+
+    /// Handle workspace settings change
+    async fn handle_did_change_configuration(&mut self, settings: Value) -> Result<(), String> {
+        tracing::trace!("LSP: did_change_configuration");
+
+        let params = DidChangeConfigurationParams { settings };
+
+        self.send_notification::<DidChangeConfiguration>(params)
+            .await
+    }
+
+    /// Handle workspace folders being added
+    async fn handle_did_change_workspace_folders(
+        &mut self,
+        added: Vec<WorkspaceFolder>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: did_change_workspace_folders ({} added)", added.len());
+
+        let params = DidChangeWorkspaceFoldersParams {
+            event: WorkspaceFoldersChangeEvent {
+                added,
+                removed: Vec::new(),
+            },
+        };
+
+        self.send_notification::<DidChangeWorkspaceFolders>(params)
+            .await
+    }
+
     /// Handle completion request
     async fn handle_completion(
         &mut self,
@@ -755,9 +894,11 @@ impl LspState {
                 };
 
                 // Send to main loop
-                let _ = self
-                    .async_tx
-                    .send(AsyncMessage::LspCompletion { request_id, items });
+                let _ = self.async_tx.send(AsyncMessage::LspCompletion {
+                    request_id,
+                    server: self.server.clone(),
+                    items,
+                });
                 Ok(())
             }
             Err(e) => {
@@ -765,6 +906,7 @@ impl LspState {
                 // Send empty completion on error
                 let _ = self.async_tx.send(AsyncMessage::LspCompletion {
                     request_id,
+                    server: self.server.clone(),
                     items: vec![],
                 });
                 Err(e)
@@ -1317,6 +1459,7 @@ impl LspState {
 
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
+                        server: self.server.clone(),
                         uri: uri_string,
                         result_id,
                         diagnostics,
@@ -1338,6 +1481,7 @@ impl LspState {
 
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
+                        server: self.server.clone(),
                         uri: uri_string,
                         result_id: Some(result_id),
                         diagnostics: Vec::new(),
@@ -1351,6 +1495,7 @@ impl LspState {
                     );
                     let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                         request_id,
+                        server: self.server.clone(),
                         uri: uri_string,
                         result_id: None,
                         diagnostics: Vec::new(),
@@ -1365,6 +1510,7 @@ impl LspState {
                 // Send empty result on error
                 let _ = self.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                     request_id,
+                    server: self.server.clone(),
                     uri: uri.as_str().to_string(),
                     result_id: None,
                     diagnostics: Vec::new(),
@@ -1375,6 +1521,328 @@ impl LspState {
         }
     }
 
+    /// Handle document or range formatting request
+    async fn handle_formatting(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        range: Option<lsp_types::Range>,
+        options: lsp_types::FormattingOptions,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            DocumentFormattingParams, DocumentRangeFormattingParams, TextDocumentIdentifier,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: formatting request for {} (range: {:?})",
+            uri.as_str(),
+            range
+        );
+
+        let text_document = TextDocumentIdentifier { uri };
+        let work_done_progress_params = WorkDoneProgressParams::default();
+        let response = match range {
+            Some(range) => {
+                let params = DocumentRangeFormattingParams {
+                    text_document,
+                    range,
+                    options,
+                    work_done_progress_params,
+                };
+                self.send_request_sequential::<_, Value>(
+                    "textDocument/rangeFormatting",
+                    Some(params),
+                    pending,
+                )
+                .await
+            }
+            None => {
+                let params = DocumentFormattingParams {
+                    text_document,
+                    options,
+                    work_done_progress_params,
+                };
+                self.send_request_sequential::<_, Value>(
+                    "textDocument/formatting",
+                    Some(params),
+                    pending,
+                )
+                .await
+            }
+        };
+
+        // The response is TextEdit[] or null (no changes)
+        let result = response.and_then(|result| {
+            if result.is_null() {
+                Ok(Vec::new())
+            } else {
+                serde_json::from_value::<Vec<lsp_types::TextEdit>>(result)
+                    .map_err(|e| format!("Failed to parse formatting response: {}", e))
+            }
+        });
+        if let Err(e) = &result {
+            tracing::error!("Formatting request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspFormatting {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle folding range request
+    async fn handle_folding_range(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            FoldingRange, FoldingRangeParams, PartialResultParams, TextDocumentIdentifier,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!("LSP: folding range request for {}", uri.as_str());
+
+        let params = FoldingRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let response = self
+            .send_request_sequential::<_, Value>("textDocument/foldingRange", Some(params), pending)
+            .await;
+
+        // The response is FoldingRange[] or null
+        let result = response.and_then(|result| {
+            if result.is_null() {
+                Ok(Vec::new())
+            } else {
+                serde_json::from_value::<Vec<FoldingRange>>(result)
+                    .map_err(|e| format!("Failed to parse folding range response: {}", e))
+            }
+        });
+        if let Err(e) = &result {
+            tracing::debug!("Folding range request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspFoldingRanges {
+            request_id,
+            uri: uri.as_str().to_string(),
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle selection range request
+    async fn handle_selection_range(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        positions: Vec<lsp_types::Position>,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            PartialResultParams, SelectionRange, SelectionRangeParams, TextDocumentIdentifier,
+            WorkDoneProgressParams,
+        };
+
+        tracing::trace!(
+            "LSP: selection range request for {} at {} positions",
+            uri.as_str(),
+            positions.len()
+        );
+
+        let params = SelectionRangeParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            positions,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let response = self
+            .send_request_sequential::<_, Value>(
+                "textDocument/selectionRange",
+                Some(params),
+                pending,
+            )
+            .await;
+
+        // The response is SelectionRange[] (one per position) or null
+        let result = response.and_then(|result| {
+            if result.is_null() {
+                Ok(Vec::new())
+            } else {
+                serde_json::from_value::<Vec<SelectionRange>>(result)
+                    .map_err(|e| format!("Failed to parse selection range response: {}", e))
+            }
+        });
+        if let Err(e) = &result {
+            tracing::debug!("Selection range request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspSelectionRanges {
+            request_id,
+            uri: uri.as_str().to_string(),
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle document symbol request
+    async fn handle_document_symbol(
+        &mut self,
+        request_id: u64,
+        uri: Uri,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            DocumentSymbolParams, DocumentSymbolResponse, PartialResultParams,
+            TextDocumentIdentifier, WorkDoneProgressParams,
+        };
+
+        tracing::trace!("LSP: document symbol request for {}", uri.as_str());
+
+        let params = DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let response = self
+            .send_request_sequential::<_, Value>(
+                "textDocument/documentSymbol",
+                Some(params),
+                pending,
+            )
+            .await;
+
+        // The response is DocumentSymbol[], SymbolInformation[] or null
+        let result = response.and_then(|result| {
+            if result.is_null() {
+                Ok(DocumentSymbolResponse::Nested(Vec::new()))
+            } else {
+                serde_json::from_value::<DocumentSymbolResponse>(result)
+                    .map_err(|e| format!("Failed to parse document symbol response: {}", e))
+            }
+        });
+        if let Err(e) = &result {
+            tracing::debug!("Document symbol request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspDocumentSymbols {
+            request_id,
+            uri: uri.as_str().to_string(),
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle workspace symbol request
+    async fn handle_workspace_symbol(
+        &mut self,
+        request_id: u64,
+        query: String,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{
+            PartialResultParams, WorkDoneProgressParams, WorkspaceSymbolParams,
+            WorkspaceSymbolResponse,
+        };
+
+        tracing::trace!("LSP: workspace symbol request for {:?}", query);
+
+        let params = WorkspaceSymbolParams {
+            query,
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let response = self
+            .send_request_sequential::<_, Value>("workspace/symbol", Some(params), pending)
+            .await;
+
+        // The response is SymbolInformation[], WorkspaceSymbol[] or null
+        let result = response.and_then(|result| {
+            if result.is_null() {
+                Ok(WorkspaceSymbolResponse::Flat(Vec::new()))
+            } else {
+                serde_json::from_value::<WorkspaceSymbolResponse>(result)
+                    .map_err(|e| format!("Failed to parse workspace symbol response: {}", e))
+            }
+        });
+        if let Err(e) = &result {
+            tracing::debug!("Workspace symbol request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspWorkspaceSymbols {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle call or type hierarchy prepare request
+    async fn handle_prepare_hierarchy(
+        &mut self,
+        request_id: u64,
+        direction: HierarchyDirection,
+        uri: Uri,
+        line: u32,
+        character: u32,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        use lsp_types::{Position, TextDocumentIdentifier, TextDocumentPositionParams};
+
+        tracing::trace!(
+            "LSP: {} request at {}:{}:{}",
+            direction.prepare_method(),
+            uri.as_str(),
+            line,
+            character
+        );
+
+        let params = TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri },
+            position: Position { line, character },
+        };
+        let response = self
+            .send_request_sequential::<_, Value>(direction.prepare_method(), Some(params), pending)
+            .await;
+
+        // The response is CallHierarchyItem[], TypeHierarchyItem[] or null
+        let result = response.and_then(|result| parse_hierarchy_items(None, result));
+        if let Err(e) = &result {
+            tracing::debug!("Hierarchy prepare request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspHierarchyItems {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
+    /// Handle request for the children of a call or type hierarchy item
+    async fn handle_hierarchy_children(
+        &mut self,
+        request_id: u64,
+        direction: HierarchyDirection,
+        item: Value,
+        pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
+    ) -> Result<(), String> {
+        tracing::trace!("LSP: {} request", direction.method());
+
+        let params = serde_json::json!({ "item": item });
+        let response = self
+            .send_request_sequential::<_, Value>(direction.method(), Some(params), pending)
+            .await;
+
+        let result = response.and_then(|result| parse_hierarchy_items(Some(direction), result));
+        if let Err(e) = &result {
+            tracing::debug!("Hierarchy request failed: {}", e);
+        }
+        let _ = self.async_tx.send(AsyncMessage::LspHierarchyItems {
+            request_id,
+            result: result.clone(),
+        });
+        result.map(|_| ())
+    }
+
     /// Handle inlay hints request (LSP 3.17+)
     async fn handle_inlay_hints(
         &mut self,
@@ -1577,6 +2045,12 @@ struct LspTask {
     /// Language ID (for error reporting)
     language: String,
 
+    /// Server name, identifying this server among the language's servers
+    server: String,
+
+    /// Workspace root the server was started in (for status updates)
+    root: Option<Uri>,
+
     /// Server command (for plugin identification)
     server_command: String,
 
@@ -1587,10 +2061,13 @@ struct LspTask {
 #[allow(dead_code)]
 impl LspTask {
     /// Create a new LSP task
+    #[allow(clippy::too_many_arguments)]
     async fn spawn(
         command: &str,
         args: &[String],
         language: String,
+        server: String,
+        root: Option<Uri>,
         async_tx: std_mpsc::Sender<AsyncMessage>,
         process_limits: &ProcessLimits,
         stderr_log_path: std::path::PathBuf,
@@ -1647,21 +2124,27 @@ impl LspTask {
             initialized: false,
             async_tx,
             language,
+            server,
+            root,
             server_command: command.to_string(),
             stderr_log_path,
         })
     }
 
     /// Spawn the stdout reader task that continuously reads and dispatches LSP messages
+    #[allow(clippy::too_many_arguments)]
     fn spawn_stdout_reader(
         mut stdout: BufReader<ChildStdout>,
         pending: Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
         async_tx: std_mpsc::Sender<AsyncMessage>,
         language: String,
+        server: String,
+        root: Option<Uri>,
         server_command: String,
         server_response_tx: mpsc::Sender<JsonRpcResponse>,
         stderr_log_path: std::path::PathBuf,
         shutting_down: Arc<AtomicBool>,
+        settings: Arc<Mutex<Option<Value>>>,
     ) {
         tokio::spawn(async move {
             tracing::info!("LSP stdout reader task started for {}", language);
@@ -1674,8 +2157,10 @@ impl LspTask {
                             &pending,
                             &async_tx,
                             &language,
+                            &server,
                             &server_command,
                             &server_response_tx,
+                            &settings,
                         )
                         .await
                         {
@@ -1693,10 +2178,13 @@ impl LspTask {
                             tracing::error!("Error reading from LSP server: {}", e);
                             let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                                 language: language.clone(),
+                                server: server.clone(),
+                                root: root.clone(),
                                 status: LspServerStatus::Error,
                             });
                             let _ = async_tx.send(AsyncMessage::LspError {
                                 language: language.clone(),
+                                server: server.clone(),
                                 error: format!("Read error: {}", e),
                                 stderr_log_path: Some(stderr_log_path.clone()),
                             });
@@ -1710,7 +2198,11 @@ impl LspTask {
     }
 
     /// Run the task (processes commands and reads from stdout)
-    async fn run(self, mut command_rx: mpsc::Receiver<LspCommand>) {
+    async fn run(
+        self,
+        mut command_rx: mpsc::Receiver<LspCommand>,
+        settings: Arc<Mutex<Option<Value>>>,
+    ) {
         tracing::info!("LspTask::run() started for language: {}", self.language);
 
         // Create state struct for command processing
@@ -1723,12 +2215,16 @@ impl LspTask {
             initialized: self.initialized,
             async_tx: self.async_tx.clone(),
             language: self.language.clone(),
+            server: self.server.clone(),
+            root: self.root.clone(),
             active_requests: HashMap::new(),
         };
 
         let pending = Arc::new(Mutex::new(self.pending));
         let async_tx = state.async_tx.clone();
         let language_clone = state.language.clone();
+        let server_clone = state.server.clone();
+        let root_clone = state.root.clone();
 
         // Create channel for server-to-client request responses
         let (server_response_tx, mut server_response_rx) = mpsc::channel::<JsonRpcResponse>(100);
@@ -1742,10 +2238,13 @@ impl LspTask {
             pending.clone(),
             async_tx.clone(),
             language_clone.clone(),
+            server_clone.clone(),
+            root_clone.clone(),
             self.server_command.clone(),
             server_response_tx,
             self.stderr_log_path,
             shutting_down.clone(),
+            settings,
         );
 
         // Sequential command processing loop with server response handling
@@ -1767,6 +2266,8 @@ impl LspTask {
                             // Send initializing status
                             let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                                 language: language_clone.clone(),
+                                server: server_clone.clone(),
+                                root: root_clone.clone(),
                                 status: LspServerStatus::Initializing,
                             });
                             tracing::info!("Processing Initialize command");
@@ -1835,6 +2336,35 @@ impl LspTask {
                                 pending_commands.push(LspCommand::DidSave { uri, text });
                             }
                         }
+                        LspCommand::DidClose { uri } => {
+                            if state.initialized {
+                                tracing::info!("Processing DidClose for {}", uri.as_str());
+                                let _ = state.handle_did_close(uri).await;
+                            } else {
+                                tracing::trace!(
+                                    "Queueing DidClose for {} until initialization completes",
+                                    uri.as_str()
+                                );
+                                pending_commands.push(LspCommand::DidClose { uri });
+                            }
+                        }
+                        LspCommand::DidChangeConfiguration { settings } => {
+                            // Before initialization there is nothing to update: the
+                            // server asks for its settings once it starts
+                            if state.initialized {
+                                tracing::info!("Processing DidChangeConfiguration");
+                                let _ = state.handle_did_change_configuration(settings).await;
+                            }
+                        }
+                        LspCommand::DidChangeWorkspaceFolders { added } => {
+                            if state.initialized {
+                                tracing::info!("Processing DidChangeWorkspaceFolders");
+                                let _ = state.handle_did_change_workspace_folders(added).await;
+                            } else {
+                                pending_commands
+                                    .push(LspCommand::DidChangeWorkspaceFolders { added });
+                            }
+                        }
                         LspCommand::Completion {
                             request_id,
                             uri,
@@ -1853,6 +2383,7 @@ impl LspTask {
                                 tracing::trace!("LSP not initialized, sending empty completion");
                                 let _ = state.async_tx.send(AsyncMessage::LspCompletion {
                                     request_id,
+                                    server: state.server.clone(),
                                     items: vec![],
                                 });
                             }
@@ -2017,6 +2548,7 @@ impl LspTask {
                                 );
                                 let _ = state.async_tx.send(AsyncMessage::LspPulledDiagnostics {
                                     request_id,
+                                    server: state.server.clone(),
                                     uri: uri.as_str().to_string(),
                                     result_id: None,
                                     diagnostics: Vec::new(),
@@ -2024,6 +2556,142 @@ impl LspTask {
                                 });
                             }
                         }
+                        LspCommand::Formatting {
+                            request_id,
+                            uri,
+                            range,
+                            options,
+                        } => {
+                            if state.initialized {
+                                tracing::info!("Processing Formatting request for {}", uri.as_str());
+                                let _ = state
+                                    .handle_formatting(request_id, uri, range, options, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot format");
+                                let _ = state.async_tx.send(AsyncMessage::LspFormatting {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::FoldingRange { request_id, uri } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing FoldingRange request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_folding_range(request_id, uri, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot get folding ranges");
+                                let _ = state.async_tx.send(AsyncMessage::LspFoldingRanges {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::SelectionRange {
+                            request_id,
+                            uri,
+                            positions,
+                        } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing SelectionRange request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_selection_range(request_id, uri, positions, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot get selection ranges");
+                                let _ = state.async_tx.send(AsyncMessage::LspSelectionRanges {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::DocumentSymbol { request_id, uri } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing DocumentSymbol request for {}",
+                                    uri.as_str()
+                                );
+                                let _ = state
+                                    .handle_document_symbol(request_id, uri, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot get document symbols");
+                                let _ = state.async_tx.send(AsyncMessage::LspDocumentSymbols {
+                                    request_id,
+                                    uri: uri.as_str().to_string(),
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::WorkspaceSymbol { request_id, query } => {
+                            if state.initialized {
+                                tracing::info!("Processing WorkspaceSymbol request for {:?}", query);
+                                let _ = state
+                                    .handle_workspace_symbol(request_id, query, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot get workspace symbols");
+                                let _ = state.async_tx.send(AsyncMessage::LspWorkspaceSymbols {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::PrepareHierarchy {
+                            request_id,
+                            direction,
+                            uri,
+                            line,
+                            character,
+                        } => {
+                            if state.initialized {
+                                tracing::info!(
+                                    "Processing PrepareHierarchy request at {}:{}:{}",
+                                    uri.as_str(),
+                                    line,
+                                    character
+                                );
+                                let _ = state
+                                    .handle_prepare_hierarchy(
+                                        request_id, direction, uri, line, character, &pending,
+                                    )
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot prepare hierarchy");
+                                let _ = state.async_tx.send(AsyncMessage::LspHierarchyItems {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
+                        LspCommand::HierarchyChildren {
+                            request_id,
+                            direction,
+                            item,
+                        } => {
+                            if state.initialized {
+                                tracing::info!("Processing {} request", direction.method());
+                                let _ = state
+                                    .handle_hierarchy_children(request_id, direction, item, &pending)
+                                    .await;
+                            } else {
+                                tracing::trace!("LSP not initialized, cannot expand hierarchy");
+                                let _ = state.async_tx.send(AsyncMessage::LspHierarchyItems {
+                                    request_id,
+                                    result: Err("LSP not initialized".to_string()),
+                                });
+                            }
+                        }
                         LspCommand::InlayHints {
                             request_id,
                             uri,
@@ -2130,18 +2798,7 @@ impl LspTask {
             initialization_options
         );
 
-        let workspace_folders = root_uri.as_ref().map(|uri| {
-            vec![WorkspaceFolder {
-                uri: uri.clone(),
-                name: uri
-                    .path()
-                    .as_str()
-                    .split('/')
-                    .last()
-                    .unwrap_or("workspace")
-                    .to_string(),
-            }]
-        });
+        let workspace_folders = root_uri.as_ref().map(|uri| vec![workspace_folder(uri)]);
 
         let params = InitializeParams {
             process_id: Some(std::process::id()),
@@ -2171,6 +2828,8 @@ impl LspTask {
         // Send running status
         let _ = self.async_tx.send(AsyncMessage::LspStatusUpdate {
             language: self.language.clone(),
+            server: self.server.clone(),
+            root: self.root.clone(),
             status: LspServerStatus::Running,
         });
 
@@ -2483,6 +3142,8 @@ impl LspTask {
 
                     // Send to main loop
                     let _ = self.async_tx.send(AsyncMessage::LspDiagnostics {
+                        language: self.language.clone(),
+                        server: self.server.clone(),
                         uri: params.uri.to_string(),
                         diagnostics: params.diagnostics,
                     });
@@ -2727,14 +3388,51 @@ async fn read_message_from_stdout(
     serde_json::from_str(&json).map_err(|e| format!("Failed to deserialize message: {}", e))
 }
 
+/// A workspace folder named after its directory
+fn workspace_folder(uri: &Uri) -> WorkspaceFolder {
+    WorkspaceFolder {
+        uri: uri.clone(),
+        name: uri
+            .path()
+            .as_str()
+            .split('/')
+            .rfind(|segment| !segment.is_empty())
+            .unwrap_or("workspace")
+            .to_string(),
+    }
+}
+
+/// The part of a server's settings for a `workspace/configuration` section.
+/// A section like `python.analysis` is looked up as a key first, then as a
+/// path of nested keys; a missing section is `null`.
+fn settings_section(settings: Option<&Value>, section: Option<&str>) -> Value {
+    let Some(settings) = settings else {
+        return Value::Null;
+    };
+    let Some(section) = section.filter(|s| !s.is_empty()) else {
+        return settings.clone();
+    };
+    if let Some(value) = settings.get(section) {
+        return value.clone();
+    }
+    section
+        .split('.')
+        .try_fold(settings, |value, key| value.get(key))
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
 /// Standalone function to handle and dispatch messages (for reader task)
+#[allow(clippy::too_many_arguments)]
 async fn handle_message_dispatch(
     message: JsonRpcMessage,
     pending: &Arc<Mutex<HashMap<i64, oneshot::Sender<Result<Value, String>>>>>,
     async_tx: &std_mpsc::Sender<AsyncMessage>,
     language: &str,
+    server: &str,
     server_command: &str,
     server_response_tx: &mpsc::Sender<JsonRpcResponse>,
+    settings: &Arc<Mutex<Option<Value>>>,
 ) -> Result<(), String> {
     match message {
         JsonRpcMessage::Response(response) => {
@@ -2765,7 +3463,7 @@ async fn handle_message_dispatch(
         }
         JsonRpcMessage::Notification(notification) => {
             tracing::trace!("Received LSP notification: {}", notification.method);
-            handle_notification_dispatch(notification, async_tx, language).await?;
+            handle_notification_dispatch(notification, async_tx, language, server).await?;
         }
         JsonRpcMessage::Request(request) => {
             // Handle server-to-client requests - MUST respond to avoid timeouts
@@ -2782,42 +3480,27 @@ async fn handle_message_dispatch(
                     }
                 }
                 "workspace/configuration" => {
-                    // Return configuration with inlay hints enabled for rust-analyzer
-                    // The request contains items asking for configuration sections
-                    // We return an array with one config object per requested item
-                    tracing::trace!(
-                        "Responding to workspace/configuration with inlay hints enabled"
-                    );
-
-                    // Parse request params to see how many items are requested
-                    let num_items = request
+                    // Answer each requested item with its section of the settings
+                    let settings = settings.lock().unwrap().clone();
+                    let configs: Vec<Value> = request
                         .params
                         .as_ref()
                         .and_then(|p| p.get("items"))
                         .and_then(|items| items.as_array())
-                        .map(|arr| arr.len())
-                        .unwrap_or(1);
-
-                    // rust-analyzer configuration with inlay hints enabled
-                    let ra_config = serde_json::json!({
-                        "inlayHints": {
-                            "typeHints": {
-                                "enable": true
-                            },
-                            "parameterHints": {
-                                "enable": true
-                            },
-                            "chainingHints": {
-                                "enable": true
-                            },
-                            "closureReturnTypeHints": {
-                                "enable": "always"
-                            }
-                        }
-                    });
-
-                    // Return one config object for each requested item
-                    let configs: Vec<Value> = (0..num_items).map(|_| ra_config.clone()).collect();
+                        .map(|items| {
+                            items
+                                .iter()
+                                .map(|item| {
+                                    let section = item.get("section").and_then(|s| s.as_str());
+                                    settings_section(settings.as_ref(), section)
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    tracing::trace!(
+                        "Responding to workspace/configuration with {} items",
+                        configs.len()
+                    );
 
                     JsonRpcResponse {
                         jsonrpc: "2.0".to_string(),
@@ -2871,6 +3554,7 @@ async fn handle_notification_dispatch(
     notification: JsonRpcNotification,
     async_tx: &std_mpsc::Sender<AsyncMessage>,
     language: &str,
+    server: &str,
 ) -> Result<(), String> {
     match notification.method.as_str() {
         PublishDiagnostics::METHOD => {
@@ -2886,6 +3570,8 @@ async fn handle_notification_dispatch(
 
                 // Send to main loop
                 let _ = async_tx.send(AsyncMessage::LspDiagnostics {
+                    language: language.to_string(),
+                    server: server.to_string(),
                     uri: params.uri.to_string(),
                     diagnostics: params.diagnostics,
                 });
@@ -3098,34 +3784,59 @@ pub struct LspHandle {
     /// Client state
     state: Arc<Mutex<LspClientState>>,
 
+    /// Capabilities the server advertised when it initialized
+    capabilities: Arc<Mutex<Option<ServerCapabilities>>>,
+
+    /// Workspace settings served to the server (see `LspServerConfig::settings`)
+    settings: Arc<Mutex<Option<Value>>>,
+
+    /// Server name (see `LspServerConfig::display_name`)
+    server: String,
+
     /// Runtime handle for blocking operations
     runtime: tokio::runtime::Handle,
 }
 
 impl LspHandle {
-    /// Spawn a new LSP server in an async task
+    /// Spawn a new LSP server in an async task, for the workspace `root`
+    #[allow(clippy::too_many_arguments)]
     pub fn spawn(
         runtime: &tokio::runtime::Handle,
         command: &str,
         args: &[String],
         language: String,
+        server: String,
+        root: Option<Uri>,
         async_bridge: &AsyncBridge,
         process_limits: ProcessLimits,
     ) -> Result<Self, String> {
         let (command_tx, command_rx) = mpsc::channel(100); // Buffer up to 100 commands
         let async_tx = async_bridge.sender();
         let language_clone = language.clone();
+        let server_clone = server.clone();
+        let root_clone = root.clone();
         let command = command.to_string();
         let args = args.to_vec();
         let state = Arc::new(Mutex::new(LspClientState::Starting));
-
-        // Create stderr log path in system temp directory (cross-platform)
-        let stderr_log_path =
-            std::env::temp_dir().join(format!("fresh-lsp-{}-{}.log", language, std::process::id()));
+        let settings = Arc::new(Mutex::new(None));
+        let settings_clone = settings.clone();
+
+        // Create stderr log path in system temp directory (cross-platform),
+        // numbered as a server can run once per workspace root
+        static INSTANCES: AtomicUsize = AtomicUsize::new(0);
+        let stderr_log_path = std::env::temp_dir().join(format!(
+            "fresh-lsp-{}-{}-{}-{}.log",
+            language,
+            server,
+            std::process::id(),
+            INSTANCES.fetch_add(1, Ordering::Relaxed)
+        ));
 
         // Send starting status
         let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
             language: language.clone(),
+            server: server.clone(),
+            root: root.clone(),
             status: LspServerStatus::Starting,
         });
 
@@ -3136,6 +3847,8 @@ impl LspHandle {
                 &command,
                 &args,
                 language_clone.clone(),
+                server_clone.clone(),
+                root_clone.clone(),
                 async_tx.clone(),
                 &process_limits,
                 stderr_log_path_clone.clone(),
@@ -3143,7 +3856,7 @@ impl LspHandle {
             .await
             {
                 Ok(task) => {
-                    task.run(command_rx).await;
+                    task.run(command_rx, settings_clone).await;
                 }
                 Err(e) => {
                     tracing::error!("Failed to spawn LSP task: {}", e);
@@ -3155,10 +3868,13 @@ impl LspHandle {
 
                     let _ = async_tx.send(AsyncMessage::LspStatusUpdate {
                         language: language_clone.clone(),
+                        server: server_clone.clone(),
+                        root: root_clone.clone(),
                         status: LspServerStatus::Error,
                     });
                     let _ = async_tx.send(AsyncMessage::LspError {
                         language: language_clone,
+                        server: server_clone,
                         error: e,
                         stderr_log_path: Some(stderr_log_path_clone),
                     });
@@ -3169,6 +3885,9 @@ impl LspHandle {
         Ok(Self {
             command_tx,
             state,
+            capabilities: Arc::new(Mutex::new(None)),
+            settings,
+            server,
             runtime: runtime.clone(),
         })
     }
@@ -3200,6 +3919,7 @@ impl LspHandle {
         }
 
         let state = self.state.clone();
+        let capabilities = self.capabilities.clone();
 
         // Create a channel for the response, but don't wait for it
         let (tx, rx) = oneshot::channel();
@@ -3216,8 +3936,11 @@ impl LspHandle {
         let runtime = self.runtime.clone();
         runtime.spawn(async move {
             match tokio::time::timeout(std::time::Duration::from_secs(10), rx).await {
-                Ok(Ok(Ok(_))) => {
+                Ok(Ok(Ok(result))) => {
                     // Successfully initialized
+                    if let Ok(mut c) = capabilities.lock() {
+                        *c = Some(result.capabilities);
+                    }
                     if let Ok(mut s) = state.lock() {
                         let _ = s.transition_to(LspClientState::Running);
                     }
@@ -3257,6 +3980,68 @@ impl LspHandle {
         *self.state.lock().unwrap()
     }
 
+    /// The server's name
+    pub fn server(&self) -> &str {
+        &self.server
+    }
+
+    /// Replace the workspace settings, notifying the server if it is running
+    /// and they changed
+    pub fn update_settings(&self, settings: Option<Value>) -> Result<(), String> {
+        {
+            let mut current = self.settings.lock().unwrap();
+            if *current == settings {
+                return Ok(());
+            }
+            *current = settings.clone();
+        }
+
+        if !self.is_initialized() {
+            return Ok(());
+        }
+        self.command_tx
+            .try_send(LspCommand::DidChangeConfiguration {
+                settings: settings.unwrap_or(Value::Null),
+            })
+            .map_err(|_| "Failed to send did_change_configuration command".to_string())
+    }
+
+    /// Whether the server can be given more workspace folders after it started,
+    /// `None` until it has initialized
+    pub fn supports_workspace_folders(&self) -> Option<bool> {
+        self.capabilities
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|capabilities| {
+                capabilities
+                    .workspace
+                    .as_ref()
+                    .and_then(|workspace| workspace.workspace_folders.as_ref())
+                    .is_some_and(|folders| {
+                        folders.supported == Some(true) && folders.change_notifications.is_some()
+                    })
+            })
+    }
+
+    /// Add a workspace folder (see `supports_workspace_folders`)
+    pub fn add_workspace_folder(&self, uri: &Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidChangeWorkspaceFolders {
+                added: vec![workspace_folder(uri)],
+            })
+            .map_err(|_| "Failed to send did_change_workspace_folders command".to_string())
+    }
+
+    /// Whether the server advertised a feature, `None` until it has initialized
+    pub fn advertises(&self, feature: LspFeature) -> Option<bool> {
+        self.capabilities
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|capabilities| feature.is_advertised(capabilities))
+    }
+
     /// Notify document opened
     pub fn did_open(&self, uri: Uri, text: String, language_id: String) -> Result<(), String> {
         // Send command to LspTask which will queue it if not initialized yet
@@ -3291,6 +4076,13 @@ impl LspHandle {
             .map_err(|_| "Failed to send did_save command".to_string())
     }
 
+    /// Send didClose notification
+    pub fn did_close(&self, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DidClose { uri })
+            .map_err(|_| "Failed to send did_close command".to_string())
+    }
+
     /// Request completion at position
     pub fn completion(
         &self,
@@ -3468,6 +4260,114 @@ impl LspHandle {
             .map_err(|_| "Failed to send inlay_hints command".to_string())
     }
 
+    /// Request formatting of the whole document (textDocument/formatting)
+    pub fn formatting(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        options: lsp_types::FormattingOptions,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::Formatting {
+                request_id,
+                uri,
+                range: None,
+                options,
+            })
+            .map_err(|_| "Failed to send formatting command".to_string())
+    }
+
+    /// Request formatting of a range (textDocument/rangeFormatting)
+    pub fn range_formatting(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        range: lsp_types::Range,
+        options: lsp_types::FormattingOptions,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::Formatting {
+                request_id,
+                uri,
+                range: Some(range),
+                options,
+            })
+            .map_err(|_| "Failed to send range_formatting command".to_string())
+    }
+
+    /// Request the document's foldable ranges (textDocument/foldingRange)
+    pub fn folding_ranges(&self, request_id: u64, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::FoldingRange { request_id, uri })
+            .map_err(|_| "Failed to send folding_range command".to_string())
+    }
+
+    /// Request the enclosing selection ranges at each position (textDocument/selectionRange)
+    pub fn selection_ranges(
+        &self,
+        request_id: u64,
+        uri: Uri,
+        positions: Vec<lsp_types::Position>,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::SelectionRange {
+                request_id,
+                uri,
+                positions,
+            })
+            .map_err(|_| "Failed to send selection_range command".to_string())
+    }
+
+    /// Request the document's symbols (textDocument/documentSymbol)
+    pub fn document_symbols(&self, request_id: u64, uri: Uri) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::DocumentSymbol { request_id, uri })
+            .map_err(|_| "Failed to send document_symbol command".to_string())
+    }
+
+    /// Request the workspace's symbols matching `query` (workspace/symbol)
+    pub fn workspace_symbols(&self, request_id: u64, query: String) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::WorkspaceSymbol { request_id, query })
+            .map_err(|_| "Failed to send workspace_symbol command".to_string())
+    }
+
+    /// Request the call or type hierarchy items at a position
+    pub fn prepare_hierarchy(
+        &self,
+        request_id: u64,
+        direction: HierarchyDirection,
+        uri: Uri,
+        line: u32,
+        character: u32,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::PrepareHierarchy {
+                request_id,
+                direction,
+                uri,
+                line,
+                character,
+            })
+            .map_err(|_| "Failed to send prepare_hierarchy command".to_string())
+    }
+
+    /// Request the children of a hierarchy item, as the server sent it
+    pub fn hierarchy_children(
+        &self,
+        request_id: u64,
+        direction: HierarchyDirection,
+        item: Value,
+    ) -> Result<(), String> {
+        self.command_tx
+            .try_send(LspCommand::HierarchyChildren {
+                request_id,
+                direction,
+                item,
+            })
+            .map_err(|_| "Failed to send hierarchy_children command".to_string())
+    }
+
     /// Cancel a pending request by its editor request_id
     ///
     /// This sends a $/cancelRequest notification to the LSP server.
@@ -3689,6 +4589,79 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_workspace_folder_name() {
+        let uri: Uri = "file:///work/crates/core/".parse().unwrap();
+        assert_eq!(workspace_folder(&uri).name, "core");
+        let uri: Uri = "file:///".parse().unwrap();
+        assert_eq!(workspace_folder(&uri).name, "workspace");
+    }
+
+    fn test_settings() -> Value {
+        serde_json::json!({
+            "python": { "analysis": { "typeCheckingMode": "strict" } },
+            "rust-analyzer": { "check": { "command": "clippy" } },
+            "pyright.disableLanguageServices": false,
+            "ruff": null
+        })
+    }
+
+    #[test]
+    fn test_settings_section_nested() {
+        let settings = test_settings();
+        let settings = Some(&settings);
+
+        assert_eq!(
+            settings_section(settings, Some("rust-analyzer")),
+            serde_json::json!({ "check": { "command": "clippy" } })
+        );
+        assert_eq!(
+            settings_section(settings, Some("python.analysis")),
+            serde_json::json!({ "typeCheckingMode": "strict" })
+        );
+        assert_eq!(
+            settings_section(settings, Some("python.analysis.typeCheckingMode")),
+            serde_json::json!("strict")
+        );
+        assert_eq!(
+            settings_section(settings, Some("pyright.disableLanguageServices")),
+            serde_json::json!(false)
+        );
+        assert_eq!(settings_section(settings, None), *settings.unwrap());
+        assert_eq!(settings_section(settings, Some("")), *settings.unwrap());
+    }
+
+    #[test]
+    fn test_settings_section_missing() {
+        let settings = test_settings();
+        let settings = Some(&settings);
+
+        assert_eq!(settings_section(settings, Some("gopls")), Value::Null);
+        assert_eq!(
+            settings_section(settings, Some("python.linting")),
+            Value::Null
+        );
+        assert_eq!(
+            settings_section(settings, Some("rust-analyzer.check.command.extra")),
+            Value::Null
+        );
+        assert_eq!(settings_section(None, Some("python")), Value::Null);
+        assert_eq!(settings_section(None, None), Value::Null);
+    }
+
+    #[test]
+    fn test_settings_section_null() {
+        let settings = test_settings();
+        let settings = Some(&settings);
+
+        assert_eq!(settings_section(settings, Some("ruff")), Value::Null);
+        assert_eq!(settings_section(settings, Some("ruff.lint")), Value::Null);
+
+        let null = Value::Null;
+        assert_eq!(settings_section(Some(&null), None), Value::Null);
+        assert_eq!(settings_section(Some(&null), Some("python")), Value::Null);
+    }
+
     #[tokio::test]
     async fn test_lsp_handle_spawn_and_drop() {
         // This test spawns a mock LSP server (cat command that echoes input)
@@ -3703,6 +4676,8 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "test-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         );
@@ -3729,6 +4704,8 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "test-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3755,6 +4732,8 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "test-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3787,6 +4766,8 @@ mod tests {
             "cat",
             &[],
             "test".to_string(),
+            "test-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3820,6 +4801,8 @@ mod tests {
             "this-command-does-not-exist-12345",
             &[],
             "test".to_string(),
+            "test-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         );
@@ -3857,6 +4840,8 @@ mod tests {
                     "cat",
                     &[],
                     "test".to_string(),
+                    "test-server".to_string(),
+                    None,
                     &async_bridge,
                     ProcessLimits::unlimited(),
                 )
@@ -3926,6 +4911,8 @@ mod tests {
             "cat", // Simple command that will exit immediately
             &[],
             "test".to_string(),
+            "test-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -3975,6 +4962,8 @@ mod tests {
             "bash",
             &["-c".to_string(), fake_lsp_script.to_string()],
             "fake".to_string(),
+            "fake-server".to_string(),
+            None,
             &async_bridge,
             ProcessLimits::unlimited(),
         )
@@ -1,3 +1,4 @@
+use crate::services::lsp::features::LspFeature;
 use crate::services::process_limits::ProcessLimits;
 use lsp_types::{
     notification::{Notification, PublishDiagnostics},
@@ -478,6 +479,111 @@ pub struct LspServerConfig {
     /// For example, Deno requires `{"enable": true}` to enable completions.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub initialization_options: Option<serde_json::Value>,
+
+    /// Name shown in status messages and as the source of diagnostics and
+    /// completions. Defaults to the command's file name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Features this server is used for (all advertised features if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub only_features: Option<Vec<LspFeature>>,
+
+    /// Features this server is never used for, even if it advertises them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub except_features: Vec<LspFeature>,
+
+    /// Workspace settings, served by section (e.g. `rust-analyzer` or
+    /// `python.analysis`) when the server asks for its configuration, and
+    /// sent to it again when the config is reloaded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<serde_json::Value>,
+
+    /// Files or directories (globs allowed, e.g. `*.csproj`) marking a
+    /// workspace root. The server is started in the nearest directory above
+    /// a file that contains one, within the editor's working directory; the
+    /// working directory itself is used when there is none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub root_markers: Vec<String>,
+}
+
+impl Default for LspServerConfig {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            args: Vec::new(),
+            enabled: true,
+            auto_start: false,
+            process_limits: ProcessLimits::default(),
+            initialization_options: None,
+            name: None,
+            only_features: None,
+            except_features: Vec::new(),
+            settings: None,
+            root_markers: Vec::new(),
+        }
+    }
+}
+
+impl LspServerConfig {
+    /// The server's name: `name`, or the command's file name
+    pub fn display_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            std::path::Path::new(&self.command)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.command.clone())
+        })
+    }
+
+    /// Whether the feature allow and deny lists let this server handle `feature`
+    pub fn allows_feature(&self, feature: LspFeature) -> bool {
+        self.only_features
+            .as_ref()
+            .is_none_or(|only| only.contains(&feature))
+            && !self.except_features.contains(&feature)
+    }
+}
+
+/// The language servers of a language, in order of preference: one server
+/// object or a list of them
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum LspServers {
+    One(LspServerConfig),
+    Many(Vec<LspServerConfig>),
+}
+
+impl std::ops::Deref for LspServers {
+    type Target = [LspServerConfig];
+
+    fn deref(&self) -> &[LspServerConfig] {
+        match self {
+            Self::One(server) => std::slice::from_ref(server),
+            Self::Many(servers) => servers,
+        }
+    }
+}
+
+impl std::ops::DerefMut for LspServers {
+    fn deref_mut(&mut self) -> &mut [LspServerConfig] {
+        match self {
+            Self::One(server) => std::slice::from_mut(server),
+            Self::Many(servers) => servers,
+        }
+    }
+}
+
+impl From<LspServerConfig> for LspServers {
+    fn from(server: LspServerConfig) -> Self {
+        Self::One(server)
+    }
+}
+
+impl From<Vec<LspServerConfig>> for LspServers {
+    fn from(servers: Vec<LspServerConfig>) -> Self {
+        Self::Many(servers)
+    }
 }
 
 fn default_true() -> bool {
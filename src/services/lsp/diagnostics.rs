@@ -7,6 +7,7 @@ use crate::state::EditorState;
 use crate::view::overlay::{Overlay, OverlayFace, OverlayNamespace};
 use lsp_types::{Diagnostic, DiagnosticSeverity};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
 use std::ops::Range;
 use std::sync::Mutex;
@@ -178,6 +179,66 @@ pub fn apply_diagnostics_to_state(
     }
 }
 
+/// Diagnostics per document, kept separately for each language server
+///
+/// A language can have several servers publishing diagnostics for the same
+/// file. Each publish replaces only that server's set, and the merged list is
+/// what gets displayed. Diagnostics without a `source` are labelled with the
+/// name of the server that reported them.
+#[derive(Debug, Default)]
+pub struct ServerDiagnostics {
+    by_uri: HashMap<String, BTreeMap<String, Vec<Diagnostic>>>,
+}
+
+impl ServerDiagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace one server's diagnostics for a document, returning the merged list
+    pub fn update(
+        &mut self,
+        uri: &str,
+        server: &str,
+        mut diagnostics: Vec<Diagnostic>,
+    ) -> Vec<Diagnostic> {
+        for diagnostic in &mut diagnostics {
+            diagnostic.source.get_or_insert_with(|| server.to_string());
+        }
+
+        let servers = self.by_uri.entry(uri.to_string()).or_default();
+        if diagnostics.is_empty() {
+            servers.remove(server);
+        } else {
+            servers.insert(server.to_string(), diagnostics);
+        }
+
+        let merged: Vec<Diagnostic> = servers.values().flatten().cloned().collect();
+        if servers.is_empty() {
+            self.by_uri.remove(uri);
+        }
+        merged
+    }
+
+    /// The last diagnostics a server reported for a document
+    pub fn get(&self, uri: &str, server: &str) -> Option<&Vec<Diagnostic>> {
+        self.by_uri.get(uri)?.get(server)
+    }
+
+    /// Forget everything a server reported, e.g. after it stopped or crashed.
+    /// Returns the URIs whose merged diagnostics changed.
+    pub fn remove_server(&mut self, server: &str) -> Vec<String> {
+        let mut changed = Vec::new();
+        self.by_uri.retain(|uri, servers| {
+            if servers.remove(server).is_some() {
+                changed.push(uri.clone());
+            }
+            !servers.is_empty()
+        });
+        changed
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,4 +386,38 @@ mod tests {
         assert_eq!(range.start, 3);
         assert_eq!(range.end, 8);
     }
+
+    #[test]
+    fn test_server_diagnostics_merge() {
+        let diagnostic = |message: &str, source: Option<&str>| Diagnostic {
+            range: Range::default(),
+            message: message.to_string(),
+            source: source.map(String::from),
+            ..Default::default()
+        };
+        let mut diagnostics = ServerDiagnostics::new();
+
+        let merged = diagnostics.update("file:///a.py", "pyright", vec![diagnostic("type", None)]);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].source.as_deref(), Some("pyright"));
+
+        let merged = diagnostics.update(
+            "file:///a.py",
+            "ruff",
+            vec![
+                diagnostic("unused", Some("Ruff")),
+                diagnostic("style", None),
+            ],
+        );
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[1].source.as_deref(), Some("Ruff"));
+        assert_eq!(merged[2].source.as_deref(), Some("ruff"));
+
+        // A server's new publish only replaces its own diagnostics
+        let merged = diagnostics.update("file:///a.py", "pyright", vec![]);
+        assert_eq!(merged.len(), 2);
+
+        assert_eq!(diagnostics.remove_server("ruff"), vec!["file:///a.py"]);
+        assert!(diagnostics.get("file:///a.py", "ruff").is_none());
+    }
 }
@@ -0,0 +1,107 @@
+//! LSP features and the server capabilities that advertise them
+//!
+//! A language can have several servers (e.g. pyright and ruff for Python).
+//! Each request goes to a server whose config allows the feature and whose
+//! `ServerCapabilities` advertise it; completions and diagnostics are
+//! gathered from every such server.
+
+use lsp_types::{
+    CallHierarchyServerCapability, CodeActionProviderCapability, FoldingRangeProviderCapability,
+    HoverProviderCapability, OneOf, SelectionRangeProviderCapability, ServerCapabilities,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// A language server feature that can be routed to one of a language's servers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LspFeature {
+    Completion,
+    Hover,
+    Definition,
+    References,
+    Rename,
+    SignatureHelp,
+    CodeAction,
+    Diagnostics,
+    Formatting,
+    RangeFormatting,
+    InlayHints,
+    FoldingRange,
+    SelectionRange,
+    DocumentSymbol,
+    WorkspaceSymbol,
+    CallHierarchy,
+    TypeHierarchy,
+}
+
+impl LspFeature {
+    /// Whether a server with these capabilities provides the feature.
+    /// Published diagnostics need no capability; this checks for pull
+    /// diagnostics, which a server without it still sends by notification.
+    /// `ServerCapabilities` has no `typeHierarchyProvider`, so any server is
+    /// taken to provide type hierarchies; one that doesn't answers with an error.
+    pub fn is_advertised(self, capabilities: &ServerCapabilities) -> bool {
+        fn one_of<T>(provider: &Option<OneOf<bool, T>>) -> bool {
+            !matches!(provider, None | Some(OneOf::Left(false)))
+        }
+
+        match self {
+            Self::Completion => capabilities.completion_provider.is_some(),
+            Self::Hover => !matches!(
+                capabilities.hover_provider,
+                None | Some(HoverProviderCapability::Simple(false))
+            ),
+            Self::Definition => one_of(&capabilities.definition_provider),
+            Self::References => one_of(&capabilities.references_provider),
+            Self::Rename => one_of(&capabilities.rename_provider),
+            Self::SignatureHelp => capabilities.signature_help_provider.is_some(),
+            Self::CodeAction => !matches!(
+                capabilities.code_action_provider,
+                None | Some(CodeActionProviderCapability::Simple(false))
+            ),
+            Self::Diagnostics => capabilities.diagnostic_provider.is_some(),
+            Self::Formatting => one_of(&capabilities.document_formatting_provider),
+            Self::RangeFormatting => one_of(&capabilities.document_range_formatting_provider),
+            Self::InlayHints => one_of(&capabilities.inlay_hint_provider),
+            Self::FoldingRange => !matches!(
+                capabilities.folding_range_provider,
+                None | Some(FoldingRangeProviderCapability::Simple(false))
+            ),
+            Self::SelectionRange => !matches!(
+                capabilities.selection_range_provider,
+                None | Some(SelectionRangeProviderCapability::Simple(false))
+            ),
+            Self::DocumentSymbol => one_of(&capabilities.document_symbol_provider),
+            Self::WorkspaceSymbol => one_of(&capabilities.workspace_symbol_provider),
+            Self::CallHierarchy => !matches!(
+                capabilities.call_hierarchy_provider,
+                None | Some(CallHierarchyServerCapability::Simple(false))
+            ),
+            Self::TypeHierarchy => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_advertised() {
+        let capabilities: ServerCapabilities = serde_json::from_value(serde_json::json!({
+            "hoverProvider": true,
+            "definitionProvider": false,
+            "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+            "documentFormattingProvider": true
+        }))
+        .unwrap();
+
+        assert!(LspFeature::Hover.is_advertised(&capabilities));
+        assert!(LspFeature::CodeAction.is_advertised(&capabilities));
+        assert!(LspFeature::Formatting.is_advertised(&capabilities));
+        assert!(!LspFeature::Definition.is_advertised(&capabilities));
+        assert!(!LspFeature::Completion.is_advertised(&capabilities));
+        assert!(!LspFeature::Diagnostics.is_advertised(&capabilities));
+    }
+}
@@ -0,0 +1,205 @@
+//! Call and type hierarchies
+//!
+//! Both are explored the same way: `textDocument/prepareCallHierarchy` (or
+//! `prepareTypeHierarchy`) turns a position into items, and the callers,
+//! callees, supertypes or subtypes of an item are requested when it is
+//! expanded. Items go back to the server exactly as it sent them, since servers
+//! keep their own state in an item's `data`.
+
+use crate::services::lsp::features::LspFeature;
+use lsp_types::{Position, Range, SymbolKind, Uri};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Which way a hierarchy is explored from an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HierarchyDirection {
+    /// The functions that call the item
+    IncomingCalls,
+    /// The functions the item calls
+    OutgoingCalls,
+    /// The types the item extends or implements
+    Supertypes,
+    /// The types that extend or implement the item
+    Subtypes,
+}
+
+impl HierarchyDirection {
+    pub fn feature(self) -> LspFeature {
+        match self {
+            Self::IncomingCalls | Self::OutgoingCalls => LspFeature::CallHierarchy,
+            Self::Supertypes | Self::Subtypes => LspFeature::TypeHierarchy,
+        }
+    }
+
+    /// The request that finds the items at a position
+    pub fn prepare_method(self) -> &'static str {
+        match self {
+            Self::IncomingCalls | Self::OutgoingCalls => "textDocument/prepareCallHierarchy",
+            Self::Supertypes | Self::Subtypes => "textDocument/prepareTypeHierarchy",
+        }
+    }
+
+    /// The request that finds an item's children
+    pub fn method(self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "callHierarchy/incomingCalls",
+            Self::OutgoingCalls => "callHierarchy/outgoingCalls",
+            Self::Supertypes => "typeHierarchy/supertypes",
+            Self::Subtypes => "typeHierarchy/subtypes",
+        }
+    }
+
+    /// The other direction of the same hierarchy
+    pub fn reversed(self) -> Self {
+        match self {
+            Self::IncomingCalls => Self::OutgoingCalls,
+            Self::OutgoingCalls => Self::IncomingCalls,
+            Self::Supertypes => Self::Subtypes,
+            Self::Subtypes => Self::Supertypes,
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::IncomingCalls => "Incoming calls",
+            Self::OutgoingCalls => "Outgoing calls",
+            Self::Supertypes => "Supertypes",
+            Self::Subtypes => "Subtypes",
+        }
+    }
+}
+
+/// A call or type hierarchy item
+#[derive(Debug, Clone)]
+pub struct HierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    pub uri: Uri,
+    /// Where the item is shown: the call for an incoming call, else the
+    /// item's name
+    pub target: Position,
+    /// The item as the server sent it, for requesting its children
+    pub raw: Value,
+}
+
+/// The fields of an item that are shown. Parsed by hand rather than as
+/// `lsp_types::TypeHierarchyItem`, whose `tags` isn't the list servers send.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ItemFields {
+    name: String,
+    kind: SymbolKind,
+    #[serde(default)]
+    detail: Option<String>,
+    uri: Uri,
+    selection_range: Range,
+}
+
+/// A `callHierarchy/incomingCalls` or `outgoingCalls` result
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Call {
+    #[serde(alias = "to")]
+    from: Value,
+    #[serde(default)]
+    from_ranges: Vec<Range>,
+}
+
+impl HierarchyItem {
+    fn parse(raw: Value) -> Result<Self, String> {
+        let fields = ItemFields::deserialize(&raw)
+            .map_err(|e| format!("Failed to parse hierarchy item: {}", e))?;
+        Ok(Self {
+            name: fields.name,
+            kind: fields.kind,
+            detail: fields.detail.filter(|detail| !detail.is_empty()),
+            uri: fields.uri,
+            target: fields.selection_range.start,
+            raw,
+        })
+    }
+}
+
+/// Parse the items of a prepare request, or the children of an item
+/// requested in `direction`. A null result has no items.
+pub fn parse_items(
+    direction: Option<HierarchyDirection>,
+    result: Value,
+) -> Result<Vec<HierarchyItem>, String> {
+    if result.is_null() {
+        return Ok(Vec::new());
+    }
+    let values: Vec<Value> = serde_json::from_value(result)
+        .map_err(|e| format!("Failed to parse hierarchy response: {}", e))?;
+
+    match direction {
+        Some(HierarchyDirection::IncomingCalls | HierarchyDirection::OutgoingCalls) => values
+            .into_iter()
+            .map(|value| {
+                let call: Call = serde_json::from_value(value)
+                    .map_err(|e| format!("Failed to parse call: {}", e))?;
+                let mut item = HierarchyItem::parse(call.from)?;
+                // The ranges of an incoming call are in the caller; those of
+                // an outgoing call are in the item the calls were asked for
+                if direction == Some(HierarchyDirection::IncomingCalls) {
+                    if let Some(range) = call.from_ranges.first() {
+                        item.target = range.start;
+                    }
+                }
+                Ok(item)
+            })
+            .collect(),
+        _ => values.into_iter().map(HierarchyItem::parse).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn item(name: &str, line: u32) -> Value {
+        json!({
+            "name": name,
+            "kind": 12,
+            "uri": "file:///src/main.rs",
+            "range": { "start": { "line": line, "character": 0 }, "end": { "line": line + 2, "character": 1 } },
+            "selectionRange": { "start": { "line": line, "character": 3 }, "end": { "line": line, "character": 7 } },
+            "tags": [1],
+            "data": { "id": 42 }
+        })
+    }
+
+    #[test]
+    fn test_parse_items_keeps_raw_item() {
+        let items = parse_items(None, json!([item("main", 4)])).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "main");
+        assert_eq!(items[0].kind, SymbolKind::FUNCTION);
+        assert_eq!(items[0].target, Position::new(4, 3));
+        assert_eq!(items[0].raw["data"]["id"], 42);
+
+        assert!(parse_items(None, Value::Null).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_calls_target_call_sites() {
+        let call = json!([{
+            "from": item("caller", 10),
+            "fromRanges": [{ "start": { "line": 11, "character": 4 }, "end": { "line": 11, "character": 8 } }]
+        }]);
+        let incoming = parse_items(Some(HierarchyDirection::IncomingCalls), call).unwrap();
+        assert_eq!(incoming[0].name, "caller");
+        assert_eq!(incoming[0].target, Position::new(11, 4));
+
+        let call = json!([{
+            "to": item("callee", 20),
+            "fromRanges": [{ "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 8 } }]
+        }]);
+        let outgoing = parse_items(Some(HierarchyDirection::OutgoingCalls), call).unwrap();
+        assert_eq!(outgoing[0].name, "callee");
+        assert_eq!(outgoing[0].target, Position::new(20, 3));
+    }
+}
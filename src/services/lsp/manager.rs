@@ -1,16 +1,19 @@
 //! LSP Manager - manages multiple language servers using async I/O
 //!
 //! This module provides a manager for multiple LSP servers that:
-//! - Spawns one server per language
+//! - Spawns the servers configured for each language
 //! - Uses async LspHandle for non-blocking I/O
-//! - Routes requests to appropriate servers
+//! - Routes each request to a server providing the feature
+//! - Starts servers in the workspace root of each file (see `root_markers`)
 //! - Configured via config.json
 
 use crate::services::async_bridge::AsyncBridge;
 use crate::services::lsp::async_handler::LspHandle;
-use crate::services::lsp::client::LspServerConfig;
+use crate::services::lsp::client::{LspServerConfig, LspServers};
+use crate::services::lsp::features::LspFeature;
 use lsp_types::Uri;
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 /// Result of attempting to spawn an LSP server
@@ -30,17 +33,47 @@ const MAX_RESTARTS_IN_WINDOW: usize = 5;
 const RESTART_WINDOW_SECS: u64 = 180; // 3 minutes
 const RESTART_BACKOFF_BASE_MS: u64 = 1000; // 1s, 2s, 4s, 8s...
 
+/// A running language server
+struct LspServer {
+    config: LspServerConfig,
+    /// Workspace root the server was started in
+    root: Option<Uri>,
+    /// Workspace folders added to the server since it started
+    folders: Vec<Uri>,
+    handle: LspHandle,
+}
+
+impl LspServer {
+    /// Whether the server has `root` as a workspace folder
+    fn serves(&self, root: &Option<Uri>) -> bool {
+        self.root == *root
+            || root
+                .as_ref()
+                .is_some_and(|root| self.folders.contains(root))
+    }
+}
+
+/// The server (by name) and workspace root a file's requests go to
+type Target = (String, Option<Uri>);
+
 /// Manager for multiple language servers (async version)
 pub struct LspManager {
-    /// Map from language ID to LSP handle
-    handles: HashMap<String, LspHandle>,
+    /// Map from language ID to its running servers, in config order (a
+    /// server can run once per workspace root)
+    handles: HashMap<String, Vec<LspServer>>,
 
     /// Configuration for each language
-    config: HashMap<String, LspServerConfig>,
+    config: HashMap<String, LspServers>,
 
-    /// Root URI for workspace
+    /// Root URI for workspace, used for files without a root marker
     root_uri: Option<Uri>,
 
+    /// Workspace roots found for directories, by root markers
+    root_cache: HashMap<(Vec<String>, PathBuf), Option<PathBuf>>,
+
+    /// Workspace roots to start again when a language's servers restart
+    restart_roots: HashMap<String, Vec<Target>>,
+
     /// Tokio runtime reference
     runtime: Option<tokio::runtime::Handle>,
 
@@ -72,6 +105,8 @@ impl LspManager {
             handles: HashMap::new(),
             config: HashMap::new(),
             root_uri,
+            root_cache: HashMap::new(),
+            restart_roots: HashMap::new(),
             runtime: None,
             async_bridge: None,
             restart_attempts: HashMap::new(),
@@ -99,7 +134,7 @@ impl LspManager {
     }
 
     /// Get the configuration for a specific language
-    pub fn get_config(&self, language: &str) -> Option<&LspServerConfig> {
+    pub fn get_config(&self, language: &str) -> Option<&LspServers> {
         self.config.get(language)
     }
 
@@ -110,15 +145,21 @@ impl LspManager {
     /// - `LspSpawnResult::Spawned` if the server was spawned or already running
     /// - `LspSpawnResult::NotAutoStart` if auto_start is false and not manually allowed
     /// - `LspSpawnResult::Failed` if spawn failed or language is disabled
-    pub fn try_spawn(&mut self, language: &str) -> LspSpawnResult {
+    ///
+    /// Servers are started in the workspace root of `path` (see `get_or_spawn`).
+    pub fn try_spawn(&mut self, language: &str, path: Option<&Path>) -> LspSpawnResult {
         // If handle already exists, return success
         if self.handles.contains_key(language) {
-            return LspSpawnResult::Spawned;
+            return if self.get_or_spawn(language, path).is_some() {
+                LspSpawnResult::Spawned
+            } else {
+                LspSpawnResult::Failed
+            };
         }
 
-        // Check if language is configured and enabled
-        let config = match self.config.get(language) {
-            Some(c) if c.enabled => c,
+        // Check if language is configured and has an enabled server
+        let servers = match self.config.get(language) {
+            Some(servers) if servers.iter().any(|s| s.enabled) => servers,
             Some(_) => return LspSpawnResult::Failed, // Disabled
             None => return LspSpawnResult::Failed,    // Not configured
         };
@@ -129,12 +170,14 @@ impl LspManager {
         }
 
         // Check if auto_start is enabled or language was manually allowed
-        if !config.auto_start && !self.allowed_languages.contains(language) {
+        if !servers.iter().any(|s| s.enabled && s.auto_start)
+            && !self.allowed_languages.contains(language)
+        {
             return LspSpawnResult::NotAutoStart;
         }
 
         // Spawn the server
-        if self.get_or_spawn(language).is_some() {
+        if self.get_or_spawn(language, path).is_some() {
             LspSpawnResult::Spawned
         } else {
             LspSpawnResult::Failed
@@ -150,7 +193,35 @@ impl LspManager {
     }
 
     /// Set configuration for a language
-    pub fn set_language_config(&mut self, language: String, config: LspServerConfig) {
+    ///
+    /// Running servers get their new workspace settings right away; other
+    /// changes (command, arguments, ...) take effect when they restart.
+    pub fn set_language_config(&mut self, language: String, config: LspServers) {
+        self.root_cache.clear();
+        if let Some(servers) = self.handles.get_mut(&language) {
+            for server in servers.iter_mut() {
+                let Some(new_config) = config
+                    .iter()
+                    .find(|c| c.display_name() == server.handle.server())
+                else {
+                    continue;
+                };
+                // A reload that leaves a server's settings alone sends it nothing
+                if server.config.settings == new_config.settings {
+                    server.config = new_config.clone();
+                    continue;
+                }
+                if let Err(e) = server.handle.update_settings(new_config.settings.clone()) {
+                    tracing::warn!(
+                        "Failed to update settings of {} ({}): {}",
+                        language,
+                        server.handle.server(),
+                        e
+                    );
+                }
+                server.config = new_config.clone();
+            }
+        }
         self.config.insert(language, config);
     }
 
@@ -160,6 +231,7 @@ impl LspManager {
     /// Servers spawned after this will use the new root URI.
     pub fn set_root_uri(&mut self, root_uri: Option<Uri>) {
         self.root_uri = root_uri;
+        self.root_cache.clear();
     }
 
     /// Reset the manager for a new project
@@ -172,8 +244,10 @@ impl LspManager {
 
         // Update root URI
         self.root_uri = new_root_uri;
+        self.root_cache.clear();
 
         // Clear restart tracking state (fresh start)
+        self.restart_roots.clear();
         self.restart_attempts.clear();
         self.restart_cooldown.clear();
         self.pending_restarts.clear();
@@ -187,12 +261,154 @@ impl LspManager {
         );
     }
 
-    /// Get or spawn an LSP handle for a language
-    pub fn get_or_spawn(&mut self, language: &str) -> Option<&mut LspHandle> {
-        // Return existing handle if available
-        if self.handles.contains_key(language) {
-            return self.handles.get_mut(language);
+    /// Get or spawn the LSP handle of a language's first server, which also
+    /// gets requests that aren't routed by feature (e.g. from plugins)
+    ///
+    /// `path` is the file the request is about; servers are started (or
+    /// given a workspace folder) for its workspace root. Without one, any
+    /// running instance of a server is used.
+    pub fn get_or_spawn(&mut self, language: &str, path: Option<&Path>) -> Option<&mut LspHandle> {
+        let targets = self.spawn_missing(language, path);
+        let servers = self.handles.get_mut(language)?;
+        let index = *serving(servers, &targets).first()?;
+        Some(&mut servers[index].handle)
+    }
+
+    /// Get or spawn the LSP handles of all of a language's servers (for
+    /// document notifications, which every server needs)
+    pub fn get_or_spawn_all(&mut self, language: &str, path: Option<&Path>) -> Vec<&mut LspHandle> {
+        let targets = self.spawn_missing(language, path);
+        let Some(servers) = self.handles.get_mut(language) else {
+            return Vec::new();
+        };
+        let indexes = serving(servers, &targets);
+        select(servers, &indexes)
+    }
+
+    /// The running LSP handles serving `path`, without spawning any (for
+    /// notifications like didClose that only concern servers already open)
+    pub fn get_running_all(&mut self, language: &str, path: &Path) -> Vec<&mut LspHandle> {
+        let targets = self.targets(language, Some(path));
+        let Some(servers) = self.handles.get_mut(language) else {
+            return Vec::new();
+        };
+        let indexes = serving(servers, &targets);
+        select(servers, &indexes)
+    }
+
+    /// Get or spawn the LSP handle to send a `feature` request to
+    pub fn get_or_spawn_for(
+        &mut self,
+        language: &str,
+        path: Option<&Path>,
+        feature: LspFeature,
+    ) -> Option<&mut LspHandle> {
+        let targets = self.spawn_missing(language, path);
+        let servers = self.handles.get_mut(language)?;
+        let serving = serving(servers, &targets);
+        let index = route(servers, feature)
+            .into_iter()
+            .find(|index| serving.contains(index))?;
+        Some(&mut servers[index].handle)
+    }
+
+    /// Get or spawn the LSP handles of every server providing `feature`, for
+    /// features whose results are merged (completions, diagnostics)
+    pub fn get_or_spawn_all_for(
+        &mut self,
+        language: &str,
+        path: Option<&Path>,
+        feature: LspFeature,
+    ) -> Vec<&mut LspHandle> {
+        let targets = self.spawn_missing(language, path);
+        let Some(servers) = self.handles.get_mut(language) else {
+            return Vec::new();
+        };
+        let serving = serving(servers, &targets);
+        let indexes: Vec<usize> = route(servers, feature)
+            .into_iter()
+            .filter(|index| serving.contains(index))
+            .collect();
+        select(servers, &indexes)
+    }
+
+    /// The workspace root a file is attached to: that of the first enabled
+    /// server of its language
+    pub fn root_for(&mut self, language: &str, path: &Path) -> Option<PathBuf> {
+        let config = self
+            .config
+            .get(language)?
+            .iter()
+            .find(|c| c.enabled)?
+            .clone();
+        self.resolve_root(&config, path)
+            .as_ref()
+            .and_then(uri_to_path)
+    }
+
+    /// Whether a server's config lets it be used for `feature` (servers that
+    /// aren't configured, e.g. ones started by plugins, are allowed anything)
+    pub fn allows_feature(&self, language: &str, server: &str, feature: LspFeature) -> bool {
+        self.config
+            .get(language)
+            .and_then(|servers| servers.iter().find(|s| s.display_name() == server))
+            .is_none_or(|config| config.allows_feature(feature))
+    }
+
+    /// The workspace root `config`'s server serves `path` from: the nearest
+    /// directory with one of its root markers, else the working directory
+    fn resolve_root(&mut self, config: &LspServerConfig, path: &Path) -> Option<Uri> {
+        let workspace = self.root_uri.as_ref().and_then(uri_to_path);
+        let (Some(workspace), Some(dir)) = (workspace, path.parent()) else {
+            return self.root_uri.clone();
+        };
+        if config.root_markers.is_empty() {
+            return self.root_uri.clone();
         }
+        let key = (config.root_markers.clone(), dir.to_path_buf());
+        let root = match self.root_cache.get(&key) {
+            Some(root) => root.clone(),
+            None => {
+                let root = find_root(path, &config.root_markers, &workspace);
+                self.root_cache.insert(key, root.clone());
+                root
+            }
+        };
+        match root {
+            Some(root) => path_to_uri(&root),
+            None => self.root_uri.clone(),
+        }
+    }
+
+    /// The server and workspace root each enabled server of a language
+    /// should serve `path` from
+    fn targets(&mut self, language: &str, path: Option<&Path>) -> Vec<Target> {
+        let Some(configs) = self.config.get(language) else {
+            return Vec::new();
+        };
+        let configs: Vec<LspServerConfig> = configs.iter().filter(|c| c.enabled).cloned().collect();
+        configs
+            .iter()
+            .map(|config| {
+                let name = config.display_name();
+                let root = match path {
+                    Some(path) => self.resolve_root(config, path),
+                    None => self
+                        .handles
+                        .get(language)
+                        .and_then(|servers| servers.iter().find(|s| s.handle.server() == name))
+                        .map(|server| server.root.clone())
+                        .unwrap_or_else(|| self.root_uri.clone()),
+                };
+                (name, root)
+            })
+            .collect()
+    }
+
+    /// Spawn the enabled servers of a language that aren't running for the
+    /// workspace root of `path`, returning the servers and roots to use
+    fn spawn_missing(&mut self, language: &str, path: Option<&Path>) -> Vec<Target> {
+        let targets = self.targets(language, path);
 
         // Check if language was explicitly disabled by user (via stop command)
         // Don't auto-spawn disabled languages
@@ -201,63 +417,171 @@ impl LspManager {
                 "LSP for {} is disabled, not spawning (use manual restart to re-enable)",
                 language
             );
-            return None;
+            return targets;
         }
+        self.spawn_targets(language, &targets);
+        targets
+    }
 
-        // Get config for this language
-        let config = self.config.get(language)?;
-
-        if !config.enabled {
-            return None;
-        }
+    /// Spawn the servers of `targets` that don't serve their root yet, or add
+    /// the root as a workspace folder to a running instance that supports it
+    fn spawn_targets(&mut self, language: &str, targets: &[Target]) {
+        let Some(configs) = self.config.get(language) else {
+            return;
+        };
 
         // Check we have runtime and bridge
-        let runtime = self.runtime.as_ref()?;
-        let async_bridge = self.async_bridge.as_ref()?;
-
-        // Spawn new handle
-        tracing::info!("Spawning async LSP server for language: {}", language);
-
-        match LspHandle::spawn(
-            runtime,
-            &config.command,
-            &config.args,
-            language.to_string(),
-            async_bridge,
-            config.process_limits.clone(),
-        ) {
-            Ok(handle) => {
-                // Initialize the handle (non-blocking)
-                // The handle will become ready asynchronously
-                if let Err(e) =
-                    handle.initialize(self.root_uri.clone(), config.initialization_options.clone())
-                {
-                    tracing::error!("Failed to send initialize command for {}: {}", language, e);
-                    return None;
+        let (Some(runtime), Some(async_bridge)) = (&self.runtime, &self.async_bridge) else {
+            return;
+        };
+
+        let running = self.handles.entry(language.to_string()).or_default();
+        for (name, root) in targets {
+            let Some(config) = configs.iter().find(|c| c.display_name() == *name) else {
+                continue;
+            };
+            if running
+                .iter()
+                .any(|s| s.handle.server() == name && s.serves(root))
+            {
+                continue;
+            }
+
+            // Servers supporting several workspace folders serve all roots
+            if let Some(root) = root {
+                if let Some(server) = running.iter_mut().find(|s| {
+                    s.handle.server() == name && s.handle.supports_workspace_folders() == Some(true)
+                }) {
+                    tracing::info!(
+                        "Adding workspace folder {} to LSP server {} for {}",
+                        root.as_str(),
+                        name,
+                        language
+                    );
+                    match server.handle.add_workspace_folder(root) {
+                        Ok(()) => server.folders.push(root.clone()),
+                        Err(e) => tracing::warn!("Failed to add workspace folder: {}", e),
+                    }
+                    continue;
                 }
+            }
 
-                tracing::info!(
-                    "LSP initialization started for {}, will be ready asynchronously",
-                    language
-                );
-                self.handles.insert(language.to_string(), handle);
-                self.handles.get_mut(language)
+            // Spawn new handle
+            tracing::info!(
+                "Spawning async LSP server {} for language: {} (root: {:?})",
+                name,
+                language,
+                root.as_ref().map(|u| u.as_str())
+            );
+
+            match LspHandle::spawn(
+                runtime,
+                &config.command,
+                &config.args,
+                language.to_string(),
+                name.clone(),
+                root.clone(),
+                async_bridge,
+                config.process_limits.clone(),
+            ) {
+                Ok(handle) => {
+                    // Settings are served when the server asks for them, which
+                    // it may do while initializing
+                    let _ = handle.update_settings(config.settings.clone());
+
+                    // Initialize the handle (non-blocking)
+                    // The handle will become ready asynchronously
+                    if let Err(e) =
+                        handle.initialize(root.clone(), config.initialization_options.clone())
+                    {
+                        tracing::error!(
+                            "Failed to send initialize command for {} ({}): {}",
+                            language,
+                            name,
+                            e
+                        );
+                        continue;
+                    }
+
+                    tracing::info!(
+                        "LSP initialization started for {} ({}), will be ready asynchronously",
+                        language,
+                        name
+                    );
+                    running.push(LspServer {
+                        config: config.clone(),
+                        root: root.clone(),
+                        folders: Vec::new(),
+                        handle,
+                    });
+                }
+                Err(e) => {
+                    tracing::error!(
+                        "Failed to spawn LSP handle for {} ({}): {}",
+                        language,
+                        name,
+                        e
+                    );
+                }
             }
-            Err(e) => {
-                tracing::error!("Failed to spawn LSP handle for {}: {}", language, e);
-                None
+        }
+
+        // A restarted server goes back to its place in the preference order
+        running.sort_by_key(|s| {
+            configs
+                .iter()
+                .position(|c| c.display_name() == s.handle.server())
+        });
+        if running.is_empty() {
+            self.handles.remove(language);
+        }
+    }
+
+    /// Stop a language's servers, remembering their workspace roots so that a
+    /// restart starts them in the same roots
+    fn remove_servers(&mut self, language: &str, server: Option<&str>) {
+        let Some(servers) = self.handles.get_mut(language) else {
+            return;
+        };
+        let (removed, kept): (Vec<LspServer>, Vec<LspServer>) = std::mem::take(servers)
+            .into_iter()
+            .partition(|s| server.is_none_or(|name| s.handle.server() == name));
+        *servers = kept;
+        if servers.is_empty() {
+            self.handles.remove(language);
+        }
+
+        let roots = self.restart_roots.entry(language.to_string()).or_default();
+        for server in removed {
+            let name = server.handle.server().to_string();
+            let _ = server.handle.shutdown(); // Best-effort cleanup
+            for root in std::iter::once(server.root).chain(server.folders.into_iter().map(Some)) {
+                let target = (name.clone(), root);
+                if !roots.contains(&target) {
+                    roots.push(target);
+                }
             }
         }
     }
 
+    /// Start a language's servers again, in the workspace roots they served
+    /// (or the working directory). Returns whether any server is running.
+    fn restart(&mut self, language: &str) -> bool {
+        let mut targets = self.restart_roots.remove(language).unwrap_or_default();
+        if targets.is_empty() {
+            targets = self.targets(language, None);
+        }
+        self.spawn_targets(language, &targets);
+        self.handles.contains_key(language)
+    }
+
     /// Handle a server crash by scheduling a restart with exponential backoff
     ///
     /// Returns a message describing the action taken (for UI notification)
-    pub fn handle_server_crash(&mut self, language: &str) -> String {
-        // Remove the crashed handle
-        if let Some(handle) = self.handles.remove(language) {
-            let _ = handle.shutdown(); // Best-effort cleanup
-        }
+    pub fn handle_server_crash(&mut self, language: &str, server: &str) -> String {
+        // Remove the crashed handle (every instance of the server, as the
+        // crash report doesn't say which root's crashed)
+        self.remove_servers(language, Some(server));
 
         // Check if server was explicitly disabled by user (via stop command)
         // Don't auto-restart disabled servers
@@ -312,7 +636,8 @@ impl LspManager {
             .insert(language.to_string(), restart_time);
 
         tracing::info!(
-            "LSP server for {} crashed (attempt {}/{}), will restart in {}ms",
+            "LSP server {} for {} crashed (attempt {}/{}), will restart in {}ms",
+            server,
             language,
             attempt_number + 1,
             MAX_RESTARTS_IN_WINDOW,
@@ -320,7 +645,8 @@ impl LspManager {
         );
 
         format!(
-            "LSP server for {} crashed (attempt {}/{}), restarting in {}s...",
+            "LSP server {} for {} crashed (attempt {}/{}), restarting in {}s...",
+            server,
             language,
             attempt_number + 1,
             MAX_RESTARTS_IN_WINDOW,
@@ -353,7 +679,7 @@ impl LspManager {
                 .push(now);
 
             // Attempt to spawn the server
-            if self.get_or_spawn(&language).is_some() {
+            if self.restart(&language) {
                 let message = format!("LSP server for {} restarted successfully", language);
                 tracing::info!("{}", message);
                 results.push((language, true, message));
@@ -401,13 +727,11 @@ impl LspManager {
         // Add to allowed languages so it stays active even if auto_start=false
         self.allowed_languages.insert(language.to_string());
 
-        // Remove existing handle
-        if let Some(handle) = self.handles.remove(language) {
-            let _ = handle.shutdown();
-        }
+        // Remove existing handles
+        self.remove_servers(language, None);
 
-        // Spawn new server
-        if self.get_or_spawn(language).is_some() {
+        // Spawn new servers
+        if self.restart(language) {
             let message = format!("LSP server for {} started", language);
             tracing::info!("{}", message);
             (true, message)
@@ -440,25 +764,26 @@ impl LspManager {
 
     /// Check if an LSP server for a language is running and ready to serve requests
     pub fn is_server_ready(&self, language: &str) -> bool {
-        self.handles
-            .get(language)
-            .map(|handle| handle.state().can_send_requests())
-            .unwrap_or(false)
+        self.handles.get(language).is_some_and(|servers| {
+            servers
+                .iter()
+                .any(|server| server.handle.state().can_send_requests())
+        })
     }
 
-    /// Shutdown a specific language server
+    /// Shutdown the servers of a language
     ///
-    /// This marks the server as disabled, preventing auto-restart until the user
+    /// This marks the language as disabled, preventing auto-restart until the user
     /// explicitly restarts it using the restart command.
     ///
-    /// Returns true if the server was found and shutdown, false otherwise
+    /// Returns true if a server was found and shutdown, false otherwise
     pub fn shutdown_server(&mut self, language: &str) -> bool {
-        if let Some(handle) = self.handles.remove(language) {
+        if self.handles.contains_key(language) {
             tracing::info!(
-                "Shutting down LSP server for {} (disabled until manual restart)",
+                "Shutting down LSP servers for {} (disabled until manual restart)",
                 language
             );
-            let _ = handle.shutdown();
+            self.remove_servers(language, None);
             // Mark as disabled to prevent auto-restart
             self.disabled_languages.insert(language.to_string());
             // Cancel any pending restarts
@@ -477,9 +802,15 @@ impl LspManager {
 
     /// Shutdown all language servers
     pub fn shutdown_all(&mut self) {
-        for (language, handle) in self.handles.iter() {
-            tracing::info!("Shutting down LSP server for {}", language);
-            let _ = handle.shutdown();
+        for (language, servers) in self.handles.iter() {
+            for server in servers {
+                tracing::info!(
+                    "Shutting down LSP server {} for {}",
+                    server.handle.server(),
+                    language
+                );
+                let _ = server.handle.shutdown();
+            }
         }
         self.handles.clear();
     }
@@ -491,21 +822,149 @@ impl Drop for LspManager {
     }
 }
 
-/// Helper function to detect language from file extension using the config's languages section
+/// Indexes of the running servers serving the roots of `targets`
+fn serving(servers: &[LspServer], targets: &[Target]) -> Vec<usize> {
+    servers
+        .iter()
+        .enumerate()
+        .filter(|(_, server)| {
+            targets
+                .iter()
+                .any(|(name, root)| server.handle.server() == name && server.serves(root))
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The handles of the servers at `indexes`, in that order
+fn select<'a>(servers: &'a mut [LspServer], indexes: &[usize]) -> Vec<&'a mut LspHandle> {
+    let mut selected: Vec<(usize, &mut LspHandle)> = servers
+        .iter_mut()
+        .enumerate()
+        .filter(|(index, _)| indexes.contains(index))
+        .map(|(index, server)| (index, &mut server.handle))
+        .collect();
+    selected.sort_by_key(|(index, _)| indexes.iter().position(|i| i == index));
+    selected.into_iter().map(|(_, handle)| handle).collect()
+}
+
+/// The nearest directory containing `file` that has one of `markers` (file
+/// names or globs), looking no higher than `workspace`. `None` for files
+/// outside the workspace or without a marker above them.
+pub fn find_root(file: &Path, markers: &[String], workspace: &Path) -> Option<PathBuf> {
+    if !file.starts_with(workspace) {
+        return None;
+    }
+    file.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(workspace))
+        .find(|dir| markers.iter().any(|marker| has_marker(dir, marker)))
+        .map(Path::to_path_buf)
+}
+
+fn has_marker(dir: &Path, marker: &str) -> bool {
+    if !marker.contains(['*', '?']) {
+        return dir.join(marker).exists();
+    }
+    std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries.flatten().any(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| wildcard_matches(marker.as_bytes(), name.as_bytes()))
+        })
+    })
+}
+
+fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    url::Url::parse(uri.as_str()).ok()?.to_file_path().ok()
+}
+
+fn path_to_uri(path: &Path) -> Option<Uri> {
+    url::Url::from_file_path(path).ok()?.as_str().parse().ok()
+}
+
+/// Indexes of the servers that can take `feature` requests, best first:
+/// those that advertised the feature, then those that haven't initialized
+/// yet. Servers whose config excludes the feature are left out.
+fn route(servers: &[LspServer], feature: LspFeature) -> Vec<usize> {
+    rank_servers(
+        servers
+            .iter()
+            .map(|s| (&s.config, s.handle.advertises(feature))),
+        feature,
+    )
+}
+
+/// `route` for servers given by config and advertised feature
+fn rank_servers<'a>(
+    servers: impl Iterator<Item = (&'a LspServerConfig, Option<bool>)>,
+    feature: LspFeature,
+) -> Vec<usize> {
+    let mut ranked: Vec<(usize, bool)> = servers
+        .enumerate()
+        .filter(|(_, (config, advertised))| {
+            config.allows_feature(feature) && *advertised != Some(false)
+        })
+        .map(|(index, (_, advertised))| (index, advertised.is_none()))
+        .collect();
+    // Stable, so servers keep their config order within each group
+    ranked.sort_by_key(|(_, uninitialized)| *uninitialized);
+    ranked.into_iter().map(|(index, _)| index).collect()
+}
+
+/// Detect a file's language from its path using the config's languages section:
+/// an exact file name first, then a file name glob, then the extension
 pub fn detect_language(
     path: &std::path::Path,
     languages: &std::collections::HashMap<String, crate::config::LanguageConfig>,
 ) -> Option<String> {
-    let extension = path.extension()?.to_str()?;
+    let file_name = path.file_name()?.to_str()?;
+    let find = |matches: &dyn Fn(&crate::config::LanguageConfig) -> bool| {
+        languages
+            .iter()
+            .find(|(_, lang_config)| matches(lang_config))
+            .map(|(language_name, _)| language_name.clone())
+    };
+
+    find(&|lang_config| lang_config.filenames.iter().any(|name| name == file_name))
+        .or_else(|| {
+            find(&|lang_config| {
+                lang_config
+                    .filenames
+                    .iter()
+                    .any(|pattern| filename_pattern_matches(pattern, path))
+            })
+        })
+        .or_else(|| {
+            let extension = path.extension()?.to_str()?;
+            find(&|lang_config| lang_config.extensions.iter().any(|ext| ext == extension))
+        })
+}
 
-    // Search through configured languages for a matching extension
-    for (language_name, lang_config) in languages {
-        if lang_config.extensions.iter().any(|ext| ext == extension) {
-            return Some(language_name.clone());
-        }
-    }
+/// Whether a `filenames` glob (`*` and `?`) matches the end of a path. Patterns
+/// with a slash (e.g. `.github/workflows/*.yml`) match the last components.
+fn filename_pattern_matches(pattern: &str, path: &std::path::Path) -> bool {
+    let components: Vec<&str> = path
+        .components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .collect();
+    let parts: Vec<&str> = pattern.split('/').collect();
+    components.len() >= parts.len()
+        && parts
+            .iter()
+            .rev()
+            .zip(components.iter().rev())
+            .all(|(part, component)| wildcard_matches(part.as_bytes(), component.as_bytes()))
+}
 
-    None
+fn wildcard_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|skip| wildcard_matches(rest, &text[skip..])),
+        Some((b'?', rest)) => !text.is_empty() && wildcard_matches(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && wildcard_matches(rest, &text[1..]),
+    }
 }
 
 #[cfg(test)]
@@ -537,13 +996,14 @@ mod tests {
             process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
             auto_start: false,
             initialization_options: None,
+            ..Default::default()
         };
 
-        manager.set_language_config("rust".to_string(), config);
+        manager.set_language_config("rust".to_string(), config.into());
 
         assert_eq!(manager.config.len(), 1);
         assert!(manager.config.contains_key("rust"));
-        assert!(manager.config.get("rust").unwrap().enabled);
+        assert!(manager.config["rust"][0].enabled);
     }
 
     #[test]
@@ -560,11 +1020,13 @@ mod tests {
                 process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
                 auto_start: false,
                 initialization_options: None,
-            },
+                ..Default::default()
+            }
+            .into(),
         );
 
         // get_or_spawn should return None without runtime
-        let result = manager.get_or_spawn("rust");
+        let result = manager.get_or_spawn("rust", None);
         assert!(result.is_none());
     }
 
@@ -577,7 +1039,7 @@ mod tests {
         manager.set_runtime(rt.handle().clone(), async_bridge);
 
         // get_or_spawn should return None for unconfigured language
-        let result = manager.get_or_spawn("rust");
+        let result = manager.get_or_spawn("rust", None);
         assert!(result.is_none());
     }
 
@@ -599,14 +1061,85 @@ mod tests {
                 process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
                 auto_start: false,
                 initialization_options: None,
-            },
+                ..Default::default()
+            }
+            .into(),
         );
 
         // get_or_spawn should return None for disabled language
-        let result = manager.get_or_spawn("rust");
+        let result = manager.get_or_spawn("rust", None);
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_rank_servers() {
+        let server = |command: &str, only_features: Option<Vec<LspFeature>>| LspServerConfig {
+            enabled: true,
+            command: command.to_string(),
+            args: vec![],
+            process_limits: crate::services::process_limits::ProcessLimits::unlimited(),
+            auto_start: false,
+            initialization_options: None,
+            only_features,
+            ..Default::default()
+        };
+        let pyright = server("pyright-langserver", None);
+        let ruff = server(
+            "ruff",
+            Some(vec![LspFeature::Diagnostics, LspFeature::Formatting]),
+        );
+        let pylsp = server("pylsp", None);
+
+        // Servers that advertise a feature come before ones still starting
+        let ranked = rank_servers(
+            [(&pyright, None), (&ruff, Some(true)), (&pylsp, Some(true))].into_iter(),
+            LspFeature::Formatting,
+        );
+        assert_eq!(ranked, vec![1, 2, 0]);
+
+        // Config allow lists and unadvertised features rule servers out
+        let ranked = rank_servers(
+            [
+                (&pyright, Some(true)),
+                (&ruff, Some(true)),
+                (&pylsp, Some(false)),
+            ]
+            .into_iter(),
+            LspFeature::Hover,
+        );
+        assert_eq!(ranked, vec![0]);
+    }
+
+    #[test]
+    fn test_find_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let workspace = temp.path();
+        let crate_dir = workspace.join("crates").join("core");
+        std::fs::create_dir_all(crate_dir.join("src")).unwrap();
+        std::fs::write(crate_dir.join("Cargo.toml"), "").unwrap();
+        std::fs::create_dir_all(workspace.join("web")).unwrap();
+        std::fs::write(workspace.join("web").join("App.csproj"), "").unwrap();
+
+        let markers = vec!["Cargo.toml".to_string()];
+        let file = crate_dir.join("src").join("lib.rs");
+        assert_eq!(
+            find_root(&file, &markers, workspace),
+            Some(crate_dir.clone())
+        );
+        // No marker up to the workspace
+        let other = workspace.join("scripts").join("build.rs");
+        assert_eq!(find_root(&other, &markers, workspace), None);
+        // Files outside the workspace aren't looked up
+        assert_eq!(find_root(&file, &markers, &workspace.join("web")), None);
+
+        let globs = vec!["*.csproj".to_string()];
+        let page = workspace.join("web").join("Page.cs");
+        assert_eq!(
+            find_root(&page, &globs, workspace),
+            Some(workspace.join("web"))
+        );
+    }
+
     #[test]
     fn test_lsp_manager_shutdown_all() {
         let mut manager = LspManager::new(None);
@@ -622,33 +1155,48 @@ mod tests {
             "rust".to_string(),
             crate::config::LanguageConfig {
                 extensions: vec!["rs".to_string()],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "rust".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: 1000,
+                formatters: Vec::new(),
             },
         );
         languages.insert(
             "javascript".to_string(),
             crate::config::LanguageConfig {
                 extensions: vec!["js".to_string(), "jsx".to_string()],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "javascript".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: 1000,
+                formatters: Vec::new(),
             },
         );
         languages.insert(
             "csharp".to_string(),
             crate::config::LanguageConfig {
                 extensions: vec!["cs".to_string()],
+                filenames: Vec::new(),
+                interpreters: Vec::new(),
                 grammar: "c_sharp".to_string(),
                 comment_prefix: Some("//".to_string()),
                 auto_indent: true,
                 highlighter: crate::config::HighlighterPreference::Auto,
                 textmate_grammar: None,
+                format_on_save: false,
+                format_on_save_timeout_ms: 1000,
+                formatters: Vec::new(),
             },
         );
         languages
@@ -688,4 +1236,27 @@ mod tests {
         assert_eq!(detect_language(Path::new("README"), &languages), None);
         assert_eq!(detect_language(Path::new("Makefile"), &languages), None);
     }
+
+    #[test]
+    fn test_detect_language_by_filename() {
+        let languages = crate::config::Config::default().languages;
+        let detect = |path: &str| detect_language(Path::new(path), &languages);
+
+        assert_eq!(detect("Makefile"), Some("makefile".to_string()));
+        assert_eq!(detect("/home/user/.bashrc"), Some("bash".to_string()));
+        assert_eq!(detect("src/PKGBUILD"), Some("bash".to_string()));
+        assert_eq!(detect("Dockerfile.dev"), Some("dockerfile".to_string()));
+        assert_eq!(detect("rules.mk"), Some("makefile".to_string()));
+        assert_eq!(detect("main.py"), Some("python".to_string()));
+        assert_eq!(detect("Makefile.bak"), None);
+
+        assert!(filename_pattern_matches(
+            ".github/workflows/*.yml",
+            Path::new("/repo/.github/workflows/ci.yml")
+        ));
+        assert!(!filename_pattern_matches(
+            ".github/workflows/*.yml",
+            Path::new("/repo/workflows/ci.yml")
+        ));
+    }
 }
@@ -3,4 +3,6 @@
 pub mod async_handler;
 pub mod client;
 pub mod diagnostics;
+pub mod features;
+pub mod hierarchy;
 pub mod manager;
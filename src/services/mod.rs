@@ -5,15 +5,23 @@
 
 pub mod async_bridge;
 pub mod clipboard;
+pub mod editorconfig;
+pub mod file_transfer;
+pub mod formatter;
 pub mod fs;
+pub mod git_status;
 #[cfg(target_os = "linux")]
 pub mod gpm;
+pub mod language_detection;
 pub mod lsp;
 pub mod plugins;
+pub mod privileged_save;
 pub mod process_limits;
 pub mod recovery;
 pub mod release_checker;
 pub mod signal_handler;
 pub mod terminal;
 pub mod tracing_setup;
+pub mod trash;
+pub mod undo_history;
 pub mod warning_log;
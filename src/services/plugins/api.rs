@@ -400,6 +400,23 @@ pub enum PluginCommand {
         namespace: String,
     },
 
+    /// Set a badge on a file in the file explorer, shown instead of its git
+    /// status letter
+    SetFileExplorerDecoration {
+        /// Namespace for grouping (e.g., "diagnostics")
+        namespace: String,
+        path: PathBuf,
+        /// Symbol to display (e.g., "●", "2")
+        symbol: String,
+        /// Color as RGB tuple
+        color: (u8, u8, u8),
+        /// Priority when several namespaces decorate a file (higher wins)
+        priority: i32,
+    },
+
+    /// Clear all file explorer badges of a namespace
+    ClearFileExplorerDecorations { namespace: String },
+
     /// Open a file at a specific line and column
     /// Line and column are 1-indexed to match git grep output
     OpenFileAtLocation {
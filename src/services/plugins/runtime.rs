@@ -845,6 +845,61 @@ fn op_fresh_clear_line_indicators(
     false
 }
 
+/// Set a badge on a file in the file explorer, shown instead of its git status
+/// @param namespace - Namespace for grouping (e.g., "diagnostics")
+/// @param path - File path (absolute or relative to the working directory)
+/// @param symbol - Symbol to display (e.g., "●", "2")
+/// @param r - Red color component (0-255)
+/// @param g - Green color component (0-255)
+/// @param b - Blue color component (0-255)
+/// @param priority - Priority when several namespaces decorate a file (higher wins)
+/// @returns true if the badge was set
+#[op2(fast)]
+#[allow(clippy::too_many_arguments)]
+fn op_fresh_set_file_explorer_decoration(
+    state: &mut OpState,
+    #[string] namespace: String,
+    #[string] path: String,
+    #[string] symbol: String,
+    r: u8,
+    g: u8,
+    b: u8,
+    priority: i32,
+) -> bool {
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        let runtime_state = runtime_state.borrow();
+        let result = runtime_state
+            .command_sender
+            .send(PluginCommand::SetFileExplorerDecoration {
+                namespace,
+                path: std::path::PathBuf::from(path),
+                symbol,
+                color: (r, g, b),
+                priority,
+            });
+        return result.is_ok();
+    }
+    false
+}
+
+/// Clear all file explorer badges of a namespace
+/// @param namespace - Namespace to clear (e.g., "diagnostics")
+/// @returns true if the badges were cleared
+#[op2(fast)]
+fn op_fresh_clear_file_explorer_decorations(
+    state: &mut OpState,
+    #[string] namespace: String,
+) -> bool {
+    if let Some(runtime_state) = state.try_borrow::<Rc<RefCell<TsRuntimeState>>>() {
+        let runtime_state = runtime_state.borrow();
+        let result = runtime_state
+            .command_sender
+            .send(PluginCommand::ClearFileExplorerDecorations { namespace });
+        return result.is_ok();
+    }
+    false
+}
+
 /// Submit a transformed view stream for a viewport
 /// @param buffer_id - Buffer to apply the transform to
 /// @param start - Viewport start byte
@@ -2828,6 +2883,8 @@ extension!(
         op_fresh_refresh_lines,
         op_fresh_set_line_indicator,
         op_fresh_clear_line_indicators,
+        op_fresh_set_file_explorer_decoration,
+        op_fresh_clear_file_explorer_decorations,
         op_fresh_insert_at_cursor,
         op_fresh_register_command,
         op_fresh_unregister_command,
@@ -3090,6 +3147,14 @@ impl TypeScriptRuntime {
                         return core.ops.op_fresh_clear_line_indicators(bufferId, namespace);
                     },
 
+                    // File explorer badges
+                    setFileExplorerDecoration(namespace, path, symbol, r, g, b, priority) {
+                        return core.ops.op_fresh_set_file_explorer_decoration(namespace, path, symbol, r, g, b, priority);
+                    },
+                    clearFileExplorerDecorations(namespace) {
+                        return core.ops.op_fresh_clear_file_explorer_decorations(namespace);
+                    },
+
                     // Convenience
                     insertAtCursor(text) {
                         return core.ops.op_fresh_insert_at_cursor(text);
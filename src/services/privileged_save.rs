@@ -0,0 +1,221 @@
+//! Writing files with elevated privileges ("Save as Root")
+//!
+//! The buffer content is written to a private temporary file, which is then
+//! handed to a configurable helper command (e.g. `sudo tee {path}`,
+//! `doas tee {path}`, `pkexec cp {temp} {path}`) on stdin. The helper writes
+//! the target file in place, so its ownership and permissions are kept.
+//!
+//! Password-reading helpers would otherwise take over the terminal, so for
+//! `sudo` the editor asks for the password itself: credentials are validated
+//! with `sudo -S -v` (password on stdin), and the helper then runs with
+//! `sudo -n` so it never prompts. The password and the file content never
+//! share a stdin.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Placeholder for the target file in the helper command
+pub const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Placeholder for the temporary file holding the new content
+pub const TEMP_PLACEHOLDER: &str = "{temp}";
+
+/// Whether the helper command is run through sudo (whose password the editor asks for)
+pub fn uses_sudo(command: &[String]) -> bool {
+    command
+        .first()
+        .and_then(|program| Path::new(program).file_name())
+        .is_some_and(|name| name == "sudo")
+}
+
+/// Whether running the helper command needs a password first
+/// (sudo without cached credentials or NOPASSWD)
+pub fn needs_password(command: &[String]) -> bool {
+    uses_sudo(command)
+        && !Command::new(&command[0])
+            .args(["-n", "true"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+}
+
+/// Validate sudo credentials with the given password, caching them for the helper
+pub fn authenticate(command: &[String], password: &str) -> io::Result<()> {
+    let mut child = Command::new(&command[0])
+        .args(["-S", "-p", "", "-v"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // sudo may exit before reading; a broken pipe is reported via the status
+        let _ = writeln!(stdin, "{}", password);
+    }
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "authentication failed",
+        ))
+    }
+}
+
+/// Build the helper command line for a target and temp file. sudo is made
+/// non-interactive so it can never prompt on the editor's terminal.
+fn helper_args(command: &[String], target: &Path, temp: &Path) -> io::Result<Vec<String>> {
+    if command.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "privileged_save_command is empty",
+        ));
+    }
+    let mut args: Vec<String> = command
+        .iter()
+        .map(|arg| {
+            arg.replace(PATH_PLACEHOLDER, &target.to_string_lossy())
+                .replace(TEMP_PLACEHOLDER, &temp.to_string_lossy())
+        })
+        .collect();
+    if uses_sudo(command) && !args.iter().any(|arg| arg == "-n") {
+        args.insert(1, "-n".to_string());
+    }
+    Ok(args)
+}
+
+/// A private temporary file, removed when dropped
+struct TempContent(PathBuf);
+
+impl TempContent {
+    fn create<F>(write_content: F) -> io::Result<(Self, File)>
+    where
+        F: FnOnce(&mut File) -> io::Result<()>,
+    {
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let name = format!(
+            "fresh-save-{}-{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or_default()
+        );
+        let temp = Self(std::env::temp_dir().join(name));
+        let mut file = options.open(&temp.0)?;
+        write_content(&mut file)?;
+        file.sync_all()?;
+        let content = File::open(&temp.0)?;
+        Ok((temp, content))
+    }
+}
+
+impl Drop for TempContent {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Write `target` through the helper command. The content produced by
+/// `write_content` is staged in a temporary file first, so unloaded regions of
+/// large files are read before the target is touched.
+pub fn write_privileged<F>(command: &[String], target: &Path, write_content: F) -> io::Result<()>
+where
+    F: FnOnce(&mut File) -> io::Result<()>,
+{
+    let (temp, content) = TempContent::create(write_content)?;
+    let args = helper_args(command, target, &temp.0)?;
+
+    let output = Command::new(&args[0])
+        .args(&args[1..])
+        .stdin(content)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = stderr
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.trim().to_string())
+        .unwrap_or_else(|| format!("{} exited with {}", args[0], output.status));
+    Err(io::Error::other(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_helper_args_substitutes_placeholders() {
+        let args = helper_args(
+            &command(&["pkexec", "cp", "{temp}", "{path}"]),
+            Path::new("/etc/hosts"),
+            Path::new("/tmp/content"),
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            command(&["pkexec", "cp", "/tmp/content", "/etc/hosts"])
+        );
+    }
+
+    #[test]
+    fn test_helper_args_makes_sudo_non_interactive() {
+        let target = Path::new("/etc/hosts");
+        let temp = Path::new("/tmp/content");
+        let args = helper_args(&command(&["sudo", "tee", "{path}"]), target, temp).unwrap();
+        assert_eq!(args, command(&["sudo", "-n", "tee", "/etc/hosts"]));
+
+        assert!(helper_args(&[], target, temp).is_err());
+        assert!(!uses_sudo(&command(&["doas", "tee", "{path}"])));
+        assert!(uses_sudo(&command(&["/usr/bin/sudo", "tee", "{path}"])));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_privileged_with_plain_helpers() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("config.txt");
+        fs::write(&target, "old").unwrap();
+
+        // Content on stdin
+        write_privileged(&command(&["tee", "{path}"]), &target, |out| {
+            out.write_all(b"via stdin\n")
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "via stdin\n");
+
+        // Content from the temp file
+        write_privileged(&command(&["cp", "{temp}", "{path}"]), &target, |out| {
+            out.write_all(b"via temp\n")
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "via temp\n");
+
+        // Helper failures are reported with their stderr
+        let missing = dir.path().join("missing").join("config.txt");
+        let err = write_privileged(&command(&["cp", "{temp}", "{path}"]), &missing, |out| {
+            out.write_all(b"x")
+        })
+        .unwrap_err();
+        assert!(err.to_string().contains("cp"));
+    }
+}
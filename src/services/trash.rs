@@ -0,0 +1,313 @@
+//! Moving files to the trash and restoring them
+//!
+//! Implements the freedesktop.org Trash specification: a trashed file is moved
+//! into `files/` and described by a `.trashinfo` file in `info/` holding its
+//! original path and deletion date, so file managers can list and restore it
+//! too. Files go to the home trash (`$XDG_DATA_HOME/Trash`, usually
+//! `~/.local/share/Trash`) when they are on its file system, and otherwise to
+//! the trash at the top of their own file system, `$topdir/.Trash/$uid` or
+//! `$topdir/.Trash-$uid`, so trashing never copies. The home trash is
+//! `DirectoryContext::trash_dir`, which only exists on platforms following the
+//! specification.
+
+use crate::services::file_transfer::{self, TransferMode};
+use std::ffi::OsString;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A file or directory in the trash
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedItem {
+    /// Where it was deleted from
+    pub original_path: PathBuf,
+    /// Where it is in the trash's `files/` directory
+    pub trashed_path: PathBuf,
+    info_path: PathBuf,
+}
+
+/// A trash directory
+#[derive(Debug, Clone)]
+pub struct Trash {
+    root: PathBuf,
+    /// The top directory of the file system of a trash outside the home
+    /// directory; the paths it records are relative to it
+    topdir: Option<PathBuf>,
+}
+
+impl Trash {
+    /// The home trash in `root` (containing `files/` and `info/`)
+    pub fn new(root: PathBuf) -> Self {
+        Self { root, topdir: None }
+    }
+
+    /// The trash of the file system whose top directory is `topdir`: its
+    /// shared `.Trash/$uid` if the administrator set one up (a sticky
+    /// directory that isn't a link), else `.Trash-$uid`
+    #[cfg(unix)]
+    fn in_topdir(topdir: &Path) -> io::Result<Self> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+        let uid = unsafe { libc::getuid() };
+        let shared = topdir.join(".Trash");
+        let shared_usable = std::fs::symlink_metadata(&shared)
+            .map(|m| m.is_dir() && m.permissions().mode() & 0o1000 != 0)
+            .unwrap_or(false);
+        let candidates = shared_usable
+            .then(|| shared.join(uid.to_string()))
+            .into_iter()
+            .chain(std::iter::once(topdir.join(format!(".Trash-{}", uid))));
+
+        let mut last_error = None;
+        for root in candidates {
+            let created = std::fs::DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(&root);
+            match created.and_then(|_| std::fs::symlink_metadata(&root)) {
+                Ok(m) if m.is_dir() => {
+                    return Ok(Self {
+                        root,
+                        topdir: Some(topdir.to_path_buf()),
+                    })
+                }
+                Ok(_) => {}
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::other(format!("No usable trash in {}", topdir.display()))
+        }))
+    }
+
+    #[cfg(not(unix))]
+    fn in_topdir(topdir: &Path) -> io::Result<Self> {
+        Err(io::Error::other(format!(
+            "No trash in {}",
+            topdir.display()
+        )))
+    }
+
+    /// Move `path` to the trash of its file system
+    pub fn trash(&self, path: &Path) -> io::Result<TrashedItem> {
+        let original_path = std::path::absolute(path)?;
+        if let Some(topdir) = self.other_file_system_topdir(&original_path) {
+            // A file system without a usable trash falls back to copying into
+            // the home trash
+            match Self::in_topdir(&topdir) {
+                Ok(trash) => return trash.trash_absolute(original_path),
+                Err(e) => tracing::warn!(
+                    "Cannot use the trash of {}, using the home trash: {}",
+                    topdir.display(),
+                    e
+                ),
+            }
+        }
+        self.trash_absolute(original_path)
+    }
+
+    /// The top directory of `path`'s file system if it isn't the file system
+    /// of this trash
+    #[cfg(unix)]
+    fn other_file_system_topdir(&self, path: &Path) -> Option<PathBuf> {
+        let parent = path.parent()?;
+        let parent_device = device(parent)?;
+        if self.topdir.is_some() || device(&self.root)? == parent_device {
+            return None;
+        }
+        let mut topdir = parent;
+        while let Some(up) = topdir.parent() {
+            if device(up) != Some(parent_device) {
+                break;
+            }
+            topdir = up;
+        }
+        Some(topdir.to_path_buf())
+    }
+
+    #[cfg(not(unix))]
+    fn other_file_system_topdir(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
+
+    fn trash_absolute(&self, original_path: PathBuf) -> io::Result<TrashedItem> {
+        let name = original_path.file_name().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Cannot trash {}", original_path.display()),
+            )
+        })?;
+        let files_dir = self.root.join("files");
+        let info_dir = self.root.join("info");
+        std::fs::create_dir_all(&files_dir)?;
+        std::fs::create_dir_all(&info_dir)?;
+
+        let recorded_path = match &self.topdir {
+            Some(topdir) => encode_relative_path(&original_path, topdir),
+            None => encode_path(&original_path),
+        };
+        let info = format!(
+            "[Trash Info]\nPath={}\nDeletionDate={}\n",
+            recorded_path,
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+        );
+        // Creating the info file reserves the name; "a.txt" is taken, try "a.txt.2"
+        for n in 1.. {
+            let mut trashed_name = name.to_os_string();
+            if n > 1 {
+                trashed_name.push(format!(".{}", n));
+            }
+            let info_path = info_dir.join(info_file_name(&trashed_name));
+            let mut info_file = match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            let trashed_path = files_dir.join(&trashed_name);
+            let moved = info_file.write_all(info.as_bytes()).and_then(|_| {
+                file_transfer::transfer(&original_path, &trashed_path, TransferMode::Move, false)
+            });
+            if let Err(e) = moved {
+                let _ = std::fs::remove_file(&info_path);
+                return Err(e);
+            }
+            return Ok(TrashedItem {
+                original_path,
+                trashed_path,
+                info_path,
+            });
+        }
+        unreachable!("some trash name is free")
+    }
+}
+
+impl TrashedItem {
+    /// Put the item back where it was deleted from. Fails if something else
+    /// is there now.
+    pub fn restore(&self) -> io::Result<()> {
+        if let Some(parent) = self.original_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        file_transfer::transfer(
+            &self.trashed_path,
+            &self.original_path,
+            TransferMode::Move,
+            false,
+        )?;
+        std::fs::remove_file(&self.info_path)
+    }
+}
+
+fn info_file_name(trashed_name: &OsString) -> OsString {
+    let mut name = trashed_name.clone();
+    name.push(".trashinfo");
+    name
+}
+
+/// An absolute path with the escaping of a URL path, as the spec requires
+fn encode_path(path: &Path) -> String {
+    url::Url::from_file_path(path)
+        .map(|url| url.path().to_string())
+        .unwrap_or_else(|_| path.to_string_lossy().into_owned())
+}
+
+/// A path relative to `topdir`, escaped like `encode_path`
+fn encode_relative_path(path: &Path, topdir: &Path) -> String {
+    let encoded = encode_path(path);
+    let prefix = encode_path(topdir);
+    encoded
+        .strip_prefix(prefix.trim_end_matches('/'))
+        .and_then(|relative| relative.strip_prefix('/'))
+        .map(str::to_string)
+        .unwrap_or(encoded)
+}
+
+/// The device of the file system `path` is on, or of its nearest existing
+/// ancestor
+#[cfg(unix)]
+fn device(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    path.ancestors()
+        .find_map(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.dev())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trash_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash = Trash::new(dir.path().join("Trash"));
+        let project = dir.path().join("my project");
+        std::fs::create_dir_all(project.join("src")).unwrap();
+        std::fs::write(project.join("src/a.txt"), "a").unwrap();
+
+        let first = trash.trash(&project.join("src")).unwrap();
+        assert!(!project.join("src").exists());
+        assert_eq!(
+            std::fs::read_to_string(first.trashed_path.join("a.txt")).unwrap(),
+            "a"
+        );
+        let info = std::fs::read_to_string(&first.info_path).unwrap();
+        assert!(info.contains("/my%20project/src\n"), "{}", info);
+
+        // A second "src" gets another name in the trash
+        std::fs::create_dir(project.join("src")).unwrap();
+        let second = trash.trash(&project.join("src")).unwrap();
+        assert_eq!(second.trashed_path.file_name().unwrap(), "src.2");
+
+        // Restoring fails while the original path is taken
+        std::fs::create_dir(project.join("src")).unwrap();
+        assert!(first.restore().is_err());
+        std::fs::remove_dir(project.join("src")).unwrap();
+
+        first.restore().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(project.join("src/a.txt")).unwrap(),
+            "a"
+        );
+        assert!(!first.info_path.exists());
+        assert!(second.trashed_path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_topdir_trash() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let topdir = dir.path();
+        let uid = unsafe { libc::getuid() };
+        std::fs::create_dir(topdir.join("project")).unwrap();
+        std::fs::write(topdir.join("project/a b.txt"), "a").unwrap();
+
+        // Without a shared .Trash, the user's own .Trash-$uid
+        let trash = Trash::in_topdir(topdir).unwrap();
+        assert_eq!(trash.root, topdir.join(format!(".Trash-{}", uid)));
+        let item = trash.trash(&topdir.join("project/a b.txt")).unwrap();
+        assert!(item.trashed_path.starts_with(&trash.root));
+        let info = std::fs::read_to_string(&item.info_path).unwrap();
+        assert!(info.contains("\nPath=project/a%20b.txt\n"), "{}", info);
+        item.restore().unwrap();
+        assert!(topdir.join("project/a b.txt").exists());
+
+        // A shared .Trash is only used when it is sticky
+        std::fs::create_dir(topdir.join(".Trash")).unwrap();
+        let trash = Trash::in_topdir(topdir).unwrap();
+        assert_eq!(trash.root, topdir.join(format!(".Trash-{}", uid)));
+
+        std::fs::set_permissions(
+            topdir.join(".Trash"),
+            std::fs::Permissions::from_mode(0o1777),
+        )
+        .unwrap();
+        let trash = Trash::in_topdir(topdir).unwrap();
+        assert_eq!(trash.root, topdir.join(".Trash").join(uid.to_string()));
+    }
+}
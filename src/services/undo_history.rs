@@ -0,0 +1,281 @@
+//! Persistent undo history
+//!
+//! Stores each file's undo tree in the data directory so edits can still be
+//! undone after the buffer is closed or the editor restarts. Files are keyed by
+//! a hash of the file path (like recovery files), and every history records a
+//! hash of the file content it was saved against. A history is only restored
+//! if the file on disk still has that exact content. Large files, which are
+//! never read whole, are identified by their size and modification time
+//! instead.
+
+use crate::model::event::{EventLog, UndoTreeData};
+use crate::services::recovery::types::path_hash;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Version of the on-disk format; histories with another version are ignored
+const FORMAT_VERSION: u32 = 1;
+
+/// On-disk representation of a file's undo history
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedUndoHistory {
+    /// Format version
+    version: u32,
+    /// The file this history belongs to (for diagnostics; lookup is by path hash)
+    file_path: PathBuf,
+    /// SHA-256 of the file content at the saved node (or the size and
+    /// modification time of a large file, see `file_fingerprint`)
+    content_hash: String,
+    /// The undo tree
+    tree: UndoTreeData,
+}
+
+/// Compute the SHA-256 of a file's content, reading it in chunks
+pub fn file_content_hash(path: &Path) -> io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut chunk = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut chunk)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&chunk[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Identify a file's content: by its SHA-256, or for a `large` file by its
+/// size and modification time, so that it isn't read whole
+pub fn file_fingerprint(path: &Path, large: bool) -> io::Result<String> {
+    if !large {
+        return file_content_hash(path);
+    }
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| since.as_nanos())
+        .unwrap_or(0);
+    Ok(format!("size {} modified {}", metadata.len(), modified))
+}
+
+/// Reads and writes per-file undo histories in the data directory
+#[derive(Debug)]
+pub struct UndoHistoryStorage {
+    /// Directory holding one history file per edited file
+    dir: PathBuf,
+
+    /// Content hash the in-memory history of each open file was saved or loaded against
+    content_hashes: HashMap<PathBuf, String>,
+}
+
+impl UndoHistoryStorage {
+    /// Create a storage rooted at the given directory
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            content_hashes: HashMap::new(),
+        }
+    }
+
+    /// Path of the history file for a file path
+    fn history_path(&self, path: &Path) -> PathBuf {
+        self.dir.join(format!("{}.json", path_hash(path)))
+    }
+
+    /// Load the persisted history for a file, if the file content still matches it.
+    /// Stale or unreadable histories are deleted. The file's content hash is
+    /// recorded even when it has no history yet, so the history started now
+    /// can be saved. `large` files are identified by `file_fingerprint`.
+    pub fn load(&mut self, path: &Path, large: bool) -> Option<EventLog> {
+        let current_hash = file_fingerprint(path, large).ok()?;
+        self.content_hashes
+            .insert(path.to_path_buf(), current_hash.clone());
+        let history_path = self.history_path(path);
+        let content = fs::read(&history_path).ok()?;
+
+        let persisted: PersistedUndoHistory = match serde_json::from_slice(&content) {
+            Ok(persisted) => persisted,
+            Err(e) => {
+                tracing::warn!(
+                    "Discarding unreadable undo history {}: {}",
+                    history_path.display(),
+                    e
+                );
+                let _ = fs::remove_file(&history_path);
+                return None;
+            }
+        };
+
+        if persisted.version != FORMAT_VERSION || persisted.content_hash != current_hash {
+            tracing::debug!(
+                "Undo history for {} does not match file content, discarding",
+                path.display()
+            );
+            let _ = fs::remove_file(&history_path);
+            return None;
+        }
+
+        EventLog::from_tree(persisted.tree)
+    }
+
+    /// Record that the file on disk now matches the saved node of its history
+    /// (call after saving the buffer)
+    pub fn record_saved(&mut self, path: &Path, large: bool) -> io::Result<()> {
+        let hash = file_fingerprint(path, large)?;
+        self.content_hashes.insert(path.to_path_buf(), hash);
+        Ok(())
+    }
+
+    /// Forget the content hash of a file (e.g. after reloading it from disk),
+    /// so its current history is not persisted against the wrong content
+    pub fn forget(&mut self, path: &Path) {
+        self.content_hashes.remove(path);
+    }
+
+    /// Persist the history of a file. Does nothing if the file content the
+    /// history's saved node corresponds to is unknown.
+    pub fn save(&self, path: &Path, log: &EventLog, compact: bool) -> io::Result<()> {
+        let Some(content_hash) = self.content_hashes.get(path) else {
+            return Ok(());
+        };
+        let Some(tree) = log.export_tree(compact) else {
+            return Ok(());
+        };
+        if tree.nodes.is_empty() {
+            return Ok(());
+        }
+
+        let persisted = PersistedUndoHistory {
+            version: FORMAT_VERSION,
+            file_path: path.to_path_buf(),
+            content_hash: content_hash.clone(),
+            tree,
+        };
+        let json = serde_json::to_vec(&persisted)?;
+
+        fs::create_dir_all(&self.dir)?;
+        let target = self.history_path(path);
+        let temp_path = target.with_extension("json.tmp");
+        let mut file = File::create(&temp_path)?;
+        file.write_all(&json)?;
+        drop(file);
+        fs::rename(&temp_path, &target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::event::{CursorId, Event};
+
+    fn typed_log(text: &str) -> EventLog {
+        let mut log = EventLog::new();
+        for (i, ch) in text.chars().enumerate() {
+            log.append(Event::Insert {
+                position: i,
+                text: ch.to_string(),
+                cursor_id: CursorId(0),
+            });
+        }
+        log.mark_saved();
+        log
+    }
+
+    #[test]
+    fn test_history_roundtrip() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        fs::write(&file, "abc").unwrap();
+
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        storage.record_saved(&file, false).unwrap();
+        storage.save(&file, &typed_log("abc"), false).unwrap();
+
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        let mut log = storage.load(&file, false).expect("history should load");
+        assert!(log.is_at_saved_position());
+        assert_eq!(log.len(), 3);
+        let events = log.undo();
+        assert!(matches!(&events[0], Event::Delete { deleted_text, .. } if deleted_text == "c"));
+    }
+
+    #[test]
+    fn test_history_discarded_when_file_changed() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        fs::write(&file, "abc").unwrap();
+
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        storage.record_saved(&file, false).unwrap();
+        storage.save(&file, &typed_log("abc"), false).unwrap();
+
+        fs::write(&file, "changed elsewhere").unwrap();
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        assert!(storage.load(&file, false).is_none());
+        assert!(!storage.history_path(&file).exists());
+    }
+
+    #[test]
+    fn test_first_history_saved_after_load() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        fs::write(&file, "abc").unwrap();
+
+        // Opened for the first time: there is no history to load, but the
+        // edits made now are saved when the file is closed
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        assert!(storage.load(&file, false).is_none());
+        let mut log = EventLog::new();
+        log.append(Event::Insert {
+            position: 3,
+            text: "d".to_string(),
+            cursor_id: CursorId(0),
+        });
+        storage.save(&file, &log, false).unwrap();
+        assert!(storage.history_path(&file).exists());
+
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        // The file was closed unsaved, so the edit is there to redo
+        let mut log = storage.load(&file, false).expect("history should load");
+        assert!(log.is_at_saved_position());
+        let events = log.redo();
+        assert!(matches!(&events[0], Event::Insert { text, .. } if text == "d"));
+    }
+
+    #[test]
+    fn test_large_file_identified_by_size_and_time() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("large.log");
+        fs::write(&file, "abc").unwrap();
+
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        storage.record_saved(&file, true).unwrap();
+        // Not read: identified by its size and modification time
+        assert!(storage.content_hashes[&file].starts_with("size 3 modified "));
+        storage.save(&file, &typed_log("abc"), true).unwrap();
+
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        assert!(storage.load(&file, true).is_some());
+
+        fs::write(&file, "abcd").unwrap();
+        let mut storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        assert!(storage.load(&file, true).is_none());
+    }
+
+    #[test]
+    fn test_save_skipped_without_known_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let file = temp.path().join("notes.txt");
+        fs::write(&file, "abc").unwrap();
+
+        let storage = UndoHistoryStorage::new(temp.path().join("undo"));
+        storage.save(&file, &typed_log("abc"), false).unwrap();
+        assert!(!storage.history_path(&file).exists());
+    }
+}
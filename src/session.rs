@@ -155,6 +155,19 @@ pub struct SerializedFileState {
 
     /// Scroll position (byte offset)
     pub scroll: SerializedScroll,
+
+    /// Folded regions
+    #[serde(default)]
+    pub folds: Vec<SerializedFold>,
+}
+
+/// A folded region as line numbers (0-indexed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedFold {
+    /// Header line that stays visible
+    pub start_line: usize,
+    /// Last hidden line (inclusive)
+    pub end_line: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -718,6 +731,10 @@ mod tests {
                 top_view_line_offset: 2,
                 left_column: 10,
             },
+            folds: vec![SerializedFold {
+                start_line: 3,
+                end_line: 7,
+            }],
         };
 
         let json = serde_json::to_string(&file_state).unwrap();
@@ -729,6 +746,13 @@ mod tests {
         assert_eq!(restored.additional_cursors.len(), 1);
         assert_eq!(restored.scroll.top_byte, 500);
         assert_eq!(restored.scroll.left_column, 10);
+        assert_eq!(
+            restored.folds,
+            vec![SerializedFold {
+                start_line: 3,
+                end_line: 7
+            }]
+        );
     }
 
     #[test]
@@ -1,19 +1,26 @@
+use crate::config::LanguageConfig;
 use crate::model::buffer::{Buffer, LineNumber};
 use crate::model::cursor::{Cursor, Cursors};
 use crate::model::document_model::{
     DocumentCapabilities, DocumentModel, DocumentPosition, ViewportContent, ViewportLine,
 };
+use crate::model::encoding::Encoding;
 use crate::model::event::{
     Event, MarginContentData, MarginPositionData, OverlayFace as EventOverlayFace, PopupData,
     PopupPositionData,
 };
 use crate::model::marker::MarkerList;
+use crate::primitives::fold_ranges::{self, FoldRange};
 use crate::primitives::grammar_registry::GrammarRegistry;
 use crate::primitives::highlight_engine::HighlightEngine;
 use crate::primitives::highlighter::Language;
 use crate::primitives::indent::IndentCalculator;
+use crate::primitives::selection_expansion::SelectionExpansion;
 use crate::primitives::semantic_highlight::SemanticHighlighter;
+use crate::primitives::syntax_tree::SyntaxTree;
 use crate::primitives::text_property::TextPropertyManager;
+use crate::primitives::visual_layout::DEFAULT_TAB_WIDTH;
+use crate::view::folding::{FoldManager, FoldRangeSource};
 use crate::view::margin::{MarginAnnotation, MarginContent, MarginManager, MarginPosition};
 use crate::view::overlay::{Overlay, OverlayFace, OverlayManager, UnderlineStyle};
 use crate::view::popup::{Popup, PopupContent, PopupListItem, PopupManager, PopupPosition};
@@ -31,6 +38,49 @@ pub enum ViewMode {
     Compose,
 }
 
+/// Per-buffer overrides of the global editor settings (e.g. from `.editorconfig`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BufferSettings {
+    /// Columns per indentation level (overrides `editor.tab_size`)
+    pub indent_size: Option<usize>,
+    /// Indent with tab characters instead of spaces
+    pub use_tabs: bool,
+    /// Columns a tab character stands for (defaults to the indent size)
+    pub tab_width: Option<usize>,
+    /// Strip trailing whitespace from all lines when saving
+    pub trim_trailing_whitespace: bool,
+    /// Make sure the file ends with a newline when saving
+    pub insert_final_newline: bool,
+    /// Column at which a guide is drawn
+    pub max_line_length: Option<usize>,
+}
+
+impl BufferSettings {
+    /// Columns per indentation level, given the global default
+    pub fn indent_size(&self, default: usize) -> usize {
+        self.indent_size.unwrap_or(default)
+    }
+
+    /// Columns a tab character is displayed as
+    pub fn display_tab_width(&self) -> usize {
+        self.tab_width.unwrap_or(DEFAULT_TAB_WIDTH).max(1)
+    }
+
+    /// Whitespace that indents to `columns`, given the global indent size
+    pub fn indent_string(&self, columns: usize, default: usize) -> String {
+        if self.use_tabs {
+            let tab_width = self.tab_width.unwrap_or(self.indent_size(default)).max(1);
+            format!(
+                "{}{}",
+                "\t".repeat(columns / tab_width),
+                " ".repeat(columns % tab_width)
+            )
+        } else {
+            " ".repeat(columns)
+        }
+    }
+}
+
 /// The complete editor state - everything needed to represent the current editing session
 ///
 /// NOTE: Viewport is NOT stored here - it lives in SplitViewState.
@@ -46,6 +96,9 @@ pub struct EditorState {
     /// Syntax highlighter (tree-sitter or TextMate based on language)
     pub highlighter: HighlightEngine,
 
+    /// Full-document syntax tree (None for large files and languages without a tree-sitter grammar)
+    pub syntax_tree: Option<SyntaxTree>,
+
     /// Auto-indent calculator for smart indentation (RefCell for interior mutability)
     pub indent_calculator: RefCell<IndentCalculator>,
 
@@ -64,6 +117,12 @@ pub struct EditorState {
     /// Margins for line numbers, annotations, gutter symbols, etc.)
     pub margins: MarginManager,
 
+    /// Folded regions and the buffer's foldable ranges
+    pub folds: FoldManager,
+
+    /// Expand/shrink selection history and the language server's selection ranges
+    pub selection_expansion: SelectionExpansion,
+
     /// Cached line number for primary cursor (0-indexed)
     /// Maintained incrementally to avoid O(n) scanning on every render
     pub primary_cursor_line_number: LineNumber,
@@ -101,6 +160,9 @@ pub struct EditorState {
 
     /// Optional transformed view payload for current viewport (tokens + map)
     pub view_transform: Option<crate::services::plugins::api::ViewTransformPayload>,
+
+    /// Per-buffer indentation and whitespace settings
+    pub settings: BufferSettings,
 }
 
 impl EditorState {
@@ -113,12 +175,15 @@ impl EditorState {
             buffer: Buffer::new(large_file_threshold),
             cursors: Cursors::new(),
             highlighter: HighlightEngine::None, // No file path, so no syntax highlighting
+            syntax_tree: None,
             indent_calculator: RefCell::new(IndentCalculator::new()),
             overlays: OverlayManager::new(),
             marker_list: MarkerList::new(),
             virtual_texts: VirtualTextManager::new(),
             popups: PopupManager::new(),
             margins: MarginManager::new(),
+            folds: FoldManager::new(),
+            selection_expansion: SelectionExpansion::new(),
             primary_cursor_line_number: LineNumber::Absolute(0), // Start at line 0
             mode: "insert".to_string(),
             text_properties: TextPropertyManager::new(),
@@ -130,6 +195,7 @@ impl EditorState {
             compose_prev_line_numbers: None,
             compose_column_guides: None,
             view_transform: None,
+            settings: BufferSettings::default(),
         }
     }
 
@@ -138,6 +204,8 @@ impl EditorState {
     pub fn set_language_from_name(&mut self, name: &str, registry: &GrammarRegistry) {
         let path = std::path::Path::new(name);
         self.highlighter = HighlightEngine::for_file(path, registry);
+        self.syntax_tree =
+            Language::from_path(path).and_then(|language| SyntaxTree::new(language, &self.buffer));
         if let Some(language) = Language::from_path(path) {
             self.semantic_highlighter.set_language(&language);
         }
@@ -148,18 +216,53 @@ impl EditorState {
         );
     }
 
+    /// Set the syntax highlighting and syntax tree language for a configured
+    /// language, or turn them off for none
+    pub fn set_language(&mut self, config: Option<&LanguageConfig>, registry: &GrammarRegistry) {
+        let Some(config) = config else {
+            self.highlighter = HighlightEngine::None;
+            self.syntax_tree = None;
+            return;
+        };
+        let names: Vec<&str> = config
+            .extensions
+            .iter()
+            .chain(&config.filenames)
+            .map(String::as_str)
+            .collect();
+        self.highlighter = HighlightEngine::for_language(&config.grammar, &names, registry);
+        let language = Language::from_grammar(&config.grammar);
+        self.syntax_tree = language.and_then(|language| SyntaxTree::new(language, &self.buffer));
+        if let Some(language) = language {
+            self.semantic_highlighter.set_language(&language);
+        }
+    }
+
     /// Create an editor state from a file
     ///
     /// Note: width/height parameters are kept for backward compatibility but
     /// are no longer used - viewport is now owned by SplitViewState.
     pub fn from_file(
+        path: &std::path::Path,
+        width: u16,
+        height: u16,
+        large_file_threshold: usize,
+        registry: &GrammarRegistry,
+    ) -> std::io::Result<Self> {
+        Self::from_file_with_encoding(path, width, height, large_file_threshold, registry, None)
+    }
+
+    /// Create an editor state from a file, decoding it with the given encoding
+    /// (or a detected one if `None`)
+    pub fn from_file_with_encoding(
         path: &std::path::Path,
         _width: u16,
         _height: u16,
         large_file_threshold: usize,
         registry: &GrammarRegistry,
+        encoding: Option<Encoding>,
     ) -> std::io::Result<Self> {
-        let buffer = Buffer::load_from_file(path, large_file_threshold)?;
+        let buffer = Buffer::load_from_file_with_encoding(path, large_file_threshold, encoding)?;
 
         // Create highlighter using HighlightEngine (tree-sitter preferred, TextMate fallback)
         let highlighter = HighlightEngine::for_file(path, registry);
@@ -171,6 +274,7 @@ impl EditorState {
 
         // Initialize semantic highlighter with language if available
         let language = Language::from_path(path);
+        let syntax_tree = language.and_then(|language| SyntaxTree::new(language, &buffer));
         let mut semantic_highlighter = SemanticHighlighter::new();
         if let Some(lang) = language {
             semantic_highlighter.set_language(&lang);
@@ -190,12 +294,15 @@ impl EditorState {
             buffer,
             cursors: Cursors::new(),
             highlighter,
+            syntax_tree,
             indent_calculator: RefCell::new(IndentCalculator::new()),
             overlays: OverlayManager::new(),
             marker_list,
             virtual_texts: VirtualTextManager::new(),
             popups: PopupManager::new(),
             margins: MarginManager::new(),
+            folds: FoldManager::new(),
+            selection_expansion: SelectionExpansion::new(),
             primary_cursor_line_number: LineNumber::Absolute(0), // Start at line 0
             mode: "insert".to_string(),
             text_properties: TextPropertyManager::new(),
@@ -207,10 +314,11 @@ impl EditorState {
             compose_prev_line_numbers: None,
             compose_column_guides: None,
             view_transform: None,
+            settings: BufferSettings::default(),
         })
     }
 
-    /// Handle an Insert event - adjusts markers, buffer, highlighter, cursors, and line numbers
+    /// Handle an Insert event - adjusts markers, buffer, syntax tree, highlighter, cursors, and line numbers
     fn apply_insert(
         &mut self,
         position: usize,
@@ -222,6 +330,11 @@ impl EditorState {
         // CRITICAL: Adjust markers BEFORE modifying buffer
         self.marker_list.adjust_for_insert(position, text.len());
         self.margins.adjust_for_insert(position, text.len());
+        self.folds.adjust_for_insert(position, text.len());
+        self.selection_expansion.clear();
+        if let Some(syntax_tree) = &mut self.syntax_tree {
+            syntax_tree.edit_insert(&self.buffer, position, text);
+        }
 
         // Insert text into buffer
         self.buffer.insert(position, text);
@@ -254,7 +367,7 @@ impl EditorState {
         }
     }
 
-    /// Handle a Delete event - adjusts markers, buffer, highlighter, cursors, and line numbers
+    /// Handle a Delete event - adjusts markers, buffer, syntax tree, highlighter, cursors, and line numbers
     fn apply_delete(
         &mut self,
         range: &std::ops::Range<usize>,
@@ -267,6 +380,11 @@ impl EditorState {
         // CRITICAL: Adjust markers BEFORE modifying buffer
         self.marker_list.adjust_for_delete(range.start, len);
         self.margins.adjust_for_delete(range.start, len);
+        self.folds.adjust_for_delete(range.start, len);
+        self.selection_expansion.clear();
+        if let Some(syntax_tree) = &mut self.syntax_tree {
+            syntax_tree.edit_delete(&self.buffer, range.clone(), deleted_text);
+        }
 
         // Delete from buffer
         self.buffer.delete(range.clone());
@@ -568,6 +686,115 @@ impl EditorState {
     pub fn primary_cursor_mut(&mut self) -> &mut Cursor {
         self.cursors.primary_mut()
     }
+
+    /// Foldable line ranges, sorted by start line: from the syntax tree when there
+    /// is one, else from the language server's last answer, else from indentation.
+    /// Large files have none.
+    pub fn fold_ranges(&mut self) -> Vec<FoldRange> {
+        if self.buffer.is_large_file() {
+            return Vec::new();
+        }
+        let tree = self
+            .syntax_tree
+            .as_mut()
+            .and_then(|syntax| syntax.tree(&self.buffer));
+        let source = fold_range_source(tree.is_some(), &self.folds);
+        if let Some(ranges) = self.folds.cached_ranges(source) {
+            return ranges.to_vec();
+        }
+
+        let ranges = match (source, tree) {
+            (_, Some(tree)) => fold_ranges::from_syntax_tree(tree),
+            (FoldRangeSource::Lsp, None) => self.folds.lsp_ranges().unwrap_or_default().to_vec(),
+            _ => {
+                let tab_width = self.fold_tab_width();
+                self.buffer
+                    .to_string()
+                    .map(|text| fold_ranges::from_indentation(&text, tab_width))
+                    .unwrap_or_default()
+            }
+        };
+        self.folds.cache_ranges(source, ranges.clone());
+        ranges
+    }
+
+    /// Lines in `lines` that start a foldable range. Unlike `fold_ranges`, only
+    /// those lines are looked at, so this stays cheap while typing.
+    pub fn fold_headers(&mut self, lines: std::ops::Range<usize>) -> Vec<usize> {
+        if self.buffer.is_large_file() || lines.is_empty() {
+            return Vec::new();
+        }
+        let tree = self
+            .syntax_tree
+            .as_mut()
+            .and_then(|syntax| syntax.tree(&self.buffer));
+        let source = fold_range_source(tree.is_some(), &self.folds);
+        let cached = self.folds.cached_ranges(source).or(match source {
+            FoldRangeSource::Lsp => self.folds.lsp_ranges(),
+            _ => None,
+        });
+        if let Some(ranges) = cached {
+            return ranges
+                .iter()
+                .map(|range| range.start_line)
+                .filter(|line| lines.contains(line))
+                .collect();
+        }
+
+        match tree {
+            Some(tree) => fold_ranges::from_syntax_tree_in(tree, lines)
+                .into_iter()
+                .map(|range| range.start_line)
+                .collect(),
+            None => self.indentation_fold_headers(lines),
+        }
+    }
+
+    /// Lines in `lines` followed by deeper indented lines
+    fn indentation_fold_headers(&mut self, lines: std::ops::Range<usize>) -> Vec<usize> {
+        let tab_width = self.fold_tab_width();
+        let Some(start) = self.buffer.line_start_offset(lines.start) else {
+            return Vec::new();
+        };
+        // Whether the last line starts a range depends on the next non-blank line
+        let mut window = String::new();
+        let mut line = lines.start;
+        let mut iter = self.buffer.line_iterator(start, 80);
+        while let Some((_, content)) = iter.next() {
+            let blank = content
+                .chars()
+                .all(|ch| matches!(ch, ' ' | '\t' | '\r' | '\n'));
+            window.push_str(&content);
+            line += 1;
+            if line > lines.end && !blank {
+                break;
+            }
+        }
+        fold_ranges::from_indentation(&window, tab_width)
+            .into_iter()
+            .map(|range| lines.start + range.start_line)
+            .filter(|line| lines.contains(line))
+            .collect()
+    }
+
+    /// Columns per tab when measuring indentation for folding
+    fn fold_tab_width(&self) -> usize {
+        self.settings
+            .tab_width
+            .or(self.settings.indent_size)
+            .unwrap_or(4)
+    }
+}
+
+/// Where a buffer's foldable ranges come from, given whether it has a syntax tree
+fn fold_range_source(has_tree: bool, folds: &FoldManager) -> FoldRangeSource {
+    if has_tree {
+        FoldRangeSource::SyntaxTree
+    } else if folds.lsp_ranges().is_some() {
+        FoldRangeSource::Lsp
+    } else {
+        FoldRangeSource::Indentation
+    }
 }
 
 /// Convert event overlay face to the actual overlay face
@@ -0,0 +1,103 @@
+//! Decorations of file tree entries
+//!
+//! The file explorer colors entries by their git status and shows a badge
+//! after them: the status letter, or a symbol a plugin put on the file. Plugin
+//! badges are grouped by namespace (like line indicators) so a plugin can
+//! replace its own without touching others'.
+
+use crate::services::git_status::{GitFileStatus, GitStatus};
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A badge set on a file by a plugin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDecoration {
+    /// Short text shown after the file name (e.g., "●", "2")
+    pub symbol: String,
+    pub color: Color,
+    /// Priority when several namespaces decorate a file (higher wins)
+    pub priority: i32,
+}
+
+/// Git status and plugin badges of file tree entries
+#[derive(Debug, Default)]
+pub struct FileDecorations {
+    git_status: Option<GitStatus>,
+    /// Plugin badges by namespace, then path
+    plugin_decorations: HashMap<String, HashMap<PathBuf, FileDecoration>>,
+}
+
+impl FileDecorations {
+    /// Replace the git status (`None` outside a git work tree)
+    pub fn set_git_status(&mut self, status: Option<GitStatus>) {
+        self.git_status = status;
+    }
+
+    /// Git status of a file or directory
+    pub fn git_status(&self, path: &Path) -> Option<GitFileStatus> {
+        self.git_status.as_ref()?.get(path)
+    }
+
+    /// Set a plugin's badge on a file
+    pub fn set(&mut self, namespace: String, path: PathBuf, decoration: FileDecoration) {
+        self.plugin_decorations
+            .entry(namespace)
+            .or_default()
+            .insert(path, decoration);
+    }
+
+    /// Remove all badges of a namespace
+    pub fn clear_namespace(&mut self, namespace: &str) {
+        self.plugin_decorations.remove(namespace);
+    }
+
+    /// The highest priority plugin badge on a file
+    pub fn plugin_decoration(&self, path: &Path) -> Option<&FileDecoration> {
+        self.plugin_decorations
+            .values()
+            .filter_map(|decorations| decorations.get(path))
+            .max_by_key(|decoration| decoration.priority)
+    }
+
+    /// Whether any entry can have a badge, so the tree needs a badge column
+    pub fn has_badges(&self) -> bool {
+        self.git_status.is_some() || self.plugin_decorations.values().any(|d| !d.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin_decorations() {
+        let mut decorations = FileDecorations::default();
+        let path = PathBuf::from("/project/main.rs");
+        let badge = |symbol: &str, priority| FileDecoration {
+            symbol: symbol.to_string(),
+            color: Color::Red,
+            priority,
+        };
+        assert!(!decorations.has_badges());
+
+        decorations.set("errors".to_string(), path.clone(), badge("2", 10));
+        decorations.set("bookmarks".to_string(), path.clone(), badge("★", 5));
+        assert!(decorations.has_badges());
+        assert_eq!(
+            decorations
+                .plugin_decoration(&path)
+                .map(|d| d.symbol.as_str()),
+            Some("2")
+        );
+
+        decorations.clear_namespace("errors");
+        assert_eq!(
+            decorations
+                .plugin_decoration(&path)
+                .map(|d| d.symbol.as_str()),
+            Some("★")
+        );
+        assert_eq!(decorations.git_status(&path), None);
+    }
+}
@@ -4,11 +4,13 @@
 // with lazy loading (directories are only read when expanded) and efficient
 // navigation.
 
+pub mod decorations;
 pub mod ignore;
 pub mod node;
 pub mod tree;
 pub mod view;
 
+pub use decorations::{FileDecoration, FileDecorations};
 pub use ignore::{IgnorePatterns, IgnoreStatus};
 pub use node::{NodeId, NodeState, TreeNode};
 pub use tree::FileTree;
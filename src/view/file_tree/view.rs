@@ -2,6 +2,8 @@ use super::ignore::IgnorePatterns;
 use super::node::NodeId;
 use super::tree::FileTree;
 use crate::services::fs::FsEntry;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 
 /// View state for file tree navigation and filtering
 #[derive(Debug)]
@@ -18,6 +20,11 @@ pub struct FileTreeView {
     ignore_patterns: IgnorePatterns,
     /// Last known viewport height (for scrolling calculations)
     pub(crate) viewport_height: usize,
+    /// Paths marked for multi-node operations (copy, cut, drag). Kept by path
+    /// because refreshing a directory recreates its nodes.
+    marked: BTreeSet<PathBuf>,
+    /// Directory under the mouse while dragging nodes
+    drop_target: Option<PathBuf>,
 }
 
 /// Sort mode for file tree entries
@@ -42,6 +49,8 @@ impl FileTreeView {
             sort_mode: SortMode::Type,
             ignore_patterns: IgnorePatterns::new(),
             viewport_height: 10, // Default, will be updated during rendering
+            marked: BTreeSet::new(),
+            drop_target: None,
         }
     }
 
@@ -275,6 +284,91 @@ impl FileTreeView {
             .map(|node| &node.entry)
     }
 
+    /// Mark or unmark a node for multi-node operations (the root can't be marked)
+    pub fn toggle_mark(&mut self, node_id: NodeId) {
+        if node_id == self.tree.root_id() {
+            return;
+        }
+        if let Some(node) = self.tree.get_node(node_id) {
+            let path = node.entry.path.clone();
+            if !self.marked.remove(&path) {
+                self.marked.insert(path);
+            }
+        }
+    }
+
+    /// Mark a node, keeping existing marks
+    pub fn mark(&mut self, node_id: NodeId) {
+        if node_id == self.tree.root_id() {
+            return;
+        }
+        if let Some(node) = self.tree.get_node(node_id) {
+            self.marked.insert(node.entry.path.clone());
+        }
+    }
+
+    /// Mark the visible nodes from the selected node to `node_id`
+    pub fn mark_range_to(&mut self, node_id: NodeId) {
+        let visible = self.tree.get_visible_nodes();
+        let Some(end) = visible.iter().position(|&id| id == node_id) else {
+            return;
+        };
+        let start = self
+            .selected_node
+            .and_then(|selected| visible.iter().position(|&id| id == selected))
+            .unwrap_or(end);
+        for &id in &visible[start.min(end)..=start.max(end)] {
+            self.mark(id);
+        }
+    }
+
+    /// Whether a path is marked
+    pub fn is_marked(&self, path: &Path) -> bool {
+        self.marked.contains(path)
+    }
+
+    /// Whether any node is marked
+    pub fn has_marks(&self) -> bool {
+        !self.marked.is_empty()
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Paths an operation applies to: the marked ones, or else the selected
+    /// node's (never the root). Paths inside a marked directory are left out
+    /// since the directory brings them along.
+    pub fn operation_paths(&self) -> Vec<PathBuf> {
+        if self.marked.is_empty() {
+            return self
+                .selected_node
+                .filter(|&id| id != self.tree.root_id())
+                .and_then(|id| self.tree.get_node(id))
+                .map(|node| vec![node.entry.path.clone()])
+                .unwrap_or_default();
+        }
+        self.marked
+            .iter()
+            .filter(|path| {
+                !self
+                    .marked
+                    .iter()
+                    .any(|other| other != *path && path.starts_with(other))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Directory highlighted as the drop target of a mouse drag
+    pub fn drop_target(&self) -> Option<&Path> {
+        self.drop_target.as_deref()
+    }
+
+    pub fn set_drop_target(&mut self, dir: Option<PathBuf>) {
+        self.drop_target = dir;
+    }
+
     /// Navigate to a specific path if it exists in the tree
     pub fn navigate_to_path(&mut self, path: &std::path::Path) {
         if let Some(node) = self.tree.get_node_by_path(path) {
@@ -556,6 +650,35 @@ mod tests {
         assert_eq!(view.visible_count(), 4); // root + 3 children
     }
 
+    #[tokio::test]
+    async fn test_marks() {
+        let (_temp_dir, mut view) = create_test_view().await;
+
+        let root_id = view.tree().root_id();
+        view.tree_mut().expand_node(root_id).await.unwrap();
+        let dir1 = view.tree().root_path().join("dir1");
+        let dir1_id = view.tree().get_node_by_path(&dir1).unwrap().id;
+        view.tree_mut().expand_node(dir1_id).await.unwrap();
+
+        // Without marks, operations apply to the selected node (not the root)
+        assert!(view.operation_paths().is_empty());
+        view.navigate_to_path(&dir1);
+        assert_eq!(view.operation_paths(), vec![dir1.clone()]);
+
+        // Marking dir1 through file1.txt leaves out file1.txt, inside dir1
+        let file1 = dir1.join("file1.txt");
+        let file1_id = view.tree().get_node_by_path(&file1).unwrap().id;
+        view.mark_range_to(file1_id);
+        assert!(view.is_marked(&file1));
+        assert_eq!(view.operation_paths(), vec![dir1.clone()]);
+
+        view.toggle_mark(dir1_id);
+        assert_eq!(view.operation_paths(), vec![file1]);
+        view.toggle_mark(root_id);
+        view.clear_marks();
+        assert!(!view.has_marks());
+    }
+
     #[tokio::test]
     async fn test_sort_mode() {
         let (_temp_dir, mut view) = create_test_view().await;
@@ -0,0 +1,326 @@
+//! Folded regions of a buffer
+//!
+//! A fold hides the lines after a header line. Each fold is anchored by two
+//! markers, at the end of the header line and at the end of the last hidden
+//! line, so folds follow edits. Their lines are derived from the markers when
+//! read; a fold whose hidden lines were all deleted is dropped.
+//!
+//! The manager also caches the buffer's foldable ranges (see
+//! `EditorState::fold_ranges`) until the next edit, the bytes its folds hide
+//! until the next edit or fold change, and keeps the language server's last
+//! answer, which is dropped on every edit since it is line based.
+
+use crate::model::buffer::Buffer;
+use crate::model::marker::{MarkerId, MarkerList};
+use crate::primitives::fold_ranges::FoldRange;
+use std::ops::Range;
+
+/// Where foldable ranges were computed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldRangeSource {
+    SyntaxTree,
+    Lsp,
+    Indentation,
+}
+
+/// A folded region, anchored to the ends of its header line and last hidden line
+#[derive(Debug)]
+struct Fold {
+    header: MarkerId,
+    end: MarkerId,
+}
+
+/// Folded regions and foldable ranges of a buffer
+#[derive(Debug)]
+pub struct FoldManager {
+    markers: MarkerList,
+    folds: Vec<Fold>,
+    /// Ranges from the language server's last `textDocument/foldingRange` answer
+    lsp_ranges: Option<Vec<FoldRange>>,
+    /// Number of edits so far, telling whether cached ranges are current
+    edits: u64,
+    /// Foldable ranges with their source and the edit count they were computed at
+    cached_ranges: Option<(FoldRangeSource, u64, Vec<FoldRange>)>,
+    /// Byte ranges hidden by the folds, until the next edit or fold change
+    hidden: Option<Vec<Range<usize>>>,
+}
+
+impl FoldManager {
+    pub fn new() -> Self {
+        Self {
+            markers: MarkerList::new(),
+            folds: Vec::new(),
+            lsp_ranges: None,
+            edits: 0,
+            cached_ranges: None,
+            hidden: None,
+        }
+    }
+
+    /// Adjust fold markers after an insertion
+    pub fn adjust_for_insert(&mut self, position: usize, length: usize) {
+        self.markers.adjust_for_insert(position, length);
+        self.edits += 1;
+        self.lsp_ranges = None;
+        self.hidden = None;
+    }
+
+    /// Adjust fold markers after a deletion
+    pub fn adjust_for_delete(&mut self, position: usize, length: usize) {
+        self.markers.adjust_for_delete(position, length);
+        self.edits += 1;
+        self.lsp_ranges = None;
+        self.hidden = None;
+    }
+
+    /// Ranges last reported by the language server
+    pub fn lsp_ranges(&self) -> Option<&[FoldRange]> {
+        self.lsp_ranges.as_deref()
+    }
+
+    /// Replace the ranges reported by the language server
+    pub fn set_lsp_ranges(&mut self, ranges: Vec<FoldRange>) {
+        self.lsp_ranges = Some(ranges);
+        self.cached_ranges = None;
+    }
+
+    /// Cached foldable ranges, if they were computed from `source` since the last edit
+    pub fn cached_ranges(&self, source: FoldRangeSource) -> Option<&[FoldRange]> {
+        match &self.cached_ranges {
+            Some((cached_source, edits, ranges))
+                if *cached_source == source && *edits == self.edits =>
+            {
+                Some(ranges)
+            }
+            _ => None,
+        }
+    }
+
+    /// Remember the foldable ranges computed from `source`
+    pub fn cache_ranges(&mut self, source: FoldRangeSource, ranges: Vec<FoldRange>) {
+        self.cached_ranges = Some((source, self.edits, ranges));
+    }
+
+    /// Whether nothing is folded
+    pub fn is_empty(&self) -> bool {
+        self.folds.is_empty()
+    }
+
+    /// Fold `range`. Returns false if it's already folded or has no lines to hide.
+    pub fn fold(&mut self, buffer: &Buffer, range: FoldRange) -> bool {
+        if range.end_line <= range.start_line || self.is_folded(buffer, range.start_line) {
+            return false;
+        }
+        let (Some(header_end), Some(end)) = (
+            line_end(buffer, range.start_line),
+            line_end(buffer, range.end_line),
+        ) else {
+            return false;
+        };
+        let header = self.markers.create(header_end, true);
+        let end = self.markers.create(end, true);
+        self.folds.push(Fold { header, end });
+        self.hidden = None;
+        true
+    }
+
+    /// Unfold the fold whose header is `line`. Returns false if there is none.
+    pub fn unfold(&mut self, buffer: &Buffer, line: usize) -> bool {
+        self.remove_where(|range, _| range.start_line == line, buffer)
+    }
+
+    /// Unfold everything
+    pub fn unfold_all(&mut self) {
+        for fold in self.folds.drain(..) {
+            self.markers.delete(fold.header);
+            self.markers.delete(fold.end);
+        }
+        self.hidden = None;
+    }
+
+    /// Whether the fold with header `line` is folded
+    pub fn is_folded(&self, buffer: &Buffer, line: usize) -> bool {
+        self.folds
+            .iter()
+            .filter_map(|fold| self.resolve(buffer, fold))
+            .any(|range| range.start_line == line)
+    }
+
+    /// All folded ranges (nested ones included), sorted by header line
+    pub fn folded(&self, buffer: &Buffer) -> Vec<FoldRange> {
+        let mut ranges: Vec<FoldRange> = self
+            .folds
+            .iter()
+            .filter_map(|fold| self.resolve(buffer, fold))
+            .collect();
+        ranges.sort();
+        ranges
+    }
+
+    /// Outermost folded ranges, sorted by header line
+    pub fn outermost(&self, buffer: &Buffer) -> Vec<FoldRange> {
+        let mut outermost: Vec<FoldRange> = Vec::new();
+        for range in self.folded(buffer) {
+            match outermost.last_mut() {
+                Some(last) if range.start_line <= last.end_line => {
+                    last.end_line = last.end_line.max(range.end_line);
+                }
+                _ => outermost.push(range),
+            }
+        }
+        outermost
+    }
+
+    /// Byte ranges hidden by folds, from the start of the first hidden line to
+    /// the start of the line after the last one. Sorted and non-overlapping.
+    pub fn hidden_byte_ranges(&mut self, buffer: &Buffer) -> Vec<Range<usize>> {
+        if self.folds.is_empty() {
+            return Vec::new();
+        }
+        if let Some(hidden) = &self.hidden {
+            return hidden.clone();
+        }
+        let hidden: Vec<Range<usize>> = self
+            .outermost(buffer)
+            .into_iter()
+            .filter_map(|range| hidden_bytes(buffer, range))
+            .collect();
+        self.hidden = Some(hidden.clone());
+        hidden
+    }
+
+    /// The outermost folded range whose hidden lines include `position`
+    pub fn hiding(&self, buffer: &Buffer, position: usize) -> Option<FoldRange> {
+        let line = buffer.get_line_number(position);
+        self.outermost(buffer)
+            .into_iter()
+            .find(|range| range.start_line < line && line <= range.end_line)
+    }
+
+    /// Unfold every fold hiding one of `positions`. Returns true if anything was unfolded.
+    pub fn reveal(&mut self, buffer: &Buffer, positions: &[usize]) -> bool {
+        self.remove_where(
+            |range, buffer| {
+                hidden_bytes(buffer, range).is_some_and(|hidden| {
+                    // A fold reaching the end of the buffer also hides the position at the very end
+                    let hides_eof = hidden.end == buffer.len()
+                        && buffer.line_start_offset(range.end_line + 1).is_none();
+                    positions
+                        .iter()
+                        .any(|&pos| hidden.contains(&pos) || (hides_eof && pos == hidden.end))
+                })
+            },
+            buffer,
+        )
+    }
+
+    /// The lines of a fold, or None if its hidden lines are gone
+    fn resolve(&self, buffer: &Buffer, fold: &Fold) -> Option<FoldRange> {
+        let start_line = buffer.get_line_number(self.markers.get_position(fold.header)?);
+        let end_line = buffer.get_line_number(self.markers.get_position(fold.end)?);
+        (end_line > start_line).then(|| FoldRange::new(start_line, end_line))
+    }
+
+    /// Remove folds (and folds that no longer hide anything) matching `predicate`.
+    /// Returns true if a matching fold was removed.
+    fn remove_where(
+        &mut self,
+        predicate: impl Fn(FoldRange, &Buffer) -> bool,
+        buffer: &Buffer,
+    ) -> bool {
+        let mut removed_any = false;
+        let count = self.folds.len();
+        let mut kept = Vec::with_capacity(count);
+        for fold in std::mem::take(&mut self.folds) {
+            match self.resolve(buffer, &fold) {
+                Some(range) if !predicate(range, buffer) => kept.push(fold),
+                resolved => {
+                    removed_any |= resolved.is_some();
+                    self.markers.delete(fold.header);
+                    self.markers.delete(fold.end);
+                }
+            }
+        }
+        if kept.len() != count {
+            self.hidden = None;
+        }
+        self.folds = kept;
+        removed_any
+    }
+}
+
+impl Default for FoldManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Byte offset of the end of a line's content (its newline, or the end of the buffer)
+fn line_end(buffer: &Buffer, line: usize) -> Option<usize> {
+    buffer.line_start_offset(line)?;
+    Some(
+        buffer
+            .line_start_offset(line + 1)
+            .map(|next| next.saturating_sub(1))
+            .unwrap_or(buffer.len()),
+    )
+}
+
+/// Bytes hidden when `range` is folded
+fn hidden_bytes(buffer: &Buffer, range: FoldRange) -> Option<Range<usize>> {
+    let start = buffer.line_start_offset(range.start_line + 1)?;
+    let end = buffer
+        .line_start_offset(range.end_line + 1)
+        .unwrap_or(buffer.len());
+    (start < end).then_some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold_follows_edits() {
+        let mut buffer = Buffer::from_str_test("a {\n  b\n  c\n}\nd\n");
+        let mut folds = FoldManager::new();
+        assert!(folds.fold(&buffer, FoldRange::new(0, 3)));
+        assert!(!folds.fold(&buffer, FoldRange::new(0, 3)));
+        assert_eq!(folds.hidden_byte_ranges(&buffer), vec![4..14]);
+
+        // Two lines inserted above the fold move it down
+        folds.adjust_for_insert(0, 4);
+        buffer.insert(0, "x\ny\n");
+        assert_eq!(folds.folded(&buffer), vec![FoldRange::new(2, 5)]);
+        assert_eq!(folds.hidden_byte_ranges(&buffer), vec![8..18]);
+
+        // Deleting the hidden lines along with the header's newline drops the fold
+        folds.adjust_for_delete(7, 11);
+        buffer.delete(7..18);
+        assert!(folds.folded(&buffer).is_empty());
+    }
+
+    #[test]
+    fn test_nested_folds_and_reveal() {
+        let buffer = Buffer::from_str_test("a\n b\n  c\n d\ne\n");
+        let mut folds = FoldManager::new();
+        folds.fold(&buffer, FoldRange::new(1, 2));
+        folds.fold(&buffer, FoldRange::new(0, 3));
+        assert_eq!(folds.outermost(&buffer), vec![FoldRange::new(0, 3)]);
+        assert_eq!(folds.hidden_byte_ranges(&buffer), vec![2..12]);
+
+        // A position in the inner fold reveals both folds
+        assert!(folds.reveal(&buffer, &[6]));
+        assert!(folds.is_empty());
+    }
+
+    #[test]
+    fn test_unfold_keeps_nested_folds() {
+        let buffer = Buffer::from_str_test("a\n b\n  c\n d\ne\n");
+        let mut folds = FoldManager::new();
+        folds.fold(&buffer, FoldRange::new(1, 2));
+        folds.fold(&buffer, FoldRange::new(0, 3));
+        assert!(folds.unfold(&buffer, 0));
+        assert!(!folds.unfold(&buffer, 0));
+        assert_eq!(folds.folded(&buffer), vec![FoldRange::new(1, 2)]);
+    }
+}
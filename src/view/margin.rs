@@ -56,6 +56,25 @@ impl LineIndicator {
     }
 }
 
+/// Fold marker displayed in the gutter's indicator column on foldable lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldMarker {
+    /// The line starts a foldable region that is currently shown
+    Expanded,
+    /// The line is the header of a folded region
+    Collapsed,
+}
+
+impl FoldMarker {
+    /// Symbol drawn in the gutter
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Expanded => "▾",
+            Self::Collapsed => "▸",
+        }
+    }
+}
+
 /// Content type for a margin at a specific line
 #[derive(Debug, Clone, PartialEq)]
 pub enum MarginContent {
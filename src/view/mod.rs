@@ -3,6 +3,7 @@
 //! This module contains all presentation and rendering components.
 
 pub mod file_tree;
+pub mod folding;
 pub mod margin;
 pub mod overlay;
 pub mod popup;
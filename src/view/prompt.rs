@@ -30,6 +30,8 @@ pub enum PromptType {
     Command,
     /// Go to a specific line number
     GotoLine,
+    /// Go to an undo tree state by number or time
+    UndoTreeGoto,
     /// Choose an ANSI background file
     SetBackgroundFile,
     /// Set background blend ratio (0-1)
@@ -49,6 +51,14 @@ pub enum PromptType {
     RecordMacro,
     /// Play a macro - prompts for register (0-9)
     PlayMacro,
+    /// Edit a macro in a buffer - prompts for register (0-9)
+    EditMacro,
+    /// Play a macro on each selected line - prompts for register (0-9)
+    ApplyMacroToLines,
+    /// Play a macro at each search match - prompts for register (0-9)
+    ApplyMacroToMatches,
+    /// Repeat a macro - prompts for register (0-9) and an optional count
+    RepeatMacro,
     /// Set a bookmark - prompts for register (0-9)
     SetBookmark,
     /// Jump to a bookmark - prompts for register (0-9)
@@ -78,8 +88,41 @@ pub enum PromptType {
         original_path: std::path::PathBuf,
         original_name: String,
     },
+    /// Confirm deleting files in the file explorer: directories to the trash,
+    /// or anything permanently
+    ConfirmFileExplorerDelete {
+        paths: Vec<std::path::PathBuf>,
+        permanent: bool,
+    },
+    /// A pasted or dropped file's name is taken in the target directory -
+    /// overwrite, keep both, skip or cancel
+    FileTransferConflict,
     /// Switch to a tab by name (from the current split's open buffers)
     SwitchToTab,
+    /// Reload the current file with a different encoding (select from list)
+    ReopenWithEncoding,
+    /// Save the current file in a different encoding (select from list)
+    SaveWithEncoding,
+    /// Override the current buffer's language (select from list)
+    SetLanguage,
+    /// Go to a symbol of the current buffer (select from list)
+    GotoSymbol,
+    /// Go to a symbol anywhere in the workspace, asking the language server
+    /// for symbols matching the input as it changes
+    WorkspaceSymbol,
+    /// Offer saving with elevated privileges after a permission error
+    ConfirmSaveAsRoot,
+    /// Password for the privileged save helper (input is masked)
+    SaveAsRootPassword,
+    /// Trust a project config that sets commands (y/N)
+    TrustProjectConfig,
+}
+
+impl PromptType {
+    /// Whether the input must not be shown on screen
+    pub fn is_masked(&self) -> bool {
+        matches!(self, PromptType::SaveAsRootPassword)
+    }
 }
 
 /// Prompt state for the minibuffer
@@ -141,6 +141,16 @@ struct UiColors {
     compose_margin_bg: ColorDef,
     #[serde(default = "default_semantic_highlight_bg")]
     semantic_highlight_bg: ColorDef,
+    #[serde(default = "default_git_modified_fg")]
+    git_modified_fg: ColorDef,
+    #[serde(default = "default_git_added_fg")]
+    git_added_fg: ColorDef,
+    #[serde(default = "default_git_untracked_fg")]
+    git_untracked_fg: ColorDef,
+    #[serde(default = "default_git_conflicted_fg")]
+    git_conflicted_fg: ColorDef,
+    #[serde(default = "default_git_ignored_fg")]
+    git_ignored_fg: ColorDef,
 }
 
 // Default tab close hover color (for backward compatibility with existing themes)
@@ -218,6 +228,21 @@ fn default_compose_margin_bg() -> ColorDef {
 fn default_semantic_highlight_bg() -> ColorDef {
     ColorDef::Rgb(60, 60, 80) // Subtle dark highlight for word occurrences
 }
+fn default_git_modified_fg() -> ColorDef {
+    ColorDef::Rgb(220, 180, 80) // Amber
+}
+fn default_git_added_fg() -> ColorDef {
+    ColorDef::Rgb(110, 190, 110) // Green
+}
+fn default_git_untracked_fg() -> ColorDef {
+    ColorDef::Rgb(100, 180, 200) // Teal
+}
+fn default_git_conflicted_fg() -> ColorDef {
+    ColorDef::Rgb(230, 90, 90) // Red
+}
+fn default_git_ignored_fg() -> ColorDef {
+    ColorDef::Rgb(110, 110, 110) // Dim gray
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SearchColors {
@@ -332,6 +357,13 @@ pub struct Theme {
     // Semantic highlighting (word under cursor)
     pub semantic_highlight_bg: Color,
 
+    // Git status of files in the file explorer
+    pub git_modified_fg: Color,
+    pub git_added_fg: Color,
+    pub git_untracked_fg: Color,
+    pub git_conflicted_fg: Color,
+    pub git_ignored_fg: Color,
+
     // Search colors
     pub search_match_bg: Color,
     pub search_match_fg: Color,
@@ -417,6 +449,11 @@ impl From<ThemeFile> for Theme {
             scrollbar_thumb_hover_fg: file.ui.scrollbar_thumb_hover_fg.into(),
             compose_margin_bg: file.ui.compose_margin_bg.into(),
             semantic_highlight_bg: file.ui.semantic_highlight_bg.into(),
+            git_modified_fg: file.ui.git_modified_fg.into(),
+            git_added_fg: file.ui.git_added_fg.into(),
+            git_untracked_fg: file.ui.git_untracked_fg.into(),
+            git_conflicted_fg: file.ui.git_conflicted_fg.into(),
+            git_ignored_fg: file.ui.git_ignored_fg.into(),
             search_match_bg: file.search.match_bg.into(),
             search_match_fg: file.search.match_fg.into(),
             diagnostic_error_fg: file.diagnostic.error_fg.into(),
@@ -557,6 +594,13 @@ impl Theme {
             // Semantic highlighting (word under cursor)
             semantic_highlight_bg: Color::Rgb(60, 60, 80), // Subtle dark highlight
 
+            // Git status colors
+            git_modified_fg: Color::Rgb(220, 180, 80),
+            git_added_fg: Color::Rgb(110, 190, 110),
+            git_untracked_fg: Color::Rgb(100, 180, 200),
+            git_conflicted_fg: Color::Rgb(230, 90, 90),
+            git_ignored_fg: Color::Rgb(110, 110, 110),
+
             // Search colors
             search_match_bg: Color::Rgb(100, 100, 20), // Yellow-brown highlight
             search_match_fg: Color::Rgb(255, 255, 255),
@@ -663,6 +707,13 @@ impl Theme {
             // Semantic highlighting (word under cursor)
             semantic_highlight_bg: Color::Rgb(220, 230, 240), // Subtle light blue highlight
 
+            // Git status colors
+            git_modified_fg: Color::Rgb(160, 110, 0),
+            git_added_fg: Color::Rgb(30, 130, 30),
+            git_untracked_fg: Color::Rgb(0, 120, 150),
+            git_conflicted_fg: Color::Rgb(190, 30, 30),
+            git_ignored_fg: Color::Rgb(150, 150, 150),
+
             // Search colors
             search_match_bg: Color::Rgb(255, 255, 150), // Light yellow highlight
             search_match_fg: Color::Rgb(0, 0, 0),
@@ -769,6 +820,13 @@ impl Theme {
             // Semantic highlighting (word under cursor)
             semantic_highlight_bg: Color::Rgb(0, 60, 100), // Bright blue highlight for visibility
 
+            // Git status colors
+            git_modified_fg: Color::Yellow,
+            git_added_fg: Color::LightGreen,
+            git_untracked_fg: Color::LightCyan,
+            git_conflicted_fg: Color::LightRed,
+            git_ignored_fg: Color::DarkGray,
+
             // Search colors
             search_match_bg: Color::Yellow,
             search_match_fg: Color::Black,
@@ -925,6 +983,13 @@ impl Theme {
             // Semantic highlighting (word under cursor)
             semantic_highlight_bg: Color::Rgb(0, 85, 170), // Lighter blue highlight
 
+            // Git status colors
+            git_modified_fg: Color::Rgb(255, 255, 85),
+            git_added_fg: Color::Rgb(85, 255, 85),
+            git_untracked_fg: Color::Rgb(85, 255, 255),
+            git_conflicted_fg: Color::Rgb(255, 85, 85),
+            git_ignored_fg: Color::Rgb(170, 170, 170),
+
             // Search colors
             search_match_bg: Color::Rgb(170, 85, 0), // Orange/brown
             search_match_fg: Color::Rgb(255, 255, 255),
@@ -1,5 +1,6 @@
 use crate::primitives::display_width::str_width;
-use crate::view::file_tree::{FileTreeView, NodeId};
+use crate::services::git_status::GitFileStatus;
+use crate::view::file_tree::{FileDecorations, FileTreeView, NodeId};
 use crate::view::theme::Theme;
 use ratatui::{
     layout::Rect,
@@ -12,6 +13,9 @@ use ratatui::{
 use std::collections::HashSet;
 use std::path::PathBuf;
 
+/// Columns for the badge after each entry (git status letter or plugin symbol)
+const BADGE_WIDTH: usize = 3;
+
 pub struct FileExplorerRenderer;
 
 impl FileExplorerRenderer {
@@ -22,6 +26,7 @@ impl FileExplorerRenderer {
         area: Rect,
         is_focused: bool,
         files_with_unsaved_changes: &HashSet<PathBuf>,
+        decorations: &FileDecorations,
         keybinding_resolver: &crate::input::keybindings::KeybindingResolver,
         current_context: crate::input::keybindings::KeyContext,
         theme: &Theme,
@@ -59,6 +64,7 @@ impl FileExplorerRenderer {
                     is_selected,
                     is_focused,
                     files_with_unsaved_changes,
+                    decorations,
                     theme,
                     content_width,
                 )
@@ -162,6 +168,7 @@ impl FileExplorerRenderer {
         is_selected: bool,
         is_focused: bool,
         files_with_unsaved_changes: &HashSet<PathBuf>,
+        decorations: &FileDecorations,
         theme: &Theme,
         content_width: usize,
     ) -> ListItem<'static> {
@@ -170,6 +177,28 @@ impl FileExplorerRenderer {
         // Build the line with indentation and tree structure
         let mut spans = Vec::new();
 
+        // Git status colors the name; the badge column shows its letter (a
+        // dot for directories) unless a plugin decorated the file
+        let git_status = decorations.git_status(&node.entry.path);
+        let badge = if let Some(decoration) = decorations.plugin_decoration(&node.entry.path) {
+            Some((decoration.symbol.clone(), decoration.color))
+        } else if let Some(status) = git_status {
+            let symbol = if node.is_dir() {
+                status.badge().map(|_| "•".to_string())
+            } else {
+                status.badge().map(String::from)
+            };
+            symbol.map(|symbol| (symbol, Self::git_status_color(status, theme)))
+        } else {
+            None
+        };
+        // Reserve the badge column on every row so that sizes stay aligned
+        let content_width = if decorations.has_badges() {
+            content_width.saturating_sub(BADGE_WIDTH)
+        } else {
+            content_width
+        };
+
         // Calculate the left side width for padding calculation
         let indent_width = indent * 2;
         let indicator_width = 2; // "▼ " or "● " or "  "
@@ -219,6 +248,8 @@ impl FileExplorerRenderer {
             .unwrap_or(false)
         {
             Style::default().fg(theme.line_number_fg)
+        } else if let Some(status) = git_status {
+            Style::default().fg(Self::git_status_color(status, theme))
         } else if node.is_dir() {
             Style::default().fg(theme.syntax_keyword)
         } else {
@@ -245,6 +276,7 @@ impl FileExplorerRenderer {
             None
         };
 
+        let mut used_width = left_side_width;
         if let Some(size_text) = size_str {
             let size_display_width = str_width(&size_text);
             // Calculate padding needed for right-alignment
@@ -261,6 +293,15 @@ impl FileExplorerRenderer {
                 size_text,
                 Style::default().fg(theme.line_number_fg),
             ));
+            used_width += padding + size_display_width;
+        }
+
+        if let Some((symbol, color)) = badge {
+            // Right-align the badge in its column
+            let padding = content_width.saturating_sub(used_width)
+                + BADGE_WIDTH.saturating_sub(str_width(&symbol));
+            spans.push(Span::raw(" ".repeat(padding.max(1))));
+            spans.push(Span::styled(symbol, Style::default().fg(color)));
         }
 
         // Error indicator
@@ -271,7 +312,32 @@ impl FileExplorerRenderer {
             ));
         }
 
-        ListItem::new(Line::from(spans)).style(Style::default().bg(theme.editor_bg))
+        // Marked nodes and the drop target of a drag stand out like selected text
+        let is_marked = view.is_marked(&node.entry.path);
+        let is_drop_target = view.drop_target() == Some(node.entry.path.as_path());
+        let row_style = if is_drop_target {
+            Style::default()
+                .bg(theme.selection_bg)
+                .add_modifier(Modifier::UNDERLINED)
+        } else if is_marked {
+            Style::default()
+                .bg(theme.selection_bg)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().bg(theme.editor_bg)
+        };
+        ListItem::new(Line::from(spans)).style(row_style)
+    }
+
+    /// Theme color of a git status
+    fn git_status_color(status: GitFileStatus, theme: &Theme) -> ratatui::style::Color {
+        match status {
+            GitFileStatus::Ignored => theme.git_ignored_fg,
+            GitFileStatus::Untracked => theme.git_untracked_fg,
+            GitFileStatus::Added => theme.git_added_fg,
+            GitFileStatus::Modified => theme.git_modified_fg,
+            GitFileStatus::Conflicted => theme.git_conflicted_fg,
+        }
     }
 
     /// Format file size for display
@@ -16,6 +16,7 @@ pub mod context_keys {
     pub const COMPOSE_MODE: &str = "compose_mode";
     pub const FILE_EXPLORER: &str = "file_explorer";
     pub const FILE_EXPLORER_FOCUSED: &str = "file_explorer_focused";
+    pub const OUTLINE: &str = "outline";
     pub const MOUSE_CAPTURE: &str = "mouse_capture";
     pub const MOUSE_HOVER: &str = "mouse_hover";
     pub const LSP_AVAILABLE: &str = "lsp_available";
@@ -12,6 +12,7 @@ use crate::primitives::ansi_background::AnsiBackground;
 use crate::primitives::display_width::char_width;
 use crate::services::plugins::api::ViewTransformPayload;
 use crate::state::{EditorState, ViewMode};
+use crate::view::margin::FoldMarker;
 use crate::view::split::SplitManager;
 use crate::view::ui::tabs::TabsRenderer;
 use crate::view::ui::view_pipeline::{
@@ -26,6 +27,9 @@ use ratatui::Frame;
 use std::collections::{HashMap, HashSet};
 use std::ops::Range;
 
+/// Text shown at the end of a folded header line in place of its hidden lines
+const FOLD_PLACEHOLDER: &str = " ⋯";
+
 fn push_span_with_map(
     spans: &mut Vec<Span<'static>>,
     map: &mut Vec<Option<usize>>,
@@ -80,6 +84,10 @@ struct DecorationContext {
     diagnostic_lines: HashSet<usize>,
     /// Line indicators indexed by line number (highest priority indicator per line)
     line_indicators: BTreeMap<usize, crate::view::margin::LineIndicator>,
+    /// Fold markers indexed by line number
+    fold_markers: BTreeMap<usize, FoldMarker>,
+    /// Number of hidden lines after each folded header line
+    folded_lines: BTreeMap<usize, usize>,
 }
 
 struct LineRenderOutput {
@@ -159,6 +167,8 @@ struct LeftMarginContext<'a> {
     diagnostic_lines: &'a HashSet<usize>,
     /// Pre-computed line indicators (line_num -> indicator)
     line_indicators: &'a BTreeMap<usize, crate::view::margin::LineIndicator>,
+    /// Pre-computed fold markers (line_num -> marker)
+    fold_markers: &'a BTreeMap<usize, FoldMarker>,
 }
 
 /// Render the left margin (indicators + line numbers + separator) to line_spans
@@ -189,6 +199,17 @@ fn render_left_margin(
             Style::default().fg(ratatui::style::Color::Red),
             None,
         );
+    } else if let Some(marker @ FoldMarker::Collapsed) =
+        ctx.fold_markers.get(&ctx.current_source_line_num)
+    {
+        // Folded regions must stay discoverable, so they beat other indicators
+        push_span_with_map(
+            line_spans,
+            line_view_map,
+            marker.symbol().to_string(),
+            Style::default().fg(ctx.theme.line_number_fg),
+            None,
+        );
     } else if let Some(indicator) = ctx.line_indicators.get(&ctx.current_source_line_num) {
         // Show line indicator (git gutter, breakpoints, etc.)
         push_span_with_map(
@@ -198,6 +219,15 @@ fn render_left_margin(
             Style::default().fg(indicator.color),
             None,
         );
+    } else if let Some(marker) = ctx.fold_markers.get(&ctx.current_source_line_num) {
+        // Show that the line can be folded
+        push_span_with_map(
+            line_spans,
+            line_view_map,
+            marker.symbol().to_string(),
+            Style::default().fg(ctx.theme.line_number_fg),
+            None,
+        );
     } else {
         // Show space (no indicator)
         push_span_with_map(
@@ -566,6 +596,7 @@ impl SplitRenderer {
                     split_id,
                     is_active,
                 );
+                Self::sync_folds_to_cursors(state, &mut viewport);
                 Self::sync_viewport_to_content(
                     &mut viewport,
                     &mut state.buffer,
@@ -775,6 +806,33 @@ impl SplitRenderer {
         }
     }
 
+    /// Unfold folds that hide a cursor and keep the viewport from starting
+    /// inside a folded region
+    fn sync_folds_to_cursors(
+        state: &mut EditorState,
+        viewport: &mut crate::view::viewport::Viewport,
+    ) {
+        if state.folds.is_empty() {
+            viewport.hidden_ranges.clear();
+            return;
+        }
+
+        let positions: Vec<usize> = state.cursors.iter().map(|(_, c)| c.position).collect();
+        state.folds.reveal(&state.buffer, &positions);
+        viewport.hidden_ranges = state.folds.hidden_byte_ranges(&state.buffer);
+
+        let top_byte = viewport.top_byte;
+        if let Some(range) = viewport
+            .hidden_ranges
+            .iter()
+            .find(|range| range.contains(&top_byte))
+        {
+            // Start at the fold's header line instead
+            let header_line = state.buffer.get_line_number(range.start.saturating_sub(1));
+            viewport.top_byte = state.buffer.line_start_offset(header_line).unwrap_or(0);
+        }
+    }
+
     fn sync_viewport_to_content(
         viewport: &mut crate::view::viewport::Viewport,
         buffer: &mut crate::model::buffer::Buffer,
@@ -971,13 +1029,15 @@ impl SplitRenderer {
         // Check if buffer is binary before building tokens
         let is_binary = state.buffer.is_binary();
 
-        // Build base token stream from source
+        // Build base token stream from source, leaving out folded lines
+        let hidden = state.folds.hidden_byte_ranges(&state.buffer);
         let base_tokens = Self::build_base_tokens(
             &mut state.buffer,
             viewport.top_byte,
             estimated_line_length,
             visible_count,
             is_binary,
+            &hidden,
         );
 
         // Use plugin transform if available, otherwise use base tokens
@@ -994,8 +1054,9 @@ impl SplitRenderer {
         // Enable ANSI awareness for non-binary content to handle escape sequences correctly
         let is_binary = state.buffer.is_binary();
         let ansi_aware = !is_binary; // ANSI parsing for normal text files
+        let tab_width = state.settings.display_tab_width();
         let source_lines: Vec<ViewLine> =
-            ViewLineIterator::with_options(&tokens, is_binary, ansi_aware).collect();
+            ViewLineIterator::with_options(&tokens, is_binary, ansi_aware, tab_width).collect();
 
         // Inject virtual lines (LineAbove/LineBelow) from VirtualTextManager
         let lines = Self::inject_virtual_lines(source_lines, state);
@@ -1118,6 +1179,7 @@ impl SplitRenderer {
         estimated_line_length: usize,
         visible_count: usize,
         is_binary: bool,
+        hidden: &[Range<usize>],
     ) -> Vec<crate::services::plugins::api::ViewTokenWire> {
         use crate::services::plugins::api::{ViewTokenWire, ViewTokenWireKind};
 
@@ -1147,6 +1209,15 @@ impl SplitRenderer {
 
                     match ch {
                         '\n' => {
+                            // A folded header line ends with a placeholder for its hidden lines
+                            let next_line_start = line_start + byte_offset + 1;
+                            if hidden.iter().any(|range| range.start == next_line_start) {
+                                tokens.push(ViewTokenWire {
+                                    source_offset: None,
+                                    kind: ViewTokenWireKind::Text(FOLD_PLACEHOLDER.to_string()),
+                                    style: None,
+                                });
+                            }
                             tokens.push(ViewTokenWire {
                                 source_offset,
                                 kind: ViewTokenWireKind::Newline,
@@ -1199,6 +1270,12 @@ impl SplitRenderer {
                     byte_offset += ch_len;
                 }
                 lines_seen += 1;
+
+                // Skip the lines hidden by a fold
+                let next_line_start = line_start + byte_offset;
+                if let Some(range) = hidden.iter().find(|range| range.start == next_line_start) {
+                    iter = buffer.line_iterator(range.end, estimated_line_length);
+                }
             } else {
                 break;
             }
@@ -1386,6 +1463,7 @@ impl SplitRenderer {
         estimated_line_length: usize,
         visible_count: usize,
         is_binary: bool,
+        hidden: &[Range<usize>],
     ) -> Vec<crate::services::plugins::api::ViewTokenWire> {
         Self::build_base_tokens(
             buffer,
@@ -1393,6 +1471,7 @@ impl SplitRenderer {
             estimated_line_length,
             visible_count,
             is_binary,
+            hidden,
         )
     }
 
@@ -1722,12 +1801,18 @@ impl SplitRenderer {
             .saturating_add(viewport_size)
             .min(state.buffer.len());
 
+        let syntax_tree = state
+            .syntax_tree
+            .as_mut()
+            .filter(|_| state.highlighter.uses_syntax_tree())
+            .and_then(|syntax| syntax.tree(&state.buffer));
         let highlight_spans = state.highlighter.highlight_viewport(
             &state.buffer,
             highlight_start,
             highlight_end,
             theme,
             highlight_context_bytes,
+            syntax_tree,
         );
 
         // Update semantic highlighter color from theme
@@ -1775,6 +1860,27 @@ impl SplitRenderer {
             |byte_offset| state.buffer.get_line_number(byte_offset),
         );
 
+        // Fold markers for foldable lines in the viewport, collapsed ones winning
+        let first_line = state.buffer.get_line_number(viewport_start);
+        let last_line = state.buffer.get_line_number(viewport_end);
+        let mut fold_markers: BTreeMap<usize, FoldMarker> = state
+            .fold_headers(first_line..last_line + 1)
+            .into_iter()
+            .map(|line| (line, FoldMarker::Expanded))
+            .collect();
+        let mut folded_lines = BTreeMap::new();
+        if !state.folds.is_empty() {
+            let in_viewport = |line: usize| line >= first_line && line <= last_line;
+            for range in state.folds.folded(&state.buffer) {
+                if in_viewport(range.start_line) {
+                    fold_markers.insert(range.start_line, FoldMarker::Collapsed);
+                }
+            }
+            for range in state.folds.outermost(&state.buffer) {
+                folded_lines.insert(range.start_line, range.end_line - range.start_line);
+            }
+        }
+
         DecorationContext {
             highlight_spans,
             semantic_spans,
@@ -1782,6 +1888,8 @@ impl SplitRenderer {
             virtual_text_lookup,
             diagnostic_lines,
             line_indicators,
+            fold_markers,
+            folded_lines,
         }
     }
 
@@ -1791,6 +1899,7 @@ impl SplitRenderer {
         estimated_line_length: usize,
         visible_count: usize,
     ) -> usize {
+        let hidden = state.folds.hidden_byte_ranges(&state.buffer);
         let mut iter_temp = state
             .buffer
             .line_iterator(viewport_start, estimated_line_length);
@@ -1798,6 +1907,10 @@ impl SplitRenderer {
         for _ in 0..visible_count {
             if let Some((line_start, line_content)) = iter_temp.next() {
                 viewport_end = line_start + line_content.len();
+                // Folded lines take no rows
+                if let Some(range) = hidden.iter().find(|range| range.start == viewport_end) {
+                    iter_temp = state.buffer.line_iterator(range.end, estimated_line_length);
+                }
             } else {
                 break;
             }
@@ -1835,6 +1948,7 @@ impl SplitRenderer {
         let virtual_text_lookup = &decorations.virtual_text_lookup;
         let diagnostic_lines = &decorations.diagnostic_lines;
         let line_indicators = &decorations.line_indicators;
+        let fold_markers = &decorations.fold_markers;
 
         let mut lines = Vec::new();
         let mut lines_rendered = 0usize;
@@ -1907,7 +2021,13 @@ impl SplitRenderer {
             // 2. The CURRENT line is also a source line
             // This ensures virtual/injected lines don't cause line numbers to skip
             if show_line_number && prev_was_source_line {
-                current_source_line_num += 1;
+                // Lines hidden by a fold after the previous line are skipped too
+                let hidden_lines = decorations
+                    .folded_lines
+                    .get(&current_source_line_num)
+                    .copied()
+                    .unwrap_or(0);
+                current_source_line_num += 1 + hidden_lines;
             }
             // Only update the flag when we see a source line - virtual lines
             // between source lines shouldn't reset the tracking
@@ -1939,6 +2059,7 @@ impl SplitRenderer {
                     estimated_lines,
                     diagnostic_lines,
                     line_indicators,
+                    fold_markers,
                 },
                 &mut line_spans,
                 &mut line_view_map,
@@ -2648,6 +2769,32 @@ impl SplitRenderer {
             }
         }
 
+        // Render the maximum line length guide, only over empty cells so no text is hidden
+        if let Some(max_line_length) = state.settings.max_line_length {
+            let guide_style = Style::default()
+                .fg(theme.line_number_fg)
+                .add_modifier(Modifier::DIM);
+            let guide_height = render_output
+                .content_lines_rendered
+                .min(render_area.height as usize);
+            let content_width = (render_area.width as usize).saturating_sub(gutter_width);
+
+            if let Some(col) = max_line_length
+                .checked_sub(viewport.left_column)
+                .filter(|&col| col < content_width)
+            {
+                let guide_x = render_area.x + (gutter_width + col) as u16;
+                let buf = frame.buffer_mut();
+                for row in 0..guide_height {
+                    let cell = &mut buf[(guide_x, render_area.y + row as u16)];
+                    if cell.symbol() == " " {
+                        cell.set_symbol("│");
+                        cell.set_style(guide_style);
+                    }
+                }
+            }
+        }
+
         let buffer_ends_with_newline = if state.buffer.len() > 0 {
             let last_char = state.get_text_range(state.buffer.len() - 1, state.buffer.len());
             last_char == "\n"
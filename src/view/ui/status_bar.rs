@@ -203,6 +203,21 @@ impl StatusBarRenderer {
         // Create spans for the prompt
         let mut spans = vec![Span::styled(prompt.message.clone(), base_style)];
 
+        if prompt.prompt_type.is_masked() {
+            // Show one '*' per character and nothing else about the input
+            let masked_cursor = prompt.input[..prompt.cursor_pos].chars().count();
+            spans.push(Span::styled(
+                "*".repeat(prompt.input.chars().count()),
+                base_style,
+            ));
+            frame.render_widget(Paragraph::new(Line::from(spans)).style(base_style), area);
+            let cursor_x = (prompt.message.len() + masked_cursor) as u16;
+            if cursor_x < area.width {
+                frame.set_cursor_position((area.x + cursor_x, area.y));
+            }
+            return;
+        }
+
         // If there's a selection, split the input into parts
         if let Some((sel_start, sel_end)) = prompt.selection_range() {
             let input = &prompt.input;
@@ -488,7 +503,25 @@ impl StatusBarRenderer {
         // Calculate available width - reserve space for right side indicators
         let available_width = area.width as usize;
         let cmd_palette_width = padded_cmd_palette.len();
-        let right_side_width = update_width + cmd_palette_width;
+
+        // Build file format indicator (line ending and encoding) for right side
+        // Only shown when it doesn't force the left status to be truncated
+        let file_format_indicator = format!(
+            " {} {} ",
+            state.buffer.line_ending().display_name(),
+            state.buffer.encoding().display_name()
+        );
+        let show_file_format = str_width(&left_status)
+            + file_format_indicator.len()
+            + update_width
+            + cmd_palette_width
+            < available_width;
+        let file_format_width = if show_file_format {
+            file_format_indicator.len()
+        } else {
+            0
+        };
+        let right_side_width = file_format_width + update_width + cmd_palette_width;
 
         // Only show command palette indicator if there's enough space (at least 15 chars for minimal display)
         let spans = if available_width >= 15 {
@@ -556,6 +589,15 @@ impl StatusBarRenderer {
                 ));
             }
 
+            if show_file_format {
+                spans.push(Span::styled(
+                    file_format_indicator.clone(),
+                    Style::default()
+                        .fg(theme.status_bar_fg)
+                        .bg(theme.status_bar_bg),
+                ));
+            }
+
             // Add update indicator if available (with highlighted styling)
             if let Some(ref update_text) = update_indicator {
                 spans.push(Span::styled(
@@ -22,6 +22,7 @@
 
 use crate::primitives::ansi::AnsiParser;
 use crate::primitives::display_width::char_width;
+use crate::primitives::visual_layout::{tab_expansion_width, DEFAULT_TAB_WIDTH};
 use crate::services::plugins::api::{ViewTokenStyle, ViewTokenWire, ViewTokenWireKind};
 use std::collections::HashSet;
 
@@ -115,14 +116,6 @@ impl LineStart {
     }
 }
 
-/// Standard tab width for terminal display
-pub const TAB_WIDTH: usize = 8;
-
-/// Expand a tab to spaces based on current column
-fn tab_expansion_width(col: usize) -> usize {
-    TAB_WIDTH - (col % TAB_WIDTH)
-}
-
 /// Iterator that converts a token stream into display lines
 pub struct ViewLineIterator<'a> {
     tokens: &'a [ViewTokenWire],
@@ -133,6 +126,8 @@ pub struct ViewLineIterator<'a> {
     binary_mode: bool,
     /// Whether to parse ANSI escape sequences (giving them zero visual width)
     ansi_aware: bool,
+    /// Columns between tab stops
+    tab_width: usize,
 }
 
 impl<'a> ViewLineIterator<'a> {
@@ -143,6 +138,7 @@ impl<'a> ViewLineIterator<'a> {
             next_line_start: LineStart::Beginning,
             binary_mode: false,
             ansi_aware: false,
+            tab_width: DEFAULT_TAB_WIDTH,
         }
     }
 
@@ -154,6 +150,7 @@ impl<'a> ViewLineIterator<'a> {
             next_line_start: LineStart::Beginning,
             binary_mode: binary,
             ansi_aware: false,
+            tab_width: DEFAULT_TAB_WIDTH,
         }
     }
 
@@ -165,17 +162,25 @@ impl<'a> ViewLineIterator<'a> {
             next_line_start: LineStart::Beginning,
             binary_mode: false,
             ansi_aware,
+            tab_width: DEFAULT_TAB_WIDTH,
         }
     }
 
-    /// Create a new ViewLineIterator with both binary mode and ANSI awareness configurable
-    pub fn with_options(tokens: &'a [ViewTokenWire], binary_mode: bool, ansi_aware: bool) -> Self {
+    /// Create a new ViewLineIterator with binary mode, ANSI awareness and the
+    /// tab width configurable
+    pub fn with_options(
+        tokens: &'a [ViewTokenWire],
+        binary_mode: bool,
+        ansi_aware: bool,
+        tab_width: usize,
+    ) -> Self {
         Self {
             tokens,
             token_idx: 0,
             next_line_start: LineStart::Beginning,
             binary_mode,
             ansi_aware,
+            tab_width,
         }
     }
 }
@@ -334,7 +339,7 @@ impl<'a> Iterator for ViewLineIterator<'a> {
                             // Tab expands to spaces - record start position
                             let tab_start_pos = char_source_bytes.len();
                             tab_starts.insert(tab_start_pos);
-                            let spaces = tab_expansion_width(col);
+                            let spaces = tab_expansion_width(col, self.tab_width);
 
                             // Tab is ONE character that expands to multiple visual columns
                             let char_idx = char_source_bytes.len();
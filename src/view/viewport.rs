@@ -3,6 +3,7 @@ use crate::model::cursor::Cursor;
 use crate::primitives::display_width::{char_width, str_width};
 use crate::primitives::line_wrapping::{char_position_to_segment, wrap_line, WrapConfig};
 use crate::view::ui::view_pipeline::ViewLine;
+use std::ops::Range;
 /// The viewport - what portion of the buffer is visible
 #[derive(Debug, Clone)]
 pub struct Viewport {
@@ -49,6 +50,10 @@ pub struct Viewport {
     /// This is set after scroll actions (Ctrl+Up/Down) to prevent the scroll
     /// from being immediately undone by ensure_visible
     skip_ensure_visible: bool,
+
+    /// Byte ranges hidden by folds, sorted (set before rendering)
+    /// Hidden lines don't take up rows when scrolling to the cursor
+    pub hidden_ranges: Vec<Range<usize>>,
 }
 
 impl Viewport {
@@ -66,9 +71,18 @@ impl Viewport {
             needs_sync: false,
             skip_resize_sync: false,
             skip_ensure_visible: false,
+            hidden_ranges: Vec::new(),
         }
     }
 
+    /// The folded range containing `position`, if any
+    fn hidden_range_at(&self, position: usize) -> Option<Range<usize>> {
+        self.hidden_ranges
+            .iter()
+            .find(|range| range.contains(&position))
+            .cloned()
+    }
+
     /// Mark viewport to skip sync on next resize (used after session restore)
     pub fn set_skip_resize_sync(&mut self) {
         self.skip_resize_sync = true;
@@ -738,6 +752,10 @@ impl Viewport {
                     if iter.next().is_none() {
                         break;
                     }
+                    // Folded lines take no rows
+                    if let Some(range) = self.hidden_range_at(iter.current_position()) {
+                        iter = buffer.line_iterator(range.end, 80);
+                    }
                     lines_from_top += 1;
                 }
 
@@ -821,6 +839,10 @@ impl Viewport {
                     if iter.prev().is_none() {
                         break; // Hit beginning of buffer
                     }
+                    // Folded lines take no rows: continue from the fold's header line
+                    if let Some(range) = self.hidden_range_at(iter.current_position()) {
+                        iter = buffer.line_iterator(range.start.saturating_sub(1), 80);
+                    }
                 }
 
                 let new_top_byte = iter.current_position();
@@ -348,3 +348,69 @@ fn test_append_at_end_of_file() {
 
     harness.assert_buffer_content("Line 1\nLine 2\nLine 3!!\nLine 4");
 }
+
+/// Test that saving keeps the file's permissions and any symlink pointing to it
+#[cfg(unix)]
+#[test]
+fn test_save_preserves_permissions_and_symlink() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let script = temp_dir.path().join("build.sh");
+    let link = temp_dir.path().join("link.sh");
+    std::fs::write(&script, "echo hi\n").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::os::unix::fs::symlink(&script, &link).unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&link).unwrap();
+    harness.type_text("# ").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    assert_eq!(std::fs::read_to_string(&script).unwrap(), "# echo hi\n");
+    assert!(std::fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    let mode = std::fs::metadata(&script).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode, 0o755);
+    // Only the script and the link remain (no temporary files)
+    assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+}
+
+/// Test that "Save as Root" writes the file through the configured helper and
+/// marks the buffer saved
+#[cfg(unix)]
+#[test]
+fn test_save_as_root_uses_configured_helper() {
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("hosts");
+    std::fs::write(&file_path, "127.0.0.1 localhost\n").unwrap();
+
+    let mut config = fresh::config::Config::default();
+    config.editor.privileged_save_command = vec!["tee".to_string(), "{path}".to_string()];
+    let mut harness = EditorTestHarness::with_config(120, 24, config).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.type_text("# ").unwrap();
+
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("Save as Root").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "# 127.0.0.1 localhost\n"
+    );
+    harness.assert_screen_contains("Saved as root");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+}
@@ -0,0 +1,139 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use tempfile::TempDir;
+
+/// Test that `.editorconfig` indentation, line endings and save-time whitespace
+/// settings are applied to a file
+#[test]
+fn test_editorconfig_settings_applied_on_edit_and_save() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(
+        project.join(".editorconfig"),
+        "root = true\n\n[*]\nend_of_line = crlf\ninsert_final_newline = true\n\n\
+         [src/*.txt]\nindent_style = tab\ntrim_trailing_whitespace = true\n",
+    )
+    .unwrap();
+    let file_path = project.join("src").join("notes.txt");
+    std::fs::write(&file_path, "first").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text("  ").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.type_text("second").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "first\r\n\tsecond\r\n"
+    );
+    assert!(!harness.editor().active_state().buffer.is_modified());
+
+    // The whitespace fixes are a single undoable edit
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "first  \n\tsecond");
+}
+
+/// Test that `indent_size` controls the indentation inserted by Tab and that
+/// files outside the matching sections keep the global settings
+#[test]
+fn test_editorconfig_indent_size_and_sections() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".editorconfig"),
+        "root = true\n\n[*.{yml,yaml}]\nindent_style = space\nindent_size = 2\n",
+    )
+    .unwrap();
+    let yaml = temp_dir.path().join("ci.yml");
+    let text = temp_dir.path().join("readme.txt");
+    std::fs::write(&yaml, "").unwrap();
+    std::fs::write(&text, "").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&yaml).unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.type_text("a").unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "  a");
+
+    harness.open_file(&text).unwrap();
+    harness.send_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+    harness.type_text("b").unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "    b");
+}
+
+/// Test that `max_line_length` draws a guide at that column without hiding text
+#[test]
+fn test_editorconfig_max_line_length_guide() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".editorconfig"),
+        "[*]\nmax_line_length = 20\n",
+    )
+    .unwrap();
+    let file_path = temp_dir.path().join("long.txt");
+    std::fs::write(&file_path, "short\nthis line is longer than twenty\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    harness.assert_screen_contains("this line is longer than twenty");
+    let screen = harness.screen_to_string();
+    let short_line: Vec<char> = screen
+        .lines()
+        .find(|line| line.contains("short"))
+        .unwrap()
+        .chars()
+        .collect();
+    let text_start = short_line
+        .windows(5)
+        .position(|w| w.iter().collect::<String>() == "short")
+        .unwrap();
+    assert_eq!(short_line[text_start + 20], '│');
+}
+
+/// Column of `needle` in the screen line that contains it, in characters
+fn screen_column(harness: &EditorTestHarness, line_marker: &str, needle: char) -> usize {
+    let screen = harness.screen_to_string();
+    let line = screen
+        .lines()
+        .find(|line| line.contains(line_marker))
+        .unwrap();
+    line.chars().position(|ch| ch == needle).unwrap()
+}
+
+/// Test that tabs are drawn `tab_width` columns wide, and 8 wide without one
+#[test]
+fn test_editorconfig_tab_width_rendering() {
+    let temp_dir = TempDir::new().unwrap();
+    std::fs::write(
+        temp_dir.path().join(".editorconfig"),
+        "root = true\n\n[*.go]\nindent_style = tab\ntab_width = 4\n",
+    )
+    .unwrap();
+    let go = temp_dir.path().join("main.go");
+    let text = temp_dir.path().join("notes.txt");
+    std::fs::write(&go, "\tx\n").unwrap();
+    std::fs::write(&text, "\tx\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+    harness.open_file(&go).unwrap();
+    harness.render().unwrap();
+    let tab = screen_column(&harness, "→", '→');
+    assert_eq!(screen_column(&harness, "→", 'x'), tab + 4);
+
+    harness.open_file(&text).unwrap();
+    harness.render().unwrap();
+    let tab = screen_column(&harness, "→", '→');
+    assert_eq!(screen_column(&harness, "→", 'x'), tab + 8);
+}
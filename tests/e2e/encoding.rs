@@ -0,0 +1,91 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use tempfile::TempDir;
+
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+fn utf16le_with_bom(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    bytes
+}
+
+/// Test that a UTF-16 file is editable (not treated as binary) and saved back as UTF-16
+#[test]
+fn test_utf16_file_is_decoded_and_saved_in_original_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("strings.rc");
+    std::fs::write(&file_path, utf16le_with_bom("Hello\r\n")).unwrap();
+
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    harness.assert_screen_contains("Hello");
+    harness.assert_screen_contains("CRLF UTF-16 LE");
+    assert!(!harness.editor().active_state().buffer.is_binary());
+
+    harness.type_text("Ahoy ").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read(&file_path).unwrap(),
+        utf16le_with_bom("Ahoy Hello\r\n")
+    );
+}
+
+/// Test that a plain file shows the default line ending and encoding in the status bar
+#[test]
+fn test_status_bar_shows_default_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("plain.txt");
+    std::fs::write(&file_path, "plain\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+
+    harness.assert_screen_contains("LF UTF-8");
+}
+
+/// Test reopening a file with a different encoding and converting it on save
+#[test]
+fn test_reopen_and_save_with_encoding() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("legacy.conf");
+    // "é" in Latin-1; short enough that detection is ambiguous, so pick explicitly
+    std::fs::write(&file_path, b"caf\xE9\n").unwrap();
+
+    let mut harness = EditorTestHarness::new(120, 24).unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    run_command(&mut harness, "Reopen with Encoding");
+    harness.type_text("Latin-1").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+    harness.assert_buffer_content("café\n");
+    harness.assert_screen_contains("Latin-1");
+
+    run_command(&mut harness, "Save with Encoding");
+    harness.type_text("UTF-8").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+
+    assert_eq!(std::fs::read(&file_path).unwrap(), "café\n".as_bytes());
+    harness.assert_screen_contains("LF UTF-8");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+}
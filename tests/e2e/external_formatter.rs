@@ -0,0 +1,248 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::{Config, ExternalFormatterConfig};
+use tempfile::TempDir;
+
+fn formatter(command: &str, args: &[&str]) -> ExternalFormatterConfig {
+    ExternalFormatterConfig {
+        command: command.to_string(),
+        args: args.iter().map(|arg| arg.to_string()).collect(),
+        timeout_ms: 5000,
+    }
+}
+
+/// Config where Rust files go through the given formatters
+fn formatter_config(formatters: Vec<ExternalFormatterConfig>, format_on_save: bool) -> Config {
+    let mut config = Config::default();
+    // No language server: formatting must not depend on one
+    config.lsp.clear();
+    let rust = config.languages.get_mut("rust").unwrap();
+    rust.formatters = formatters;
+    rust.format_on_save = format_on_save;
+    config
+}
+
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+}
+
+/// Test that Format Document pipes the buffer through the formatters in order,
+/// only touches changed lines and is undone in one step
+#[test]
+#[cfg(unix)]
+fn test_format_document_with_external_formatters() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "fn main() {\nlet x = 1;\n}\nfn other() {}\n").unwrap();
+
+    let config = formatter_config(
+        vec![
+            formatter("sed", &["s/^let/  let/"]),
+            formatter("sed", &["s/^  let/    let/"]),
+        ],
+        false,
+    );
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    // Cursor in an untouched line after the change
+    for _ in 0..3 {
+        harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    }
+    for _ in 0..3 {
+        harness
+            .send_key(KeyCode::Right, KeyModifiers::NONE)
+            .unwrap();
+    }
+    assert_eq!(harness.cursor_position(), 28);
+
+    run_command(&mut harness, "Format Document");
+    harness
+        .wait_until(|h| {
+            h.get_buffer_content().unwrap() == "fn main() {\n    let x = 1;\n}\nfn other() {}\n"
+        })
+        .unwrap();
+    assert_eq!(harness.cursor_position(), 32);
+    assert_eq!(
+        harness.editor().get_status_message().map(String::as_str),
+        Some("Formatted (1 edits)")
+    );
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn main() {\nlet x = 1;\n}\nfn other() {}\n"
+    );
+}
+
+/// Test that a failing formatter leaves the buffer unchanged and reports its error
+#[test]
+#[cfg(unix)]
+fn test_failing_external_formatter_leaves_buffer_unchanged() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "fn main() {\nlet x = 1;\n}\n").unwrap();
+
+    let config = formatter_config(
+        vec![formatter(
+            "sh",
+            &[
+                "-c",
+                "cat >/dev/null; echo 'error: unexpected token' >&2; exit 1",
+            ],
+        )],
+        true,
+    );
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    run_command(&mut harness, "Format Document");
+    harness
+        .wait_until(|h| {
+            h.editor()
+                .get_status_message()
+                .is_some_and(|status| status.starts_with("Formatting failed"))
+        })
+        .unwrap();
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn main() {\nlet x = 1;\n}\n"
+    );
+    let status = harness.editor().get_status_message().cloned().unwrap();
+    assert!(
+        status.contains("sh: error: unexpected token"),
+        "status: {}",
+        status
+    );
+
+    // Format on save still saves, noting that formatting failed
+    harness.type_text("// ").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .wait_until(|h| !h.editor().active_state().buffer.is_modified())
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "// fn main() {\nlet x = 1;\n}\n"
+    );
+    let status = harness.editor().get_status_message().cloned().unwrap();
+    assert!(
+        status.starts_with("Saved (not formatted"),
+        "status: {}",
+        status
+    );
+}
+
+/// Test that format on save runs the formatters before writing the file
+#[test]
+#[cfg(unix)]
+fn test_external_formatter_on_save() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "fn main() {}\n").unwrap();
+
+    let config = formatter_config(vec![formatter("tr", &["a-z", "A-Z"])], true);
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    harness.type_text("x").unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness
+        .wait_until(|h| !h.editor().active_state().buffer.is_modified())
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file_path).unwrap(),
+        "XFN MAIN() {}\n"
+    );
+}
+
+/// Test that a slow formatter doesn't block editing, and that its output is
+/// dropped when the buffer changed meanwhile
+#[test]
+#[cfg(unix)]
+fn test_external_formatter_runs_in_background() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, "fn main() {}\n").unwrap();
+
+    let config = formatter_config(
+        vec![formatter("sh", &["-c", "sleep 0.5; tr a-z A-Z"])],
+        false,
+    );
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&file_path).unwrap();
+
+    run_command(&mut harness, "Format Document");
+    // Still editable while the formatter runs
+    harness.type_text("x").unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "xfn main() {}\n");
+
+    harness
+        .wait_until(|h| {
+            h.editor()
+                .get_status_message()
+                .is_some_and(|status| status.starts_with("Formatting failed"))
+        })
+        .unwrap();
+    assert_eq!(harness.get_buffer_content().unwrap(), "xfn main() {}\n");
+    let status = harness.editor().get_status_message().cloned().unwrap();
+    assert!(status.contains("buffer changed"), "status: {}", status);
+}
+
+/// Test that format on save of one buffer doesn't drop another's whose
+/// formatters are still running
+#[test]
+#[cfg(unix)]
+fn test_external_formatter_on_save_two_buffers() {
+    let temp_dir = TempDir::new().unwrap();
+    let first = temp_dir.path().join("first.rs");
+    let second = temp_dir.path().join("second.rs");
+    std::fs::write(&first, "fn first() {}\n").unwrap();
+    std::fs::write(&second, "fn second() {}\n").unwrap();
+
+    let config = formatter_config(
+        vec![formatter("sh", &["-c", "sleep 0.5; tr a-z A-Z"])],
+        true,
+    );
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&first).unwrap();
+    harness.open_file(&second).unwrap();
+
+    // Both formatters run at once
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.open_file(&first).unwrap();
+    harness
+        .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+        .unwrap();
+
+    harness
+        .wait_until(|_| {
+            std::fs::read_to_string(&first).unwrap() == "FN FIRST() {}\n"
+                && std::fs::read_to_string(&second).unwrap() == "FN SECOND() {}\n"
+        })
+        .unwrap();
+}
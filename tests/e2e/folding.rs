@@ -0,0 +1,75 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
+use tempfile::TempDir;
+
+const SOURCE: &str = "fn main() {\n    let hidden = 1;\n}\nfn after() {}\n";
+/// Byte offset of `fn after`
+const AFTER: usize = 34;
+
+/// A harness with `content` open in a file named `name` and no language server
+fn harness_with_file(temp_dir: &TempDir, name: &str, content: &str) -> EditorTestHarness {
+    let file_path = temp_dir.path().join(name);
+    std::fs::write(&file_path, content).unwrap();
+
+    let mut config = Config::default();
+    config.lsp.clear();
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness.render().unwrap();
+    harness
+}
+
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Test that a folded region is left out of the rendering and skipped by the cursor
+#[test]
+fn test_folded_region_rendering() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_file(&temp_dir, "main.rs", SOURCE);
+    harness.assert_screen_contains("▾");
+    harness.assert_screen_contains("let hidden");
+
+    run_command(&mut harness, "Fold");
+    harness.assert_screen_contains("▸");
+    harness.assert_screen_contains("fn main() {");
+    harness.assert_screen_not_contains("let hidden");
+    harness.assert_screen_contains("fn after() {}");
+
+    // Moving down lands on the line after the fold, and back up on its header
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), AFTER);
+    harness.send_key(KeyCode::Up, KeyModifiers::NONE).unwrap();
+    assert_eq!(harness.cursor_position(), 0);
+    harness.render().unwrap();
+    harness.assert_screen_not_contains("let hidden");
+
+    run_command(&mut harness, "Unfold All");
+    harness.assert_screen_contains("let hidden");
+    harness.assert_screen_not_contains("▸");
+}
+
+/// Test that indentation fold markers follow edits
+#[test]
+fn test_indentation_fold_markers_follow_edits() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_file(&temp_dir, "notes.txt", "a\nb\n");
+    harness.assert_screen_not_contains("▾");
+
+    // Indenting the second line makes the first one foldable
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness.type_text("  ").unwrap();
+    harness.render().unwrap();
+    harness.assert_screen_contains("▾");
+}
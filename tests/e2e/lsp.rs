@@ -776,7 +776,9 @@ fn test_lsp_waiting_indicator() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -949,7 +951,9 @@ fn test_lsp_completion_canceled_on_cursor_move() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1014,7 +1018,9 @@ fn test_lsp_cursor_animation() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1080,7 +1086,9 @@ fn test_lsp_completion_canceled_on_text_edit() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config
@@ -1664,7 +1672,9 @@ fn test_lsp_diagnostics_non_blocking() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -1821,7 +1831,9 @@ fn test_rust_analyzer_rename_real_scenario() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // CRITICAL: Set working directory to the temp project so rust-analyzer
@@ -2429,7 +2441,9 @@ fn test_lsp_progress_status_display() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -2589,7 +2603,9 @@ fn test_lsp_crash_detection_and_restart() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -2800,6 +2816,7 @@ fn test_pull_diagnostics_message_handling() -> std::io::Result<()> {
     if let Some(bridge) = harness.editor().async_bridge() {
         let _ = bridge.sender().send(AsyncMessage::LspPulledDiagnostics {
             request_id: 1,
+            server: "test".to_string(),
             uri: uri.as_str().to_string(),
             result_id: Some("test-result-id-123".to_string()),
             diagnostics: vec![diagnostic],
@@ -2846,6 +2863,7 @@ fn test_pull_diagnostics_unchanged_response() -> std::io::Result<()> {
     if let Some(bridge) = harness.editor().async_bridge() {
         let _ = bridge.sender().send(AsyncMessage::LspPulledDiagnostics {
             request_id: 2,
+            server: "test".to_string(),
             uri: uri.as_str().to_string(),
             result_id: Some("test-result-id-456".to_string()),
             diagnostics: Vec::new(), // Empty when unchanged
@@ -2885,7 +2903,9 @@ fn test_pull_diagnostics_auto_trigger_after_open() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create a temp directory and test file
@@ -2963,7 +2983,9 @@ fn test_pull_diagnostics_result_id_tracking() -> std::io::Result<()> {
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create a temp directory and test file
@@ -3220,7 +3242,9 @@ fn test_stopped_lsp_does_not_auto_restart_on_edit() -> std::io::Result<()> {
             auto_start: true, // Auto-start so it starts when we open the file
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -3368,3 +3392,180 @@ fn test_hover_popup_at_right_edge_does_not_panic() -> std::io::Result<()> {
 
     Ok(())
 }
+
+/// Config using the formatting fake LSP server for Rust files
+fn formatting_lsp_config(format_on_save: bool, timeout_ms: u64) -> fresh::config::Config {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let mut config = fresh::config::Config::default();
+    config.lsp.insert(
+        "rust".to_string(),
+        fresh::services::lsp::client::LspServerConfig {
+            command: FakeLspServer::formatting_script_path()
+                .to_string_lossy()
+                .to_string(),
+            auto_start: true,
+            ..Default::default()
+        }
+        .into(),
+    );
+    let rust = config.languages.get_mut("rust").unwrap();
+    rust.format_on_save = format_on_save;
+    rust.format_on_save_timeout_ms = timeout_ms;
+    config
+}
+
+/// Open a file and wait for its language server to be ready
+fn open_and_wait_for_lsp(
+    harness: &mut EditorTestHarness,
+    path: &std::path::Path,
+) -> std::io::Result<()> {
+    harness.open_file(path)?;
+    let ready = harness.wait_for_async(
+        |h| {
+            h.editor()
+                .get_status_message()
+                .is_some_and(|msg| msg.contains("LSP (rust) ready"))
+        },
+        5000,
+    )?;
+    assert!(ready, "fake LSP server should initialize");
+    Ok(())
+}
+
+/// Test that format-on-save applies the server's edits before writing the file
+#[test]
+fn test_lsp_format_on_save() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _server = FakeLspServer::spawn_with_formatting()?;
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("test.rs");
+    std::fs::write(&test_file, "fn main() {\nlet x = 1;\n}\n")?;
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        formatting_lsp_config(true, 5000),
+        temp_dir.path().to_path_buf(),
+    )?;
+    open_and_wait_for_lsp(&mut harness, &test_file)?;
+
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+    let saved = harness.wait_for_async(
+        |_| std::fs::read_to_string(&test_file).unwrap() == "fn main() {\n    let x = 1;\n}\n",
+        5000,
+    )?;
+    assert!(saved, "file should be saved formatted");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+    Ok(())
+}
+
+/// Test that a slow server doesn't block format-on-save: the file is saved unformatted
+#[test]
+fn test_lsp_format_on_save_timeout() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _server = FakeLspServer::spawn_with_formatting()?;
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("slow.rs");
+    std::fs::write(&test_file, "fn main() {\nlet x = 1;\n}\n")?;
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        formatting_lsp_config(true, 100),
+        temp_dir.path().to_path_buf(),
+    )?;
+    open_and_wait_for_lsp(&mut harness, &test_file)?;
+
+    harness.type_text("// ")?;
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+    let saved = harness.wait_for_async(
+        |_| {
+            std::fs::read_to_string(&test_file)
+                .unwrap()
+                .starts_with("// fn main")
+        },
+        900,
+    )?;
+    assert!(saved, "file should be saved before the server answers");
+    assert_eq!(
+        std::fs::read_to_string(&test_file)?,
+        "// fn main() {\nlet x = 1;\n}\n"
+    );
+    harness.assert_screen_contains("not formatted");
+    Ok(())
+}
+
+/// Test that format-on-save of one buffer doesn't drop another's that is
+/// still waiting for the server
+#[test]
+fn test_lsp_format_on_save_two_buffers() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _server = FakeLspServer::spawn_with_formatting()?;
+    let temp_dir = tempfile::tempdir()?;
+    // The server takes a second to format each "slow" file
+    let first = temp_dir.path().join("slow_first.rs");
+    let second = temp_dir.path().join("slow_second.rs");
+    std::fs::write(&first, "fn first() {\nlet x = 1;\n}\n")?;
+    std::fs::write(&second, "fn second() {\nlet y = 2;\n}\n")?;
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        formatting_lsp_config(true, 5000),
+        temp_dir.path().to_path_buf(),
+    )?;
+    open_and_wait_for_lsp(&mut harness, &first)?;
+    harness.open_file(&second)?;
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+    harness.open_file(&first)?;
+    harness.send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)?;
+
+    let saved = harness.wait_for_async(
+        |_| {
+            std::fs::read_to_string(&first).unwrap() == "fn first() {\n    let x = 1;\n}\n"
+                && std::fs::read_to_string(&second).unwrap() == "fn second() {\n    let y = 2;\n}\n"
+        },
+        5000,
+    )?;
+    assert!(saved, "both files should be saved formatted");
+    Ok(())
+}
+
+/// Test that Format Document applies the edits as a single undo step
+#[test]
+fn test_lsp_format_document_single_undo() -> std::io::Result<()> {
+    use crate::common::fake_lsp::FakeLspServer;
+
+    let _server = FakeLspServer::spawn_with_formatting()?;
+    let temp_dir = tempfile::tempdir()?;
+    let test_file = temp_dir.path().join("test.rs");
+    std::fs::write(&test_file, "fn main() {\nlet x = 1;\n}\n")?;
+
+    let mut harness = EditorTestHarness::with_config_and_working_dir(
+        80,
+        24,
+        formatting_lsp_config(false, 1000),
+        temp_dir.path().to_path_buf(),
+    )?;
+    open_and_wait_for_lsp(&mut harness, &test_file)?;
+
+    harness.send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)?;
+    harness.type_text("Format Document")?;
+    harness.send_key(KeyCode::Enter, KeyModifiers::NONE)?;
+    let formatted = harness.wait_for_async(
+        |h| h.get_buffer_content().unwrap() == "fn main() {\n    let x = 1;\n}\n",
+        5000,
+    )?;
+    assert!(formatted, "buffer should be formatted");
+
+    harness.send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)?;
+    assert_eq!(
+        harness.get_buffer_content().unwrap(),
+        "fn main() {\nlet x = 1;\n}\n"
+    );
+    Ok(())
+}
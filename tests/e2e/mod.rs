@@ -7,10 +7,14 @@ pub mod buffer_lifecycle;
 pub mod command_palette;
 pub mod crlf_rendering;
 pub mod document_model;
+pub mod editorconfig;
 pub mod emacs_actions;
+pub mod encoding;
 pub mod explorer_menu;
+pub mod external_formatter;
 pub mod file_browser;
 pub mod file_explorer;
+pub mod folding;
 pub mod large_file_mode;
 pub mod lifecycle;
 pub mod line_wrapping;
@@ -31,6 +35,7 @@ pub mod position_history;
 pub mod position_history_bugs;
 pub mod position_history_debug;
 pub mod position_history_truncate_debug;
+pub mod project_config;
 pub mod prompt;
 pub mod prompt_editing;
 pub mod recovery;
@@ -45,6 +50,7 @@ pub mod smart_editing;
 pub mod split_tabs;
 pub mod split_view;
 pub mod split_view_expectations;
+pub mod symbols;
 pub mod tab_scrolling;
 pub mod terminal;
 pub mod terminal_resize;
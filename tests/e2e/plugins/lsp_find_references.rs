@@ -135,7 +135,9 @@ done
             auto_start: false,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with config and working directory
@@ -690,7 +690,9 @@ fn test_diagnostics_panel_plugin_loads() {
             auto_start: true,
             process_limits: fresh::services::process_limits::ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     // Create harness with the project directory and LSP config
@@ -1584,7 +1586,9 @@ fn test_clangd_plugin_file_status_notification() -> std::io::Result<()> {
             auto_start: false,
             process_limits: ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     let mut harness =
@@ -1666,7 +1670,9 @@ fn test_clangd_plugin_switch_source_header() -> std::io::Result<()> {
             auto_start: false,
             process_limits: ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     let mut harness =
@@ -1897,7 +1903,9 @@ editor.setStatus("Test diagnostics plugin loaded");
             auto_start: false,
             process_limits: ProcessLimits::default(),
             initialization_options: None,
-        },
+            ..Default::default()
+        }
+        .into(),
     );
 
     let mut harness =
@@ -0,0 +1,51 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
+use tempfile::TempDir;
+
+/// Test that a project's `.fresh/config.json` is layered over the user config,
+/// with its language server commands held back until the project is trusted
+#[test]
+fn test_project_config_layered_with_trust_prompt() {
+    let temp_dir = TempDir::new().unwrap();
+    let project = temp_dir.path().join("project");
+    std::fs::create_dir_all(project.join(".fresh")).unwrap();
+    std::fs::write(
+        project.join(".fresh").join("config.json"),
+        r#"{"editor": {"tab_size": 2}, "lsp": {"rust": {"command": "project-analyzer"}}}"#,
+    )
+    .unwrap();
+
+    let mut user_config = Config::default();
+    user_config.editor.line_numbers = false;
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, user_config, project).unwrap();
+    harness.render().unwrap();
+
+    // Plain settings apply right away, the server command waits for trust
+    assert_eq!(harness.editor().config().editor.tab_size, 2);
+    assert!(!harness.editor().config().editor.line_numbers);
+    assert_ne!(
+        harness.editor().config().lsp["rust"][0].command,
+        "project-analyzer"
+    );
+    assert!(harness.editor().is_prompting());
+    harness.assert_screen_contains("Trust project config (sets lsp.rust)? (y/N)");
+
+    harness.type_text("y").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    assert_eq!(
+        harness.editor().config().lsp["rust"][0].command,
+        "project-analyzer"
+    );
+
+    // The layers report attributes each setting to its source
+    harness.editor_mut().dump_config();
+    let report = harness.get_buffer_content().unwrap();
+    assert!(report.contains("[project] editor.tab_size = 2"));
+    assert!(report.contains("[user   ] editor.line_numbers = false"));
+    assert!(report.contains("[project] lsp.rust.command = \"project-analyzer\""));
+    assert!(report.contains("[default] editor.auto_indent = true"));
+}
@@ -789,6 +789,128 @@ fn test_toggle_macro_recording() {
     );
 }
 
+/// Run a command from the command palette, answering its prompt with `input`
+fn run_command_with_input(harness: &mut EditorTestHarness, name: &str, input: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.type_text(input).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Record macro 1 as "go to the end of the line and type `;`"
+fn record_semicolon_macro(harness: &mut EditorTestHarness) {
+    harness
+        .send_key(KeyCode::Char('1'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text(";").unwrap();
+    harness
+        .send_key(KeyCode::Char('1'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Test playing a macro on each selected line right after an undo: the run
+/// must still be a single undo step
+#[test]
+fn test_macro_on_each_line_after_undo_is_one_undo_step() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "one\ntwo\nthree\nfour").unwrap();
+
+    let mut harness = harness_with_auto_indent();
+    harness.open_file(&file_path).unwrap();
+
+    record_semicolon_macro(&mut harness);
+    harness.assert_buffer_content("one;\ntwo\nthree\nfour");
+
+    // Undo the recording's edit, leaving it to be redone
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("one\ntwo\nthree\nfour");
+
+    harness
+        .send_key(KeyCode::Char('a'), KeyModifiers::CONTROL)
+        .unwrap();
+    run_command_with_input(&mut harness, "Play Macro on Each Line", "1");
+    harness.assert_buffer_content("one;\ntwo;\nthree;\nfour;");
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("one\ntwo\nthree\nfour");
+}
+
+/// Test playing a macro at each search match, as one undo step
+#[test]
+fn test_macro_at_each_match() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "let a\nfoo\nlet b\nlet c").unwrap();
+
+    let mut harness = harness_with_auto_indent();
+    harness.open_file(&file_path).unwrap();
+
+    record_semicolon_macro(&mut harness);
+    harness.assert_buffer_content("let a;\nfoo\nlet b\nlet c");
+
+    harness
+        .send_key(KeyCode::Char('f'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("let").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+
+    run_command_with_input(&mut harness, "Play Macro at Each Match", "1");
+    harness.assert_buffer_content("let a;;\nfoo\nlet b;\nlet c;");
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("let a;\nfoo\nlet b\nlet c");
+}
+
+/// Test repeating a macro a given number of times, as one undo step
+#[test]
+fn test_repeat_macro_with_count() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("test.txt");
+    std::fs::write(&file_path, "one\ntwo\nthree\nfour").unwrap();
+
+    let mut harness = harness_with_auto_indent();
+    harness.open_file(&file_path).unwrap();
+
+    // Record "type `;` at the end of the line and go down"
+    harness
+        .send_key(KeyCode::Char('1'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+    harness.send_key(KeyCode::End, KeyModifiers::NONE).unwrap();
+    harness.type_text(";").unwrap();
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Char('1'), KeyModifiers::ALT | KeyModifiers::SHIFT)
+        .unwrap();
+
+    run_command_with_input(&mut harness, "Repeat Macro", "1 2");
+    harness.assert_buffer_content("one;\ntwo;\nthree;\nfour");
+
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("one;\ntwo\nthree\nfour");
+}
+
 // =============================================================================
 // Jump to Next/Previous Error Tests
 // =============================================================================
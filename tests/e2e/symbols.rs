@@ -0,0 +1,93 @@
+use crate::common::harness::EditorTestHarness;
+use crossterm::event::{KeyCode, KeyModifiers};
+use fresh::config::Config;
+use tempfile::TempDir;
+
+/// Byte offset of `beta`'s name in `SOURCE`
+const BETA: usize = 18;
+const SOURCE: &str = "struct Alpha;\n\nfn beta() {}\n";
+
+/// A harness with a Rust file open and no language server, so that symbols
+/// come from the syntax tree
+fn harness_with_source(temp_dir: &TempDir) -> EditorTestHarness {
+    let file_path = temp_dir.path().join("main.rs");
+    std::fs::write(&file_path, SOURCE).unwrap();
+
+    let mut config = Config::default();
+    config.lsp.clear();
+    let mut harness =
+        EditorTestHarness::with_config_and_working_dir(80, 24, config, temp_dir.path().into())
+            .unwrap();
+    harness.open_file(&file_path).unwrap();
+    harness
+}
+
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Test that Go to Symbol in File jumps to the picked symbol
+#[test]
+fn test_goto_symbol_in_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_source(&temp_dir);
+
+    run_command(&mut harness, "Go to Symbol in File");
+    harness.assert_screen_contains("Go to symbol: ");
+    harness.assert_screen_contains("Alpha");
+
+    harness.type_text("beta").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    assert_eq!(harness.cursor_position(), BETA);
+}
+
+/// Test that the outline panel lists the symbols and marks the one at the cursor
+#[test]
+fn test_outline_panel_follows_cursor() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_source(&temp_dir);
+
+    run_command(&mut harness, "Toggle Outline");
+    harness.assert_screen_contains("▶ Alpha  struct");
+    harness.assert_screen_contains("beta  function");
+
+    // Editing continues in the source
+    for _ in 0..2 {
+        harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    }
+    assert!(harness.editor_mut().check_outline_panel());
+    harness.render().unwrap();
+    harness.assert_screen_contains("▶ beta  function");
+
+    run_command(&mut harness, "Toggle Outline");
+    harness.assert_screen_not_contains("beta  function");
+}
+
+/// Test that Enter on an outline entry jumps to its symbol in the source
+#[test]
+fn test_outline_panel_enter_jumps_to_symbol() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut harness = harness_with_source(&temp_dir);
+    let source = harness.editor().active_buffer();
+
+    run_command(&mut harness, "Toggle Outline");
+    run_command(&mut harness, "Next Split");
+    assert_ne!(harness.editor().active_buffer(), source);
+
+    // The panel's cursor starts on the marked symbol, Alpha
+    harness.send_key(KeyCode::Down, KeyModifiers::NONE).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    assert_eq!(harness.editor().active_buffer(), source);
+    assert_eq!(harness.cursor_position(), BETA);
+}
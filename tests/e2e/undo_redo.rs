@@ -503,3 +503,126 @@ fn test_undo_to_empty_after_save_as() {
         final_content
     );
 }
+
+/// Run a command from the command palette by name
+fn run_command(harness: &mut EditorTestHarness, name: &str) {
+    harness
+        .send_key(KeyCode::Char('p'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text(name).unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.render().unwrap();
+}
+
+/// Test that typing after an undo keeps the undone edits reachable through the undo tree
+#[test]
+fn test_undo_tree_keeps_branch_after_edit() {
+    let mut harness = EditorTestHarness::new(80, 24).unwrap();
+
+    harness.type_text("ab").unwrap();
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.assert_buffer_content("a");
+
+    // Typing now would have discarded "b" with a linear history
+    harness.type_text("c").unwrap();
+    harness.assert_buffer_content("ac");
+
+    // The state before "c" (chronologically) is "ab", on the other branch
+    run_command(&mut harness, "Undo Tree: Earlier State");
+    harness.assert_buffer_content("ab");
+
+    run_command(&mut harness, "Undo Tree: Later State");
+    harness.assert_buffer_content("ac");
+
+    // Jump back to the original state by number
+    run_command(&mut harness, "Undo Tree: Go to State");
+    harness.type_text("#0").unwrap();
+    harness
+        .send_key(KeyCode::Enter, KeyModifiers::NONE)
+        .unwrap();
+    harness.assert_buffer_content("");
+    assert!(!harness.editor().active_state().buffer.is_modified());
+}
+
+/// Test that the undo tree visualizer lists both branches
+#[test]
+fn test_undo_tree_visualizer_shows_branches() {
+    let mut harness = EditorTestHarness::new(100, 30).unwrap();
+
+    harness.type_text("ab").unwrap();
+    harness
+        .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+        .unwrap();
+    harness.type_text("c").unwrap();
+
+    run_command(&mut harness, "Undo Tree: Show");
+    harness.assert_screen_contains("Undo Tree");
+    harness.assert_screen_contains("branch 1/2:");
+    harness.assert_screen_contains("branch 2/2:");
+    harness.assert_screen_contains("+\"b\" @1");
+    harness.assert_screen_contains("+\"c\" @1");
+}
+
+/// Test that undo history survives closing and reopening the editor when
+/// `editor.persistent_undo` is enabled, but only while the file is unchanged
+#[test]
+fn test_persistent_undo_history_across_restarts() {
+    let project = tempfile::TempDir::new().unwrap();
+    let file = project.path().canonicalize().unwrap().join("notes.txt");
+    std::fs::write(&file, "one").unwrap();
+
+    let context_temp = tempfile::TempDir::new().unwrap();
+    let dir_context = fresh::config::DirectoryContext::for_testing(context_temp.path());
+    let mut config = fresh::config::Config::default();
+    config.editor.persistent_undo = true;
+
+    let start_editor = || {
+        EditorTestHarness::with_shared_dir_context(
+            80,
+            24,
+            config.clone(),
+            project.path().to_path_buf(),
+            dir_context.clone(),
+        )
+        .unwrap()
+    };
+
+    // Session 1: edit and save
+    {
+        let mut harness = start_editor();
+        harness.open_file(&file).unwrap();
+        harness.type_text("X").unwrap();
+        harness
+            .send_key(KeyCode::Char('s'), KeyModifiers::CONTROL)
+            .unwrap();
+        harness.assert_buffer_content("Xone");
+        harness.editor().persist_all_undo_history();
+    }
+
+    // Session 2: the edit can still be undone
+    {
+        let mut harness = start_editor();
+        harness.open_file(&file).unwrap();
+        harness.assert_buffer_content("Xone");
+        harness
+            .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+            .unwrap();
+        harness.assert_buffer_content("one");
+        assert!(harness.editor().active_state().buffer.is_modified());
+    }
+
+    // The file changes outside the editor: the stale history is discarded
+    std::fs::write(&file, "changed").unwrap();
+    {
+        let mut harness = start_editor();
+        harness.open_file(&file).unwrap();
+        harness
+            .send_key(KeyCode::Char('z'), KeyModifiers::CONTROL)
+            .unwrap();
+        harness.assert_buffer_content("changed");
+    }
+}